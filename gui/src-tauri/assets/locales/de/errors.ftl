@@ -0,0 +1,67 @@
+# Backend error messages (German). English comes from the error types' Display.
+
+auth-not-found = Kein Token im Schlüsselbund oder in der Datei gefunden
+auth-invalid-format = Ungültiges Token-Format (muss mit rl_ beginnen)
+auth-keychain-error = Schlüsselbund-Fehler: { $detail }
+auth-file-error = Dateifehler: { $detail }
+auth-permissions-unavailable = Die Engine meldet keine Token-Berechtigungen
+auth-missing-scope = Dem Token fehlt die Berechtigung { $scope }
+auth-impersonation-reason-required = Für das Handeln als anderer Benutzer ist eine Begründung erforderlich
+
+http-transport = Anfrage fehlgeschlagen: { $detail }
+http-status = Die Engine antwortete mit HTTP { $status }: { $body }
+http-decode = Ungültige Antwort der Engine: { $detail }
+http-offline-mode = Übersprungen: Der Offline-Modus ist aktiv
+http-invalid-path-segment = Ungültige Kennung für einen Anfragepfad: { $id }
+
+engine-not-running = Engine läuft nicht
+engine-start-failed = Engine konnte nicht gestartet werden: { $detail }
+engine-stop-failed = Engine konnte nicht gestoppt werden: { $detail }
+engine-storage-error = Speicherfehler: { $detail }
+engine-rate-limit-config = Ungültige Ratenbegrenzung: { $detail }
+engine-schema-unavailable = Das Konfigurationsschema der Engine ist nicht verfügbar
+engine-corpus-not-found = Unbekanntes Korpus: { $id }
+engine-corpus-not-downloadable = Für das Korpus { $id } ist kein Download verfügbar
+engine-corpus-checksum-mismatch = Prüfsumme für das Korpus { $id } stimmt nicht; der Download wurde verworfen
+engine-corpus-active = Das Korpus { $id } ist die aktive Quelle und kann nicht entfernt werden
+engine-extension-load-error = Erweiterung konnte nicht geladen werden: { $detail }
+engine-lexicon-unavailable = Das Offline-Lexikon ist nicht installiert
+engine-invalid-strongs = Ungültige Strong-Nummer { $number }: erwartet G1 bis G5624
+engine-invalid-hebrew-strongs = Ungültige Strong-Nummer { $number }: erwartet H1 bis H8674
+engine-grpc-error = gRPC-Zustandsprüfung fehlgeschlagen: { $detail }
+engine-cors-config = Ungültige CORS-Konfiguration: { $detail }
+engine-invalid-search = Ungültige Suchanfrage: { $detail }
+engine-thread-limit = Ungültiges Thread-Limit: { $detail }
+engine-invalid-config = Ungültige Engine-Konfiguration: { $detail }
+engine-invalid-reference = Ungültige Bibelstelle: { $detail }
+engine-commentary-not-found = Kommentar nicht gefunden: { $id }
+engine-invalid-versions = Ungültige Textausgaben: { $detail }
+engine-highlight-error = Hervorhebung fehlgeschlagen: { $detail }
+engine-passage-not-found = Bibelstelle nicht gefunden: { $reference }
+engine-flashcard-deck-error = Karteikartenstapel konnte nicht erstellt werden: { $detail }
+
+reference-empty = Leere Stellenangabe
+reference-unknown-book = Unbekanntes Buch: { $text }
+reference-missing-book = Kein Buch angegeben für „{ $text }“
+reference-invalid-number = Ungültiges Kapitel oder ungültiger Vers: „{ $text }“
+reference-invalid-range = Ungültiger Bereich: „{ $text }“
+reference-chapter-out-of-range = { $book } hat kein Kapitel { $chapter }
+reference-verse-out-of-range = { $book } { $chapter } hat keinen Vers { $verse }
+
+project-newer-version = Dieses Projekt wurde mit einer neueren Version von Red Letters erstellt ({ $app_version }); aktualisieren Sie die App, um es zu öffnen
+project-invalid = Kein Red-Letters-Projekt: { $detail }
+project-file-error = Fehler bei der Projektdatei: { $detail }
+
+tts-unavailable = Auf diesem System ist keine Sprachausgabe verfügbar ({ $detail })
+tts-invalid-rate = Die Sprechgeschwindigkeit muss zwischen 0,25 und 2,0 liegen, erhalten: { $rate }
+tts-failed = Sprachausgabe fehlgeschlagen: { $detail }
+
+corpus-import-parse = { $count } Problem(e) in der Quelldatei:
+    { $issues }
+corpus-import-exists = Ein Korpus mit der ID { $id } ist bereits installiert
+corpus-import-file = Die Quelldatei konnte nicht gelesen werden: { $detail }
+
+# Commands without a typed error; the detail is not translated
+app-invalid-input = { $detail }
+app-not-found = { $detail }
+app-failed = { $detail }
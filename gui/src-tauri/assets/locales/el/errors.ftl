@@ -0,0 +1,67 @@
+# Backend error messages (Greek). English comes from the error types' Display.
+
+auth-not-found = Δεν βρέθηκε διακριτικό στην κλειδοθήκη ή σε αρχείο
+auth-invalid-format = Μη έγκυρη μορφή διακριτικού (πρέπει να ξεκινά με rl_)
+auth-keychain-error = Σφάλμα κλειδοθήκης: { $detail }
+auth-file-error = Σφάλμα αρχείου: { $detail }
+auth-permissions-unavailable = Η μηχανή δεν αναφέρει δικαιώματα διακριτικού
+auth-missing-scope = Στο διακριτικό λείπει το δικαίωμα { $scope }
+auth-impersonation-reason-required = Απαιτείται αιτιολόγηση για ενέργεια ως άλλος χρήστης
+
+http-transport = Το αίτημα απέτυχε: { $detail }
+http-status = Η μηχανή απάντησε με HTTP { $status }: { $body }
+http-decode = Μη έγκυρη απάντηση από τη μηχανή: { $detail }
+http-offline-mode = Παραλείφθηκε: η λειτουργία εκτός σύνδεσης είναι ενεργή
+http-invalid-path-segment = Μη έγκυρο αναγνωριστικό για διαδρομή αιτήματος: { $id }
+
+engine-not-running = Η μηχανή δεν εκτελείται
+engine-start-failed = Αποτυχία εκκίνησης της μηχανής: { $detail }
+engine-stop-failed = Αποτυχία τερματισμού της μηχανής: { $detail }
+engine-storage-error = Σφάλμα αποθήκευσης: { $detail }
+engine-rate-limit-config = Μη έγκυρη ρύθμιση ορίου αιτημάτων: { $detail }
+engine-schema-unavailable = Το σχήμα ρυθμίσεων της μηχανής δεν είναι διαθέσιμο
+engine-corpus-not-found = Άγνωστο σώμα κειμένων: { $id }
+engine-corpus-not-downloadable = Δεν υπάρχει διαθέσιμη λήψη για το σώμα κειμένων { $id }
+engine-corpus-checksum-mismatch = Το άθροισμα ελέγχου για το σώμα κειμένων { $id } δεν ταιριάζει· η λήψη απορρίφθηκε
+engine-corpus-active = Το σώμα κειμένων { $id } είναι η ενεργή πηγή και δεν μπορεί να αφαιρεθεί
+engine-extension-load-error = Αποτυχία φόρτωσης της επέκτασης: { $detail }
+engine-lexicon-unavailable = Το λεξικό εκτός σύνδεσης δεν είναι εγκατεστημένο
+engine-invalid-strongs = Μη έγκυρος αριθμός Strong { $number }: αναμένεται G1 έως G5624
+engine-invalid-hebrew-strongs = Μη έγκυρος αριθμός Strong { $number }: αναμένεται H1 έως H8674
+engine-grpc-error = Αποτυχία ελέγχου υγείας gRPC: { $detail }
+engine-cors-config = Μη έγκυρη ρύθμιση CORS: { $detail }
+engine-invalid-search = Μη έγκυρο ερώτημα αναζήτησης: { $detail }
+engine-thread-limit = Μη έγκυρο όριο νημάτων: { $detail }
+engine-invalid-config = Μη έγκυρες ρυθμίσεις μηχανής: { $detail }
+engine-invalid-reference = Μη έγκυρη παραπομπή: { $detail }
+engine-commentary-not-found = Δεν βρέθηκε σχόλιο: { $id }
+engine-invalid-versions = Μη έγκυρες εκδόσεις: { $detail }
+engine-highlight-error = Η επισήμανση απέτυχε: { $detail }
+engine-passage-not-found = Δεν βρέθηκε το χωρίο: { $reference }
+engine-flashcard-deck-error = Δεν ήταν δυνατή η δημιουργία της τράπουλας καρτών: { $detail }
+
+reference-empty = Κενή παραπομπή
+reference-unknown-book = Άγνωστο βιβλίο: { $text }
+reference-missing-book = Δεν δόθηκε βιβλίο για «{ $text }»
+reference-invalid-number = Μη έγκυρο κεφάλαιο ή εδάφιο: «{ $text }»
+reference-invalid-range = Μη έγκυρο εύρος: «{ $text }»
+reference-chapter-out-of-range = Το βιβλίο { $book } δεν έχει κεφάλαιο { $chapter }
+reference-verse-out-of-range = Το { $book } { $chapter } δεν έχει εδάφιο { $verse }
+
+project-newer-version = Αυτό το έργο δημιουργήθηκε με νεότερη έκδοση του Red Letters ({ $app_version }). Ενημερώστε την εφαρμογή για να το ανοίξετε
+project-invalid = Δεν είναι έργο του Red Letters: { $detail }
+project-file-error = Σφάλμα αρχείου έργου: { $detail }
+
+tts-unavailable = Δεν υπάρχει διαθέσιμη σύνθεση ομιλίας σε αυτό το σύστημα ({ $detail })
+tts-invalid-rate = Η ταχύτητα ομιλίας πρέπει να είναι μεταξύ 0,25 και 2,0, δόθηκε { $rate }
+tts-failed = Η σύνθεση ομιλίας απέτυχε: { $detail }
+
+corpus-import-parse = { $count } πρόβλημα(τα) στο αρχείο πηγής:
+    { $issues }
+corpus-import-exists = Υπάρχει ήδη εγκατεστημένο σώμα κειμένων με αναγνωριστικό { $id }
+corpus-import-file = Δεν ήταν δυνατή η ανάγνωση του αρχείου πηγής: { $detail }
+
+# Commands without a typed error; the detail is not translated
+app-invalid-input = { $detail }
+app-not-found = { $detail }
+app-failed = { $detail }
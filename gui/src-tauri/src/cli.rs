@@ -0,0 +1,117 @@
+//! Command-line arguments.
+//!
+//! Parsed in `main` before the app is built (so `--help` never opens a
+//! window) and again for arguments forwarded from a second instance.
+
+use std::path::Path;
+
+use crate::commands::hardware_acceleration::DISABLE_GPU_ARG;
+use crate::commands::open_requests::OpenRequest;
+use crate::commands::projects::is_project_file;
+use crate::commands::reference::parse_reference_list;
+
+pub const USAGE: &str = "\
+Usage: redletters-gui [OPTIONS] [PROJECT]
+
+Arguments:
+  [PROJECT]        A .rlproj project to open (as file associations pass it)
+
+Options:
+  --open <TARGET>  Open a passage (e.g. \"John 3:16\") or a .rlproj project
+  --disable-gpu    Start without hardware acceleration
+  -h, --help       Print this help and exit";
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct CliArgs {
+    pub help: bool,
+    pub open: Option<OpenRequest>,
+    pub disable_gpu: bool,
+}
+
+/// Turn an `--open` value into a request; invalid references are logged and dropped.
+fn open_target(value: &str, cwd: &Path) -> Option<OpenRequest> {
+    if is_project_file(Path::new(value)) {
+        return Some(OpenRequest::Project {
+            path: cwd.join(value),
+        });
+    }
+    match parse_reference_list(value) {
+        Ok(parsed) => Some(OpenRequest::Passage {
+            reference: parsed.canonical,
+        }),
+        Err(e) => {
+            eprintln!("Ignoring --open {:?}: {}", value, e);
+            None
+        }
+    }
+}
+
+/// Parse arguments (without the program name). A bare project path is
+/// taken as `--open`; relative project paths are resolved against `cwd`.
+/// Unknown arguments are logged and ignored.
+pub fn parse_args<I: IntoIterator<Item = String>>(args: I, cwd: &Path) -> CliArgs {
+    let mut parsed = CliArgs::default();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => parsed.help = true,
+            DISABLE_GPU_ARG => parsed.disable_gpu = true,
+            "--open" => match args.next() {
+                Some(value) => parsed.open = open_target(&value, cwd),
+                None => eprintln!("Ignoring --open without a value"),
+            },
+            _ => match arg.strip_prefix("--open=") {
+                Some(value) => parsed.open = open_target(value, cwd),
+                None if !arg.starts_with('-') && is_project_file(Path::new(&arg)) => {
+                    parsed.open = open_target(&arg, cwd)
+                }
+                None => eprintln!("Ignoring unknown argument: {}", arg),
+            },
+        }
+    }
+
+    parsed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn parse(args: &[&str]) -> CliArgs {
+        parse_args(args.iter().map(|a| a.to_string()), Path::new("/work"))
+    }
+
+    #[test]
+    fn test_parse_open_targets() {
+        assert_eq!(
+            parse(&["--open", "jn 3:16"]).open,
+            Some(OpenRequest::Passage {
+                reference: "John 3:16".to_string()
+            })
+        );
+        assert_eq!(
+            parse(&["--open=course/week1.RLPROJ"]).open,
+            Some(OpenRequest::Project {
+                path: PathBuf::from("/work/course/week1.RLPROJ")
+            })
+        );
+        assert_eq!(parse(&["--open", "Hezekiah 1:1"]).open, None);
+        // As a file association launches the app
+        assert_eq!(
+            parse(&["/home/me/Greek II.rlproj"]).open,
+            Some(OpenRequest::Project {
+                path: PathBuf::from("/home/me/Greek II.rlproj")
+            })
+        );
+        assert_eq!(parse(&["notes.txt"]).open, None);
+    }
+
+    #[test]
+    fn test_help_and_unknown_arguments() {
+        assert!(parse(&["--verbose", "-h"]).help);
+        assert!(parse(&["--disable-gpu"]).disable_gpu);
+        assert_eq!(parse(&["--verbose", "--open"]), CliArgs::default());
+    }
+}
@@ -0,0 +1,367 @@
+//! OS accessibility preferences (high contrast, reduced motion, ...).
+//!
+//! The webview's media queries don't reliably reflect these, so they are
+//! read from the platform settings store instead:
+//! - macOS: `defaults read com.apple.universalaccess`
+//! - Windows: `reg query` under `HKCU\Control Panel`
+//! - Linux: GNOME `gsettings`
+//!
+//! A preference that can't be read is reported as `None` rather than guessed.
+//! A background watcher polls for changes and emits
+//! `accessibility-prefs-changed`.
+//!
+//! The app's own accessibility settings start out matching these
+//! preferences and are stored once the user changes them.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, Theme};
+
+use super::errors::AppError;
+use super::{process, settings};
+
+/// Event emitted when any preference changes
+const PREFS_CHANGED_EVENT: &str = "accessibility-prefs-changed";
+/// Event emitted when the app's accessibility settings change
+const SETTINGS_CHANGED_EVENT: &str = "accessibility-settings-changed";
+/// Allowed range of `font_size_scale`
+const FONT_SCALE_RANGE: std::ops::RangeInclusive<f32> = 0.5..=4.0;
+/// Cursor size (pixels) from which the OS cursor counts as large
+const LARGE_CURSOR_PX: u32 = 32;
+/// How often the watcher re-reads the preferences
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Label of the main window (tauri.conf.json default)
+const MAIN_LABEL: &str = "main";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContrastPreference {
+    NoPreference,
+    More,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct AccessibilityPrefs {
+    pub high_contrast: Option<bool>,
+    pub reduced_motion: Option<bool>,
+    pub preferred_contrast: Option<ContrastPreference>,
+    pub inverted_colors: Option<bool>,
+    pub large_cursor: Option<bool>,
+    /// OS text scaling, 1.0 being the default size
+    pub text_scale: Option<f32>,
+}
+
+/// The app's own accessibility settings.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AccessibilitySettings {
+    pub high_contrast: bool,
+    pub large_cursor: bool,
+    /// Extra labels and live-region announcements for screen readers
+    pub screen_reader_hints: bool,
+    pub reduce_motion: bool,
+    /// Multiplier on the base font size, 0.5 to 4.0
+    pub font_size_scale: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThemePreference {
+    System,
+    Light,
+    Dark,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EffectiveTheme {
+    Light,
+    Dark,
+    HighContrastLight,
+    HighContrastDark,
+}
+
+/// Run a command and return its trimmed stdout if it succeeded.
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = process::command(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Parse a `defaults read` or `gsettings get` boolean ("1"/"0", "true"/"false").
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim() {
+        "1" | "true" => Some(true),
+        "0" | "false" => Some(false),
+        _ => None,
+    }
+}
+
+/// Parse a `REG_DWORD` as printed by `reg query` ("0x40").
+#[cfg(any(target_os = "windows", test))]
+fn parse_reg_dword(value: &str) -> Option<u32> {
+    u32::from_str_radix(value.trim().strip_prefix("0x")?, 16).ok()
+}
+
+/// Parse the value out of `reg query` output, e.g.
+/// `    Flags    REG_SZ    126`.
+#[cfg(any(target_os = "windows", test))]
+fn parse_reg_value(output: &str, name: &str) -> Option<String> {
+    output.lines().find_map(|line| {
+        let mut parts = line.split_whitespace();
+        if parts.next()? != name {
+            return None;
+        }
+        let _kind = parts.next()?;
+        Some(parts.collect::<Vec<_>>().join(" "))
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn read_prefs() -> AccessibilityPrefs {
+    let read = |key: &str| {
+        command_output("defaults", &["read", "com.apple.universalaccess", key])
+            .and_then(|v| parse_bool(&v))
+    };
+    let increase_contrast = read("increaseContrast");
+    // 1.0 is the normal cursor; the slider goes up to 4.0
+    let cursor_size = command_output(
+        "defaults",
+        &["read", "com.apple.universalaccess", "mouseDriverCursorSize"],
+    )
+    .and_then(|v| v.parse::<f32>().ok());
+    AccessibilityPrefs {
+        high_contrast: increase_contrast,
+        reduced_motion: read("reduceMotion"),
+        preferred_contrast: increase_contrast.map(contrast_preference),
+        inverted_colors: read("whiteOnBlack"),
+        large_cursor: cursor_size.map(|size| size > 1.0),
+        text_scale: None,
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn read_prefs() -> AccessibilityPrefs {
+    let read = |key: &str, name: &str| {
+        command_output("reg", &["query", key, "/v", name]).and_then(|o| parse_reg_value(&o, name))
+    };
+    // HCF_HIGHCONTRASTON is bit 0 of the Flags value
+    let high_contrast = read(r"HKCU\Control Panel\Accessibility\HighContrast", "Flags")
+        .and_then(|v| v.parse::<u32>().ok())
+        .map(|flags| flags & 1 == 1);
+    // "Show animations in Windows" off sets MinAnimate to 0
+    let reduced_motion = read(r"HKCU\Control Panel\Desktop\WindowMetrics", "MinAnimate")
+        .and_then(|v| parse_bool(&v))
+        .map(|animate| !animate);
+    let cursor_size =
+        read(r"HKCU\Control Panel\Cursors", "CursorBaseSize").and_then(|v| parse_reg_dword(&v));
+    // "Make text bigger" stores a percentage
+    let text_scale = read(r"HKCU\Software\Microsoft\Accessibility", "TextScaleFactor")
+        .and_then(|v| parse_reg_dword(&v))
+        .map(|percent| percent as f32 / 100.0);
+    AccessibilityPrefs {
+        high_contrast,
+        reduced_motion,
+        preferred_contrast: high_contrast.map(contrast_preference),
+        inverted_colors: None,
+        large_cursor: cursor_size.map(|size| size >= LARGE_CURSOR_PX),
+        text_scale,
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn read_prefs() -> AccessibilityPrefs {
+    let read = |schema: &str, key: &str| {
+        command_output("gsettings", &["get", schema, key]).and_then(|v| parse_bool(&v))
+    };
+    let read_number = |key: &str| {
+        command_output("gsettings", &["get", "org.gnome.desktop.interface", key])
+            .and_then(|v| v.parse::<f32>().ok())
+    };
+    let high_contrast = read("org.gnome.desktop.a11y.interface", "high-contrast");
+    AccessibilityPrefs {
+        high_contrast,
+        reduced_motion: read("org.gnome.desktop.interface", "enable-animations")
+            .map(|animate| !animate),
+        preferred_contrast: high_contrast.map(contrast_preference),
+        inverted_colors: None,
+        large_cursor: read_number("cursor-size").map(|size| size >= LARGE_CURSOR_PX as f32),
+        text_scale: read_number("text-scaling-factor"),
+    }
+}
+
+fn contrast_preference(high_contrast: bool) -> ContrastPreference {
+    if high_contrast {
+        ContrastPreference::More
+    } else {
+        ContrastPreference::NoPreference
+    }
+}
+
+/// Resolve the theme the UI should render. High contrast wins over the
+/// user's light/dark choice but keeps its polarity.
+fn resolve_theme(
+    preference: ThemePreference,
+    system_dark: Option<bool>,
+    prefs: &AccessibilityPrefs,
+) -> EffectiveTheme {
+    let dark = match preference {
+        ThemePreference::Light => false,
+        ThemePreference::Dark => true,
+        // The app's design is dark-first, so unknown means dark
+        ThemePreference::System => system_dark.unwrap_or(true),
+    };
+    match (prefs.high_contrast == Some(true), dark) {
+        (true, true) => EffectiveTheme::HighContrastDark,
+        (true, false) => EffectiveTheme::HighContrastLight,
+        (false, true) => EffectiveTheme::Dark,
+        (false, false) => EffectiveTheme::Light,
+    }
+}
+
+/// Settings for a user who hasn't changed them: whatever the OS asks for.
+fn default_settings(prefs: &AccessibilityPrefs) -> AccessibilitySettings {
+    AccessibilitySettings {
+        high_contrast: prefs.high_contrast.unwrap_or(false),
+        large_cursor: prefs.large_cursor.unwrap_or(false),
+        screen_reader_hints: false,
+        reduce_motion: prefs.reduced_motion.unwrap_or(false),
+        font_size_scale: prefs
+            .text_scale
+            .filter(|scale| FONT_SCALE_RANGE.contains(scale))
+            .unwrap_or(1.0),
+    }
+}
+
+fn validate_settings(settings: &AccessibilitySettings) -> Result<(), String> {
+    if !FONT_SCALE_RANGE.contains(&settings.font_size_scale) {
+        return Err(format!(
+            "Font size scale must be between {} and {}, got {}",
+            FONT_SCALE_RANGE.start(),
+            FONT_SCALE_RANGE.end(),
+            settings.font_size_scale
+        ));
+    }
+    Ok(())
+}
+
+/// Poll the preferences in the background and emit changes.
+pub fn start_accessibility_watcher(app: &AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let mut last = read_prefs();
+        loop {
+            std::thread::sleep(POLL_INTERVAL);
+            let current = read_prefs();
+            if current != last {
+                let _ = app.emit(PREFS_CHANGED_EVENT, &current);
+                last = current;
+            }
+        }
+    });
+}
+
+/// Current OS accessibility preferences; unreadable ones are `null`.
+#[tauri::command]
+pub async fn get_system_accessibility_prefs() -> AccessibilityPrefs {
+    read_prefs()
+}
+
+/// The app's accessibility settings, defaulting to the OS preferences
+/// until the user changes them.
+#[tauri::command]
+pub async fn get_accessibility_settings(app: AppHandle) -> Result<AccessibilitySettings, AppError> {
+    let saved = settings::load_settings(&app)?.accessibility;
+    Ok(saved.unwrap_or_else(|| default_settings(&read_prefs())))
+}
+
+/// Save the app's accessibility settings and tell every window to apply them.
+#[tauri::command]
+pub fn set_accessibility_settings(
+    app: AppHandle,
+    settings: AccessibilitySettings,
+) -> Result<(), AppError> {
+    validate_settings(&settings)?;
+    settings::update_settings(&app, |s| s.accessibility = Some(settings))?;
+    let _ = app.emit(SETTINGS_CHANGED_EVENT, settings);
+    Ok(())
+}
+
+/// Theme to render for the user's preference, accounting for high contrast.
+#[tauri::command]
+pub async fn get_effective_theme(app: AppHandle, preference: ThemePreference) -> EffectiveTheme {
+    let system_dark = app
+        .get_webview_window(MAIN_LABEL)
+        .and_then(|w| w.theme().ok())
+        .map(|theme| theme == Theme::Dark);
+    resolve_theme(preference, system_dark, &read_prefs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_platform_values() {
+        assert_eq!(parse_bool("true\n"), Some(true));
+        assert_eq!(parse_bool("0"), Some(false));
+        assert_eq!(parse_bool("The domain/default pair does not exist"), None);
+
+        let reg = "\r\nHKEY_CURRENT_USER\\Control Panel\\Accessibility\\HighContrast\r\n    Flags    REG_SZ    126\r\n";
+        assert_eq!(parse_reg_value(reg, "Flags").as_deref(), Some("126"));
+        assert_eq!(parse_reg_value(reg, "MinAnimate"), None);
+        assert_eq!(parse_reg_dword("0x40"), Some(64));
+        assert_eq!(parse_reg_dword("64"), None);
+    }
+
+    #[test]
+    fn test_settings_default_to_os_prefs() {
+        let unknown = default_settings(&AccessibilityPrefs::default());
+        assert!(!unknown.high_contrast && !unknown.reduce_motion);
+        assert_eq!(unknown.font_size_scale, 1.0);
+
+        let prefs = AccessibilityPrefs {
+            high_contrast: Some(true),
+            reduced_motion: Some(true),
+            large_cursor: Some(true),
+            text_scale: Some(1.5),
+            ..Default::default()
+        };
+        let settings = default_settings(&prefs);
+        assert!(settings.high_contrast && settings.reduce_motion && settings.large_cursor);
+        assert_eq!(settings.font_size_scale, 1.5);
+        assert!(validate_settings(&settings).is_ok());
+
+        for scale in [0.4, 4.5, f32::NAN] {
+            let settings = AccessibilitySettings {
+                font_size_scale: scale,
+                ..settings
+            };
+            assert!(validate_settings(&settings).is_err(), "{}", scale);
+        }
+    }
+
+    #[test]
+    fn test_high_contrast_overrides_theme() {
+        let mut prefs = AccessibilityPrefs::default();
+        assert_eq!(
+            resolve_theme(ThemePreference::System, Some(false), &prefs),
+            EffectiveTheme::Light
+        );
+        assert_eq!(
+            resolve_theme(ThemePreference::System, None, &prefs),
+            EffectiveTheme::Dark
+        );
+
+        prefs.high_contrast = Some(true);
+        assert_eq!(
+            resolve_theme(ThemePreference::Dark, Some(false), &prefs),
+            EffectiveTheme::HighContrastDark
+        );
+        assert_eq!(
+            resolve_theme(ThemePreference::Light, None, &prefs),
+            EffectiveTheme::HighContrastLight
+        );
+    }
+}
@@ -0,0 +1,294 @@
+//! Engine administration commands.
+//!
+//! Every command here checks the matching `admin:*` scope up front (via the
+//! cached token permissions) so the UI gets a clear `MissingScope` error
+//! instead of a bare 403.
+
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+use tauri::{AppHandle, State};
+
+use super::auth::{require_scope, PermissionsCache};
+use super::engine::EngineError;
+use super::http::{self, HttpError};
+use super::save_dialog::{validate_export_path, ExportKind};
+
+/// Scope required to read the audit log
+const AUDIT_SCOPE: &str = "admin:audit";
+/// Scope required to manage engine users
+const USERS_SCOPE: &str = "admin:users";
+/// Scope required to change engine configuration, including rate limits
+const ENGINE_SCOPE: &str = "admin:engine";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditLogEntry {
+    pub id: String,
+    pub event_type: String,
+    pub user_id: Option<String>,
+    pub ip_address: Option<String>,
+    pub timestamp_ms: u64,
+    #[serde(default)]
+    pub details: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditLogPage {
+    pub entries: Vec<AuditLogEntry>,
+    pub total: u64,
+    pub page: u32,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UserRole {
+    Admin,
+    Editor,
+    Viewer,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EngineUser {
+    pub id: String,
+    pub username: String,
+    pub email: String,
+    pub role: String,
+    pub created_at_ms: u64,
+    /// The new user's token; only returned by `create_user`
+    #[serde(default)]
+    pub token: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserPage {
+    pub users: Vec<EngineUser>,
+    pub total: u64,
+    pub page: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    pub requests_per_minute: u32,
+    pub burst_size: u32,
+    #[serde(default)]
+    pub remaining_requests: Option<u32>,
+    #[serde(default)]
+    pub reset_at_ms: Option<u64>,
+}
+
+/// Query string shared by the page and export endpoints.
+fn audit_query(user_id: Option<String>, since_ms: Option<u64>) -> Vec<(&'static str, String)> {
+    let mut query = Vec::new();
+    if let Some(user_id) = user_id {
+        query.push(("user_id", user_id));
+    }
+    if let Some(since_ms) = since_ms {
+        query.push(("since_ms", since_ms.to_string()));
+    }
+    query
+}
+
+/// Fetch one page of the engine's audit log. Requires `admin:audit`.
+#[tauri::command]
+pub async fn get_engine_audit_log(
+    permissions: State<'_, PermissionsCache>,
+    port: u16,
+    page: u32,
+    page_size: u32,
+    user_id: Option<String>,
+    since_ms: Option<u64>,
+) -> Result<AuditLogPage, EngineError> {
+    require_scope(&permissions, port, AUDIT_SCOPE).await?;
+
+    let mut query = audit_query(user_id, since_ms);
+    query.push(("page", page.to_string()));
+    query.push(("page_size", page_size.to_string()));
+
+    let request = http::request(Method::GET, port, "/v1/audit-log")?.query(&query);
+    Ok(http::send_json(request).await?)
+}
+
+/// Stream the full audit log (JSON Lines) to a file. Requires `admin:audit`.
+///
+/// `path` should come from `choose_save_path` with kind `audit_log`.
+/// Returns the number of bytes written.
+#[tauri::command]
+pub async fn export_engine_audit_log(
+    app: AppHandle,
+    permissions: State<'_, PermissionsCache>,
+    port: u16,
+    path: String,
+    user_id: Option<String>,
+    since_ms: Option<u64>,
+) -> Result<u64, EngineError> {
+    require_scope(&permissions, port, AUDIT_SCOPE).await?;
+    let path = validate_export_path(&app, ExportKind::AuditLog, &PathBuf::from(path))
+        .map_err(EngineError::Storage)?;
+
+    let request = http::request(Method::GET, port, "/v1/audit-log/export")?
+        .query(&audit_query(user_id, since_ms))
+        // The export can run long; the shared 30s timeout would cut it off
+        .timeout(std::time::Duration::from_secs(60 * 60));
+    let mut response = http::send(request).await?;
+
+    // Write to a temp file so a failed export never leaves a partial log behind
+    let tmp = path.with_extension("part");
+    let mut file = File::create(&tmp).map_err(|e| EngineError::Storage(e.to_string()))?;
+    let mut written = 0u64;
+    let result: Result<(), EngineError> = async {
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| HttpError::Transport(e.to_string()))?
+        {
+            file.write_all(&chunk)
+                .map_err(|e| EngineError::Storage(e.to_string()))?;
+            written += chunk.len() as u64;
+        }
+        file.sync_all()
+            .map_err(|e| EngineError::Storage(e.to_string()))
+    }
+    .await;
+
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(e);
+    }
+    std::fs::rename(&tmp, &path).map_err(|e| EngineError::Storage(e.to_string()))?;
+
+    Ok(written)
+}
+
+/// Create an engine user. Requires `admin:users`.
+///
+/// The returned `token` is the only time the new user's token is shown.
+#[tauri::command]
+pub async fn create_user(
+    permissions: State<'_, PermissionsCache>,
+    port: u16,
+    username: String,
+    email: String,
+    role: UserRole,
+) -> Result<EngineUser, EngineError> {
+    require_scope(&permissions, port, USERS_SCOPE).await?;
+
+    let body = serde_json::json!({ "username": username, "email": email, "role": role });
+    let request = http::request(Method::POST, port, "/v1/admin/users")?.json(&body);
+    Ok(http::send_json(request).await?)
+}
+
+/// Delete an engine user, optionally handing their data to another user.
+/// Requires `admin:users`.
+#[tauri::command]
+pub async fn delete_user(
+    permissions: State<'_, PermissionsCache>,
+    port: u16,
+    user_id: String,
+    transfer_data_to: Option<String>,
+) -> Result<(), EngineError> {
+    require_scope(&permissions, port, USERS_SCOPE).await?;
+
+    let path = format!("/v1/admin/users/{}", http::path_segment(&user_id)?);
+    let mut request = http::request(Method::DELETE, port, &path)?;
+    if let Some(target) = transfer_data_to {
+        request = request.query(&[("transfer_data_to", target)]);
+    }
+    http::send(request).await?;
+    Ok(())
+}
+
+/// List engine users, one page at a time. Requires `admin:users`.
+#[tauri::command]
+pub async fn list_users(
+    permissions: State<'_, PermissionsCache>,
+    port: u16,
+    page: u32,
+) -> Result<UserPage, EngineError> {
+    require_scope(&permissions, port, USERS_SCOPE).await?;
+
+    let request = http::request(Method::GET, port, "/v1/admin/users")?.query(&[("page", page)]);
+    Ok(http::send_json(request).await?)
+}
+
+/// Change a user's role. Requires `admin:users`.
+#[tauri::command]
+pub async fn update_user_role(
+    permissions: State<'_, PermissionsCache>,
+    port: u16,
+    user_id: String,
+    role: UserRole,
+) -> Result<(), EngineError> {
+    require_scope(&permissions, port, USERS_SCOPE).await?;
+
+    let path = format!("/v1/admin/users/{}/role", http::path_segment(&user_id)?);
+    let body = serde_json::json!({ "role": role });
+    http::send(http::request(Method::PUT, port, &path)?.json(&body)).await?;
+    Ok(())
+}
+
+/// Set the rate limit for a user, or the global default when `user_id` is
+/// `None`. Requires `admin:engine`.
+#[tauri::command]
+pub async fn set_engine_rate_limit(
+    permissions: State<'_, PermissionsCache>,
+    port: u16,
+    user_id: Option<String>,
+    requests_per_minute: u32,
+    burst_size: u32,
+) -> Result<(), EngineError> {
+    if requests_per_minute == 0 {
+        return Err(EngineError::RateLimitConfigError(
+            "requests_per_minute must be at least 1".to_string(),
+        ));
+    }
+    if burst_size == 0 {
+        return Err(EngineError::RateLimitConfigError(
+            "burst_size must be at least 1".to_string(),
+        ));
+    }
+    require_scope(&permissions, port, ENGINE_SCOPE).await?;
+
+    let body = serde_json::json!({
+        "user_id": user_id,
+        "requests_per_minute": requests_per_minute,
+        "burst_size": burst_size,
+    });
+    let request = http::request(Method::PATCH, port, "/v1/admin/rate-limits")?.json(&body);
+    match http::send(request).await {
+        Ok(_) => Ok(()),
+        // The engine rejects limits it can't apply with a validation error
+        Err(HttpError::Status {
+            status: 400 | 422,
+            body,
+        }) => Err(EngineError::RateLimitConfigError(body)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Get the rate limit for a user, or the global default. Requires `admin:engine`.
+///
+/// `remaining_requests` and `reset_at_ms` are only what the engine reports
+/// for that user. The `X-RateLimit-*` headers seen on this port describe the
+/// caller's own token, so they're left to [`get_rate_limit_status`].
+#[tauri::command]
+pub async fn get_engine_rate_limits(
+    permissions: State<'_, PermissionsCache>,
+    port: u16,
+    user_id: Option<String>,
+) -> Result<RateLimitConfig, EngineError> {
+    require_scope(&permissions, port, ENGINE_SCOPE).await?;
+
+    let mut request = http::request(Method::GET, port, "/v1/admin/rate-limits")?;
+    if let Some(user_id) = user_id {
+        request = request.query(&[("user_id", user_id)]);
+    }
+    Ok(http::send_json(request).await?)
+}
+
+/// Latest `X-RateLimit-*` headers seen from the engine, if any.
+#[tauri::command]
+pub fn get_rate_limit_status(port: u16) -> Option<http::RateLimitStatus> {
+    http::rate_limit_status(port)
+}
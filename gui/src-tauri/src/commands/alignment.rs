@@ -0,0 +1,227 @@
+//! Word alignment of a Greek text with its English translation.
+//!
+//! The engine aligns with `/v1/text/align`. Engines without that endpoint
+//! (404) get a naive alignment instead: English words are shared out over
+//! the Greek words in proportion to the two word counts, with a low
+//! confidence. Long texts whose sentences pair up are sent a sentence at a
+//! time, emitting `alignment-progress` after each. Results are cached in
+//! memory by a hash of the two texts.
+
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, State};
+
+use super::cache::TtlCache;
+use super::engine::EngineError;
+use super::http::{self, HttpError};
+use super::telemetry::{self, Feature};
+use super::text_utils::{sentences_in, SplitLanguage};
+
+const PROGRESS_EVENT: &str = "alignment-progress";
+/// Greek words above which a text is aligned a sentence at a time
+const LONG_TEXT_WORDS: usize = 200;
+const ALIGNMENT_TTL: Duration = Duration::from_secs(60 * 60);
+const ALIGNMENT_CACHE_SIZE: usize = 200;
+/// Confidence of a naive pair when both texts have the same word count
+const NAIVE_CONFIDENCE: f32 = 0.3;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlignedPair {
+    pub greek_tokens: Vec<String>,
+    /// Empty when the Greek has no counterpart
+    pub english_tokens: Vec<String>,
+    /// 0–1
+    pub confidence: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlignedText {
+    pub pairs: Vec<AlignedPair>,
+    /// Aligned locally because the engine has no alignment endpoint
+    #[serde(default)]
+    pub naive: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AlignmentProgress {
+    pub chunks_done: usize,
+    pub chunks_total: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct AlignRequest<'a> {
+    greek_text: &'a str,
+    english_text: &'a str,
+}
+
+/// Alignments, keyed by `alignment_key`.
+pub struct AlignmentCache(Mutex<TtlCache<String, AlignedText>>);
+
+impl Default for AlignmentCache {
+    fn default() -> Self {
+        Self(Mutex::new(TtlCache::with_capacity(
+            ALIGNMENT_TTL,
+            ALIGNMENT_CACHE_SIZE,
+        )))
+    }
+}
+
+/// SHA-256 of the two texts, hex.
+fn alignment_key(greek: &str, english: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(greek.as_bytes());
+    // Separator, so moving text across the boundary changes the key
+    hasher.update([0]);
+    hasher.update(english.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+/// The pieces to align: sentence pairs for long texts whose sentence
+/// counts match, otherwise the whole texts.
+fn chunks(greek: &str, english: &str) -> Vec<(String, String)> {
+    let whole = vec![(greek.to_string(), english.to_string())];
+    if greek.split_whitespace().count() <= LONG_TEXT_WORDS {
+        return whole;
+    }
+    let greek_sentences = sentences_in(greek, SplitLanguage::Greek);
+    let english_sentences = sentences_in(english, SplitLanguage::English);
+    if greek_sentences.len() != english_sentences.len() {
+        return whole;
+    }
+    greek_sentences
+        .into_iter()
+        .zip(english_sentences)
+        .map(|(g, e)| (g.text, e.text))
+        .collect()
+}
+
+/// Share the English words out over the Greek words in proportion to the
+/// word counts. Greek words left without English join the pair before.
+pub fn naive_alignment(greek: &str, english: &str) -> Vec<AlignedPair> {
+    let greek: Vec<&str> = greek.split_whitespace().collect();
+    let english: Vec<&str> = english.split_whitespace().collect();
+    let (n, m) = (greek.len(), english.len());
+    if n == 0 {
+        return Vec::new();
+    }
+    let confidence = NAIVE_CONFIDENCE * n.min(m) as f32 / n.max(m) as f32;
+    let mut pairs: Vec<AlignedPair> = Vec::new();
+    for (i, word) in greek.iter().enumerate() {
+        let english_tokens: Vec<String> = english[(i * m).div_ceil(n)..((i + 1) * m).div_ceil(n)]
+            .iter()
+            .map(|w| w.to_string())
+            .collect();
+        match pairs.last_mut() {
+            Some(last) if english_tokens.is_empty() => last.greek_tokens.push(word.to_string()),
+            _ => pairs.push(AlignedPair {
+                greek_tokens: vec![word.to_string()],
+                english_tokens,
+                confidence,
+            }),
+        }
+    }
+    pairs
+}
+
+/// Align a Greek text with its English translation, from the cache when
+/// the same pair was aligned before.
+#[tauri::command]
+pub async fn align_parallel_texts(
+    app: AppHandle,
+    cache: State<'_, AlignmentCache>,
+    port: u16,
+    greek_text: String,
+    english_text: String,
+) -> Result<AlignedText, EngineError> {
+    telemetry::record_feature(Feature::Alignment);
+    let key = alignment_key(&greek_text, &english_text);
+    if let Some(aligned) = cache.0.lock().unwrap().get(&key) {
+        return Ok(aligned);
+    }
+
+    let chunks = chunks(&greek_text, &english_text);
+    let mut aligned = AlignedText {
+        pairs: Vec::new(),
+        naive: false,
+    };
+    for (done, (greek, english)) in chunks.iter().enumerate() {
+        if aligned.naive {
+            aligned.pairs.extend(naive_alignment(greek, english));
+        } else {
+            let body = AlignRequest {
+                greek_text: greek,
+                english_text: english,
+            };
+            let request = http::request(Method::POST, port, "/v1/text/align")?.json(&body);
+            match http::send_json::<AlignedText>(request).await {
+                Ok(chunk) => aligned.pairs.extend(chunk.pairs),
+                Err(HttpError::Status { status: 404, .. }) => {
+                    aligned.naive = true;
+                    aligned.pairs.extend(naive_alignment(greek, english));
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        if chunks.len() > 1 {
+            let _ = app.emit(
+                PROGRESS_EVENT,
+                AlignmentProgress {
+                    chunks_done: done + 1,
+                    chunks_total: chunks.len(),
+                },
+            );
+        }
+    }
+
+    cache.0.lock().unwrap().insert(key, aligned.clone());
+    Ok(aligned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(pairs: &[AlignedPair]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|p| (p.greek_tokens.join(" "), p.english_tokens.join(" ")))
+            .collect()
+    }
+
+    #[test]
+    fn test_naive_alignment() {
+        let pairs = naive_alignment("Ἐν ἀρχῇ ἦν ὁ λόγος", "In the beginning was the Word");
+        assert_eq!(
+            tokens(&pairs),
+            [
+                ("Ἐν".to_string(), "In the".to_string()),
+                ("ἀρχῇ".to_string(), "beginning".to_string()),
+                ("ἦν".to_string(), "was".to_string()),
+                ("ὁ".to_string(), "the".to_string()),
+                ("λόγος".to_string(), "Word".to_string()),
+            ]
+        );
+        assert!((pairs[0].confidence - 0.25).abs() < 1e-6);
+
+        // More Greek than English: the extra Greek joins the pair before
+        let pairs = naive_alignment("ἐγώ εἰμι ὁ ἄρτος", "I am bread");
+        assert_eq!(pairs.len(), 3);
+        assert_eq!(pairs[2].greek_tokens, ["ὁ", "ἄρτος"]);
+        assert!(naive_alignment(" ", "Word").is_empty());
+    }
+
+    #[test]
+    fn test_chunks_and_key() {
+        assert_eq!(chunks("λόγος.", "Word.").len(), 1);
+        let greek = "καὶ ἦν. ".repeat(LONG_TEXT_WORDS);
+        let english = "and was. ".repeat(LONG_TEXT_WORDS);
+        assert_eq!(chunks(&greek, &english).len(), LONG_TEXT_WORDS);
+        assert_eq!(chunks(&greek, "and was").len(), 1);
+
+        assert_eq!(alignment_key("ab", "c"), alignment_key("ab", "c"));
+        assert_ne!(alignment_key("ab", "c"), alignment_key("a", "bc"));
+    }
+}
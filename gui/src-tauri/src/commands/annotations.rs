@@ -0,0 +1,453 @@
+//! User annotations on verses.
+//!
+//! Annotations live in `annotations.json` under the app data dir, oldest
+//! first. Each is attached to a single verse, stored in the reference
+//! parser's canonical form. `export_annotations` and `import_annotations`
+//! move them between machines as JSON or CSV.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+use super::document_export::escape_markdown;
+use super::errors::AppError;
+use super::interlinear_table::csv_field;
+use super::passage_lists::parse_csv;
+use super::reference::{canonical_reference, parse_reference_list};
+use super::save_dialog::validate_export_path;
+use super::saved_results::ExportFormat;
+use super::storage;
+
+const ANNOTATIONS_FILE: &str = "annotations.json";
+/// Larger files are not annotation backups
+const MAX_IMPORT_BYTES: u64 = 50 * 1024 * 1024;
+/// Columns of the CSV export, in order
+const CSV_COLUMNS: [&str; 6] = ["id", "reference", "text", "color", "created_at_ms", "tags"];
+/// Separates tags inside the CSV `tags` column
+const TAG_SEPARATOR: char = ';';
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Annotation {
+    pub id: String,
+    /// Canonical single verse, e.g. "John 3:16"
+    pub reference: String,
+    pub text: String,
+    pub color: String,
+    pub created_at_ms: u64,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportResult {
+    pub imported: u32,
+    /// Annotations whose id was already present
+    pub skipped_duplicates: u32,
+    /// Annotations that could not be read, with the reason
+    pub errors: Vec<String>,
+}
+
+pub fn load_annotations(app: &AppHandle) -> Result<Vec<Annotation>, String> {
+    storage::read_json_or_default(&storage::app_data_file(app, ANNOTATIONS_FILE)?)
+}
+
+fn store_annotations(app: &AppHandle, annotations: &[Annotation]) -> Result<(), String> {
+    storage::write_json(&storage::app_data_file(app, ANNOTATIONS_FILE)?, annotations)
+}
+
+/// Canonical form of a single-verse reference like "John 3:16".
+fn verse_reference(reference: &str) -> Result<String, String> {
+    let parsed = parse_reference_list(reference).map_err(|e| e.to_string())?;
+    if parsed.verses.len() != 1 {
+        return Err(format!(
+            "An annotation belongs to one verse (BOOK CHAPTER:VERSE), not {}",
+            parsed.canonical
+        ));
+    }
+    Ok(parsed.canonical)
+}
+
+/// Trimmed tags without empty ones or repeats.
+fn clean_tags(tags: Vec<String>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    tags.into_iter()
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty() && seen.insert(t.to_lowercase()))
+        .collect()
+}
+
+fn clean_color(color: &str) -> Result<String, String> {
+    let color = color.trim();
+    if color.is_empty() {
+        return Err("An annotation needs a color".to_string());
+    }
+    Ok(color.to_string())
+}
+
+/// Validate and normalize an annotation read from a file.
+fn clean_annotation(mut annotation: Annotation) -> Result<Annotation, String> {
+    if annotation.id.trim().is_empty() {
+        return Err("missing id".to_string());
+    }
+    annotation.reference = verse_reference(&annotation.reference)?;
+    annotation.color = clean_color(&annotation.color)?;
+    annotation.tags = clean_tags(annotation.tags);
+    Ok(annotation)
+}
+
+fn write_annotations(
+    out: &mut dyn Write,
+    format: ExportFormat,
+    annotations: &[Annotation],
+) -> std::io::Result<()> {
+    match format {
+        ExportFormat::Json => {
+            serde_json::to_writer_pretty(&mut *out, annotations)?;
+            writeln!(out)
+        }
+        ExportFormat::Csv => {
+            write!(out, "{}\r\n", CSV_COLUMNS.join(","))?;
+            for a in annotations {
+                write!(
+                    out,
+                    "{},{},{},{},{},{}\r\n",
+                    csv_field(&a.id),
+                    csv_field(&a.reference),
+                    csv_field(&a.text),
+                    csv_field(&a.color),
+                    a.created_at_ms,
+                    csv_field(&a.tags.join(&TAG_SEPARATOR.to_string()))
+                )?;
+            }
+            Ok(())
+        }
+        ExportFormat::Markdown => {
+            writeln!(out, "# Annotations\n")?;
+            for a in annotations {
+                write!(
+                    out,
+                    "- **{}** — {}",
+                    escape_markdown(&a.reference),
+                    escape_markdown(&a.text)
+                )?;
+                if !a.tags.is_empty() {
+                    let tags: Vec<String> = a.tags.iter().map(|t| escape_markdown(t)).collect();
+                    write!(out, " *({})*", tags.join(", "))?;
+                }
+                writeln!(out)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Annotations from an exported CSV file; rows that don't fit are errors.
+fn read_csv(text: &str) -> Result<(Vec<Annotation>, Vec<String>), String> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut records = parse_csv(text, ',').into_iter();
+    let (_, header) = records.next().ok_or("The CSV file is empty")?;
+    let columns = CSV_COLUMNS
+        .iter()
+        .map(|name| {
+            header
+                .iter()
+                .position(|h| h.trim().eq_ignore_ascii_case(name))
+                .ok_or_else(|| format!("The CSV file has no \"{}\" column", name))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let mut annotations = Vec::new();
+    let mut errors = Vec::new();
+    for (line, record) in records {
+        let field = |i: usize| record.get(columns[i]).cloned().unwrap_or_default();
+        let Ok(created_at_ms) = field(4).trim().parse() else {
+            errors.push(format!("line {}: invalid created_at_ms", line));
+            continue;
+        };
+        annotations.push(Annotation {
+            id: field(0).trim().to_string(),
+            reference: field(1),
+            text: field(2),
+            color: field(3),
+            created_at_ms,
+            tags: field(5).split(TAG_SEPARATOR).map(str::to_string).collect(),
+        });
+    }
+    Ok((annotations, errors))
+}
+
+/// Add `incoming` to `existing` (or replace it without `merge`), skipping
+/// ids already present. Returns how many were added and skipped.
+fn merge_annotations(
+    existing: &mut Vec<Annotation>,
+    incoming: Vec<Annotation>,
+    merge: bool,
+) -> (u32, u32) {
+    if !merge {
+        existing.clear();
+    }
+    let mut ids: HashSet<String> = existing.iter().map(|a| a.id.clone()).collect();
+    let (mut imported, mut duplicates) = (0, 0);
+    for annotation in incoming {
+        if ids.insert(annotation.id.clone()) {
+            existing.push(annotation);
+            imported += 1;
+        } else {
+            duplicates += 1;
+        }
+    }
+    existing.sort_by_key(|a| a.created_at_ms);
+    (imported, duplicates)
+}
+
+/// Replacing only goes ahead when every annotation in the file was read,
+/// so a damaged or empty backup can't wipe the stored ones.
+fn check_replace(incoming: &[Annotation], errors: &[String]) -> Result<(), String> {
+    if !errors.is_empty() {
+        return Err(format!(
+            "{} annotations in the backup could not be read, so nothing was replaced: {}",
+            errors.len(),
+            errors.join("; ")
+        ));
+    }
+    if incoming.is_empty() {
+        return Err("The backup has no annotations, so nothing was replaced".to_string());
+    }
+    Ok(())
+}
+
+fn is_csv(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"))
+}
+
+/// All annotations, oldest first; only those on `reference` when given.
+#[tauri::command]
+pub fn list_annotations(
+    app: AppHandle,
+    reference: Option<String>,
+) -> Result<Vec<Annotation>, AppError> {
+    let mut annotations = load_annotations(&app)?;
+    if let Some(reference) = reference {
+        let wanted = canonical_reference(&reference);
+        annotations.retain(|a| a.reference == wanted);
+    }
+    Ok(annotations)
+}
+
+/// Annotate a verse.
+#[tauri::command]
+pub fn create_annotation(
+    app: AppHandle,
+    reference: String,
+    text: String,
+    color: String,
+    tags: Option<Vec<String>>,
+) -> Result<Annotation, AppError> {
+    let annotation = Annotation {
+        id: uuid::Uuid::new_v4().to_string(),
+        reference: verse_reference(&reference)?,
+        text,
+        color: clean_color(&color)?,
+        created_at_ms: storage::now_ms(),
+        tags: clean_tags(tags.unwrap_or_default()),
+    };
+    let mut annotations = load_annotations(&app)?;
+    annotations.push(annotation.clone());
+    store_annotations(&app, &annotations)?;
+    Ok(annotation)
+}
+
+/// Change the text, color or tags of an annotation; unset fields are kept.
+#[tauri::command]
+pub fn update_annotation(
+    app: AppHandle,
+    id: String,
+    text: Option<String>,
+    color: Option<String>,
+    tags: Option<Vec<String>>,
+) -> Result<Annotation, AppError> {
+    let mut annotations = load_annotations(&app)?;
+    let annotation = annotations
+        .iter_mut()
+        .find(|a| a.id == id)
+        .ok_or_else(|| format!("No annotation {}", id))?;
+    if let Some(text) = text {
+        annotation.text = text;
+    }
+    if let Some(color) = color {
+        annotation.color = clean_color(&color)?;
+    }
+    if let Some(tags) = tags {
+        annotation.tags = clean_tags(tags);
+    }
+    let updated = annotation.clone();
+    store_annotations(&app, &annotations)?;
+    Ok(updated)
+}
+
+/// Remove an annotation; `false` when there was none with that id.
+#[tauri::command]
+pub fn delete_annotation(app: AppHandle, id: String) -> Result<bool, AppError> {
+    let mut annotations = load_annotations(&app)?;
+    let before = annotations.len();
+    annotations.retain(|a| a.id != id);
+    if annotations.len() == before {
+        return Ok(false);
+    }
+    store_annotations(&app, &annotations)?;
+    Ok(true)
+}
+
+/// Write all annotations to a file and return how many were written.
+///
+/// `output_path` should come from `choose_save_path` with the kind that
+/// matches `format`.
+#[tauri::command]
+pub fn export_annotations(
+    app: AppHandle,
+    output_path: String,
+    format: ExportFormat,
+) -> Result<u32, AppError> {
+    let path = validate_export_path(&app, format.kind(), &PathBuf::from(output_path))?;
+    let annotations = load_annotations(&app)?;
+    storage::write_atomic_with(&path, |out| write_annotations(out, format, &annotations))?;
+    Ok(annotations.len() as u32)
+}
+
+/// Read annotations exported as JSON or CSV (by extension).
+///
+/// With `merge` they are added to the existing ones, otherwise they
+/// replace them. Annotations whose id is already present are skipped.
+/// Replacing fails, leaving the stored annotations alone, when the file
+/// has none or any of them can't be read.
+#[tauri::command]
+pub fn import_annotations(
+    app: AppHandle,
+    input_path: String,
+    merge: bool,
+) -> Result<ImportResult, AppError> {
+    let path = PathBuf::from(&input_path);
+    let size = fs::metadata(&path)
+        .map_err(|e| format!("{}: {}", input_path, e))?
+        .len();
+    if size > MAX_IMPORT_BYTES {
+        return Err(AppError::InvalidInput(format!(
+            "{} is too large for an annotation backup",
+            input_path
+        )));
+    }
+    let text = fs::read_to_string(&path).map_err(|e| format!("{}: {}", input_path, e))?;
+    let (read, mut errors) = if is_csv(&path) {
+        read_csv(&text)?
+    } else {
+        let annotations: Vec<Annotation> =
+            serde_json::from_str(&text).map_err(|e| format!("{}: {}", input_path, e))?;
+        (annotations, Vec::new())
+    };
+
+    let mut incoming = Vec::new();
+    for annotation in read {
+        let id = annotation.id.clone();
+        match clean_annotation(annotation) {
+            Ok(annotation) => incoming.push(annotation),
+            Err(e) => errors.push(format!("{}: {}", id, e)),
+        }
+    }
+    if !merge {
+        check_replace(&incoming, &errors)?;
+    }
+    let mut annotations = load_annotations(&app)?;
+    let (imported, skipped_duplicates) = merge_annotations(&mut annotations, incoming, merge);
+    store_annotations(&app, &annotations)?;
+    Ok(ImportResult {
+        imported,
+        skipped_duplicates,
+        errors,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn annotation(id: &str, created_at_ms: u64) -> Annotation {
+        Annotation {
+            id: id.to_string(),
+            reference: "John 3:16".to_string(),
+            text: "loved, \"so\"".to_string(),
+            color: "#ffd54f".to_string(),
+            created_at_ms,
+            tags: vec!["love".to_string(), "gospel".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_verse_reference() {
+        assert_eq!(verse_reference("jn 3.16").unwrap(), "John 3:16");
+        assert_eq!(verse_reference("Jude 3").unwrap(), "Jude 1:3");
+        assert!(verse_reference("John 3:16-18").is_err());
+        assert!(verse_reference("John 3").is_err());
+        assert!(verse_reference("Hezekiah 1:1").is_err());
+    }
+
+    #[test]
+    fn test_csv_round_trip() {
+        let annotations = [annotation("a", 1), annotation("b", 2)];
+        let mut csv = Vec::new();
+        write_annotations(&mut csv, ExportFormat::Csv, &annotations).unwrap();
+        let (read, errors) = read_csv(&String::from_utf8(csv).unwrap()).unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(read, annotations);
+
+        let (_, errors) =
+            read_csv("id,reference,text,color,created_at_ms,tags\nx,John 1:1,,red,soon,\n")
+                .unwrap();
+        assert_eq!(errors, ["line 2: invalid created_at_ms"]);
+        assert!(read_csv("id,reference\n").is_err());
+    }
+
+    #[test]
+    fn test_merge_annotations() {
+        let mut existing = vec![annotation("a", 1), annotation("c", 3)];
+        let incoming = vec![annotation("b", 2), annotation("a", 9), annotation("b", 2)];
+        assert_eq!(
+            merge_annotations(&mut existing, incoming.clone(), true),
+            (1, 2)
+        );
+        let ids: Vec<_> = existing.iter().map(|a| a.id.as_str()).collect();
+        assert_eq!(ids, ["a", "b", "c"]);
+
+        assert_eq!(merge_annotations(&mut existing, incoming, false), (2, 1));
+        assert_eq!(existing.len(), 2);
+    }
+
+    #[test]
+    fn test_check_replace() {
+        let incoming = vec![annotation("a", 1)];
+        assert!(check_replace(&incoming, &[]).is_ok());
+        assert!(check_replace(&[], &[]).is_err());
+        let errors = vec!["line 2: invalid created_at_ms".to_string()];
+        assert!(check_replace(&incoming, &errors).is_err());
+        assert!(check_replace(&[], &errors).is_err());
+    }
+
+    #[test]
+    fn test_clean_annotation() {
+        let mut raw = annotation("a", 1);
+        raw.reference = "Jn 3.16".to_string();
+        raw.tags = vec![" love ".to_string(), "Love".to_string(), String::new()];
+        let cleaned = clean_annotation(raw).unwrap();
+        assert_eq!(cleaned.reference, "John 3:16");
+        assert_eq!(cleaned.tags, ["love"]);
+
+        let mut raw = annotation(" ", 1);
+        assert!(clean_annotation(raw.clone()).is_err());
+        raw.id = "a".to_string();
+        raw.color = " ".to_string();
+        assert!(clean_annotation(raw).is_err());
+    }
+}
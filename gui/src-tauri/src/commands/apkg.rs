@@ -0,0 +1,308 @@
+//! Anki package (.apkg) writer for flashcard decks.
+//!
+//! A package is a zip holding `collection.anki2`, an SQLite database in
+//! Anki's schema 11, and a `media` index (empty here). The collection has
+//! one deck and one note type, "Red Letters Vocabulary", whose fields are
+//! `NOTE_FIELDS`. The note type id is fixed and note GUIDs come from the
+//! caller, so importing a deck again updates its notes instead of adding
+//! copies.
+
+use serde_json::json;
+use sha1::{Digest, Sha1};
+use std::fs;
+use std::io::{Cursor, Write};
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use super::storage;
+
+/// Fields of the note type, in order
+pub const NOTE_FIELDS: [&str; 5] = ["Front", "Back", "PartOfSpeech", "Strongs", "Example"];
+/// Same id in every package, so Anki reuses the note type
+const MODEL_ID: i64 = 1_706_140_800_000;
+const MODEL_NAME: &str = "Red Letters Vocabulary";
+/// Anki's field separator
+const FIELD_SEPARATOR: char = '\x1f';
+
+const SCHEMA: &str = "
+CREATE TABLE col (
+    id integer primary key, crt integer not null, mod integer not null,
+    scm integer not null, ver integer not null, dty integer not null,
+    usn integer not null, ls integer not null, conf text not null,
+    models text not null, decks text not null, dconf text not null, tags text not null
+);
+CREATE TABLE notes (
+    id integer primary key, guid text not null, mid integer not null,
+    mod integer not null, usn integer not null, tags text not null, flds text not null,
+    sfld integer not null, csum integer not null, flags integer not null, data text not null
+);
+CREATE TABLE cards (
+    id integer primary key, nid integer not null, did integer not null,
+    ord integer not null, mod integer not null, usn integer not null, type integer not null,
+    queue integer not null, due integer not null, ivl integer not null,
+    factor integer not null, reps integer not null, lapses integer not null,
+    left integer not null, odue integer not null, odid integer not null,
+    flags integer not null, data text not null
+);
+CREATE TABLE revlog (
+    id integer primary key, cid integer not null, usn integer not null,
+    ease integer not null, ivl integer not null, lastIvl integer not null,
+    factor integer not null, time integer not null, type integer not null
+);
+CREATE TABLE graves (usn integer not null, oid integer not null, type integer not null);
+CREATE INDEX ix_notes_usn on notes (usn);
+CREATE INDEX ix_cards_usn on cards (usn);
+CREATE INDEX ix_revlog_usn on revlog (usn);
+CREATE INDEX ix_cards_nid on cards (nid);
+CREATE INDEX ix_cards_sched on cards (did, queue, due);
+CREATE INDEX ix_revlog_cid on revlog (cid);
+CREATE INDEX ix_notes_csum on notes (csum);
+";
+
+const QUESTION_TEMPLATE: &str = r#"<div class="greek">{{Front}}</div>"#;
+const ANSWER_TEMPLATE: &str = concat!(
+    "{{FrontSide}}<hr id=answer><div>{{Back}}</div>",
+    r#"{{#PartOfSpeech}}<div class="detail">{{PartOfSpeech}}</div>{{/PartOfSpeech}}"#,
+    r#"{{#Strongs}}<div class="detail">{{Strongs}}</div>{{/Strongs}}"#,
+    r#"{{#Example}}<div class="example">{{Example}}</div>{{/Example}}"#,
+);
+const CARD_CSS: &str = "\
+.card { font-family: Arial, sans-serif; font-size: 20px; text-align: center; }
+.greek { font-family: 'Gentium Plus', 'SBL Greek', serif; font-size: 32px; }
+.detail { color: #666; font-size: 16px; }
+.example { margin-top: 1em; font-size: 16px; }
+";
+
+#[derive(Debug, Clone)]
+pub struct AnkiNote {
+    /// Stable across exports, so a second import updates the note
+    pub guid: String,
+    /// HTML, one per `NOTE_FIELDS`
+    pub fields: Vec<String>,
+    /// Plain text of the first field, for sorting and duplicate checks
+    pub sort_field: String,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AnkiDeck {
+    /// Stable across exports, like the note GUIDs
+    pub id: i64,
+    pub name: String,
+    pub notes: Vec<AnkiNote>,
+}
+
+/// Anki's duplicate-check hash: the first 8 hex digits of the SHA-1.
+fn field_checksum(text: &str) -> i64 {
+    let digest = Sha1::digest(text.as_bytes());
+    i64::from(u32::from_be_bytes([
+        digest[0], digest[1], digest[2], digest[3],
+    ]))
+}
+
+fn collection_json(deck: &AnkiDeck, now_secs: i64) -> (String, String, String, String) {
+    let conf = json!({
+        "activeDecks": [1], "curDeck": 1, "newSpread": 0, "collapseTime": 1200,
+        "timeLim": 0, "estTimes": true, "dueCounts": true, "curModel": null,
+        "nextPos": 1, "sortType": "noteFld", "sortBackwards": false, "addToCur": true,
+    });
+    let fields: Vec<_> = NOTE_FIELDS
+        .iter()
+        .enumerate()
+        .map(|(ord, name)| {
+            json!({
+                "name": name, "ord": ord, "sticky": false, "rtl": false,
+                "font": "Arial", "size": 20, "media": [],
+            })
+        })
+        .collect();
+    let models = json!({ MODEL_ID.to_string(): {
+        "id": MODEL_ID, "name": MODEL_NAME, "type": 0, "mod": now_secs, "usn": -1,
+        "sortf": 0, "did": deck.id, "flds": fields, "css": CARD_CSS,
+        "tmpls": [{
+            "name": "Card 1", "ord": 0, "qfmt": QUESTION_TEMPLATE, "afmt": ANSWER_TEMPLATE,
+            "bqfmt": "", "bafmt": "", "did": null,
+        }],
+        "latexPre": "\\documentclass[12pt]{article}\n\\special{papersize=3in,5in}\n\\usepackage[utf8]{inputenc}\n\\usepackage{amssymb,amsmath}\n\\pagestyle{empty}\n\\setlength{\\parindent}{0in}\n\\begin{document}\n",
+        "latexPost": "\\end{document}",
+        "req": [[0, "any", [0]]], "tags": [], "vers": [],
+    }});
+    let deck_json = |id: i64, name: &str| {
+        json!({
+            "id": id, "name": name, "desc": "", "mod": now_secs, "usn": -1, "conf": 1,
+            "dyn": 0, "collapsed": false, "extendNew": 10, "extendRev": 50,
+            "newToday": [0, 0], "revToday": [0, 0], "lrnToday": [0, 0], "timeToday": [0, 0],
+        })
+    };
+    let decks = json!({
+        "1": deck_json(1, "Default"),
+        deck.id.to_string(): deck_json(deck.id, &deck.name),
+    });
+    let dconf = json!({ "1": {
+        "id": 1, "name": "Default", "mod": 0, "usn": 0, "maxTaken": 60,
+        "autoplay": true, "replayq": true, "timer": 0,
+        "new": {
+            "bury": true, "delays": [1, 10], "initialFactor": 2500, "ints": [1, 4, 7],
+            "order": 1, "perDay": 20, "separate": true,
+        },
+        "rev": {
+            "bury": true, "ease4": 1.3, "fuzz": 0.05, "ivlFct": 1, "maxIvl": 36500,
+            "minSpace": 1, "perDay": 100,
+        },
+        "lapse": { "delays": [10], "leechAction": 0, "leechFails": 8, "minInt": 1, "mult": 0 },
+    }});
+    (
+        conf.to_string(),
+        models.to_string(),
+        decks.to_string(),
+        dconf.to_string(),
+    )
+}
+
+/// Fill an empty database with the collection.
+fn write_collection(
+    conn: &rusqlite::Connection,
+    deck: &AnkiDeck,
+    now_ms: u64,
+) -> rusqlite::Result<()> {
+    let now_ms = now_ms as i64;
+    let now_secs = now_ms / 1000;
+    conn.execute_batch(SCHEMA)?;
+    let (conf, models, decks, dconf) = collection_json(deck, now_secs);
+    conn.execute(
+        "INSERT INTO col VALUES (1, ?1, ?2, ?2, 11, 0, 0, 0, ?3, ?4, ?5, ?6, '{}')",
+        rusqlite::params![now_secs, now_ms, conf, models, decks, dconf],
+    )?;
+
+    let separator = FIELD_SEPARATOR.to_string();
+    for (i, note) in deck.notes.iter().enumerate() {
+        // Millisecond ids, one apart, as if added one by one
+        let id = now_ms + i as i64;
+        let tags = format!(" {} ", note.tags.join(" "));
+        conn.execute(
+            "INSERT INTO notes VALUES (?1, ?2, ?3, ?4, -1, ?5, ?6, ?7, ?8, 0, '')",
+            rusqlite::params![
+                id,
+                note.guid,
+                MODEL_ID,
+                now_secs,
+                tags,
+                note.fields.join(&separator),
+                note.sort_field,
+                field_checksum(&note.sort_field),
+            ],
+        )?;
+        conn.execute(
+            "INSERT INTO cards VALUES (?1, ?1, ?2, 0, ?3, -1, 0, 0, ?4, 0, 0, 0, 0, 0, 0, 0, 0, '')",
+            rusqlite::params![id, deck.id, now_secs, i as i64 + 1],
+        )?;
+    }
+    Ok(())
+}
+
+/// Write `deck` to `path` as an Anki package. The collection is built in
+/// its own temp file, which is removed afterwards.
+pub fn write_apkg(path: &Path, deck: &AnkiDeck) -> Result<(), String> {
+    let scratch = tempfile::Builder::new()
+        .suffix(".anki2")
+        .tempfile()
+        .map_err(|e| e.to_string())?;
+    let scratch_path = scratch.path();
+    let collection = rusqlite::Connection::open(scratch_path)
+        .and_then(|conn| {
+            write_collection(&conn, deck, storage::now_ms())?;
+            conn.close().map_err(|(_, e)| e)
+        })
+        .map_err(|e| format!("{}: {}", scratch_path.display(), e))
+        .and_then(|()| {
+            fs::read(scratch_path).map_err(|e| format!("{}: {}", scratch_path.display(), e))
+        });
+    drop(scratch);
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let parts: [(&str, &[u8]); 2] = [("collection.anki2", &collection?), ("media", b"{}")];
+    for (name, contents) in parts {
+        zip.start_file(name, options).map_err(|e| e.to_string())?;
+        zip.write_all(contents).map_err(|e| e.to_string())?;
+    }
+    let package = zip.finish().map_err(|e| e.to_string())?.into_inner();
+    storage::write_atomic(path, &package)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_field_checksum() {
+        // sha1("λόγος") = 7be15f98...
+        assert_eq!(field_checksum("λόγος"), 0x7be15f98);
+    }
+
+    #[test]
+    fn test_write_apkg() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("vocabulary.apkg");
+        let note = |lemma: &str, gloss: &str| AnkiNote {
+            guid: format!("guid-{}", lemma),
+            fields: vec![lemma.to_string(), gloss.to_string(), "noun".to_string()],
+            sort_field: lemma.to_string(),
+            tags: vec!["redletters".to_string()],
+        };
+        let deck = AnkiDeck {
+            id: 1_234_567_890,
+            name: "John 1".to_string(),
+            notes: vec![note("λόγος", "word"), note("ἀρχή", "beginning")],
+        };
+        write_apkg(&path, &deck).unwrap();
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        let mut archive = zip::ZipArchive::new(fs::File::open(&path).unwrap()).unwrap();
+        let mut media = String::new();
+        archive
+            .by_name("media")
+            .unwrap()
+            .read_to_string(&mut media)
+            .unwrap();
+        assert_eq!(media, "{}");
+        let collection = dir.path().join("collection.anki2");
+        let mut out = fs::File::create(&collection).unwrap();
+        std::io::copy(&mut archive.by_name("collection.anki2").unwrap(), &mut out).unwrap();
+
+        let conn = rusqlite::Connection::open(&collection).unwrap();
+        let (ver, decks): (i64, String) = conn
+            .query_row("SELECT ver, decks FROM col", [], |r| {
+                Ok((r.get(0)?, r.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(ver, 11);
+        let decks: serde_json::Value = serde_json::from_str(&decks).unwrap();
+        assert_eq!(decks["1234567890"]["name"], "John 1");
+        let notes: Vec<(String, String, String)> = conn
+            .prepare("SELECT flds, sfld, tags FROM notes ORDER BY id")
+            .unwrap()
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            notes[0],
+            (
+                "λόγος\x1fword\x1fnoun".to_string(),
+                "λόγος".to_string(),
+                " redletters ".to_string()
+            )
+        );
+        let cards: i64 = conn
+            .query_row(
+                "SELECT count(*) FROM cards WHERE did = 1234567890",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(cards, 2);
+    }
+}
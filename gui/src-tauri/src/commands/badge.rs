@@ -0,0 +1,147 @@
+//! App icon badge for "needs attention" state.
+//!
+//! Stores that track something the user should look at report their count
+//! with `set_badge_source`. The badge shows the sum and clears when every
+//! source is back to zero. Failed batch items are the only such store so
+//! far; a new one gets a `BadgeSource` variant when it starts reporting.
+//!
+//! The count is applied with the platform badge API: dock badge on macOS,
+//! launcher count on Linux (Unity), and an overlay dot on the Windows
+//! taskbar, which has no numeric badge. Failures are ignored so platforms
+//! without badge support degrade silently.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// Label of the main window (tauri.conf.json default)
+const MAIN_LABEL: &str = "main";
+/// Event emitted with the new breakdown whenever a source count changes
+const BADGE_UPDATED_EVENT: &str = "badge-updated";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BadgeSource {
+    FailedJobs,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BadgeItem {
+    pub source: BadgeSource,
+    pub count: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BadgeBreakdown {
+    pub total: u32,
+    /// Sources with a non-zero count
+    pub items: Vec<BadgeItem>,
+}
+
+/// Current count per badge source.
+#[derive(Default)]
+pub struct BadgeState(Mutex<BTreeMap<BadgeSource, u32>>);
+
+impl BadgeState {
+    fn breakdown(&self) -> BadgeBreakdown {
+        breakdown(&self.0.lock().unwrap())
+    }
+}
+
+fn breakdown(counts: &BTreeMap<BadgeSource, u32>) -> BadgeBreakdown {
+    let items: Vec<BadgeItem> = counts
+        .iter()
+        .filter(|(_, count)| **count > 0)
+        .map(|(source, count)| BadgeItem {
+            source: *source,
+            count: *count,
+        })
+        .collect();
+    BadgeBreakdown {
+        total: items.iter().map(|i| i.count).sum(),
+        items,
+    }
+}
+
+/// Apply the total to the app icon. Unsupported platforms are a no-op.
+fn apply_badge(app: &AppHandle, total: u32) {
+    let Some(window) = app.get_webview_window(MAIN_LABEL) else {
+        return;
+    };
+
+    #[cfg(target_os = "windows")]
+    {
+        let icon = (total > 0).then(overlay_dot);
+        let _ = window.set_overlay_icon(icon);
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        let count = (total > 0).then_some(i64::from(total));
+        let _ = window.set_badge_count(count);
+    }
+}
+
+/// Red dot used as the Windows taskbar overlay.
+#[cfg(target_os = "windows")]
+fn overlay_dot() -> tauri::image::Image<'static> {
+    const SIZE: u32 = 16;
+    let center = (SIZE as f32 - 1.0) / 2.0;
+    let mut rgba = Vec::with_capacity((SIZE * SIZE * 4) as usize);
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            let (dx, dy) = (x as f32 - center, y as f32 - center);
+            let alpha = if dx * dx + dy * dy <= center * center {
+                255
+            } else {
+                0
+            };
+            rgba.extend_from_slice(&[0xc0, 0x1c, 0x28, alpha]);
+        }
+    }
+    tauri::image::Image::new_owned(rgba, SIZE, SIZE)
+}
+
+/// Report the current count for a source and refresh the badge.
+pub fn set_badge_source(app: &AppHandle, source: BadgeSource, count: u32) {
+    let state = app.state::<BadgeState>();
+    let (before, after) = {
+        let mut counts = state.0.lock().unwrap();
+        let before = breakdown(&counts).total;
+        counts.insert(source, count);
+        (before, breakdown(&counts))
+    };
+
+    if before != after.total {
+        apply_badge(app, after.total);
+    }
+    let _ = app.emit(BADGE_UPDATED_EVENT, after);
+}
+
+/// Explain the badge number, one entry per contributing source.
+#[tauri::command]
+pub fn get_badge_breakdown(state: State<'_, BadgeState>) -> BadgeBreakdown {
+    state.breakdown()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_breakdown_skips_resolved_sources() {
+        let mut counts = BTreeMap::new();
+        counts.insert(BadgeSource::FailedJobs, 2);
+
+        let b = breakdown(&counts);
+        assert_eq!(b.total, 2);
+        assert_eq!(b.items.len(), 1);
+        assert_eq!(b.items[0].source, BadgeSource::FailedJobs);
+
+        counts.insert(BadgeSource::FailedJobs, 0);
+        let b = breakdown(&counts);
+        assert_eq!(b.total, 0);
+        assert!(b.items.is_empty());
+    }
+}
@@ -0,0 +1,811 @@
+//! Batch translation jobs that survive app restarts.
+//!
+//! A job is a list of references translated through the same pipeline as
+//! `translate_passage`. The job file under `batch_jobs/` is rewritten after
+//! every item, so after a crash or restart `resume_batch_job` picks up the
+//! items that hadn't finished. Failed items keep their error and can be
+//! queued again with `retry_failed_items`. References are normalized when
+//! the job is created and split into one item per chapter, so "John 3-4"
+//! or a whole book becomes chapter-sized translations; chapters longer
+//! than the translate verse limit are split further.
+//!
+//! While a job runs, progress is shown on the taskbar icon and the system
+//! is kept from sleeping; the number of failed items across all jobs feeds
+//! the app badge.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::window::{ProgressBarState, ProgressBarStatus};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use super::badge::{set_badge_source, BadgeSource};
+use super::engine::EngineError;
+use super::errors::AppError;
+use super::passages::{
+    max_translate_verses, translate_and_cache, TranslateOptions, TranslateOutcome,
+};
+use super::reference::{chapter_chunks, parse_reference_list};
+use super::sleep_inhibit::SleepInhibitor;
+use super::storage;
+
+const JOBS_DIR: &str = "batch_jobs";
+/// Label of the main window (tauri.conf.json default)
+const MAIN_LABEL: &str = "main";
+/// Event emitted when an item finishes, successfully or not
+const ITEM_EVENT: &str = "batch-job-item";
+/// Event emitted with the job's totals after each item and on state changes
+const PROGRESS_EVENT: &str = "batch-job-progress";
+/// Upper bound for concurrent translate requests per job
+const MAX_CONCURRENCY: usize = 4;
+
+/// Run signals shared between the commands and a job's workers
+const RUN: u8 = 0;
+const PAUSE: u8 = 1;
+const CANCEL: u8 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchJobStatus {
+    /// Created but never started
+    Pending,
+    Running,
+    Paused,
+    /// The app closed while the job was running; resume to continue
+    Interrupted,
+    Cancelled,
+    Completed,
+    CompletedWithErrors,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchItemStatus {
+    Pending,
+    Running,
+    Done,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchJobOptions {
+    #[serde(default)]
+    pub translate: TranslateOptions,
+    /// Translate requests in flight at once (1-4)
+    #[serde(default = "default_concurrency")]
+    pub concurrency: usize,
+}
+
+fn default_concurrency() -> usize {
+    2
+}
+
+impl Default for BatchJobOptions {
+    fn default() -> Self {
+        Self {
+            translate: TranslateOptions::default(),
+            concurrency: default_concurrency(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchItem {
+    pub reference: String,
+    pub status: BatchItemStatus,
+    /// Cached passage id once translated
+    #[serde(default)]
+    pub passage_id: Option<String>,
+    /// Error of the last attempt
+    #[serde(default)]
+    pub error: Option<String>,
+    #[serde(default)]
+    pub attempts: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchJob {
+    pub id: String,
+    pub status: BatchJobStatus,
+    pub options: BatchJobOptions,
+    pub items: Vec<BatchItem>,
+    pub created_at_ms: u64,
+    pub updated_at_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchJobProgress {
+    pub job_id: String,
+    pub status: BatchJobStatus,
+    pub total: usize,
+    pub done: usize,
+    pub failed: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchItemProgress {
+    pub job_id: String,
+    pub index: usize,
+    pub reference: String,
+    pub status: BatchItemStatus,
+    pub passage_id: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchJobSummary {
+    pub id: String,
+    pub status: BatchJobStatus,
+    pub total: usize,
+    pub done: usize,
+    pub failed: usize,
+    pub created_at_ms: u64,
+    pub updated_at_ms: u64,
+}
+
+/// Run signal of each job that has workers, keyed by job id.
+#[derive(Default)]
+pub struct BatchJobState(Mutex<HashMap<String, Arc<AtomicU8>>>);
+
+impl BatchJobState {
+    fn is_active(&self, id: &str) -> bool {
+        self.0.lock().unwrap().contains_key(id)
+    }
+
+    fn signal(&self, id: &str, signal: u8) -> bool {
+        match self.0.lock().unwrap().get(id) {
+            Some(control) => {
+                control.store(signal, Ordering::SeqCst);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Ask every running job to pause; returns how many were running.
+    pub fn pause_all(&self) -> usize {
+        let jobs = self.0.lock().unwrap();
+        for control in jobs.values() {
+            control.store(PAUSE, Ordering::SeqCst);
+        }
+        jobs.len()
+    }
+
+    /// Jobs whose workers haven't stopped yet.
+    pub fn active_count(&self) -> usize {
+        self.0.lock().unwrap().len()
+    }
+}
+
+impl BatchJob {
+    fn count(&self, status: BatchItemStatus) -> usize {
+        self.items.iter().filter(|i| i.status == status).count()
+    }
+
+    fn progress(&self) -> BatchJobProgress {
+        BatchJobProgress {
+            job_id: self.id.clone(),
+            status: self.status,
+            total: self.items.len(),
+            done: self.count(BatchItemStatus::Done),
+            failed: self.count(BatchItemStatus::Failed),
+        }
+    }
+
+    fn summary(&self) -> BatchJobSummary {
+        BatchJobSummary {
+            id: self.id.clone(),
+            status: self.status,
+            total: self.items.len(),
+            done: self.count(BatchItemStatus::Done),
+            failed: self.count(BatchItemStatus::Failed),
+            created_at_ms: self.created_at_ms,
+            updated_at_ms: self.updated_at_ms,
+        }
+    }
+
+    /// Claim the next pending item for a worker.
+    fn next_item(&mut self) -> Option<(usize, String)> {
+        let (index, item) = self
+            .items
+            .iter_mut()
+            .enumerate()
+            .find(|(_, i)| i.status == BatchItemStatus::Pending)?;
+        item.status = BatchItemStatus::Running;
+        item.attempts += 1;
+        Some((index, item.reference.clone()))
+    }
+
+    /// Items left running by a crash have to be done again.
+    fn requeue_running(&mut self) {
+        for item in &mut self.items {
+            if item.status == BatchItemStatus::Running {
+                item.status = BatchItemStatus::Pending;
+            }
+        }
+    }
+
+    /// Set up a saved job to run again: a stale `running` status counts as
+    /// interrupted, `prepare` checks and adjusts the job, and items left
+    /// running are queued again.
+    fn restart(
+        &mut self,
+        prepare: impl FnOnce(&mut BatchJob) -> Result<(), String>,
+    ) -> Result<(), String> {
+        if self.status == BatchJobStatus::Running {
+            self.status = BatchJobStatus::Interrupted;
+        }
+        prepare(self)?;
+        self.requeue_running();
+        self.status = BatchJobStatus::Running;
+        self.updated_at_ms = storage::now_ms();
+        Ok(())
+    }
+
+    /// Record how the item at `index` went.
+    fn record_result(
+        &mut self,
+        index: usize,
+        result: Result<TranslateOutcome, EngineError>,
+    ) -> BatchItemProgress {
+        let item = &mut self.items[index];
+        match result {
+            Ok(TranslateOutcome::Translation(passage)) => {
+                item.status = BatchItemStatus::Done;
+                item.passage_id = Some(passage.id);
+                item.error = None;
+            }
+            Ok(TranslateOutcome::Gate(_)) => {
+                item.status = BatchItemStatus::Failed;
+                item.error =
+                    Some("The engine needs an acknowledgement before translating".to_string());
+            }
+            Err(e) => {
+                item.status = BatchItemStatus::Failed;
+                item.error = Some(e.to_string());
+            }
+        }
+        let progress = BatchItemProgress {
+            job_id: self.id.clone(),
+            index,
+            reference: item.reference.clone(),
+            status: item.status,
+            passage_id: item.passage_id.clone(),
+            error: item.error.clone(),
+        };
+        self.updated_at_ms = storage::now_ms();
+        progress
+    }
+
+    /// Status once every worker has stopped.
+    fn finished_status(&self, signal: u8) -> BatchJobStatus {
+        match signal {
+            CANCEL => BatchJobStatus::Cancelled,
+            PAUSE => BatchJobStatus::Paused,
+            _ if self.count(BatchItemStatus::Failed) > 0 => BatchJobStatus::CompletedWithErrors,
+            _ => BatchJobStatus::Completed,
+        }
+    }
+}
+
+/// Job file path; ids are UUIDs so a crafted id can't leave the directory.
+fn job_path(app: &AppHandle, id: &str) -> Result<PathBuf, String> {
+    uuid::Uuid::parse_str(id).map_err(|_| format!("Invalid batch job id: {}", id))?;
+    Ok(storage::app_data_subdir(app, JOBS_DIR)?.join(format!("{}.json", id)))
+}
+
+fn save_job(app: &AppHandle, job: &BatchJob) -> Result<(), String> {
+    storage::write_json(&job_path(app, &job.id)?, job)
+}
+
+/// Read a job file, with its status as last saved.
+fn read_job(app: &AppHandle, id: &str) -> Result<BatchJob, AppError> {
+    let path = job_path(app, id)?;
+    if !path.exists() {
+        return Err(AppError::NotFound(format!("No batch job {}", id)));
+    }
+    Ok(storage::read_json(&path)?)
+}
+
+/// Load a job, reporting a stale `running` status as interrupted.
+fn load_job(app: &AppHandle, state: &BatchJobState, id: &str) -> Result<BatchJob, AppError> {
+    let mut job = read_job(app, id)?;
+    if job.status == BatchJobStatus::Running && !state.is_active(id) {
+        job.status = BatchJobStatus::Interrupted;
+    }
+    Ok(job)
+}
+
+fn load_all_jobs(app: &AppHandle, state: &BatchJobState) -> Result<Vec<BatchJob>, String> {
+    let dir = storage::app_data_subdir(app, JOBS_DIR)?;
+    let entries = fs::read_dir(&dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    let mut jobs: Vec<BatchJob> = entries
+        .filter_map(Result::ok)
+        .filter_map(|e| {
+            let name = e.file_name().into_string().ok()?;
+            load_job(app, state, name.strip_suffix(".json")?).ok()
+        })
+        .collect();
+    jobs.sort_by_key(|job| std::cmp::Reverse(job.created_at_ms));
+    Ok(jobs)
+}
+
+fn set_taskbar_progress(app: &AppHandle, progress: Option<&BatchJobProgress>) {
+    let Some(window) = app.get_webview_window(MAIN_LABEL) else {
+        return;
+    };
+    let state = match progress {
+        Some(p) => ProgressBarState {
+            status: Some(ProgressBarStatus::Normal),
+            progress: Some(((p.done + p.failed) * 100 / p.total.max(1)) as u64),
+        },
+        None => ProgressBarState {
+            status: Some(ProgressBarStatus::None),
+            progress: None,
+        },
+    };
+    let _ = window.set_progress_bar(state);
+}
+
+/// Badge count: failed items across all jobs, until retried.
+fn refresh_failed_badge(app: &AppHandle) {
+    let state = app.state::<BatchJobState>();
+    let failed: usize = load_all_jobs(app, &state)
+        .unwrap_or_default()
+        .iter()
+        .map(|job| job.count(BatchItemStatus::Failed))
+        .sum();
+    set_badge_source(app, BadgeSource::FailedJobs, failed as u32);
+}
+
+/// Translate pending items until none are left or the job is stopped,
+/// checkpointing the job after each one. `finished` gets each item's
+/// result and the job's totals once the checkpoint is written.
+async fn run_items<F>(
+    job: &Mutex<BatchJob>,
+    control: &AtomicU8,
+    translate: impl Fn(String) -> F,
+    checkpoint: impl Fn(&BatchJob) -> Result<(), String>,
+    mut finished: impl FnMut(BatchItemProgress, BatchJobProgress),
+) where
+    F: Future<Output = Result<TranslateOutcome, EngineError>>,
+{
+    while control.load(Ordering::SeqCst) == RUN {
+        let Some((index, reference)) = job.lock().unwrap().next_item() else {
+            break;
+        };
+        let result = translate(reference).await;
+
+        let (item, progress) = {
+            let mut job = job.lock().unwrap();
+            let item = job.record_result(index, result);
+            if let Err(e) = checkpoint(&job) {
+                tracing::warn!("Could not checkpoint batch job {}: {}", job.id, e);
+            }
+            (item, job.progress())
+        };
+        finished(item, progress);
+    }
+}
+
+/// Run a job's items through the app's translate pipeline and job file.
+async fn worker(app: AppHandle, port: u16, job: Arc<Mutex<BatchJob>>, control: Arc<AtomicU8>) {
+    let options = job.lock().unwrap().options.translate.clone();
+    run_items(
+        &job,
+        &control,
+        |reference| {
+            let (app, options) = (app.clone(), options.clone());
+            async move { translate_and_cache(&app, port, &reference, options).await }
+        },
+        |job| save_job(&app, job),
+        |item, progress| {
+            let _ = app.emit(ITEM_EVENT, item);
+            set_taskbar_progress(&app, Some(&progress));
+            let _ = app.emit(PROGRESS_EVENT, progress);
+        },
+    )
+    .await;
+}
+
+async fn run_job(app: AppHandle, port: u16, job: Arc<Mutex<BatchJob>>, control: Arc<AtomicU8>) {
+    let concurrency = job.lock().unwrap().options.concurrency;
+    let inhibitor = SleepInhibitor::acquire("Translating a batch job");
+    let workers: Vec<_> = (0..concurrency.clamp(1, MAX_CONCURRENCY))
+        .map(|_| {
+            tauri::async_runtime::spawn(worker(app.clone(), port, job.clone(), control.clone()))
+        })
+        .collect();
+    for handle in workers {
+        let _ = handle.await;
+    }
+    drop(inhibitor);
+
+    let progress = {
+        let mut job = job.lock().unwrap();
+        job.status = job.finished_status(control.load(Ordering::SeqCst));
+        job.updated_at_ms = storage::now_ms();
+        if let Err(e) = save_job(&app, &job) {
+            tracing::warn!("Could not save batch job {}: {}", job.id, e);
+        }
+        app.state::<BatchJobState>()
+            .0
+            .lock()
+            .unwrap()
+            .remove(&job.id);
+        job.progress()
+    };
+    set_taskbar_progress(&app, None);
+    refresh_failed_badge(&app);
+    let _ = app.emit(PROGRESS_EVENT, progress);
+}
+
+/// Load a job, let `prepare` check and adjust it, and start its workers.
+fn launch(
+    app: &AppHandle,
+    state: &BatchJobState,
+    port: u16,
+    id: &str,
+    prepare: impl FnOnce(&mut BatchJob) -> Result<(), String>,
+) -> Result<BatchJob, AppError> {
+    let mut active = state.0.lock().unwrap();
+    if active.contains_key(id) {
+        return Err(AppError::InvalidInput(format!(
+            "Batch job {} is already running",
+            id
+        )));
+    }
+    let mut job = read_job(app, id)?;
+    job.restart(prepare).map_err(AppError::InvalidInput)?;
+    save_job(app, &job)?;
+
+    let control = Arc::new(AtomicU8::new(RUN));
+    active.insert(id.to_string(), control.clone());
+    let _ = app.emit(PROGRESS_EVENT, job.progress());
+    tauri::async_runtime::spawn(run_job(
+        app.clone(),
+        port,
+        Arc::new(Mutex::new(job.clone())),
+        control,
+    ));
+    Ok(job)
+}
+
+/// Save a new job for `passages` (references) without starting it.
+///
+/// Fails on the first reference that doesn't parse.
+#[tauri::command]
+pub fn create_batch_job(
+    app: AppHandle,
+    passages: Vec<String>,
+    options: Option<BatchJobOptions>,
+) -> Result<BatchJob, AppError> {
+    let limit = max_translate_verses(&app);
+    let mut references = Vec::new();
+    for passage in passages.iter().filter(|p| !p.trim().is_empty()) {
+        let parsed = parse_reference_list(passage)
+            .map_err(|e| AppError::InvalidInput(format!("{}: {}", passage, e)))?;
+        references.extend(chapter_chunks(&parsed, limit));
+    }
+    let mut seen = HashSet::new();
+    let items: Vec<BatchItem> = references
+        .into_iter()
+        .filter(|r| seen.insert(r.clone()))
+        .map(|reference| BatchItem {
+            reference,
+            status: BatchItemStatus::Pending,
+            passage_id: None,
+            error: None,
+            attempts: 0,
+        })
+        .collect();
+    if items.is_empty() {
+        return Err(AppError::InvalidInput(
+            "A batch job needs at least one passage".to_string(),
+        ));
+    }
+
+    let mut options = options.unwrap_or_default();
+    options.concurrency = options.concurrency.clamp(1, MAX_CONCURRENCY);
+    let now = storage::now_ms();
+    let job = BatchJob {
+        id: uuid::Uuid::new_v4().to_string(),
+        status: BatchJobStatus::Pending,
+        options,
+        items,
+        created_at_ms: now,
+        updated_at_ms: now,
+    };
+    save_job(&app, &job)?;
+    Ok(job)
+}
+
+/// Start a new job. Emits `batch-job-item` and `batch-job-progress`.
+#[tauri::command]
+pub fn start_batch_job(
+    app: AppHandle,
+    state: State<'_, BatchJobState>,
+    port: u16,
+    id: String,
+) -> Result<BatchJob, AppError> {
+    launch(&app, &state, port, &id, |job| match job.status {
+        BatchJobStatus::Pending => Ok(()),
+        status => Err(format!("Batch job {} is {:?}, not pending", job.id, status)),
+    })
+}
+
+/// Continue a paused or interrupted job; finished items are not redone.
+#[tauri::command]
+pub fn resume_batch_job(
+    app: AppHandle,
+    state: State<'_, BatchJobState>,
+    port: u16,
+    id: String,
+) -> Result<BatchJob, AppError> {
+    launch(&app, &state, port, &id, check_resumable)
+}
+
+/// Only paused and interrupted jobs are resumed.
+fn check_resumable(job: &mut BatchJob) -> Result<(), String> {
+    match job.status {
+        BatchJobStatus::Paused | BatchJobStatus::Interrupted => Ok(()),
+        status => Err(format!("Batch job {} is {:?}, not paused", job.id, status)),
+    }
+}
+
+/// Queue failed items again and run the job.
+#[tauri::command]
+pub fn retry_failed_items(
+    app: AppHandle,
+    state: State<'_, BatchJobState>,
+    port: u16,
+    id: String,
+) -> Result<BatchJob, AppError> {
+    launch(&app, &state, port, &id, |job| {
+        let mut retried = 0;
+        for item in &mut job.items {
+            if item.status == BatchItemStatus::Failed {
+                item.status = BatchItemStatus::Pending;
+                retried += 1;
+            }
+        }
+        if retried == 0 {
+            return Err(format!("Batch job {} has no failed items", job.id));
+        }
+        Ok(())
+    })
+}
+
+/// Stop taking new items; items in flight still finish and are saved.
+#[tauri::command]
+pub fn pause_batch_job(state: State<'_, BatchJobState>, id: String) -> Result<(), AppError> {
+    if state.signal(&id, PAUSE) {
+        Ok(())
+    } else {
+        Err(AppError::InvalidInput(format!(
+            "Batch job {} is not running",
+            id
+        )))
+    }
+}
+
+/// Stop a job for good. Finished items stay in the passage cache.
+#[tauri::command]
+pub fn cancel_batch_job(
+    app: AppHandle,
+    state: State<'_, BatchJobState>,
+    id: String,
+) -> Result<(), AppError> {
+    if state.signal(&id, CANCEL) {
+        return Ok(());
+    }
+    let mut job = load_job(&app, &state, &id)?;
+    if matches!(
+        job.status,
+        BatchJobStatus::Completed | BatchJobStatus::CompletedWithErrors
+    ) {
+        return Err(AppError::InvalidInput(format!(
+            "Batch job {} has already finished",
+            id
+        )));
+    }
+    job.status = BatchJobStatus::Cancelled;
+    job.requeue_running();
+    job.updated_at_ms = storage::now_ms();
+    save_job(&app, &job)?;
+    let _ = app.emit(PROGRESS_EVENT, job.progress());
+    Ok(())
+}
+
+/// A job with the status of every item.
+#[tauri::command]
+pub fn get_batch_job(
+    app: AppHandle,
+    state: State<'_, BatchJobState>,
+    id: String,
+) -> Result<BatchJob, AppError> {
+    load_job(&app, &state, &id)
+}
+
+/// All saved jobs, newest first.
+#[tauri::command]
+pub fn list_batch_jobs(
+    app: AppHandle,
+    state: State<'_, BatchJobState>,
+) -> Result<Vec<BatchJobSummary>, AppError> {
+    Ok(load_all_jobs(&app, &state)?
+        .iter()
+        .map(BatchJob::summary)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::auth::set_auth_token;
+    use crate::commands::http;
+    use crate::testsupport::mock_engine::MockEngine;
+    use crate::testsupport::passage::PassageBuilder;
+    use crate::testsupport::TestEnv;
+    use reqwest::Method;
+    use serde_json::{json, Value};
+    use std::pin::pin;
+    use std::sync::atomic::AtomicBool;
+    use std::task::Poll;
+
+    fn job(statuses: &[BatchItemStatus]) -> BatchJob {
+        BatchJob {
+            id: "00000000-0000-4000-8000-000000000000".to_string(),
+            status: BatchJobStatus::Running,
+            options: BatchJobOptions::default(),
+            items: statuses
+                .iter()
+                .enumerate()
+                .map(|(i, status)| BatchItem {
+                    reference: format!("Mark {}", i + 1),
+                    status: *status,
+                    passage_id: None,
+                    error: None,
+                    attempts: 0,
+                })
+                .collect(),
+            created_at_ms: 0,
+            updated_at_ms: 0,
+        }
+    }
+
+    #[test]
+    fn test_next_item_skips_finished() {
+        use BatchItemStatus::*;
+        let mut job = job(&[Done, Failed, Pending, Pending]);
+        assert_eq!(job.next_item(), Some((2, "Mark 3".to_string())));
+        assert_eq!(job.next_item(), Some((3, "Mark 4".to_string())));
+        assert_eq!(job.next_item(), None);
+        assert_eq!(job.items[2].attempts, 1);
+
+        // A restart puts in-flight items back in the queue
+        job.requeue_running();
+        assert_eq!(job.count(Pending), 2);
+        assert_eq!(job.count(Done), 1);
+    }
+
+    #[test]
+    fn test_finished_status() {
+        use BatchItemStatus::*;
+        let clean = job(&[Done, Done]);
+        assert_eq!(clean.finished_status(RUN), BatchJobStatus::Completed);
+        assert_eq!(clean.finished_status(PAUSE), BatchJobStatus::Paused);
+        let failed = job(&[Done, Failed]);
+        assert_eq!(
+            failed.finished_status(RUN),
+            BatchJobStatus::CompletedWithErrors
+        );
+        assert_eq!(failed.finished_status(CANCEL), BatchJobStatus::Cancelled);
+
+        let progress = failed.progress();
+        assert_eq!((progress.total, progress.done, progress.failed), (2, 1, 1));
+    }
+
+    #[test]
+    fn test_resume_after_crash() {
+        use BatchItemStatus::*;
+        let env = TestEnv::new();
+        let engine = MockEngine::start();
+        let port = engine.port();
+        set_auth_token("rl_batch_job_test_token_01".to_string()).unwrap();
+
+        let saved = job(&[Pending, Pending, Pending]);
+        let path = env.home().join(JOBS_DIR).join(format!("{}.json", saved.id));
+        storage::write_json(&path, &saved).unwrap();
+
+        let translate = |reference: String| async move {
+            let request = http::request(Method::POST, port, "/translate")?
+                .json(&json!({ "reference": reference }));
+            http::send_json::<Value>(request).await?;
+            let passage = PassageBuilder::new().id(&reference).reference(&reference);
+            Ok::<_, EngineError>(TranslateOutcome::Translation(Box::new(passage.build())))
+        };
+        let checkpoint = |job: &BatchJob| storage::write_json(&path, job);
+        let run = |job: BatchJob| {
+            let job = Mutex::new(job);
+            let control = AtomicU8::new(RUN);
+            tauri::async_runtime::block_on(run_items(
+                &job,
+                &control,
+                translate,
+                checkpoint,
+                |_, _| {},
+            ));
+            job.into_inner().unwrap()
+        };
+
+        // The app dies while "Mark 2" is being translated
+        let crashed = AtomicBool::new(false);
+        {
+            let job = Mutex::new(saved);
+            let control = AtomicU8::new(RUN);
+            let mut items = pin!(run_items(
+                &job,
+                &control,
+                |reference| {
+                    let crashed = &crashed;
+                    async move {
+                        if reference == "Mark 2" {
+                            crashed.store(true, Ordering::SeqCst);
+                            std::future::pending::<()>().await;
+                        }
+                        translate(reference).await
+                    }
+                },
+                checkpoint,
+                |_, _| {},
+            ));
+            tauri::async_runtime::block_on(std::future::poll_fn(|cx| {
+                match items.as_mut().poll(cx) {
+                    Poll::Pending if !crashed.load(Ordering::SeqCst) => Poll::Pending,
+                    _ => Poll::Ready(()),
+                }
+            }));
+        }
+        let on_disk: BatchJob = storage::read_json(&path).unwrap();
+        assert_eq!(on_disk.status, BatchJobStatus::Running);
+        assert_eq!(on_disk.count(Done), 1);
+        assert_eq!(on_disk.count(Pending), 2);
+
+        // Resuming runs only the unfinished items
+        let mut resumed = on_disk;
+        resumed.restart(check_resumable).unwrap();
+        let finished = run(resumed);
+        assert_eq!(finished.finished_status(RUN), BatchJobStatus::Completed);
+
+        let translated: Vec<Value> = engine
+            .requests()
+            .into_iter()
+            .map(|r| r.body["reference"].clone())
+            .collect();
+        assert_eq!(
+            translated,
+            [json!("Mark 1"), json!("Mark 2"), json!("Mark 3")]
+        );
+        let on_disk: BatchJob = storage::read_json(&path).unwrap();
+        assert_eq!(on_disk.count(Done), 3);
+        let passages: Vec<_> = on_disk.items.iter().map(|i| i.passage_id.clone()).collect();
+        assert_eq!(
+            passages,
+            [
+                Some("Mark 1".into()),
+                Some("Mark 2".into()),
+                Some("Mark 3".into())
+            ]
+        );
+
+        // A completed job isn't resumed again
+        let mut done = on_disk;
+        done.status = BatchJobStatus::Completed;
+        assert!(done.restart(check_resumable).is_err());
+    }
+}
@@ -0,0 +1,153 @@
+//! Books, chapters and verse counts for the navigation sidebar.
+//!
+//! The tree comes from the engine's `/v1/text/books` and never changes,
+//! so it is kept for the rest of the session. When the engine can't be
+//! reached, or predates the endpoint, a tree built from the books
+//! `reference` knows (the New Testament) is used instead; it is not
+//! cached, so the engine's tree replaces it once the engine answers.
+
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::State;
+
+use super::engine::EngineError;
+use super::http::{self, HttpError};
+use super::reference::{book_abbreviation, book_display_name, book_verse_counts};
+use super::strongs::Testament;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChapterNode {
+    pub number: u32,
+    pub verse_count: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BookNode {
+    /// Canonical id, e.g. "1Corinthians"
+    pub id: String,
+    pub name: String,
+    /// Abbreviation, e.g. "1 Cor"
+    #[serde(default)]
+    pub short_name: String,
+    /// "ot" or "nt"
+    pub testament: String,
+    #[serde(default)]
+    pub chapter_count: u32,
+    pub chapters: Vec<ChapterNode>,
+}
+
+/// Book trees by engine port.
+#[derive(Default)]
+pub struct BookTreeCache(Mutex<HashMap<u16, Vec<BookNode>>>);
+
+/// Tree for engines without `/v1/text/books`, or none running
+fn bundled_tree() -> Vec<BookNode> {
+    book_verse_counts()
+        .map(|(id, verses)| BookNode {
+            id: id.to_string(),
+            name: book_display_name(id).unwrap_or(id).to_string(),
+            short_name: book_abbreviation(id).unwrap_or_else(|| id.to_string()),
+            testament: Testament::Nt.as_str().to_string(),
+            chapter_count: verses.len() as u32,
+            chapters: verses
+                .iter()
+                .zip(1..)
+                .map(|(&verse_count, number)| ChapterNode {
+                    number,
+                    verse_count,
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// Fill in what older engines leave out.
+fn complete(mut books: Vec<BookNode>) -> Vec<BookNode> {
+    for book in &mut books {
+        if book.chapter_count == 0 {
+            book.chapter_count = book.chapters.len() as u32;
+        }
+        if book.short_name.is_empty() {
+            book.short_name = book.name.clone();
+        }
+    }
+    books
+}
+
+fn filter_testament(books: Vec<BookNode>, testament: Option<Testament>) -> Vec<BookNode> {
+    match testament {
+        Some(testament) => books
+            .into_iter()
+            .filter(|b| b.testament.eq_ignore_ascii_case(testament.as_str()))
+            .collect(),
+        None => books,
+    }
+}
+
+/// Every book with its chapters and their verse counts, in canonical
+/// order; optionally only one testament's.
+#[tauri::command]
+pub async fn get_bible_book_tree(
+    cache: State<'_, BookTreeCache>,
+    port: u16,
+    testament_filter: Option<Testament>,
+) -> Result<Vec<BookNode>, EngineError> {
+    let cached = cache.0.lock().unwrap().get(&port).cloned();
+    if let Some(books) = cached {
+        return Ok(filter_testament(books, testament_filter));
+    }
+
+    let request = http::request(Method::GET, port, "/v1/text/books")?;
+    let books = match http::send_json(request).await {
+        Ok(books) => complete(books),
+        Err(HttpError::Status { status: 404, .. }) | Err(HttpError::Transport(_)) => {
+            return Ok(filter_testament(bundled_tree(), testament_filter));
+        }
+        Err(e) => return Err(e.into()),
+    };
+    cache.0.lock().unwrap().insert(port, books.clone());
+    Ok(filter_testament(books, testament_filter))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_tree() {
+        let books = bundled_tree();
+        assert_eq!(books.len(), 27);
+        assert_eq!(books[0].id, "Matthew");
+        assert_eq!(books[6].name, "1 Corinthians");
+        assert_eq!(books[6].short_name, "1 Cor");
+        assert_eq!(books[26].short_name, "Rev");
+        assert!(books.iter().all(|b| b.testament == "nt"));
+        let john = books.iter().find(|b| b.id == "John").unwrap();
+        assert_eq!(john.chapter_count, 21);
+        assert_eq!(john.chapters[2].verse_count, 36);
+        assert!(books
+            .iter()
+            .all(|b| b.chapter_count as usize == b.chapters.len()));
+    }
+
+    #[test]
+    fn test_filter_and_complete() {
+        let books: Vec<BookNode> = serde_json::from_str(
+            r#"[{"id": "Genesis", "name": "Genesis", "testament": "ot",
+                 "chapters": [{"number": 1, "verse_count": 31}]},
+                {"id": "Jude", "name": "Jude", "short_name": "Jude", "testament": "NT",
+                 "chapter_count": 1, "chapters": [{"number": 1, "verse_count": 25}]}]"#,
+        )
+        .unwrap();
+        let books = complete(books);
+        assert_eq!(books[0].chapter_count, 1);
+        assert_eq!(books[0].short_name, "Genesis");
+
+        let nt = filter_testament(books.clone(), Some(Testament::Nt));
+        assert_eq!(nt.len(), 1);
+        assert_eq!(nt[0].id, "Jude");
+        assert_eq!(filter_testament(books, None).len(), 2);
+    }
+}
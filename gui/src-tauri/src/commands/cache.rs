@@ -0,0 +1,113 @@
+//! In-memory TTL cache for engine responses held in Tauri state.
+//!
+//! Entries expire `ttl` after insertion. An optional capacity evicts the
+//! oldest entry when full.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+#[derive(Debug)]
+pub struct TtlCache<K, V> {
+    ttl: Duration,
+    capacity: Option<usize>,
+    entries: HashMap<K, (V, Instant)>,
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> TtlCache<K, V> {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            capacity: None,
+            entries: HashMap::new(),
+        }
+    }
+
+    pub fn with_capacity(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            capacity: Some(capacity),
+            ..Self::new(ttl)
+        }
+    }
+
+    /// Get a live entry, dropping it if it has expired.
+    pub fn get(&mut self, key: &K) -> Option<V> {
+        match self.entries.get(key) {
+            Some((value, inserted)) if inserted.elapsed() < self.ttl => Some(value.clone()),
+            Some(_) => {
+                self.entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        if let Some(capacity) = self.capacity {
+            if !self.entries.contains_key(&key) && self.entries.len() >= capacity {
+                self.evict_oldest();
+            }
+        }
+        self.entries.insert(key, (value, Instant::now()));
+    }
+
+    pub fn remove(&mut self, key: &K) {
+        self.entries.remove(key);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn evict_oldest(&mut self) {
+        let oldest = self
+            .entries
+            .iter()
+            .min_by_key(|(_, (_, inserted))| *inserted)
+            .map(|(key, _)| key.clone());
+        if let Some(key) = oldest {
+            self.entries.remove(&key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expired_entries_are_dropped() {
+        let mut cache = TtlCache::new(Duration::ZERO);
+        cache.insert("a", 1);
+        assert_eq!(cache.get(&"a"), None);
+        assert!(cache.is_empty());
+
+        let mut cache = TtlCache::new(Duration::from_secs(60));
+        cache.insert("a", 1);
+        assert_eq!(cache.get(&"a"), Some(1));
+    }
+
+    #[test]
+    fn test_capacity_evicts_oldest() {
+        let mut cache = TtlCache::with_capacity(Duration::from_secs(60), 2);
+        cache.insert("a", 1);
+        std::thread::sleep(Duration::from_millis(2));
+        cache.insert("b", 2);
+        cache.insert("b", 20);
+        assert_eq!(cache.len(), 2);
+        std::thread::sleep(Duration::from_millis(2));
+        cache.insert("c", 3);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&"a"), None);
+        assert_eq!(cache.get(&"b"), Some(20));
+        assert_eq!(cache.get(&"c"), Some(3));
+    }
+}
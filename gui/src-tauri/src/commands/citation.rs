@@ -0,0 +1,327 @@
+//! Citations for quoted and exported passages.
+//!
+//! Styles are data, not code: `assets/citation_styles.json` lists each
+//! style's templates and how it writes references. Templates fill
+//! `{name}` placeholders, and a `[...]` group is left out when a
+//! placeholder inside it is empty, so "({reference}[ {translation}])"
+//! reads "(John 3:16)" when no translation is named.
+
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+use super::errors::AppError;
+use super::openapi::engine_version;
+use super::passages::CachedPassage;
+use super::reference::{format_reference, parse_reference_list, ReferenceFormat};
+
+const BUNDLED_STYLES: &str = include_str!("../../assets/citation_styles.json");
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CitationStyle {
+    pub id: String,
+    pub name: String,
+    /// Citation of quoted text: `{text}`, `{reference}`, `{translation}`
+    pub quote: String,
+    /// Citation without text, e.g. export footers: `{reference}`, `{translation}`
+    pub reference: String,
+    /// Translation identifier: `{version}`, `{translator}`, `{mode}`
+    pub translation: String,
+    #[serde(default)]
+    pub abbreviate_books: bool,
+    #[serde(default = "default_range_dash")]
+    pub range_dash: String,
+    #[serde(default = "default_verse_separator")]
+    pub verse_separator: String,
+    #[serde(default = "default_chapter_separator")]
+    pub chapter_separator: String,
+}
+
+fn default_range_dash() -> String {
+    "-".to_string()
+}
+
+fn default_verse_separator() -> String {
+    ", ".to_string()
+}
+
+fn default_chapter_separator() -> String {
+    "; ".to_string()
+}
+
+/// What produced a translation; unset parts are left out of the identifier.
+#[derive(Debug, Clone, Default)]
+pub struct TranslationInfo {
+    pub version: Option<String>,
+    pub translator: Option<String>,
+    pub mode: Option<String>,
+}
+
+impl TranslationInfo {
+    pub fn from_passage(passage: &CachedPassage) -> Self {
+        Self {
+            version: passage.engine_version.clone(),
+            translator: Some(passage.translator.clone()),
+            mode: Some(passage.mode.clone()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CitationOptions {
+    /// Quoted text; without it only the reference is cited
+    #[serde(default)]
+    pub text: Option<String>,
+    /// Name the translation (engine version, translator and mode)
+    #[serde(default)]
+    pub include_version: bool,
+    #[serde(default)]
+    pub translator: Option<String>,
+    #[serde(default)]
+    pub mode: Option<String>,
+    /// Engine asked for its version when `include_version` is set
+    #[serde(default)]
+    pub port: Option<u16>,
+}
+
+fn styles() -> &'static [CitationStyle] {
+    static STYLES: OnceLock<Vec<CitationStyle>> = OnceLock::new();
+    STYLES.get_or_init(|| {
+        serde_json::from_str(BUNDLED_STYLES).unwrap_or_else(|e| {
+            tracing::warn!("Invalid citation styles: {}", e);
+            Vec::new()
+        })
+    })
+}
+
+/// A citation style by id.
+pub fn citation_style(id: &str) -> Result<&'static CitationStyle, String> {
+    styles()
+        .iter()
+        .find(|s| s.id == id)
+        .ok_or_else(|| format!("Unknown citation style: {}", id))
+}
+
+/// Fill `{name}` placeholders from `value`. A `[...]` group is dropped
+/// when any placeholder in it has no value; groups don't nest.
+fn render_template(template: &str, value: &dyn Fn(&str) -> Option<String>) -> String {
+    let mut out = String::new();
+    // Text of the open group and whether it is missing a value
+    let mut group: Option<(String, bool)> = None;
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '[' if group.is_none() => group = Some((String::new(), false)),
+            ']' if group.is_some() => {
+                if let Some((text, false)) = group.take() {
+                    out.push_str(&text);
+                }
+            }
+            '{' => {
+                let name: String = chars.by_ref().take_while(|c| *c != '}').collect();
+                let filled = value(&name).filter(|v| !v.is_empty());
+                match (&mut group, filled) {
+                    (Some((text, _)), Some(filled)) => text.push_str(&filled),
+                    (Some((_, missing)), None) => *missing = true,
+                    (None, filled) => out.push_str(&filled.unwrap_or_default()),
+                }
+            }
+            c => match &mut group {
+                Some((text, _)) => text.push(c),
+                None => out.push(c),
+            },
+        }
+    }
+    out
+}
+
+/// The style's reference text for `reference`; input the parser rejects
+/// is used as it is.
+fn styled_reference(style: &CitationStyle, reference: &str) -> String {
+    let format = ReferenceFormat {
+        abbreviate_books: style.abbreviate_books,
+        range_dash: &style.range_dash,
+        verse_separator: &style.verse_separator,
+        chapter_separator: &style.chapter_separator,
+    };
+    parse_reference_list(reference).map_or_else(
+        |_| reference.trim().to_string(),
+        |parsed| format_reference(&parsed.ranges, &format),
+    )
+}
+
+/// Citation of `reference` in `style`, quoting `text` when given and
+/// naming the translation when `translation` is given.
+pub fn cite(
+    style: &CitationStyle,
+    reference: &str,
+    text: Option<&str>,
+    translation: Option<&TranslationInfo>,
+) -> String {
+    let translation = translation.map(|info| {
+        render_template(&style.translation, &|name| match name {
+            "version" => info.version.clone(),
+            "translator" => info.translator.clone(),
+            "mode" => info.mode.clone(),
+            _ => None,
+        })
+    });
+    let reference = styled_reference(style, reference);
+    let text = text.map(str::trim).filter(|t| !t.is_empty());
+    let template = if text.is_some() {
+        &style.quote
+    } else {
+        &style.reference
+    };
+    render_template(template, &|name| match name {
+        "text" => text.map(str::to_string),
+        "reference" => Some(reference.clone()),
+        "translation" => translation.clone(),
+        _ => None,
+    })
+}
+
+/// Footer citation for an exported passage in `style`.
+pub fn passage_citation(style: &CitationStyle, passage: &CachedPassage) -> String {
+    cite(
+        style,
+        &passage.reference,
+        None,
+        Some(&TranslationInfo::from_passage(passage)),
+    )
+}
+
+/// Citation styles available to `format_citation` and the exports.
+#[tauri::command]
+pub fn list_citation_styles() -> Vec<CitationStyle> {
+    styles().to_vec()
+}
+
+/// Format a citation for a reference (or list of references).
+///
+/// With `options.text` the quote template is used. `include_version`
+/// names the translation, asking the engine on `options.port` for its
+/// version when given.
+#[tauri::command]
+pub async fn format_citation(
+    passage_ref: String,
+    style: String,
+    options: Option<CitationOptions>,
+) -> Result<String, AppError> {
+    let style = citation_style(&style)?;
+    let options = options.unwrap_or_default();
+    parse_reference_list(&passage_ref).map_err(|e| e.to_string())?;
+
+    let translation = if options.include_version {
+        let version = match options.port {
+            Some(port) => engine_version(port).await,
+            None => None,
+        };
+        Some(TranslationInfo {
+            version,
+            translator: options.translator,
+            mode: options.mode,
+        })
+    } else {
+        None
+    };
+    Ok(cite(
+        style,
+        &passage_ref,
+        options.text.as_deref(),
+        translation.as_ref(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version() -> TranslationInfo {
+        TranslationInfo {
+            version: Some("0.1.0".to_string()),
+            translator: Some("literal".to_string()),
+            mode: Some("readable".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_render_template() {
+        let value = |name: &str| (name == "a").then(|| "A".to_string());
+        assert_eq!(
+            render_template("{a}[ {b}] ({a}[, {a}])", &value),
+            "A (A, A)"
+        );
+        assert_eq!(render_template("[x{b}]y{c}", &value), "y");
+    }
+
+    #[test]
+    fn test_reference_shapes() {
+        let sbl = citation_style("sbl").unwrap();
+        let simple = citation_style("simple").unwrap();
+        let cases = [
+            ("jn 3.16", "John 3:16", "John 3:16"),
+            ("John 3:16-18", "John 3:16–18", "John 3:16-18"),
+            ("1 Cor 13:4-7", "1 Cor 13:4–7", "1 Corinthians 13:4-7"),
+            ("John 3:16-4:2", "John 3:16–4:2", "John 3:16-4:2"),
+            ("Matt 5", "Matt 5", "Matthew 5"),
+            ("Matt 5-7", "Matt 5–7", "Matthew 5-7"),
+            ("Philemon", "Phlm", "Philemon"),
+            ("Jude 3", "Jude 1:3", "Jude 1:3"),
+            (
+                "Jn 3:16, 18-20; 4:1; Rom 5:8",
+                "John 3:16, 18–20; 4:1; Rom 5:8",
+                "John 3:16, 18-20; 4:1; Romans 5:8",
+            ),
+            (
+                "1 Thess 4:13; 2 Pet 3:9",
+                "1 Thess 4:13; 2 Pet 3:9",
+                "1 Thessalonians 4:13; 2 Peter 3:9",
+            ),
+        ];
+        for (input, expected_sbl, expected_simple) in cases {
+            assert_eq!(cite(sbl, input, None, None), expected_sbl, "{}", input);
+            assert_eq!(
+                cite(simple, input, None, None),
+                expected_simple,
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_quotes_and_translation() {
+        let sbl = citation_style("sbl").unwrap();
+        let simple = citation_style("simple").unwrap();
+        let text = Some(" Jesus wept. ");
+        assert_eq!(
+            cite(sbl, "Jn 11:35", text, None),
+            "“Jesus wept.” (John 11:35)"
+        );
+        assert_eq!(
+            cite(sbl, "Jn 11:35", text, Some(&version())),
+            "“Jesus wept.” (John 11:35 Red Letters 0.1.0)"
+        );
+        assert_eq!(
+            cite(simple, "Jn 11:35", text, None),
+            "Jesus wept. (John 11:35)"
+        );
+        assert_eq!(
+            cite(simple, "Jn 11:35", text, Some(&version())),
+            "Jesus wept. (John 11:35, Red Letters 0.1.0, literal translator, readable mode)"
+        );
+        let unversioned = TranslationInfo {
+            version: None,
+            ..version()
+        };
+        assert_eq!(
+            cite(simple, "Jn 11:35", None, Some(&unversioned)),
+            "John 11:35, Red Letters, literal translator, readable mode"
+        );
+        assert_eq!(
+            cite(sbl, "Jn 11:35", None, Some(&TranslationInfo::default())),
+            "John 11:35 Red Letters"
+        );
+        assert!(citation_style("apa").is_err());
+    }
+}
@@ -0,0 +1,295 @@
+//! Opt-in clipboard watcher that offers to translate copied Greek text.
+//!
+//! Off by default. When enabled, the clipboard is polled and newly copied
+//! text that is mostly Greek is cleaned up with `normalize_greek` and
+//! emitted as `greek-text-detected`. Text the app
+//! wrote itself (share/copy features call `note_app_clipboard_write`) is
+//! skipped to avoid feedback loops. Clipboard contents are only compared
+//! by hash in memory; they are never persisted, and logged only as
+//! `Redacted`.
+//!
+//! `start_clipboard_monitor` runs a separate, session-only monitor for a
+//! window that analyzes anything Greek as soon as it is copied: it polls
+//! at the window's chosen interval and reports any text containing Greek
+//! letters as `clipboard-greek-text-detected`, unfiltered.
+
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State, Window};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+use super::errors::AppError;
+use super::greek_input::{normalize_greek, InputChange};
+use super::logging::Redacted;
+use super::settings;
+use super::text_utils::is_greek_letter;
+
+/// Event emitted with newly copied Greek text
+const GREEK_TEXT_EVENT: &str = "greek-text-detected";
+/// Clipboard polling interval
+const POLL_INTERVAL: Duration = Duration::from_millis(1000);
+/// Minimum number of Greek letters before text counts as Greek
+const MIN_GREEK_LETTERS: usize = 12;
+/// Minimum share of letters that must be Greek
+const MIN_GREEK_RATIO: f64 = 0.6;
+/// Maximum characters sent with the event
+const MAX_EVENT_CHARS: usize = 2_000;
+/// Event emitted by `start_clipboard_monitor`
+const MONITOR_EVENT: &str = "clipboard-greek-text-detected";
+/// Shortest polling interval `start_clipboard_monitor` accepts
+const MIN_MONITOR_INTERVAL_MS: u64 = 250;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GreekTextDetected {
+    pub text: String,
+    pub truncated: bool,
+    /// What the cleanup changed in the copied text
+    pub changes: Vec<InputChange>,
+}
+
+#[derive(Default)]
+pub struct ClipboardWatchState {
+    enabled: AtomicBool,
+    running: AtomicBool,
+    /// Hash of the last clipboard text seen
+    last_seen: Mutex<Option<u64>>,
+    /// Hash of the last text the app put on the clipboard
+    app_written: Mutex<Option<u64>>,
+    monitor_active: AtomicBool,
+    /// Bumped on every start and stop, so a monitor thread left over
+    /// from an earlier start exits
+    monitor_generation: AtomicU64,
+    /// Hash of the last clipboard text the monitor saw
+    monitor_last_seen: Mutex<Option<u64>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClipboardGreekText {
+    pub text: String,
+    pub char_count: usize,
+}
+
+fn text_hash(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Whether text is long enough and mostly Greek letters.
+fn looks_greek(text: &str) -> bool {
+    let (mut letters, mut greek) = (0usize, 0usize);
+    for c in text.chars().filter(|c| c.is_alphabetic()) {
+        letters += 1;
+        if is_greek_letter(c) {
+            greek += 1;
+        }
+    }
+    greek >= MIN_GREEK_LETTERS && greek as f64 / letters as f64 >= MIN_GREEK_RATIO
+}
+
+/// Record text the app is about to put on the clipboard so the watcher ignores it.
+pub fn note_app_clipboard_write(app: &AppHandle, text: &str) {
+    let state = app.state::<ClipboardWatchState>();
+    *state.app_written.lock().unwrap() = Some(text_hash(text));
+}
+
+fn poll_once(app: &AppHandle, state: &ClipboardWatchState) {
+    let Ok(text) = app.clipboard().read_text() else {
+        return;
+    };
+    let hash = text_hash(&text);
+    {
+        let mut last_seen = state.last_seen.lock().unwrap();
+        if *last_seen == Some(hash) {
+            return;
+        }
+        *last_seen = Some(hash);
+    }
+    if *state.app_written.lock().unwrap() == Some(hash) || !looks_greek(&text) {
+        return;
+    }
+
+    let normalized = normalize_greek(&text);
+    let truncated = normalized.text.chars().count() > MAX_EVENT_CHARS;
+    let text = if truncated {
+        normalized.text.chars().take(MAX_EVENT_CHARS).collect()
+    } else {
+        normalized.text
+    };
+    tracing::debug!(text = %Redacted(&text), truncated, "Greek text copied");
+    let _ = app.emit(
+        GREEK_TEXT_EVENT,
+        GreekTextDetected {
+            text,
+            truncated,
+            changes: normalized.changes,
+        },
+    );
+}
+
+/// Start the polling thread unless it is already running.
+fn start_watcher(app: &AppHandle) {
+    let state = app.state::<ClipboardWatchState>();
+    if state.running.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    // Only text copied after enabling should trigger a prompt
+    if let Ok(text) = app.clipboard().read_text() {
+        *state.last_seen.lock().unwrap() = Some(text_hash(&text));
+    }
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let state = app.state::<ClipboardWatchState>();
+        while state.enabled.load(Ordering::SeqCst) {
+            std::thread::sleep(POLL_INTERVAL);
+            if state.enabled.load(Ordering::SeqCst) {
+                poll_once(&app, &state);
+            }
+        }
+        state.running.store(false, Ordering::SeqCst);
+    });
+}
+
+fn contains_greek(text: &str) -> bool {
+    text.chars().any(is_greek_letter)
+}
+
+/// Newly copied text containing Greek, if the clipboard changed.
+fn monitor_poll(app: &AppHandle, state: &ClipboardWatchState) -> Option<ClipboardGreekText> {
+    let text = app.clipboard().read_text().ok()?;
+    let hash = text_hash(&text);
+    {
+        let mut last_seen = state.monitor_last_seen.lock().unwrap();
+        if *last_seen == Some(hash) {
+            return None;
+        }
+        *last_seen = Some(hash);
+    }
+    if *state.app_written.lock().unwrap() == Some(hash) || !contains_greek(&text) {
+        return None;
+    }
+    Some(ClipboardGreekText {
+        char_count: text.chars().count(),
+        text,
+    })
+}
+
+/// Resume watching at startup if the user turned it on previously.
+pub fn init_clipboard_watch(app: &AppHandle) {
+    let enabled = settings::load_settings(app)
+        .map(|s| s.clipboard_watch)
+        .unwrap_or(false);
+    if enabled {
+        app.state::<ClipboardWatchState>()
+            .enabled
+            .store(true, Ordering::SeqCst);
+        start_watcher(app);
+    }
+}
+
+/// Turn the clipboard watcher on or off (remembered across restarts).
+#[tauri::command]
+pub fn set_clipboard_watch(
+    app: AppHandle,
+    state: State<'_, ClipboardWatchState>,
+    enabled: bool,
+) -> Result<(), AppError> {
+    settings::update_settings(&app, |s| s.clipboard_watch = enabled)?;
+    state.enabled.store(enabled, Ordering::SeqCst);
+    if enabled {
+        start_watcher(&app);
+    }
+    Ok(())
+}
+
+/// Whether the clipboard watcher is on.
+#[tauri::command]
+pub fn get_clipboard_watch(state: State<'_, ClipboardWatchState>) -> bool {
+    state.enabled.load(Ordering::SeqCst)
+}
+
+/// Watch the clipboard for Greek text copied in other apps and report it
+/// to `window`, checking every `debounce_ms` (at least 250). Only one
+/// monitor runs at a time; it lasts until `stop_clipboard_monitor` or
+/// the app exits.
+#[tauri::command]
+pub fn start_clipboard_monitor(
+    window: Window,
+    state: State<'_, ClipboardWatchState>,
+    debounce_ms: u64,
+) -> Result<(), AppError> {
+    if debounce_ms < MIN_MONITOR_INTERVAL_MS {
+        return Err(AppError::InvalidInput(format!(
+            "The clipboard monitor interval must be at least {} ms",
+            MIN_MONITOR_INTERVAL_MS
+        )));
+    }
+    if state.monitor_active.swap(true, Ordering::SeqCst) {
+        return Err(AppError::InvalidInput(
+            "A clipboard monitor is already running".to_string(),
+        ));
+    }
+    let generation = state.monitor_generation.fetch_add(1, Ordering::SeqCst) + 1;
+    let app = window.app_handle().clone();
+    // Only text copied after starting counts
+    *state.monitor_last_seen.lock().unwrap() =
+        app.clipboard().read_text().ok().map(|t| text_hash(&t));
+
+    std::thread::spawn(move || {
+        let state = app.state::<ClipboardWatchState>();
+        let interval = Duration::from_millis(debounce_ms);
+        loop {
+            std::thread::sleep(interval);
+            if state.monitor_generation.load(Ordering::SeqCst) != generation {
+                break;
+            }
+            if let Some(found) = monitor_poll(&app, &state) {
+                let _ = window.emit_to(window.label(), MONITOR_EVENT, found);
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Stop the clipboard monitor, if one is running.
+#[tauri::command]
+pub fn stop_clipboard_monitor(state: State<'_, ClipboardWatchState>) -> Result<(), AppError> {
+    if state.monitor_active.swap(false, Ordering::SeqCst) {
+        state.monitor_generation.fetch_add(1, Ordering::SeqCst);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_looks_greek() {
+        assert!(looks_greek(
+            "Ἐν ἀρχῇ ἦν ὁ λόγος, καὶ ὁ λόγος ἦν πρὸς τὸν θεόν"
+        ));
+        assert!(looks_greek("John 1:1 Ἐν ἀρχῇ ἦν ὁ λόγος καὶ ὁ λόγος"));
+        // Too short
+        assert!(!looks_greek("λόγος"));
+        // Mostly English with a Greek word
+        assert!(!looks_greek(
+            "The word λόγος is usually translated as word in English Bibles"
+        ));
+        assert!(!looks_greek(""));
+    }
+
+    #[test]
+    fn test_contains_greek() {
+        assert!(contains_greek("see λόγος"));
+        assert!(contains_greek("ἀ"));
+        assert!(!contains_greek("logos; 3:16 — ✓"));
+        // Greek-block symbols that aren't letters don't count
+        assert!(!contains_greek("\u{0375}\u{037E}"));
+    }
+}
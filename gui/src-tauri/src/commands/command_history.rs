@@ -0,0 +1,44 @@
+//! In-memory log of notable commands, newest last.
+//!
+//! Entries hold only a command name, a short detail and a timestamp, never
+//! payloads, so the log is safe to include in diagnostics.
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+use super::storage::now_ms;
+
+/// Oldest entries are dropped beyond this many
+const MAX_ENTRIES: usize = 500;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandHistoryEntry {
+    pub command: String,
+    pub detail: String,
+    pub at_ms: u64,
+}
+
+#[derive(Default)]
+pub struct CommandHistory(Mutex<VecDeque<CommandHistoryEntry>>);
+
+/// Append an entry to the command history.
+pub fn record_command(app: &AppHandle, command: &str, detail: &str) {
+    let history = app.state::<CommandHistory>();
+    let mut entries = history.0.lock().unwrap();
+    if entries.len() == MAX_ENTRIES {
+        entries.pop_front();
+    }
+    entries.push_back(CommandHistoryEntry {
+        command: command.to_string(),
+        detail: detail.to_string(),
+        at_ms: now_ms(),
+    });
+}
+
+/// Recorded commands, oldest first.
+#[tauri::command]
+pub fn get_command_history(history: State<'_, CommandHistory>) -> Vec<CommandHistoryEntry> {
+    history.0.lock().unwrap().iter().cloned().collect()
+}
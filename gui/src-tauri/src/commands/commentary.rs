@@ -0,0 +1,151 @@
+//! Commentaries on a passage from the engine.
+//!
+//! Entries come from `/v1/commentary/{commentary_id}/{reference}` with
+//! their footnotes and cross-references; the installed commentaries are
+//! listed at `/v1/commentary`. References are checked locally first and
+//! entries are cached for half an hour.
+
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::State;
+
+use super::cache::TtlCache;
+use super::engine::EngineError;
+use super::http::{self, HttpError};
+use super::reference::validate_scripture_reference;
+use super::telemetry::{self, Feature};
+
+const COMMENTARY_TTL: Duration = Duration::from_secs(30 * 60);
+const COMMENTARY_CACHE_SIZE: usize = 500;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Footnote {
+    /// As it appears in the text, e.g. "a" or "1"
+    #[serde(default)]
+    pub marker: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommentaryEntry {
+    pub reference: String,
+    pub commentary_id: String,
+    #[serde(default)]
+    pub author: String,
+    pub text: String,
+    #[serde(default)]
+    pub footnotes: Vec<Footnote>,
+    /// References the commentator points to, e.g. "Numbers 21:9"
+    #[serde(default)]
+    pub cross_references: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommentaryInfo {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub author: String,
+    /// Year of publication, when known
+    #[serde(default)]
+    pub year: Option<i32>,
+    #[serde(default)]
+    pub entry_count: u32,
+}
+
+/// Entries by canonical reference and commentary.
+pub struct CommentaryCache(Mutex<TtlCache<(String, String), CommentaryEntry>>);
+
+impl Default for CommentaryCache {
+    fn default() -> Self {
+        Self(Mutex::new(TtlCache::with_capacity(
+            COMMENTARY_TTL,
+            COMMENTARY_CACHE_SIZE,
+        )))
+    }
+}
+
+/// Commentary ids are a single path segment, e.g. "matthew-henry".
+fn is_valid_commentary_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// One commentary's entry on a passage, e.g. "matthew-henry" on "John 3:16".
+#[tauri::command]
+pub async fn get_text_commentary(
+    cache: State<'_, CommentaryCache>,
+    port: u16,
+    reference: String,
+    commentary_id: String,
+) -> Result<CommentaryEntry, EngineError> {
+    telemetry::record_feature(Feature::Commentary);
+    if !is_valid_commentary_id(&commentary_id) {
+        return Err(EngineError::CommentaryNotFound(commentary_id));
+    }
+    let validation = validate_scripture_reference(reference);
+    let Some(normalized) = validation.normalized.filter(|_| validation.valid) else {
+        return Err(EngineError::InvalidReference(
+            validation.error.unwrap_or_default(),
+        ));
+    };
+    let key = (normalized, commentary_id);
+    if let Some(entry) = cache.0.lock().unwrap().get(&key) {
+        return Ok(entry);
+    }
+    let (normalized, commentary_id) = &key;
+    let path = format!("/v1/commentary/{}/{}", commentary_id, normalized);
+    let entry: CommentaryEntry =
+        match http::send_json(http::request(Method::GET, port, &path)?).await {
+            Ok(entry) => entry,
+            Err(HttpError::Status { status: 404, .. }) => {
+                return Err(EngineError::CommentaryNotFound(format!(
+                    "{} on {}",
+                    commentary_id, normalized
+                )))
+            }
+            Err(e) => return Err(e.into()),
+        };
+    cache.0.lock().unwrap().insert(key, entry.clone());
+    Ok(entry)
+}
+
+/// The commentaries the engine has installed.
+#[tauri::command]
+pub async fn list_available_commentaries(port: u16) -> Result<Vec<CommentaryInfo>, EngineError> {
+    Ok(http::send_json(http::request(Method::GET, port, "/v1/commentary")?).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_valid_commentary_id() {
+        assert!(is_valid_commentary_id("matthew-henry"));
+        assert!(is_valid_commentary_id("jfb_1871"));
+        assert!(!is_valid_commentary_id(""));
+        assert!(!is_valid_commentary_id("../admin"));
+        assert!(!is_valid_commentary_id("gill john"));
+    }
+
+    #[test]
+    fn test_entry_defaults() {
+        let entry: CommentaryEntry = serde_json::from_str(
+            r#"{"reference": "John 3:16", "commentary_id": "gill", "text": "For God so loved",
+                "footnotes": [{"text": "Gr. kosmos"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(entry.footnotes[0].marker, "");
+        assert!(entry.cross_references.is_empty());
+
+        let info: CommentaryInfo =
+            serde_json::from_str(r#"{"id": "gill", "name": "Exposition of the Bible"}"#).unwrap();
+        assert_eq!(info.year, None);
+        assert_eq!(info.entry_count, 0);
+    }
+}
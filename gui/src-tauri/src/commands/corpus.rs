@@ -0,0 +1,837 @@
+//! Greek source corpora (SBLGNT, LXX portions, ...) installed for the engine.
+//!
+//! Listing and removal go through the engine's `/v1/corpora` endpoints.
+//! Downloads are fetched by the GUI into the engine's data directory, then
+//! handed to the engine to unpack and register, so no restart is needed.
+//! A partial download is kept next to the archive and resumed with a
+//! `Range` request; it is keyed by checksum so a new corpus version always
+//! starts from scratch.
+//!
+//! `verify_corpus` checks installed files against the engine's per-file
+//! checksums and `repair_corpus` re-downloads only the files that failed.
+//! The last report per corpus is kept for the diagnostics bundle.
+
+use reqwest::header::{CONTENT_LENGTH, RANGE};
+use reqwest::{Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, State};
+
+use super::corpus_import::is_user_corpus;
+use super::engine::EngineError;
+use super::http::{self, HttpError};
+use super::offline::{self, skip_when_offline, NetworkOutcome, QueuedMutation};
+use super::storage::now_ms;
+
+/// Event emitted while a corpus archive downloads
+const DOWNLOAD_PROGRESS_EVENT: &str = "corpus-download-progress";
+/// Minimum bytes between progress events
+const PROGRESS_STEP: u64 = 256 * 1024;
+/// Corpus archives are large; the shared 30s timeout would cut them off
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(2 * 60 * 60);
+/// Manifest of installed sources written by the engine's installer
+pub const INSTALLED_MANIFEST: &str = "installed_sources.json";
+/// Event emitted while corpus files are checked
+const VERIFY_PROGRESS_EVENT: &str = "corpus-verify-progress";
+
+/// A corpus offered by the engine's catalog or a manifest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorpusInfo {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub license: String,
+    #[serde(default)]
+    pub size_bytes: Option<u64>,
+    /// Archive URL; corpora without one can only be installed by the engine
+    #[serde(default)]
+    pub download_url: Option<String>,
+    /// Hex SHA-256 of the archive
+    #[serde(default)]
+    pub sha256: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstalledCorpus {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    /// License identifier recorded at install time, e.g. "CC-BY-SA-3.0"
+    #[serde(default)]
+    pub license: String,
+    pub install_path: String,
+    #[serde(default)]
+    pub installed_at: Option<String>,
+    /// Whether the engine currently reads from this corpus. Always false
+    /// when listed from the data directory without a running engine.
+    #[serde(default)]
+    pub active: bool,
+    /// Imported from a local file by `import_local_corpus`
+    #[serde(default)]
+    pub user_imported: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CorpusDownloadProgress {
+    pub id: String,
+    pub downloaded_bytes: u64,
+    pub total_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CorpusInstallResult {
+    pub id: String,
+    pub archive_path: String,
+    pub bytes: u64,
+    /// True if the download picked up an earlier partial file
+    pub resumed: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CorpusRemoval {
+    pub id: String,
+    pub freed_bytes: u64,
+}
+
+/// One file of an installed corpus, from `/v1/corpora/{id}/files`.
+#[derive(Debug, Clone, Deserialize)]
+struct CorpusFile {
+    /// Path relative to the install directory, `/`-separated
+    path: String,
+    sha256: String,
+    /// Where to fetch this file again; required for repair
+    #[serde(default)]
+    url: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct CorpusFileManifest {
+    files: Vec<CorpusFile>,
+    #[serde(default)]
+    expected_books: Option<u32>,
+    #[serde(default)]
+    expected_chapters: Option<u32>,
+}
+
+/// Book and chapter counts the engine actually loaded.
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct CorpusStats {
+    books: u32,
+    chapters: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CorpusVerifyProgress {
+    pub id: String,
+    pub checked_files: usize,
+    pub total_files: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CorpusVerifyReport {
+    pub id: String,
+    pub checked_files: usize,
+    pub missing: Vec<String>,
+    pub mismatched: Vec<String>,
+    /// Files on disk the manifest doesn't list (reported, never deleted)
+    pub extra: Vec<String>,
+    pub expected_books: Option<u32>,
+    pub actual_books: Option<u32>,
+    pub expected_chapters: Option<u32>,
+    pub actual_chapters: Option<u32>,
+    pub verified_at_ms: u64,
+}
+
+impl CorpusVerifyReport {
+    /// No missing or mismatched files and no book/chapter shortfall.
+    pub fn is_ok(&self) -> bool {
+        let counts_ok = |expected: Option<u32>, actual: Option<u32>| match (expected, actual) {
+            (Some(expected), Some(actual)) => actual >= expected,
+            _ => true,
+        };
+        self.missing.is_empty()
+            && self.mismatched.is_empty()
+            && counts_ok(self.expected_books, self.actual_books)
+            && counts_ok(self.expected_chapters, self.actual_chapters)
+    }
+}
+
+/// One line per corpus for the diagnostics bundle.
+#[derive(Debug, Clone, Serialize)]
+pub struct CorpusIntegritySummary {
+    pub id: String,
+    pub ok: bool,
+    pub missing: usize,
+    pub mismatched: usize,
+    pub extra: usize,
+    pub verified_at_ms: u64,
+}
+
+/// Last verification report per corpus id.
+#[derive(Default)]
+pub struct CorpusIntegrityState(Mutex<BTreeMap<String, CorpusVerifyReport>>);
+
+impl CorpusIntegrityState {
+    fn record(&self, report: &CorpusVerifyReport) {
+        self.0
+            .lock()
+            .unwrap()
+            .insert(report.id.clone(), report.clone());
+    }
+
+    /// Summary of the last verification of each corpus.
+    pub fn summary(&self) -> Vec<CorpusIntegritySummary> {
+        self.0
+            .lock()
+            .unwrap()
+            .values()
+            .map(|r| CorpusIntegritySummary {
+                id: r.id.clone(),
+                ok: r.is_ok(),
+                missing: r.missing.len(),
+                mismatched: r.mismatched.len(),
+                extra: r.extra.len(),
+                verified_at_ms: r.verified_at_ms,
+            })
+            .collect()
+    }
+}
+
+/// Entry in the installer's `installed_sources.json`.
+#[derive(Debug, Deserialize)]
+struct ManifestSource {
+    source_id: String,
+    name: String,
+    install_path: String,
+    #[serde(default)]
+    version: String,
+    #[serde(default)]
+    license: String,
+    #[serde(default)]
+    installed_at: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct InstalledManifest {
+    #[serde(default)]
+    sources: BTreeMap<String, ManifestSource>,
+}
+
+/// Engine data directory, resolved the same way as the engine's installer.
+pub fn data_root() -> Result<PathBuf, EngineError> {
+    if let Some(root) = std::env::var_os("REDLETTERS_DATA_ROOT") {
+        return Ok(PathBuf::from(root));
+    }
+    dirs::home_dir()
+        .map(|home| home.join(".redletters").join("data"))
+        .ok_or_else(|| EngineError::Storage("Could not determine home directory".to_string()))
+}
+
+fn storage_err(path: &Path, e: std::io::Error) -> EngineError {
+    EngineError::Storage(format!("{}: {}", path.display(), e))
+}
+
+/// Installed corpora from the installer manifest, for when the engine is down.
+pub fn installed_from_manifest(root: &Path) -> Result<Vec<InstalledCorpus>, EngineError> {
+    let path = root.join(INSTALLED_MANIFEST);
+    let manifest: InstalledManifest = match fs::read_to_string(&path) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .map_err(|e| EngineError::Storage(format!("{}: {}", path.display(), e)))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => InstalledManifest::default(),
+        Err(e) => return Err(storage_err(&path, e)),
+    };
+    Ok(manifest
+        .sources
+        .into_values()
+        .map(|s| InstalledCorpus {
+            user_imported: is_user_corpus(&s.source_id),
+            id: s.source_id,
+            name: s.name,
+            version: s.version,
+            license: s.license,
+            install_path: s.install_path,
+            installed_at: s.installed_at,
+            active: false,
+        })
+        .collect())
+}
+
+/// Total size of a file or directory tree.
+fn path_size(path: &Path) -> u64 {
+    let Ok(meta) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !meta.is_dir() {
+        return meta.len();
+    }
+    fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| path_size(&entry.path()))
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+/// Hex SHA-256 of a file.
+fn file_sha256(path: &Path) -> Result<String, EngineError> {
+    let mut file = File::open(path).map_err(|e| storage_err(path, e))?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf).map_err(|e| storage_err(path, e))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Resolve a manifest path inside the install directory, rejecting any
+/// path that would escape it.
+fn corpus_file_path(root: &Path, relative: &str) -> Option<PathBuf> {
+    let mut path = root.to_path_buf();
+    for part in relative.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => return None,
+            part if part.contains(['\\', ':']) => return None,
+            part => path.push(part),
+        }
+    }
+    Some(path)
+}
+
+/// Relative (`/`-separated) paths of every file under `root`.
+fn list_files(root: &Path, dir: &Path, out: &mut BTreeSet<String>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            list_files(root, &path, out);
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            let parts: Vec<_> = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().into_owned())
+                .collect();
+            out.insert(parts.join("/"));
+        }
+    }
+}
+
+/// Hash every manifest file under `root`, reporting progress after each file.
+fn check_files(
+    root: &Path,
+    files: &[CorpusFile],
+    mut progress: impl FnMut(usize),
+) -> (Vec<String>, Vec<String>, Vec<String>) {
+    let (mut missing, mut mismatched) = (Vec::new(), Vec::new());
+    for (i, file) in files.iter().enumerate() {
+        match corpus_file_path(root, &file.path) {
+            Some(path) if path.is_file() => match file_sha256(&path) {
+                Ok(hash) if hash.eq_ignore_ascii_case(&file.sha256) => {}
+                _ => mismatched.push(file.path.clone()),
+            },
+            _ => missing.push(file.path.clone()),
+        }
+        progress(i + 1);
+    }
+
+    let mut on_disk = BTreeSet::new();
+    list_files(root, root, &mut on_disk);
+    for file in files {
+        on_disk.remove(file.path.trim_start_matches("./"));
+    }
+    (missing, mismatched, on_disk.into_iter().collect())
+}
+
+/// Catalog corpus ids become file names, e.g. "morphgnt-sblgnt"; a
+/// leading dot or a path separator would escape the downloads directory.
+fn is_valid_corpus_id(id: &str) -> bool {
+    !id.is_empty()
+        && !id.starts_with('.')
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// A catalog checksum, as 64 lowercase hex digits.
+fn catalog_sha256(sha256: &str) -> Option<String> {
+    (sha256.len() == 64 && sha256.bytes().all(|b| b.is_ascii_hexdigit()))
+        .then(|| sha256.to_ascii_lowercase())
+}
+
+/// Archive file name taken from the download URL.
+fn archive_name(id: &str, url: &str) -> String {
+    let last = url
+        .split(['?', '#'])
+        .next()
+        .and_then(|path| path.rsplit('/').next())
+        .unwrap_or_default();
+    if last.is_empty() || last.contains("..") {
+        format!("{}.zip", id)
+    } else {
+        last.to_string()
+    }
+}
+
+/// Corpora available to install, from the engine catalog or `manifest_url`.
+///
+/// A remote `manifest_url` is skipped in offline mode.
+#[tauri::command]
+pub async fn list_available_corpora(
+    port: u16,
+    manifest_url: Option<String>,
+) -> Result<NetworkOutcome<Vec<CorpusInfo>>, EngineError> {
+    skip_when_offline(available_corpora(port, manifest_url).await)
+}
+
+async fn available_corpora(
+    port: u16,
+    manifest_url: Option<String>,
+) -> Result<Vec<CorpusInfo>, EngineError> {
+    match manifest_url {
+        // Third-party manifests must not see the engine token
+        Some(url) => {
+            let request = http::remote(Method::GET, &url)?;
+            Ok(http::send_json(request).await?)
+        }
+        None => {
+            let request = http::request(Method::GET, port, "/v1/corpora/available")?;
+            Ok(http::send_json(request).await?)
+        }
+    }
+}
+
+/// Installed corpora, from the engine or (if it isn't running) the data directory.
+#[tauri::command]
+pub async fn list_installed_corpora(port: u16) -> Result<Vec<InstalledCorpus>, EngineError> {
+    let request = http::request(Method::GET, port, "/v1/corpora")?;
+    match http::send_json::<Vec<InstalledCorpus>>(request).await {
+        Ok(mut corpora) => {
+            for corpus in &mut corpora {
+                corpus.user_imported = is_user_corpus(&corpus.id);
+            }
+            Ok(corpora)
+        }
+        Err(HttpError::Transport(_)) => installed_from_manifest(&data_root()?),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Download a corpus archive, verify its checksum and install it in the engine.
+///
+/// Emits `corpus-download-progress` while downloading. An interrupted
+/// download is resumed on the next call; a checksum mismatch discards the
+/// partial file so the next attempt starts clean. In offline mode the
+/// download is skipped and queued until offline mode is turned off.
+#[tauri::command]
+pub async fn download_corpus(
+    app: AppHandle,
+    port: u16,
+    id: String,
+    manifest_url: Option<String>,
+) -> Result<NetworkOutcome<CorpusInstallResult>, EngineError> {
+    let mutation = QueuedMutation::DownloadCorpus {
+        id: id.clone(),
+        manifest_url: manifest_url.clone(),
+    };
+    let outcome = skip_when_offline(install_corpus(&app, port, id, manifest_url).await)?;
+    offline::queue_if_skipped(&app, &outcome, mutation);
+    Ok(outcome)
+}
+
+pub(super) async fn install_corpus(
+    app: &AppHandle,
+    port: u16,
+    id: String,
+    manifest_url: Option<String>,
+) -> Result<CorpusInstallResult, EngineError> {
+    let corpus = available_corpora(port, manifest_url)
+        .await?
+        .into_iter()
+        .find(|c| c.id == id)
+        .ok_or_else(|| EngineError::CorpusNotFound(id.clone()))?;
+    // The catalog may come from a third-party manifest
+    if !is_valid_corpus_id(&id) {
+        return Err(HttpError::InvalidPathSegment(id).into());
+    }
+    let (Some(url), Some(expected)) = (
+        corpus.download_url,
+        corpus.sha256.as_deref().and_then(catalog_sha256),
+    ) else {
+        return Err(EngineError::CorpusNotDownloadable(id));
+    };
+
+    let dir = data_root()?.join("downloads");
+    fs::create_dir_all(&dir).map_err(|e| storage_err(&dir, e))?;
+    let archive = dir.join(archive_name(&id, &url));
+    let partial = dir.join(format!("{}-{}.part", id, &expected[..12]));
+
+    let offset = fs::metadata(&partial).map(|m| m.len()).unwrap_or(0);
+    let mut request = http::remote(Method::GET, &url)?.timeout(DOWNLOAD_TIMEOUT);
+    if offset > 0 {
+        request = request.header(RANGE, format!("bytes={}-", offset));
+    }
+    let mut response = request
+        .send()
+        .await
+        .map_err(|e| HttpError::Transport(e.to_string()))?;
+
+    let status = response.status();
+    let resumed = status == StatusCode::PARTIAL_CONTENT;
+    // 416: the partial file already holds the whole archive
+    let complete = offset > 0 && status == StatusCode::RANGE_NOT_SATISFIABLE;
+    if !status.is_success() && !complete {
+        return Err(HttpError::Status {
+            status: status.as_u16(),
+            body: response.text().await.unwrap_or_default(),
+        }
+        .into());
+    }
+
+    let mut downloaded = if resumed || complete { offset } else { 0 };
+    if !complete {
+        let total = response
+            .headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok())
+            .map(|len| len + downloaded)
+            .or(corpus.size_bytes);
+        // A server that ignores Range sends the whole file again
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(resumed)
+            .truncate(!resumed)
+            .open(&partial)
+            .map_err(|e| storage_err(&partial, e))?;
+
+        let mut last_emitted = 0u64;
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| HttpError::Transport(e.to_string()))?
+        {
+            file.write_all(&chunk)
+                .map_err(|e| storage_err(&partial, e))?;
+            downloaded += chunk.len() as u64;
+            if downloaded - last_emitted >= PROGRESS_STEP {
+                last_emitted = downloaded;
+                let _ = app.emit(
+                    DOWNLOAD_PROGRESS_EVENT,
+                    CorpusDownloadProgress {
+                        id: id.clone(),
+                        downloaded_bytes: downloaded,
+                        total_bytes: total,
+                    },
+                );
+            }
+        }
+        file.sync_all().map_err(|e| storage_err(&partial, e))?;
+        let _ = app.emit(
+            DOWNLOAD_PROGRESS_EVENT,
+            CorpusDownloadProgress {
+                id: id.clone(),
+                downloaded_bytes: downloaded,
+                total_bytes: total,
+            },
+        );
+    }
+
+    if file_sha256(&partial)? != expected {
+        let _ = fs::remove_file(&partial);
+        return Err(EngineError::CorpusChecksumMismatch(id));
+    }
+    fs::rename(&partial, &archive).map_err(|e| storage_err(&archive, e))?;
+
+    // Let the running engine unpack and register the archive
+    let request = http::request(Method::POST, port, "/v1/corpora/install")?.json(&json!({
+        "source_id": id,
+        "archive_path": archive,
+        "sha256": expected,
+    }));
+    http::send(request).await?;
+
+    Ok(CorpusInstallResult {
+        id,
+        archive_path: archive.to_string_lossy().into_owned(),
+        bytes: downloaded,
+        resumed,
+    })
+}
+
+/// Remove an installed corpus and report how much disk space it used.
+///
+/// Refuses to remove the corpus the engine is currently reading from.
+#[tauri::command]
+pub async fn remove_corpus(port: u16, id: String) -> Result<CorpusRemoval, EngineError> {
+    let request = http::request(Method::GET, port, "/v1/corpora")?;
+    let installed: Vec<InstalledCorpus> = http::send_json(request).await?;
+    let corpus = installed
+        .into_iter()
+        .find(|c| c.id == id)
+        .ok_or_else(|| EngineError::CorpusNotFound(id.clone()))?;
+    if corpus.active {
+        return Err(EngineError::CorpusActive(id));
+    }
+
+    let freed_bytes = path_size(Path::new(&corpus.install_path));
+    let request = http::request(
+        Method::DELETE,
+        port,
+        &format!("/v1/corpora/{}", http::path_segment(&id)?),
+    )?;
+    http::send(request).await?;
+
+    Ok(CorpusRemoval { id, freed_bytes })
+}
+
+/// Install path of an installed corpus.
+async fn install_path(port: u16, id: &str) -> Result<PathBuf, EngineError> {
+    list_installed_corpora(port)
+        .await?
+        .into_iter()
+        .find(|c| c.id == id)
+        .map(|c| PathBuf::from(c.install_path))
+        .ok_or_else(|| EngineError::CorpusNotFound(id.to_string()))
+}
+
+async fn file_manifest(port: u16, id: &str) -> Result<CorpusFileManifest, EngineError> {
+    let path = format!("/v1/corpora/{}/files", http::path_segment(id)?);
+    Ok(http::send_json(http::request(Method::GET, port, &path)?).await?)
+}
+
+/// Hash files on a blocking thread and compare counts with what the engine loaded.
+async fn run_verify(
+    app: &AppHandle,
+    port: u16,
+    id: &str,
+) -> Result<(CorpusVerifyReport, CorpusFileManifest, PathBuf), EngineError> {
+    let root = install_path(port, id).await?;
+    let manifest = file_manifest(port, id).await?;
+
+    let files = manifest.files.clone();
+    let total_files = files.len();
+    let (app_handle, corpus_id, dir) = (app.clone(), id.to_string(), root.clone());
+    let (missing, mismatched, extra) = tauri::async_runtime::spawn_blocking(move || {
+        check_files(&dir, &files, |checked_files| {
+            let _ = app_handle.emit(
+                VERIFY_PROGRESS_EVENT,
+                CorpusVerifyProgress {
+                    id: corpus_id.clone(),
+                    checked_files,
+                    total_files,
+                },
+            );
+        })
+    })
+    .await
+    .map_err(|e| EngineError::Storage(e.to_string()))?;
+
+    // Older engines don't report stats; the file check still stands on its own
+    let path = format!("/v1/corpora/{}/stats", http::path_segment(id)?);
+    let stats: Option<CorpusStats> = http::send_json(http::request(Method::GET, port, &path)?)
+        .await
+        .ok();
+
+    let report = CorpusVerifyReport {
+        id: id.to_string(),
+        checked_files: total_files,
+        missing,
+        mismatched,
+        extra,
+        expected_books: manifest.expected_books,
+        actual_books: stats.map(|s| s.books),
+        expected_chapters: manifest.expected_chapters,
+        actual_chapters: stats.map(|s| s.chapters),
+        verified_at_ms: now_ms(),
+    };
+    Ok((report, manifest, root))
+}
+
+/// Check an installed corpus against the engine's per-file checksums.
+///
+/// Emits `corpus-verify-progress` as files are hashed.
+#[tauri::command]
+pub async fn verify_corpus(
+    app: AppHandle,
+    integrity: State<'_, CorpusIntegrityState>,
+    port: u16,
+    id: String,
+) -> Result<CorpusVerifyReport, EngineError> {
+    let (report, _, _) = run_verify(&app, port, &id).await?;
+    integrity.record(&report);
+    Ok(report)
+}
+
+/// Re-download missing and mismatched files, then verify again.
+///
+/// Each file is checked before it replaces the bad copy, so a failed repair
+/// never makes things worse. The engine reloads the corpus afterwards. In
+/// offline mode the repair is skipped and queued until offline mode is
+/// turned off.
+#[tauri::command]
+pub async fn repair_corpus(
+    app: AppHandle,
+    integrity: State<'_, CorpusIntegrityState>,
+    port: u16,
+    id: String,
+) -> Result<NetworkOutcome<CorpusVerifyReport>, EngineError> {
+    let mutation = QueuedMutation::RepairCorpus { id: id.clone() };
+    let outcome = skip_when_offline(repair(&app, &integrity, port, id).await)?;
+    offline::queue_if_skipped(&app, &outcome, mutation);
+    Ok(outcome)
+}
+
+pub(super) async fn repair(
+    app: &AppHandle,
+    integrity: &CorpusIntegrityState,
+    port: u16,
+    id: String,
+) -> Result<CorpusVerifyReport, EngineError> {
+    let (report, manifest, root) = run_verify(app, port, &id).await?;
+    let bad: BTreeSet<&String> = report.missing.iter().chain(&report.mismatched).collect();
+
+    for file in manifest.files.iter().filter(|f| bad.contains(&f.path)) {
+        let Some(url) = &file.url else {
+            return Err(EngineError::CorpusNotDownloadable(id));
+        };
+        let Some(path) = corpus_file_path(&root, &file.path) else {
+            continue;
+        };
+        let request = http::remote(Method::GET, url)?.timeout(DOWNLOAD_TIMEOUT);
+        let bytes = http::send(request)
+            .await?
+            .bytes()
+            .await
+            .map_err(|e| HttpError::Transport(e.to_string()))?;
+        if !hex::encode(Sha256::digest(&bytes)).eq_ignore_ascii_case(&file.sha256) {
+            return Err(EngineError::CorpusChecksumMismatch(id));
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| storage_err(parent, e))?;
+        }
+        let tmp = path.with_extension("part");
+        fs::write(&tmp, &bytes).map_err(|e| storage_err(&tmp, e))?;
+        fs::rename(&tmp, &path).map_err(|e| storage_err(&path, e))?;
+    }
+
+    if !bad.is_empty() {
+        let path = format!("/v1/corpora/{}/reload", http::path_segment(&id)?);
+        http::send(http::request(Method::POST, port, &path)?).await?;
+    }
+
+    let (report, _, _) = run_verify(app, port, &id).await?;
+    integrity.record(&report);
+    Ok(report)
+}
+
+/// Last verification result per corpus, for the diagnostics bundle.
+#[tauri::command]
+pub fn get_corpus_integrity_summary(
+    integrity: State<'_, CorpusIntegrityState>,
+) -> Vec<CorpusIntegritySummary> {
+    integrity.summary()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archive_name() {
+        assert_eq!(
+            archive_name("sblgnt", "https://example.org/c/sblgnt-2.1.zip?sig=abc"),
+            "sblgnt-2.1.zip"
+        );
+        assert_eq!(
+            archive_name("sblgnt", "https://example.org/c/"),
+            "sblgnt.zip"
+        );
+    }
+
+    #[test]
+    fn test_catalog_entries_are_checked_before_use() {
+        for id in ["sblgnt", "morphgnt-sblgnt", "lxx_rahlfs.2"] {
+            assert!(is_valid_corpus_id(id), "{}", id);
+        }
+        for id in ["", "..", "../../x", ".hidden", "a/b", "a\\b", "sblgnt?"] {
+            assert!(!is_valid_corpus_id(id), "{}", id);
+        }
+
+        let sha256 = hex::encode(Sha256::digest(b"archive"));
+        assert_eq!(catalog_sha256(&sha256.to_uppercase()), Some(sha256.clone()));
+        assert_eq!(catalog_sha256(&sha256[..63]), None);
+        // 64 bytes, but not hex; slicing this at 12 bytes would panic
+        assert_eq!(catalog_sha256(&format!("{}λ", &sha256[..62])), None);
+    }
+
+    #[test]
+    fn test_installed_from_manifest() {
+        let root = std::env::temp_dir().join(format!("rl-corpus-test-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        assert!(installed_from_manifest(&root).unwrap().is_empty());
+
+        fs::write(
+            root.join(INSTALLED_MANIFEST),
+            r#"{"manifest_version": "1.0", "sources": {"morphgnt-sblgnt": {
+                "source_id": "morphgnt-sblgnt", "name": "MorphGNT SBLGNT",
+                "installed_at": "2026-01-01T00:00:00", "install_path": "/data/morphgnt-sblgnt",
+                "version": "6.12", "license": "CC-BY-SA-3.0"}}}"#,
+        )
+        .unwrap();
+        let corpora = installed_from_manifest(&root).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(corpora.len(), 1);
+        assert_eq!(corpora[0].id, "morphgnt-sblgnt");
+        assert_eq!(corpora[0].version, "6.12");
+        assert_eq!(corpora[0].license, "CC-BY-SA-3.0");
+        assert!(!corpora[0].active);
+    }
+
+    #[test]
+    fn test_check_files() {
+        let root = std::env::temp_dir().join(format!("rl-corpus-verify-{}", std::process::id()));
+        fs::create_dir_all(root.join("books")).unwrap();
+        fs::write(root.join("books/john.tsv"), b"good").unwrap();
+        fs::write(root.join("books/mark.tsv"), b"corrupt").unwrap();
+        fs::write(root.join("notes.txt"), b"extra").unwrap();
+
+        let file = |path: &str, contents: &[u8]| CorpusFile {
+            path: path.to_string(),
+            sha256: hex::encode(Sha256::digest(contents)),
+            url: None,
+        };
+        let files = [
+            file("books/john.tsv", b"good"),
+            file("books/mark.tsv", b"good"),
+            file("books/luke.tsv", b"good"),
+            file("../outside.tsv", b"good"),
+        ];
+        let mut checked = 0;
+        let (missing, mismatched, extra) = check_files(&root, &files, |n| checked = n);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(checked, 4);
+        assert_eq!(missing, ["books/luke.tsv", "../outside.tsv"]);
+        assert_eq!(mismatched, ["books/mark.tsv"]);
+        assert_eq!(extra, ["notes.txt"]);
+    }
+}
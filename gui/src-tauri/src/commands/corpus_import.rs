@@ -0,0 +1,820 @@
+//! Custom corpora imported from a local source text.
+//!
+//! Two formats are read:
+//! - plain text, one verse per line: a reference, then the Greek
+//!   ("John 3:16 Οὕτως γὰρ …", "John.3.16<TAB>Οὕτως γὰρ …"); blank lines
+//!   and lines starting with `#` are skipped
+//! - basic USFM: `\id`, `\c` and `\v`, with paragraph and character
+//!   markers dropped and footnotes and cross references left out
+//!
+//! The whole file is parsed before anything is written, and every problem
+//! is reported with its line. The Greek goes through `normalize_greek`,
+//! then each book is written as a MorphGNT-layout file, which is what the
+//! engine loads an installed source from. Lemmas and parsing aren't known,
+//! so those columns hold the word itself and placeholders.
+//!
+//! Files are written to a staging directory that is renamed into place,
+//! and the corpus is only added to `installed_sources.json` after that, so
+//! a failed import leaves nothing the engine would try to load. The engine
+//! is then asked to register the corpus; one without the register endpoint
+//! picks it up from the manifest after a restart. Imported corpora have ids
+//! starting with `user-` and are removed with `remove_corpus` like any other.
+
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use unicode_normalization::char::is_combining_mark;
+
+use super::corpus::{data_root, INSTALLED_MANIFEST};
+use super::engine::EngineError;
+use super::errors::{command_error, CommandError};
+use super::greek_input::normalize_greek;
+use super::http::{self, HttpError};
+use super::i18n::Localize;
+use super::reference::{book_for_usfm_code, book_position, parse_reference_list};
+use super::storage;
+use super::usfm::split_verse_id;
+
+/// Prefix of the ids of imported corpora
+const USER_CORPUS_PREFIX: &str = "user-";
+/// Larger than any edition of the NT as plain text
+const MAX_SOURCE_BYTES: u64 = 64 * 1024 * 1024;
+/// Issues listed in the error; the count covers all of them
+const MAX_REPORTED_ISSUES: usize = 50;
+const REGISTER_PATH: &str = "/v1/corpora/register";
+/// USFM markers whose line is a heading, title or comment, not verse text
+const NON_TEXT_MARKERS: &[&str] = &[
+    "ide", "h", "toc", "toca", "mt", "mte", "imt", "is", "ip", "ipr", "io", "iot", "ie", "s", "sr",
+    "ms", "mr", "r", "d", "sp", "cl", "cd", "rem", "usfm", "sts", "restore",
+];
+/// USFM notes, whose content is left out
+const NOTE_MARKERS: &[&str] = &["f", "fe", "ef", "x", "ex"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LocalCorpusFormat {
+    /// One verse per line, reference first
+    PlainText,
+    Usfm,
+}
+
+/// A problem on one line of the source file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ImportIssue {
+    /// 1-based; `None` for the file as a whole
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl ImportIssue {
+    fn at(line: usize, message: impl Into<String>) -> Self {
+        ImportIssue {
+            line: Some(line),
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum CorpusImportError {
+    #[error("{} problem(s) in the source file", issues.len())]
+    Parse { issues: Vec<ImportIssue> },
+    #[error("A corpus with id {0} is already installed")]
+    Exists(String),
+    #[error("Could not read the source file: {0}")]
+    File(String),
+    #[error(transparent)]
+    Engine(#[from] EngineError),
+}
+
+impl Localize for CorpusImportError {
+    fn code(&self) -> &'static str {
+        match self {
+            CorpusImportError::Parse { .. } => "corpus-import-parse",
+            CorpusImportError::Exists(_) => "corpus-import-exists",
+            CorpusImportError::File(_) => "corpus-import-file",
+            CorpusImportError::Engine(e) => e.code(),
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            CorpusImportError::Parse { issues } => {
+                let mut listed = String::new();
+                for issue in issues.iter().take(MAX_REPORTED_ISSUES) {
+                    match issue.line {
+                        Some(line) => {
+                            let _ = writeln!(listed, "{}: {}", line, issue.message);
+                        }
+                        None => {
+                            let _ = writeln!(listed, "{}", issue.message);
+                        }
+                    }
+                }
+                vec![
+                    ("count", issues.len().to_string()),
+                    ("issues", listed.trim_end().to_string()),
+                ]
+            }
+            CorpusImportError::Exists(id) => vec![("id", id.clone())],
+            CorpusImportError::File(detail) => vec![("detail", detail.clone())],
+            CorpusImportError::Engine(e) => e.params(),
+        }
+    }
+}
+
+impl CommandError for CorpusImportError {
+    fn retryable(&self) -> bool {
+        match self {
+            CorpusImportError::Engine(e) => e.retryable(),
+            _ => false,
+        }
+    }
+}
+
+command_error!(CorpusImportError);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CorpusImportResult {
+    pub id: String,
+    pub name: String,
+    pub install_path: String,
+    pub books: usize,
+    pub verses: usize,
+    /// The running engine registered the corpus
+    pub registered: bool,
+    /// The engine only sees the corpus after a restart
+    pub restart_required: bool,
+}
+
+/// A verse read from the source file.
+#[derive(Debug, Clone, PartialEq)]
+struct ImportedVerse {
+    /// Canonical book id
+    book: String,
+    chapter: u32,
+    verse: u32,
+    text: String,
+    line: usize,
+}
+
+/// Whether a corpus was imported by `import_local_corpus`.
+pub fn is_user_corpus(id: &str) -> bool {
+    id.starts_with(USER_CORPUS_PREFIX)
+}
+
+/// `user-` and the name as lowercase ASCII words joined by `-`; a hash of
+/// the name when it has no ASCII letters or digits (a Greek name).
+fn corpus_id(name: &str) -> String {
+    let mut slug = String::new();
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        let hash = hex::encode(Sha256::digest(name.trim().as_bytes()));
+        format!("{}{}", USER_CORPUS_PREFIX, &hash[..12])
+    } else {
+        format!("{}{}", USER_CORPUS_PREFIX, slug)
+    }
+}
+
+/// Book, chapter and verse of a reference to one verse, checked against
+/// the book's chapter and verse counts.
+fn single_verse(reference: &str) -> Result<(String, u32, u32), String> {
+    let parsed = parse_reference_list(reference).map_err(|e| e.to_string())?;
+    let [verse_id] = parsed.verses.as_slice() else {
+        return Err(format!("\"{}\" is not a single verse", reference.trim()));
+    };
+    let (book, chapter, verse) =
+        split_verse_id(verse_id).ok_or_else(|| format!("Invalid verse id {}", verse_id))?;
+    Ok((book.to_string(), chapter, verse))
+}
+
+/// Whether a token ends in a chapter and verse ("3:16", "John.3.16").
+fn is_location(token: &str) -> bool {
+    let Some(i) = token.rfind([':', '.']) else {
+        return false;
+    };
+    let verse = &token[i + 1..];
+    !verse.is_empty()
+        && verse.bytes().all(|b| b.is_ascii_digit())
+        && token[..i].ends_with(|c: char| c.is_ascii_digit())
+}
+
+/// Split a plain-text line into its reference and text: at the first tab,
+/// else after the first token ending in a chapter and verse.
+fn split_plain_line(line: &str) -> Option<(&str, &str)> {
+    if let Some((reference, text)) = line.split_once('\t') {
+        return Some((reference, text));
+    }
+    let mut end = 0;
+    for token in line.split_inclusive(char::is_whitespace) {
+        end += token.len();
+        if is_location(token.trim_end()) {
+            return Some((&line[..end], &line[end..]));
+        }
+    }
+    None
+}
+
+fn parse_plain_text(source: &str) -> (Vec<ImportedVerse>, Vec<ImportIssue>) {
+    let (mut verses, mut issues) = (Vec::new(), Vec::new());
+    for (i, line) in source.lines().enumerate() {
+        let (line_no, line) = (i + 1, line.trim());
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((reference, text)) = split_plain_line(line) else {
+            issues.push(ImportIssue::at(
+                line_no,
+                "Expected a reference such as \"John 3:16\" before the text",
+            ));
+            continue;
+        };
+        match single_verse(reference) {
+            Ok((book, chapter, verse)) => verses.push(ImportedVerse {
+                book,
+                chapter,
+                verse,
+                text: text.trim().to_string(),
+                line: line_no,
+            }),
+            Err(message) => issues.push(ImportIssue::at(line_no, message)),
+        }
+    }
+    (verses, issues)
+}
+
+/// A USFM marker at the start of `rest` (just after the backslash): its
+/// name without `+` or `*`, whether it closes, and the text after it.
+fn split_marker(rest: &str) -> (&str, bool, &str) {
+    let rest = rest.strip_prefix('+').unwrap_or(rest);
+    let end = rest
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .unwrap_or(rest.len());
+    let (name, after) = rest.split_at(end);
+    match after.strip_prefix('*') {
+        Some(after) => (name, true, after),
+        // A space after an opening marker belongs to the marker
+        None => (
+            name,
+            false,
+            after.strip_prefix(char::is_whitespace).unwrap_or(after),
+        ),
+    }
+}
+
+/// Add text to the verse being read, if any. `\w word|lemma="…"\w*`
+/// keeps the word and drops the attributes.
+fn push_usfm_text(current: &mut Option<ImportedVerse>, text: &str, in_attributes: &mut bool) {
+    let Some(verse) = current.as_mut() else {
+        return;
+    };
+    let text = if *in_attributes {
+        ""
+    } else if let Some((word, _)) = text.split_once('|') {
+        *in_attributes = true;
+        word
+    } else {
+        text
+    };
+    verse.text.push(' ');
+    verse.text.push_str(text);
+}
+
+fn parse_usfm(source: &str) -> (Vec<ImportedVerse>, Vec<ImportIssue>) {
+    let (mut verses, mut issues) = (Vec::new(), Vec::new());
+    let mut book: Option<&'static str> = None;
+    let mut chapter: Option<u32> = None;
+    let mut current: Option<ImportedVerse> = None;
+    let (mut in_note, mut in_attributes) = (false, false);
+
+    for (i, line) in source.lines().enumerate() {
+        let line_no = i + 1;
+        let mut pieces = line.split('\\');
+        let leading = pieces.next().unwrap_or("");
+        if !in_note {
+            push_usfm_text(&mut current, leading, &mut in_attributes);
+        }
+        for piece in pieces {
+            let (name, closing, text) = split_marker(piece);
+            let base = name.trim_end_matches(|c: char| c.is_ascii_digit());
+            if closing {
+                if NOTE_MARKERS.contains(&name) {
+                    in_note = false;
+                }
+                if name == "w" {
+                    in_attributes = false;
+                }
+                if !in_note {
+                    push_usfm_text(&mut current, text, &mut in_attributes);
+                }
+                continue;
+            }
+            if in_note {
+                continue;
+            }
+            let mut words = text.split_whitespace();
+            match name {
+                "id" => {
+                    let code = words.next().unwrap_or("");
+                    book = book_for_usfm_code(code);
+                    chapter = None;
+                    if book.is_none() {
+                        issues.push(ImportIssue::at(
+                            line_no,
+                            format!("Unknown USFM book code \"{}\"", code),
+                        ));
+                    }
+                    break;
+                }
+                "c" => {
+                    verses.extend(current.take());
+                    chapter = words.next().and_then(|n| n.parse().ok());
+                    if book.is_none() {
+                        issues.push(ImportIssue::at(line_no, "\\c before a valid \\id"));
+                    } else if chapter.is_none() {
+                        issues.push(ImportIssue::at(line_no, "\\c without a chapter number"));
+                    }
+                    break;
+                }
+                "v" => {
+                    verses.extend(current.take());
+                    let number = words.next().unwrap_or("");
+                    let (Some(book), Some(chapter)) = (book, chapter) else {
+                        issues.push(ImportIssue::at(line_no, "\\v before \\id and \\c"));
+                        continue;
+                    };
+                    let Ok(verse) = number.parse::<u32>() else {
+                        issues.push(ImportIssue::at(
+                            line_no,
+                            format!("Invalid verse number \"{}\"", number),
+                        ));
+                        continue;
+                    };
+                    match single_verse(&format!("{} {}:{}", book, chapter, verse)) {
+                        Ok((book, chapter, verse)) => {
+                            let text = text.trim_start()[number.len()..].to_string();
+                            current = Some(ImportedVerse {
+                                book,
+                                chapter,
+                                verse,
+                                text: String::new(),
+                                line: line_no,
+                            });
+                            push_usfm_text(&mut current, &text, &mut in_attributes);
+                        }
+                        Err(message) => issues.push(ImportIssue::at(line_no, message)),
+                    }
+                }
+                _ if NOTE_MARKERS.contains(&name) => in_note = true,
+                _ if NON_TEXT_MARKERS.contains(&base) => break,
+                _ => push_usfm_text(&mut current, text, &mut in_attributes),
+            }
+        }
+    }
+    verses.extend(current);
+    (verses, issues)
+}
+
+/// Normalize the verses' Greek and check that the file holds verses, each
+/// once and with text.
+fn check_verses(verses: &mut [ImportedVerse], issues: &mut Vec<ImportIssue>) {
+    if verses.is_empty() && issues.is_empty() {
+        issues.push(ImportIssue {
+            line: None,
+            message: "No verses found".to_string(),
+        });
+    }
+    let mut seen: HashMap<(String, u32, u32), usize> = HashMap::new();
+    for verse in verses.iter_mut() {
+        verse.text = normalize_greek(&verse.text).text;
+        if verse.text.is_empty() {
+            issues.push(ImportIssue::at(verse.line, "Verse has no text"));
+        }
+        let key = (verse.book.clone(), verse.chapter, verse.verse);
+        if let Some(first) = seen.insert(key, verse.line) {
+            issues.push(ImportIssue::at(
+                verse.line,
+                format!(
+                    "{} {}:{} already appears on line {}",
+                    verse.book, verse.chapter, verse.verse, first
+                ),
+            ));
+        }
+    }
+}
+
+/// Parse a source file into normalized verses, or every problem found.
+fn parse_source(
+    source: &str,
+    format: LocalCorpusFormat,
+) -> Result<Vec<ImportedVerse>, CorpusImportError> {
+    let (mut verses, mut issues) = match format {
+        LocalCorpusFormat::PlainText => parse_plain_text(source),
+        LocalCorpusFormat::Usfm => parse_usfm(source),
+    };
+    check_verses(&mut verses, &mut issues);
+    if !issues.is_empty() {
+        issues.sort_by_key(|issue| issue.line);
+        return Err(CorpusImportError::Parse { issues });
+    }
+    Ok(verses)
+}
+
+/// Punctuation-free form of a word, for the MorphGNT word columns.
+fn bare_word(surface: &str) -> String {
+    surface
+        .chars()
+        .filter(|c| c.is_alphanumeric() || is_combining_mark(*c) || matches!(c, '’' | 'ʼ' | '\''))
+        .collect()
+}
+
+/// One MorphGNT-layout file per book, by file name, in canonical order.
+/// Punctuation standing alone stays with the word before it.
+fn morphgnt_files(verses: &[ImportedVerse]) -> BTreeMap<String, String> {
+    let mut by_book: BTreeMap<(usize, &str), Vec<&ImportedVerse>> = BTreeMap::new();
+    for verse in verses {
+        let position = book_position(&verse.book).unwrap_or(usize::MAX);
+        by_book
+            .entry((position, verse.book.as_str()))
+            .or_default()
+            .push(verse);
+    }
+    let mut files = BTreeMap::new();
+    for ((position, book), mut verses) in by_book {
+        verses.sort_by_key(|v| (v.chapter, v.verse));
+        let mut out = String::new();
+        for verse in verses {
+            let mut words: Vec<(String, String)> = Vec::new();
+            for surface in verse.text.split_whitespace() {
+                let word = bare_word(surface);
+                match words.last_mut() {
+                    Some((last, _)) if word.is_empty() => last.push_str(surface),
+                    _ => words.push((surface.to_string(), word)),
+                }
+            }
+            for (surface, word) in words {
+                let word = if word.is_empty() { &surface } else { &word };
+                let _ = writeln!(
+                    out,
+                    "{:02}{:02}{:02} X- -------- {} {} {} {}",
+                    position + 1,
+                    verse.chapter,
+                    verse.verse,
+                    surface,
+                    word,
+                    word,
+                    word
+                );
+            }
+        }
+        files.insert(format!("{:02}-{}-morphgnt.txt", position + 1, book), out);
+    }
+    files
+}
+
+fn storage_err(path: &Path, e: std::io::Error) -> EngineError {
+    EngineError::Storage(format!("{}: {}", path.display(), e))
+}
+
+/// Write the files to a staging directory and rename it to `install_path`.
+fn install_files(
+    root: &Path,
+    install_path: &Path,
+    files: &BTreeMap<String, String>,
+) -> Result<(), EngineError> {
+    let name = install_path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let staging = root.join(format!(".{}.importing", name));
+    // Left over from an import that was interrupted
+    if staging.exists() {
+        fs::remove_dir_all(&staging).map_err(|e| storage_err(&staging, e))?;
+    }
+    fs::create_dir_all(&staging).map_err(|e| storage_err(&staging, e))?;
+    let written = files.iter().try_for_each(|(file_name, contents)| {
+        let path = staging.join(file_name);
+        fs::write(&path, contents).map_err(|e| storage_err(&path, e))
+    });
+    let result = written.and_then(|()| {
+        fs::rename(&staging, install_path).map_err(|e| storage_err(install_path, e))
+    });
+    if result.is_err() {
+        let _ = fs::remove_dir_all(&staging);
+    }
+    result
+}
+
+fn read_manifest(path: &Path) -> Result<Value, EngineError> {
+    match fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .map_err(|e| EngineError::Storage(format!("{}: {}", path.display(), e))),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(json!({})),
+        Err(e) => Err(storage_err(path, e)),
+    }
+}
+
+/// Add or remove a corpus in the installer manifest, keeping every other
+/// field as the engine wrote it.
+fn update_manifest(root: &Path, id: &str, entry: Option<Value>) -> Result<(), EngineError> {
+    let path = root.join(INSTALLED_MANIFEST);
+    let mut manifest = read_manifest(&path)?;
+    let Some(object) = manifest.as_object_mut() else {
+        return Err(EngineError::Storage(format!(
+            "{}: not a JSON object",
+            path.display()
+        )));
+    };
+    object
+        .entry("manifest_version")
+        .or_insert_with(|| json!("1.0"));
+    object.insert(
+        "last_updated".to_string(),
+        json!(chrono::Utc::now().to_rfc3339()),
+    );
+    let sources = object
+        .entry("sources")
+        .or_insert_with(|| Value::Object(Map::new()));
+    if let Some(sources) = sources.as_object_mut() {
+        match entry {
+            Some(entry) => sources.insert(id.to_string(), entry),
+            None => sources.remove(id),
+        };
+    }
+    storage::write_json(&path, &manifest).map_err(EngineError::Storage)
+}
+
+/// Ask the running engine to load the corpus: `(registered, restart_required)`.
+async fn register_with_engine(
+    port: u16,
+    id: &str,
+    name: &str,
+    install_path: &Path,
+) -> Result<(bool, bool), EngineError> {
+    let request = http::request(Method::POST, port, REGISTER_PATH)?.json(&json!({
+        "source_id": id,
+        "name": name,
+        "install_path": install_path,
+    }));
+    match http::send(request).await {
+        Ok(_) => Ok((true, false)),
+        // Older engine: it reads the manifest when it starts
+        Err(HttpError::Status { status: 404, .. }) => Ok((false, true)),
+        // Not running: it reads the manifest when it starts
+        Err(HttpError::Transport(_)) => Ok((false, false)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Import a local source text as a custom corpus named `name`.
+///
+/// Parse problems come back together, each with its line, and nothing is
+/// installed. Check `restart_required` to prompt for an engine restart.
+#[tauri::command]
+pub async fn import_local_corpus(
+    port: u16,
+    path: String,
+    name: String,
+    format: LocalCorpusFormat,
+) -> Result<CorpusImportResult, CorpusImportError> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(CorpusImportError::Parse {
+            issues: vec![ImportIssue {
+                line: None,
+                message: "The corpus needs a name".to_string(),
+            }],
+        });
+    }
+    let source_path = PathBuf::from(&path);
+    let size = fs::metadata(&source_path)
+        .map_err(|e| CorpusImportError::File(format!("{}: {}", path, e)))?
+        .len();
+    if size > MAX_SOURCE_BYTES {
+        return Err(CorpusImportError::File(format!(
+            "{} is larger than {} MB",
+            path,
+            MAX_SOURCE_BYTES / (1024 * 1024)
+        )));
+    }
+    let source = fs::read_to_string(&source_path)
+        .map_err(|e| CorpusImportError::File(format!("{}: {}", path, e)))?;
+    let verses = parse_source(source.trim_start_matches('\u{feff}'), format)?;
+    let files = morphgnt_files(&verses);
+
+    let root = data_root()?;
+    let id = corpus_id(&name);
+    let install_path = root.join(&id);
+    let manifest = read_manifest(&root.join(INSTALLED_MANIFEST))?;
+    if install_path.exists() || manifest["sources"].get(&id).is_some() {
+        return Err(CorpusImportError::Exists(id));
+    }
+
+    install_files(&root, &install_path, &files)?;
+    let entry = json!({
+        "source_id": id,
+        "name": name,
+        "installed_at": chrono::Utc::now().to_rfc3339(),
+        "install_path": install_path,
+        "version": "",
+        "revision": "",
+        "license": "",
+        "eula_accepted_at": null,
+        "file_count": files.len(),
+        "sha256_manifest": "",
+    });
+    if let Err(e) = update_manifest(&root, &id, Some(entry)) {
+        let _ = fs::remove_dir_all(&install_path);
+        return Err(e.into());
+    }
+
+    let (registered, restart_required) =
+        match register_with_engine(port, &id, &name, &install_path).await {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                // The engine refused it: undo the install
+                let _ = update_manifest(&root, &id, None);
+                let _ = fs::remove_dir_all(&install_path);
+                return Err(e.into());
+            }
+        };
+
+    Ok(CorpusImportResult {
+        id,
+        name,
+        install_path: install_path.to_string_lossy().into_owned(),
+        books: files.len(),
+        verses: verses.len(),
+        registered,
+        restart_required,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locations(verses: &[ImportedVerse]) -> Vec<(String, u32, u32, String)> {
+        verses
+            .iter()
+            .map(|v| (v.book.clone(), v.chapter, v.verse, v.text.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_plain_text() {
+        let source = "# My edition\n\
+                      John 1:1 Ἐν ἀρχῇ ἦν ὁ λόγος,\n\
+                      \n\
+                      John.1.2\tοὗτος ἦν ἐν ἀρχῇ πρὸς τὸν θεόν.\n\
+                      1 Cor 13:13 νυνὶ δὲ μένει πίστις\n";
+        let verses = parse_source(source, LocalCorpusFormat::PlainText).unwrap();
+        assert_eq!(
+            locations(&verses),
+            [
+                ("John".to_string(), 1, 1, "Ἐν ἀρχῇ ἦν ὁ λόγος,".to_string()),
+                (
+                    "John".to_string(),
+                    1,
+                    2,
+                    "οὗτος ἦν ἐν ἀρχῇ πρὸς τὸν θεόν.".to_string()
+                ),
+                (
+                    "1Corinthians".to_string(),
+                    13,
+                    13,
+                    "νυνὶ δὲ μένει πίστις".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plain_text_issues_carry_lines() {
+        let source = "John 1:1 Ἐν ἀρχῇ\n\
+                      Ἐν ἀρχῇ ἦν ὁ λόγος\n\
+                      John 1:99 ἦν\n\
+                      Hezekiah 1:1 ἦν\n\
+                      John 1:1 Ἐν ἀρχῇ\n\
+                      John 1:3\n";
+        let Err(CorpusImportError::Parse { issues }) =
+            parse_source(source, LocalCorpusFormat::PlainText)
+        else {
+            panic!("expected parse issues");
+        };
+        let lines: Vec<_> = issues.iter().map(|i| i.line).collect();
+        assert_eq!(lines, [Some(2), Some(3), Some(4), Some(5), Some(6)]);
+        assert!(issues[3].message.contains("line 1"));
+
+        let Err(CorpusImportError::Parse { issues }) =
+            parse_source("# nothing\n", LocalCorpusFormat::PlainText)
+        else {
+            panic!("expected parse issues");
+        };
+        assert_eq!(issues[0].line, None);
+    }
+
+    #[test]
+    fn test_parse_usfm() {
+        let source = "\\id JHN My edition\n\
+                      \\h John\n\
+                      \\mt1 ΚΑΤΑ ΙΩΑΝΝΗΝ\n\
+                      \\c 1\n\
+                      \\p\n\
+                      \\v 1 Ἐν ἀρχῇ ἦν \\w ὁ|lemma=\"ὁ\"\\w* λόγος,\\f + \\fr 1:1 \\ft note\\f*\n\
+                      \\v 2 οὗτος ἦν\n\
+                      \\q ἐν ἀρχῇ \\add πρὸς\\add* τὸν θεόν.\n";
+        let verses = parse_source(source, LocalCorpusFormat::Usfm).unwrap();
+        assert_eq!(
+            locations(&verses),
+            [
+                ("John".to_string(), 1, 1, "Ἐν ἀρχῇ ἦν ὁ λόγος,".to_string()),
+                (
+                    "John".to_string(),
+                    1,
+                    2,
+                    "οὗτος ἦν ἐν ἀρχῇ πρὸς τὸν θεόν.".to_string()
+                ),
+            ]
+        );
+
+        let source = "\\id XYZ\n\\c 1\n\\v 1 λόγος\n";
+        let Err(CorpusImportError::Parse { issues }) =
+            parse_source(source, LocalCorpusFormat::Usfm)
+        else {
+            panic!("expected parse issues");
+        };
+        assert_eq!(issues[0].line, Some(1));
+        assert!(issues.iter().any(|i| i.line == Some(2)));
+
+        let source = "\\id JUD\n\\c 1\n\\v 26 ἀμήν\n";
+        assert!(parse_source(source, LocalCorpusFormat::Usfm).is_err());
+    }
+
+    #[test]
+    fn test_morphgnt_files() {
+        let verses = parse_source(
+            "John 1:1 Ἐν ἀρχῇ — λόγος.\nMatt 1:1 Βίβλος\n",
+            LocalCorpusFormat::PlainText,
+        )
+        .unwrap();
+        let files = morphgnt_files(&verses);
+        let names: Vec<_> = files.keys().cloned().collect();
+        assert_eq!(names, ["01-Matthew-morphgnt.txt", "04-John-morphgnt.txt"]);
+        assert_eq!(
+            files["04-John-morphgnt.txt"],
+            "040101 X- -------- Ἐν Ἐν Ἐν Ἐν\n\
+             040101 X- -------- ἀρχῇ— ἀρχῇ ἀρχῇ ἀρχῇ\n\
+             040101 X- -------- λόγος. λόγος λόγος λόγος\n"
+        );
+    }
+
+    #[test]
+    fn test_install_and_manifest() {
+        let root = std::env::temp_dir().join(format!("rl-import-test-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(
+            root.join(INSTALLED_MANIFEST),
+            r#"{"manifest_version": "1.0", "sources": {"morphgnt-sblgnt": {"name": "SBLGNT"}}}"#,
+        )
+        .unwrap();
+
+        let install_path = root.join("user-test");
+        let files =
+            BTreeMap::from([("04-John-morphgnt.txt".to_string(), "040101 …\n".to_string())]);
+        install_files(&root, &install_path, &files).unwrap();
+        assert!(install_path.join("04-John-morphgnt.txt").is_file());
+        assert!(!root.join(".user-test.importing").exists());
+
+        update_manifest(&root, "user-test", Some(json!({"name": "Test"}))).unwrap();
+        let manifest = read_manifest(&root.join(INSTALLED_MANIFEST)).unwrap();
+        assert_eq!(manifest["sources"]["user-test"]["name"], "Test");
+        assert_eq!(manifest["sources"]["morphgnt-sblgnt"]["name"], "SBLGNT");
+        update_manifest(&root, "user-test", None).unwrap();
+        let manifest = read_manifest(&root.join(INSTALLED_MANIFEST)).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+        assert!(manifest["sources"].get("user-test").is_none());
+        assert_eq!(manifest["manifest_version"], "1.0");
+    }
+
+    #[test]
+    fn test_corpus_id() {
+        assert_eq!(corpus_id("Tischendorf 8th ed."), "user-tischendorf-8th-ed");
+        assert!(is_user_corpus(&corpus_id("Κείμενο")));
+        assert_eq!(corpus_id("Κείμενο").len(), "user-".len() + 12);
+        assert!(!is_user_corpus("morphgnt-sblgnt"));
+    }
+}
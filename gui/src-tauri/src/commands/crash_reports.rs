@@ -0,0 +1,438 @@
+//! Crash reports for sessions that ended abnormally.
+//!
+//! Each session writes `crash_reports/session_started` when it starts and
+//! `crash_reports/clean_shutdown` when it exits normally. At the next
+//! launch a session marker without the shutdown flag means the previous
+//! session crashed, and a report is filed in `crash_reports/{id}.json`.
+//! A panic hook keeps the last panic in `last_panic.json` so the report
+//! can say what happened: the message, backtrace, app version and OS, the
+//! last command the frontend invoked and the end of the backend log. The hook does little
+//! more than copy these, and passes the message and log through
+//! `redact_tokens`.
+//!
+//! At launch `get_pending_crash_reports` lets the frontend offer to put
+//! the reports in a diagnostics bundle; `discard_crash_report` deletes one.
+//! Reports stay on this machine until the user sends one, and sending
+//! only works after opting in and configuring an endpoint.
+
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::backtrace::Backtrace;
+use std::fs;
+use std::panic::PanicHookInfo;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tauri::ipc::Invoke;
+use tauri::{AppHandle, Runtime};
+
+use super::auth::redact_tokens;
+use super::errors::AppError;
+use super::http;
+use super::logging;
+use super::settings;
+use super::storage::{self, now_ms};
+
+const CRASH_DIR: &str = "crash_reports";
+/// Written on clean exit, removed at startup
+const SHUTDOWN_FLAG: &str = "clean_shutdown";
+/// Written at startup; shows a session ran since the flag was removed
+const SESSION_MARKER: &str = "session_started";
+const PANIC_FILE: &str = "last_panic.json";
+/// Reports kept, newest first
+const MAX_REPORTS: usize = 20;
+/// Longer panic messages are cut once tokens are redacted
+const MAX_PANIC_MESSAGE_CHARS: usize = 2000;
+/// Backend log lines kept with a panic
+const LOG_TAIL_LINES: usize = 100;
+
+/// Command the frontend invoked last
+static LAST_COMMAND: Mutex<Option<String>> = Mutex::new(None);
+/// Set while the hook writes a record; a panic on another thread meanwhile
+/// is not recorded
+static IN_PANIC_HOOK: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CrashReportingSettings {
+    /// Opt-in for sending reports; nothing is sent while unset
+    pub enabled: bool,
+    /// Where `send_crash_report` posts reports
+    pub endpoint: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrashReport {
+    pub id: String,
+    /// When the crash was detected, or when the panic happened if known
+    pub timestamp_ms: u64,
+    pub panic_message: Option<String>,
+    pub backtrace: Option<String>,
+    pub reported: bool,
+    /// Unknown for crashes without a panic, and in older reports
+    #[serde(default)]
+    pub app_version: Option<String>,
+    /// OS and architecture, e.g. "linux x86_64"
+    #[serde(default)]
+    pub os: Option<String>,
+    /// Last command the frontend invoked before the panic. Commands overlap,
+    /// so this need not be the one that panicked.
+    #[serde(default, alias = "command")]
+    pub last_command: Option<String>,
+    /// End of the backend log at the time of the panic
+    #[serde(default)]
+    pub log_tail: Vec<String>,
+}
+
+/// The last panic of a session, written by the panic hook.
+#[derive(Debug, Serialize, Deserialize)]
+struct PanicRecord {
+    message: String,
+    backtrace: String,
+    timestamp_ms: u64,
+    #[serde(default)]
+    app_version: String,
+    #[serde(default)]
+    os: String,
+    #[serde(default, alias = "command")]
+    last_command: Option<String>,
+    #[serde(default)]
+    log_tail: Vec<String>,
+}
+
+/// Wrap the invoke handler to note each command, so crash reports can
+/// name the last one. Async commands run on tasks tauri spawns after the
+/// handler returns, so which command panicked is not known.
+pub fn track_commands<R: Runtime>(
+    handler: impl Fn(Invoke<R>) -> bool + Send + Sync + 'static,
+) -> impl Fn(Invoke<R>) -> bool + Send + Sync + 'static {
+    move |invoke| {
+        if let Ok(mut last) = LAST_COMMAND.lock() {
+            *last = Some(invoke.message.command().to_string());
+        }
+        handler(invoke)
+    }
+}
+
+/// Never blocks: the panicking thread may be the one holding the lock.
+fn last_command() -> Option<String> {
+    LAST_COMMAND.try_lock().ok().and_then(|last| last.clone())
+}
+
+fn crash_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    storage::app_data_subdir(app, CRASH_DIR)
+}
+
+fn report_path(dir: &Path, id: &str) -> Result<PathBuf, String> {
+    // Ids are UUIDs; anything else could escape the directory
+    uuid::Uuid::parse_str(id).map_err(|_| format!("Invalid crash report id: {}", id))?;
+    Ok(dir.join(format!("{}.json", id)))
+}
+
+fn remove_if_exists(path: &Path) -> Result<(), String> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(format!("{}: {}", path.display(), e))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Redact before cutting: a token cut short is too short to be redacted.
+fn redacted_message(message: &str) -> String {
+    let mut message = redact_tokens(message);
+    if let Some((end, _)) = message.char_indices().nth(MAX_PANIC_MESSAGE_CHARS) {
+        message.truncate(end);
+    }
+    message
+}
+
+fn panic_message(info: &PanicHookInfo) -> String {
+    let payload = info.payload();
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("Box<dyn Any>");
+    let message = redacted_message(message);
+    match info.location() {
+        Some(location) => format!("{} at {}", message, location),
+        None => message,
+    }
+}
+
+fn install_panic_hook(dir: PathBuf, app_version: String) {
+    let os = format!("{} {}", std::env::consts::OS, std::env::consts::ARCH);
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if !IN_PANIC_HOOK.swap(true, Ordering::SeqCst) {
+            let record = PanicRecord {
+                message: panic_message(info),
+                backtrace: Backtrace::force_capture().to_string(),
+                timestamp_ms: now_ms(),
+                app_version: app_version.clone(),
+                os: os.clone(),
+                last_command: last_command(),
+                log_tail: logging::recent_lines(LOG_TAIL_LINES)
+                    .iter()
+                    .map(|line| redact_tokens(line))
+                    .collect(),
+            };
+            let _ = storage::write_json(&dir.join(PANIC_FILE), &record);
+            IN_PANIC_HOOK.store(false, Ordering::SeqCst);
+        }
+        previous(info);
+    }));
+}
+
+/// Check how the previous session ended and start this one. Returns a
+/// report when it crashed.
+fn start_session(dir: &Path, now: u64) -> Result<Option<CrashReport>, String> {
+    let crashed = dir.join(SESSION_MARKER).exists() && !dir.join(SHUTDOWN_FLAG).exists();
+    let panic: Option<PanicRecord> = storage::read_json(&dir.join(PANIC_FILE)).ok();
+    remove_if_exists(&dir.join(PANIC_FILE))?;
+    remove_if_exists(&dir.join(SHUTDOWN_FLAG))?;
+    storage::write_atomic(&dir.join(SESSION_MARKER), now.to_string().as_bytes())?;
+    if !crashed {
+        return Ok(None);
+    }
+    let id = uuid::Uuid::new_v4().to_string();
+    Ok(Some(match panic {
+        Some(panic) => CrashReport {
+            id,
+            timestamp_ms: panic.timestamp_ms,
+            panic_message: Some(panic.message),
+            backtrace: Some(panic.backtrace),
+            reported: false,
+            app_version: Some(panic.app_version).filter(|v| !v.is_empty()),
+            os: Some(panic.os).filter(|os| !os.is_empty()),
+            last_command: panic.last_command,
+            log_tail: panic.log_tail,
+        },
+        None => CrashReport {
+            id,
+            timestamp_ms: now,
+            panic_message: None,
+            backtrace: None,
+            reported: false,
+            app_version: None,
+            os: None,
+            last_command: None,
+            log_tail: Vec::new(),
+        },
+    }))
+}
+
+/// Stored reports, newest first.
+fn load_reports(dir: &Path) -> Result<Vec<CrashReport>, String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    let mut reports: Vec<CrashReport> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| storage::read_json(&path).ok())
+        .collect();
+    reports.sort_by_key(|r| std::cmp::Reverse(r.timestamp_ms));
+    Ok(reports)
+}
+
+fn file_report(dir: &Path, report: &CrashReport) -> Result<(), String> {
+    storage::write_json(&report_path(dir, &report.id)?, report)?;
+    for old in load_reports(dir)?.iter().skip(MAX_REPORTS) {
+        remove_if_exists(&report_path(dir, &old.id)?)?;
+    }
+    Ok(())
+}
+
+/// File a report if the previous session crashed, and record panics from
+/// this one. Called from setup, after the single-instance check, so a
+/// second launch can't mistake the running session for a crashed one.
+pub fn init_crash_reporting(app: &AppHandle) {
+    let dir = match crash_dir(app) {
+        Ok(dir) => dir,
+        Err(e) => {
+            tracing::warn!("Crash reporting unavailable: {}", e);
+            return;
+        }
+    };
+    match start_session(&dir, now_ms()) {
+        Ok(Some(report)) => {
+            if let Err(e) = file_report(&dir, &report) {
+                tracing::warn!("Could not save the crash report: {}", e);
+            }
+        }
+        Ok(None) => {}
+        Err(e) => tracing::warn!("Could not check the previous session: {}", e),
+    }
+    install_panic_hook(dir, app.package_info().version.to_string());
+}
+
+/// Record that this session is ending normally. Call before any exit.
+pub fn mark_clean_shutdown(app: &AppHandle) {
+    let result = crash_dir(app).and_then(|dir| {
+        // A panic the app survived isn't a crash
+        remove_if_exists(&dir.join(PANIC_FILE))?;
+        storage::write_atomic(&dir.join(SHUTDOWN_FLAG), now_ms().to_string().as_bytes())
+    });
+    if let Err(e) = result {
+        tracing::warn!("Could not record a clean shutdown: {}", e);
+    }
+}
+
+/// Crash reports from earlier sessions, newest first.
+#[tauri::command]
+pub fn get_crash_reports(app: AppHandle) -> Result<Vec<CrashReport>, AppError> {
+    Ok(load_reports(&crash_dir(&app)?)?)
+}
+
+/// Reports not yet sent, for offering them at launch. Newest first.
+#[tauri::command]
+pub fn get_pending_crash_reports(app: AppHandle) -> Result<Vec<CrashReport>, AppError> {
+    let reports = load_reports(&crash_dir(&app)?)?;
+    Ok(reports.into_iter().filter(|r| !r.reported).collect())
+}
+
+/// Delete a crash report, once it is in a bundle or the user declines.
+#[tauri::command]
+pub fn discard_crash_report(app: AppHandle, id: String) -> Result<(), AppError> {
+    Ok(remove_if_exists(&report_path(&crash_dir(&app)?, &id)?)?)
+}
+
+/// Opt in to or out of sending crash reports, and set where they go.
+#[tauri::command]
+pub fn set_crash_reporting(
+    app: AppHandle,
+    settings: CrashReportingSettings,
+) -> Result<CrashReportingSettings, AppError> {
+    if let Some(url) = &settings.endpoint {
+        if !url.starts_with("https://") && !url.starts_with("http://") {
+            return Err(AppError::InvalidInput(format!(
+                "Invalid crash report endpoint: {}",
+                url
+            )));
+        }
+    }
+    settings::update_settings(&app, |s| s.crash_reporting = settings.clone())?;
+    Ok(settings)
+}
+
+/// Send a crash report to the configured endpoint and mark it reported.
+/// Requires the user to have opted in.
+#[tauri::command]
+pub async fn send_crash_report(app: AppHandle, id: String) -> Result<(), AppError> {
+    let reporting = settings::load_settings(&app)?.crash_reporting;
+    if !reporting.enabled {
+        return Err(AppError::InvalidInput(
+            "Crash reporting is turned off".to_string(),
+        ));
+    }
+    let endpoint = reporting.endpoint.ok_or_else(|| {
+        AppError::InvalidInput("No crash report endpoint is configured".to_string())
+    })?;
+    let path = report_path(&crash_dir(&app)?, &id)?;
+    if !path.exists() {
+        return Err(AppError::NotFound(format!("No crash report {}", id)));
+    }
+    let mut report: CrashReport = storage::read_json(&path)?;
+    if report.reported {
+        return Ok(());
+    }
+
+    let body = serde_json::json!({
+        "report": report,
+        "app_version": app.package_info().version.to_string(),
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+    });
+    let response = http::remote(Method::POST, &endpoint)?
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| format!("Could not send the crash report: {}", e))?;
+    if !response.status().is_success() {
+        return Err(AppError::Failed(format!(
+            "The crash report endpoint returned HTTP {}",
+            response.status().as_u16()
+        )));
+    }
+
+    report.reported = true;
+    Ok(storage::write_json(&path, &report)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sessions_and_crash_detection() {
+        let dir = std::env::temp_dir().join(format!("rl-crash-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        // First launch: nothing ran before
+        assert_eq!(start_session(&dir, 1_000).unwrap(), None);
+
+        // The session ends cleanly
+        storage::write_atomic(&dir.join(SHUTDOWN_FLAG), b"2000").unwrap();
+        assert_eq!(start_session(&dir, 3_000).unwrap(), None);
+        assert!(!dir.join(SHUTDOWN_FLAG).exists());
+
+        // The session dies after a panic
+        let record = PanicRecord {
+            message: "index out of bounds at src/lib.rs:1:1".to_string(),
+            backtrace: "0: main".to_string(),
+            timestamp_ms: 4_000,
+            app_version: "1.2.0".to_string(),
+            os: "linux x86_64".to_string(),
+            last_command: Some("translate_passage".to_string()),
+            log_tail: vec!["INFO started".to_string()],
+        };
+        storage::write_json(&dir.join(PANIC_FILE), &record).unwrap();
+        let report = start_session(&dir, 5_000).unwrap().unwrap();
+        assert_eq!(report.timestamp_ms, 4_000);
+        assert_eq!(
+            report.panic_message.as_deref(),
+            Some(record.message.as_str())
+        );
+        assert!(!report.reported);
+        assert_eq!(report.app_version.as_deref(), Some("1.2.0"));
+        assert_eq!(report.last_command.as_deref(), Some("translate_passage"));
+        assert_eq!(report.log_tail, record.log_tail);
+        assert!(!dir.join(PANIC_FILE).exists());
+
+        // The session dies without a panic
+        let report = start_session(&dir, 6_000).unwrap().unwrap();
+        assert_eq!((report.timestamp_ms, report.backtrace), (6_000, None));
+
+        // Reports filed before the field was renamed
+        let older: CrashReport = serde_json::from_value(serde_json::json!({
+            "id": report.id, "timestamp_ms": 1, "panic_message": null,
+            "backtrace": null, "reported": false, "command": "get_settings",
+        }))
+        .unwrap();
+        assert_eq!(older.last_command.as_deref(), Some("get_settings"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_tokens_cut_by_the_length_limit_are_redacted() {
+        let token = format!("rl_{}", "a".repeat(30));
+        let padding = "x".repeat(MAX_PANIC_MESSAGE_CHARS - 10);
+        let message = redacted_message(&format!("{} {} trailing", padding, token));
+        assert!(!message.contains("aaaa"), "{}", message);
+        assert!(message.ends_with("rl_<redac"), "{}", message);
+        assert_eq!(message.chars().count(), MAX_PANIC_MESSAGE_CHARS);
+    }
+
+    #[test]
+    fn test_report_ids_are_validated() {
+        let dir = Path::new("/tmp");
+        assert!(report_path(dir, "../settings").is_err());
+        let id = uuid::Uuid::new_v4().to_string();
+        assert_eq!(
+            report_path(dir, &id).unwrap(),
+            dir.join(format!("{}.json", id))
+        );
+    }
+}
@@ -0,0 +1,108 @@
+//! Frontend-initiated events relayed to other windows.
+//!
+//! Multi-window layouts use this to broadcast through the backend. Names
+//! must start with `ext-` and are otherwise lowercase kebab/snake case, so
+//! they can't collide with the app's own events (`locale-changed`,
+//! `revoke-current-token`, ...), which never use that prefix.
+
+use serde::Deserialize;
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, WebviewWindow};
+
+use super::command_history::record_command;
+use super::errors::AppError;
+
+/// Prefix every custom event name must start with
+const EVENT_PREFIX: &str = "ext-";
+/// Longest accepted event name
+const MAX_EVENT_NAME_LEN: usize = 64;
+/// Largest accepted payload, serialized
+const MAX_PAYLOAD_BYTES: usize = 1024 * 1024;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventTarget {
+    AllWindows,
+    /// The window that invoked the command
+    CurrentWindow,
+    WindowByLabel(String),
+}
+
+/// `ext-[a-z0-9_-]+`, at most 64 characters.
+fn is_valid_event_name(name: &str) -> bool {
+    let Some(rest) = name.strip_prefix(EVENT_PREFIX) else {
+        return false;
+    };
+    !rest.is_empty()
+        && name.len() <= MAX_EVENT_NAME_LEN
+        && rest
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_' || b == b'-')
+}
+
+/// Publish a named event to all windows, the calling window, or one window.
+#[tauri::command]
+pub fn emit_custom_event(
+    app: AppHandle,
+    window: WebviewWindow,
+    event_name: String,
+    payload: Value,
+    target: EventTarget,
+) -> Result<(), AppError> {
+    if !is_valid_event_name(&event_name) {
+        return Err(AppError::InvalidInput(format!(
+            "Invalid event name: {:?} (names must start with {:?})",
+            event_name, EVENT_PREFIX
+        )));
+    }
+    let size = serde_json::to_vec(&payload)
+        .map_err(|e| e.to_string())?
+        .len();
+    if size > MAX_PAYLOAD_BYTES {
+        return Err(AppError::InvalidInput(format!(
+            "Event payload is {} bytes; the limit is {}",
+            size, MAX_PAYLOAD_BYTES
+        )));
+    }
+
+    let result = match &target {
+        EventTarget::AllWindows => app.emit(&event_name, payload),
+        EventTarget::CurrentWindow => app.emit_to(window.label(), &event_name, payload),
+        EventTarget::WindowByLabel(label) => app.emit_to(label.as_str(), &event_name, payload),
+    };
+    result.map_err(|e| e.to_string())?;
+
+    record_command(&app, "emit_custom_event", &event_name);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_name_validation() {
+        assert!(is_valid_event_name("ext-workspace-sync"));
+        assert!(is_valid_event_name("ext-pane_2_scrolled"));
+        assert!(!is_valid_event_name(""));
+        assert!(!is_valid_event_name("ext-"));
+        assert!(!is_valid_event_name("ext-Open-Request"));
+        assert!(!is_valid_event_name("tauri://focus"));
+        assert!(is_valid_event_name(&format!("ext-{}", "a".repeat(60))));
+        assert!(!is_valid_event_name(&format!("ext-{}", "a".repeat(61))));
+    }
+
+    #[test]
+    fn test_app_event_names_rejected() {
+        for name in [
+            "revoke-current-token",
+            "project-opened",
+            "locale-changed",
+            "offline-mode-changed",
+            "badge-updated",
+            "workspace-sync",
+        ] {
+            assert!(!is_valid_event_name(name), "{}", name);
+        }
+    }
+}
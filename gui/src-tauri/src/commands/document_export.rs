@@ -0,0 +1,758 @@
+//! Markdown and standalone HTML export of cached passages.
+//!
+//! Both formats share one rendering layer: passages are loaded and written
+//! one at a time, so large selections never sit in memory as a whole, and
+//! a passage that can't be loaded or rendered is reported without
+//! aborting the export. HTML output inlines its stylesheet and, when the
+//! app ships one, the Greek webfont, so the file renders the same when
+//! emailed. Both end with the attribution the source texts' licenses
+//! ask for, unless it is turned off.
+
+use base64::Engine as _;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use tauri::path::BaseDirectory;
+use tauri::{AppHandle, Manager};
+
+use super::citation::{citation_style, passage_citation};
+use super::errors::AppError;
+use super::licensing::{self, Attribution};
+use super::passages::{load_passage, CachedPassage};
+use super::red_letters::{red_letter_verses, MARKDOWN_LEGEND};
+use super::save_dialog::{validate_export_path, ExportKind};
+use super::storage;
+use super::tags;
+use super::transliteration::{transliterate_text, TransliterationScheme};
+
+/// Greek font embedded in HTML exports, relative to the resource dir. One
+/// of the fonts shipped for `install_bundled_font`.
+const GREEK_FONT_RESOURCE: &str = "fonts/DejaVuSerif.ttf";
+/// Fallback Greek font stack, matching the app's reading view
+pub const GREEK_FONT_STACK: &str = "'SBL Greek', 'Cardo', 'Gentium Plus', serif";
+
+pub const HTML_STYLE: &str = "
+body { max-width: 42rem; margin: 2rem auto; padding: 0 1rem; font-family: Georgia, Cambria, 'Times New Roman', serif; line-height: 1.6; color: #222; }
+h2 { font-size: 1.2rem; margin-top: 2rem; }
+.greek { font-family: var(--greek-font); }
+.verse-num { font-size: 0.7em; vertical-align: super; color: #666; margin-right: 0.2em; }
+.interlinear .verse { margin-bottom: 0.75rem; }
+.interlinear .greek { display: block; }
+.interlinear .translit { display: block; font-style: italic; color: #666; }
+.interlinear .gloss { display: block; color: #444; }
+.red-letter { color: #b00020; }
+.note-ref { font-size: 0.7em; vertical-align: super; }
+.notes, .citation, .attribution { margin-top: 2rem; border-top: 1px solid #ccc; font-size: 0.9rem; color: #555; }
+";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DocumentOptions {
+    /// Greek line above each verse's translation
+    #[serde(default)]
+    pub interlinear: bool,
+    /// Emphasize red-letter verses (bold with a legend in Markdown, a CSS
+    /// class in HTML)
+    #[serde(default = "default_true", alias = "red_letter")]
+    pub highlight_red_letters: bool,
+    /// Include the private passage notes as footnotes
+    #[serde(default)]
+    pub include_notes: bool,
+    /// Citation footer listing each passage's source
+    #[serde(default = "default_true")]
+    pub citation: bool,
+    /// Citation style id for the footer; the provenance line when unset
+    #[serde(default)]
+    pub citation_style: Option<String>,
+    /// Romanized line under the Greek in interlinear layout
+    #[serde(default)]
+    pub transliteration: Option<TransliterationScheme>,
+    /// Only passages with this tag; with no passage ids, every passage
+    /// carrying it
+    #[serde(default)]
+    pub tag: Option<String>,
+    /// Closing list of source texts, their licenses and the engine; most
+    /// corpus licenses require it when the file is shared
+    #[serde(default = "default_true")]
+    pub include_attribution: bool,
+}
+fn default_true() -> bool {
+    true
+}
+
+impl Default for DocumentOptions {
+    fn default() -> Self {
+        Self {
+            interlinear: false,
+            highlight_red_letters: true,
+            include_notes: false,
+            citation: true,
+            citation_style: None,
+            transliteration: None,
+            tag: None,
+            include_attribution: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PassageExportFailure {
+    pub passage_id: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DocumentExportReport {
+    pub path: String,
+    pub passages_written: usize,
+    pub failed: Vec<PassageExportFailure>,
+}
+
+/// One verse as handed to a renderer.
+pub struct RenderVerse<'a> {
+    /// Verse number, empty for passages cached without verse breakdown
+    pub number: &'a str,
+    pub greek: &'a str,
+    pub translation: &'a str,
+    pub red_letter: bool,
+}
+
+/// Output format for `export_document`.
+trait DocumentRenderer {
+    fn begin(&mut self, out: &mut dyn Write) -> io::Result<()>;
+    fn heading(&mut self, out: &mut dyn Write, reference: &str) -> io::Result<()>;
+    fn verses(&mut self, out: &mut dyn Write, verses: &[RenderVerse]) -> io::Result<()>;
+    /// Reference a footnote from the current passage
+    fn note(&mut self, out: &mut dyn Write, note: &str) -> io::Result<()>;
+    fn end(
+        &mut self,
+        out: &mut dyn Write,
+        citations: &[String],
+        attribution: &[String],
+    ) -> io::Result<()>;
+}
+
+/// Heading of the closing attribution list
+pub const ATTRIBUTION_HEADING: &str = "Sources and licenses";
+
+/// Attribution list closing a Markdown document.
+pub fn write_markdown_attribution(out: &mut dyn Write, attribution: &[String]) -> io::Result<()> {
+    if attribution.is_empty() {
+        return Ok(());
+    }
+    write!(out, "\n**{}**\n\n", ATTRIBUTION_HEADING)?;
+    for line in attribution {
+        writeln!(out, "- {}", escape_markdown(line))?;
+    }
+    Ok(())
+}
+
+/// Attribution footer closing an HTML document.
+pub fn write_html_attribution(out: &mut dyn Write, attribution: &[String]) -> io::Result<()> {
+    if attribution.is_empty() {
+        return Ok(());
+    }
+    writeln!(
+        out,
+        "<footer class=\"attribution\"><p>{}</p><ul>",
+        ATTRIBUTION_HEADING
+    )?;
+    for line in attribution {
+        writeln!(out, "<li>{}</li>", escape_html(line))?;
+    }
+    writeln!(out, "</ul></footer>")
+}
+
+/// Escape characters Markdown would interpret.
+pub fn escape_markdown(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '*' | '_' | '`' | '[' | ']' | '<' | '>' | '#' | '|'
+        ) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+pub fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Collapse line breaks and runs of spaces.
+pub fn one_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+struct MarkdownRenderer {
+    options: DocumentOptions,
+    notes: Vec<String>,
+    /// Whether any verse was emphasized, so the legend is needed
+    red_letters_shown: bool,
+}
+
+impl DocumentRenderer for MarkdownRenderer {
+    fn begin(&mut self, _out: &mut dyn Write) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn heading(&mut self, out: &mut dyn Write, reference: &str) -> io::Result<()> {
+        write!(out, "## {}\n\n", escape_markdown(reference))
+    }
+
+    fn verses(&mut self, out: &mut dyn Write, verses: &[RenderVerse]) -> io::Result<()> {
+        for v in verses {
+            let mut text = escape_markdown(&one_line(v.translation));
+            if self.options.highlight_red_letters && v.red_letter {
+                text = format!("**{}**", text);
+                self.red_letters_shown = true;
+            }
+            if self.options.interlinear {
+                // Two trailing spaces keep the gloss on its own line
+                write!(out, "<sup>{}</sup> ", v.number)?;
+                if !v.greek.trim().is_empty() {
+                    writeln!(out, "*{}*  ", escape_markdown(&one_line(v.greek)))?;
+                    if let Some(scheme) = self.options.transliteration {
+                        let romanized = transliterate_text(&one_line(v.greek), scheme, false);
+                        writeln!(out, "{}  ", escape_markdown(&romanized))?;
+                    }
+                }
+                write!(out, "{}\n\n", text)?;
+            } else {
+                write!(out, "<sup>{}</sup> {} ", v.number, text)?;
+            }
+        }
+        if !self.options.interlinear {
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+
+    fn note(&mut self, out: &mut dyn Write, note: &str) -> io::Result<()> {
+        self.notes.push(note.to_string());
+        write!(out, "[^{}]\n\n", self.notes.len())
+    }
+
+    fn end(
+        &mut self,
+        out: &mut dyn Write,
+        citations: &[String],
+        attribution: &[String],
+    ) -> io::Result<()> {
+        for (i, note) in self.notes.iter().enumerate() {
+            // Continuation lines of a footnote are indented
+            writeln!(
+                out,
+                "[^{}]: {}",
+                i + 1,
+                escape_markdown(note.trim()).replace('\n', "\n    ")
+            )?;
+        }
+        if !self.notes.is_empty() {
+            writeln!(out)?;
+        }
+        if self.red_letters_shown {
+            write!(out, "{}\n\n", MARKDOWN_LEGEND)?;
+        }
+        if !citations.is_empty() {
+            writeln!(out, "---\n")?;
+            for citation in citations {
+                writeln!(out, "- {}", escape_markdown(citation))?;
+            }
+        }
+        write_markdown_attribution(out, attribution)
+    }
+}
+
+struct HtmlRenderer {
+    options: DocumentOptions,
+    title: String,
+    /// `@font-face` rule embedding the Greek webfont, if available
+    font_face: Option<String>,
+    notes: Vec<String>,
+}
+
+impl DocumentRenderer for HtmlRenderer {
+    fn begin(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        let greek_font = match &self.font_face {
+            Some(_) => format!("'RedLettersGreek', {}", GREEK_FONT_STACK),
+            None => GREEK_FONT_STACK.to_string(),
+        };
+        write!(
+            out,
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>{}</title>\n<style>{}\n:root {{ --greek-font: {}; }}{}</style>\n</head>\n",
+            escape_html(&self.title),
+            self.font_face.as_deref().unwrap_or(""),
+            greek_font,
+            HTML_STYLE
+        )?;
+        let class = if self.options.interlinear {
+            " class=\"interlinear\""
+        } else {
+            ""
+        };
+        writeln!(out, "<body{}>", class)
+    }
+
+    fn heading(&mut self, out: &mut dyn Write, reference: &str) -> io::Result<()> {
+        writeln!(out, "<h2>{}</h2>", escape_html(reference))
+    }
+
+    fn verses(&mut self, out: &mut dyn Write, verses: &[RenderVerse]) -> io::Result<()> {
+        if !self.options.interlinear {
+            write!(out, "<p>")?;
+        }
+        for v in verses {
+            let class = if self.options.highlight_red_letters && v.red_letter {
+                "gloss red-letter"
+            } else {
+                "gloss"
+            };
+            let gloss = format!(
+                "<span class=\"{}\">{}</span>",
+                class,
+                escape_html(&one_line(v.translation))
+            );
+            let number = format!("<span class=\"verse-num\">{}</span>", v.number);
+            if self.options.interlinear {
+                let romanized = match self.options.transliteration {
+                    Some(scheme) => format!(
+                        "<span class=\"translit\">{}</span>",
+                        escape_html(&transliterate_text(&one_line(v.greek), scheme, false))
+                    ),
+                    None => String::new(),
+                };
+                writeln!(
+                    out,
+                    "<div class=\"verse\">{}<span class=\"greek\" lang=\"grc\">{}</span>{}{}</div>",
+                    number,
+                    escape_html(&one_line(v.greek)),
+                    romanized,
+                    gloss
+                )?;
+            } else {
+                write!(out, "{}{} ", number, gloss)?;
+            }
+        }
+        if !self.options.interlinear {
+            writeln!(out, "</p>")?;
+        }
+        Ok(())
+    }
+
+    fn note(&mut self, out: &mut dyn Write, note: &str) -> io::Result<()> {
+        self.notes.push(note.to_string());
+        let n = self.notes.len();
+        writeln!(
+            out,
+            "<p><a class=\"note-ref\" id=\"note-ref-{}\" href=\"#note-{}\">[{}]</a></p>",
+            n, n, n
+        )
+    }
+
+    fn end(
+        &mut self,
+        out: &mut dyn Write,
+        citations: &[String],
+        attribution: &[String],
+    ) -> io::Result<()> {
+        if !self.notes.is_empty() {
+            writeln!(out, "<section class=\"notes\"><ol>")?;
+            for (i, note) in self.notes.iter().enumerate() {
+                writeln!(
+                    out,
+                    "<li id=\"note-{}\">{} <a href=\"#note-ref-{}\">↩</a></li>",
+                    i + 1,
+                    escape_html(note.trim()).replace('\n', "<br>"),
+                    i + 1
+                )?;
+            }
+            writeln!(out, "</ol></section>")?;
+        }
+        if !citations.is_empty() {
+            writeln!(out, "<footer class=\"citation\"><ul>")?;
+            for citation in citations {
+                writeln!(out, "<li>{}</li>", escape_html(citation))?;
+            }
+            writeln!(out, "</ul></footer>")?;
+        }
+        write_html_attribution(out, attribution)?;
+        writeln!(out, "</body>\n</html>")
+    }
+}
+
+/// Citation line for a passage, e.g.
+/// "John 3:16 - Red Letters, literal translator, readable mode, 2024-05-01".
+fn citation(passage: &CachedPassage) -> String {
+    let date = chrono::DateTime::from_timestamp_millis(passage.translated_at_ms as i64)
+        .map(|d| format!(", {}", d.format("%Y-%m-%d")))
+        .unwrap_or_default();
+    format!(
+        "{} - Red Letters, {} translator, {} mode{}",
+        passage.reference, passage.translator, passage.mode, date
+    )
+}
+
+/// Verses of a passage to render; fails if there is no translation text.
+pub fn passage_verses(passage: &CachedPassage) -> Result<Vec<RenderVerse<'_>>, String> {
+    let red: HashSet<String> = red_letter_verses(passage);
+    let verses: Vec<RenderVerse> = if passage.verses.is_empty() {
+        // Older cache entries only have the whole-passage text
+        vec![RenderVerse {
+            number: "",
+            greek: &passage.greek,
+            translation: &passage.translation,
+            red_letter: false,
+        }]
+    } else {
+        passage
+            .verses
+            .iter()
+            .map(|v| RenderVerse {
+                number: v.verse_id.rsplit('.').next().unwrap_or(&v.verse_id),
+                greek: &v.greek,
+                translation: &v.translation,
+                red_letter: red.contains(&v.verse_id),
+            })
+            .collect()
+    };
+    if verses.iter().all(|v| v.translation.trim().is_empty()) {
+        return Err("No cached translation text".to_string());
+    }
+    Ok(verses)
+}
+
+/// Stream passages through a renderer into `out`, returning how many were
+/// written. Passages that fail to load or have no text are skipped and
+/// recorded in `failed`; only I/O errors abort. The written passages are
+/// credited in `attribution`, when given.
+fn render_document(
+    renderer: &mut dyn DocumentRenderer,
+    out: &mut dyn Write,
+    passage_ids: &[String],
+    options: &DocumentOptions,
+    load: &dyn Fn(&str) -> Result<CachedPassage, String>,
+    failed: &mut Vec<PassageExportFailure>,
+    mut attribution: Option<Attribution>,
+) -> io::Result<usize> {
+    let style = options
+        .citation_style
+        .as_deref()
+        .and_then(|id| citation_style(id).ok());
+    let mut citations = Vec::new();
+    renderer.begin(out)?;
+    for id in passage_ids {
+        let passage = match load(id) {
+            Ok(passage) => passage,
+            Err(error) => {
+                failed.push(PassageExportFailure {
+                    passage_id: id.clone(),
+                    error,
+                });
+                continue;
+            }
+        };
+        let verses = match passage_verses(&passage) {
+            Ok(verses) => verses,
+            Err(error) => {
+                failed.push(PassageExportFailure {
+                    passage_id: id.clone(),
+                    error,
+                });
+                continue;
+            }
+        };
+
+        renderer.heading(out, &passage.reference)?;
+        renderer.verses(out, &verses)?;
+        let note = passage.note.as_deref().filter(|n| !n.trim().is_empty());
+        if let Some(note) = note.filter(|_| options.include_notes) {
+            renderer.note(out, note)?;
+        }
+        citations.push(match style {
+            Some(style) => passage_citation(style, &passage),
+            None => citation(&passage),
+        });
+        if let Some(attribution) = &mut attribution {
+            attribution.add_passage(&passage);
+        }
+    }
+    let written = citations.len();
+    if !options.citation {
+        citations.clear();
+    }
+    let attribution = attribution.map(|a| a.lines()).unwrap_or_default();
+    renderer.end(out, &citations, &attribution)?;
+    Ok(written)
+}
+
+fn font_face_rule(font: &[u8]) -> String {
+    format!(
+        "@font-face {{ font-family: 'RedLettersGreek'; src: url(data:font/ttf;base64,{}) format('truetype'); }}",
+        base64::engine::general_purpose::STANDARD.encode(font)
+    )
+}
+
+/// `@font-face` rule embedding the bundled Greek font, if present.
+pub fn greek_font_face(app: &AppHandle) -> Option<String> {
+    let path = app
+        .path()
+        .resolve(GREEK_FONT_RESOURCE, BaseDirectory::Resource)
+        .ok()?;
+    let font = std::fs::read(path).ok()?;
+    Some(font_face_rule(&font))
+}
+
+fn export_document(
+    app: &AppHandle,
+    kind: ExportKind,
+    mut renderer: Box<dyn DocumentRenderer>,
+    passage_ids: Vec<String>,
+    dest_path: String,
+    options: &DocumentOptions,
+) -> Result<DocumentExportReport, String> {
+    let path = validate_export_path(app, kind, &PathBuf::from(dest_path))?;
+    if let Some(style) = &options.citation_style {
+        citation_style(style)?;
+    }
+    let passage_ids = tags::passage_ids_for_export(app, passage_ids, options.tag.as_deref())?;
+    if passage_ids.is_empty() {
+        return Err("No passages selected".to_string());
+    }
+
+    let mut failed = Vec::new();
+    let mut written = 0;
+    let load = |id: &str| load_passage(app, id);
+    let attribution = licensing::export_attribution(options.include_attribution);
+    storage::write_atomic_with(&path, |out| {
+        written = render_document(
+            renderer.as_mut(),
+            out,
+            &passage_ids,
+            options,
+            &load,
+            &mut failed,
+            attribution,
+        )?;
+        Ok(())
+    })?;
+
+    Ok(DocumentExportReport {
+        path: path.to_string_lossy().into_owned(),
+        passages_written: written,
+        failed,
+    })
+}
+
+/// Export cached passages as a Markdown document.
+///
+/// `dest_path` should come from `choose_save_path` with kind `markdown`.
+#[tauri::command]
+pub fn export_markdown(
+    app: AppHandle,
+    passage_ids: Vec<String>,
+    dest_path: String,
+    options: Option<DocumentOptions>,
+) -> Result<DocumentExportReport, AppError> {
+    let options = options.unwrap_or_default();
+    let renderer = MarkdownRenderer {
+        options: options.clone(),
+        notes: Vec::new(),
+        red_letters_shown: false,
+    };
+    Ok(export_document(
+        &app,
+        ExportKind::Markdown,
+        Box::new(renderer),
+        passage_ids,
+        dest_path,
+        &options,
+    )?)
+}
+
+/// Export cached passages as a self-contained HTML file.
+///
+/// `dest_path` should come from `choose_save_path` with kind `html`.
+#[tauri::command]
+pub fn export_html(
+    app: AppHandle,
+    passage_ids: Vec<String>,
+    dest_path: String,
+    options: Option<DocumentOptions>,
+) -> Result<DocumentExportReport, AppError> {
+    let options = options.unwrap_or_default();
+    let renderer = HtmlRenderer {
+        options: options.clone(),
+        title: "Red Letters passages".to_string(),
+        font_face: greek_font_face(&app),
+        notes: Vec::new(),
+    };
+    Ok(export_document(
+        &app,
+        ExportKind::Html,
+        Box::new(renderer),
+        passage_ids,
+        dest_path,
+        &options,
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testsupport::passage::PassageBuilder;
+
+    fn passage(id: &str, verses: &[(&str, &str)], red: &[&str]) -> CachedPassage {
+        PassageBuilder::new()
+            .id(id)
+            .reference("John 3:16-17")
+            .verses(verses, "οὕτως γὰρ")
+            .red_letters(red)
+            .note(Some("See <1 John 4:9> & *love*"))
+            .translated_at_ms(1_714_521_600_000)
+            .build()
+    }
+
+    fn load(id: &str) -> Result<CachedPassage, String> {
+        match id {
+            "good" => Ok(passage(
+                "good",
+                &[
+                    ("John.3.16", "For God so loved"),
+                    ("John.3.17", "For God sent"),
+                ],
+                &["John.3.17"],
+            )),
+            "empty" => Ok(passage("empty", &[("John.3.16", " ")], &[])),
+            _ => Err(format!("{}: not cached", id)),
+        }
+    }
+
+    fn render(renderer: &mut dyn DocumentRenderer, options: &DocumentOptions) -> String {
+        let ids = ["good", "missing", "empty"].map(String::from);
+        let mut out = Vec::new();
+        let mut failed = Vec::new();
+        let attribution = options
+            .include_attribution
+            .then(|| Attribution::new(Vec::new()));
+        let written = render_document(
+            renderer,
+            &mut out,
+            &ids,
+            options,
+            &load,
+            &mut failed,
+            attribution,
+        )
+        .unwrap();
+        assert_eq!(written, 1);
+        let failed: Vec<&str> = failed.iter().map(|f| f.passage_id.as_str()).collect();
+        assert_eq!(failed, ["missing", "empty"]);
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_markdown_export() {
+        let options = DocumentOptions {
+            include_notes: true,
+            ..Default::default()
+        };
+        let mut renderer = MarkdownRenderer {
+            options: options.clone(),
+            notes: Vec::new(),
+            red_letters_shown: false,
+        };
+        let md = render(&mut renderer, &options);
+        assert!(md.starts_with("## John 3:16-17\n\n<sup>16</sup> For God so loved "));
+        assert!(md.contains("<sup>17</sup> **For God sent**"));
+        assert!(md.contains(MARKDOWN_LEGEND));
+        assert!(md.contains("[^1]: See \\<1 John 4:9\\> & \\*love\\*"));
+        assert!(md.contains(
+            "- John 3:16-17 - Red Letters, literal translator, readable mode, 2024-05-01"
+        ));
+        assert!(!md.contains("οὕτως"));
+        assert!(md.contains("**Sources and licenses**\n\n- Greek text: The Greek New Testament"));
+        assert!(md.ends_with(
+            "- Translated with Red Letters: literal translator, engine version not recorded.\n"
+        ));
+    }
+
+    #[test]
+    fn test_styled_citation_footer() {
+        let options = DocumentOptions {
+            citation_style: Some("simple".to_string()),
+            ..Default::default()
+        };
+        let mut renderer = MarkdownRenderer {
+            options: options.clone(),
+            notes: Vec::new(),
+            red_letters_shown: false,
+        };
+        let md = render(&mut renderer, &options);
+        assert!(md.contains("- John 3:16-17, Red Letters, literal translator, readable mode\n"));
+    }
+
+    #[test]
+    fn test_html_export() {
+        let options = DocumentOptions {
+            interlinear: true,
+            citation: false,
+            transliteration: Some(TransliterationScheme::Simple),
+            include_attribution: false,
+            ..Default::default()
+        };
+        let mut renderer = HtmlRenderer {
+            options: options.clone(),
+            title: "Test".to_string(),
+            font_face: None,
+            notes: Vec::new(),
+        };
+        let html = render(&mut renderer, &options);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<body class=\"interlinear\">"));
+        assert!(html.contains(
+            "<span class=\"greek\" lang=\"grc\">οὕτως γὰρ</span><span class=\"translit\">houtos gar</span>"
+        ));
+        assert!(html.contains("<span class=\"gloss red-letter\">For God sent</span>"));
+        assert!(!html.contains("<a class=\"note-ref\""));
+        assert!(!html.contains("<footer"));
+        assert!(html.trim_end().ends_with("</html>"));
+    }
+
+    #[test]
+    fn test_greek_font_face() {
+        let font = std::fs::read(
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join(GREEK_FONT_RESOURCE),
+        )
+        .expect("the embedded Greek font is missing from the resources");
+        assert!(ttf_parser::Face::parse(&font, 0).is_ok());
+        let rule = font_face_rule(&font);
+        assert!(rule.starts_with(
+            "@font-face { font-family: 'RedLettersGreek'; src: url(data:font/ttf;base64,AAEAAA"
+        ));
+        assert!(rule.ends_with(") format('truetype'); }"));
+    }
+
+    #[test]
+    fn test_escape_html() {
+        assert_eq!(
+            escape_html(r#"<a href="x">Tom & Jerry's</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/a&gt;"
+        );
+    }
+}
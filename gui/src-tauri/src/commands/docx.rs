@@ -0,0 +1,729 @@
+//! DOCX (Word) export of cached passages.
+//!
+//! The package is assembled directly: a zip holding the WordprocessingML
+//! parts (document, styles, footnotes and their relationships). Each part
+//! is re-parsed before the file is written, and passages that can't be
+//! loaded are skipped and listed instead of ending up half-rendered.
+//!
+//! Interlinear tables repeat their header row on every page and keep each
+//! verse's row on one page, so long passages break cleanly between verses.
+
+use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::{Reader, Writer};
+use serde::{Deserialize, Serialize};
+use std::io::{self, Cursor, Write};
+use std::path::PathBuf;
+use tauri::AppHandle;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use super::citation::{citation_style, passage_citation, CitationStyle};
+use super::document_export::{passage_verses, PassageExportFailure, RenderVerse};
+use super::errors::AppError;
+use super::licensing::{self, Attribution};
+use super::passages::{load_passage, CachedPassage};
+use super::save_dialog::{validate_export_path, ExportKind};
+use super::storage;
+use super::tags;
+
+const W_NAMESPACE: &str = "http://schemas.openxmlformats.org/wordprocessingml/2006/main";
+const R_NAMESPACE: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+/// Greek-capable font named on Greek runs
+const GREEK_FONT: &str = "Gentium Plus";
+/// Word's dark red
+const RED: &str = "C00000";
+
+const CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/><Override PartName="/word/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.styles+xml"/><Override PartName="/word/footnotes.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.footnotes+xml"/></Types>"#;
+
+const PACKAGE_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/></Relationships>"#;
+
+const DOCUMENT_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/><Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/footnotes" Target="footnotes.xml"/></Relationships>"#;
+
+const STYLES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:styles xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:docDefaults><w:rPrDefault><w:rPr><w:rFonts w:ascii="Cambria" w:hAnsi="Cambria" w:cs="Cambria"/><w:sz w:val="24"/></w:rPr></w:rPrDefault><w:pPrDefault><w:pPr><w:spacing w:after="120" w:line="276" w:lineRule="auto"/></w:pPr></w:pPrDefault></w:docDefaults><w:style w:type="paragraph" w:default="1" w:styleId="Normal"><w:name w:val="Normal"/><w:qFormat/></w:style><w:style w:type="paragraph" w:styleId="Heading1"><w:name w:val="heading 1"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:keepNext/><w:spacing w:before="360" w:after="120"/><w:outlineLvl w:val="0"/></w:pPr><w:rPr><w:b/><w:sz w:val="32"/></w:rPr></w:style><w:style w:type="paragraph" w:customStyle="1" w:styleId="PassageNote"><w:name w:val="Passage Note"/><w:basedOn w:val="Normal"/><w:pPr><w:ind w:left="360"/></w:pPr><w:rPr><w:i/><w:sz w:val="20"/></w:rPr></w:style><w:style w:type="paragraph" w:customStyle="1" w:styleId="Citation"><w:name w:val="Citation"/><w:basedOn w:val="Normal"/><w:pPr><w:spacing w:after="0"/></w:pPr><w:rPr><w:sz w:val="20"/></w:rPr></w:style><w:style w:type="paragraph" w:styleId="FootnoteText"><w:name w:val="footnote text"/><w:basedOn w:val="Normal"/><w:pPr><w:spacing w:after="0"/></w:pPr><w:rPr><w:sz w:val="20"/></w:rPr></w:style><w:style w:type="character" w:styleId="FootnoteReference"><w:name w:val="footnote reference"/><w:rPr><w:vertAlign w:val="superscript"/></w:rPr></w:style><w:style w:type="table" w:styleId="TableGrid"><w:name w:val="Table Grid"/><w:tblPr><w:tblBorders><w:top w:val="single" w:sz="4" w:space="0" w:color="auto"/><w:left w:val="single" w:sz="4" w:space="0" w:color="auto"/><w:bottom w:val="single" w:sz="4" w:space="0" w:color="auto"/><w:right w:val="single" w:sz="4" w:space="0" w:color="auto"/><w:insideH w:val="single" w:sz="4" w:space="0" w:color="auto"/><w:insideV w:val="single" w:sz="4" w:space="0" w:color="auto"/></w:tblBorders><w:tblCellMar><w:left w:w="108" w:type="dxa"/><w:right w:w="108" w:type="dxa"/></w:tblCellMar></w:tblPr></w:style></w:styles>"#;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DocxLayout {
+    /// Running translation text with verse numbers
+    #[default]
+    Paragraph,
+    /// Two-column table: Greek beside the translation, one row per verse
+    Interlinear,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DocxNotes {
+    #[default]
+    Omit,
+    /// Paragraphs at the end of each passage
+    Paragraph,
+    /// Word footnotes referenced from the passage heading
+    Footnote,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DocxOptions {
+    #[serde(default)]
+    pub layout: DocxLayout,
+    /// Red-letter verses in red
+    #[serde(default = "default_true", alias = "red_letter")]
+    pub highlight_red_letters: bool,
+    #[serde(default)]
+    pub notes: DocxNotes,
+    /// Citation style id; when set, the document ends with a citation
+    /// for each passage
+    #[serde(default)]
+    pub citation_style: Option<String>,
+    /// Only passages with this tag; with no passage ids, every passage
+    /// carrying it
+    #[serde(default)]
+    pub tag: Option<String>,
+    /// Closing list of source texts, their licenses and the engine; most
+    /// corpus licenses require it when the file is shared
+    #[serde(default = "default_true")]
+    pub include_attribution: bool,
+}
+fn default_true() -> bool {
+    true
+}
+
+impl Default for DocxOptions {
+    fn default() -> Self {
+        Self {
+            layout: DocxLayout::Paragraph,
+            highlight_red_letters: true,
+            notes: DocxNotes::Omit,
+            citation_style: None,
+            tag: None,
+            include_attribution: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DocxExportReport {
+    pub path: String,
+    pub passages_written: usize,
+    pub verses_written: usize,
+    pub notes_written: usize,
+    /// Passages that couldn't be loaded or had no text
+    pub skipped: Vec<PassageExportFailure>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct RunStyle {
+    bold: bool,
+    red: bool,
+    greek: bool,
+    superscript: bool,
+}
+
+type Xml = Writer<Vec<u8>>;
+/// Package parts as (zip path, contents)
+type Parts = Vec<(&'static str, String)>;
+
+fn start(w: &mut Xml, name: &str, attrs: &[(&str, &str)]) -> io::Result<()> {
+    let tag = BytesStart::new(name).with_attributes(attrs.iter().copied());
+    w.write_event(Event::Start(tag))
+}
+
+fn end(w: &mut Xml, name: &str) -> io::Result<()> {
+    w.write_event(Event::End(BytesEnd::new(name)))
+}
+
+fn empty(w: &mut Xml, name: &str, attrs: &[(&str, &str)]) -> io::Result<()> {
+    let tag = BytesStart::new(name).with_attributes(attrs.iter().copied());
+    w.write_event(Event::Empty(tag))
+}
+
+/// A run of text; line breaks become `<w:br/>`.
+fn run(w: &mut Xml, text: &str, style: RunStyle) -> io::Result<()> {
+    start(w, "w:r", &[])?;
+    if style.bold || style.red || style.greek || style.superscript {
+        start(w, "w:rPr", &[])?;
+        // Children in schema order
+        if style.greek {
+            let font = [
+                ("w:ascii", GREEK_FONT),
+                ("w:hAnsi", GREEK_FONT),
+                ("w:cs", GREEK_FONT),
+            ];
+            empty(w, "w:rFonts", &font)?;
+        }
+        if style.bold {
+            empty(w, "w:b", &[])?;
+        }
+        if style.red {
+            empty(w, "w:color", &[("w:val", RED)])?;
+        }
+        if style.superscript {
+            empty(w, "w:vertAlign", &[("w:val", "superscript")])?;
+        }
+        if style.greek {
+            empty(w, "w:lang", &[("w:val", "el-GR")])?;
+        }
+        end(w, "w:rPr")?;
+    }
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 {
+            empty(w, "w:br", &[])?;
+        }
+        start(w, "w:t", &[("xml:space", "preserve")])?;
+        w.write_event(Event::Text(BytesText::new(line)))?;
+        end(w, "w:t")?;
+    }
+    end(w, "w:r")
+}
+
+fn paragraph_start(w: &mut Xml, style: Option<&str>) -> io::Result<()> {
+    start(w, "w:p", &[])?;
+    if let Some(style) = style {
+        start(w, "w:pPr", &[])?;
+        empty(w, "w:pStyle", &[("w:val", style)])?;
+        end(w, "w:pPr")?;
+    }
+    Ok(())
+}
+
+/// Verse number and translation runs for one verse.
+fn verse_runs(w: &mut Xml, v: &RenderVerse, red_letter: bool) -> io::Result<()> {
+    if !v.number.is_empty() {
+        let number = RunStyle {
+            superscript: true,
+            ..Default::default()
+        };
+        run(w, v.number, number)?;
+        run(w, " ", RunStyle::default())?;
+    }
+    let text = RunStyle {
+        red: red_letter && v.red_letter,
+        ..Default::default()
+    };
+    run(w, &one_line(v.translation), text)
+}
+
+fn one_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct DocxCounts {
+    passages: usize,
+    verses: usize,
+    notes: usize,
+}
+
+/// Accumulates the document body and footnotes.
+struct DocxBuilder {
+    options: DocxOptions,
+    body: Xml,
+    footnotes: Xml,
+    footnote_count: usize,
+    counts: DocxCounts,
+    citations: Vec<String>,
+    /// Closing attribution lines
+    attribution: Vec<String>,
+}
+
+impl DocxBuilder {
+    fn new(options: DocxOptions) -> Self {
+        Self {
+            options,
+            body: Writer::new(Vec::new()),
+            footnotes: Writer::new(Vec::new()),
+            footnote_count: 0,
+            counts: DocxCounts::default(),
+            citations: Vec::new(),
+            attribution: Vec::new(),
+        }
+    }
+
+    fn passage(&mut self, passage: &CachedPassage, verses: &[RenderVerse]) -> io::Result<()> {
+        let note = passage
+            .note
+            .as_deref()
+            .map(str::trim)
+            .filter(|n| !n.is_empty() && self.options.notes != DocxNotes::Omit);
+
+        let w = &mut self.body;
+        paragraph_start(w, Some("Heading1"))?;
+        run(w, &passage.reference, RunStyle::default())?;
+        if let (Some(note), DocxNotes::Footnote) = (note, self.options.notes) {
+            // Ids -1 and 0 are the separator footnotes
+            self.footnote_count += 1;
+            let id = self.footnote_count.to_string();
+            start(w, "w:r", &[])?;
+            start(w, "w:rPr", &[])?;
+            empty(w, "w:rStyle", &[("w:val", "FootnoteReference")])?;
+            end(w, "w:rPr")?;
+            empty(w, "w:footnoteReference", &[("w:id", &id)])?;
+            end(w, "w:r")?;
+            footnote(&mut self.footnotes, &id, note)?;
+        }
+        end(w, "w:p")?;
+
+        match self.options.layout {
+            DocxLayout::Paragraph => {
+                paragraph_start(w, None)?;
+                for (i, v) in verses.iter().enumerate() {
+                    if i > 0 {
+                        run(w, " ", RunStyle::default())?;
+                    }
+                    verse_runs(w, v, self.options.highlight_red_letters)?;
+                }
+                end(w, "w:p")?;
+            }
+            DocxLayout::Interlinear => {
+                interlinear_table(w, verses, self.options.highlight_red_letters)?
+            }
+        }
+
+        if let (Some(note), DocxNotes::Paragraph) = (note, self.options.notes) {
+            for (i, line) in note.lines().filter(|l| !l.trim().is_empty()).enumerate() {
+                paragraph_start(w, Some("PassageNote"))?;
+                if i == 0 {
+                    let bold = RunStyle {
+                        bold: true,
+                        ..Default::default()
+                    };
+                    run(w, "Note: ", bold)?;
+                }
+                run(w, line.trim(), RunStyle::default())?;
+                end(w, "w:p")?;
+            }
+        }
+
+        self.counts.passages += 1;
+        self.counts.verses += verses.len();
+        self.counts.notes += usize::from(note.is_some());
+        Ok(())
+    }
+
+    /// The package parts.
+    fn finish(self) -> io::Result<Parts> {
+        let mut document = Writer::new(Vec::new());
+        let w = &mut document;
+        w.write_event(Event::Decl(BytesDecl::new(
+            "1.0",
+            Some("UTF-8"),
+            Some("yes"),
+        )))?;
+        start(
+            w,
+            "w:document",
+            &[("xmlns:w", W_NAMESPACE), ("xmlns:r", R_NAMESPACE)],
+        )?;
+        start(w, "w:body", &[])?;
+        w.get_mut().extend_from_slice(&self.body.into_inner());
+        for (i, citation) in self.citations.iter().enumerate() {
+            paragraph_start(w, Some("Citation"))?;
+            if i == 0 {
+                // Rule the citations off from the last passage
+                start(w, "w:pPr", &[])?;
+                empty(w, "w:spacing", &[("w:before", "480")])?;
+                end(w, "w:pPr")?;
+            }
+            run(w, citation, RunStyle::default())?;
+            end(w, "w:p")?;
+        }
+        if !self.attribution.is_empty() {
+            paragraph_start(w, Some("Citation"))?;
+            start(w, "w:pPr", &[])?;
+            empty(w, "w:spacing", &[("w:before", "480")])?;
+            end(w, "w:pPr")?;
+            let bold = RunStyle {
+                bold: true,
+                ..Default::default()
+            };
+            run(w, "Sources and licenses", bold)?;
+            end(w, "w:p")?;
+        }
+        for line in &self.attribution {
+            paragraph_start(w, Some("Citation"))?;
+            run(w, line, RunStyle::default())?;
+            end(w, "w:p")?;
+        }
+        // US Letter with 1" margins
+        start(w, "w:sectPr", &[])?;
+        empty(w, "w:pgSz", &[("w:w", "12240"), ("w:h", "15840")])?;
+        let margins = [
+            ("w:top", "1440"),
+            ("w:right", "1440"),
+            ("w:bottom", "1440"),
+            ("w:left", "1440"),
+            ("w:header", "720"),
+            ("w:footer", "720"),
+            ("w:gutter", "0"),
+        ];
+        empty(w, "w:pgMar", &margins)?;
+        end(w, "w:sectPr")?;
+        end(w, "w:body")?;
+        end(w, "w:document")?;
+
+        let mut footnotes = Writer::new(Vec::new());
+        let w = &mut footnotes;
+        w.write_event(Event::Decl(BytesDecl::new(
+            "1.0",
+            Some("UTF-8"),
+            Some("yes"),
+        )))?;
+        start(w, "w:footnotes", &[("xmlns:w", W_NAMESPACE)])?;
+        for (id, kind, mark) in [
+            ("-1", "separator", "w:separator"),
+            ("0", "continuationSeparator", "w:continuationSeparator"),
+        ] {
+            start(w, "w:footnote", &[("w:type", kind), ("w:id", id)])?;
+            start(w, "w:p", &[])?;
+            start(w, "w:r", &[])?;
+            empty(w, mark, &[])?;
+            end(w, "w:r")?;
+            end(w, "w:p")?;
+            end(w, "w:footnote")?;
+        }
+        w.get_mut().extend_from_slice(&self.footnotes.into_inner());
+        end(w, "w:footnotes")?;
+
+        let text = |w: Xml| String::from_utf8_lossy(&w.into_inner()).into_owned();
+        Ok(vec![
+            ("[Content_Types].xml", CONTENT_TYPES.to_string()),
+            ("_rels/.rels", PACKAGE_RELS.to_string()),
+            ("word/_rels/document.xml.rels", DOCUMENT_RELS.to_string()),
+            ("word/styles.xml", STYLES.to_string()),
+            ("word/document.xml", text(document)),
+            ("word/footnotes.xml", text(footnotes)),
+        ])
+    }
+}
+
+fn footnote(w: &mut Xml, id: &str, note: &str) -> io::Result<()> {
+    start(w, "w:footnote", &[("w:id", id)])?;
+    for (i, line) in note.lines().filter(|l| !l.trim().is_empty()).enumerate() {
+        paragraph_start(w, Some("FootnoteText"))?;
+        if i == 0 {
+            start(w, "w:r", &[])?;
+            start(w, "w:rPr", &[])?;
+            empty(w, "w:rStyle", &[("w:val", "FootnoteReference")])?;
+            end(w, "w:rPr")?;
+            empty(w, "w:footnoteRef", &[])?;
+            end(w, "w:r")?;
+            run(w, " ", RunStyle::default())?;
+        }
+        run(w, line.trim(), RunStyle::default())?;
+        end(w, "w:p")?;
+    }
+    end(w, "w:footnote")
+}
+
+/// Greek | translation table. The header row repeats on each page and rows
+/// never split, so long passages break between verses.
+fn interlinear_table(w: &mut Xml, verses: &[RenderVerse], red_letter: bool) -> io::Result<()> {
+    start(w, "w:tbl", &[])?;
+    start(w, "w:tblPr", &[])?;
+    empty(w, "w:tblStyle", &[("w:val", "TableGrid")])?;
+    empty(w, "w:tblW", &[("w:w", "5000"), ("w:type", "pct")])?;
+    end(w, "w:tblPr")?;
+    start(w, "w:tblGrid", &[])?;
+    empty(w, "w:gridCol", &[("w:w", "4680")])?;
+    empty(w, "w:gridCol", &[("w:w", "4680")])?;
+    end(w, "w:tblGrid")?;
+
+    let cell = |w: &mut Xml, body: &dyn Fn(&mut Xml) -> io::Result<()>| -> io::Result<()> {
+        start(w, "w:tc", &[])?;
+        start(w, "w:tcPr", &[])?;
+        empty(w, "w:tcW", &[("w:w", "2500"), ("w:type", "pct")])?;
+        end(w, "w:tcPr")?;
+        paragraph_start(w, None)?;
+        body(w)?;
+        end(w, "w:p")?;
+        end(w, "w:tc")
+    };
+    let bold = RunStyle {
+        bold: true,
+        ..Default::default()
+    };
+
+    start(w, "w:tr", &[])?;
+    start(w, "w:trPr", &[])?;
+    empty(w, "w:cantSplit", &[])?;
+    empty(w, "w:tblHeader", &[])?;
+    end(w, "w:trPr")?;
+    cell(w, &|w| run(w, "Greek", bold))?;
+    cell(w, &|w| run(w, "Translation", bold))?;
+    end(w, "w:tr")?;
+
+    for v in verses {
+        start(w, "w:tr", &[])?;
+        start(w, "w:trPr", &[])?;
+        empty(w, "w:cantSplit", &[])?;
+        end(w, "w:trPr")?;
+        cell(w, &|w| {
+            let greek = RunStyle {
+                greek: true,
+                ..Default::default()
+            };
+            run(w, &one_line(v.greek), greek)
+        })?;
+        cell(w, &|w| verse_runs(w, v, red_letter))?;
+        end(w, "w:tr")?;
+    }
+    end(w, "w:tbl")?;
+    // Word needs a paragraph between a table and whatever follows
+    empty(w, "w:p", &[])
+}
+
+/// Check that a part is well-formed XML.
+fn check_xml(name: &str, xml: &str) -> Result<(), String> {
+    let mut reader = Reader::from_str(xml);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Eof) => return Ok(()),
+            Ok(_) => {}
+            Err(e) => {
+                return Err(format!(
+                    "{} is malformed at byte {}: {}",
+                    name,
+                    reader.error_position(),
+                    e
+                ))
+            }
+        }
+    }
+}
+
+/// Zip the parts into a .docx package.
+fn package(parts: &[(&str, String)]) -> Result<Vec<u8>, String> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (name, contents) in parts {
+        zip.start_file(*name, options).map_err(|e| e.to_string())?;
+        zip.write_all(contents.as_bytes())
+            .map_err(|e| e.to_string())?;
+    }
+    let cursor = zip.finish().map_err(|e| e.to_string())?;
+    Ok(cursor.into_inner())
+}
+
+/// Render passages into the package parts, skipping ones that fail to load.
+/// The written passages are credited in `attribution`, when given.
+fn build_docx(
+    passage_ids: &[String],
+    options: &DocxOptions,
+    load: &dyn Fn(&str) -> Result<CachedPassage, String>,
+    skipped: &mut Vec<PassageExportFailure>,
+    mut attribution: Option<Attribution>,
+) -> Result<(DocxCounts, Parts), String> {
+    let style: Option<&CitationStyle> = options
+        .citation_style
+        .as_deref()
+        .map(citation_style)
+        .transpose()?;
+    let mut builder = DocxBuilder::new(options.clone());
+    for id in passage_ids {
+        let result = load(id).and_then(|passage| {
+            let verses = passage_verses(&passage)?;
+            builder
+                .passage(&passage, &verses)
+                .map_err(|e| e.to_string())?;
+            if let Some(style) = style {
+                builder.citations.push(passage_citation(style, &passage));
+            }
+            if let Some(attribution) = &mut attribution {
+                attribution.add_passage(&passage);
+            }
+            Ok(())
+        });
+        if let Err(error) = result {
+            skipped.push(PassageExportFailure {
+                passage_id: id.clone(),
+                error,
+            });
+        }
+    }
+    if builder.counts.passages == 0 {
+        return Err("No cached passages to export".to_string());
+    }
+
+    builder.attribution = attribution.map(|a| a.lines()).unwrap_or_default();
+    let counts = builder.counts;
+    let parts = builder.finish().map_err(|e| e.to_string())?;
+    for (name, xml) in &parts {
+        check_xml(name, xml)?;
+    }
+    Ok((counts, parts))
+}
+
+/// Export cached passages as a Word document.
+///
+/// `dest_path` should come from `choose_save_path` with kind `docx`.
+#[tauri::command]
+pub fn export_docx(
+    app: AppHandle,
+    passage_ids: Vec<String>,
+    dest_path: String,
+    options: Option<DocxOptions>,
+) -> Result<DocxExportReport, AppError> {
+    let options = options.unwrap_or_default();
+    let passage_ids = tags::passage_ids_for_export(&app, passage_ids, options.tag.as_deref())?;
+    let path = validate_export_path(&app, ExportKind::Docx, &PathBuf::from(dest_path))?;
+
+    let mut skipped = Vec::new();
+    let load = |id: &str| load_passage(&app, id);
+    let attribution = licensing::export_attribution(options.include_attribution);
+    let (counts, parts) = build_docx(&passage_ids, &options, &load, &mut skipped, attribution)?;
+    storage::write_atomic(&path, &package(&parts)?)?;
+
+    Ok(DocxExportReport {
+        path: path.to_string_lossy().into_owned(),
+        passages_written: counts.passages,
+        verses_written: counts.verses,
+        notes_written: counts.notes,
+        skipped,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testsupport::passage::PassageBuilder;
+    use std::io::Read;
+
+    fn load(id: &str) -> Result<CachedPassage, String> {
+        if id == "corrupt" {
+            return Err("corrupt.json: expected value at line 1 column 1".to_string());
+        }
+        Ok(PassageBuilder::new()
+            .id(id)
+            .reference("John 3:16-17")
+            .verses(
+                &[
+                    ("John.3.16", "For God so loved <the> world & more"),
+                    ("John.3.17", "For God so loved <the> world & more"),
+                ],
+                "Οὕτως γὰρ ἠγάπησεν",
+            )
+            .note(Some("First line\nSecond line"))
+            .red_letters(&["John.3.17"])
+            .build())
+    }
+
+    #[test]
+    fn test_build_docx() {
+        let ids = ["a", "corrupt", "b"].map(String::from);
+        for layout in [DocxLayout::Paragraph, DocxLayout::Interlinear] {
+            for notes in [DocxNotes::Omit, DocxNotes::Paragraph, DocxNotes::Footnote] {
+                let options = DocxOptions {
+                    layout,
+                    highlight_red_letters: true,
+                    notes,
+                    citation_style: None,
+                    tag: None,
+                    include_attribution: false,
+                };
+                let mut skipped = Vec::new();
+                let (counts, parts) =
+                    build_docx(&ids, &options, &load, &mut skipped, None).unwrap();
+                assert_eq!(skipped.len(), 1);
+                assert_eq!(skipped[0].passage_id, "corrupt");
+                assert_eq!((counts.passages, counts.verses), (2, 4));
+                assert_eq!(counts.notes, if notes == DocxNotes::Omit { 0 } else { 2 });
+
+                let (_, document) = parts
+                    .iter()
+                    .find(|(n, _)| *n == "word/document.xml")
+                    .unwrap();
+                let (_, footnotes) = parts
+                    .iter()
+                    .find(|(n, _)| *n == "word/footnotes.xml")
+                    .unwrap();
+                assert!(document.contains("For God so loved &lt;the&gt; world &amp; more"));
+                assert!(document.contains(&format!(r#"<w:color w:val="{}"/>"#, RED)));
+                assert_eq!(
+                    document.contains("<w:tblHeader/>"),
+                    layout == DocxLayout::Interlinear
+                );
+                assert_eq!(
+                    document.contains(GREEK_FONT),
+                    layout == DocxLayout::Interlinear
+                );
+                assert_eq!(
+                    document.contains("PassageNote"),
+                    notes == DocxNotes::Paragraph
+                );
+                assert_eq!(
+                    document.contains(r#"<w:footnoteReference w:id="2"/>"#),
+                    notes == DocxNotes::Footnote
+                );
+                assert_eq!(
+                    footnotes.contains("Second line"),
+                    notes == DocxNotes::Footnote
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_citation_footer() {
+        let ids = ["a".to_string(), "b".to_string()];
+        let options = DocxOptions {
+            citation_style: Some("sbl".to_string()),
+            ..Default::default()
+        };
+        let (_, parts) = build_docx(&ids, &options, &load, &mut Vec::new(), None).unwrap();
+        let (_, document) = parts
+            .iter()
+            .find(|(n, _)| *n == "word/document.xml")
+            .unwrap();
+        assert_eq!(
+            document.matches(r#"<w:pStyle w:val="Citation"/>"#).count(),
+            2
+        );
+        assert!(document.contains("John 3:16–17 Red Letters"));
+
+        let options = DocxOptions {
+            citation_style: Some("apa".to_string()),
+            ..Default::default()
+        };
+        assert!(build_docx(&ids, &options, &load, &mut Vec::new(), None).is_err());
+    }
+
+    #[test]
+    fn test_package_is_a_readable_zip() {
+        let mut skipped = Vec::new();
+        let ids = ["a".to_string()];
+        let attribution = Some(Attribution::new(Vec::new()));
+        let (_, parts) = build_docx(
+            &ids,
+            &DocxOptions::default(),
+            &load,
+            &mut skipped,
+            attribution,
+        )
+        .unwrap();
+        let (_, document) = parts
+            .iter()
+            .find(|(n, _)| *n == "word/document.xml")
+            .unwrap();
+        assert!(document.contains("Sources and licenses"));
+        let bytes = package(&parts).unwrap();
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+        let mut content_types = String::new();
+        archive
+            .by_name("[Content_Types].xml")
+            .unwrap()
+            .read_to_string(&mut content_types)
+            .unwrap();
+        assert!(content_types.contains("/word/document.xml"));
+        assert_eq!(archive.len(), parts.len());
+    }
+
+    #[test]
+    fn test_nothing_to_export() {
+        let ids = ["corrupt".to_string()];
+        assert!(build_docx(&ids, &DocxOptions::default(), &load, &mut Vec::new(), None).is_err());
+    }
+}
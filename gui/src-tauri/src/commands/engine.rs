@@ -4,9 +4,18 @@
 //! including safe mode restart.
 
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+use std::ffi::{OsStr, OsString};
+use sysinfo::{Pid, Process, ProcessRefreshKind, ProcessesToUpdate, Signal, System, UpdateKind};
 use thiserror::Error;
 
+use super::auth::AuthError;
+use super::errors::{command_error, CommandError};
+use super::http::HttpError;
+use super::i18n::Localize;
+use super::logging;
+use super::process;
+use super::telemetry::{self, TelemetryEvent};
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct EngineProcessInfo {
     pub running: bool,
@@ -14,6 +23,16 @@ pub struct EngineProcessInfo {
     pub port: u16,
 }
 
+/// A flag accepted by `redletters engine start`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EngineOption {
+    pub flag: String,
+    pub short: Option<String>,
+    pub default_value: Option<String>,
+    pub description: String,
+    pub required: bool,
+}
+
 #[derive(Debug, Error)]
 pub enum EngineError {
     #[error("Engine not running")]
@@ -22,17 +41,135 @@ pub enum EngineError {
     StartFailed(String),
     #[error("Failed to stop engine: {0}")]
     StopFailed(String),
+    #[error(transparent)]
+    Http(#[from] HttpError),
+    #[error(transparent)]
+    Auth(#[from] AuthError),
+    #[error("Storage error: {0}")]
+    Storage(String),
+    #[error("Invalid rate limit configuration: {0}")]
+    RateLimitConfigError(String),
+    #[error("Engine config schema unavailable")]
+    SchemaUnavailable,
+    #[error("Unknown corpus: {0}")]
+    CorpusNotFound(String),
+    #[error("Corpus {0} has no download available")]
+    CorpusNotDownloadable(String),
+    #[error("Checksum mismatch for corpus {0}; the download was discarded")]
+    CorpusChecksumMismatch(String),
+    #[error("Corpus {0} is the active source and cannot be removed")]
+    CorpusActive(String),
+    #[error("Extension failed to load: {0}")]
+    ExtensionLoadError(String),
+    #[error("Offline lexicon is not installed")]
+    LexiconUnavailable,
+    #[error("Invalid Strong's number {0}: expected G1 to G5624")]
+    InvalidStrongsNumber(String),
+    #[error("Invalid Strong's number {0}: expected H1 to H8674")]
+    InvalidHebrewStrongsNumber(String),
+    #[error("gRPC health check failed: {0}")]
+    GrpcError(String),
+    #[error("Invalid CORS configuration: {0}")]
+    CorsConfigError(String),
+    #[error("Invalid search query: {0}")]
+    InvalidSearchQuery(String),
+    #[error("Invalid thread limit: {0}")]
+    InvalidThreadLimit(String),
+    #[error("Invalid engine configuration: {0}")]
+    InvalidConfig(String),
+    #[error("Invalid reference: {0}")]
+    InvalidReference(String),
+    #[error("Commentary not found: {0}")]
+    CommentaryNotFound(String),
+    #[error("Invalid versions: {0}")]
+    InvalidVersions(String),
+    #[error("Highlighting failed: {0}")]
+    HighlightError(String),
+    #[error("Passage not found: {0}")]
+    PassageNotFound(String),
+    #[error("Cannot build the flashcard deck: {0}")]
+    FlashcardDeckError(String),
+}
+
+impl Localize for EngineError {
+    fn code(&self) -> &'static str {
+        match self {
+            EngineError::NotRunning => "engine-not-running",
+            EngineError::StartFailed(_) => "engine-start-failed",
+            EngineError::StopFailed(_) => "engine-stop-failed",
+            EngineError::Http(e) => e.code(),
+            EngineError::Auth(e) => e.code(),
+            EngineError::Storage(_) => "engine-storage-error",
+            EngineError::RateLimitConfigError(_) => "engine-rate-limit-config",
+            EngineError::SchemaUnavailable => "engine-schema-unavailable",
+            EngineError::CorpusNotFound(_) => "engine-corpus-not-found",
+            EngineError::CorpusNotDownloadable(_) => "engine-corpus-not-downloadable",
+            EngineError::CorpusChecksumMismatch(_) => "engine-corpus-checksum-mismatch",
+            EngineError::CorpusActive(_) => "engine-corpus-active",
+            EngineError::ExtensionLoadError(_) => "engine-extension-load-error",
+            EngineError::LexiconUnavailable => "engine-lexicon-unavailable",
+            EngineError::InvalidStrongsNumber(_) => "engine-invalid-strongs",
+            EngineError::InvalidHebrewStrongsNumber(_) => "engine-invalid-hebrew-strongs",
+            EngineError::GrpcError(_) => "engine-grpc-error",
+            EngineError::CorsConfigError(_) => "engine-cors-config",
+            EngineError::InvalidSearchQuery(_) => "engine-invalid-search",
+            EngineError::InvalidThreadLimit(_) => "engine-thread-limit",
+            EngineError::InvalidConfig(_) => "engine-invalid-config",
+            EngineError::InvalidReference(_) => "engine-invalid-reference",
+            EngineError::CommentaryNotFound(_) => "engine-commentary-not-found",
+            EngineError::InvalidVersions(_) => "engine-invalid-versions",
+            EngineError::HighlightError(_) => "engine-highlight-error",
+            EngineError::PassageNotFound(_) => "engine-passage-not-found",
+            EngineError::FlashcardDeckError(_) => "engine-flashcard-deck-error",
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            EngineError::StartFailed(detail)
+            | EngineError::StopFailed(detail)
+            | EngineError::Storage(detail)
+            | EngineError::RateLimitConfigError(detail)
+            | EngineError::ExtensionLoadError(detail)
+            | EngineError::GrpcError(detail)
+            | EngineError::CorsConfigError(detail)
+            | EngineError::InvalidSearchQuery(detail)
+            | EngineError::InvalidThreadLimit(detail)
+            | EngineError::InvalidConfig(detail)
+            | EngineError::InvalidReference(detail)
+            | EngineError::InvalidVersions(detail)
+            | EngineError::HighlightError(detail)
+            | EngineError::FlashcardDeckError(detail) => vec![("detail", detail.clone())],
+            EngineError::CorpusNotFound(id)
+            | EngineError::CorpusNotDownloadable(id)
+            | EngineError::CorpusChecksumMismatch(id)
+            | EngineError::CorpusActive(id)
+            | EngineError::CommentaryNotFound(id) => vec![("id", id.clone())],
+            EngineError::PassageNotFound(reference) => vec![("reference", reference.clone())],
+            EngineError::InvalidStrongsNumber(number)
+            | EngineError::InvalidHebrewStrongsNumber(number) => vec![("number", number.clone())],
+            EngineError::Http(e) => e.params(),
+            EngineError::Auth(e) => e.params(),
+            EngineError::NotRunning
+            | EngineError::SchemaUnavailable
+            | EngineError::LexiconUnavailable => Vec::new(),
+        }
+    }
 }
 
-impl Serialize for EngineError {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        serializer.serialize_str(&self.to_string())
+impl CommandError for EngineError {
+    fn retryable(&self) -> bool {
+        match self {
+            EngineError::NotRunning | EngineError::GrpcError(_) => true,
+            EngineError::Http(e) => e.retryable(),
+            EngineError::Auth(e) => e.retryable(),
+            _ => false,
+        }
     }
 }
 
+command_error!(EngineError);
+
 /// Check if engine process is running by trying to connect to the port.
 #[tauri::command]
 pub fn check_engine_running(port: u16) -> EngineProcessInfo {
@@ -51,15 +188,30 @@ pub fn check_engine_running(port: u16) -> EngineProcessInfo {
 /// Note: This spawns a new process. The GUI doesn't manage the engine lifecycle
 /// directly - this is just a convenience for restarting in safe mode.
 #[tauri::command]
+#[tracing::instrument(fields(request_id = %logging::request_id()))]
 pub fn start_engine_safe_mode(port: u16) -> Result<(), EngineError> {
     // Try to start using the redletters CLI
-    let result = Command::new("redletters")
-        .args(["engine", "start", "--safe-mode", "--port", &port.to_string()])
+    let result = process::command("redletters")
+        .args([
+            "engine",
+            "start",
+            "--safe-mode",
+            "--port",
+            &port.to_string(),
+        ])
         .spawn();
 
     match result {
-        Ok(_child) => Ok(()),
-        Err(e) => Err(EngineError::StartFailed(e.to_string())),
+        Ok(child) => {
+            tracing::info!(pid = child.id(), "Started the engine in safe mode");
+            telemetry::record(TelemetryEvent::EngineStart { success: true });
+            Ok(())
+        }
+        Err(e) => {
+            tracing::warn!("Could not start the engine: {}", e);
+            telemetry::record(TelemetryEvent::EngineStart { success: false });
+            Err(EngineError::StartFailed(e.to_string()))
+        }
     }
 }
 
@@ -71,3 +223,517 @@ pub fn start_engine_safe_mode(port: u16) -> Result<(), EngineError> {
 pub fn get_engine_command_hint() -> String {
     "Use API endpoint POST /v1/engine/shutdown to request graceful shutdown".to_string()
 }
+
+/// Executable name of the engine
+const ENGINE_BINARY: &str = "redletters";
+
+/// Signal sent by `kill_all_engine_processes`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KillSignal {
+    /// Ask the process to exit (SIGTERM); not available on Windows
+    Term,
+    /// Force it to exit (SIGKILL, or `taskkill /F` on Windows)
+    Kill,
+}
+
+/// Whether a process name or path (possibly with `.exe`) is `binary_name`.
+fn is_binary(executable: &OsStr, binary_name: &str) -> bool {
+    let executable = executable.to_string_lossy();
+    let file_name = executable.rsplit(['/', '\\']).next().unwrap_or(&executable);
+    let strip = |name: &str| {
+        let name = name.to_ascii_lowercase();
+        name.strip_suffix(".exe")
+            .map(str::to_string)
+            .unwrap_or(name)
+    };
+    strip(file_name) == strip(binary_name)
+}
+
+/// Whether a process runs `binary_name`: by its name, its argv[0], or,
+/// when it is a Python interpreter, the script in argv[1]. The engine is a
+/// Python console script, so on Linux and macOS it runs as `python3`.
+fn runs_binary(name: &OsStr, cmd: &[OsString], binary_name: &str) -> bool {
+    let is_python = |arg: &OsStr| {
+        let arg = arg.to_string_lossy();
+        let file_name = arg.rsplit(['/', '\\']).next().unwrap_or(&arg);
+        file_name.to_ascii_lowercase().starts_with("python")
+    };
+    is_binary(name, binary_name)
+        || match cmd {
+            [arg0, script, ..] if is_python(arg0) => {
+                is_binary(arg0, binary_name) || is_binary(script, binary_name)
+            }
+            [arg0, ..] => is_binary(arg0, binary_name),
+            [] => false,
+        }
+}
+
+/// Running processes, with their names and command lines.
+fn process_table() -> System {
+    let mut system = System::new();
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::All,
+        true,
+        ProcessRefreshKind::nothing().with_cmd(UpdateKind::Always),
+    );
+    system
+}
+
+/// Processes running `binary_name`, other than this one.
+fn engine_processes<'a>(
+    system: &'a System,
+    binary_name: &'a str,
+) -> impl Iterator<Item = &'a Process> + 'a {
+    let own_pid = Pid::from_u32(std::process::id());
+    system.processes().values().filter(move |process| {
+        process.pid() != own_pid && runs_binary(process.name(), process.cmd(), binary_name)
+    })
+}
+
+fn send_signal(process: &Process, signal: KillSignal) -> Result<(), String> {
+    let signal = match signal {
+        KillSignal::Term => Signal::Term,
+        KillSignal::Kill => Signal::Kill,
+    };
+    match process.kill_with(signal) {
+        Some(true) => Ok(()),
+        Some(false) => Err(format!("could not signal process {}", process.pid())),
+        None => Err(format!("{} is not supported on this platform", signal)),
+    }
+}
+
+/// Count running engine processes, to spot leftovers from earlier
+/// sessions. `binary_name` defaults to `redletters`.
+#[tauri::command]
+pub fn get_process_count(binary_name: Option<String>) -> u32 {
+    let binary_name = binary_name.as_deref().unwrap_or(ENGINE_BINARY);
+    engine_processes(&process_table(), binary_name).count() as u32
+}
+
+/// Signal every process running `binary_name` and return how many were
+/// signaled.
+///
+/// Unlike `get_process_count` there is no default name, so killing every
+/// `redletters` process always has to be asked for explicitly.
+#[tauri::command]
+#[tracing::instrument(fields(request_id = %logging::request_id()))]
+pub fn kill_all_engine_processes(
+    binary_name: Option<String>,
+    signal: KillSignal,
+) -> Result<u32, EngineError> {
+    let binary_name = binary_name
+        .filter(|name| !name.trim().is_empty())
+        .ok_or_else(|| EngineError::StopFailed("a binary name is required".to_string()))?;
+    let system = process_table();
+
+    let mut signaled = 0;
+    let mut last_error = None;
+    for process in engine_processes(&system, &binary_name) {
+        match send_signal(process, signal) {
+            Ok(()) => signaled += 1,
+            Err(e) => last_error = Some(e),
+        }
+    }
+    tracing::info!(signaled, "Signaled engine processes");
+    match last_error {
+        Some(e) if signaled == 0 => Err(EngineError::StopFailed(e)),
+        _ => Ok(signaled),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ThreadCountInfo {
+    pub thread_count: u32,
+    /// Soft limit on the processes and threads the engine's user may run
+    /// (Linux `RLIMIT_NPROC`), when there is one
+    pub max_threads: Option<u32>,
+    /// Hardware threads available to the GUI
+    pub cpu_threads: u32,
+}
+
+/// The `Threads:` field of `/proc/{pid}/status`.
+#[cfg(any(target_os = "linux", test))]
+fn parse_status_threads(status: &str) -> Option<u32> {
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("Threads:"))
+        .and_then(|count| count.trim().parse().ok())
+}
+
+/// The soft "Max processes" limit from `/proc/{pid}/limits`, or None when
+/// unlimited.
+#[cfg(any(target_os = "linux", test))]
+fn parse_limits_max_processes(limits: &str) -> Option<u32> {
+    let line = limits
+        .lines()
+        .find(|line| line.starts_with("Max processes"))?;
+    line["Max processes".len()..]
+        .split_whitespace()
+        .next()
+        .and_then(|soft| soft.parse().ok())
+}
+
+/// Threads of a process and its thread limit, or None if it isn't running.
+#[cfg(target_os = "linux")]
+fn process_threads(pid: u32) -> Option<(u32, Option<u32>)> {
+    let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+    let limits = std::fs::read_to_string(format!("/proc/{}/limits", pid)).unwrap_or_default();
+    Some((
+        parse_status_threads(&status)?,
+        parse_limits_max_processes(&limits),
+    ))
+}
+
+#[cfg(target_os = "macos")]
+fn process_threads(pid: u32) -> Option<(u32, Option<u32>)> {
+    // SAFETY: the ports and the thread list the kernel hands out are
+    // released before returning; `info` is plain data sized for the call.
+    unsafe {
+        #[allow(deprecated)]
+        let own_task = libc::mach_task_self_;
+        let mut task: libc::mach_port_t = 0;
+        if libc::task_for_pid(own_task, pid as libc::pid_t, &mut task) == libc::KERN_SUCCESS {
+            let mut threads: libc::thread_act_array_t = std::ptr::null_mut();
+            let mut count: libc::mach_msg_type_number_t = 0;
+            let result = libc::task_threads(task, &mut threads, &mut count);
+            if result == libc::KERN_SUCCESS {
+                for i in 0..count as usize {
+                    libc::mach_port_deallocate(own_task, *threads.add(i));
+                }
+                libc::vm_deallocate(
+                    own_task,
+                    threads as libc::vm_address_t,
+                    count as libc::vm_size_t * std::mem::size_of::<libc::thread_act_t>(),
+                );
+            }
+            libc::mach_port_deallocate(own_task, task);
+            if result == libc::KERN_SUCCESS {
+                return Some((count, None));
+            }
+        }
+
+        // task_for_pid needs extra privileges; proc_pidinfo works for
+        // processes of the same user
+        let mut info: libc::proc_taskinfo = std::mem::zeroed();
+        let size = std::mem::size_of::<libc::proc_taskinfo>() as libc::c_int;
+        let written = libc::proc_pidinfo(
+            pid as libc::c_int,
+            libc::PROC_PIDTASKINFO,
+            0,
+            &mut info as *mut libc::proc_taskinfo as *mut libc::c_void,
+            size,
+        );
+        (written == size).then_some((info.pti_threadnum as u32, None))
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn process_threads(pid: u32) -> Option<(u32, Option<u32>)> {
+    use windows_sys::Win32::Foundation::{CloseHandle, INVALID_HANDLE_VALUE};
+    use windows_sys::Win32::System::Diagnostics::ToolHelp::{
+        CreateToolhelp32Snapshot, Thread32First, Thread32Next, TH32CS_SNAPTHREAD, THREADENTRY32,
+    };
+
+    // SAFETY: the snapshot handle is closed before returning and `entry`
+    // carries its size as the API requires.
+    unsafe {
+        let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD, 0);
+        if snapshot == INVALID_HANDLE_VALUE {
+            return None;
+        }
+        let mut entry: THREADENTRY32 = std::mem::zeroed();
+        entry.dwSize = std::mem::size_of::<THREADENTRY32>() as u32;
+        let mut count = 0;
+        let mut more = Thread32First(snapshot, &mut entry) != 0;
+        while more {
+            if entry.th32OwnerProcessID == pid {
+                count += 1;
+            }
+            more = Thread32Next(snapshot, &mut entry) != 0;
+        }
+        CloseHandle(snapshot);
+        // Every running process has at least one thread
+        (count > 0).then_some((count, None))
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn process_threads(_pid: u32) -> Option<(u32, Option<u32>)> {
+    None
+}
+
+/// Count the threads of the engine process, to see whether it might be
+/// starving the GUI.
+#[tauri::command]
+pub fn get_engine_thread_count(pid: u32) -> Result<ThreadCountInfo, EngineError> {
+    let (thread_count, max_threads) = process_threads(pid).ok_or(EngineError::NotRunning)?;
+    Ok(ThreadCountInfo {
+        thread_count,
+        max_threads,
+        cpu_threads: std::thread::available_parallelism().map_or(1, |n| n.get() as u32),
+    })
+}
+
+/// Core `engine start` options, used when `--help` can't be run or parsed.
+fn fallback_startup_options() -> Vec<EngineOption> {
+    let option = |flag: &str, default_value: Option<&str>, description: &str| EngineOption {
+        flag: flag.to_string(),
+        short: None,
+        default_value: default_value.map(str::to_string),
+        description: description.to_string(),
+        required: false,
+    };
+    vec![
+        option("--host", Some("127.0.0.1"), "Bind host (127.0.0.1 only)"),
+        option("--port", Some("47200"), "Bind port"),
+        option("--safe-mode", None, "Start in safe mode (jobs disabled)"),
+        option("--log-level", Some("info"), "Log level"),
+    ]
+}
+
+/// Split a trailing Click annotation like "[default: 47200; required]" off
+/// a description.
+fn split_annotations(description: &str) -> (String, Option<String>, bool) {
+    let description = description.trim();
+    let Some(start) = description
+        .rfind('[')
+        .filter(|_| description.ends_with(']'))
+    else {
+        return (description.to_string(), None, false);
+    };
+
+    let mut default_value = None;
+    let mut required = false;
+    for part in description[start + 1..description.len() - 1].split(';') {
+        let part = part.trim();
+        if let Some(value) = part.strip_prefix("default:") {
+            default_value = Some(value.trim().to_string());
+        } else if part == "required" {
+            required = true;
+        }
+    }
+    (
+        description[..start].trim_end().to_string(),
+        default_value,
+        required,
+    )
+}
+
+/// Parse the "Options:" section of Click-style `--help` output.
+fn parse_help_options(help: &str) -> Vec<EngineOption> {
+    let mut options: Vec<EngineOption> = Vec::new();
+    let mut in_options = false;
+
+    for line in help.lines() {
+        if line.trim_end() == "Options:" {
+            in_options = true;
+            continue;
+        }
+        if !in_options || line.trim().is_empty() {
+            continue;
+        }
+        // A new unindented heading ends the section
+        if !line.starts_with(' ') {
+            break;
+        }
+
+        let trimmed = line.trim_start();
+        if !trimmed.starts_with('-') {
+            // Wrapped description from the previous option
+            if let Some(last) = options.last_mut() {
+                last.description.push(' ');
+                last.description.push_str(trimmed);
+            }
+            continue;
+        }
+
+        // Flags and metavar are separated from the description by 2+ spaces
+        let (spec, description) = match trimmed.find("  ") {
+            Some(i) => (&trimmed[..i], trimmed[i..].trim()),
+            None => (trimmed, ""),
+        };
+        let mut flag = None;
+        let mut short = None;
+        for name in spec.split([',', '/']).map(str::trim) {
+            let name = name.split_whitespace().next().unwrap_or_default();
+            if name.starts_with("--") {
+                flag.get_or_insert_with(|| name.to_string());
+            } else if name.starts_with('-') {
+                short.get_or_insert_with(|| name.to_string());
+            }
+        }
+        let Some(flag) = flag else {
+            continue;
+        };
+        options.push(EngineOption {
+            flag,
+            short,
+            default_value: None,
+            description: description.to_string(),
+            required: false,
+        });
+    }
+
+    options
+        .into_iter()
+        .filter(|o| o.flag != "--help")
+        .map(|mut o| {
+            let (description, default_value, required) = split_annotations(&o.description);
+            o.description = description;
+            o.default_value = default_value;
+            o.required = required;
+            o
+        })
+        .collect()
+}
+
+/// List the flags `redletters engine start` accepts, for the start dialog.
+///
+/// The flags live on the `start` subcommand, so that is the help that gets
+/// parsed. Falls back to the core options if the CLI can't be run or its
+/// help output can't be parsed.
+#[tauri::command]
+pub async fn get_engine_startup_options() -> Result<Vec<EngineOption>, EngineError> {
+    let output = process::command("redletters")
+        .args(["engine", "start", "--help"])
+        .output();
+
+    let options = match output {
+        Ok(output) if output.status.success() => {
+            parse_help_options(&String::from_utf8_lossy(&output.stdout))
+        }
+        Ok(output) => {
+            tracing::warn!(
+                "redletters engine start --help exited with {}",
+                output.status
+            );
+            Vec::new()
+        }
+        Err(e) => {
+            tracing::warn!("Could not run redletters: {}", e);
+            Vec::new()
+        }
+    };
+
+    if options.is_empty() {
+        return Ok(fallback_startup_options());
+    }
+    Ok(options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_click_help() {
+        let help = "\
+Usage: redletters engine start [OPTIONS]
+
+  Start the engine server.
+
+Options:
+  --host TEXT          Bind host (127.0.0.1 only)  [default: 127.0.0.1]
+  -p, --port INTEGER   Bind port  [default: 47200]
+  --safe-mode          Start in safe mode (jobs disabled)
+  --log-level TEXT     Log level used for the engine and its workers, written
+                       to the log directory  [required]
+  --help               Show this message and exit.
+";
+        let options = parse_help_options(help);
+        assert_eq!(options.len(), 4);
+        assert_eq!(options[0].default_value.as_deref(), Some("127.0.0.1"));
+        assert_eq!(options[1].flag, "--port");
+        assert_eq!(options[1].short.as_deref(), Some("-p"));
+        assert_eq!(options[1].description, "Bind port");
+        assert_eq!(options[2].default_value, None);
+        assert!(options[3].required);
+        assert_eq!(
+            options[3].description,
+            "Log level used for the engine and its workers, written to the log directory"
+        );
+    }
+
+    #[test]
+    fn test_engine_process_matching() {
+        let runs = |name: &str, cmd: &[&str]| {
+            let cmd: Vec<OsString> = cmd.iter().map(OsString::from).collect();
+            runs_binary(OsStr::new(name), &cmd, ENGINE_BINARY)
+        };
+        // The console script runs as its interpreter
+        assert!(runs(
+            "python3",
+            &[
+                "/usr/bin/python3",
+                "/usr/local/bin/redletters",
+                "engine",
+                "start"
+            ]
+        ));
+        assert!(runs("python3.11", &["python3.11", "redletters"]));
+        assert!(runs("RedLetters.exe", &["C:\\Tools\\RedLetters.exe"]));
+        assert!(runs("redletters", &[]));
+        assert!(!runs("python3", &["/usr/bin/python3", "-m", "http.server"]));
+        assert!(!runs("redletters-gui", &["/opt/rl/redletters-gui"]));
+        // Only an interpreter's script counts, not any argument
+        assert!(!runs("vim", &["vim", "redletters"]));
+        assert!(!runs("python3", &[]));
+    }
+
+    #[test]
+    fn test_own_process_is_not_an_engine() {
+        let system = process_table();
+        let own_name = system
+            .process(Pid::from_u32(std::process::id()))
+            .unwrap()
+            .name()
+            .to_string_lossy()
+            .into_owned();
+        assert_eq!(engine_processes(&system, &own_name).count(), 0);
+    }
+
+    #[test]
+    fn test_proc_thread_fields() {
+        let status = "Name:\tredletters\nState:\tS (sleeping)\nThreads:\t37\nVmRSS:\t1024 kB\n";
+        assert_eq!(parse_status_threads(status), Some(37));
+        assert_eq!(parse_status_threads("Name:\tx\n"), None);
+
+        let limits = "\
+Limit                     Soft Limit           Hard Limit           Units
+Max cpu time              unlimited            unlimited            seconds
+Max processes             63432                63432                processes
+";
+        assert_eq!(parse_limits_max_processes(limits), Some(63432));
+        let unlimited = limits.replace("63432 ", "unlimited");
+        assert_eq!(parse_limits_max_processes(&unlimited), None);
+    }
+
+    #[test]
+    fn test_own_thread_count() {
+        #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+        assert!(
+            get_engine_thread_count(std::process::id())
+                .unwrap()
+                .thread_count
+                >= 1
+        );
+        assert!(matches!(
+            get_engine_thread_count(u32::MAX),
+            Err(EngineError::NotRunning)
+        ));
+    }
+
+    #[test]
+    fn test_kill_requires_binary_name() {
+        assert!(matches!(
+            kill_all_engine_processes(None, KillSignal::Kill),
+            Err(EngineError::StopFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_unparseable_help_yields_nothing() {
+        assert!(parse_help_options("command not found").is_empty());
+    }
+}
@@ -0,0 +1,394 @@
+//! Engine configuration (config.toml) as seen by the settings editor.
+//!
+//! The schema is fetched from the engine and cached for 24 hours. Engines
+//! that predate the schema endpoint get the schema bundled with the GUI.
+//! CORS settings have their own endpoint so they can be changed live, and
+//! `test_cors_preflight` checks them the way a browser would.
+
+use reqwest::header::{HeaderMap, ACCESS_CONTROL_REQUEST_HEADERS, ACCESS_CONTROL_REQUEST_METHOD};
+use reqwest::{Method, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, State};
+
+use super::cache::TtlCache;
+use super::engine::EngineError;
+use super::http::{self, HttpError};
+
+/// How long a schema fetched from the engine is reused
+const SCHEMA_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+/// Event emitted with each preflight test result
+const CORS_TEST_EVENT: &str = "cors-test-complete";
+/// Endpoint preflighted by `test_cors_preflight`; browser clients mostly call it
+const PREFLIGHT_PATH: &str = "/translate";
+/// Request headers browsers send without listing them in a preflight
+const SAFELISTED_HEADERS: &[&str] = &["accept", "accept-language", "content-language"];
+/// Schema for engines without `/v1/engine/config/schema`
+const BUNDLED_SCHEMA: &str = include_str!("../../assets/engine_config_schema.json");
+
+/// Remote config schemas, keyed by engine port.
+pub struct ConfigSchemaCache(Mutex<TtlCache<u16, Value>>);
+
+impl Default for ConfigSchemaCache {
+    fn default() -> Self {
+        Self(Mutex::new(TtlCache::new(SCHEMA_TTL)))
+    }
+}
+
+fn bundled_schema() -> Result<Value, EngineError> {
+    serde_json::from_str(BUNDLED_SCHEMA).map_err(|_| EngineError::SchemaUnavailable)
+}
+
+/// JSON Schema for the engine config file, used to build the settings form.
+#[tauri::command]
+pub async fn get_engine_config_schema(
+    cache: State<'_, ConfigSchemaCache>,
+    port: u16,
+) -> Result<Value, EngineError> {
+    if let Some(schema) = cache.0.lock().unwrap().get(&port) {
+        return Ok(schema);
+    }
+
+    let request = http::request(Method::GET, port, "/v1/engine/config/schema")?;
+    let schema: Value = match http::send_json(request).await {
+        Ok(schema) => schema,
+        Err(HttpError::Status { status: 404, .. }) => return bundled_schema(),
+        Err(e) => return Err(e.into()),
+    };
+    if !schema.is_object() {
+        return Err(EngineError::SchemaUnavailable);
+    }
+
+    cache.0.lock().unwrap().insert(port, schema.clone());
+    Ok(schema)
+}
+
+/// Current engine config, keyed like the schema's properties.
+#[tauri::command]
+pub async fn get_engine_config(port: u16) -> Result<Value, EngineError> {
+    let request = http::request(Method::GET, port, "/v1/engine/config")?;
+    Ok(http::send_json(request).await?)
+}
+
+/// Change engine config fields; fields left out of `changes` keep their value.
+#[tauri::command]
+pub async fn set_engine_config(port: u16, changes: Value) -> Result<(), EngineError> {
+    if !changes.is_object() {
+        return Err(EngineError::InvalidConfig(
+            "expected an object of config fields".to_string(),
+        ));
+    }
+
+    let request = http::request(Method::PATCH, port, "/v1/engine/config")?.json(&changes);
+    match http::send(request).await {
+        Ok(_) => Ok(()),
+        Err(HttpError::Status {
+            status: 400 | 422,
+            body,
+        }) => Err(EngineError::InvalidConfig(body)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Cross-origin settings of the engine's HTTP API.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CorsConfig {
+    /// Origins like `https://example.org`, or `*` for any origin
+    pub allowed_origins: Vec<String>,
+    pub allowed_methods: Vec<String>,
+    pub allowed_headers: Vec<String>,
+    pub allow_credentials: bool,
+    #[serde(default)]
+    pub max_age_seconds: Option<u32>,
+}
+
+/// Check that an origin is `*` or a bare `scheme://host[:port]`.
+fn validate_origin(origin: &str) -> Result<(), String> {
+    if origin == "*" {
+        return Ok(());
+    }
+    let url = Url::parse(origin).map_err(|e| format!("{}: {}", origin, e))?;
+    if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none() {
+        return Err(format!("{}: expected an http(s) origin", origin));
+    }
+    // An origin has no path, query or credentials; Url adds a "/" path itself
+    if url.path() != "/"
+        || origin.ends_with('/')
+        || url.query().is_some()
+        || url.fragment().is_some()
+        || !url.username().is_empty()
+    {
+        return Err(format!("{}: origins cannot have a path or query", origin));
+    }
+    Ok(())
+}
+
+fn validate_cors(config: &CorsConfig) -> Result<(), EngineError> {
+    for origin in &config.allowed_origins {
+        validate_origin(origin).map_err(EngineError::CorsConfigError)?;
+    }
+    if config.allow_credentials && config.allowed_origins.iter().any(|o| o == "*") {
+        tracing::warn!(
+            "CORS allows credentials with origin \"*\"; browsers will reject credentialed requests"
+        );
+    }
+    Ok(())
+}
+
+/// CORS settings of the engine API.
+#[tauri::command]
+pub async fn get_cors_config(port: u16) -> Result<CorsConfig, EngineError> {
+    let request = http::request(Method::GET, port, "/v1/engine/cors")?;
+    Ok(http::send_json(request).await?)
+}
+
+/// Replace the CORS settings of the engine API.
+#[tauri::command]
+pub async fn set_cors_config(port: u16, config: CorsConfig) -> Result<(), EngineError> {
+    validate_cors(&config)?;
+
+    let request = http::request(Method::PATCH, port, "/v1/engine/cors")?.json(&config);
+    match http::send(request).await {
+        Ok(_) => Ok(()),
+        Err(HttpError::Status {
+            status: 400 | 422,
+            body,
+        }) => Err(EngineError::CorsConfigError(body)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Cap the engine's worker threads, e.g. when `get_engine_thread_count`
+/// shows it crowding out the GUI.
+#[tauri::command]
+pub async fn set_engine_thread_limit(port: u16, max_workers: u32) -> Result<(), EngineError> {
+    if max_workers == 0 {
+        return Err(EngineError::InvalidThreadLimit(
+            "max_workers must be at least 1".to_string(),
+        ));
+    }
+
+    let body = serde_json::json!({ "max_workers": max_workers });
+    let request = http::request(Method::PATCH, port, "/v1/engine/config")?.json(&body);
+    match http::send(request).await {
+        Ok(_) => Ok(()),
+        Err(HttpError::Status {
+            status: 400 | 422,
+            body,
+        }) => Err(EngineError::InvalidThreadLimit(body)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PreflightResult {
+    pub allowed: bool,
+    /// Response headers, with lowercase names
+    pub response_headers: HashMap<String, String>,
+    pub status_code: u16,
+    /// Why a browser would block the request, when `allowed` is false
+    pub rejection_reason: Option<String>,
+}
+
+fn header_map(headers: &HeaderMap) -> HashMap<String, String> {
+    let mut map: HashMap<String, String> = HashMap::new();
+    for (name, value) in headers {
+        let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+        map.entry(name.as_str().to_string())
+            .and_modify(|v| {
+                v.push_str(", ");
+                v.push_str(&value);
+            })
+            .or_insert(value);
+    }
+    map
+}
+
+/// Whether a comma-separated header value lists `item` (or `*`).
+fn lists(value: Option<&String>, item: &str) -> bool {
+    value.is_some_and(|v| {
+        v.split(',')
+            .map(str::trim)
+            .any(|v| v == "*" || v.eq_ignore_ascii_case(item))
+    })
+}
+
+/// Apply a browser's preflight checks; returns the rejection reason, if any.
+fn preflight_rejection(
+    status: u16,
+    headers: &HashMap<String, String>,
+    origin: &str,
+    method: &str,
+    request_headers: &[String],
+) -> Option<String> {
+    if !(200..300).contains(&status) {
+        return Some(format!("Preflight returned HTTP {}", status));
+    }
+    match headers.get("access-control-allow-origin") {
+        None => return Some("No Access-Control-Allow-Origin header".to_string()),
+        Some(allowed) if allowed != "*" && allowed != origin => {
+            return Some(format!(
+                "Access-Control-Allow-Origin is {}, not {}",
+                allowed, origin
+            ))
+        }
+        Some(_) => {}
+    }
+    // GET, HEAD and POST never need to be listed
+    let simple_method = ["GET", "HEAD", "POST"].contains(&method.to_ascii_uppercase().as_str());
+    if !simple_method && !lists(headers.get("access-control-allow-methods"), method) {
+        return Some(format!("Method {} is not allowed", method));
+    }
+    let allowed_headers = headers.get("access-control-allow-headers");
+    for header in request_headers {
+        let header = header.trim().to_ascii_lowercase();
+        if SAFELISTED_HEADERS.contains(&header.as_str()) {
+            continue;
+        }
+        // "*" never covers Authorization
+        let listed = if header == "authorization" {
+            allowed_headers
+                .is_some_and(|v| v.split(',').any(|h| h.trim().eq_ignore_ascii_case(&header)))
+        } else {
+            lists(allowed_headers, &header)
+        };
+        if !listed {
+            return Some(format!("Request header {} is not allowed", header));
+        }
+    }
+    None
+}
+
+/// Send a browser-style CORS preflight (OPTIONS) from `origin` and report
+/// whether a browser would let the request through.
+#[tauri::command]
+pub async fn test_cors_preflight(
+    app: AppHandle,
+    port: u16,
+    origin: String,
+    method: String,
+    request_headers: Vec<String>,
+) -> Result<PreflightResult, EngineError> {
+    // Browsers send preflights without credentials, so no bearer token here
+    let mut request = http::client()
+        .request(Method::OPTIONS, http::engine_url(port, PREFLIGHT_PATH))
+        .header(reqwest::header::ORIGIN, &origin)
+        .header(ACCESS_CONTROL_REQUEST_METHOD, &method);
+    if !request_headers.is_empty() {
+        request = request.header(ACCESS_CONTROL_REQUEST_HEADERS, request_headers.join(", "));
+    }
+    let response = request
+        .send()
+        .await
+        .map_err(|e| HttpError::Transport(e.to_string()))?;
+
+    let status_code = response.status().as_u16();
+    let response_headers = header_map(response.headers());
+    let rejection_reason = preflight_rejection(
+        status_code,
+        &response_headers,
+        &origin,
+        &method,
+        &request_headers,
+    );
+    let result = PreflightResult {
+        allowed: rejection_reason.is_none(),
+        response_headers,
+        status_code,
+        rejection_reason,
+    };
+    let _ = app.emit(CORS_TEST_EVENT, &result);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::auth::set_auth_token;
+    use crate::testsupport::mock_engine::MockEngine;
+    use crate::testsupport::TestEnv;
+
+    #[test]
+    fn test_bundled_schema_is_valid_json_schema_object() {
+        let schema = bundled_schema().unwrap();
+        assert_eq!(schema["type"], "object");
+        assert_eq!(schema["properties"]["port"]["default"], 47200);
+    }
+
+    #[test]
+    fn test_validate_origin() {
+        for origin in ["*", "https://example.org", "http://localhost:5173"] {
+            assert!(validate_origin(origin).is_ok(), "{}", origin);
+        }
+        for origin in [
+            "example.org",
+            "ftp://example.org",
+            "https://example.org/",
+            "https://example.org/app",
+            "https://example.org?x=1",
+            "https://user@example.org",
+            "",
+        ] {
+            assert!(validate_origin(origin).is_err(), "{}", origin);
+        }
+    }
+
+    #[test]
+    fn test_preflight_rejection() {
+        let origin = "https://example.org";
+        let headers: HashMap<String, String> = [
+            ("access-control-allow-origin", origin),
+            ("access-control-allow-methods", "GET, POST, PATCH"),
+            ("access-control-allow-headers", "Content-Type, *"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        let check = |status, method: &str, request_headers: &[&str]| {
+            let request_headers: Vec<String> =
+                request_headers.iter().map(|h| h.to_string()).collect();
+            preflight_rejection(status, &headers, origin, method, &request_headers)
+        };
+
+        assert_eq!(check(204, "patch", &["content-type", "x-trace"]), None);
+        assert_eq!(check(200, "PUT", &[]).unwrap(), "Method PUT is not allowed");
+        assert!(check(200, "POST", &["Authorization"]).is_some());
+        assert!(check(405, "GET", &[]).is_some());
+        assert!(preflight_rejection(200, &headers, "https://evil.test", "GET", &[]).is_some());
+        assert!(preflight_rejection(200, &HashMap::new(), origin, "GET", &[]).is_some());
+    }
+
+    #[test]
+    fn test_set_engine_config() {
+        let _env = TestEnv::new();
+        let engine = MockEngine::start();
+        let port = engine.port();
+        set_auth_token("rl_engine_config_test_token_1".to_string()).unwrap();
+        let set = |changes| tauri::async_runtime::block_on(set_engine_config(port, changes));
+
+        assert!(matches!(
+            set(serde_json::json!(["port"])),
+            Err(EngineError::InvalidConfig(_))
+        ));
+        assert!(engine.requests().is_empty());
+
+        engine.respond("PATCH", "/v1/engine/config", 204, Value::Null);
+        set(serde_json::json!({ "log_level": "debug" })).unwrap();
+        let sent = engine.requests().pop().unwrap();
+        assert_eq!(sent.body["log_level"], "debug");
+
+        engine.respond(
+            "PATCH",
+            "/v1/engine/config",
+            422,
+            serde_json::json!({ "detail": "port out of range" }),
+        );
+        let e = set(serde_json::json!({ "port": 80 })).unwrap_err();
+        assert!(
+            matches!(e, EngineError::InvalidConfig(body) if body.contains("port out of range"))
+        );
+    }
+}
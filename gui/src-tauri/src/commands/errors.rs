@@ -0,0 +1,379 @@
+//! The envelope typed command errors are sent to the frontend in.
+//!
+//! ```json
+//! { "code": "auth/not_found", "message": "...", "details": {}, "retryable": false }
+//! ```
+//!
+//! `code` is a stable contract (listed in `gui/docs/error-codes.md`): the
+//! frontend branches on it and only displays `message`, which is
+//! localized and may be reworded. Codes are derived from the translation
+//! keys of `Localize::code`, the first segment being the namespace, so
+//! "auth-not-found" is sent as "auth/not_found". `details` holds the
+//! message parameters. `retryable` says whether the same call may succeed
+//! if simply tried again (the engine was down or busy).
+//!
+//! An error type implements `CommandError` and gets its `Serialize` impl
+//! from `command_error!`. Commands without a domain error of their own
+//! return `AppError`, whose codes only say what kind of failure it was.
+
+use serde::ser::SerializeStruct;
+use serde::Serializer;
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+use super::http::HttpError;
+use super::i18n::{localized_message, Localize};
+
+/// An error returned from a command.
+pub trait CommandError: Localize {
+    /// Whether trying the same call again may succeed.
+    fn retryable(&self) -> bool {
+        false
+    }
+}
+
+/// The stable code for a translation key: "engine-invalid-strongs" is
+/// "engine/invalid_strongs".
+pub fn stable_code(key: &str) -> String {
+    match key.split_once('-') {
+        Some((namespace, rest)) => format!("{}/{}", namespace, rest.replace('-', "_")),
+        None => key.to_string(),
+    }
+}
+
+/// Serialize an error as `{ code, message, details, retryable }`.
+pub fn serialize_envelope<E, S>(error: &E, serializer: S) -> Result<S::Ok, S::Error>
+where
+    E: CommandError + ?Sized,
+    S: Serializer,
+{
+    let details: BTreeMap<&str, String> = error.params().into_iter().collect();
+    let mut state = serializer.serialize_struct("CommandError", 4)?;
+    state.serialize_field("code", &stable_code(error.code()))?;
+    state.serialize_field("message", &localized_message(error))?;
+    state.serialize_field("details", &details)?;
+    state.serialize_field("retryable", &error.retryable())?;
+    state.end()
+}
+
+/// `Serialize` impls sending errors in the envelope.
+macro_rules! command_error {
+    ($($error:ty),+ $(,)?) => {
+        $(
+            impl serde::Serialize for $error {
+                fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+                where
+                    S: serde::Serializer,
+                {
+                    $crate::commands::errors::serialize_envelope(self, serializer)
+                }
+            }
+        )+
+    };
+}
+
+pub(crate) use command_error;
+
+/// Error of commands with no domain error type. The message is the
+/// command's own text, which is not translated.
+#[derive(Debug, Error)]
+pub enum AppError {
+    /// Arguments the command can't act on
+    #[error("{0}")]
+    InvalidInput(String),
+    /// What the command refers to doesn't exist
+    #[error("{0}")]
+    NotFound(String),
+    #[error(transparent)]
+    Http(#[from] HttpError),
+    /// Anything else: a file that couldn't be read or written, a failed
+    /// platform call
+    #[error("{0}")]
+    Failed(String),
+}
+
+impl From<String> for AppError {
+    fn from(detail: String) -> Self {
+        AppError::Failed(detail)
+    }
+}
+
+impl Localize for AppError {
+    fn code(&self) -> &'static str {
+        match self {
+            AppError::InvalidInput(_) => "app-invalid-input",
+            AppError::NotFound(_) => "app-not-found",
+            AppError::Http(e) => e.code(),
+            AppError::Failed(_) => "app-failed",
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            AppError::InvalidInput(detail)
+            | AppError::NotFound(detail)
+            | AppError::Failed(detail) => vec![("detail", detail.clone())],
+            AppError::Http(e) => e.params(),
+        }
+    }
+}
+
+impl CommandError for AppError {
+    fn retryable(&self) -> bool {
+        match self {
+            AppError::Http(e) => e.retryable(),
+            _ => false,
+        }
+    }
+}
+
+command_error!(AppError);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::auth::AuthError;
+    use crate::commands::corpus_import::{CorpusImportError, ImportIssue};
+    use crate::commands::engine::EngineError;
+    use crate::commands::http::HttpError;
+    use crate::commands::projects::ProjectError;
+    use crate::commands::reference::{parse_reference_list, ReferenceError};
+    use crate::commands::speech::TtsError;
+    use serde_json::{json, Value};
+
+    fn code(error: &impl serde::Serialize) -> String {
+        serde_json::to_value(error).unwrap()["code"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn test_envelope_snapshot() {
+        let error = EngineError::Http(HttpError::Status {
+            status: 503,
+            body: "busy".to_string(),
+        });
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            json!({
+                "code": "http/status",
+                "message": "Engine returned HTTP 503: busy",
+                "details": { "status": "503", "body": "busy" },
+                "retryable": true,
+            })
+        );
+        let error = parse_reference_list("Hezekiah 1:1").unwrap_err();
+        let value: Value = serde_json::to_value(&error).unwrap();
+        assert_eq!(value["code"], "reference/unknown_book");
+        assert_eq!(value["details"]["text"], "Hezekiah");
+        assert_eq!(value["retryable"], false);
+
+        let error = AppError::NotFound("No batch job 7".to_string());
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            json!({
+                "code": "app/not_found",
+                "message": "No batch job 7",
+                "details": { "detail": "No batch job 7" },
+                "retryable": false,
+            })
+        );
+    }
+
+    /// Renaming any of these breaks the frontend; update the docs with them
+    #[test]
+    fn test_stable_codes() {
+        let auth = [
+            (AuthError::NotFound, "auth/not_found"),
+            (AuthError::InvalidFormat, "auth/invalid_format"),
+            (
+                AuthError::KeychainError(String::new()),
+                "auth/keychain_error",
+            ),
+            (AuthError::FileError(String::new()), "auth/file_error"),
+            (
+                AuthError::PermissionsUnavailable,
+                "auth/permissions_unavailable",
+            ),
+            (AuthError::MissingScope(String::new()), "auth/missing_scope"),
+            (
+                AuthError::ImpersonationReasonRequired,
+                "auth/impersonation_reason_required",
+            ),
+        ];
+        for (error, expected) in auth {
+            assert_eq!(code(&error), expected);
+        }
+
+        let detail = String::new;
+        let engine = [
+            (EngineError::NotRunning, "engine/not_running"),
+            (EngineError::StartFailed(detail()), "engine/start_failed"),
+            (EngineError::StopFailed(detail()), "engine/stop_failed"),
+            (EngineError::Storage(detail()), "engine/storage_error"),
+            (
+                EngineError::RateLimitConfigError(detail()),
+                "engine/rate_limit_config",
+            ),
+            (EngineError::SchemaUnavailable, "engine/schema_unavailable"),
+            (
+                EngineError::CorpusNotFound(detail()),
+                "engine/corpus_not_found",
+            ),
+            (
+                EngineError::CorpusNotDownloadable(detail()),
+                "engine/corpus_not_downloadable",
+            ),
+            (
+                EngineError::CorpusChecksumMismatch(detail()),
+                "engine/corpus_checksum_mismatch",
+            ),
+            (EngineError::CorpusActive(detail()), "engine/corpus_active"),
+            (
+                EngineError::ExtensionLoadError(detail()),
+                "engine/extension_load_error",
+            ),
+            (
+                EngineError::LexiconUnavailable,
+                "engine/lexicon_unavailable",
+            ),
+            (
+                EngineError::InvalidStrongsNumber(detail()),
+                "engine/invalid_strongs",
+            ),
+            (
+                EngineError::InvalidHebrewStrongsNumber(detail()),
+                "engine/invalid_hebrew_strongs",
+            ),
+            (EngineError::GrpcError(detail()), "engine/grpc_error"),
+            (EngineError::CorsConfigError(detail()), "engine/cors_config"),
+            (
+                EngineError::InvalidSearchQuery(detail()),
+                "engine/invalid_search",
+            ),
+            (
+                EngineError::InvalidThreadLimit(detail()),
+                "engine/thread_limit",
+            ),
+            (
+                EngineError::InvalidConfig(detail()),
+                "engine/invalid_config",
+            ),
+            (
+                EngineError::InvalidReference(detail()),
+                "engine/invalid_reference",
+            ),
+            (
+                EngineError::CommentaryNotFound(detail()),
+                "engine/commentary_not_found",
+            ),
+            (
+                EngineError::InvalidVersions(detail()),
+                "engine/invalid_versions",
+            ),
+            (
+                EngineError::HighlightError(detail()),
+                "engine/highlight_error",
+            ),
+            (
+                EngineError::PassageNotFound(detail()),
+                "engine/passage_not_found",
+            ),
+            (
+                EngineError::FlashcardDeckError(detail()),
+                "engine/flashcard_deck_error",
+            ),
+            (HttpError::Transport(detail()).into(), "http/transport"),
+            (HttpError::Decode(detail()).into(), "http/decode"),
+            (HttpError::Offline.into(), "http/offline_mode"),
+            (
+                HttpError::InvalidPathSegment(detail()).into(),
+                "http/invalid_path_segment",
+            ),
+        ];
+        for (error, expected) in engine {
+            assert_eq!(code(&error), expected);
+        }
+
+        assert_eq!(code(&ReferenceError::Empty), "reference/empty");
+        for (input, expected) in [
+            ("Hezekiah 1:1", "reference/unknown_book"),
+            ("16; John 3", "reference/missing_book"),
+            ("John 3:x6", "reference/invalid_number"),
+            ("John 3:18-16", "reference/invalid_range"),
+            ("John 22", "reference/chapter_out_of_range"),
+            ("John 3:99", "reference/verse_out_of_range"),
+        ] {
+            let error = parse_reference_list(input).unwrap_err();
+            assert_eq!(code(&error), expected, "{}", input);
+        }
+
+        let project = [
+            (
+                ProjectError::NewerVersion {
+                    format_version: 9,
+                    app_version: detail(),
+                },
+                "project/newer_version",
+            ),
+            (ProjectError::Invalid(detail()), "project/invalid"),
+            (ProjectError::File(detail()), "project/file_error"),
+        ];
+        for (error, expected) in project {
+            assert_eq!(code(&error), expected);
+        }
+
+        let tts = [
+            (TtsError::Unavailable(detail()), "tts/unavailable"),
+            (TtsError::InvalidRate(3.0), "tts/invalid_rate"),
+            (TtsError::Failed(detail()), "tts/failed"),
+        ];
+        for (error, expected) in tts {
+            assert_eq!(code(&error), expected);
+        }
+
+        let corpus_import = [
+            (
+                CorpusImportError::Parse {
+                    issues: vec![ImportIssue {
+                        line: None,
+                        message: detail(),
+                    }],
+                },
+                "corpus/import_parse",
+            ),
+            (CorpusImportError::Exists(detail()), "corpus/import_exists"),
+            (CorpusImportError::File(detail()), "corpus/import_file"),
+        ];
+        for (error, expected) in corpus_import {
+            assert_eq!(code(&error), expected);
+        }
+
+        let app = [
+            (AppError::InvalidInput(detail()), "app/invalid_input"),
+            (AppError::NotFound(detail()), "app/not_found"),
+            (AppError::Failed(detail()), "app/failed"),
+            (HttpError::Offline.into(), "http/offline_mode"),
+        ];
+        for (error, expected) in app {
+            assert_eq!(code(&error), expected);
+        }
+    }
+
+    #[test]
+    fn test_retryable() {
+        assert!(EngineError::NotRunning.retryable());
+        assert!(EngineError::from(HttpError::Transport(String::new())).retryable());
+        assert!(!EngineError::from(HttpError::Status {
+            status: 404,
+            body: String::new(),
+        })
+        .retryable());
+        assert!(!EngineError::from(HttpError::Offline).retryable());
+        assert!(!AuthError::NotFound.retryable());
+        assert!(AppError::from(HttpError::Transport(String::new())).retryable());
+        assert!(!AppError::Failed(String::new()).retryable());
+        assert_eq!(stable_code("plain"), "plain");
+    }
+}
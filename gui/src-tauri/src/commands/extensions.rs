@@ -0,0 +1,129 @@
+//! Engine extension (plugin module) management.
+//!
+//! Extensions that failed to load are still listed, with `error` set, so
+//! the UI can flag them with a warning badge.
+
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use super::engine::EngineError;
+use super::http::{self, HttpError};
+
+/// Event emitted after an extension is reloaded or unloaded
+const EXTENSION_STATE_EVENT: &str = "extension-state-changed";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EngineExtension {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub enabled: bool,
+    #[serde(default)]
+    pub loaded_at_ms: Option<u64>,
+    /// Load error from the engine; set when the extension failed to load
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExtensionAction {
+    Reloaded,
+    Unloaded,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExtensionStateChanged {
+    pub id: String,
+    pub action: ExtensionAction,
+}
+
+/// API path of an action on extension `id`.
+fn action_path(id: &str, action: ExtensionAction) -> Result<String, HttpError> {
+    let verb = match action {
+        ExtensionAction::Reloaded => "reload",
+        ExtensionAction::Unloaded => "unload",
+    };
+    Ok(format!(
+        "/v1/extensions/{}/{}",
+        http::path_segment(id)?,
+        verb
+    ))
+}
+
+/// POST an extension action and announce the change.
+async fn extension_action(
+    app: &AppHandle,
+    port: u16,
+    id: String,
+    action: ExtensionAction,
+) -> Result<(), EngineError> {
+    let path = action_path(&id, action)?;
+    match http::send(http::request(Method::POST, port, &path)?).await {
+        Ok(_) => {}
+        // The engine reports import/initialization failures as 422
+        Err(HttpError::Status { status: 422, body }) => {
+            return Err(EngineError::ExtensionLoadError(body))
+        }
+        Err(e) => return Err(e.into()),
+    }
+    let _ = app.emit(EXTENSION_STATE_EVENT, ExtensionStateChanged { id, action });
+    Ok(())
+}
+
+/// List extensions installed in the engine, including ones that failed to load.
+#[tauri::command]
+pub async fn get_engine_installed_extensions(
+    port: u16,
+) -> Result<Vec<EngineExtension>, EngineError> {
+    let request = http::request(Method::GET, port, "/v1/extensions")?;
+    Ok(http::send_json(request).await?)
+}
+
+/// Reload an extension from disk.
+#[tauri::command]
+pub async fn reload_extension(app: AppHandle, port: u16, id: String) -> Result<(), EngineError> {
+    extension_action(&app, port, id, ExtensionAction::Reloaded).await
+}
+
+/// Unload an extension until it is reloaded or the engine restarts.
+#[tauri::command]
+pub async fn unload_extension(app: AppHandle, port: u16, id: String) -> Result<(), EngineError> {
+    extension_action(&app, port, id, ExtensionAction::Unloaded).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_action_path_keeps_id_in_one_segment() {
+        assert_eq!(
+            action_path("lxx-morph", ExtensionAction::Reloaded).unwrap(),
+            "/v1/extensions/lxx-morph/reload"
+        );
+        assert_eq!(
+            action_path("../admin/users/bob", ExtensionAction::Reloaded).unwrap(),
+            "/v1/extensions/..%2Fadmin%2Fusers%2Fbob/reload"
+        );
+        assert_eq!(
+            action_path("x/../../auth/tokens?", ExtensionAction::Unloaded).unwrap(),
+            "/v1/extensions/x%2F..%2F..%2Fauth%2Ftokens%3F/unload"
+        );
+        for id in ["", ".", ".."] {
+            assert!(matches!(
+                action_path(id, ExtensionAction::Unloaded),
+                Err(HttpError::InvalidPathSegment(_))
+            ));
+        }
+
+        let url = reqwest::Url::parse(&http::engine_url(
+            47200,
+            &action_path("x/../../auth/tokens?", ExtensionAction::Reloaded).unwrap(),
+        ))
+        .unwrap();
+        assert!(url.path().starts_with("/v1/extensions/"));
+        assert_eq!(url.query(), None);
+    }
+}
@@ -0,0 +1,942 @@
+//! Vocabulary flashcards for Anki.
+//!
+//! Lemmas come from the cached token data of each passage: the token ledger
+//! when the passage was translated in traceable mode, otherwise the spine
+//! tokens of its verse blocks. Glosses, morphology summaries and NT
+//! frequencies come from the offline lexicon, with the ledger gloss as a
+//! fallback; a personal gloss beats both and tags the card. The file is Anki's plain-text note format: tab-separated
+//! front, back and tags, with header lines so Anki picks the right options.
+//! The source attribution goes in a text file next to it (see
+//! `licensing::write_sidecar`).
+//!
+//! Decks made by `generate_flashcard_deck` are kept in `flashcard_decks/`
+//! under the app data dir, one JSON file per deck, and exported as Anki
+//! packages (see `apkg`). Their words come from the personal glossary,
+//! a range of NT frequency ranks or a passage.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+use super::apkg::{self, AnkiDeck, AnkiNote};
+use super::document_export::{escape_html, PassageExportFailure};
+use super::engine::EngineError;
+use super::errors::AppError;
+use super::glossary::{list_personal_glossary, personal_glossary, PersonalGlossary};
+use super::interlinear_table::{tsv_field, verse_reference};
+use super::lexicon::{
+    lexicon_entries, lookup_lemma, normalize_lemma, parse_strongs, LemmaEntry, LemmaSource,
+};
+use super::licensing;
+use super::passages::{
+    load_all_passages, load_passage, translate_and_cache, CachedPassage, TranslateOptions,
+    TranslateOutcome,
+};
+use super::reference::{canonical_reference, validate_scripture_reference};
+use super::save_dialog::{validate_export_path, ExportKind};
+use super::storage;
+use super::tags;
+use super::transliteration::{transliterate_text, TransliterationScheme};
+use super::usfm::split_verse_id;
+use super::word_frequency::{words_by_rank, FrequencyCorpus};
+
+/// Tag on every card, so an import can be found and replaced in Anki
+const DECK_TAG: &str = "redletters";
+/// Tag on cards whose gloss comes from the personal glossary
+const PERSONAL_GLOSS_TAG: &str = "personal-gloss";
+const DECKS_DIR: &str = "flashcard_decks";
+/// Most cards a generated deck holds
+const MAX_DECK_CARDS: u32 = 1000;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FlashcardOptions {
+    /// Leave out lemmas occurring more often than this (e.g. καί, ὁ)
+    #[serde(default)]
+    pub max_frequency: Option<u32>,
+    #[serde(default)]
+    pub include_morphology: bool,
+    /// Add the first verse the lemma occurs in to the back
+    #[serde(default)]
+    pub include_example: bool,
+    /// Romanized lemma above the gloss on the back
+    #[serde(default)]
+    pub transliteration: Option<TransliterationScheme>,
+    /// Only passages with this tag; with no passage ids, every passage
+    /// carrying it
+    #[serde(default)]
+    pub tag: Option<String>,
+    /// Write the source attribution file next to the cards; most corpus
+    /// licenses require it when the data is shared
+    #[serde(default = "default_true")]
+    pub include_attribution: bool,
+}
+fn default_true() -> bool {
+    true
+}
+
+impl Default for FlashcardOptions {
+    fn default() -> Self {
+        Self {
+            max_frequency: None,
+            include_morphology: false,
+            include_example: false,
+            transliteration: None,
+            tag: None,
+            include_attribution: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FlashcardExportReport {
+    pub path: String,
+    pub cards_written: usize,
+    /// Lemmas with neither a lexicon nor a ledger gloss
+    pub skipped_missing_gloss: usize,
+    /// Lemmas above `max_frequency`
+    pub skipped_frequent: usize,
+    /// Source attribution written next to the cards, if any
+    pub attribution_path: Option<String>,
+    pub failed: Vec<PassageExportFailure>,
+}
+
+/// A row of a passage's vocabulary table.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VocabularyEntry {
+    pub lemma: String,
+    pub gloss: Option<String>,
+    /// NT frequency from the lexicon, else occurrences in the passage
+    pub frequency: u32,
+    /// Where the gloss came from; `personal` for the personal glossary
+    pub source: LemmaSource,
+}
+
+/// Where the words of a generated deck come from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FlashcardSource {
+    /// Lemmas in the personal glossary, alphabetically
+    StudyList,
+    /// NT lemmas by frequency rank, inclusive, 1 being the most frequent
+    FrequencyRange { min_rank: u32, max_rank: u32 },
+    /// Vocabulary of a passage, most frequent first; translated if no
+    /// cached translation has token data
+    PassageReference(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Flashcard {
+    /// The lemma
+    pub front: String,
+    /// The gloss, plain text
+    pub back: String,
+    pub strongs: Option<u32>,
+    /// "noun", "verb", ...; empty when unknown
+    pub part_of_speech: String,
+    /// First cached verse with the lemma, e.g. "John 1:1 — Ἐν ἀρχῇ ἦν ὁ λόγος"
+    pub example_verse: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FlashcardDeck {
+    pub id: String,
+    /// e.g. "John 1:1-5", "Study list" or "NT ranks 1–100"
+    pub name: String,
+    pub source: FlashcardSource,
+    pub cards: Vec<Flashcard>,
+    pub created_at_ms: u64,
+}
+
+/// One lemma occurrence from the cached token data.
+#[derive(Debug, Clone)]
+struct Occurrence {
+    lemma: String,
+    morph: String,
+    gloss: Option<String>,
+    verse_id: String,
+}
+
+/// A lemma and everything known about it, before the lexicon is joined.
+#[derive(Debug, Clone)]
+struct Vocab {
+    lemma: String,
+    /// Occurrences in the selected passages
+    count: u32,
+    morph: String,
+    gloss: Option<String>,
+    /// First verse the lemma occurs in, as (verse id, Greek, translation)
+    example: Option<(String, String, String)>,
+    books: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Card {
+    front: String,
+    back: String,
+    tags: String,
+    /// NT frequency from the lexicon, else occurrences in the passages
+    frequency: u32,
+}
+
+fn text(token: &Value, key: &str) -> String {
+    token
+        .get(key)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .trim()
+        .to_string()
+}
+
+/// Lemma occurrences in a cached response, in reading order.
+fn occurrences(response: &Value) -> Vec<Occurrence> {
+    let ledger = response
+        .get("ledger")
+        .and_then(Value::as_array)
+        .filter(|ledger| !ledger.is_empty());
+    let (verses, with_gloss) = match ledger {
+        Some(ledger) => (ledger, true),
+        None => match response.get("verse_blocks").and_then(Value::as_array) {
+            Some(blocks) => (blocks, false),
+            None => return Vec::new(),
+        },
+    };
+    let mut found = Vec::new();
+    for verse in verses {
+        let verse_id = text(verse, "verse_id");
+        let tokens = verse.get("tokens").and_then(Value::as_array);
+        for token in tokens.into_iter().flatten() {
+            let lemma = text(token, "lemma");
+            if lemma.is_empty() {
+                continue;
+            }
+            let gloss = Some(text(token, "gloss")).filter(|g| with_gloss && !g.is_empty());
+            found.push(Occurrence {
+                lemma,
+                morph: text(token, "morph"),
+                gloss,
+                verse_id: verse_id.clone(),
+            });
+        }
+    }
+    found
+}
+
+/// Distinct lemmas in a cached response, in reading order.
+pub fn passage_lemmas(response: &Value) -> Vec<String> {
+    let mut lemmas: Vec<String> = Vec::new();
+    for occurrence in occurrences(response) {
+        if !lemmas.contains(&occurrence.lemma) {
+            lemmas.push(occurrence.lemma);
+        }
+    }
+    lemmas
+}
+
+/// Merge a passage's occurrences into `vocab`, keyed by normalized lemma.
+fn collect(vocab: &mut Vec<Vocab>, index: &mut HashMap<String, usize>, passage: &CachedPassage) {
+    for occurrence in occurrences(&passage.response) {
+        let key = normalize_lemma(&occurrence.lemma);
+        let book = split_verse_id(&occurrence.verse_id).map(|(book, _, _)| book.to_string());
+        let i = *index.entry(key).or_insert_with(|| {
+            let example = passage
+                .verses
+                .iter()
+                .find(|v| v.verse_id == occurrence.verse_id)
+                .map(|v| (v.verse_id.clone(), v.greek.clone(), v.translation.clone()));
+            vocab.push(Vocab {
+                lemma: occurrence.lemma.clone(),
+                count: 0,
+                morph: occurrence.morph.clone(),
+                gloss: None,
+                example,
+                books: Vec::new(),
+            });
+            vocab.len() - 1
+        });
+        let entry = &mut vocab[i];
+        entry.count += 1;
+        if entry.gloss.is_none() {
+            entry.gloss = occurrence.gloss;
+        }
+        if let Some(book) = book.filter(|b| !entry.books.contains(b)) {
+            entry.books.push(book);
+        }
+    }
+}
+
+/// Join the lexicon, apply the options and sort by frequency, most common
+/// first. Returns the cards and the (missing gloss, too frequent) counts.
+fn build_cards(
+    vocab: Vec<Vocab>,
+    options: &FlashcardOptions,
+    glossary: &PersonalGlossary,
+) -> (Vec<Card>, usize, usize) {
+    let lexicon = lexicon_entries(vocab.iter().map(|v| v.lemma.as_str()));
+    let mut cards = Vec::new();
+    let (mut missing_gloss, mut frequent) = (0, 0);
+    for word in vocab {
+        let entry = lexicon.get(&word.lemma);
+        let frequency = entry.and_then(|(_, f)| *f).unwrap_or(word.count);
+        if options.max_frequency.is_some_and(|max| frequency > max) {
+            frequent += 1;
+            continue;
+        }
+        let personal = glossary.get(&word.lemma);
+        let gloss = personal.map(|p| p.gloss.clone()).or_else(|| {
+            entry
+                .map(|(e, _)| e.glosses.join("; "))
+                .filter(|g| !g.is_empty())
+                .or(word.gloss)
+        });
+        let Some(gloss) = gloss else {
+            missing_gloss += 1;
+            continue;
+        };
+
+        let mut back = match options.transliteration {
+            Some(scheme) => format!(
+                "{}<br>",
+                escape_html(&transliterate_text(&word.lemma, scheme, false))
+            ),
+            None => String::new(),
+        };
+        back.push_str(&escape_html(&gloss));
+        if options.include_morphology {
+            let morphology = entry
+                .and_then(|(e, _)| e.morphology.clone())
+                .unwrap_or(word.morph);
+            if !morphology.is_empty() {
+                back.push_str(&format!("<br><i>{}</i>", escape_html(&morphology)));
+            }
+        }
+        if let Some((verse_id, greek, translation)) =
+            word.example.filter(|_| options.include_example)
+        {
+            back.push_str(&format!(
+                "<br><br><b>{}</b><br>{}<br>{}",
+                escape_html(&verse_reference(&verse_id)),
+                escape_html(&greek),
+                escape_html(&translation)
+            ));
+        }
+
+        let mut tags = vec![DECK_TAG.to_string()];
+        if personal.is_some() {
+            tags.push(PERSONAL_GLOSS_TAG.to_string());
+        }
+        tags.extend(word.books);
+        cards.push(Card {
+            front: word.lemma,
+            back,
+            tags: tags.join(" "),
+            frequency,
+        });
+    }
+    cards.sort_by(|a, b| {
+        b.frequency
+            .cmp(&a.frequency)
+            .then_with(|| normalize_lemma(&a.front).cmp(&normalize_lemma(&b.front)))
+    });
+    (cards, missing_gloss, frequent)
+}
+
+/// Vocabulary of one passage with lexicon glosses and NT frequencies, most
+/// frequent first, for the vocabulary table of study documents.
+pub fn passage_vocabulary(
+    passage: &CachedPassage,
+    glossary: &PersonalGlossary,
+) -> Vec<VocabularyEntry> {
+    let mut vocab = Vec::new();
+    collect(&mut vocab, &mut HashMap::new(), passage);
+    let lexicon = lexicon_entries(vocab.iter().map(|v| v.lemma.as_str()));
+    let mut entries: Vec<VocabularyEntry> = vocab
+        .into_iter()
+        .map(|word| {
+            let entry = lexicon.get(&word.lemma);
+            let frequency = entry.and_then(|(_, f)| *f).unwrap_or(word.count);
+            if let Some(personal) = glossary.get(&word.lemma) {
+                return VocabularyEntry {
+                    gloss: Some(personal.gloss.clone()),
+                    source: LemmaSource::Personal,
+                    frequency,
+                    lemma: word.lemma,
+                };
+            }
+            let lexicon_gloss = entry
+                .map(|(e, _)| e.glosses.join("; "))
+                .filter(|g| !g.is_empty());
+            VocabularyEntry {
+                source: if lexicon_gloss.is_some() {
+                    LemmaSource::Offline
+                } else {
+                    LemmaSource::Engine
+                },
+                gloss: lexicon_gloss.or(word.gloss),
+                frequency,
+                lemma: word.lemma,
+            }
+        })
+        .collect();
+    entries.sort_by(|a, b| {
+        b.frequency
+            .cmp(&a.frequency)
+            .then_with(|| normalize_lemma(&a.lemma).cmp(&normalize_lemma(&b.lemma)))
+    });
+    entries
+}
+
+fn write_cards(out: &mut dyn Write, cards: &[Card]) -> std::io::Result<()> {
+    // Header lines understood by Anki 2.1.54 and later
+    writeln!(out, "#separator:tab")?;
+    writeln!(out, "#html:true")?;
+    writeln!(out, "#tags column:3")?;
+    for card in cards {
+        writeln!(
+            out,
+            "{}\t{}\t{}",
+            tsv_field(&card.front),
+            tsv_field(&card.back),
+            tsv_field(&card.tags)
+        )?;
+    }
+    Ok(())
+}
+
+/// Export the vocabulary of cached passages as Anki flashcards.
+///
+/// Each lemma becomes one card, however many passages it occurs in.
+/// `dest_path` should come from `choose_save_path` with kind `flashcards`.
+#[tauri::command]
+pub fn export_flashcards(
+    app: AppHandle,
+    passage_ids: Vec<String>,
+    dest_path: String,
+    options: Option<FlashcardOptions>,
+) -> Result<FlashcardExportReport, AppError> {
+    let options = options.unwrap_or_default();
+    let passage_ids = tags::passage_ids_for_export(&app, passage_ids, options.tag.as_deref())?;
+    let path = validate_export_path(&app, ExportKind::Flashcards, &PathBuf::from(dest_path))?;
+
+    let mut attribution = licensing::export_attribution(options.include_attribution);
+    let mut vocab = Vec::new();
+    let mut index = HashMap::new();
+    let mut failed = Vec::new();
+    for id in &passage_ids {
+        match load_passage(&app, id) {
+            Ok(passage) if occurrences(&passage.response).is_empty() => {
+                failed.push(PassageExportFailure {
+                    passage_id: id.clone(),
+                    error: "No cached token data".to_string(),
+                })
+            }
+            Ok(passage) => {
+                collect(&mut vocab, &mut index, &passage);
+                if let Some(attribution) = &mut attribution {
+                    attribution.add_passage(&passage);
+                }
+            }
+            Err(error) => failed.push(PassageExportFailure {
+                passage_id: id.clone(),
+                error,
+            }),
+        }
+    }
+
+    let (cards, skipped_missing_gloss, skipped_frequent) =
+        build_cards(vocab, &options, &personal_glossary(&app));
+    storage::write_atomic_with(&path, |out| write_cards(out, &cards))?;
+    let attribution_path = match &attribution {
+        Some(attribution) => Some(licensing::write_sidecar(&path, attribution)?),
+        None => None,
+    };
+
+    Ok(FlashcardExportReport {
+        path: path.to_string_lossy().into_owned(),
+        cards_written: cards.len(),
+        skipped_missing_gloss,
+        skipped_frequent,
+        attribution_path: attribution_path.map(|p| p.to_string_lossy().into_owned()),
+        failed,
+    })
+}
+
+/// Deck file path; ids are UUIDs so a crafted id can't leave the directory.
+fn deck_path(app: &AppHandle, id: &str) -> Result<PathBuf, String> {
+    uuid::Uuid::parse_str(id).map_err(|_| format!("Invalid flashcard deck id: {}", id))?;
+    Ok(storage::app_data_subdir(app, DECKS_DIR)?.join(format!("{}.json", id)))
+}
+
+/// The part of speech from a lexicon summary ("noun, masc., 2nd decl."),
+/// else from a Robinson code ("N-NSM"); empty when neither tells.
+fn part_of_speech(summary: Option<&str>, code: &str) -> String {
+    if let Some(first) = summary
+        .and_then(|s| s.split(',').next())
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+    {
+        return first.to_string();
+    }
+    let part = match code.split('-').next().unwrap_or_default() {
+        "N" => "noun",
+        "V" => "verb",
+        "A" => "adjective",
+        "T" => "article",
+        "ADV" => "adverb",
+        "CONJ" | "COND" => "conjunction",
+        "PREP" => "preposition",
+        "PRT" => "particle",
+        "INJ" => "interjection",
+        "P" | "R" | "C" | "D" | "F" | "I" | "K" | "Q" | "S" | "X" => "pronoun",
+        _ => "",
+    };
+    part.to_string()
+}
+
+/// A cached passage of `reference` with token data, else a fresh
+/// translation of it.
+async fn deck_passage(
+    app: &AppHandle,
+    port: u16,
+    reference: &str,
+) -> Result<CachedPassage, EngineError> {
+    let wanted = canonical_reference(reference);
+    let cached = load_all_passages(app)
+        .map_err(EngineError::Storage)?
+        .into_iter()
+        .find(|p| {
+            canonical_reference(&p.normalized_ref) == wanted && !occurrences(&p.response).is_empty()
+        });
+    let passage = match cached {
+        Some(passage) => passage,
+        None => match translate_and_cache(app, port, reference, TranslateOptions::default()).await?
+        {
+            TranslateOutcome::Translation(passage) => *passage,
+            TranslateOutcome::Gate(_) => {
+                return Err(EngineError::FlashcardDeckError(format!(
+                    "{} must be acknowledged in the translation view first",
+                    reference
+                )))
+            }
+        },
+    };
+    if occurrences(&passage.response).is_empty() {
+        return Err(EngineError::FlashcardDeckError(format!(
+            "No token data for {}",
+            reference
+        )));
+    }
+    Ok(passage)
+}
+
+/// The deck name and words of a source, in deck order; the words of a
+/// passage still need sorting by frequency.
+async fn source_vocab(
+    app: &AppHandle,
+    port: u16,
+    source: &FlashcardSource,
+    max_cards: u32,
+) -> Result<(String, Vec<Vocab>), EngineError> {
+    let word = |lemma: String, count: u32| Vocab {
+        lemma,
+        count,
+        morph: String::new(),
+        gloss: None,
+        example: None,
+        books: Vec::new(),
+    };
+    match source {
+        FlashcardSource::StudyList => {
+            let entries = list_personal_glossary(app.clone())
+                .map_err(|e| EngineError::Storage(e.to_string()))?;
+            if entries.is_empty() {
+                return Err(EngineError::FlashcardDeckError(
+                    "The study list is empty".to_string(),
+                ));
+            }
+            let vocab = entries.into_iter().map(|g| word(g.lemma, 0)).collect();
+            Ok(("Study list".to_string(), vocab))
+        }
+        FlashcardSource::FrequencyRange { min_rank, max_rank } => {
+            let min_rank = (*min_rank).max(1);
+            if min_rank > *max_rank {
+                return Err(EngineError::FlashcardDeckError(format!(
+                    "No ranks between {} and {}",
+                    min_rank, max_rank
+                )));
+            }
+            // Ask only for the ranks that fit in the deck
+            let max_rank = (*max_rank).min(min_rank.saturating_add(max_cards - 1));
+            let words =
+                words_by_rank(port, min_rank, max_rank, FrequencyCorpus::NewTestament).await?;
+            let vocab = words
+                .into_iter()
+                .map(|w| word(w.lemma, w.occurrences))
+                .collect();
+            Ok((format!("NT ranks {}–{}", min_rank, max_rank), vocab))
+        }
+        FlashcardSource::PassageReference(reference) => {
+            let validation = validate_scripture_reference(reference.clone());
+            let Some(normalized) = validation.normalized.filter(|_| validation.valid) else {
+                return Err(EngineError::InvalidReference(
+                    validation.error.unwrap_or_default(),
+                ));
+            };
+            let passage = deck_passage(app, port, &normalized).await?;
+            let mut vocab = Vec::new();
+            collect(&mut vocab, &mut HashMap::new(), &passage);
+            Ok((normalized, vocab))
+        }
+    }
+}
+
+/// A card for `word`, or `None` when nothing gives it a gloss. `entry` is
+/// its lexicon entry, offline or from the engine.
+fn deck_card(
+    word: Vocab,
+    entry: Option<&LemmaEntry>,
+    glossary: &PersonalGlossary,
+) -> Option<Flashcard> {
+    let back = glossary
+        .get(&word.lemma)
+        .map(|p| p.gloss.clone())
+        .or_else(|| {
+            entry
+                .map(|e| e.glosses.join("; "))
+                .filter(|g| !g.is_empty())
+                .or(word.gloss)
+        })?;
+    let strongs = entry
+        .and_then(|e| e.strongs.as_deref())
+        .and_then(|number| parse_strongs(number).ok());
+    let summary = entry.and_then(|e| e.morphology.as_deref());
+    Some(Flashcard {
+        part_of_speech: part_of_speech(summary, &word.morph),
+        example_verse: word
+            .example
+            .map(|(verse_id, greek, _)| format!("{} — {}", verse_reference(&verse_id), greek)),
+        front: word.lemma,
+        back,
+        strongs,
+    })
+}
+
+/// Build a deck of at most `max_cards` (1–1000) cards and save it.
+///
+/// Glosses come from the personal glossary, the offline lexicon, the token
+/// ledger or the engine's lexicon, in that order; words none of them
+/// knows are left out. Examples and Robinson codes come from the cached
+/// passages.
+#[tauri::command]
+pub async fn generate_flashcard_deck(
+    app: AppHandle,
+    port: u16,
+    source: FlashcardSource,
+    max_cards: u32,
+) -> Result<FlashcardDeck, EngineError> {
+    let max_cards = max_cards.clamp(1, MAX_DECK_CARDS);
+    let (name, mut vocab) = source_vocab(&app, port, &source, max_cards).await?;
+    let mut lexicon = lexicon_entries(vocab.iter().map(|v| v.lemma.as_str()));
+    if matches!(source, FlashcardSource::PassageReference(_)) {
+        let frequency = |word: &Vocab| {
+            lexicon
+                .get(&word.lemma)
+                .and_then(|(_, f)| *f)
+                .unwrap_or(word.count)
+        };
+        vocab.sort_by(|a, b| {
+            frequency(b)
+                .cmp(&frequency(a))
+                .then_with(|| normalize_lemma(&a.lemma).cmp(&normalize_lemma(&b.lemma)))
+        });
+    }
+    vocab.truncate(max_cards as usize);
+
+    // First occurrence of every lemma in the cached passages, most
+    // recently translated first
+    let mut seen = Vec::new();
+    let mut index = HashMap::new();
+    for passage in load_all_passages(&app).unwrap_or_default() {
+        collect(&mut seen, &mut index, &passage);
+    }
+
+    let glossary = personal_glossary(&app);
+    let mut cards = Vec::new();
+    for mut word in vocab {
+        if let Some(&i) = index.get(&normalize_lemma(&word.lemma)) {
+            let found: &Vocab = &seen[i];
+            if word.example.is_none() {
+                word.example = found.example.clone();
+            }
+            if word.morph.is_empty() {
+                word.morph = found.morph.clone();
+            }
+        }
+        let mut entry = lexicon.remove(&word.lemma).map(|(entry, _)| entry);
+        let has_gloss = glossary.get(&word.lemma).is_some()
+            || word.gloss.is_some()
+            || entry.as_ref().is_some_and(|e| !e.glosses.is_empty());
+        if !has_gloss {
+            let found = lookup_lemma(app.clone(), port, word.lemma.clone()).await;
+            entry = found.ok().flatten().or(entry);
+        }
+        cards.extend(deck_card(word, entry.as_ref(), &glossary));
+    }
+    if cards.is_empty() {
+        return Err(EngineError::FlashcardDeckError(format!(
+            "No glosses found for the words of {}",
+            name
+        )));
+    }
+
+    let deck = FlashcardDeck {
+        id: uuid::Uuid::new_v4().to_string(),
+        name,
+        source,
+        cards,
+        created_at_ms: storage::now_ms(),
+    };
+    let path = deck_path(&app, &deck.id).map_err(EngineError::Storage)?;
+    storage::write_json(&path, &deck).map_err(EngineError::Storage)?;
+    Ok(deck)
+}
+
+/// A deck as Anki notes. Ids and GUIDs are hashed from the deck id and
+/// lemma, so importing the deck again updates it instead of copying it.
+fn anki_deck(deck: &FlashcardDeck) -> AnkiDeck {
+    let digest = |text: &str| Sha256::digest(text.as_bytes());
+    let deck_digest = digest(&deck.id);
+    // 48 bits, so the id survives being read as a JavaScript number
+    let mut id_bytes = [0u8; 8];
+    id_bytes[2..].copy_from_slice(&deck_digest[..6]);
+    let notes = deck
+        .cards
+        .iter()
+        .map(|card| {
+            let guid = digest(&format!("{}\x1f{}", deck.id, normalize_lemma(&card.front)));
+            let strongs = card.strongs.map(|n| format!("G{}", n)).unwrap_or_default();
+            AnkiNote {
+                guid: hex::encode(&guid[..8]),
+                fields: vec![
+                    escape_html(&card.front),
+                    escape_html(&card.back),
+                    escape_html(&card.part_of_speech),
+                    strongs,
+                    escape_html(card.example_verse.as_deref().unwrap_or_default()),
+                ],
+                sort_field: card.front.clone(),
+                tags: vec![DECK_TAG.to_string()],
+            }
+        })
+        .collect();
+    AnkiDeck {
+        id: i64::from_be_bytes(id_bytes),
+        name: deck.name.clone(),
+        notes,
+    }
+}
+
+/// Export a deck made by `generate_flashcard_deck` as an Anki package and
+/// return the number of cards. `output_path` should come from
+/// `choose_save_path` with kind `anki_package`.
+#[tauri::command]
+pub fn export_flashcard_deck_to_anki(
+    app: AppHandle,
+    deck_id: String,
+    output_path: String,
+) -> Result<u32, AppError> {
+    let path = deck_path(&app, &deck_id)?;
+    if !path.exists() {
+        return Err(AppError::NotFound(format!("No flashcard deck {}", deck_id)));
+    }
+    let deck: FlashcardDeck = storage::read_json(&path)?;
+    let output = validate_export_path(&app, ExportKind::AnkiPackage, &PathBuf::from(output_path))?;
+    apkg::write_apkg(&output, &anki_deck(&deck))?;
+    Ok(deck.cards.len() as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::glossary::PersonalGloss;
+    use crate::testsupport::passage::PassageBuilder;
+
+    fn passage(response: Value) -> CachedPassage {
+        PassageBuilder::new()
+            .id("p")
+            .reference("John 1:1")
+            .mode("traceable")
+            .translator("traceable")
+            .verse(
+                "John.1.1",
+                "Ἐν ἀρχῇ ἦν ὁ λόγος",
+                "In the beginning was the Word",
+            )
+            .response(response)
+            .build()
+    }
+
+    fn vocab() -> Vec<Vocab> {
+        let ledger = serde_json::json!({"ledger": [{
+            "verse_id": "John.1.1",
+            "tokens": [
+                {"surface": "ἀρχῇ", "lemma": "ἀρχή", "morph": "N-DSF", "gloss": "beginning"},
+                {"surface": "ὁ", "lemma": "ὁ", "morph": "T-NSM", "gloss": "the"},
+                {"surface": "λόγος", "lemma": "λόγος", "morph": "N-NSM", "gloss": "word"},
+                {"surface": "ὁ", "lemma": "ὁ", "morph": "T-NSM", "gloss": "the"},
+                {"surface": "ἦν", "lemma": "εἰμί", "morph": "V-IAI-3S", "gloss": ""},
+            ],
+        }]});
+        // Verse blocks carry tokens without glosses
+        let blocks = serde_json::json!({"verse_blocks": [{
+            "verse_id": "John.1.1",
+            "tokens": [{"surface": "Λόγος", "lemma": "Λόγος", "morph": "N-NSM"}],
+        }]});
+        let mut vocab = Vec::new();
+        let mut index = HashMap::new();
+        collect(&mut vocab, &mut index, &passage(ledger));
+        collect(&mut vocab, &mut index, &passage(blocks));
+        vocab
+    }
+
+    #[test]
+    fn test_collect_dedupes_lemmas() {
+        let vocab = vocab();
+        let counts: Vec<_> = vocab.iter().map(|v| (v.lemma.as_str(), v.count)).collect();
+        assert_eq!(counts, [("ἀρχή", 1), ("ὁ", 2), ("λόγος", 2), ("εἰμί", 1)]);
+        assert_eq!(vocab[0].books, ["John"]);
+        assert_eq!(vocab[3].gloss, None);
+        assert!(occurrences(&serde_json::json!({"ledger": null})).is_empty());
+    }
+
+    #[test]
+    fn test_build_and_write_cards() {
+        let options = FlashcardOptions {
+            max_frequency: Some(1),
+            include_morphology: true,
+            include_example: true,
+            transliteration: None,
+            tag: None,
+            include_attribution: false,
+        };
+        let (cards, missing, frequent) =
+            build_cards(vocab(), &options, &PersonalGlossary::default());
+        assert_eq!((cards.len(), missing, frequent), (1, 1, 2));
+        assert_eq!(cards[0].front, "ἀρχή");
+        assert!(cards[0].back.starts_with("beginning<br><i>N-DSF</i>"));
+        assert!(cards[0].back.contains("<b>John 1:1</b>"));
+
+        let options = FlashcardOptions {
+            transliteration: Some(TransliterationScheme::Sbl),
+            ..options
+        };
+        let (cards, _, _) = build_cards(vocab(), &options, &PersonalGlossary::default());
+        assert!(cards[0].back.starts_with("archē<br>beginning"));
+
+        let (cards, _, _) = build_cards(
+            vocab(),
+            &FlashcardOptions::default(),
+            &PersonalGlossary::default(),
+        );
+        let fronts: Vec<_> = cards.iter().map(|c| c.front.as_str()).collect();
+        assert_eq!(fronts, ["λόγος", "ὁ", "ἀρχή"]);
+
+        let mut out = Vec::new();
+        write_cards(&mut out, &cards).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("#separator:tab\n#html:true\n#tags column:3\n"));
+        assert!(out.contains("λόγος\tword\tredletters John\n"));
+    }
+
+    #[test]
+    fn test_personal_gloss_on_cards() {
+        let glossary = PersonalGlossary::new(vec![PersonalGloss {
+            lemma: "ειμι".to_string(),
+            gloss: "to be, exist".to_string(),
+            note: None,
+            updated_at_ms: 0,
+        }]);
+        let (cards, missing, _) = build_cards(vocab(), &FlashcardOptions::default(), &glossary);
+        assert_eq!(missing, 0);
+        let card = cards.iter().find(|c| c.front == "εἰμί").unwrap();
+        assert_eq!(card.back, "to be, exist");
+        assert_eq!(card.tags, "redletters personal-gloss John");
+    }
+
+    #[test]
+    fn test_part_of_speech() {
+        assert_eq!(
+            part_of_speech(Some("noun, masc., 2nd decl."), "V-PAI-3S"),
+            "noun"
+        );
+        assert_eq!(part_of_speech(Some(""), "V-IAI-3S"), "verb");
+        assert_eq!(part_of_speech(None, "T-NSM"), "article");
+        assert_eq!(part_of_speech(None, "ADV"), "adverb");
+        assert_eq!(part_of_speech(None, "R-GSM"), "pronoun");
+        assert_eq!(part_of_speech(None, ""), "");
+    }
+
+    #[test]
+    fn test_deck_cards() {
+        let glossary = PersonalGlossary::new(vec![PersonalGloss {
+            lemma: "ἀρχή".to_string(),
+            gloss: "origin".to_string(),
+            note: None,
+            updated_at_ms: 0,
+        }]);
+        let entry = LemmaEntry {
+            lemma: "λόγος".to_string(),
+            glosses: vec!["word".to_string(), "message".to_string()],
+            morphology: Some("noun, masc., 2nd decl.".to_string()),
+            strongs: Some("G3056".to_string()),
+            source: LemmaSource::Engine,
+        };
+        let mut words = vocab().into_iter();
+        let arche = deck_card(words.next().unwrap(), None, &glossary).unwrap();
+        assert_eq!(arche.back, "origin");
+        assert_eq!(arche.part_of_speech, "noun");
+        assert_eq!(
+            arche.example_verse.as_deref(),
+            Some("John 1:1 — Ἐν ἀρχῇ ἦν ὁ λόγος")
+        );
+        let logos = deck_card(words.nth(1).unwrap(), Some(&entry), &glossary).unwrap();
+        assert_eq!(
+            (logos.back.as_str(), logos.strongs),
+            ("word; message", Some(3056))
+        );
+        // εἰμί has no gloss anywhere
+        assert_eq!(deck_card(words.next().unwrap(), None, &glossary), None);
+
+        let deck = FlashcardDeck {
+            id: "0b5e3c1e-6f0a-4d7a-9a51-0d6f3c2b7e10".to_string(),
+            name: "John 1:1".to_string(),
+            source: FlashcardSource::PassageReference("John 1:1".to_string()),
+            cards: vec![arche, logos],
+            created_at_ms: 0,
+        };
+        let anki = anki_deck(&deck);
+        assert!(anki.id > 0 && anki.id < 1 << 48);
+        assert_eq!(anki.id, anki_deck(&deck).id);
+        assert_eq!(anki.notes[1].fields[3], "G3056");
+        assert_eq!(anki.notes[1].sort_field, "λόγος");
+        assert_ne!(anki.notes[0].guid, anki.notes[1].guid);
+
+        let source: FlashcardSource =
+            serde_json::from_str(r#"{"frequency_range": {"min_rank": 1, "max_rank": 50}}"#)
+                .unwrap();
+        assert_eq!(
+            source,
+            FlashcardSource::FrequencyRange {
+                min_rank: 1,
+                max_rank: 50
+            }
+        );
+        assert_eq!(
+            serde_json::to_string(&FlashcardSource::StudyList).unwrap(),
+            r#""study_list""#
+        );
+    }
+}
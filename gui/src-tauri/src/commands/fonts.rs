@@ -0,0 +1,576 @@
+//! Installed fonts for the font picker.
+//!
+//! Each platform lists font files its own way: CTFontManager on macOS,
+//! GDI on Windows, and the user and system font directories on Linux.
+//! The files are then read with `ttf-parser`, so Greek coverage comes from
+//! the font's `cmap` table rather than its name.
+//!
+//! `install_bundled_font` copies one of the fonts shipped in the app's
+//! `fonts/` resources into the user's (or the system's) font directory.
+//! The files live in `src-tauri/fonts/` and are listed in
+//! `bundle.resources`.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::path::BaseDirectory;
+use tauri::{AppHandle, Manager};
+use ttf_parser::{name_id, Face, Language, Tag};
+
+#[cfg(not(target_os = "macos"))]
+use super::errors::AppError;
+use super::process;
+
+/// Letters a font needs to be offered for Greek
+const GREEK: &str = "ΑΒΓΔΕΖΗΘΙΚΛΜΝΞΟΠΡΣΤΥΦΧΨΩαβγδεζηθικλμνξοπρςστυφχψω";
+/// Breathings, accents and iota subscripts from Greek Extended
+const POLYTONIC_GREEK: &str = "ἀἁἄἅἂἃἆἇὰᾶᾳᾴᾷἐἑἔἕὲἠἡἤἥἢἣἦἧὴῆῃῄῇἰἱἴἵἶἷὶῖὀὁὄὅὸὐὑὔὕὖὗὺῦὠὡὤὥὦὧὼῶῳῴῷῤῥ";
+/// Font files shipped in the `fonts/` resources (DejaVu Serif, which has
+/// full polytonic coverage)
+pub const BUNDLED_FONTS: &[&str] = &["DejaVuSerif.ttf", "DejaVuSerif-Bold.ttf"];
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FontFilter {
+    /// Only fonts covering the Greek alphabet
+    #[serde(default)]
+    pub supports_greek: bool,
+    #[serde(default)]
+    pub is_monospace: Option<bool>,
+    /// Fonts that don't say whether they're serif are left out when set
+    #[serde(default)]
+    pub is_serif: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FontInfo {
+    pub family: String,
+    pub file_path: String,
+    /// Installed for all users rather than in the user's own font directory
+    pub is_system: bool,
+    pub supports_polytonic_greek: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FontInstallResult {
+    pub installed_path: String,
+    /// Other apps (and this one) may not see the font until restarted
+    pub needs_restart: bool,
+}
+
+/// A font file found by the platform listing.
+struct FontFile {
+    path: PathBuf,
+    is_system: bool,
+}
+
+/// What was read from one face of a font file.
+#[derive(Clone)]
+struct FontFace {
+    info: FontInfo,
+    /// Family name from the `name` table's legacy slot, which GDI lists
+    #[cfg(target_os = "windows")]
+    legacy_family: String,
+    supports_greek: bool,
+    monospace: bool,
+    serif: Option<bool>,
+    /// Upright, normal weight; preferred as the family's file
+    regular: bool,
+}
+
+impl FontFace {
+    fn matches(&self, filter: &FontFilter) -> bool {
+        (!filter.supports_greek || self.supports_greek)
+            && filter.is_monospace.is_none_or(|m| m == self.monospace)
+            && filter.is_serif.is_none_or(|s| Some(s) == self.serif)
+    }
+}
+
+/// Font files under `dir`, searched recursively.
+#[cfg(not(target_os = "macos"))]
+fn collect_font_files(dir: &std::path::Path, is_system: bool, files: &mut Vec<FontFile>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for path in entries.flatten().map(|e| e.path()) {
+        if path.is_dir() {
+            collect_font_files(&path, is_system, files);
+            continue;
+        }
+        let is_font = path.extension().and_then(|e| e.to_str()).is_some_and(|e| {
+            ["ttf", "otf", "ttc", "otc"].contains(&e.to_ascii_lowercase().as_str())
+        });
+        if is_font {
+            files.push(FontFile { path, is_system });
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn font_files() -> Vec<FontFile> {
+    use core_foundation::array::CFArray;
+    use core_foundation::base::TCFType;
+    use core_foundation::url::CFURL;
+    use core_text::font_manager::CTFontManagerCopyAvailableFontURLs;
+
+    let user_fonts = dirs::home_dir().map(|home| home.join("Library/Fonts"));
+    // Copy rule: the array is ours to release
+    let urls: CFArray<CFURL> =
+        unsafe { CFArray::wrap_under_create_rule(CTFontManagerCopyAvailableFontURLs()) };
+    urls.iter()
+        .filter_map(|url| url.to_path())
+        .map(|path| FontFile {
+            is_system: !user_fonts.as_ref().is_some_and(|dir| path.starts_with(dir)),
+            path,
+        })
+        .collect()
+}
+
+#[cfg(target_os = "windows")]
+fn font_files() -> Vec<FontFile> {
+    let mut files = Vec::new();
+    if let Some(windir) = std::env::var_os("WINDIR") {
+        collect_font_files(&PathBuf::from(windir).join("Fonts"), true, &mut files);
+    }
+    if let Some(local) = dirs::data_local_dir() {
+        let user_fonts = local.join("Microsoft").join("Windows").join("Fonts");
+        collect_font_files(&user_fonts, false, &mut files);
+    }
+    files
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn font_files() -> Vec<FontFile> {
+    let mut files = Vec::new();
+    if let Some(data) = dirs::data_dir() {
+        collect_font_files(&data.join("fonts"), false, &mut files);
+    }
+    collect_font_files(std::path::Path::new("/usr/share/fonts"), true, &mut files);
+    files
+}
+
+/// Lowercased family names GDI has installed.
+#[cfg(target_os = "windows")]
+fn gdi_families() -> std::collections::HashSet<String> {
+    use std::collections::HashSet;
+    use windows_sys::Win32::Foundation::LPARAM;
+    use windows_sys::Win32::Graphics::Gdi::{
+        EnumFontFamiliesExW, GetDC, ReleaseDC, DEFAULT_CHARSET, LOGFONTW, TEXTMETRICW,
+    };
+
+    unsafe extern "system" fn add_family(
+        logfont: *const LOGFONTW,
+        _: *const TEXTMETRICW,
+        _: u32,
+        families: LPARAM,
+    ) -> i32 {
+        let families = &mut *(families as *mut HashSet<String>);
+        let name = &(*logfont).lfFaceName;
+        let len = name.iter().position(|c| *c == 0).unwrap_or(name.len());
+        let family = String::from_utf16_lossy(&name[..len]);
+        // Vertical-writing variants are listed again as "@Family"
+        if !family.starts_with('@') {
+            families.insert(family.to_lowercase());
+        }
+        1
+    }
+
+    let mut families = HashSet::new();
+    unsafe {
+        let hdc = GetDC(std::ptr::null_mut());
+        if hdc.is_null() {
+            return families;
+        }
+        // An empty face name with DEFAULT_CHARSET lists every family
+        let mut logfont: LOGFONTW = std::mem::zeroed();
+        logfont.lfCharSet = DEFAULT_CHARSET;
+        EnumFontFamiliesExW(
+            hdc,
+            &logfont,
+            Some(add_family),
+            &mut families as *mut HashSet<String> as LPARAM,
+            0,
+        );
+        ReleaseDC(std::ptr::null_mut(), hdc);
+    }
+    families
+}
+
+/// A name from the `name` table, in English where the font has it.
+fn face_name(face: &Face, id: u16) -> Option<String> {
+    let names: Vec<_> = face
+        .names()
+        .into_iter()
+        .filter(|n| n.name_id == id)
+        .collect();
+    names
+        .iter()
+        .filter(|n| n.language() == Language::English_UnitedStates)
+        .chain(names.iter())
+        .filter_map(|n| n.to_string())
+        .map(|n| n.trim().to_string())
+        .find(|n| !n.is_empty())
+}
+
+/// Serif or sans from the PANOSE classification in `OS/2`, falling back
+/// to the family name. `None` when neither says.
+fn serif_style(os2: Option<&[u8]>, family: &str) -> Option<bool> {
+    // PANOSE starts at byte 32: family kind, then serif style
+    let panose = os2.and_then(|t| t.get(32..34));
+    if let Some(&[2, style]) = panose {
+        match style {
+            2..=10 => return Some(true),
+            11..=15 => return Some(false),
+            _ => {}
+        }
+    }
+    let family = family.to_lowercase();
+    if family.contains("sans") {
+        Some(false)
+    } else if family.contains("serif") {
+        Some(true)
+    } else {
+        None
+    }
+}
+
+fn covers(face: &Face, chars: &str) -> bool {
+    chars.chars().all(|c| face.glyph_index(c).is_some())
+}
+
+fn font_face(face: &Face, file: &FontFile) -> Option<FontFace> {
+    let family = face_name(face, name_id::TYPOGRAPHIC_FAMILY)
+        .or_else(|| face_name(face, name_id::FAMILY))?;
+    let os2 = face.raw_face().table(Tag::from_bytes(b"OS/2"));
+    let supports_greek = covers(face, GREEK);
+    Some(FontFace {
+        serif: serif_style(os2, &family),
+        // PANOSE proportion 9 is monospaced, for fonts that leave `post` unset
+        monospace: face.is_monospaced()
+            || os2.and_then(|t| t.get(32).zip(t.get(35))) == Some((&2, &9)),
+        regular: !face.is_bold() && !face.is_italic(),
+        supports_greek,
+        #[cfg(target_os = "windows")]
+        legacy_family: face_name(face, name_id::FAMILY).unwrap_or_else(|| family.clone()),
+        info: FontInfo {
+            family,
+            file_path: file.path.to_string_lossy().into_owned(),
+            is_system: file.is_system,
+            supports_polytonic_greek: supports_greek && covers(face, POLYTONIC_GREEK),
+        },
+    })
+}
+
+/// Every face in a font file; unreadable files are skipped.
+fn read_faces(file: &FontFile) -> Vec<FontFace> {
+    let Ok(data) = std::fs::read(&file.path) else {
+        return Vec::new();
+    };
+    let count = ttf_parser::fonts_in_collection(&data).unwrap_or(1);
+    (0..count)
+        .filter_map(|index| Face::parse(&data, index).ok())
+        .filter_map(|face| font_face(&face, file))
+        .collect()
+}
+
+/// One entry per family, preferring its regular face, sorted by family.
+fn pick_families(mut faces: Vec<FontFace>, filter: &FontFilter) -> Vec<FontInfo> {
+    faces.retain(|f| f.matches(filter));
+    faces.sort_by_cached_key(|f| {
+        (
+            f.info.family.to_lowercase(),
+            !f.regular,
+            f.info.file_path.clone(),
+        )
+    });
+    faces.dedup_by_key(|f| f.info.family.to_lowercase());
+    faces.into_iter().map(|f| f.info).collect()
+}
+
+fn list_fonts(filter: &FontFilter) -> Vec<FontInfo> {
+    let mut files = font_files();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    files.dedup_by(|a, b| a.path == b.path);
+    #[allow(unused_mut)]
+    let mut faces: Vec<FontFace> = files.iter().flat_map(read_faces).collect();
+    // Files GDI hasn't registered aren't usable by name
+    #[cfg(target_os = "windows")]
+    {
+        let installed = gdi_families();
+        faces.retain(|f| {
+            installed.contains(&f.legacy_family.to_lowercase())
+                || installed.contains(&f.info.family.to_lowercase())
+        });
+    }
+    pick_families(faces, filter)
+}
+
+/// Installed font families for the font picker, sorted by name.
+#[tauri::command]
+pub async fn get_font_list(filter: FontFilter) -> Result<Vec<FontInfo>, AppError> {
+    Ok(
+        tauri::async_runtime::spawn_blocking(move || list_fonts(&filter))
+            .await
+            .map_err(|e| e.to_string())?,
+    )
+}
+
+/// `fontRevision` from the `head` table, as 16.16 fixed point.
+fn font_revision(face: &Face) -> Option<i32> {
+    let head = face.raw_face().table(Tag::from_bytes(b"head"))?;
+    Some(i32::from_be_bytes(head.get(4..8)?.try_into().ok()?))
+}
+
+fn format_revision(revision: i32) -> String {
+    format!("{:.3}", f64::from(revision) / 65536.0)
+}
+
+/// Whether to copy a font of revision `bundled` over the installed copy.
+/// A newer installed copy is an error rather than a silent downgrade.
+fn should_install(bundled: i32, installed: Option<i32>) -> Result<bool, String> {
+    match installed {
+        Some(installed) if installed > bundled => Err(format!(
+            "A newer version ({}) of this font is already installed (bundled: {})",
+            format_revision(installed),
+            format_revision(bundled)
+        )),
+        Some(installed) => Ok(installed < bundled),
+        None => Ok(true),
+    }
+}
+
+/// Where fonts are installed for the user, or for everyone.
+#[cfg(target_os = "macos")]
+fn install_dir(user_only: bool) -> Option<PathBuf> {
+    match user_only {
+        true => dirs::home_dir().map(|home| home.join("Library/Fonts")),
+        false => Some(PathBuf::from("/Library/Fonts")),
+    }
+}
+
+/// Where fonts are installed for the user, or for everyone. Per-user
+/// fonts live under the local, not roaming, app data.
+#[cfg(target_os = "windows")]
+fn install_dir(user_only: bool) -> Option<PathBuf> {
+    match user_only {
+        true => dirs::data_local_dir().map(|d| d.join("Microsoft").join("Windows").join("Fonts")),
+        false => std::env::var_os("WINDIR").map(|w| PathBuf::from(w).join("Fonts")),
+    }
+}
+
+/// Where fonts are installed for the user, or for everyone.
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn install_dir(user_only: bool) -> Option<PathBuf> {
+    match user_only {
+        true => dirs::data_dir().map(|d| d.join("fonts")),
+        false => Some(PathBuf::from("/usr/local/share/fonts")),
+    }
+}
+
+/// Make a newly copied font visible; returns whether that worked
+/// without a restart.
+#[cfg(target_os = "macos")]
+fn register_font(_path: &Path, _family: &str, _user_only: bool) -> bool {
+    // Core Text watches the font folders
+    true
+}
+
+#[cfg(target_os = "windows")]
+fn register_font(path: &Path, family: &str, user_only: bool) -> bool {
+    let key = match user_only {
+        true => r"HKCU\Software\Microsoft\Windows NT\CurrentVersion\Fonts",
+        false => r"HKLM\Software\Microsoft\Windows NT\CurrentVersion\Fonts",
+    };
+    let value = format!("{} (TrueType)", family);
+    let registered = process::command("reg")
+        .args(["add", key, "/v", &value, "/t", "REG_SZ", "/d"])
+        .arg(path)
+        .arg("/f")
+        .status()
+        .is_ok_and(|s| s.success());
+    if !registered {
+        tracing::warn!("Could not register font {}", path.display());
+    }
+    // Running apps only see it after a WM_FONTCHANGE or a restart
+    false
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn register_font(_path: &Path, _family: &str, _user_only: bool) -> bool {
+    process::command("fc-cache")
+        .arg("-f")
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+fn install_font(source: &Path, user_only: bool) -> Result<FontInstallResult, String> {
+    let data = std::fs::read(source).map_err(|e| format!("{}: {}", source.display(), e))?;
+    let face = Face::parse(&data, 0).map_err(|e| format!("{}: {}", source.display(), e))?;
+    let family = face_name(&face, name_id::FAMILY).unwrap_or_default();
+    let revision = font_revision(&face).unwrap_or(0);
+
+    let dir = install_dir(user_only).ok_or("Could not find the font directory")?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    let dest = dir.join(source.file_name().ok_or("Invalid font path")?);
+    let installed = std::fs::read(&dest).ok().and_then(|installed| {
+        Face::parse(&installed, 0)
+            .ok()
+            .map(|face| font_revision(&face).unwrap_or(0))
+    });
+    if !should_install(revision, installed)? {
+        return Ok(FontInstallResult {
+            installed_path: dest.to_string_lossy().into_owned(),
+            needs_restart: false,
+        });
+    }
+
+    std::fs::write(&dest, &data).map_err(|e| format!("{}: {}", dest.display(), e))?;
+    Ok(FontInstallResult {
+        needs_restart: !register_font(&dest, &family, user_only),
+        installed_path: dest.to_string_lossy().into_owned(),
+    })
+}
+
+/// Install one of [`BUNDLED_FONTS`], e.g. "DejaVuSerif.ttf", for the user
+/// or (with `user_only` false) for everyone, which needs admin rights. An
+/// installed copy with the same file name is replaced only by a newer
+/// revision.
+#[tauri::command]
+pub async fn install_bundled_font(
+    app: AppHandle,
+    font_name: String,
+    user_only: bool,
+) -> Result<FontInstallResult, AppError> {
+    if !BUNDLED_FONTS.contains(&font_name.as_str()) {
+        return Err(AppError::NotFound(format!(
+            "No bundled font named {}",
+            font_name
+        )));
+    }
+    let source = app
+        .path()
+        .resolve(format!("fonts/{}", font_name), BaseDirectory::Resource)
+        .map_err(|e| e.to_string())?;
+    if !source.is_file() {
+        return Err(AppError::NotFound(format!(
+            "No bundled font named {}",
+            font_name
+        )));
+    }
+    Ok(
+        tauri::async_runtime::spawn_blocking(move || install_font(&source, user_only))
+            .await
+            .map_err(|e| e.to_string())??,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn face(family: &str, path: &str, regular: bool, greek: bool) -> FontFace {
+        FontFace {
+            info: FontInfo {
+                family: family.to_string(),
+                file_path: path.to_string(),
+                is_system: true,
+                supports_polytonic_greek: greek,
+            },
+            #[cfg(target_os = "windows")]
+            legacy_family: family.to_string(),
+            supports_greek: greek,
+            monospace: family.contains("Mono"),
+            serif: serif_style(None, family),
+            regular,
+        }
+    }
+
+    #[test]
+    fn test_serif_style() {
+        let os2 = |kind: u8, style: u8| {
+            let mut table = vec![0; 42];
+            table[32] = kind;
+            table[33] = style;
+            table
+        };
+        assert_eq!(serif_style(Some(&os2(2, 2)), "Gentium"), Some(true));
+        assert_eq!(serif_style(Some(&os2(2, 11)), "Gentium"), Some(false));
+        // Unclassified or not Latin text: the name decides
+        assert_eq!(serif_style(Some(&os2(0, 0)), "Noto Sans"), Some(false));
+        assert_eq!(serif_style(Some(&os2(3, 2)), "Noto Serif"), Some(true));
+        assert_eq!(serif_style(None, "Gentium"), None);
+    }
+
+    #[test]
+    fn test_pick_families() {
+        let faces = vec![
+            face("gentium", "/f/GentiumBold.ttf", false, true),
+            face("Gentium", "/f/Gentium.ttf", true, true),
+            face("Noto Sans Mono", "/f/NotoSansMono.ttf", true, false),
+            face("Arimo", "/f/Arimo.ttf", true, false),
+            face("Noto Serif", "/f/NotoSerif.ttf", true, true),
+        ];
+        let families = |filter: FontFilter| -> Vec<(String, String)> {
+            pick_families(faces.clone(), &filter)
+                .into_iter()
+                .map(|f| (f.family, f.file_path))
+                .collect()
+        };
+        let all = families(FontFilter::default());
+        assert_eq!(
+            all.iter()
+                .map(|(f, p)| (f.as_str(), p.as_str()))
+                .collect::<Vec<_>>(),
+            [
+                ("Arimo", "/f/Arimo.ttf"),
+                ("Gentium", "/f/Gentium.ttf"),
+                ("Noto Sans Mono", "/f/NotoSansMono.ttf"),
+                ("Noto Serif", "/f/NotoSerif.ttf"),
+            ]
+        );
+
+        let greek_serif = families(FontFilter {
+            supports_greek: true,
+            is_serif: Some(true),
+            ..Default::default()
+        });
+        assert_eq!(greek_serif.len(), 1);
+        assert_eq!(greek_serif[0].0, "Noto Serif");
+
+        let mono = families(FontFilter {
+            is_monospace: Some(true),
+            ..Default::default()
+        });
+        assert_eq!(mono.len(), 1);
+        assert_eq!(mono[0].0, "Noto Sans Mono");
+    }
+
+    #[test]
+    fn test_should_install() {
+        let v = |major: i32, minor: i32| major * 65536 + minor * 65536 / 1000;
+        assert_eq!(should_install(v(6, 200), None), Ok(true));
+        assert_eq!(should_install(v(6, 200), Some(v(6, 101))), Ok(true));
+        assert_eq!(should_install(v(6, 200), Some(v(6, 200))), Ok(false));
+        let err = should_install(v(6, 200), Some(v(6, 300))).unwrap_err();
+        assert!(err.contains("(6.300)"), "{}", err);
+    }
+
+    #[test]
+    fn test_bundled_fonts_resolve() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let config: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(root.join("tauri.conf.json")).unwrap()).unwrap();
+        assert_eq!(
+            config["bundle"]["resources"],
+            serde_json::json!(["fonts/*"]),
+            "bundle.resources must ship the fonts/ directory"
+        );
+        for name in BUNDLED_FONTS {
+            let file = FontFile {
+                path: root.join("fonts").join(name),
+                is_system: false,
+            };
+            let faces = read_faces(&file);
+            assert_eq!(faces.len(), 1, "{} is missing or unreadable", name);
+            assert!(faces[0].info.supports_polytonic_greek, "{}", name);
+        }
+    }
+}
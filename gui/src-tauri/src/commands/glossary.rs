@@ -0,0 +1,440 @@
+//! Personal glossary: the user's own glosses, which take the place of the
+//! engine's and the lexicon's wherever a lemma is shown.
+//!
+//! Entries live in `personal_glossary.json` under the app data dir and are
+//! keyed by `normalize_lemma`, so "λόγος" and "λογος" are the same entry.
+//! Lexicon lookups and the interlinear, flashcard and study-document
+//! exports consult the glossary and mark the glosses it supplied with the
+//! `personal` source. The glossary moves between machines as JSON or CSV.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+use super::document_export::escape_markdown;
+use super::errors::AppError;
+use super::interlinear_table::csv_field;
+use super::lexicon::{normalize_lemma, LemmaEntry, LemmaSource};
+use super::passage_lists::parse_csv;
+use super::save_dialog::validate_export_path;
+use super::saved_results::ExportFormat;
+use super::storage::{self, now_ms};
+
+const GLOSSARY_FILE: &str = "personal_glossary.json";
+/// Larger files are not glossary exports
+const MAX_IMPORT_BYTES: u64 = 10 * 1024 * 1024;
+/// Columns of the CSV export, in order
+const CSV_COLUMNS: [&str; 4] = ["lemma", "gloss", "note", "updated_at_ms"];
+const MAX_GLOSS_LEN: usize = 500;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PersonalGloss {
+    /// As the user typed it, accents included
+    pub lemma: String,
+    pub gloss: String,
+    #[serde(default)]
+    pub note: Option<String>,
+    #[serde(default)]
+    pub updated_at_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GlossaryImportResult {
+    pub imported: u32,
+    /// Lemmas that already had a gloss, now replaced
+    pub replaced: u32,
+    /// Rows that could not be read, with the reason
+    pub errors: Vec<String>,
+}
+
+/// The glossary keyed by normalized lemma, for renderers.
+#[derive(Debug, Clone, Default)]
+pub struct PersonalGlossary {
+    entries: HashMap<String, PersonalGloss>,
+}
+
+impl PersonalGlossary {
+    pub fn new(entries: Vec<PersonalGloss>) -> Self {
+        Self {
+            entries: entries
+                .into_iter()
+                .map(|g| (normalize_lemma(&g.lemma), g))
+                .collect(),
+        }
+    }
+
+    pub fn get(&self, lemma: &str) -> Option<&PersonalGloss> {
+        self.entries.get(&normalize_lemma(lemma))
+    }
+
+    /// The personal gloss in place of the entry's glosses, if there is one.
+    pub fn apply(&self, mut entry: LemmaEntry) -> LemmaEntry {
+        if let Some(personal) = self.get(&entry.lemma) {
+            entry.glosses = vec![personal.gloss.clone()];
+            entry.source = LemmaSource::Personal;
+        }
+        entry
+    }
+
+    /// An entry carrying only the personal gloss, for lemmas no lexicon
+    /// knows.
+    pub fn entry(&self, lemma: &str) -> Option<LemmaEntry> {
+        self.get(lemma).map(|personal| LemmaEntry {
+            lemma: personal.lemma.clone(),
+            glosses: vec![personal.gloss.clone()],
+            morphology: None,
+            strongs: None,
+            source: LemmaSource::Personal,
+        })
+    }
+}
+
+fn load_entries(app: &AppHandle) -> Result<Vec<PersonalGloss>, String> {
+    storage::read_json_or_default(&storage::app_data_file(app, GLOSSARY_FILE)?)
+}
+
+fn store_entries(app: &AppHandle, entries: &mut [PersonalGloss]) -> Result<(), String> {
+    entries.sort_by_cached_key(|g| normalize_lemma(&g.lemma));
+    storage::write_json(&storage::app_data_file(app, GLOSSARY_FILE)?, &entries)
+}
+
+/// The glossary for renderers; empty (with a warning) if it can't be read,
+/// so exports still work.
+pub fn personal_glossary(app: &AppHandle) -> PersonalGlossary {
+    match load_entries(app) {
+        Ok(entries) => PersonalGlossary::new(entries),
+        Err(e) => {
+            tracing::warn!("Could not read the personal glossary: {}", e);
+            PersonalGlossary::default()
+        }
+    }
+}
+
+/// Validate and trim an entry.
+fn clean_gloss(mut entry: PersonalGloss) -> Result<PersonalGloss, String> {
+    entry.lemma = entry.lemma.trim().to_string();
+    entry.gloss = entry.gloss.trim().to_string();
+    entry.note = entry
+        .note
+        .map(|n| n.trim().to_string())
+        .filter(|n| !n.is_empty());
+    if normalize_lemma(&entry.lemma).is_empty() {
+        return Err("A personal gloss needs a lemma".to_string());
+    }
+    if entry.gloss.is_empty() {
+        return Err(format!("The gloss for {} is empty", entry.lemma));
+    }
+    if entry.gloss.chars().count() > MAX_GLOSS_LEN {
+        return Err(format!(
+            "Glosses are limited to {} characters",
+            MAX_GLOSS_LEN
+        ));
+    }
+    Ok(entry)
+}
+
+/// Add or replace entries by normalized lemma. Returns how many were new
+/// and how many replaced an existing gloss.
+fn upsert(entries: &mut Vec<PersonalGloss>, incoming: Vec<PersonalGloss>) -> (u32, u32) {
+    let (mut added, mut replaced) = (0, 0);
+    for entry in incoming {
+        let key = normalize_lemma(&entry.lemma);
+        match entries
+            .iter_mut()
+            .find(|g| normalize_lemma(&g.lemma) == key)
+        {
+            Some(existing) => {
+                *existing = entry;
+                replaced += 1;
+            }
+            None => {
+                entries.push(entry);
+                added += 1;
+            }
+        }
+    }
+    (added, replaced)
+}
+
+fn write_glossary(
+    out: &mut dyn Write,
+    format: ExportFormat,
+    entries: &[PersonalGloss],
+) -> std::io::Result<()> {
+    match format {
+        ExportFormat::Json => {
+            serde_json::to_writer_pretty(&mut *out, entries)?;
+            writeln!(out)
+        }
+        ExportFormat::Csv => {
+            write!(out, "{}\r\n", CSV_COLUMNS.join(","))?;
+            for g in entries {
+                write!(
+                    out,
+                    "{},{},{},{}\r\n",
+                    csv_field(&g.lemma),
+                    csv_field(&g.gloss),
+                    csv_field(g.note.as_deref().unwrap_or("")),
+                    g.updated_at_ms
+                )?;
+            }
+            Ok(())
+        }
+        ExportFormat::Markdown => {
+            writeln!(out, "# Personal glossary\n")?;
+            for g in entries {
+                write!(
+                    out,
+                    "- **{}** — {}",
+                    escape_markdown(&g.lemma),
+                    escape_markdown(&g.gloss)
+                )?;
+                if let Some(note) = &g.note {
+                    write!(out, " *({})*", escape_markdown(note))?;
+                }
+                writeln!(out)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Entries from an exported CSV file. Only `lemma` and `gloss` columns are
+/// required, so hand-made spreadsheets import too.
+fn read_csv(text: &str) -> Result<(Vec<PersonalGloss>, Vec<String>), String> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut records = parse_csv(text, ',').into_iter();
+    let (_, header) = records.next().ok_or("The CSV file is empty")?;
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|h| h.trim().eq_ignore_ascii_case(name))
+    };
+    let (Some(lemma), Some(gloss)) = (column("lemma"), column("gloss")) else {
+        return Err("The CSV file needs \"lemma\" and \"gloss\" columns".to_string());
+    };
+    let (note, updated) = (column("note"), column("updated_at_ms"));
+
+    let mut entries = Vec::new();
+    let mut errors = Vec::new();
+    for (line, record) in records {
+        let field = |i: Option<usize>| i.and_then(|i| record.get(i)).cloned();
+        let updated_at_ms = match field(updated).filter(|v| !v.trim().is_empty()) {
+            Some(value) => match value.trim().parse() {
+                Ok(ms) => ms,
+                Err(_) => {
+                    errors.push(format!("line {}: invalid updated_at_ms", line));
+                    continue;
+                }
+            },
+            None => now_ms(),
+        };
+        entries.push(PersonalGloss {
+            lemma: field(Some(lemma)).unwrap_or_default(),
+            gloss: field(Some(gloss)).unwrap_or_default(),
+            note: field(note).filter(|n| !n.is_empty()),
+            updated_at_ms,
+        });
+    }
+    Ok((entries, errors))
+}
+
+fn is_csv(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"))
+}
+
+/// Set the gloss shown for a lemma everywhere, replacing any earlier one.
+#[tauri::command]
+pub fn set_personal_gloss(
+    app: AppHandle,
+    lemma: String,
+    gloss: String,
+    note: Option<String>,
+) -> Result<PersonalGloss, AppError> {
+    let entry = clean_gloss(PersonalGloss {
+        lemma,
+        gloss,
+        note,
+        updated_at_ms: now_ms(),
+    })?;
+    let mut entries = load_entries(&app)?;
+    upsert(&mut entries, vec![entry.clone()]);
+    store_entries(&app, &mut entries)?;
+    Ok(entry)
+}
+
+/// The personal gloss for a lemma, matched without accents or case.
+#[tauri::command]
+pub fn get_personal_gloss(
+    app: AppHandle,
+    lemma: String,
+) -> Result<Option<PersonalGloss>, AppError> {
+    Ok(PersonalGlossary::new(load_entries(&app)?)
+        .get(&lemma)
+        .cloned())
+}
+
+/// Every personal gloss, in alphabetical order of the lemma.
+#[tauri::command]
+pub fn list_personal_glossary(app: AppHandle) -> Result<Vec<PersonalGloss>, AppError> {
+    Ok(load_entries(&app)?)
+}
+
+/// Remove the personal gloss for a lemma; returns whether there was one.
+#[tauri::command]
+pub fn delete_personal_gloss(app: AppHandle, lemma: String) -> Result<bool, AppError> {
+    let key = normalize_lemma(&lemma);
+    let mut entries = load_entries(&app)?;
+    let before = entries.len();
+    entries.retain(|g| normalize_lemma(&g.lemma) != key);
+    if entries.len() == before {
+        return Ok(false);
+    }
+    store_entries(&app, &mut entries)?;
+    Ok(true)
+}
+
+/// Write the glossary to a file and return how many entries were written.
+///
+/// `output_path` should come from `choose_save_path` with the kind that
+/// matches `format`.
+#[tauri::command]
+pub fn export_personal_glossary(
+    app: AppHandle,
+    output_path: String,
+    format: ExportFormat,
+) -> Result<u32, AppError> {
+    let path = validate_export_path(&app, format.kind(), &PathBuf::from(output_path))?;
+    let entries = load_entries(&app)?;
+    storage::write_atomic_with(&path, |out| write_glossary(out, format, &entries))?;
+    Ok(entries.len() as u32)
+}
+
+/// Read a glossary exported as JSON or CSV (by extension).
+///
+/// With `merge` the entries are added to the glossary, replacing glosses
+/// for the same lemmas; otherwise they replace the whole glossary.
+#[tauri::command]
+pub fn import_personal_glossary(
+    app: AppHandle,
+    input_path: String,
+    merge: bool,
+) -> Result<GlossaryImportResult, AppError> {
+    let path = PathBuf::from(&input_path);
+    let size = fs::metadata(&path)
+        .map_err(|e| format!("{}: {}", input_path, e))?
+        .len();
+    if size > MAX_IMPORT_BYTES {
+        return Err(AppError::InvalidInput(format!(
+            "{} is too large for a glossary",
+            input_path
+        )));
+    }
+    let text = fs::read_to_string(&path).map_err(|e| format!("{}: {}", input_path, e))?;
+    let (read, mut errors) = if is_csv(&path) {
+        read_csv(&text)?
+    } else {
+        let entries: Vec<PersonalGloss> =
+            serde_json::from_str(&text).map_err(|e| format!("{}: {}", input_path, e))?;
+        (entries, Vec::new())
+    };
+
+    let mut incoming = Vec::new();
+    for entry in read {
+        let lemma = entry.lemma.clone();
+        match clean_gloss(entry) {
+            Ok(entry) => incoming.push(entry),
+            Err(e) => errors.push(format!("{}: {}", lemma, e)),
+        }
+    }
+    let mut entries = if merge {
+        load_entries(&app)?
+    } else {
+        Vec::new()
+    };
+    let (imported, replaced) = upsert(&mut entries, incoming);
+    store_entries(&app, &mut entries)?;
+    Ok(GlossaryImportResult {
+        imported,
+        replaced,
+        errors,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gloss(lemma: &str, gloss: &str) -> PersonalGloss {
+        PersonalGloss {
+            lemma: lemma.to_string(),
+            gloss: gloss.to_string(),
+            note: None,
+            updated_at_ms: 1,
+        }
+    }
+
+    #[test]
+    fn test_lookup_ignores_accents_and_case() {
+        let mut entries = vec![gloss("λόγος", "word")];
+        let (added, replaced) = upsert(&mut entries, vec![gloss("ΛΟΓΟΣ", "Word (Logos)")]);
+        assert_eq!((added, replaced, entries.len()), (0, 1, 1));
+
+        let glossary = PersonalGlossary::new(entries);
+        assert_eq!(glossary.get("λογος").unwrap().gloss, "Word (Logos)");
+        assert!(glossary.get("λόγιον").is_none());
+
+        let entry = LemmaEntry {
+            lemma: "λόγος".to_string(),
+            glosses: vec!["word".to_string(), "message".to_string()],
+            morphology: Some("noun".to_string()),
+            strongs: Some("G3056".to_string()),
+            source: LemmaSource::Engine,
+        };
+        let entry = glossary.apply(entry);
+        assert_eq!(entry.glosses, ["Word (Logos)"]);
+        assert_eq!(entry.source, LemmaSource::Personal);
+        assert_eq!(entry.strongs.as_deref(), Some("G3056"));
+    }
+
+    #[test]
+    fn test_clean_gloss() {
+        let entry = clean_gloss(PersonalGloss {
+            note: Some("  ".to_string()),
+            ..gloss(" ἀγάπη ", " love ")
+        })
+        .unwrap();
+        assert_eq!(
+            (entry.lemma.as_str(), entry.gloss.as_str()),
+            ("ἀγάπη", "love")
+        );
+        assert_eq!(entry.note, None);
+        assert!(clean_gloss(gloss("ἀγάπη", "")).is_err());
+        assert!(clean_gloss(gloss("  ", "love")).is_err());
+    }
+
+    #[test]
+    fn test_csv_round_trip() {
+        let mut entries = vec![PersonalGloss {
+            note: Some("per Prof. K, \"covenant\" sense".to_string()),
+            ..gloss("διαθήκη", "covenant, testament")
+        }];
+        entries.push(gloss("πίστις", "faithfulness"));
+        let mut csv = Vec::new();
+        write_glossary(&mut csv, ExportFormat::Csv, &entries).unwrap();
+        let (read, errors) = read_csv(&String::from_utf8(csv).unwrap()).unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(read, entries);
+
+        let (read, _) = read_csv("Gloss,Lemma\nlove,ἀγάπη\n").unwrap();
+        assert_eq!(
+            (read[0].lemma.as_str(), read[0].gloss.as_str()),
+            ("ἀγάπη", "love")
+        );
+        assert!(read_csv("lemma,meaning\n").is_err());
+    }
+}
@@ -0,0 +1,292 @@
+//! Cleanup of pasted Greek text.
+//!
+//! Text copied out of PDFs mixes decomposed accents with precomposed
+//! letters, uses oxia forms and typographic variants (ϐ, ϑ, lunate ϲ),
+//! writes σ where ς belongs, and carries soft hyphens, zero-width
+//! characters and odd spaces. `normalize_greek` undoes all of that in one
+//! pass and counts what it changed so the UI can explain why the text
+//! differs from what was pasted. Running it twice gives the same result
+//! as running it once.
+
+use serde::Serialize;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+const SOFT_HYPHEN: char = '\u{AD}';
+
+/// One cleanup step: the new text and how many changes it made
+type Step = fn(&str) -> (String, usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InputChangeKind {
+    /// Soft hyphens and zero-width characters
+    RemovedInvisible,
+    /// Typographic variants (ϐ, ϑ, ϲ, µ) and legacy oxia forms
+    ReplacedVariant,
+    /// Letters and separate accents combined into precomposed characters
+    Recomposed,
+    /// σ at the end of a word or ς inside one
+    FixedSigma,
+    /// Runs of spaces, non-breaking spaces and line breaks
+    CollapsedWhitespace,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InputChange {
+    pub kind: InputChangeKind,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NormalizedGreekInput {
+    pub text: String,
+    /// Only kinds that changed something, in the order they were applied
+    pub changes: Vec<InputChange>,
+}
+
+fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        SOFT_HYPHEN | '\u{200B}'..='\u{200D}' | '\u{2060}' | '\u{FEFF}'
+    )
+}
+
+/// Canonical letter for a typographic variant NFC leaves alone.
+fn variant_letter(c: char) -> Option<char> {
+    Some(match c {
+        'ϐ' => 'β',
+        'ϑ' => 'θ',
+        'ϕ' => 'φ',
+        'ϖ' => 'π',
+        'ϰ' => 'κ',
+        'ϱ' => 'ρ',
+        'ϲ' => 'σ',
+        'ϵ' => 'ε',
+        'ϴ' => 'Θ',
+        'Ϲ' => 'Σ',
+        'µ' => 'μ',
+        _ => return None,
+    })
+}
+
+/// Whether NFC replaces `c` by a different single character, as it does
+/// for oxia vowels (U+1F71 → U+03AC) and the Greek question mark.
+fn is_singleton_variant(c: char) -> bool {
+    let mut nfc = std::iter::once(c).nfc();
+    matches!((nfc.next(), nfc.next()), (Some(n), None) if n != c)
+}
+
+/// Drop invisible characters. A soft hyphen at a line break also takes
+/// the break with it, joining the word back together.
+fn remove_invisible(text: &str) -> (String, usize) {
+    let mut out = String::with_capacity(text.len());
+    let mut removed = 0;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if !is_invisible(c) {
+            out.push(c);
+            continue;
+        }
+        removed += 1;
+        if c == SOFT_HYPHEN {
+            let rest: String = chars.clone().take_while(|c| c.is_whitespace()).collect();
+            if rest.contains('\n') {
+                for _ in rest.chars() {
+                    chars.next();
+                }
+            }
+        }
+    }
+    (out, removed)
+}
+
+fn replace_variants(text: &str) -> (String, usize) {
+    let mut replaced = 0;
+    let out = text
+        .chars()
+        .map(|c| match variant_letter(c) {
+            Some(letter) => {
+                replaced += 1;
+                letter
+            }
+            None => {
+                if is_singleton_variant(c) {
+                    replaced += 1;
+                }
+                c
+            }
+        })
+        .collect();
+    (out, replaced)
+}
+
+/// NFC, counting the combining marks folded into their letters.
+fn recompose(text: &str) -> (String, usize) {
+    let marks = |s: &str| s.chars().filter(|c| is_combining_mark(*c)).count();
+    let out: String = text.nfc().collect();
+    let composed = marks(text).saturating_sub(marks(&out));
+    (out, composed)
+}
+
+/// ς at the end of a word, σ elsewhere. A lone sigma is left as it is.
+fn fix_sigmas(text: &str) -> (String, usize) {
+    let chars: Vec<char> = text.chars().collect();
+    let mut fixed = 0;
+    let mut out = String::with_capacity(text.len());
+    for (i, &c) in chars.iter().enumerate() {
+        if c != 'σ' && c != 'ς' {
+            out.push(c);
+            continue;
+        }
+        let after_letter = i > 0 && chars[i - 1].is_alphabetic();
+        let before_letter = chars[i + 1..]
+            .iter()
+            .find(|c| !is_combining_mark(**c))
+            .is_some_and(|c| c.is_alphabetic());
+        let sigma = match (after_letter, before_letter) {
+            (true, false) => 'ς',
+            (_, true) => 'σ',
+            (false, false) => c,
+        };
+        if sigma != c {
+            fixed += 1;
+        }
+        out.push(sigma);
+    }
+    (out, fixed)
+}
+
+/// Single spaces between words, none at the ends. Counts each run of
+/// whitespace that wasn't already one plain space.
+fn collapse_whitespace(text: &str) -> (String, usize) {
+    let mut out = String::with_capacity(text.len());
+    let mut collapsed = 0;
+    let mut run = String::new();
+    for c in text.chars().chain(std::iter::once('\0')) {
+        if c.is_whitespace() {
+            run.push(c);
+            continue;
+        }
+        if !run.is_empty() {
+            let at_edge = out.is_empty() || c == '\0';
+            if at_edge || run != " " {
+                collapsed += 1;
+            }
+            if !at_edge {
+                out.push(' ');
+            }
+            run.clear();
+        }
+        if c != '\0' {
+            out.push(c);
+        }
+    }
+    (out, collapsed)
+}
+
+/// Clean up pasted Greek (or a pasted reference) for the engine.
+pub fn normalize_greek(text: &str) -> NormalizedGreekInput {
+    let steps: [(InputChangeKind, Step); 5] = [
+        (InputChangeKind::RemovedInvisible, remove_invisible),
+        (InputChangeKind::ReplacedVariant, replace_variants),
+        (InputChangeKind::Recomposed, recompose),
+        (InputChangeKind::FixedSigma, fix_sigmas),
+        (InputChangeKind::CollapsedWhitespace, collapse_whitespace),
+    ];
+    let mut text = text.to_string();
+    let mut changes = Vec::new();
+    for (kind, step) in steps {
+        let (out, count) = step(&text);
+        text = out;
+        if count > 0 {
+            changes.push(InputChange { kind, count });
+        }
+    }
+    NormalizedGreekInput { text, changes }
+}
+
+/// Normalize pasted Greek input, reporting what was changed.
+#[tauri::command]
+pub fn normalize_greek_input(text: String) -> NormalizedGreekInput {
+    normalize_greek(&text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(result: &NormalizedGreekInput, kind: InputChangeKind) -> usize {
+        result
+            .changes
+            .iter()
+            .find(|c| c.kind == kind)
+            .map_or(0, |c| c.count)
+    }
+
+    #[test]
+    fn test_pdf_paste() {
+        let pasted = "  Ἐν ἀρχῇ\u{a0}ἦν ὁ λο\u{301}γοσ,\n και\u{300} ὁ λό\u{AD}\nγος ἦν \
+                      πρὸϲ τὸν θεόν\u{200B}.  ";
+        let result = normalize_greek(pasted);
+        assert_eq!(
+            result.text,
+            "Ἐν ἀρχῇ ἦν ὁ λόγος, καὶ ὁ λόγος ἦν πρὸς τὸν θεόν."
+        );
+        assert_eq!(count(&result, InputChangeKind::RemovedInvisible), 2);
+        assert_eq!(count(&result, InputChangeKind::ReplacedVariant), 1);
+        assert_eq!(count(&result, InputChangeKind::Recomposed), 2);
+        assert_eq!(count(&result, InputChangeKind::FixedSigma), 2);
+        assert_eq!(count(&result, InputChangeKind::CollapsedWhitespace), 4);
+    }
+
+    #[test]
+    fn test_variants_and_sigmas() {
+        // Oxia forms, the Greek question mark and symbol variants
+        let result = normalize_greek("\u{1F71}γιος τίς\u{37E} ϑεϐαίωϲ µή");
+        assert_eq!(result.text, "\u{3AC}γιος τίς; θεβαίως μή");
+        assert_eq!(count(&result, InputChangeKind::ReplacedVariant), 6);
+
+        assert_eq!(normalize_greek("ςοφός κόσμοσ·").text, "σοφός κόσμος·");
+        // Sigma before a combining mark is still inside the word; a lone
+        // sigma and capitals are left alone
+        assert_eq!(normalize_greek("Σ σ ΚΟΣΜΟΣ").text, "Σ σ ΚΟΣΜΟΣ");
+        let clean = normalize_greek("John 3:16");
+        assert_eq!(clean.text, "John 3:16");
+        assert!(clean.changes.is_empty());
+    }
+
+    /// Small deterministic generator, so the property test needs no crate.
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(&mut self, n: usize) -> usize {
+            self.0 = self
+                .0
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            ((self.0 >> 33) % n as u64) as usize
+        }
+    }
+
+    #[test]
+    fn test_normalizing_is_idempotent() {
+        const ALPHABET: &[char] = &[
+            'α', 'ε', 'ο', 'υ', 'ι', 'Α', 'Ω', 'λ', 'γ', 'σ', 'ς', 'Σ', 'ϲ', 'Ϲ', 'ϐ', 'ϑ', 'µ',
+            'ά', 'ἀ', 'ᾳ', '\u{1F71}', '\u{301}', '\u{300}', '\u{342}', '\u{313}', '\u{314}',
+            '\u{308}', '\u{345}', ' ', ' ', '\u{a0}', '\n', '\t', '\u{AD}', '\u{200B}', '\u{FEFF}',
+            ',', '.', '·', '\u{387}', '\u{37E}', '’', 'a', 'Z', '1',
+        ];
+        let mut rng = Lcg(0x5eed);
+        for _ in 0..2_000 {
+            let len = rng.next(24);
+            let text: String = (0..len)
+                .map(|_| ALPHABET[rng.next(ALPHABET.len())])
+                .collect();
+            let once = normalize_greek(&text);
+            let twice = normalize_greek(&once.text);
+            assert_eq!(twice.text, once.text, "{:?}", text);
+            assert!(twice.changes.is_empty(), "{:?}: {:?}", text, twice.changes);
+        }
+    }
+}
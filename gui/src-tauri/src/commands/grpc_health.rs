@@ -0,0 +1,193 @@
+//! Health checks for engine deployments that speak gRPC.
+//!
+//! Uses the standard `grpc.health.v1.Health/Check` protocol. Servers that
+//! answer `UNIMPLEMENTED` (gRPC gateway in front of an HTTP engine) are
+//! checked over HTTP at `/health` instead.
+
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use tonic::transport::{ClientTlsConfig, Endpoint};
+use tonic::Code;
+use tonic_health::pb::health_check_response::ServingStatus;
+use tonic_health::pb::health_client::HealthClient;
+use tonic_health::pb::HealthCheckRequest;
+
+use super::engine::EngineError;
+use super::http;
+use super::offline;
+
+/// Timeout for connecting and for the check itself
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum GrpcServingStatus {
+    Serving,
+    NotServing,
+    ServiceUnknown,
+    Unknown,
+}
+
+impl From<ServingStatus> for GrpcServingStatus {
+    fn from(status: ServingStatus) -> Self {
+        match status {
+            ServingStatus::Serving => GrpcServingStatus::Serving,
+            ServingStatus::NotServing => GrpcServingStatus::NotServing,
+            ServingStatus::ServiceUnknown => GrpcServingStatus::ServiceUnknown,
+            ServingStatus::Unknown => GrpcServingStatus::Unknown,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthProtocol {
+    Grpc,
+    /// gRPC health wasn't implemented; checked over HTTP instead
+    Http,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GrpcHealthStatus {
+    pub status: GrpcServingStatus,
+    pub latency_ms: u64,
+    pub protocol: HealthProtocol,
+}
+
+fn base_url(host: &str, port: u16, tls: bool) -> String {
+    let scheme = if tls { "https" } else { "http" };
+    format!("{}://{}:{}", scheme, host, port)
+}
+
+/// Connection failures are errors; the RPC's own status is returned as the
+/// inner result so the caller can decide on the fallback.
+async fn grpc_check(
+    url: String,
+    tls: bool,
+    service: String,
+) -> Result<Result<ServingStatus, tonic::Status>, EngineError> {
+    let grpc_err = |e: tonic::transport::Error| EngineError::GrpcError(e.to_string());
+    let mut endpoint = Endpoint::from_shared(url)
+        .map_err(grpc_err)?
+        .connect_timeout(CHECK_TIMEOUT)
+        .timeout(CHECK_TIMEOUT);
+    if tls {
+        endpoint = endpoint
+            .tls_config(ClientTlsConfig::new().with_native_roots())
+            .map_err(grpc_err)?;
+    }
+    let channel = endpoint.connect().await.map_err(grpc_err)?;
+
+    Ok(HealthClient::new(channel)
+        .check(HealthCheckRequest { service })
+        .await
+        .map(|response| response.into_inner().status()))
+}
+
+/// HTTP fallback: any 2xx from `/health` counts as serving.
+async fn http_check(url: &str) -> Result<GrpcServingStatus, EngineError> {
+    let request = http::client()
+        .get(format!("{}/health", url))
+        .timeout(CHECK_TIMEOUT);
+    match http::send(request).await {
+        Ok(_) => Ok(GrpcServingStatus::Serving),
+        Err(e) if e.status().is_some() => Ok(GrpcServingStatus::NotServing),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Check engine health over gRPC, falling back to HTTP when the server
+/// doesn't implement the gRPC health service.
+///
+/// `service_name` selects a single service; the default checks the server
+/// as a whole.
+#[tauri::command]
+pub async fn grpc_health_check(
+    host: String,
+    port: u16,
+    service_name: Option<String>,
+    tls: bool,
+) -> Result<GrpcHealthStatus, EngineError> {
+    let url = base_url(&host, port, tls);
+    offline::check_url(&url)?;
+    let started = Instant::now();
+    let elapsed_ms = |started: Instant| started.elapsed().as_millis() as u64;
+
+    match grpc_check(url.clone(), tls, service_name.unwrap_or_default()).await? {
+        Ok(status) => Ok(GrpcHealthStatus {
+            status: status.into(),
+            latency_ms: elapsed_ms(started),
+            protocol: HealthProtocol::Grpc,
+        }),
+        Err(status) if status.code() == Code::Unimplemented => {
+            let started = Instant::now();
+            Ok(GrpcHealthStatus {
+                status: http_check(&url).await?,
+                latency_ms: elapsed_ms(started),
+                protocol: HealthProtocol::Http,
+            })
+        }
+        // The named service isn't registered with the health server
+        Err(status) if status.code() == Code::NotFound => Ok(GrpcHealthStatus {
+            status: GrpcServingStatus::ServiceUnknown,
+            latency_ms: elapsed_ms(started),
+            protocol: HealthProtocol::Grpc,
+        }),
+        Err(status) => Err(EngineError::GrpcError(format!(
+            "{:?}: {}",
+            status.code(),
+            status.message()
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::engine::check_engine_running;
+    use crate::testsupport::mock_engine::{ForeignListener, MockEngine};
+
+    #[test]
+    fn test_status_serializes_as_grpc_names() {
+        let statuses = [
+            ServingStatus::Serving,
+            ServingStatus::NotServing,
+            ServingStatus::ServiceUnknown,
+            ServingStatus::Unknown,
+        ];
+        for status in statuses {
+            let ours = GrpcServingStatus::from(status);
+            assert_eq!(
+                serde_json::to_value(ours).unwrap(),
+                status.as_str_name(),
+                "{:?}",
+                status
+            );
+        }
+    }
+
+    #[test]
+    fn test_http_check_tells_engine_from_foreign_listener() {
+        let mut engine = MockEngine::start();
+        let port = engine.port();
+        let foreign = ForeignListener::start();
+        let check =
+            |port| tauri::async_runtime::block_on(http_check(&base_url("127.0.0.1", port, false)));
+
+        // Something answers on both ports
+        assert!(check_engine_running(port).running);
+        assert!(check_engine_running(foreign.port()).running);
+
+        assert_eq!(check(port).unwrap(), GrpcServingStatus::Serving);
+        assert_eq!(
+            check(foreign.port()).unwrap(),
+            GrpcServingStatus::NotServing
+        );
+        engine.fail_next("/health", [503]);
+        assert_eq!(check(port).unwrap(), GrpcServingStatus::NotServing);
+
+        engine.stop();
+        assert!(check(port).is_err());
+        assert!(!check_engine_running(port).running);
+    }
+}
@@ -0,0 +1,244 @@
+//! WebView GPU status for the troubleshooter.
+//!
+//! The status comes from WebGL in the main window: a software rasterizer
+//! (or no WebGL at all) means the webview is drawing without the GPU.
+//! Acceleration can only be turned off before the webview starts, so
+//! `disable_hardware_acceleration` saves the choice and relaunches the app
+//! with `--disable-gpu`, and `apply_gpu_preference` sets the webview's
+//! switches at startup. `enable_hardware_acceleration` undoes both.
+
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::process::Command;
+use std::sync::{mpsc, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use super::errors::AppError;
+use super::settings;
+use super::shutdown;
+
+const MAIN_LABEL: &str = "main";
+/// Command-line switch that turns acceleration off for one launch
+pub const DISABLE_GPU_ARG: &str = "--disable-gpu";
+/// How long to wait for the window to run the probe
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Reads the unmasked renderer and vendor where the webview exposes them.
+const PROBE_SCRIPT: &str = "(() => { \
+    const canvas = document.createElement('canvas'); \
+    const gl = canvas.getContext('webgl2') || canvas.getContext('webgl'); \
+    if (!gl) return JSON.stringify({}); \
+    const info = gl.getExtension('WEBGL_debug_renderer_info'); \
+    return JSON.stringify({ \
+        renderer: gl.getParameter(info ? info.UNMASKED_RENDERER_WEBGL : gl.RENDERER), \
+        vendor: gl.getParameter(info ? info.UNMASKED_VENDOR_WEBGL : gl.VENDOR), \
+        webgl_version: gl.getParameter(gl.VERSION) \
+    }); \
+})()";
+
+/// Renderer names of software rasterizers, lowercased
+const SOFTWARE_RENDERERS: &[&str] = &[
+    "swiftshader",
+    "llvmpipe",
+    "softpipe",
+    "software",
+    "basic render driver",
+];
+
+/// Environment variables as they were before `disable_webview_gpu` changed
+/// them, so a relaunch with acceleration back on doesn't inherit the switches
+static ORIGINAL_ENV: OnceLock<Vec<(&'static str, Option<OsString>)>> = OnceLock::new();
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HardwareAccelStatus {
+    pub gpu_enabled: bool,
+    pub renderer: Option<String>,
+    pub vendor: Option<String>,
+    pub webgl_version: Option<String>,
+    /// The user turned acceleration off (here or with `--disable-gpu`)
+    pub acceleration_disabled: bool,
+}
+
+/// Status from the probe's result. `eval_with_callback` JSON-encodes the
+/// script's return value, so the probe's own JSON arrives as a string.
+fn parse_probe(raw: &str) -> HardwareAccelStatus {
+    let value: serde_json::Value = serde_json::from_str(raw).unwrap_or_default();
+    let value = match value {
+        serde_json::Value::String(inner) => serde_json::from_str(&inner).unwrap_or_default(),
+        other => other,
+    };
+    let mut status: HardwareAccelStatus = serde_json::from_value(value).unwrap_or_default();
+    status.gpu_enabled = status.renderer.as_deref().is_some_and(|renderer| {
+        let renderer = renderer.to_lowercase();
+        !SOFTWARE_RENDERERS.iter().any(|s| renderer.contains(s))
+    });
+    status
+}
+
+/// Turn acceleration off for this launch if `--disable-gpu` was passed or
+/// the user turned it off before. Runs before the app is built, while the
+/// process is still single-threaded.
+pub fn apply_gpu_preference(disable_gpu: bool, identifier: &str) {
+    if disable_gpu
+        || settings::load_settings_before_start(identifier).hardware_acceleration_disabled
+    {
+        disable_webview_gpu();
+    }
+}
+
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+fn remember_env(names: &[&'static str]) {
+    let _ = ORIGINAL_ENV.set(names.iter().map(|&n| (n, std::env::var_os(n))).collect());
+}
+
+#[cfg(target_os = "windows")]
+fn disable_webview_gpu() {
+    const ARGS_VAR: &str = "WEBVIEW2_ADDITIONAL_BROWSER_ARGUMENTS";
+    remember_env(&[ARGS_VAR]);
+    let args = match std::env::var(ARGS_VAR) {
+        Ok(args) if !args.trim().is_empty() => format!("{} {}", args, DISABLE_GPU_ARG),
+        _ => DISABLE_GPU_ARG.to_string(),
+    };
+    std::env::set_var(ARGS_VAR, args);
+}
+
+#[cfg(target_os = "linux")]
+fn disable_webview_gpu() {
+    const VARS: [&str; 2] = [
+        "WEBKIT_DISABLE_COMPOSITING_MODE",
+        "WEBKIT_DISABLE_DMABUF_RENDERER",
+    ];
+    remember_env(&VARS);
+    for var in VARS {
+        std::env::set_var(var, "1");
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+fn disable_webview_gpu() {}
+
+/// This launch's arguments, with `--disable-gpu` only if `disable_gpu`.
+fn relaunch_args<I: IntoIterator<Item = OsString>>(args: I, disable_gpu: bool) -> Vec<OsString> {
+    let mut args: Vec<OsString> = args.into_iter().filter(|a| a != DISABLE_GPU_ARG).collect();
+    if disable_gpu {
+        args.push(DISABLE_GPU_ARG.into());
+    }
+    args
+}
+
+/// Command that starts this app again in the environment it was launched in.
+fn relaunch_command(disable_gpu: bool) -> Result<Command, String> {
+    let exe = std::env::current_exe().map_err(|e| format!("Could not relaunch: {}", e))?;
+    let mut command = Command::new(exe);
+    command.args(relaunch_args(std::env::args_os().skip(1), disable_gpu));
+    for (name, value) in ORIGINAL_ENV.get().into_iter().flatten() {
+        match value {
+            Some(value) => command.env(name, value),
+            None => command.env_remove(name),
+        };
+    }
+    Ok(command)
+}
+
+/// Save the choice and restart through the normal shutdown, so window
+/// state and batch job checkpoints are saved first.
+fn set_acceleration_and_relaunch(app: &AppHandle, disabled: bool) -> Result<(), String> {
+    // WKWebView has no switch for it
+    if cfg!(not(any(target_os = "windows", target_os = "linux"))) {
+        return Err("Hardware acceleration can't be changed on this platform".to_string());
+    }
+    let command = relaunch_command(disabled)?;
+    settings::update_settings(app, |s| s.hardware_acceleration_disabled = disabled)?;
+    shutdown::relaunch(app, command);
+    Ok(())
+}
+
+/// Whether the webview is drawing with the GPU, and which renderer it uses.
+#[tauri::command]
+pub async fn get_hardware_acceleration_status(
+    app: AppHandle,
+) -> Result<HardwareAccelStatus, AppError> {
+    let window = app
+        .get_webview_window(MAIN_LABEL)
+        .ok_or_else(|| "The main window is not open".to_string())?;
+    let (tx, rx) = mpsc::channel();
+    window
+        .eval_with_callback(PROBE_SCRIPT, move |result| {
+            let _ = tx.send(result);
+        })
+        .map_err(|e| e.to_string())?;
+    let raw = tauri::async_runtime::spawn_blocking(move || rx.recv_timeout(PROBE_TIMEOUT))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|_| "The window did not report its renderer".to_string())?;
+    let mut status = parse_probe(&raw);
+    status.acceleration_disabled = ORIGINAL_ENV.get().is_some();
+    Ok(status)
+}
+
+/// Turn hardware acceleration off from now on and relaunch the app.
+#[tauri::command]
+pub fn disable_hardware_acceleration(app: AppHandle) -> Result<(), AppError> {
+    Ok(set_acceleration_and_relaunch(&app, true)?)
+}
+
+/// Turn hardware acceleration back on and relaunch the app.
+#[tauri::command]
+pub fn enable_hardware_acceleration(app: AppHandle) -> Result<(), AppError> {
+    Ok(set_acceleration_and_relaunch(&app, false)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn probe(fields: serde_json::Value) -> String {
+        serde_json::to_string(&fields.to_string()).unwrap()
+    }
+
+    #[test]
+    fn test_parse_probe() {
+        let status = parse_probe(&probe(serde_json::json!({
+            "renderer": "ANGLE (NVIDIA, NVIDIA GeForce RTX 3060 Direct3D11 vs_5_0 ps_5_0)",
+            "vendor": "Google Inc. (NVIDIA)",
+            "webgl_version": "WebGL 2.0 (OpenGL ES 3.0 Chromium)",
+        })));
+        assert!(status.gpu_enabled);
+        assert_eq!(status.vendor.as_deref(), Some("Google Inc. (NVIDIA)"));
+
+        for renderer in [
+            "ANGLE (Google, Vulkan 1.3.0 (SwiftShader Device (Subzero)), SwiftShader driver)",
+            "llvmpipe (LLVM 15.0.7, 256 bits)",
+            "ANGLE (Microsoft, Microsoft Basic Render Driver Direct3D11)",
+        ] {
+            let status = parse_probe(&probe(serde_json::json!({ "renderer": renderer })));
+            assert!(!status.gpu_enabled, "{}", renderer);
+            assert_eq!(status.renderer.as_deref(), Some(renderer));
+        }
+
+        assert_eq!(
+            parse_probe(&probe(serde_json::json!({}))),
+            HardwareAccelStatus::default()
+        );
+        assert_eq!(parse_probe("null"), HardwareAccelStatus::default());
+    }
+
+    #[test]
+    fn test_relaunch_args() {
+        let args = |list: &[&str]| list.iter().map(OsString::from).collect::<Vec<_>>();
+        assert_eq!(
+            relaunch_args(args(&["--open", "John 3:16"]), true),
+            args(&["--open", "John 3:16", "--disable-gpu"])
+        );
+        assert_eq!(
+            relaunch_args(args(&["--disable-gpu", "--open", "x.rlproj"]), false),
+            args(&["--open", "x.rlproj"])
+        );
+        assert_eq!(
+            relaunch_args(args(&["--disable-gpu"]), true),
+            args(&["--disable-gpu"])
+        );
+    }
+}
@@ -0,0 +1,252 @@
+//! Coloring Greek text by grammatical feature.
+//!
+//! The engine parses the text and returns the spans matching each rule
+//! from `/v1/text/highlight`. Rule colors end up in the frontend's styles,
+//! so they are checked to be CSS colors first. Results are cached by a
+//! hash of the text and of the rules.
+
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::State;
+
+use super::cache::TtlCache;
+use super::engine::EngineError;
+use super::http::{self, HttpError};
+
+const HIGHLIGHT_TTL: Duration = Duration::from_secs(30 * 60);
+const HIGHLIGHT_CACHE_SIZE: usize = 200;
+
+/// CSS named colors, plus `transparent` and `currentcolor`, space separated
+const NAMED_COLORS: &str = "\
+    aliceblue antiquewhite aqua aquamarine azure beige bisque black blanchedalmond blue \
+    blueviolet brown burlywood cadetblue chartreuse chocolate coral cornflowerblue cornsilk \
+    crimson cyan darkblue darkcyan darkgoldenrod darkgray darkgreen darkgrey darkkhaki \
+    darkmagenta darkolivegreen darkorange darkorchid darkred darksalmon darkseagreen \
+    darkslateblue darkslategray darkslategrey darkturquoise darkviolet deeppink deepskyblue \
+    dimgray dimgrey dodgerblue firebrick floralwhite forestgreen fuchsia gainsboro ghostwhite \
+    gold goldenrod gray green greenyellow grey honeydew hotpink indianred indigo ivory khaki \
+    lavender lavenderblush lawngreen lemonchiffon lightblue lightcoral lightcyan \
+    lightgoldenrodyellow lightgray lightgreen lightgrey lightpink lightsalmon lightseagreen \
+    lightskyblue lightslategray lightslategrey lightsteelblue lightyellow lime limegreen linen \
+    magenta maroon mediumaquamarine mediumblue mediumorchid mediumpurple mediumseagreen \
+    mediumslateblue mediumspringgreen mediumturquoise mediumvioletred midnightblue mintcream \
+    mistyrose moccasin navajowhite navy oldlace olive olivedrab orange orangered orchid \
+    palegoldenrod palegreen paleturquoise palevioletred papayawhip peachpuff peru pink plum \
+    powderblue purple rebeccapurple red rosybrown royalblue saddlebrown salmon sandybrown \
+    seagreen seashell sienna silver skyblue slateblue slategray slategrey snow springgreen \
+    steelblue tan teal thistle tomato turquoise violet wheat white whitesmoke yellow \
+    yellowgreen transparent currentcolor";
+/// Color functions, e.g. `rgb(176 0 32)` or `hsl(30, 50%, 90%)`
+const COLOR_FUNCTIONS: &[&str] = &[
+    "rgb", "rgba", "hsl", "hsla", "hwb", "lab", "lch", "oklab", "oklch",
+];
+/// Units allowed after a color function argument
+const ARGUMENT_UNITS: &[&str] = &["", "%", "deg", "grad", "rad", "turn"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GrammarFeature {
+    Verb,
+    Noun,
+    Adjective,
+    Participle,
+    Infinitive,
+    Optative,
+    SubjunctiveVerb,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct HighlightRule {
+    pub feature: GrammarFeature,
+    /// CSS color, e.g. "#b00020" or "rgb(176 0 32)"
+    pub color: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HighlightedSpan {
+    /// Character offsets into the text, end exclusive
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+    pub feature: String,
+    pub color: String,
+}
+
+#[derive(Debug, Serialize)]
+struct HighlightRequest<'a> {
+    text: &'a str,
+    rules: &'a [HighlightRule],
+}
+
+/// Spans by (SHA-256 of the text, SHA-256 of the rules), hex.
+pub struct HighlightCache(Mutex<TtlCache<(String, String), Vec<HighlightedSpan>>>);
+
+impl Default for HighlightCache {
+    fn default() -> Self {
+        Self(Mutex::new(TtlCache::with_capacity(
+            HIGHLIGHT_TTL,
+            HIGHLIGHT_CACHE_SIZE,
+        )))
+    }
+}
+
+fn is_color_argument(arg: &str) -> bool {
+    if arg == "none" {
+        return true;
+    }
+    let number_end = arg
+        .find(|c: char| !(c.is_ascii_digit() || matches!(c, '.' | '-' | '+')))
+        .unwrap_or(arg.len());
+    let (number, unit) = arg.split_at(number_end);
+    number.parse::<f64>().is_ok() && ARGUMENT_UNITS.contains(&unit)
+}
+
+/// Whether `color` is a CSS color: a named color, a hex color or one of
+/// `COLOR_FUNCTIONS` with three or four arguments. `var()` and `calc()`
+/// are not accepted.
+fn is_css_color(color: &str) -> bool {
+    let color = color.trim().to_ascii_lowercase();
+    if let Some(hex) = color.strip_prefix('#') {
+        return matches!(hex.len(), 3 | 4 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit());
+    }
+    if let Some((function, args)) = color
+        .strip_suffix(')')
+        .and_then(|color| color.split_once('('))
+    {
+        let args: Vec<&str> = args
+            .split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+            .filter(|arg| !arg.is_empty())
+            .collect();
+        return COLOR_FUNCTIONS.contains(&function.trim_end())
+            && (3..=4).contains(&args.len())
+            && args.into_iter().all(is_color_argument);
+    }
+    NAMED_COLORS.split_whitespace().any(|name| name == color)
+}
+
+fn check_rules(rules: &[HighlightRule]) -> Result<(), EngineError> {
+    let invalid: Vec<&str> = rules
+        .iter()
+        .map(|r| r.color.as_str())
+        .filter(|color| !is_css_color(color))
+        .collect();
+    if !invalid.is_empty() {
+        return Err(EngineError::HighlightError(format!(
+            "invalid colors: {}",
+            invalid.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+fn cache_key(text: &str, rules: &[HighlightRule]) -> (String, String) {
+    let rules = serde_json::to_vec(rules).unwrap_or_default();
+    (sha256_hex(text.as_bytes()), sha256_hex(&rules))
+}
+
+/// Mark the words of `text` that have a rule's feature with its color,
+/// e.g. participles in "#b00020". Spans are in text order.
+#[tauri::command]
+pub async fn highlight_text_spans(
+    cache: State<'_, HighlightCache>,
+    port: u16,
+    text: String,
+    highlight_rules: Vec<HighlightRule>,
+) -> Result<Vec<HighlightedSpan>, EngineError> {
+    check_rules(&highlight_rules)?;
+    if text.trim().is_empty() || highlight_rules.is_empty() {
+        return Ok(Vec::new());
+    }
+    let key = cache_key(&text, &highlight_rules);
+    if let Some(spans) = cache.0.lock().unwrap().get(&key) {
+        return Ok(spans);
+    }
+
+    let body = HighlightRequest {
+        text: &text,
+        rules: &highlight_rules,
+    };
+    let request = http::request(Method::POST, port, "/v1/text/highlight")?.json(&body);
+    let mut spans: Vec<HighlightedSpan> = match http::send_json(request).await {
+        Ok(spans) => spans,
+        // The engine reports text it can't parse as 422
+        Err(HttpError::Status { status: 422, body }) => {
+            return Err(EngineError::HighlightError(body))
+        }
+        Err(e) => return Err(e.into()),
+    };
+    spans.sort_by_key(|s| (s.start, s.end));
+    cache.0.lock().unwrap().insert(key, spans.clone());
+    Ok(spans)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_css_color() {
+        for color in [
+            "red",
+            " RebeccaPurple ",
+            "transparent",
+            "#b00020",
+            "#fff",
+            "#ffd54f80",
+            "rgb(176, 0, 32)",
+            "rgb(176 0 32 / 50%)",
+            "rgba(0,0,0,0.5)",
+            "hsl(30deg 50% 90%)",
+            "oklch(0.7 0.1 120)",
+        ] {
+            assert!(is_css_color(color), "{}", color);
+        }
+        for color in [
+            "",
+            "reddish",
+            "#ggg",
+            "#12345",
+            "rgb(1, 2)",
+            "rgb(1, 2, 3",
+            "url(x)",
+            "var(--accent)",
+            "red; background: url(x)",
+            "rgb(1px, 2, 3)",
+        ] {
+            assert!(!is_css_color(color), "{}", color);
+        }
+    }
+
+    #[test]
+    fn test_check_rules_and_cache_key() {
+        let rule = |feature, color: &str| HighlightRule {
+            feature,
+            color: color.to_string(),
+        };
+        let rules = vec![
+            rule(GrammarFeature::Participle, "#b00020"),
+            rule(GrammarFeature::SubjunctiveVerb, "teal"),
+        ];
+        assert!(check_rules(&rules).is_ok());
+        assert!(matches!(
+            check_rules(&[rule(GrammarFeature::Verb, "nope"), rules[0].clone()]),
+            Err(EngineError::HighlightError(detail)) if detail == "invalid colors: nope"
+        ));
+
+        let key = cache_key("ἐν ἀρχῇ", &rules);
+        assert_eq!(key, cache_key("ἐν ἀρχῇ", &rules));
+        assert_ne!(key.0, cache_key("ἐν ἀρχῇ ἦν", &rules).0);
+        assert_ne!(key.1, cache_key("ἐν ἀρχῇ", &rules[..1]).1);
+        assert_eq!(
+            serde_json::to_value(&rules[1]).unwrap(),
+            serde_json::json!({"feature": "subjunctive_verb", "color": "teal"})
+        );
+    }
+}
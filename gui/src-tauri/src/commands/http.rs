@@ -0,0 +1,363 @@
+//! Shared HTTP plumbing for engine API calls.
+//!
+//! Every request to the engine goes through this module so the bearer
+//! token, impersonation header and response handling live in one place.
+//! Each request is logged in a span with a request id, also sent to the
+//! engine as `X-Request-Id` so both logs can be matched up.
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use reqwest::header::{HeaderMap, HeaderValue};
+use reqwest::{Method, RequestBuilder, Response};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use thiserror::Error;
+use tracing::Instrument;
+
+use super::auth::{get_auth_token, AuthError};
+use super::errors::CommandError;
+use super::i18n::Localize;
+use super::logging;
+use super::offline;
+use super::storage::now_ms;
+
+/// Default timeout for engine requests
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// Header naming the user an admin is acting as
+const IMPERSONATE_HEADER: &str = "X-Impersonate-User";
+/// Header carrying the id the request is logged under
+const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+/// User whose data requests should act on, set by `impersonate_user`.
+#[derive(Debug, Clone)]
+pub struct Impersonation {
+    pub user_id: String,
+    pub expires_at_ms: u64,
+}
+
+static IMPERSONATION: Mutex<Option<Impersonation>> = Mutex::new(None);
+
+/// Rate limit state from the engine's `X-RateLimit-*` response headers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RateLimitStatus {
+    pub limit: Option<u32>,
+    pub remaining: Option<u32>,
+    pub reset_at_ms: Option<u64>,
+    /// When these headers were seen
+    pub observed_at_ms: u64,
+}
+
+/// Latest rate limit headers per engine port
+static RATE_LIMITS: Mutex<BTreeMap<u16, RateLimitStatus>> = Mutex::new(BTreeMap::new());
+
+/// Interpret `X-RateLimit-Reset`, which engines send either as epoch
+/// seconds, epoch milliseconds, or seconds until the window resets.
+fn parse_reset(value: u64, now: u64) -> u64 {
+    if value >= 1_000_000_000_000 {
+        value
+    } else if value >= 1_000_000_000 {
+        value * 1000
+    } else {
+        now + value * 1000
+    }
+}
+
+fn rate_limit_from_headers(headers: &HeaderMap, now: u64) -> Option<RateLimitStatus> {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<u64>().ok())
+    };
+    let limit = header("x-ratelimit-limit");
+    let remaining = header("x-ratelimit-remaining");
+    let reset = header("x-ratelimit-reset");
+    if limit.is_none() && remaining.is_none() && reset.is_none() {
+        return None;
+    }
+    Some(RateLimitStatus {
+        limit: limit.map(|v| v.min(u32::MAX as u64) as u32),
+        remaining: remaining.map(|v| v.min(u32::MAX as u64) as u32),
+        reset_at_ms: reset.map(|v| parse_reset(v, now)),
+        observed_at_ms: now,
+    })
+}
+
+/// Remember rate limit headers from a response, if it carried any.
+fn record_rate_limit(response: &Response) {
+    let Some(port) = response.url().port() else {
+        return;
+    };
+    if let Some(status) = rate_limit_from_headers(response.headers(), now_ms()) {
+        RATE_LIMITS.lock().unwrap().insert(port, status);
+    }
+}
+
+/// Most recent rate limit headers seen from the engine on `port`.
+pub fn rate_limit_status(port: u16) -> Option<RateLimitStatus> {
+    RATE_LIMITS.lock().unwrap().get(&port).cloned()
+}
+
+/// Set or clear the impersonation header for subsequent requests.
+pub fn set_impersonation(impersonation: Option<Impersonation>) {
+    *IMPERSONATION.lock().unwrap() = impersonation;
+}
+
+/// Impersonated user id, dropping the session once it has expired.
+fn impersonated_user() -> Option<String> {
+    let mut current = IMPERSONATION.lock().unwrap();
+    match current.as_ref() {
+        Some(i) if i.expires_at_ms > now_ms() => Some(i.user_id.clone()),
+        Some(_) => {
+            *current = None;
+            None
+        }
+        None => None,
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum HttpError {
+    #[error("Request failed: {0}")]
+    Transport(String),
+    #[error("Engine returned HTTP {status}: {body}")]
+    Status { status: u16, body: String },
+    #[error("Invalid response from engine: {0}")]
+    Decode(String),
+    /// A request that would leave this machine while offline mode is on
+    #[error("Skipped: offline mode is on")]
+    Offline,
+    /// An id that can't be one segment of an API path
+    #[error("Invalid id for a request path: {0:?}")]
+    InvalidPathSegment(String),
+}
+
+impl Localize for HttpError {
+    fn code(&self) -> &'static str {
+        match self {
+            HttpError::Transport(_) => "http-transport",
+            HttpError::Status { .. } => "http-status",
+            HttpError::Decode(_) => "http-decode",
+            HttpError::Offline => "http-offline-mode",
+            HttpError::InvalidPathSegment(_) => "http-invalid-path-segment",
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            HttpError::Transport(detail) | HttpError::Decode(detail) => {
+                vec![("detail", detail.clone())]
+            }
+            HttpError::Status { status, body } => {
+                vec![("status", status.to_string()), ("body", body.clone())]
+            }
+            HttpError::Offline => Vec::new(),
+            HttpError::InvalidPathSegment(id) => vec![("id", id.clone())],
+        }
+    }
+}
+
+impl CommandError for HttpError {
+    /// The engine couldn't be reached or was overloaded.
+    fn retryable(&self) -> bool {
+        match self {
+            HttpError::Transport(_) => true,
+            HttpError::Status { status, .. } => matches!(status, 408 | 429 | 502 | 503 | 504),
+            HttpError::Decode(_) | HttpError::Offline | HttpError::InvalidPathSegment(_) => false,
+        }
+    }
+}
+
+impl HttpError {
+    /// HTTP status code, if the engine answered at all.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            HttpError::Status { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+}
+
+/// Characters a path segment keeps as they are: RFC 3986 unreserved
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Percent-encode an id for use as one segment of an API path, so a `/`,
+/// `?` or `#` in it can't reach another endpoint. `.` and `..` would be
+/// resolved away by the URL parser, so they are refused with the empty id.
+pub fn path_segment(id: &str) -> Result<String, HttpError> {
+    if matches!(id, "" | "." | "..") {
+        return Err(HttpError::InvalidPathSegment(id.to_string()));
+    }
+    Ok(utf8_percent_encode(id, PATH_SEGMENT).to_string())
+}
+
+/// Build the engine URL for an API path.
+pub fn engine_url(port: u16, path: &str) -> String {
+    format!("http://127.0.0.1:{}/{}", port, path.trim_start_matches('/'))
+}
+
+/// Shared client (connection pool is reused across commands).
+pub fn client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .expect("failed to build HTTP client")
+    })
+}
+
+/// Start a request to a URL outside the engine API (manifests, downloads,
+/// report endpoints). Refused in offline mode unless the URL is on this
+/// machine.
+pub fn remote(method: Method, url: &str) -> Result<RequestBuilder, HttpError> {
+    offline::check_url(url)?;
+    Ok(client().request(method, url))
+}
+
+/// Start an authenticated request to the engine using the stored token.
+pub fn request(method: Method, port: u16, path: &str) -> Result<RequestBuilder, AuthError> {
+    let token = get_auth_token()?;
+    let builder = client()
+        .request(method, engine_url(port, path))
+        .bearer_auth(token.token);
+    Ok(match impersonated_user() {
+        Some(user_id) => builder.header(IMPERSONATE_HEADER, user_id),
+        None => builder,
+    })
+}
+
+/// Send a request, turning non-2xx responses into `HttpError::Status`.
+///
+/// Rate limit headers are cached from every response, including errors.
+pub async fn send(builder: RequestBuilder) -> Result<Response, HttpError> {
+    let (client, request) = builder.build_split();
+    let mut request = request.map_err(|e| HttpError::Transport(e.to_string()))?;
+    let request_id = logging::request_id();
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        request.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    let span = tracing::info_span!(
+        "http",
+        request_id = %request_id,
+        method = %request.method(),
+        url = %logging::url_for_log(request.url().as_str()),
+    );
+
+    async move {
+        let response = client.execute(request).await.map_err(|e| {
+            let detail = e.to_string();
+            // The URL is already on the span, minus its query string
+            tracing::warn!("Request failed: {}", e.without_url());
+            HttpError::Transport(detail)
+        })?;
+        record_rate_limit(&response);
+
+        let status = response.status();
+        tracing::debug!(status = status.as_u16(), "Response");
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        let body = response.text().await.unwrap_or_default();
+        Err(HttpError::Status {
+            status: status.as_u16(),
+            body,
+        })
+    }
+    .instrument(span)
+    .await
+}
+
+/// Send a request and decode the JSON body.
+pub async fn send_json<T: DeserializeOwned>(builder: RequestBuilder) -> Result<T, HttpError> {
+    send(builder)
+        .await?
+        .json::<T>()
+        .await
+        .map_err(|e| HttpError::Decode(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::auth::set_auth_token;
+    use crate::testsupport::mock_engine::{MockEngine, MOCK_VERSION};
+    use crate::testsupport::TestEnv;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn test_rate_limit_headers() {
+        let now = 1_700_000_000_000;
+        let mut headers = HeaderMap::new();
+        assert_eq!(rate_limit_from_headers(&headers, now), None);
+
+        headers.insert("X-RateLimit-Limit", HeaderValue::from_static("120"));
+        headers.insert("X-RateLimit-Remaining", HeaderValue::from_static("7"));
+        headers.insert("X-RateLimit-Reset", HeaderValue::from_static("30"));
+        let status = rate_limit_from_headers(&headers, now).unwrap();
+        assert_eq!(status.limit, Some(120));
+        assert_eq!(status.remaining, Some(7));
+        assert_eq!(status.reset_at_ms, Some(now + 30_000));
+    }
+
+    #[test]
+    fn test_path_segment() {
+        assert_eq!(path_segment("user-42_a.b~").unwrap(), "user-42_a.b~");
+        assert_eq!(path_segment("../tokens").unwrap(), "..%2Ftokens");
+        assert_eq!(path_segment("a?b#c").unwrap(), "a%3Fb%23c");
+        assert_eq!(path_segment("λ").unwrap(), "%CE%BB");
+        for id in ["", ".", ".."] {
+            assert!(matches!(
+                path_segment(id),
+                Err(HttpError::InvalidPathSegment(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_parse_reset_formats() {
+        let now = 1_700_000_000_000;
+        assert_eq!(parse_reset(1_700_000_060, now), 1_700_000_060_000);
+        assert_eq!(parse_reset(1_700_000_060_000, now), 1_700_000_060_000);
+        assert_eq!(parse_reset(60, now), now + 60_000);
+    }
+
+    #[test]
+    fn test_engine_requests_and_failures() {
+        let _env = TestEnv::new();
+        let mut engine = MockEngine::start();
+        let port = engine.port();
+        let token = "rl_http_test_token_00000001";
+        set_auth_token(token.to_string()).unwrap();
+        let status = || {
+            let request = request(Method::GET, port, "/v1/engine/status").unwrap();
+            tauri::async_runtime::block_on(send_json::<serde_json::Value>(request))
+        };
+
+        engine.fail_next("/v1/engine/status", [503, 404]);
+        let e = status().unwrap_err();
+        assert_eq!(e.status(), Some(503));
+        assert!(e.retryable());
+        assert!(!status().unwrap_err().retryable());
+        // Nothing is retried behind the caller's back
+        assert_eq!(status().unwrap()["version"], MOCK_VERSION);
+        assert_eq!(engine.request_count("/v1/engine/status"), 3);
+
+        let sent = engine.requests().pop().unwrap();
+        assert_eq!(sent.method, "GET");
+        assert_eq!(sent.authorization, Some(format!("Bearer {}", token)));
+        assert!(sent.request_id.is_some());
+
+        engine.stop();
+        let e = status().unwrap_err();
+        assert!(matches!(e, HttpError::Transport(_)));
+        assert!(e.retryable());
+    }
+}
@@ -0,0 +1,385 @@
+//! Localization: backend error messages and the frontend's UI strings.
+//!
+//! Error messages are rendered from the Fluent bundle for the current
+//! locale, keyed by `Localize::code`; `errors` wraps them in the envelope
+//! sent to the frontend. English is the error's own `Display` string, which is
+//! also the per-message fallback when a translation is missing or fails
+//! to format.
+//!
+//! UI strings are flat JSON tables in `assets/locales/<tag>/ui.json`.
+//! Keys missing from a translation fall back to the English table.
+//!
+//! The locale comes from the `locale` setting, or the OS locale on first run.
+
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+use tauri::{AppHandle, Emitter};
+use unic_langid::LanguageIdentifier;
+
+use super::errors::AppError;
+use super::settings;
+
+/// Locale whose messages are the errors' Display strings
+const DEFAULT_LOCALE: &str = "en";
+/// Event emitted after the locale changes
+const LOCALE_CHANGED_EVENT: &str = "locale-changed";
+
+/// Embedded translations: (tag, native name, Fluent source)
+const TRANSLATIONS: &[(&str, &str, &str)] = &[
+    (
+        "de",
+        "Deutsch",
+        include_str!("../../assets/locales/de/errors.ftl"),
+    ),
+    (
+        "el",
+        "Ελληνικά",
+        include_str!("../../assets/locales/el/errors.ftl"),
+    ),
+];
+
+/// Embedded UI string tables: (tag, JSON source). English comes first.
+const UI_STRINGS: &[(&str, &str)] = &[
+    ("en", include_str!("../../assets/locales/en/ui.json")),
+    ("de", include_str!("../../assets/locales/de/ui.json")),
+    ("el", include_str!("../../assets/locales/el/ui.json")),
+];
+
+static CURRENT_LOCALE: RwLock<String> = RwLock::new(String::new());
+
+/// An error with a stable code and parameters for translation.
+pub trait Localize: std::fmt::Display {
+    fn code(&self) -> &'static str;
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        Vec::new()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AvailableLocale {
+    pub tag: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LocaleInfo {
+    pub current: String,
+    /// Locale the OS asks for, resolved to an available one
+    pub os_default: String,
+    /// Locales with bundled UI strings
+    pub available: Vec<String>,
+}
+
+/// Payload of the `locale-changed` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct LocaleChanged {
+    pub locale: String,
+}
+
+fn bundles() -> &'static HashMap<&'static str, FluentBundle<FluentResource>> {
+    static BUNDLES: OnceLock<HashMap<&'static str, FluentBundle<FluentResource>>> = OnceLock::new();
+    BUNDLES.get_or_init(|| {
+        let mut bundles = HashMap::new();
+        for (tag, _, source) in TRANSLATIONS {
+            let resource = match FluentResource::try_new(source.to_string()) {
+                Ok(resource) => resource,
+                Err((resource, errors)) => {
+                    tracing::warn!("Errors in {} translations: {:?}", tag, errors);
+                    resource
+                }
+            };
+            let langid: LanguageIdentifier = tag.parse().unwrap_or_default();
+            let mut bundle = FluentBundle::new_concurrent(vec![langid]);
+            bundle.set_use_isolating(false);
+            if let Err(errors) = bundle.add_resource(resource) {
+                tracing::warn!("Errors in {} translations: {:?}", tag, errors);
+            }
+            bundles.insert(*tag, bundle);
+        }
+        bundles
+    })
+}
+
+/// Map a BCP 47 tag (e.g. "de-AT") to a supported locale, or English.
+fn resolve_locale(tag: &str) -> &'static str {
+    let language = tag
+        .parse::<LanguageIdentifier>()
+        .map(|id| id.language.as_str().to_string())
+        .unwrap_or_default();
+    TRANSLATIONS
+        .iter()
+        .map(|(tag, _, _)| *tag)
+        .find(|t| *t == language)
+        .unwrap_or(DEFAULT_LOCALE)
+}
+
+/// Whether `tag` is a language code with an optional region, e.g. "de" or
+/// "de-AT".
+fn is_locale_code(tag: &str) -> bool {
+    let (language, region) = match tag.split_once('-') {
+        Some((language, region)) => (language, Some(region)),
+        None => (tag, None),
+    };
+    let is_code = |s: &str, upper: bool| {
+        s.len() == 2
+            && s.chars().all(|c| {
+                if upper {
+                    c.is_ascii_uppercase()
+                } else {
+                    c.is_ascii_lowercase()
+                }
+            })
+    };
+    is_code(language, false) && region.is_none_or(|r| is_code(r, true))
+}
+
+/// A UI string table, or `None` if none is bundled for `locale`.
+fn ui_strings(locale: &str) -> Option<Result<HashMap<String, String>, String>> {
+    let (_, source) = UI_STRINGS.iter().find(|(tag, _)| *tag == locale)?;
+    Some(
+        serde_json::from_str(source)
+            .map_err(|e| format!("Invalid UI strings for {}: {}", locale, e)),
+    )
+}
+
+/// Translate a message, or `None` to fall back to English.
+fn translate(locale: &str, code: &str, params: &[(&'static str, String)]) -> Option<String> {
+    let bundle = bundles().get(locale)?;
+    let pattern = bundle.get_message(code)?.value()?;
+
+    let mut args = FluentArgs::new();
+    for (name, value) in params {
+        args.set(*name, value.as_str());
+    }
+    let mut errors = Vec::new();
+    let message = bundle.format_pattern(pattern, Some(&args), &mut errors);
+    errors.is_empty().then(|| message.into_owned())
+}
+
+/// Currently selected locale tag.
+pub fn current_locale() -> String {
+    let locale = CURRENT_LOCALE.read().unwrap();
+    if locale.is_empty() {
+        DEFAULT_LOCALE.to_string()
+    } else {
+        locale.clone()
+    }
+}
+
+/// Message for an error in the current locale.
+pub fn localized_message<E: Localize + ?Sized>(error: &E) -> String {
+    translate(&current_locale(), error.code(), &error.params()).unwrap_or_else(|| error.to_string())
+}
+
+/// Pick the startup locale: saved setting, else the OS locale.
+pub fn init_locale(app: &AppHandle) {
+    let saved = settings::load_settings(app)
+        .map_err(|e| tracing::warn!("Could not read settings: {}", e))
+        .ok()
+        .and_then(|s| s.locale);
+    let tag = saved
+        .or_else(sys_locale::get_locale)
+        .unwrap_or_else(|| DEFAULT_LOCALE.to_string());
+    *CURRENT_LOCALE.write().unwrap() = resolve_locale(&tag).to_string();
+}
+
+/// Locales backend messages are available in.
+#[tauri::command]
+pub fn get_available_locales() -> Vec<AvailableLocale> {
+    std::iter::once((DEFAULT_LOCALE, "English"))
+        .chain(TRANSLATIONS.iter().map(|(tag, name, _)| (*tag, *name)))
+        .map(|(tag, name)| AvailableLocale {
+            tag: tag.to_string(),
+            name: name.to_string(),
+        })
+        .collect()
+}
+
+/// Current locale, the OS default and the locales with UI strings.
+#[tauri::command]
+pub fn get_locale() -> LocaleInfo {
+    let os_default = sys_locale::get_locale().map_or(DEFAULT_LOCALE, |tag| resolve_locale(&tag));
+    LocaleInfo {
+        current: current_locale(),
+        os_default: os_default.to_string(),
+        available: UI_STRINGS.iter().map(|(tag, _)| tag.to_string()).collect(),
+    }
+}
+
+/// Change the locale and remember it.
+///
+/// `locale` must look like "de" or "de-AT"; a region or language without
+/// translations resolves to the closest available locale (or English),
+/// which is what `locale-changed` reports.
+#[tauri::command]
+pub fn set_locale(app: AppHandle, locale: String) -> Result<(), AppError> {
+    if !is_locale_code(&locale) {
+        return Err(AppError::InvalidInput(format!(
+            "Invalid locale code: {:?}",
+            locale
+        )));
+    }
+    let locale = resolve_locale(&locale).to_string();
+    settings::update_settings(&app, |s| s.locale = Some(locale.clone()))?;
+    *CURRENT_LOCALE.write().unwrap() = locale.clone();
+
+    let _ = app.emit(
+        LOCALE_CHANGED_EVENT,
+        LocaleChanged {
+            locale: locale.clone(),
+        },
+    );
+    Ok(())
+}
+
+/// UI strings for `locale`, with English for keys it doesn't translate.
+#[tauri::command]
+pub fn load_translation_strings(locale: String) -> Result<HashMap<String, String>, AppError> {
+    if !is_locale_code(&locale) {
+        return Err(AppError::InvalidInput(format!(
+            "Invalid locale code: {:?}",
+            locale
+        )));
+    }
+    let resolved = resolve_locale(&locale);
+    let mut strings = ui_strings(DEFAULT_LOCALE).unwrap_or_else(|| Ok(HashMap::new()))?;
+    if resolved != DEFAULT_LOCALE {
+        let translated =
+            ui_strings(resolved).ok_or_else(|| format!("No UI strings for {}", locale))??;
+        strings.extend(translated);
+    }
+    Ok(strings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every code the error types can produce
+    const CODES: &[&str] = &[
+        "auth-not-found",
+        "auth-invalid-format",
+        "auth-keychain-error",
+        "auth-file-error",
+        "auth-permissions-unavailable",
+        "auth-missing-scope",
+        "auth-impersonation-reason-required",
+        "http-transport",
+        "http-status",
+        "http-decode",
+        "http-offline-mode",
+        "http-invalid-path-segment",
+        "engine-not-running",
+        "engine-start-failed",
+        "engine-stop-failed",
+        "engine-storage-error",
+        "engine-rate-limit-config",
+        "engine-schema-unavailable",
+        "engine-corpus-not-found",
+        "engine-corpus-not-downloadable",
+        "engine-corpus-checksum-mismatch",
+        "engine-corpus-active",
+        "engine-extension-load-error",
+        "engine-lexicon-unavailable",
+        "engine-invalid-strongs",
+        "engine-invalid-hebrew-strongs",
+        "engine-grpc-error",
+        "engine-cors-config",
+        "engine-invalid-search",
+        "engine-thread-limit",
+        "engine-invalid-config",
+        "engine-invalid-reference",
+        "engine-commentary-not-found",
+        "engine-invalid-versions",
+        "engine-highlight-error",
+        "engine-passage-not-found",
+        "engine-flashcard-deck-error",
+        "reference-empty",
+        "reference-unknown-book",
+        "reference-missing-book",
+        "reference-invalid-number",
+        "reference-invalid-range",
+        "reference-chapter-out-of-range",
+        "reference-verse-out-of-range",
+        "project-newer-version",
+        "project-invalid",
+        "project-file-error",
+        "tts-unavailable",
+        "tts-invalid-rate",
+        "tts-failed",
+        "corpus-import-parse",
+        "corpus-import-exists",
+        "corpus-import-file",
+        "app-invalid-input",
+        "app-not-found",
+        "app-failed",
+    ];
+
+    #[test]
+    fn test_translations_cover_every_code() {
+        for (tag, _, _) in TRANSLATIONS {
+            let bundle = &bundles()[tag];
+            for code in CODES {
+                assert!(bundle.has_message(code), "{} is missing {}", tag, code);
+            }
+        }
+    }
+
+    #[test]
+    fn test_translate_with_params_and_fallback() {
+        let params = vec![("status", "503".to_string()), ("body", "busy".to_string())];
+        assert_eq!(
+            translate("de", "http-status", &params).as_deref(),
+            Some("Die Engine antwortete mit HTTP 503: busy")
+        );
+        assert_eq!(translate("en", "http-status", &params), None);
+        assert_eq!(translate("de", "no-such-message", &params), None);
+    }
+
+    #[test]
+    fn test_ui_strings_match_english() {
+        let english = ui_strings(DEFAULT_LOCALE).unwrap().unwrap();
+        for (tag, _) in UI_STRINGS {
+            let strings = ui_strings(tag).unwrap().unwrap();
+            let mut keys: Vec<_> = strings.keys().collect();
+            let mut english_keys: Vec<_> = english.keys().collect();
+            keys.sort();
+            english_keys.sort();
+            assert_eq!(keys, english_keys, "{}", tag);
+        }
+        let german = load_translation_strings("de-AT".to_string()).unwrap();
+        assert_eq!(german["nav.sources"], "Quellen");
+        assert!(load_translation_strings("fr".to_string()).is_ok());
+    }
+
+    #[test]
+    fn test_is_locale_code() {
+        for valid in ["en", "de", "de-AT", "el-GR"] {
+            assert!(is_locale_code(valid), "{}", valid);
+        }
+        for invalid in [
+            "",
+            "e",
+            "eng",
+            "DE",
+            "de-at",
+            "de_AT",
+            "de-AT-1996",
+            "zh-Hant",
+        ] {
+            assert!(!is_locale_code(invalid), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_resolve_locale() {
+        assert_eq!(resolve_locale("de-AT"), "de");
+        assert_eq!(resolve_locale("el"), "el");
+        assert_eq!(resolve_locale("fr-FR"), "en");
+        assert_eq!(resolve_locale("not a tag"), "en");
+    }
+}
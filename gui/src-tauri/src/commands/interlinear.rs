@@ -0,0 +1,152 @@
+//! Word-by-word interlinear view of a passage.
+//!
+//! The engine lines up each Greek word with its transliteration, gloss,
+//! Strong's number and parsing at `/v1/text/interlinear/{reference}`.
+//! References are checked locally first, so typos don't cost a request,
+//! and passages are cached for five minutes. `export_interlinear_to_html`
+//! turns a passage into a standalone page for printing.
+
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::State;
+
+use super::cache::TtlCache;
+use super::document_export::{escape_html, GREEK_FONT_STACK};
+use super::engine::EngineError;
+use super::http;
+use super::reference::validate_scripture_reference;
+use super::telemetry::{self, Feature};
+
+const INTERLINEAR_TTL: Duration = Duration::from_secs(5 * 60);
+const INTERLINEAR_CACHE_SIZE: usize = 100;
+/// Page style of the HTML export; the table must fit a printed page
+const INTERLINEAR_STYLE: &str = "
+body { margin: 2rem; font-family: Georgia, Cambria, 'Times New Roman', serif; color: #222; }
+h1 { font-size: 1.3rem; }
+table { border-collapse: collapse; width: 100%; }
+th, td { border: 1px solid #999; padding: 0.25rem 0.5rem; text-align: left; vertical-align: top; }
+th { background: #eee; }
+tr { page-break-inside: avoid; }
+.greek { font-family: var(--greek-font); font-size: 1.15em; }
+.translit { font-style: italic; }
+.num { text-align: right; color: #666; }
+@media print { body { margin: 0; } th { background: none; } }
+";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InterlinearWord {
+    pub greek: String,
+    #[serde(default)]
+    pub transliteration: String,
+    #[serde(default)]
+    pub gloss: String,
+    #[serde(default)]
+    pub strongs: Option<u32>,
+    /// Morphological parsing, e.g. "N-NSM"
+    #[serde(default)]
+    pub parsing: Option<String>,
+    /// 1-based position in the passage
+    pub position: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InterlinearPassage {
+    pub reference: String,
+    pub words: Vec<InterlinearWord>,
+}
+
+/// Passages by canonical reference.
+pub struct InterlinearCache(Mutex<TtlCache<String, InterlinearPassage>>);
+
+impl Default for InterlinearCache {
+    fn default() -> Self {
+        Self(Mutex::new(TtlCache::with_capacity(
+            INTERLINEAR_TTL,
+            INTERLINEAR_CACHE_SIZE,
+        )))
+    }
+}
+
+/// The words of a passage with their glosses, parsings and Strong's
+/// numbers, e.g. for "John 1:1".
+#[tauri::command]
+pub async fn generate_interlinear_view(
+    cache: State<'_, InterlinearCache>,
+    port: u16,
+    reference: String,
+) -> Result<InterlinearPassage, EngineError> {
+    telemetry::record_feature(Feature::Interlinear);
+    let validation = validate_scripture_reference(reference);
+    let Some(normalized) = validation.normalized.filter(|_| validation.valid) else {
+        return Err(EngineError::InvalidReference(
+            validation.error.unwrap_or_default(),
+        ));
+    };
+    if let Some(passage) = cache.0.lock().unwrap().get(&normalized) {
+        return Ok(passage);
+    }
+    let path = format!("/v1/text/interlinear/{}", normalized);
+    let mut passage: InterlinearPassage =
+        http::send_json(http::request(Method::GET, port, &path)?).await?;
+    passage.words.sort_by_key(|w| w.position);
+    cache.0.lock().unwrap().insert(normalized, passage.clone());
+    Ok(passage)
+}
+
+/// A standalone HTML page with the passage as a table, one row per word.
+#[tauri::command]
+pub fn export_interlinear_to_html(passage: InterlinearPassage) -> String {
+    let title = escape_html(&passage.reference);
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{}</title>\n<style>:root {{ --greek-font: {}; }}{}</style>\n</head>\n<body>\n\
+         <h1>{}</h1>\n<table>\n<thead><tr><th>#</th><th>Greek</th><th>Transliteration</th>\
+         <th>Gloss</th><th>Strong's</th><th>Parsing</th></tr></thead>\n<tbody>\n",
+        title, GREEK_FONT_STACK, INTERLINEAR_STYLE, title
+    );
+    for word in &passage.words {
+        // Writing to a String can't fail
+        let _ = writeln!(
+            html,
+            "<tr><td class=\"num\">{}</td><td class=\"greek\" lang=\"grc\">{}</td>\
+             <td class=\"translit\">{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            word.position,
+            escape_html(&word.greek),
+            escape_html(&word.transliteration),
+            escape_html(&word.gloss),
+            word.strongs.map(|n| format!("G{}", n)).unwrap_or_default(),
+            escape_html(word.parsing.as_deref().unwrap_or("")),
+        );
+    }
+    html.push_str("</tbody>\n</table>\n</body>\n</html>\n");
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_interlinear_to_html() {
+        let passage: InterlinearPassage = serde_json::from_str(
+            r#"{"reference": "John 1:1", "words": [
+                {"greek": "Ἐν", "transliteration": "En", "gloss": "In", "position": 1},
+                {"greek": "ἀρχῇ", "gloss": "beginning <&>", "strongs": 746,
+                 "parsing": "N-DSF", "position": 2}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(passage.words[0].strongs, None);
+
+        let html = export_interlinear_to_html(passage);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>John 1:1</title>"));
+        assert_eq!(html.matches("<tr><td").count(), 2);
+        assert!(html.contains("<td>G746</td><td>N-DSF</td>"));
+        assert!(html.contains("beginning &lt;&amp;&gt;"));
+        assert!(html.ends_with("</html>\n"));
+    }
+}
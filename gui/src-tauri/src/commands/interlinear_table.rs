@@ -0,0 +1,410 @@
+//! Word-level interlinear export as CSV or TSV.
+//!
+//! Rows come from the token ledger of a traceable translation. When a
+//! cached passage was translated in another mode, the traceable
+//! translation of the same reference is used from the cache, or requested
+//! from the engine and cached. Strong's numbers are filled in from the
+//! offline lexicon when it is installed. The source attribution goes in
+//! a text file next to the table (see `licensing::write_sidecar`), since
+//! extra lines would break CSV readers.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::Write;
+use std::path::PathBuf;
+use tauri::{AppHandle, Emitter};
+
+use super::document_export::PassageExportFailure;
+use super::errors::AppError;
+use super::glossary::{personal_glossary, PersonalGlossary};
+use super::lexicon::strongs_numbers;
+use super::licensing;
+use super::passages::{
+    load_passage, passage_id, translate_and_cache, CachedPassage, TranslateOptions,
+    TranslateOutcome,
+};
+use super::reference::book_display_name;
+use super::save_dialog::{validate_export_path, ExportKind};
+use super::storage;
+use super::usfm::split_verse_id;
+
+/// Event emitted after each passage is prepared
+const PROGRESS_EVENT: &str = "interlinear-export-progress";
+/// Mode and translator that produce a token ledger
+const TRACEABLE: &str = "traceable";
+/// Columns of every exported table, in order
+pub const INTERLINEAR_COLUMNS: &[&str] = &[
+    "reference",
+    "verse_id",
+    "position",
+    "greek",
+    "lemma",
+    "morphology",
+    "gloss",
+    "strongs",
+    "gloss_source",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TableFormat {
+    Csv,
+    Tsv,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct InterlinearTableOptions {
+    /// Start with a UTF-8 BOM so Excel detects the encoding
+    #[serde(default)]
+    pub bom: bool,
+    /// Count rows without writing a file
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Write the source attribution file next to the table; most corpus
+    /// licenses require it when the data is shared
+    #[serde(default = "default_true")]
+    pub include_attribution: bool,
+}
+fn default_true() -> bool {
+    true
+}
+
+impl Default for InterlinearTableOptions {
+    fn default() -> Self {
+        Self {
+            bom: false,
+            dry_run: false,
+            include_attribution: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InterlinearExportProgress {
+    pub passages_done: usize,
+    pub passages_total: usize,
+    pub rows: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InterlinearExportReport {
+    /// Not set for dry runs
+    pub path: Option<String>,
+    pub rows: usize,
+    /// Column names, in file order
+    pub columns: Vec<String>,
+    /// Source attribution written next to the table, if any
+    pub attribution_path: Option<String>,
+    pub failed: Vec<PassageExportFailure>,
+}
+
+/// One token row, in `INTERLINEAR_COLUMNS` order.
+type Row = [String; 9];
+
+/// Human-readable reference for a verse id ("1Corinthians.13.4" -> "1 Corinthians 13:4").
+pub fn verse_reference(verse_id: &str) -> String {
+    match split_verse_id(verse_id) {
+        Some((book, chapter, verse)) => format!(
+            "{} {}:{}",
+            book_display_name(book).unwrap_or(book),
+            chapter,
+            verse
+        ),
+        None => verse_id.to_string(),
+    }
+}
+
+/// The ledger array of a cached response, if it has any tokens.
+pub fn ledger(response: &Value) -> Option<&Vec<Value>> {
+    let ledger = response.get("ledger")?.as_array()?;
+    let has_tokens = ledger.iter().any(|verse| {
+        verse
+            .get("tokens")
+            .and_then(Value::as_array)
+            .is_some_and(|t| !t.is_empty())
+    });
+    has_tokens.then_some(ledger)
+}
+
+/// Rows for each ledger token; the Strong's and gloss source columns are
+/// filled in later.
+fn ledger_rows(ledger: &[Value]) -> Vec<Row> {
+    let text = |token: &Value, key: &str| match token.get(key) {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Number(n)) => n.to_string(),
+        _ => String::new(),
+    };
+    let mut rows = Vec::new();
+    for verse in ledger {
+        let verse_id = verse
+            .get("verse_id")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let tokens = verse.get("tokens").and_then(Value::as_array);
+        for token in tokens.into_iter().flatten() {
+            rows.push([
+                verse_reference(verse_id),
+                verse_id.to_string(),
+                text(token, "position"),
+                text(token, "surface"),
+                text(token, "lemma"),
+                text(token, "morph"),
+                text(token, "gloss"),
+                String::new(),
+                String::new(),
+            ]);
+        }
+    }
+    rows
+}
+
+fn fill_strongs(rows: &mut [Row]) {
+    let numbers = strongs_numbers(rows.iter().map(|r| r[4].as_str()).filter(|l| !l.is_empty()));
+    for row in rows {
+        if let Some(number) = numbers.get(&row[4]) {
+            row[7] = number.clone();
+        }
+    }
+}
+
+/// Put personal glosses in place of the engine's and record where each
+/// gloss came from.
+fn fill_glosses(rows: &mut [Row], glossary: &PersonalGlossary) {
+    for row in rows {
+        if let Some(personal) = glossary.get(&row[4]) {
+            row[6] = personal.gloss.clone();
+            row[8] = "personal".to_string();
+        } else if !row[6].is_empty() {
+            row[8] = "engine".to_string();
+        }
+    }
+}
+
+/// Quote a CSV field when it contains a delimiter, quote or line break.
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// TSV has no quoting, so tabs and line breaks become spaces.
+pub fn tsv_field(value: &str) -> String {
+    value.replace(['\t', '\n', '\r'], " ")
+}
+
+fn write_table(
+    out: &mut dyn Write,
+    format: TableFormat,
+    bom: bool,
+    rows: &[Row],
+) -> std::io::Result<()> {
+    if bom {
+        out.write_all("\u{feff}".as_bytes())?;
+    }
+    let line = |fields: &mut dyn Iterator<Item = &str>| -> String {
+        match format {
+            TableFormat::Csv => fields.map(csv_field).collect::<Vec<_>>().join(","),
+            TableFormat::Tsv => fields.map(tsv_field).collect::<Vec<_>>().join("\t"),
+        }
+    };
+    // CRLF per RFC 4180; Excel and pandas accept it for TSV too
+    write!(
+        out,
+        "{}\r\n",
+        line(&mut INTERLINEAR_COLUMNS.iter().copied())
+    )?;
+    for row in rows {
+        write!(out, "{}\r\n", line(&mut row.iter().map(String::as_str)))?;
+    }
+    Ok(())
+}
+
+/// Ledger for a cached passage, from its own response, the cached
+/// traceable translation of the same reference, or a fresh engine request.
+async fn passage_ledger(
+    app: &AppHandle,
+    port: u16,
+    passage: &CachedPassage,
+) -> Result<Vec<Value>, String> {
+    if let Some(ledger) = ledger(&passage.response) {
+        return Ok(ledger.clone());
+    }
+    let traceable_id = passage_id(&passage.normalized_ref, TRACEABLE, TRACEABLE);
+    if let Some(ledger) = load_passage(app, &traceable_id)
+        .ok()
+        .and_then(|p| ledger(&p.response).cloned())
+    {
+        return Ok(ledger);
+    }
+
+    let options = TranslateOptions {
+        mode: TRACEABLE.to_string(),
+        translator: TRACEABLE.to_string(),
+        ..Default::default()
+    };
+    match translate_and_cache(app, port, &passage.normalized_ref, options)
+        .await
+        .map_err(|e| e.to_string())?
+    {
+        TranslateOutcome::Translation(traceable) => ledger(&traceable.response)
+            .cloned()
+            .ok_or_else(|| "The engine returned no token alignment".to_string()),
+        TranslateOutcome::Gate(_) => {
+            Err("The engine needs an acknowledgement before translating".to_string())
+        }
+    }
+}
+
+/// Export word-level interlinear rows for cached passages as CSV or TSV.
+///
+/// Columns are listed in the report (see `INTERLINEAR_COLUMNS`). With
+/// `dry_run` only the row count is returned. `dest_path` should come from
+/// `choose_save_path` with kind `csv`; a `.tsv` name is kept as is.
+#[tauri::command]
+pub async fn export_interlinear_table(
+    app: AppHandle,
+    port: u16,
+    passage_ids: Vec<String>,
+    dest_path: String,
+    format: TableFormat,
+    options: Option<InterlinearTableOptions>,
+) -> Result<InterlinearExportReport, AppError> {
+    let options = options.unwrap_or_default();
+    let path = if options.dry_run {
+        None
+    } else {
+        Some(validate_export_path(
+            &app,
+            ExportKind::Csv,
+            &PathBuf::from(dest_path),
+        )?)
+    };
+
+    let mut attribution = match &path {
+        Some(_) => licensing::export_attribution(options.include_attribution),
+        None => None,
+    };
+    let mut rows = Vec::new();
+    let mut failed = Vec::new();
+    for (done, id) in passage_ids.iter().enumerate() {
+        let ledger = match load_passage(&app, id) {
+            Ok(passage) => passage_ledger(&app, port, &passage)
+                .await
+                .map(|ledger| (passage, ledger)),
+            Err(e) => Err(e),
+        };
+        match ledger {
+            Ok((passage, ledger)) => {
+                rows.extend(ledger_rows(&ledger));
+                if let Some(attribution) = &mut attribution {
+                    attribution.add_passage(&passage);
+                }
+            }
+            Err(error) => failed.push(PassageExportFailure {
+                passage_id: id.clone(),
+                error,
+            }),
+        }
+        let _ = app.emit(
+            PROGRESS_EVENT,
+            InterlinearExportProgress {
+                passages_done: done + 1,
+                passages_total: passage_ids.len(),
+                rows: rows.len(),
+            },
+        );
+    }
+
+    let mut attribution_path = None;
+    if let Some(path) = &path {
+        fill_strongs(&mut rows);
+        fill_glosses(&mut rows, &personal_glossary(&app));
+        storage::write_atomic_with(path, |out| write_table(out, format, options.bom, &rows))?;
+        if let Some(attribution) = &attribution {
+            let sidecar = licensing::write_sidecar(path, attribution)?;
+            attribution_path = Some(sidecar.to_string_lossy().into_owned());
+        }
+    }
+
+    Ok(InterlinearExportReport {
+        path: path.map(|p| p.to_string_lossy().into_owned()),
+        rows: rows.len(),
+        columns: INTERLINEAR_COLUMNS.iter().map(|c| c.to_string()).collect(),
+        attribution_path,
+        failed,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::glossary::PersonalGloss;
+
+    fn sample_ledger() -> Value {
+        serde_json::json!({
+            "ledger": [{
+                "verse_id": "1Corinthians.13.4",
+                "tokens": [
+                    {"position": 0, "surface": "Ἡ", "lemma": "ὁ", "morph": "RA-NSF", "gloss": "the"},
+                    {"position": 1, "surface": "ἀγάπη", "lemma": "ἀγάπη", "morph": "N-NSF",
+                     "gloss": "love, \"charity\""},
+                ],
+            }]
+        })
+    }
+
+    #[test]
+    fn test_ledger_rows() {
+        let response = sample_ledger();
+        let rows = ledger_rows(ledger(&response).unwrap());
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1][0], "1 Corinthians 13:4");
+        assert_eq!(rows[1][2], "1");
+        assert_eq!(rows[1][5], "N-NSF");
+        assert!(ledger(&serde_json::json!({"ledger": null})).is_none());
+        assert!(ledger(&serde_json::json!({"ledger": [{"tokens": []}]})).is_none());
+    }
+
+    #[test]
+    fn test_personal_glosses_replace_engine_glosses() {
+        let response = sample_ledger();
+        let mut rows = ledger_rows(ledger(&response).unwrap());
+        let glossary = PersonalGlossary::new(vec![PersonalGloss {
+            lemma: "αγαπη".to_string(),
+            gloss: "self-giving love".to_string(),
+            note: None,
+            updated_at_ms: 0,
+        }]);
+        fill_glosses(&mut rows, &glossary);
+        assert_eq!(
+            (rows[0][6].as_str(), rows[0][8].as_str()),
+            ("the", "engine")
+        );
+        assert_eq!(
+            (rows[1][6].as_str(), rows[1][8].as_str()),
+            ("self-giving love", "personal")
+        );
+    }
+
+    #[test]
+    fn test_write_table_escaping() {
+        let response = sample_ledger();
+        let rows = ledger_rows(ledger(&response).unwrap());
+
+        let mut csv = Vec::new();
+        write_table(&mut csv, TableFormat::Csv, true, &rows).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert!(csv.starts_with("\u{feff}reference,verse_id,position,"));
+        assert!(csv.contains(",\"love, \"\"charity\"\"\",,\r\n"));
+        assert_eq!(csv.lines().count(), 3);
+
+        let mut tsv = Vec::new();
+        write_table(&mut tsv, TableFormat::Tsv, false, &rows).unwrap();
+        let tsv = String::from_utf8(tsv).unwrap();
+        assert!(tsv.starts_with("reference\tverse_id\t"));
+        assert!(tsv.contains("\tlove, \"charity\"\t"));
+        assert_eq!(tsv_field("a\tb\nc"), "a b c");
+    }
+}
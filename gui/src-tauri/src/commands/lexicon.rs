@@ -0,0 +1,426 @@
+//! Lemma lookups, with an offline fallback.
+//!
+//! The offline lexicon is an optional pack installed through the corpus
+//! manager: a SQLite file with a `lemmas` table and a `lemmas_fts` FTS5
+//! index over the normalized lemma. Keys are normalized the same way on
+//! both sides (NFD, combining marks dropped, lowercased, final sigma
+//! folded), so "λόγος", "ΛΟΓΟΣ" and "λογος" all find the same entry.
+//!
+//! The pack also carries a `strongs` table mapping Greek Strong's numbers
+//! to lemmas, used for Strong's lookups and to fill in the number when the
+//! engine's entry lacks one. Newer packs add a `frequency` column to
+//! `lemmas` with the lemma's occurrence count in the New Testament.
+
+use reqwest::Method;
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tauri::AppHandle;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+use super::corpus::{data_root, installed_from_manifest};
+use super::engine::EngineError;
+use super::glossary::personal_glossary;
+use super::http::{self, HttpError};
+
+/// Corpus manager id of the offline lexicon pack
+const LEXICON_PACK_ID: &str = "lexicon-offline";
+/// Database file inside the pack's install directory
+const LEXICON_DB_FILE: &str = "lexicon.sqlite";
+/// Upper bound for `search_lemmas_offline`
+const MAX_SEARCH_RESULTS: u32 = 100;
+/// Highest number in Strong's Greek dictionary
+const MAX_STRONGS_GREEK: u32 = 5624;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LemmaSource {
+    #[default]
+    Engine,
+    /// From the offline pack; glosses only, no full entry
+    Offline,
+    /// The gloss is the user's own, from the personal glossary
+    Personal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LemmaEntry {
+    pub lemma: String,
+    #[serde(default)]
+    pub glosses: Vec<String>,
+    /// Short morphology summary, e.g. "noun, masc., 2nd decl."
+    #[serde(default)]
+    pub morphology: Option<String>,
+    /// Strong's number, e.g. "G3056"
+    #[serde(default)]
+    pub strongs: Option<String>,
+    #[serde(default)]
+    pub source: LemmaSource,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StrongsEntry {
+    /// Canonical form, e.g. "G3056"
+    pub number: String,
+    pub lemma: String,
+    pub transliteration: String,
+    pub gloss: String,
+}
+
+/// Parse "G3056", "g3056" or "3056" into the bare number.
+pub fn parse_strongs(number: &str) -> Result<u32, EngineError> {
+    let invalid = || EngineError::InvalidStrongsNumber(number.trim().to_string());
+    let digits = number.trim();
+    let digits = digits.strip_prefix(['G', 'g']).unwrap_or(digits);
+    digits
+        .parse::<u32>()
+        .ok()
+        .filter(|n| (1..=MAX_STRONGS_GREEK).contains(n))
+        .ok_or_else(invalid)
+}
+
+fn strongs_label(number: u32) -> String {
+    format!("G{}", number)
+}
+
+/// Normalize a lemma for lookup: strip accents and breathings, lowercase.
+pub fn normalize_lemma(lemma: &str) -> String {
+    lemma
+        .trim()
+        .nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(char::to_lowercase)
+        .map(|c| if c == 'ς' { 'σ' } else { c })
+        .collect()
+}
+
+fn sqlite_err(e: rusqlite::Error) -> EngineError {
+    EngineError::Storage(e.to_string())
+}
+
+/// Path of the installed lexicon database.
+fn lexicon_path() -> Result<PathBuf, EngineError> {
+    let pack = installed_from_manifest(&data_root()?)?
+        .into_iter()
+        .find(|c| c.id == LEXICON_PACK_ID)
+        .ok_or(EngineError::LexiconUnavailable)?;
+    let path = PathBuf::from(pack.install_path).join(LEXICON_DB_FILE);
+    if path.is_file() {
+        Ok(path)
+    } else {
+        Err(EngineError::LexiconUnavailable)
+    }
+}
+
+fn open_lexicon() -> Result<Connection, EngineError> {
+    Connection::open_with_flags(lexicon_path()?, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(sqlite_err)
+}
+
+fn entry_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<LemmaEntry> {
+    let glosses: String = row.get(1)?;
+    Ok(LemmaEntry {
+        lemma: row.get(0)?,
+        // Stored as a JSON array; tolerate a plain "; "-separated string
+        glosses: serde_json::from_str(&glosses).unwrap_or_else(|_| {
+            glosses
+                .split(';')
+                .map(|g| g.trim().to_string())
+                .filter(|g| !g.is_empty())
+                .collect()
+        }),
+        morphology: row.get(2)?,
+        strongs: None,
+        source: LemmaSource::Offline,
+    })
+}
+
+fn strongs_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<StrongsEntry> {
+    Ok(StrongsEntry {
+        number: strongs_label(row.get(0)?),
+        lemma: row.get(1)?,
+        transliteration: row.get(2)?,
+        gloss: row.get(3)?,
+    })
+}
+
+fn strongs_by_number(conn: &Connection, number: u32) -> Result<Option<StrongsEntry>, EngineError> {
+    conn.query_row(
+        "SELECT number, lemma, transliteration, gloss FROM strongs WHERE number = ?1",
+        params![number],
+        strongs_from_row,
+    )
+    .optional()
+    .map_err(sqlite_err)
+}
+
+fn strongs_by_lemma(conn: &Connection, lemma: &str) -> Result<Vec<StrongsEntry>, EngineError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT number, lemma, transliteration, gloss FROM strongs \
+             WHERE normalized = ?1 ORDER BY number",
+        )
+        .map_err(sqlite_err)?;
+    let rows = stmt
+        .query_map(params![normalize_lemma(lemma)], strongs_from_row)
+        .map_err(sqlite_err)?;
+    rows.collect::<Result<_, _>>().map_err(sqlite_err)
+}
+
+/// Fill in the Strong's number from the mapping table if it is missing.
+fn with_strongs(conn: &Connection, mut entry: LemmaEntry) -> LemmaEntry {
+    if entry.strongs.is_none() {
+        entry.strongs = strongs_by_lemma(conn, &entry.lemma)
+            .ok()
+            .and_then(|found| found.into_iter().next())
+            .map(|s| s.number);
+    }
+    entry
+}
+
+/// Strong's numbers for a batch of lemmas, keyed by the lemma as given.
+/// Empty when the offline lexicon isn't installed.
+pub fn strongs_numbers<'a>(lemmas: impl IntoIterator<Item = &'a str>) -> HashMap<String, String> {
+    let Ok(conn) = open_lexicon() else {
+        return HashMap::new();
+    };
+    let mut numbers = HashMap::new();
+    for lemma in lemmas {
+        if numbers.contains_key(lemma) {
+            continue;
+        }
+        if let Some(found) = strongs_by_lemma(&conn, lemma)
+            .ok()
+            .and_then(|found| found.into_iter().next())
+        {
+            numbers.insert(lemma.to_string(), found.number);
+        }
+    }
+    numbers
+}
+
+/// NT occurrence count; `None` for packs without the `frequency` column.
+fn frequency_in(conn: &Connection, lemma: &str) -> Option<u32> {
+    conn.query_row(
+        "SELECT frequency FROM lemmas WHERE normalized = ?1 LIMIT 1",
+        params![normalize_lemma(lemma)],
+        |row| row.get::<_, Option<u32>>(0),
+    )
+    .ok()
+    .flatten()
+}
+
+/// Offline entries and NT frequencies for a batch of lemmas, keyed by the
+/// lemma as given. Empty when the offline lexicon isn't installed.
+pub fn lexicon_entries<'a>(
+    lemmas: impl IntoIterator<Item = &'a str>,
+) -> HashMap<String, (LemmaEntry, Option<u32>)> {
+    let Ok(conn) = open_lexicon() else {
+        return HashMap::new();
+    };
+    let mut entries = HashMap::new();
+    for lemma in lemmas {
+        if entries.contains_key(lemma) {
+            continue;
+        }
+        if let Ok(Some(entry)) = lookup_in(&conn, lemma) {
+            entries.insert(lemma.to_string(), (entry, frequency_in(&conn, lemma)));
+        }
+    }
+    entries
+}
+
+fn lookup_in(conn: &Connection, lemma: &str) -> Result<Option<LemmaEntry>, EngineError> {
+    let entry = conn
+        .query_row(
+            "SELECT lemma, glosses, morphology FROM lemmas WHERE normalized = ?1 LIMIT 1",
+            params![normalize_lemma(lemma)],
+            entry_from_row,
+        )
+        .optional()
+        .map_err(sqlite_err)?;
+    Ok(entry.map(|entry| with_strongs(conn, entry)))
+}
+
+fn search_in(conn: &Connection, prefix: &str, limit: u32) -> Result<Vec<LemmaEntry>, EngineError> {
+    let prefix = normalize_lemma(prefix);
+    if prefix.is_empty() {
+        return Ok(Vec::new());
+    }
+    // Quote the term so FTS syntax characters in user input are literal
+    let query = format!("\"{}\"*", prefix.replace('"', "\"\""));
+    let mut stmt = conn
+        .prepare(
+            "SELECT l.lemma, l.glosses, l.morphology FROM lemmas_fts \
+             JOIN lemmas l ON l.rowid = lemmas_fts.rowid \
+             WHERE lemmas_fts MATCH ?1 ORDER BY length(l.normalized), l.normalized LIMIT ?2",
+        )
+        .map_err(sqlite_err)?;
+    let rows = stmt
+        .query_map(
+            params![query, limit.min(MAX_SEARCH_RESULTS)],
+            entry_from_row,
+        )
+        .map_err(sqlite_err)?;
+    rows.collect::<Result<_, _>>().map_err(sqlite_err)
+}
+
+/// Look up a lemma in the offline lexicon pack. A personal gloss takes
+/// the place of the pack's glosses, and is returned on its own for lemmas
+/// the pack lacks.
+#[tauri::command]
+pub fn lookup_lemma_offline(
+    app: AppHandle,
+    lemma: String,
+) -> Result<Option<LemmaEntry>, EngineError> {
+    let glossary = personal_glossary(&app);
+    match open_lexicon().and_then(|conn| lookup_in(&conn, &lemma)) {
+        Ok(Some(entry)) => Ok(Some(glossary.apply(entry))),
+        Ok(None) => Ok(glossary.entry(&lemma)),
+        Err(e) => glossary.entry(&lemma).map(Some).ok_or(e),
+    }
+}
+
+/// Lemmas starting with `prefix`, shortest first, for autocomplete.
+#[tauri::command]
+pub fn search_lemmas_offline(
+    app: AppHandle,
+    prefix: String,
+    limit: u32,
+) -> Result<Vec<LemmaEntry>, EngineError> {
+    let glossary = personal_glossary(&app);
+    Ok(search_in(&open_lexicon()?, &prefix, limit)?
+        .into_iter()
+        .map(|entry| glossary.apply(entry))
+        .collect())
+}
+
+/// Lemma, transliteration and gloss for a Strong's number ("G3056" or "3056").
+#[tauri::command]
+pub fn lookup_strongs(number: String) -> Result<Option<StrongsEntry>, EngineError> {
+    let number = parse_strongs(&number)?;
+    strongs_by_number(&open_lexicon()?, number)
+}
+
+/// Strong's numbers for a lemma; homographs can have more than one.
+#[tauri::command]
+pub fn strongs_for_lemma(lemma: String) -> Result<Vec<StrongsEntry>, EngineError> {
+    strongs_by_lemma(&open_lexicon()?, &lemma)
+}
+
+/// Look up a lemma through the engine, falling back to the offline pack
+/// when the engine can't be reached. Engine entries without a Strong's
+/// number get one from the pack when it is installed, and a personal
+/// gloss replaces the engine's glosses.
+#[tauri::command]
+pub async fn lookup_lemma(
+    app: AppHandle,
+    port: u16,
+    lemma: String,
+) -> Result<Option<LemmaEntry>, EngineError> {
+    let request =
+        http::request(Method::GET, port, "/v1/lexicon/lookup")?.query(&[("lemma", &lemma)]);
+    let glossary = personal_glossary(&app);
+    match http::send_json::<LemmaEntry>(request).await {
+        Ok(entry) => {
+            let entry = match open_lexicon() {
+                Ok(conn) => with_strongs(&conn, entry),
+                Err(_) => entry,
+            };
+            Ok(Some(glossary.apply(entry)))
+        }
+        Err(HttpError::Status { status: 404, .. }) => Ok(glossary.entry(&lemma)),
+        Err(HttpError::Transport(_)) => lookup_lemma_offline(app, lemma),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE lemmas (lemma TEXT, normalized TEXT, glosses TEXT, morphology TEXT);
+             CREATE VIRTUAL TABLE lemmas_fts USING fts5(normalized);
+             CREATE TABLE strongs (number INTEGER, lemma TEXT, normalized TEXT,
+                                   transliteration TEXT, gloss TEXT);",
+        )
+        .unwrap();
+        for (lemma, glosses) in [
+            ("λόγος", r#"["word", "message"]"#),
+            ("λογίζομαι", "reckon; consider"),
+            ("ἀγάπη", r#"["love"]"#),
+        ] {
+            conn.execute(
+                "INSERT INTO lemmas VALUES (?1, ?2, ?3, NULL)",
+                params![lemma, normalize_lemma(lemma), glosses],
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO lemmas_fts (rowid, normalized) VALUES (last_insert_rowid(), ?1)",
+                params![normalize_lemma(lemma)],
+            )
+            .unwrap();
+        }
+        conn.execute(
+            "INSERT INTO strongs VALUES (3056, 'λόγος', ?1, 'logos', 'word')",
+            params![normalize_lemma("λόγος")],
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_normalize_lemma() {
+        assert_eq!(normalize_lemma("Λόγος"), "λογοσ");
+        assert_eq!(normalize_lemma(" ἀγάπη "), "αγαπη");
+        assert_eq!(normalize_lemma("ΛΟΓΟΣ"), normalize_lemma("λόγος"));
+    }
+
+    #[test]
+    fn test_offline_lookup_and_search() {
+        let conn = test_db();
+        let entry = lookup_in(&conn, "ΛΟΓΟΣ").unwrap().unwrap();
+        assert_eq!(entry.lemma, "λόγος");
+        assert_eq!(entry.glosses, ["word", "message"]);
+        assert_eq!(entry.source, LemmaSource::Offline);
+        assert_eq!(entry.strongs.as_deref(), Some("G3056"));
+        assert!(lookup_in(&conn, "θεός").unwrap().is_none());
+        // The test pack predates the frequency column
+        assert_eq!(frequency_in(&conn, "λόγος"), None);
+        conn.execute_batch(
+            "ALTER TABLE lemmas ADD COLUMN frequency INTEGER; UPDATE lemmas SET frequency = 330",
+        )
+        .unwrap();
+        assert_eq!(frequency_in(&conn, "Λόγος"), Some(330));
+
+        let results = search_in(&conn, "λογ", 10).unwrap();
+        let lemmas: Vec<_> = results.iter().map(|e| e.lemma.as_str()).collect();
+        assert_eq!(lemmas, ["λόγος", "λογίζομαι"]);
+        assert_eq!(results[1].glosses, ["reckon", "consider"]);
+        assert!(search_in(&conn, "\"", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_strongs_lookup() {
+        assert_eq!(parse_strongs("G3056").unwrap(), 3056);
+        assert_eq!(parse_strongs(" 3056 ").unwrap(), 3056);
+        for invalid in ["G0", "G5625", "H430", "G", "logos"] {
+            assert!(matches!(
+                parse_strongs(invalid),
+                Err(EngineError::InvalidStrongsNumber(_))
+            ));
+        }
+
+        let conn = test_db();
+        let entry = strongs_by_number(&conn, 3056).unwrap().unwrap();
+        assert_eq!(entry.number, "G3056");
+        assert_eq!(entry.transliteration, "logos");
+        assert!(strongs_by_number(&conn, 26).unwrap().is_none());
+        assert_eq!(strongs_by_lemma(&conn, "ΛΟΓΟΣ").unwrap(), [entry]);
+    }
+}
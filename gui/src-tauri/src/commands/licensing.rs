@@ -0,0 +1,446 @@
+//! Source and license attribution for exports.
+//!
+//! The Greek texts and lexicons the engine reads carry licenses that
+//! require attribution (SBLGNT and MorphGNT are CC BY-SA). What each
+//! installed corpus needs is bundled in `assets/corpus_licenses.json`,
+//! keyed by the installer's source id; the installer's manifest supplies
+//! the installed name, version and license, and wins where it says more.
+//!
+//! A translation credits the corpora in its `provenance` (`spine_source`,
+//! `sources_used`) and the sense packs it took glosses from, which carry
+//! their own license data. Responses from engines that report no
+//! provenance were translated from the SBLGNT spine, which every
+//! translation uses. Exports gather the attributions of all their
+//! passages, plus the engine version and translator that produced them.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use super::corpus::{data_root, installed_from_manifest, InstalledCorpus};
+use super::errors::AppError;
+use super::passages::CachedPassage;
+use super::storage;
+
+const BUNDLED_LICENSES: &str = include_str!("../../assets/corpus_licenses.json");
+/// Canonical text spine, credited when a response names no sources
+const SPINE_CORPUS: &str = "morphgnt-sblgnt";
+const UNKNOWN_LICENSE: &str = "not recorded";
+/// Suffix of the attribution file written next to CSV and TSV exports
+const SIDECAR_SUFFIX: &str = "attribution.txt";
+
+/// Attribution data for a corpus the app knows about.
+#[derive(Debug, Deserialize)]
+struct KnownCorpus {
+    id: String,
+    /// Names the engine uses for it in provenance
+    #[serde(default)]
+    aliases: Vec<String>,
+    name: String,
+    license: String,
+    #[serde(default)]
+    license_url: Option<String>,
+    attribution: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LicenseInfo {
+    pub corpus_id: String,
+    pub name: String,
+    /// Empty when unknown
+    pub version: String,
+    /// SPDX identifier where there is one, e.g. "CC-BY-SA-3.0"
+    pub license: String,
+    pub license_url: Option<String>,
+    /// Text to reproduce wherever the data is redistributed
+    pub attribution: String,
+    pub installed: bool,
+}
+
+fn known_corpora() -> &'static [KnownCorpus] {
+    static KNOWN: OnceLock<Vec<KnownCorpus>> = OnceLock::new();
+    KNOWN.get_or_init(|| {
+        serde_json::from_str(BUNDLED_LICENSES).unwrap_or_else(|e| {
+            tracing::warn!("Invalid corpus licenses: {}", e);
+            Vec::new()
+        })
+    })
+}
+
+fn find_known(id: &str) -> Option<&'static KnownCorpus> {
+    known_corpora().iter().find(|k| {
+        k.id.eq_ignore_ascii_case(id) || k.aliases.iter().any(|a| a.eq_ignore_ascii_case(id))
+    })
+}
+
+/// "Name version, license: X." for sources without a bundled attribution.
+fn generic_attribution(name: &str, version: &str, license: &str) -> String {
+    let version = if version.is_empty() {
+        String::new()
+    } else {
+        format!(" {}", version)
+    };
+    format!("{}{}, license: {}.", name, version, license)
+}
+
+/// License data for a corpus, by installer id or a name the engine uses
+/// for it. `None` when the corpus is neither bundled nor installed.
+pub fn license_info(id: &str, installed: &[InstalledCorpus]) -> Option<LicenseInfo> {
+    let known = find_known(id);
+    let corpus_id = known.map_or(id, |k| k.id.as_str());
+    let installed = installed
+        .iter()
+        .find(|c| c.id.eq_ignore_ascii_case(corpus_id));
+    if known.is_none() && installed.is_none() {
+        return None;
+    }
+
+    let name = installed
+        .map(|c| c.name.clone())
+        .or_else(|| known.map(|k| k.name.clone()))
+        .unwrap_or_default();
+    let version = installed.map(|c| c.version.clone()).unwrap_or_default();
+    let license = installed
+        .map(|c| c.license.clone())
+        .filter(|l| !l.is_empty())
+        .or_else(|| known.map(|k| k.license.clone()))
+        .unwrap_or_else(|| UNKNOWN_LICENSE.to_string());
+    Some(LicenseInfo {
+        corpus_id: corpus_id.to_string(),
+        attribution: known.map_or_else(
+            || generic_attribution(&name, &version, &license),
+            |k| k.attribution.clone(),
+        ),
+        license_url: known.and_then(|k| k.license_url.clone()),
+        installed: installed.is_some(),
+        name,
+        version,
+        license,
+    })
+}
+
+/// Corpora from the installer manifest; empty if it can't be read.
+fn installed_corpora() -> Vec<InstalledCorpus> {
+    data_root()
+        .and_then(|root| installed_from_manifest(&root))
+        .unwrap_or_else(|e| {
+            tracing::warn!("Attributing without the installed corpus list: {}", e);
+            Vec::new()
+        })
+}
+
+/// A sense pack credited in provenance, with the citation data it carries.
+fn sense_pack_license(pack: &Value) -> Option<LicenseInfo> {
+    let field = |key: &str| {
+        pack.get(key)
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+    };
+    let source_id = field("source_id")?;
+    let title = field("source_title").unwrap_or(source_id);
+    let license = field("license").unwrap_or(UNKNOWN_LICENSE);
+    let year = pack
+        .get("year")
+        .and_then(Value::as_u64)
+        .map(|y| y.to_string());
+    let citation: Vec<&str> = [Some(title), field("edition"), field("publisher")]
+        .into_iter()
+        .flatten()
+        .chain(year.as_deref())
+        .collect();
+    Some(LicenseInfo {
+        corpus_id: field("pack_id").unwrap_or(source_id).to_string(),
+        name: title.to_string(),
+        version: field("edition").unwrap_or("").to_string(),
+        license: license.to_string(),
+        license_url: field("license_url").map(str::to_string),
+        attribution: format!("{}, license: {}.", citation.join(", "), license),
+        installed: true,
+    })
+}
+
+/// Source names a translation response credits.
+fn response_sources(response: &Value) -> Vec<String> {
+    let provenance = response.get("provenance");
+    let spine = provenance
+        .and_then(|p| p.get("spine_source"))
+        .and_then(Value::as_str);
+    let used = provenance
+        .and_then(|p| p.get("sources_used"))
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str);
+    let sources: Vec<String> = spine
+        .into_iter()
+        .chain(used)
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect();
+    if sources.is_empty() {
+        vec![SPINE_CORPUS.to_string()]
+    } else {
+        sources
+    }
+}
+
+/// Sources and engine runs behind a set of exported passages.
+#[derive(Debug, Clone, Default)]
+pub struct Attribution {
+    installed: Vec<InstalledCorpus>,
+    sources: Vec<LicenseInfo>,
+    /// "literal translator, engine 0.9.0", once each
+    engines: Vec<String>,
+}
+
+impl Attribution {
+    pub fn new(installed: Vec<InstalledCorpus>) -> Self {
+        Self {
+            installed,
+            ..Default::default()
+        }
+    }
+
+    /// An empty attribution using the installed corpus list.
+    pub fn load() -> Self {
+        Self::new(installed_corpora())
+    }
+
+    fn add_source(&mut self, info: LicenseInfo) {
+        if !self.sources.iter().any(|s| s.corpus_id == info.corpus_id) {
+            self.sources.push(info);
+        }
+    }
+
+    pub fn add_passage(&mut self, passage: &CachedPassage) {
+        for source in response_sources(&passage.response) {
+            let info = license_info(&source, &self.installed).unwrap_or_else(|| LicenseInfo {
+                corpus_id: source.clone(),
+                name: source.clone(),
+                version: String::new(),
+                license: UNKNOWN_LICENSE.to_string(),
+                license_url: None,
+                attribution: generic_attribution(&source, "", UNKNOWN_LICENSE),
+                installed: false,
+            });
+            self.add_source(info);
+        }
+        let packs = passage
+            .response
+            .pointer("/provenance/sense_packs_used")
+            .and_then(Value::as_array);
+        for info in packs.into_iter().flatten().filter_map(sense_pack_license) {
+            self.add_source(info);
+        }
+
+        let engine = format!(
+            "{} translator, engine {}",
+            passage.translator,
+            passage
+                .engine_version
+                .as_deref()
+                .unwrap_or("version not recorded")
+        );
+        if !self.engines.contains(&engine) {
+            self.engines.push(engine);
+        }
+    }
+
+    pub fn sources(&self) -> &[LicenseInfo] {
+        &self.sources
+    }
+
+    /// One line per source, then one naming the engine runs; empty when
+    /// no passage was added.
+    pub fn lines(&self) -> Vec<String> {
+        if self.engines.is_empty() {
+            return Vec::new();
+        }
+        let mut lines: Vec<String> = self
+            .sources
+            .iter()
+            .map(|s| match &s.license_url {
+                Some(url) => format!("{} {}", s.attribution, url),
+                None => s.attribution.clone(),
+            })
+            .collect();
+        lines.push(format!(
+            "Translated with Red Letters: {}.",
+            self.engines.join("; ")
+        ));
+        lines
+    }
+}
+
+/// The attribution for an export, or `None` when it was turned off, which
+/// is logged: most corpus licenses require it when the text is shared.
+pub fn export_attribution(include_attribution: bool) -> Option<Attribution> {
+    if !include_attribution {
+        tracing::warn!(
+            "Exporting without source attribution; the corpus licenses may require it when sharing"
+        );
+        return None;
+    }
+    Some(Attribution::load())
+}
+
+/// Attribution lines for passages already loaded; empty when turned off.
+pub fn attribution_lines<'a>(
+    include_attribution: bool,
+    passages: impl IntoIterator<Item = &'a CachedPassage>,
+) -> Vec<String> {
+    let Some(mut attribution) = export_attribution(include_attribution) else {
+        return Vec::new();
+    };
+    passages
+        .into_iter()
+        .for_each(|p| attribution.add_passage(p));
+    attribution.lines()
+}
+
+/// Path of the attribution file accompanying a table export.
+pub fn sidecar_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{}.{}", stem, SIDECAR_SUFFIX))
+}
+
+/// Write the attribution next to a CSV or TSV export, whose readers would
+/// take extra lines in the file for data. Returns the file written.
+pub fn write_sidecar(path: &Path, attribution: &Attribution) -> Result<PathBuf, String> {
+    let sidecar = sidecar_path(path);
+    let mut text = format!(
+        "Sources of {}\n\n",
+        path.file_name().unwrap_or_default().to_string_lossy()
+    );
+    for line in attribution.lines() {
+        text.push_str(&line);
+        text.push('\n');
+    }
+    storage::write_atomic(&sidecar, text.as_bytes())?;
+    Ok(sidecar)
+}
+
+/// Attribution and license of a corpus, for the About dialog.
+#[tauri::command]
+pub fn get_license_info(corpus_id: String) -> Result<LicenseInfo, AppError> {
+    license_info(&corpus_id, &installed_corpora())
+        .ok_or_else(|| AppError::NotFound(format!("Unknown corpus: {}", corpus_id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testsupport::passage::PassageBuilder;
+
+    fn installed(id: &str, license: &str) -> InstalledCorpus {
+        InstalledCorpus {
+            id: id.to_string(),
+            name: format!("{} (installed)", id),
+            version: "1.0".to_string(),
+            license: license.to_string(),
+            install_path: format!("/data/{}", id),
+            installed_at: None,
+            active: false,
+            user_imported: false,
+        }
+    }
+
+    fn passage(translator: &str, engine_version: Option<&str>, response: Value) -> CachedPassage {
+        PassageBuilder::new()
+            .reference("John 1:1")
+            .translator(translator)
+            .engine_version(engine_version)
+            .response(response)
+            .build()
+    }
+
+    #[test]
+    fn test_license_info() {
+        let sblgnt = license_info("SBLGNT", &[]).unwrap();
+        assert_eq!(sblgnt.corpus_id, "morphgnt-sblgnt");
+        assert_eq!(sblgnt.license, "CC-BY-SA-3.0");
+        assert!(sblgnt
+            .attribution
+            .contains("Society of Biblical Literature"));
+        assert!(!sblgnt.installed);
+
+        let installed = [
+            installed("morphgnt-sblgnt", ""),
+            installed("local-lxx", "CC-BY-NC-4.0"),
+        ];
+        let sblgnt = license_info("morphgnt-sblgnt", &installed).unwrap();
+        assert!(sblgnt.installed);
+        assert_eq!(
+            (sblgnt.version.as_str(), sblgnt.license.as_str()),
+            ("1.0", "CC-BY-SA-3.0")
+        );
+
+        let lxx = license_info("local-lxx", &installed).unwrap();
+        assert_eq!(
+            lxx.attribution,
+            "local-lxx (installed) 1.0, license: CC-BY-NC-4.0."
+        );
+        assert_eq!(license_info("nowhere", &installed), None);
+    }
+
+    #[test]
+    fn test_attribution_spans_corpora() {
+        let mut attribution = Attribution::new(vec![installed("byzantine-john", "")]);
+        attribution.add_passage(&passage("literal", Some("0.9.0"), Value::Null));
+        attribution.add_passage(&passage(
+            "fluent",
+            None,
+            serde_json::json!({
+                "provenance": {
+                    "spine_source": "SBLGNT",
+                    "sources_used": ["byzantine-john", "MorphGNT", "private-notes"],
+                    "sense_packs_used": [{
+                        "pack_id": "lsj-core", "source_id": "LSJ",
+                        "source_title": "A Greek-English Lexicon", "edition": "9th",
+                        "year": 1940, "license": "CC-BY-SA-4.0"
+                    }]
+                }
+            }),
+        ));
+
+        let ids: Vec<&str> = attribution
+            .sources()
+            .iter()
+            .map(|s| s.corpus_id.as_str())
+            .collect();
+        assert_eq!(
+            ids,
+            [
+                "morphgnt-sblgnt",
+                "byzantine-john",
+                "private-notes",
+                "lsj-core"
+            ]
+        );
+        let lines = attribution.lines();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].ends_with("https://creativecommons.org/licenses/by-sa/3.0/"));
+        assert_eq!(lines[2], "private-notes, license: not recorded.");
+        assert_eq!(
+            lines[3],
+            "A Greek-English Lexicon, 9th, 1940, license: CC-BY-SA-4.0."
+        );
+        assert_eq!(
+            lines[4],
+            "Translated with Red Letters: literal translator, engine 0.9.0; \
+             fluent translator, engine version not recorded."
+        );
+        assert!(Attribution::default().lines().is_empty());
+    }
+
+    #[test]
+    fn test_sidecar_path() {
+        assert_eq!(
+            sidecar_path(Path::new("/tmp/john.tsv")),
+            Path::new("/tmp/john.attribution.txt")
+        );
+    }
+}
@@ -0,0 +1,577 @@
+//! Full-text search over past translations and passage notes, offline.
+//!
+//! Every successful translation is indexed into `local_search.sqlite`
+//! under the app data dir: a `docs` table with the reference, books and
+//! timestamps, and a `docs_fts` FTS5 table over the Greek and the English
+//! (or the note text) sharing its rowids. Documents are keyed by passage
+//! id, so re-translating a passage replaces its superseded run instead of
+//! adding another. The cache in `passages/` stays the source of truth;
+//! `rebuild_search_index` recreates the index from it.
+//!
+//! The tokenizer only folds Latin diacritics, so accents and breathings are
+//! stripped from the text and the query before they reach FTS5; Greek
+//! snippets come back unaccented.
+
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::AppHandle;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+use super::errors::AppError;
+use super::passages::{load_all_passages, CachedPassage, TranslateOptions};
+use super::reference::{parse_reference_list, resolve_book};
+use super::storage::{self, now_ms};
+
+const INDEX_FILE: &str = "local_search.sqlite";
+/// Bumped when the schema changes; older indexes are rebuilt
+const SCHEMA_VERSION: i64 = 1;
+/// Default and largest number of hits returned
+const DEFAULT_LIMIT: u32 = 50;
+const MAX_LIMIT: u32 = 200;
+/// Tokens of context around a match in a snippet
+const SNIPPET_TOKENS: u32 = 16;
+/// Wait this long for another connection's write instead of failing
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+/// Stand-ins for the highlight tags until the snippet is escaped
+const MARK_START: char = '\u{E000}';
+const MARK_END: char = '\u{E001}';
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS docs (
+        id INTEGER PRIMARY KEY,
+        key TEXT NOT NULL UNIQUE,
+        kind TEXT NOT NULL,
+        passage_id TEXT NOT NULL,
+        reference TEXT NOT NULL,
+        indexed_at_ms INTEGER NOT NULL,
+        options_hash TEXT
+    );
+    CREATE TABLE IF NOT EXISTS doc_books (
+        doc_id INTEGER NOT NULL,
+        book TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS doc_books_doc ON doc_books (doc_id);
+    CREATE VIRTUAL TABLE IF NOT EXISTS docs_fts USING fts5(
+        greek, body, tokenize = 'unicode61 remove_diacritics 2'
+    );
+";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LocalSearchScope {
+    #[default]
+    Both,
+    Translations,
+    Notes,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LocalHitKind {
+    Translation,
+    Note,
+}
+
+impl LocalHitKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            LocalHitKind::Translation => "translation",
+            LocalHitKind::Note => "note",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "translation" => Some(LocalHitKind::Translation),
+            "note" => Some(LocalHitKind::Note),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct LocalSearchFilters {
+    pub scope: LocalSearchScope,
+    /// Book names in any form `resolve_book` accepts; empty means all
+    pub books: Vec<String>,
+    /// Indexed at or after, in ms since the epoch
+    pub from_ms: Option<u64>,
+    /// Indexed at or before, in ms since the epoch
+    pub to_ms: Option<u64>,
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LocalSearchHit {
+    pub kind: LocalHitKind,
+    /// Cached passage to open
+    pub passage_id: String,
+    /// Reference to navigate to
+    pub reference: String,
+    /// HTML-escaped text around the match, matches wrapped in `<mark>`
+    pub snippet: String,
+    /// Lower is better (FTS5 bm25)
+    pub rank: f64,
+    pub indexed_at_ms: u64,
+}
+
+/// Identifies the options a passage was translated with.
+pub fn options_hash(options: &TranslateOptions) -> String {
+    let digest = Sha256::digest(format!(
+        "{}|{}|{}",
+        options.mode,
+        options.translator,
+        Value::Object(options.options.clone())
+    ));
+    hex::encode(&digest[..8])
+}
+
+/// Text without accents, breathings or diaeresis, as it is indexed.
+fn strip_marks(text: &str) -> String {
+    text.nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .nfc()
+        .collect()
+}
+
+fn sql_err(e: rusqlite::Error) -> String {
+    format!("Search index: {}", e)
+}
+
+fn index_path(app: &AppHandle) -> Result<PathBuf, String> {
+    storage::app_data_file(app, INDEX_FILE)
+}
+
+fn open_index(app: &AppHandle) -> Result<Connection, String> {
+    let conn = Connection::open(index_path(app)?).map_err(sql_err)?;
+    conn.busy_timeout(BUSY_TIMEOUT).map_err(sql_err)?;
+    let version: i64 = conn
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(sql_err)?;
+    if version != SCHEMA_VERSION {
+        conn.execute_batch(
+            "DROP TABLE IF EXISTS docs; DROP TABLE IF EXISTS doc_books; \
+             DROP TABLE IF EXISTS docs_fts;",
+        )
+        .map_err(sql_err)?;
+    }
+    conn.execute_batch(SCHEMA).map_err(sql_err)?;
+    conn.pragma_update(None, "user_version", SCHEMA_VERSION)
+        .map_err(sql_err)?;
+    Ok(conn)
+}
+
+/// Canonical books a reference covers, in order without repeats.
+pub fn reference_books(reference: &str) -> Vec<String> {
+    let mut books: Vec<String> = Vec::new();
+    for range in parse_reference_list(reference)
+        .map(|r| r.ranges)
+        .unwrap_or_default()
+    {
+        if !books.contains(&range.book) {
+            books.push(range.book);
+        }
+    }
+    books
+}
+
+struct Doc<'a> {
+    kind: LocalHitKind,
+    passage: &'a CachedPassage,
+    greek: &'a str,
+    body: &'a str,
+    indexed_at_ms: u64,
+    options_hash: Option<&'a str>,
+}
+
+fn doc_key(kind: LocalHitKind, passage_id: &str) -> String {
+    format!("{}:{}", kind.as_str(), passage_id)
+}
+
+fn remove_doc(conn: &Connection, key: &str) -> rusqlite::Result<()> {
+    let id: Option<i64> = conn
+        .query_row("SELECT id FROM docs WHERE key = ?1", [key], |row| {
+            row.get(0)
+        })
+        .optional()?;
+    if let Some(id) = id {
+        conn.execute("DELETE FROM docs_fts WHERE rowid = ?1", [id])?;
+        conn.execute("DELETE FROM doc_books WHERE doc_id = ?1", [id])?;
+        conn.execute("DELETE FROM docs WHERE id = ?1", [id])?;
+    }
+    Ok(())
+}
+
+/// Replace the document with the same key, if any, by `doc`.
+fn put_doc(conn: &Connection, doc: &Doc) -> rusqlite::Result<()> {
+    let key = doc_key(doc.kind, &doc.passage.id);
+    remove_doc(conn, &key)?;
+    conn.execute(
+        "INSERT INTO docs (key, kind, passage_id, reference, indexed_at_ms, options_hash) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            key,
+            doc.kind.as_str(),
+            doc.passage.id,
+            doc.passage.reference,
+            doc.indexed_at_ms as i64,
+            doc.options_hash
+        ],
+    )?;
+    let id = conn.last_insert_rowid();
+    conn.execute(
+        "INSERT INTO docs_fts (rowid, greek, body) VALUES (?1, ?2, ?3)",
+        params![id, strip_marks(doc.greek), strip_marks(doc.body)],
+    )?;
+    for book in reference_books(&doc.passage.normalized_ref) {
+        conn.execute(
+            "INSERT INTO doc_books (doc_id, book) VALUES (?1, ?2)",
+            params![id, book],
+        )?;
+    }
+    Ok(())
+}
+
+/// Index the passage's note, or drop it from the index when it was cleared.
+fn put_note(
+    conn: &Connection,
+    passage: &CachedPassage,
+    indexed_at_ms: u64,
+) -> rusqlite::Result<()> {
+    match passage.note.as_deref().filter(|n| !n.trim().is_empty()) {
+        Some(note) => put_doc(
+            conn,
+            &Doc {
+                kind: LocalHitKind::Note,
+                passage,
+                greek: "",
+                body: note,
+                indexed_at_ms,
+                options_hash: None,
+            },
+        ),
+        None => remove_doc(conn, &doc_key(LocalHitKind::Note, &passage.id)),
+    }
+}
+
+/// Index a passage's translation and note, replacing its earlier run.
+fn index_into(
+    conn: &mut Connection,
+    passage: &CachedPassage,
+    options_hash: Option<&str>,
+) -> rusqlite::Result<()> {
+    let tx = conn.transaction()?;
+    put_doc(
+        &tx,
+        &Doc {
+            kind: LocalHitKind::Translation,
+            passage,
+            greek: &passage.greek,
+            body: &passage.translation,
+            indexed_at_ms: passage.translated_at_ms,
+            options_hash,
+        },
+    )?;
+    put_note(&tx, passage, passage.translated_at_ms)?;
+    tx.commit()
+}
+
+/// Add a freshly translated passage to the index.
+pub fn index_passage(
+    app: &AppHandle,
+    passage: &CachedPassage,
+    options_hash: Option<&str>,
+) -> Result<(), String> {
+    index_into(&mut open_index(app)?, passage, options_hash).map_err(sql_err)
+}
+
+/// Re-index a passage's note after it was set or cleared.
+pub fn index_passage_note(app: &AppHandle, passage: &CachedPassage) -> Result<(), String> {
+    put_note(&open_index(app)?, passage, now_ms()).map_err(sql_err)
+}
+
+/// FTS5 query for what the user typed. Double-quoted spans are phrases,
+/// other words are matched individually, and every term must be present.
+/// Everything is quoted, so FTS5 operators in the input are plain text.
+fn match_expression(query: &str) -> Option<String> {
+    let quote = |term: &str| format!("\"{}\"", strip_marks(term).replace('"', "\"\""));
+    let mut terms = Vec::new();
+    for (i, part) in query.split('"').enumerate() {
+        // Odd parts are inside quotes; an unclosed quote runs to the end
+        if i % 2 == 1 {
+            let phrase = part.split_whitespace().collect::<Vec<_>>().join(" ");
+            if !phrase.is_empty() {
+                terms.push(quote(&phrase));
+            }
+        } else {
+            terms.extend(part.split_whitespace().map(quote));
+        }
+    }
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+/// Escape a snippet for HTML and turn the match markers into `<mark>`.
+fn highlight(snippet: &str) -> String {
+    let mut html = String::with_capacity(snippet.len());
+    for c in snippet.chars() {
+        match c {
+            MARK_START => html.push_str("<mark>"),
+            MARK_END => html.push_str("</mark>"),
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            other => html.push(other),
+        }
+    }
+    html
+}
+
+fn search_index(
+    conn: &Connection,
+    expression: &str,
+    filters: &LocalSearchFilters,
+    books: &[&str],
+) -> rusqlite::Result<Vec<LocalSearchHit>> {
+    let mut sql = format!(
+        "SELECT d.kind, d.passage_id, d.reference, d.indexed_at_ms, \
+         snippet(docs_fts, -1, '{}', '{}', '…', {}), bm25(docs_fts) \
+         FROM docs_fts JOIN docs d ON d.id = docs_fts.rowid \
+         WHERE docs_fts MATCH ?",
+        MARK_START, MARK_END, SNIPPET_TOKENS
+    );
+    let mut values: Vec<rusqlite::types::Value> = vec![expression.to_string().into()];
+    match filters.scope {
+        LocalSearchScope::Both => {}
+        LocalSearchScope::Translations => sql.push_str(" AND d.kind = 'translation'"),
+        LocalSearchScope::Notes => sql.push_str(" AND d.kind = 'note'"),
+    }
+    if !books.is_empty() {
+        sql.push_str(&format!(
+            " AND d.id IN (SELECT doc_id FROM doc_books WHERE book IN ({}))",
+            vec!["?"; books.len()].join(", ")
+        ));
+        values.extend(books.iter().map(|b| b.to_string().into()));
+    }
+    if let Some(from) = filters.from_ms {
+        sql.push_str(" AND d.indexed_at_ms >= ?");
+        values.push((from as i64).into());
+    }
+    if let Some(to) = filters.to_ms {
+        sql.push_str(" AND d.indexed_at_ms <= ?");
+        values.push((to as i64).into());
+    }
+    sql.push_str(" ORDER BY bm25(docs_fts) LIMIT ?");
+    let limit = filters.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    values.push(i64::from(limit).into());
+
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(params_from_iter(values), |row| {
+        let kind: String = row.get(0)?;
+        let snippet: String = row.get(4)?;
+        Ok(LocalSearchHit {
+            kind: LocalHitKind::parse(&kind).unwrap_or(LocalHitKind::Translation),
+            passage_id: row.get(1)?,
+            reference: row.get(2)?,
+            indexed_at_ms: row.get::<_, i64>(3)? as u64,
+            snippet: highlight(&snippet),
+            rank: row.get(5)?,
+        })
+    })?;
+    rows.collect()
+}
+
+fn rebuild_into(conn: &mut Connection, passages: &[CachedPassage]) -> rusqlite::Result<()> {
+    for passage in passages {
+        index_into(conn, passage, None)?;
+    }
+    Ok(())
+}
+
+/// Search past translations and notes on this machine, best match first.
+///
+/// Put a phrase in double quotes to match it exactly; other words may
+/// appear anywhere in the text. Greek matches with or without accents.
+#[tauri::command]
+pub fn search_local(
+    app: AppHandle,
+    query: String,
+    filters: Option<LocalSearchFilters>,
+) -> Result<Vec<LocalSearchHit>, AppError> {
+    let filters = filters.unwrap_or_default();
+    let Some(expression) = match_expression(&query) else {
+        return Ok(Vec::new());
+    };
+    let books = filters
+        .books
+        .iter()
+        .map(|b| {
+            resolve_book(b).ok_or_else(|| AppError::InvalidInput(format!("Unknown book: {}", b)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let conn = open_index(&app)?;
+    Ok(search_index(&conn, &expression, &filters, &books).map_err(sql_err)?)
+}
+
+/// Recreate the search index from the passage cache, e.g. after the index
+/// file was damaged. Returns the number of passages indexed.
+#[tauri::command]
+pub async fn rebuild_search_index(app: AppHandle) -> Result<u32, AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let path = index_path(&app)?;
+        for suffix in ["", "-wal", "-shm", "-journal"] {
+            let file = PathBuf::from(format!("{}{}", path.display(), suffix));
+            if file.exists() {
+                fs::remove_file(&file).map_err(|e| format!("{}: {}", file.display(), e))?;
+            }
+        }
+        let passages = load_all_passages(&app)?;
+        rebuild_into(&mut open_index(&app)?, &passages).map_err(sql_err)?;
+        Ok(passages.len() as u32)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testsupport::passage::PassageBuilder;
+
+    fn passage(id: &str, reference: &str, greek: &str, translation: &str) -> CachedPassage {
+        PassageBuilder::new()
+            .id(id)
+            .reference(reference)
+            .greek(greek)
+            .translation(translation)
+            .translated_at_ms(1_000)
+            .build()
+    }
+
+    fn memory_index() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(SCHEMA).unwrap();
+        conn
+    }
+
+    fn search(conn: &Connection, query: &str, filters: LocalSearchFilters) -> Vec<String> {
+        let books: Vec<&str> = filters
+            .books
+            .iter()
+            .filter_map(|b| resolve_book(b))
+            .collect();
+        search_index(conn, &match_expression(query).unwrap(), &filters, &books)
+            .unwrap()
+            .into_iter()
+            .map(|h| format!("{:?} {}", h.kind, h.reference))
+            .collect()
+    }
+
+    #[test]
+    fn test_match_expression() {
+        assert_eq!(
+            match_expression("word  of life").as_deref(),
+            Some(r#""word" "of" "life""#)
+        );
+        assert_eq!(
+            match_expression(r#" "in the   beginning" was "#).as_deref(),
+            Some(r#""in the beginning" "was""#)
+        );
+        // Operators and stray quotes are searched for literally
+        assert_eq!(
+            match_expression("love OR hate*").as_deref(),
+            Some(r#""love" "OR" "hate*""#)
+        );
+        assert_eq!(match_expression(r#""grace"#).as_deref(), Some(r#""grace""#));
+        assert_eq!(match_expression(r#"  "" "#), None);
+    }
+
+    #[test]
+    fn test_highlight_escapes_text() {
+        assert_eq!(
+            highlight("a <b> & \u{E000}word\u{E001}"),
+            "a &lt;b&gt; &amp; <mark>word</mark>"
+        );
+    }
+
+    #[test]
+    fn test_search_filters_and_superseded_runs() {
+        let mut conn = memory_index();
+        let mut john = passage(
+            "aa",
+            "John 1:1",
+            "Ἐν ἀρχῇ ἦν ὁ λόγος",
+            "In the beginning was the Word",
+        );
+        index_into(&mut conn, &john, Some("h1")).unwrap();
+        let mut first_john = passage(
+            "bb",
+            "1 John 1:1",
+            "Ὃ ἦν ἀπ’ ἀρχῆς",
+            "That which was from the beginning",
+        );
+        first_john.note = Some("Compare the beginning of John's gospel".to_string());
+        first_john.translated_at_ms = 5_000;
+        index_into(&mut conn, &first_john, None).unwrap();
+
+        assert_eq!(search(&conn, "beginning", Default::default()).len(), 3);
+        assert_eq!(
+            search(&conn, r#""in the beginning""#, Default::default()),
+            ["Translation John 1:1"]
+        );
+        // Accents are ignored
+        assert_eq!(
+            search(&conn, "αρχη", Default::default()),
+            ["Translation John 1:1"]
+        );
+        let notes = LocalSearchFilters {
+            scope: LocalSearchScope::Notes,
+            ..Default::default()
+        };
+        assert_eq!(search(&conn, "beginning", notes), ["Note 1 John 1:1"]);
+        let gospel = LocalSearchFilters {
+            books: vec!["Jn".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(search(&conn, "beginning", gospel), ["Translation John 1:1"]);
+        let recent = LocalSearchFilters {
+            from_ms: Some(2_000),
+            scope: LocalSearchScope::Translations,
+            ..Default::default()
+        };
+        assert_eq!(
+            search(&conn, "beginning", recent),
+            ["Translation 1 John 1:1"]
+        );
+
+        // A re-translation replaces the earlier run
+        john.translation = "In the beginning was the Message".to_string();
+        index_into(&mut conn, &john, Some("h2")).unwrap();
+        assert!(search(&conn, "Word", Default::default()).is_empty());
+        assert_eq!(
+            search(&conn, "Message", Default::default()),
+            ["Translation John 1:1"]
+        );
+        let docs: i64 = conn
+            .query_row("SELECT count(*) FROM docs_fts", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(docs, 3);
+
+        let hits = search_index(
+            &conn,
+            &match_expression("Message").unwrap(),
+            &Default::default(),
+            &[],
+        )
+        .unwrap();
+        assert_eq!(hits[0].passage_id, "aa");
+        assert!(hits[0].snippet.contains("<mark>Message</mark>"));
+    }
+}
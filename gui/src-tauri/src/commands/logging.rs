@@ -0,0 +1,455 @@
+//! Backend log: `tracing` output to a daily log file in the app log dir in
+//! all builds, and to stderr in debug builds.
+//!
+//! Logging starts before the app is built, so the log dir is resolved the
+//! way Tauri's `app_log_dir` does it. The newest file is what the
+//! diagnostics panel reads with `get_backend_log_tail`.
+//!
+//! The level (`info` by default) and per-module overrides are a setting
+//! that `set_backend_log_level` changes through a reload handle, which
+//! also applies to spans and threads that are already running. `RUST_LOG`
+//! takes precedence over the setting at startup.
+//!
+//! Log sites must not record secrets. Tokens and clipboard text are logged
+//! only as `Redacted`, URLs only through `url_for_log` (no query string or
+//! credentials), and spans on commands taking either use `skip_all`.
+
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tauri::AppHandle;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+use super::errors::AppError;
+use super::settings;
+
+/// Log files are named `backend.<date>.log`
+const LOG_FILE_PREFIX: &str = "backend";
+const LOG_FILE_SUFFIX: &str = "log";
+/// Days of logs kept
+const MAX_LOG_FILES: usize = 7;
+const DEFAULT_LEVEL: &str = "info";
+const LEVELS: &[&str] = &["error", "warn", "info", "debug", "trace"];
+/// Module overrides naming a bare command module, e.g. "http", are for
+/// this module path
+const COMMANDS_TARGET: &str = concat!(env!("CARGO_CRATE_NAME"), "::commands");
+/// Most lines `get_backend_log_tail` returns
+const MAX_TAIL_LINES: usize = 5000;
+/// Bytes from the end of the log that `recent_lines` reads
+const RECENT_BYTES: u64 = 16 * 1024;
+
+static LOG_DIR: OnceLock<PathBuf> = OnceLock::new();
+static FILTER_HANDLE: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+/// How much the backend logs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackendLogLevel {
+    /// error, warn, info, debug or trace
+    pub level: String,
+    /// Comma-separated `module=level` pairs, e.g. "http=trace, engine=info"
+    #[serde(default)]
+    pub module_overrides: String,
+}
+
+impl Default for BackendLogLevel {
+    fn default() -> Self {
+        Self {
+            level: DEFAULT_LEVEL.to_string(),
+            module_overrides: String::new(),
+        }
+    }
+}
+
+fn parse_level(level: &str) -> Option<&'static str> {
+    let level = level.trim();
+    LEVELS
+        .iter()
+        .copied()
+        .find(|l| l.eq_ignore_ascii_case(level))
+}
+
+fn is_module_path(module: &str) -> bool {
+    module.split("::").all(|segment| {
+        !segment.is_empty()
+            && segment
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+/// `EnvFilter` directives for a setting. Every invalid part is reported,
+/// not just the first.
+fn filter_directives(setting: &BackendLogLevel) -> Result<String, String> {
+    let mut directives = Vec::new();
+    let mut errors = Vec::new();
+    match parse_level(&setting.level) {
+        Some(level) => directives.push(level.to_string()),
+        None => errors.push(format!("unknown level \"{}\"", setting.level.trim())),
+    }
+    let pairs = setting.module_overrides.split(',').map(str::trim);
+    for pair in pairs.filter(|p| !p.is_empty()) {
+        let Some((module, level)) = pair.split_once('=') else {
+            errors.push(format!("\"{}\": expected module=level", pair));
+            continue;
+        };
+        let module = module.trim();
+        if !is_module_path(module) {
+            errors.push(format!("\"{}\": invalid module name", pair));
+            continue;
+        }
+        let Some(level) = parse_level(level) else {
+            errors.push(format!("\"{}\": unknown level \"{}\"", pair, level.trim()));
+            continue;
+        };
+        if module.contains("::") {
+            directives.push(format!("{}={}", module, level));
+        } else {
+            directives.push(format!("{}::{}={}", COMMANDS_TARGET, module, level));
+        }
+    }
+    if !errors.is_empty() {
+        return Err(format!("Invalid log level: {}", errors.join("; ")));
+    }
+    Ok(directives.join(","))
+}
+
+fn build_filter(setting: &BackendLogLevel) -> Result<EnvFilter, String> {
+    EnvFilter::try_new(filter_directives(setting)?).map_err(|e| e.to_string())
+}
+
+/// A secret in a log line; only its length is written.
+pub struct Redacted<'a>(pub &'a str);
+
+impl fmt::Display for Redacted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<redacted, {} chars>", self.0.chars().count())
+    }
+}
+
+impl fmt::Debug for Redacted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// A URL without its credentials, query string or fragment, which may
+/// carry tokens or download signatures.
+pub fn url_for_log(url: &str) -> String {
+    let Ok(mut url) = Url::parse(url) else {
+        return "<invalid url>".to_string();
+    };
+    let _ = url.set_username("");
+    let _ = url.set_password(None);
+    url.set_query(None);
+    url.set_fragment(None);
+    url.to_string()
+}
+
+/// A short id tying together the log lines of one request, e.g. "3f9a1c02".
+pub fn request_id() -> String {
+    let mut id = uuid::Uuid::new_v4().simple().to_string();
+    id.truncate(8);
+    id
+}
+
+/// Tauri's `app_log_dir`, resolved without an `AppHandle`.
+fn log_dir(identifier: &str) -> Option<PathBuf> {
+    if cfg!(target_os = "macos") {
+        dirs::home_dir().map(|home| home.join("Library").join("Logs").join(identifier))
+    } else {
+        dirs::data_local_dir().map(|dir| dir.join(identifier).join("logs"))
+    }
+}
+
+/// Install the log subscriber. Called once, before the app is built.
+pub fn init_logging(identifier: &str) {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| {
+        let setting = settings::load_settings_before_start(identifier)
+            .backend_log
+            .unwrap_or_default();
+        build_filter(&setting).unwrap_or_else(|e| {
+            eprintln!("Warning: {}", e);
+            EnvFilter::new(DEFAULT_LEVEL)
+        })
+    });
+    let (filter, handle) = reload::Layer::new(filter);
+    let _ = FILTER_HANDLE.set(handle);
+    let stderr =
+        cfg!(debug_assertions).then(|| tracing_subscriber::fmt::layer().with_writer(io::stderr));
+    let file = log_dir(identifier).and_then(|dir| {
+        let appender = fs::create_dir_all(&dir)
+            .map_err(|e| e.to_string())
+            .and_then(|_| {
+                RollingFileAppender::builder()
+                    .rotation(Rotation::DAILY)
+                    .filename_prefix(LOG_FILE_PREFIX)
+                    .filename_suffix(LOG_FILE_SUFFIX)
+                    .max_log_files(MAX_LOG_FILES)
+                    .build(&dir)
+                    .map_err(|e| e.to_string())
+            });
+        match appender {
+            Ok(appender) => {
+                let _ = LOG_DIR.set(dir);
+                Some(
+                    tracing_subscriber::fmt::layer()
+                        .with_ansi(false)
+                        .with_writer(appender),
+                )
+            }
+            Err(e) => {
+                // Nothing is listening yet
+                eprintln!("Warning: backend log unavailable: {}", e);
+                None
+            }
+        }
+    });
+    // Span fields are formatted once, by the first layer; the file one
+    // comes first so they don't carry stderr's color codes
+    let _ = tracing_subscriber::registry()
+        .with(filter)
+        .with(file)
+        .with(stderr)
+        .try_init();
+}
+
+/// The newest log file in `dir`. Dated names sort chronologically.
+fn latest_log_file(dir: &Path) -> Option<PathBuf> {
+    fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| {
+                    name.starts_with(LOG_FILE_PREFIX) && name.ends_with(LOG_FILE_SUFFIX)
+                })
+        })
+        .max()
+}
+
+/// The last `count` lines of a file.
+fn tail_lines(path: &Path, count: usize) -> io::Result<Vec<String>> {
+    let contents = fs::read(path)?;
+    let contents = String::from_utf8_lossy(&contents);
+    let mut lines: Vec<String> = contents
+        .lines()
+        .rev()
+        .take(count)
+        .map(str::to_string)
+        .collect();
+    lines.reverse();
+    Ok(lines)
+}
+
+/// The last `count` lines within the last `max_bytes` of a file. A line
+/// cut by the byte limit is left out.
+fn tail_lines_within(path: &Path, count: usize, max_bytes: u64) -> io::Result<Vec<String>> {
+    let mut file = fs::File::open(path)?;
+    let start = file.metadata()?.len().saturating_sub(max_bytes);
+    file.seek(SeekFrom::Start(start))?;
+    let mut contents = Vec::new();
+    file.read_to_end(&mut contents)?;
+    let contents = String::from_utf8_lossy(&contents);
+    let lines: Vec<&str> = contents.lines().skip(usize::from(start > 0)).collect();
+    Ok(lines[lines.len().saturating_sub(count)..]
+        .iter()
+        .map(|line| line.to_string())
+        .collect())
+}
+
+/// The last lines of the current log, reading only its last few
+/// kilobytes; for the panic hook. Empty when there is no log.
+pub fn recent_lines(count: usize) -> Vec<String> {
+    LOG_DIR
+        .get()
+        .and_then(|dir| latest_log_file(dir))
+        .and_then(|path| tail_lines_within(&path, count, RECENT_BYTES).ok())
+        .unwrap_or_default()
+}
+
+fn log_dir_in_use() -> Result<&'static PathBuf, String> {
+    LOG_DIR
+        .get()
+        .ok_or_else(|| "The backend log is not being written".to_string())
+}
+
+/// Change what the backend logs, effective immediately and kept across
+/// restarts.
+#[tauri::command]
+pub fn set_backend_log_level(
+    app: AppHandle,
+    level: String,
+    module_overrides: Option<String>,
+) -> Result<BackendLogLevel, AppError> {
+    let setting = BackendLogLevel {
+        level: level.trim().to_lowercase(),
+        module_overrides: module_overrides.unwrap_or_default().trim().to_string(),
+    };
+    let filter = build_filter(&setting)?;
+    if let Some(handle) = FILTER_HANDLE.get() {
+        handle.reload(filter).map_err(|e| e.to_string())?;
+    }
+    settings::update_settings(&app, |s| s.backend_log = Some(setting.clone()))?;
+    tracing::info!(
+        level = %setting.level,
+        module_overrides = %setting.module_overrides,
+        "Changed the log level"
+    );
+    Ok(setting)
+}
+
+/// The saved log level, for the settings UI. `RUST_LOG`, when set at
+/// startup, is in effect until the level is changed here.
+#[tauri::command]
+pub fn get_backend_log_level(app: AppHandle) -> Result<BackendLogLevel, AppError> {
+    Ok(settings::load_settings(&app)?
+        .backend_log
+        .unwrap_or_default())
+}
+
+/// Path of the current backend log file, or of the log dir before anything
+/// has been written.
+#[tauri::command]
+pub fn get_backend_log_path() -> Result<String, AppError> {
+    let dir = log_dir_in_use()?;
+    let path = latest_log_file(dir).unwrap_or_else(|| dir.clone());
+    Ok(path.display().to_string())
+}
+
+/// The last lines of the current backend log, for the diagnostics panel and
+/// bundle.
+#[tauri::command]
+pub fn get_backend_log_tail(lines: usize) -> Result<Vec<String>, AppError> {
+    let dir = log_dir_in_use()?;
+    let Some(path) = latest_log_file(dir) else {
+        return Ok(Vec::new());
+    };
+    Ok(tail_lines(&path, lines.min(MAX_TAIL_LINES)).map_err(|e| e.to_string())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_redaction() {
+        let token = "rl_secret_token";
+        assert_eq!(Redacted(token).to_string(), "<redacted, 15 chars>");
+        assert_eq!(format!("{:?}", Redacted(token)), "<redacted, 15 chars>");
+        assert_eq!(
+            url_for_log("https://user:pw@example.org/c/sblgnt.zip?sig=abc#x"),
+            "https://example.org/c/sblgnt.zip"
+        );
+        assert_eq!(url_for_log("not a url"), "<invalid url>");
+        assert_eq!(request_id().len(), 8);
+    }
+
+    #[test]
+    fn test_filter_directives() {
+        let setting = |level: &str, overrides: &str| BackendLogLevel {
+            level: level.to_string(),
+            module_overrides: overrides.to_string(),
+        };
+        assert_eq!(
+            filter_directives(&BackendLogLevel::default()).as_deref(),
+            Ok("info")
+        );
+        assert_eq!(
+            filter_directives(&setting(
+                "DEBUG",
+                " http=trace, tungstenite::protocol=warn ,"
+            )),
+            Ok(format!(
+                "debug,{}::http=trace,tungstenite::protocol=warn",
+                COMMANDS_TARGET
+            ))
+        );
+        let error =
+            filter_directives(&setting("loud", "http, engine=verbose, a b=info")).unwrap_err();
+        assert!(error.contains("unknown level \"loud\""));
+        assert!(error.contains("\"http\": expected module=level"));
+        assert!(error.contains("\"engine=verbose\": unknown level \"verbose\""));
+        assert!(error.contains("\"a b=info\": invalid module name"));
+    }
+
+    /// Writes formatted events to a shared buffer
+    struct Capture(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Capture {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_reload_applies_to_running_spans() {
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let writer = output.clone();
+        let (filter, handle) =
+            reload::Layer::new(build_filter(&BackendLogLevel::default()).unwrap());
+        let subscriber = tracing_subscriber::registry().with(filter).with(
+            tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_writer(move || Capture(writer.clone())),
+        );
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("task");
+            let _entered = span.enter();
+            // The same callsite before and after the change
+            for round in 0..2 {
+                tracing::debug!(round, "tick");
+                if round == 0 {
+                    let debug = BackendLogLevel {
+                        level: "debug".to_string(),
+                        ..Default::default()
+                    };
+                    handle.reload(build_filter(&debug).unwrap()).unwrap();
+                }
+            }
+        });
+        let output = String::from_utf8(output.lock().unwrap().clone()).unwrap();
+        assert!(!output.contains("round=0"));
+        assert!(output.contains("task: "));
+        assert!(output.contains("round=1"));
+    }
+
+    #[test]
+    fn test_log_tail() {
+        let dir = std::env::temp_dir().join(format!("rl-log-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        assert_eq!(latest_log_file(&dir), None);
+
+        fs::write(dir.join("backend.2026-01-01.log"), "old\n").unwrap();
+        fs::write(dir.join("backend.2026-01-02.log"), "one\ntwo\nthree\n").unwrap();
+        fs::write(dir.join("settings.json"), "{}").unwrap();
+        let latest = latest_log_file(&dir).unwrap();
+        assert!(latest.ends_with("backend.2026-01-02.log"));
+        assert_eq!(tail_lines(&latest, 2).unwrap(), vec!["two", "three"]);
+        assert_eq!(tail_lines(&latest, 10).unwrap().len(), 3);
+        assert_eq!(
+            tail_lines_within(&latest, 2, 100).unwrap(),
+            vec!["two", "three"]
+        );
+        // "one" is cut to "e" and left out
+        assert_eq!(
+            tail_lines_within(&latest, 5, 12).unwrap(),
+            vec!["two", "three"]
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,7 +1,169 @@
 //! Tauri commands for Red Letters GUI.
 
+pub mod accessibility;
+pub mod admin;
+pub mod alignment;
+pub mod annotations;
+pub mod apkg;
 pub mod auth;
+pub mod badge;
+pub mod batch_jobs;
+pub mod book_tree;
+pub mod cache;
+pub mod citation;
+pub mod clipboard_watch;
+pub mod command_history;
+pub mod commentary;
+pub mod corpus;
+pub mod corpus_import;
+pub mod crash_reports;
+pub mod custom_events;
+pub mod document_export;
+pub mod docx;
 pub mod engine;
+pub mod engine_config;
+pub mod errors;
+pub mod extensions;
+pub mod flashcards;
+pub mod fonts;
+pub mod glossary;
+pub mod greek_input;
+pub mod grpc_health;
+pub mod hardware_acceleration;
+pub mod highlight;
+pub mod http;
+pub mod i18n;
+pub mod interlinear;
+pub mod interlinear_table;
+pub mod lexicon;
+pub mod licensing;
+pub mod local_search;
+pub mod logging;
+pub mod morphology;
+pub mod offline;
+pub mod open_requests;
+pub mod openapi;
+pub mod osis;
+pub mod parallel_text;
+pub mod parallel_versions;
+pub mod passage_lists;
+pub mod passages;
+pub mod pdf;
+pub mod presentation;
+pub mod process;
+pub mod projects;
+pub mod pronunciation;
+pub mod reading_plans;
+pub mod red_letters;
+pub mod reference;
+pub mod save_dialog;
+pub mod saved_results;
+pub mod search;
+pub mod settings;
+pub mod share;
+pub mod shutdown;
+pub mod similarity;
+pub mod sleep_inhibit;
+pub mod speech;
+pub mod start_state;
+pub mod storage;
+pub mod strongs;
+pub mod study_document;
+pub mod tags;
+pub mod telemetry;
+pub mod text_utils;
+pub mod themes;
+pub mod translation_diff;
+pub mod translation_history;
+pub mod transliteration;
+pub mod usfm;
+pub mod verse_of_the_day;
+pub mod webhooks;
+pub mod websocket;
+pub mod word_frequency;
 
+pub use accessibility::*;
+pub use admin::*;
+pub use alignment::{align_parallel_texts, AlignmentCache};
+pub use annotations::*;
 pub use auth::*;
+pub use badge::*;
+pub use batch_jobs::*;
+pub use book_tree::{get_bible_book_tree, BookTreeCache};
+pub use citation::{format_citation, list_citation_styles};
+pub use clipboard_watch::*;
+pub use command_history::{get_command_history, CommandHistory};
+pub use commentary::{get_text_commentary, list_available_commentaries, CommentaryCache};
+pub use corpus::*;
+pub use corpus_import::import_local_corpus;
+pub use crash_reports::{
+    discard_crash_report, get_crash_reports, get_pending_crash_reports, send_crash_report,
+    set_crash_reporting,
+};
+pub use custom_events::*;
+pub use document_export::*;
+pub use docx::*;
 pub use engine::*;
+pub use engine_config::*;
+pub use extensions::*;
+pub use flashcards::*;
+pub use fonts::*;
+pub use glossary::*;
+pub use greek_input::normalize_greek_input;
+pub use grpc_health::*;
+pub use hardware_acceleration::{
+    disable_hardware_acceleration, enable_hardware_acceleration, get_hardware_acceleration_status,
+};
+pub use highlight::{highlight_text_spans, HighlightCache};
+pub use i18n::{get_available_locales, get_locale, load_translation_strings, set_locale};
+pub use interlinear::{export_interlinear_to_html, generate_interlinear_view, InterlinearCache};
+pub use interlinear_table::*;
+pub use lexicon::*;
+pub use licensing::get_license_info;
+pub use local_search::{rebuild_search_index, search_local};
+pub use logging::{
+    get_backend_log_level, get_backend_log_path, get_backend_log_tail, set_backend_log_level,
+};
+pub use morphology::*;
+pub use offline::{get_offline_mode, get_offline_queue, set_offline_mode};
+pub use open_requests::*;
+pub use openapi::*;
+pub use osis::*;
+pub use parallel_text::*;
+pub use parallel_versions::{get_parallel_versions, list_available_versions};
+pub use passage_lists::*;
+pub use passages::*;
+pub use presentation::*;
+pub use projects::{open_project, save_project};
+pub use pronunciation::{get_pronunciation, prefetch_pronunciations};
+pub use reading_plans::*;
+pub use red_letters::get_red_letter_verses;
+pub use reference::{
+    chunk_passage, parse_reference, parse_reference_range, validate_scripture_reference,
+};
+pub use save_dialog::*;
+pub use saved_results::*;
+pub use search::*;
+pub use share::*;
+pub use shutdown::set_pre_shutdown_delay_ms;
+pub use similarity::{compute_textual_similarity, SimilarityCache};
+pub use speech::{list_tts_voices, speak_text, stop_speaking, SpeechState};
+pub use start_state::*;
+pub use strongs::{get_strongs_entry, search_strongs_by_english, StrongsCache};
+pub use study_document::*;
+pub use tags::{add_tag, find_by_tag, list_tags, remove_tag, rename_tag};
+pub use telemetry::{
+    get_telemetry_preview, purge_telemetry, set_telemetry_enabled, set_telemetry_endpoint,
+};
+pub use text_utils::{
+    detect_greek_in_text, diff_text_passages, get_text_statistics, split_text_into_sentences,
+};
+pub use themes::{apply_custom_theme, get_available_themes, import_theme, reset_to_default_theme};
+pub use translation_diff::*;
+pub use translation_history::{export_history, import_history};
+pub use transliteration::transliterate;
+pub use usfm::*;
+pub use verse_of_the_day::*;
+pub use webhooks::*;
+pub use websocket::*;
+pub use word_frequency::*;
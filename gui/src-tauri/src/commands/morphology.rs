@@ -0,0 +1,445 @@
+//! Morphology lookups with a persistent cache.
+//!
+//! Analyses are cached per corpus and normalized surface form (NFC,
+//! lowercased, punctuation trimmed; accents are kept since they can change
+//! the parse). Each entry records the engine and corpus versions it came
+//! from and is dropped once either changes. The cache is loaded from
+//! `morphology_cache.json` on first use and holds at most 20,000 forms,
+//! evicting the oldest.
+
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, State};
+use unicode_normalization::UnicodeNormalization;
+
+use super::cache::TtlCache;
+use super::corpus::{data_root, installed_from_manifest};
+use super::engine::EngineError;
+use super::errors::AppError;
+use super::http::{self, HttpError};
+use super::openapi::engine_version;
+use super::passages::{load_all_passages, load_passage, CachedPassage};
+use super::reference::canonical_reference;
+use super::storage;
+
+const CACHE_FILE: &str = "morphology_cache.json";
+/// Oldest entries are evicted beyond this many forms
+const MAX_ENTRIES: usize = 20_000;
+/// How long the engine version is trusted before asking again
+const VERSION_TTL: Duration = Duration::from_secs(60);
+/// Spine corpus assumed when a cached response doesn't name one
+const DEFAULT_CORPUS: &str = "morphgnt-sblgnt";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MorphAnalysis {
+    pub lemma: String,
+    /// e.g. "N-NSM"
+    pub parse_code: String,
+    /// e.g. "noun, nominative singular masculine"
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    /// Empty when the engine doesn't know the form
+    analyses: Vec<MorphAnalysis>,
+    engine_version: Option<String>,
+    corpus_version: Option<String>,
+    cached_at_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MorphologyCacheStats {
+    pub entries: usize,
+    pub capacity: usize,
+    /// Since the app started
+    pub hits: u64,
+    pub misses: u64,
+    pub file_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MorphologyPrefetchReport {
+    /// Distinct forms in the passage
+    pub forms: usize,
+    pub already_cached: usize,
+    pub fetched: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct BatchRequest<'a> {
+    corpus: &'a str,
+    forms: &'a [String],
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchResponse {
+    /// Analyses by form as sent; unknown forms may be missing
+    results: HashMap<String, Vec<MorphAnalysis>>,
+}
+
+/// Versions an entry must match to be used.
+#[derive(Debug, Clone, Default)]
+struct Versions {
+    engine: Option<String>,
+    corpus: Option<String>,
+}
+
+pub struct MorphologyCache {
+    /// Loaded from disk on first use
+    entries: Mutex<Option<HashMap<String, CacheEntry>>>,
+    /// Engine version by port
+    engine_versions: Mutex<TtlCache<u16, String>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl Default for MorphologyCache {
+    fn default() -> Self {
+        Self {
+            entries: Mutex::new(None),
+            engine_versions: Mutex::new(TtlCache::new(VERSION_TTL)),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+}
+
+impl MorphologyCache {
+    fn with_entries<R>(
+        &self,
+        app: &AppHandle,
+        f: impl FnOnce(&mut HashMap<String, CacheEntry>) -> R,
+    ) -> R {
+        let mut entries = self.entries.lock().unwrap();
+        let entries = entries.get_or_insert_with(|| {
+            storage::app_data_file(app, CACHE_FILE)
+                .and_then(|path| storage::read_json_or_default(&path))
+                .unwrap_or_default()
+        });
+        f(entries)
+    }
+
+    /// Cached analyses for `key`, dropping an entry from other versions.
+    fn get(&self, app: &AppHandle, key: &str, versions: &Versions) -> Option<Vec<MorphAnalysis>> {
+        let found = self.with_entries(app, |entries| {
+            let entry = entries.get(key)?;
+            if is_stale(entry, versions) {
+                entries.remove(key);
+                return None;
+            }
+            Some(entry.analyses.clone())
+        });
+        let counter = if found.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        found
+    }
+
+    fn insert(
+        &self,
+        app: &AppHandle,
+        found: impl IntoIterator<Item = (String, Vec<MorphAnalysis>)>,
+        versions: &Versions,
+    ) -> Result<(), EngineError> {
+        self.with_entries(app, |entries| {
+            let now = storage::now_ms();
+            for (key, analyses) in found {
+                entries.insert(
+                    key,
+                    CacheEntry {
+                        analyses,
+                        engine_version: versions.engine.clone(),
+                        corpus_version: versions.corpus.clone(),
+                        cached_at_ms: now,
+                    },
+                );
+            }
+            evict_oldest(entries, MAX_ENTRIES);
+            storage::write_json(&storage::app_data_file(app, CACHE_FILE)?, entries)
+        })
+        .map_err(EngineError::Storage)
+    }
+
+    async fn engine_version(&self, port: u16) -> Option<String> {
+        let cached = self.engine_versions.lock().unwrap().get(&port);
+        if cached.is_some() {
+            return cached;
+        }
+        let version = engine_version(port).await?;
+        self.engine_versions
+            .lock()
+            .unwrap()
+            .insert(port, version.clone());
+        Some(version)
+    }
+
+    async fn versions(&self, port: u16, corpus: &str) -> Versions {
+        Versions {
+            engine: self.engine_version(port).await,
+            corpus: data_root()
+                .and_then(|root| installed_from_manifest(&root))
+                .ok()
+                .and_then(|installed| installed.into_iter().find(|c| c.id == corpus))
+                .map(|c| c.version)
+                .filter(|v| !v.is_empty()),
+        }
+    }
+}
+
+/// An entry is stale when a version known now differs from the recorded one.
+fn is_stale(entry: &CacheEntry, versions: &Versions) -> bool {
+    let differs = |recorded: &Option<String>, current: &Option<String>| {
+        current.is_some() && recorded != current
+    };
+    differs(&entry.engine_version, &versions.engine)
+        || differs(&entry.corpus_version, &versions.corpus)
+}
+
+fn evict_oldest(entries: &mut HashMap<String, CacheEntry>, capacity: usize) {
+    if entries.len() <= capacity {
+        return;
+    }
+    let mut by_age: Vec<(u64, String)> = entries
+        .iter()
+        .map(|(key, entry)| (entry.cached_at_ms, key.clone()))
+        .collect();
+    by_age.sort();
+    for (_, key) in by_age.into_iter().take(entries.len() - capacity) {
+        entries.remove(&key);
+    }
+}
+
+/// NFC, lowercase, surrounding punctuation removed; accents are kept.
+fn normalize_form(token: &str) -> String {
+    // Compose first so a trailing combining mark isn't trimmed as punctuation
+    let composed: String = token.nfc().collect();
+    composed
+        .trim_matches(|c: char| !c.is_alphanumeric())
+        .chars()
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn cache_key(corpus: &str, form: &str) -> String {
+    format!("{}|{}", corpus, form)
+}
+
+/// A cached passage by id, or the most recent one of a reference.
+fn find_passage(app: &AppHandle, passage_ref: &str) -> Result<CachedPassage, String> {
+    if let Ok(passage) = load_passage(app, passage_ref) {
+        return Ok(passage);
+    }
+    let wanted = canonical_reference(passage_ref);
+    load_all_passages(app)?
+        .into_iter()
+        .find(|p| canonical_reference(&p.normalized_ref) == wanted)
+        .ok_or_else(|| format!("No cached translation of {}", passage_ref))
+}
+
+/// Spine corpus and distinct normalized surface forms of a cached passage.
+fn passage_forms(response: &Value) -> (String, BTreeSet<String>) {
+    let corpus = response["provenance"]["spine_source"]
+        .as_str()
+        .filter(|c| !c.is_empty())
+        .unwrap_or(DEFAULT_CORPUS)
+        .to_string();
+    let forms = response["verse_blocks"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .flat_map(|block| block["tokens"].as_array().into_iter().flatten())
+        .filter_map(|token| token["surface"].as_str())
+        .map(normalize_form)
+        .filter(|form| !form.is_empty())
+        .collect();
+    (corpus, forms)
+}
+
+/// Morphological analyses of a surface form, from the cache when possible.
+#[tauri::command]
+pub async fn get_morphology(
+    app: AppHandle,
+    cache: State<'_, MorphologyCache>,
+    port: u16,
+    token: String,
+    corpus: String,
+) -> Result<Vec<MorphAnalysis>, EngineError> {
+    let form = normalize_form(&token);
+    if form.is_empty() {
+        return Ok(Vec::new());
+    }
+    let versions = cache.versions(port, &corpus).await;
+    let key = cache_key(&corpus, &form);
+    if let Some(analyses) = cache.get(&app, &key, &versions) {
+        return Ok(analyses);
+    }
+
+    let request = http::request(Method::GET, port, "/v1/morphology")?
+        .query(&[("form", form.as_str()), ("corpus", corpus.as_str())]);
+    let analyses: Vec<MorphAnalysis> = match http::send_json(request).await {
+        Ok(analyses) => analyses,
+        Err(HttpError::Status { status: 404, .. }) => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+    cache.insert(&app, [(key, analyses.clone())], &versions)?;
+    Ok(analyses)
+}
+
+/// Cache the analyses of every form in a cached passage (by id or
+/// reference) with one engine request for the forms not cached yet.
+#[tauri::command]
+pub async fn prefetch_morphology(
+    app: AppHandle,
+    cache: State<'_, MorphologyCache>,
+    port: u16,
+    passage_ref: String,
+) -> Result<MorphologyPrefetchReport, EngineError> {
+    let passage = find_passage(&app, &passage_ref).map_err(EngineError::Storage)?;
+    let (corpus, forms) = passage_forms(&passage.response);
+    let versions = cache.versions(port, &corpus).await;
+    let unknown: Vec<String> = forms
+        .iter()
+        .filter(|form| {
+            cache
+                .get(&app, &cache_key(&corpus, form), &versions)
+                .is_none()
+        })
+        .cloned()
+        .collect();
+
+    if !unknown.is_empty() {
+        let body = BatchRequest {
+            corpus: &corpus,
+            forms: &unknown,
+        };
+        let request = http::request(Method::POST, port, "/v1/morphology/batch")?.json(&body);
+        let mut response: BatchResponse = http::send_json(request).await?;
+        let found: Vec<_> = unknown
+            .iter()
+            .map(|form| {
+                let analyses = response.results.remove(form).unwrap_or_default();
+                (cache_key(&corpus, form), analyses)
+            })
+            .collect();
+        cache.insert(&app, found, &versions)?;
+    }
+
+    Ok(MorphologyPrefetchReport {
+        forms: forms.len(),
+        already_cached: forms.len() - unknown.len(),
+        fetched: unknown.len(),
+    })
+}
+
+#[tauri::command]
+pub fn get_morphology_cache_stats(
+    app: AppHandle,
+    cache: State<'_, MorphologyCache>,
+) -> MorphologyCacheStats {
+    let entries = cache.with_entries(&app, |entries| entries.len());
+    let file_bytes = storage::app_data_file(&app, CACHE_FILE)
+        .ok()
+        .and_then(|path| fs::metadata(path).ok())
+        .map_or(0, |m| m.len());
+    MorphologyCacheStats {
+        entries,
+        capacity: MAX_ENTRIES,
+        hits: cache.hits.load(Ordering::Relaxed),
+        misses: cache.misses.load(Ordering::Relaxed),
+        file_bytes,
+    }
+}
+
+/// Empty the morphology cache; returns how many forms were dropped.
+#[tauri::command]
+pub fn clear_morphology_cache(
+    app: AppHandle,
+    cache: State<'_, MorphologyCache>,
+) -> Result<usize, AppError> {
+    let removed = cache.with_entries(&app, |entries| {
+        let removed = entries.len();
+        entries.clear();
+        removed
+    });
+    let path = storage::app_data_file(&app, CACHE_FILE)?;
+    if path.exists() {
+        fs::remove_file(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(engine: Option<&str>, corpus: Option<&str>, cached_at_ms: u64) -> CacheEntry {
+        CacheEntry {
+            analyses: Vec::new(),
+            engine_version: engine.map(str::to_string),
+            corpus_version: corpus.map(str::to_string),
+            cached_at_ms,
+        }
+    }
+
+    #[test]
+    fn test_normalize_form() {
+        assert_eq!(normalize_form("Λόγος,"), "λόγος");
+        assert_eq!(normalize_form("(ἀρχῇ)"), "ἀρχῇ");
+        // NFD input matches the NFC form
+        assert_eq!(normalize_form("λο\u{301}γος"), "λόγος");
+        assert_eq!(normalize_form("ἀρχη\u{342}\u{345}"), "ἀρχῇ");
+        assert_eq!(normalize_form("·"), "");
+    }
+
+    #[test]
+    fn test_is_stale() {
+        let current = Versions {
+            engine: Some("0.9".to_string()),
+            corpus: Some("6.12".to_string()),
+        };
+        assert!(!is_stale(&entry(Some("0.9"), Some("6.12"), 0), &current));
+        assert!(is_stale(&entry(Some("0.8"), Some("6.12"), 0), &current));
+        assert!(is_stale(&entry(Some("0.9"), None, 0), &current));
+        // Unknown current versions never invalidate
+        assert!(!is_stale(
+            &entry(Some("0.8"), Some("6.11"), 0),
+            &Versions::default()
+        ));
+    }
+
+    #[test]
+    fn test_evict_oldest() {
+        let mut entries: HashMap<String, CacheEntry> = (0..5)
+            .map(|i| (i.to_string(), entry(None, None, i)))
+            .collect();
+        evict_oldest(&mut entries, 3);
+        let mut keys: Vec<_> = entries.keys().cloned().collect();
+        keys.sort();
+        assert_eq!(keys, ["2", "3", "4"]);
+    }
+
+    #[test]
+    fn test_passage_forms() {
+        let response = serde_json::json!({
+            "provenance": {"spine_source": "open-greek-nt"},
+            "verse_blocks": [{"tokens": [
+                {"surface": "Ἐν"}, {"surface": "ἀρχῇ"}, {"surface": "ἐν,"},
+            ]}],
+        });
+        let (corpus, forms) = passage_forms(&response);
+        assert_eq!(corpus, "open-greek-nt");
+        assert_eq!(forms.into_iter().collect::<Vec<_>>(), ["ἀρχῇ", "ἐν"]);
+        assert_eq!(passage_forms(&serde_json::json!({})).0, DEFAULT_CORPUS);
+    }
+}
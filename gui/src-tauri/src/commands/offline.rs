@@ -0,0 +1,332 @@
+//! Offline mode: one switch for when the network is known to be gone.
+//!
+//! While it is on, requests that would leave this machine (third-party
+//! corpus manifests and downloads, the verse-of-the-day manifest, crash
+//! report uploads, health checks of remote engines) are refused up front
+//! with `HttpError::Offline`, which callers report as skipped rather than
+//! failed. Traffic to the local engine, the offline lexicon and the caches
+//! keep working. The setting is kept in `settings.json` and mirrored in a
+//! static so `http` can consult it without an `AppHandle`.
+//!
+//! Commands that change something and were skipped (corpus downloads and
+//! repairs) are queued in `offline_queue.json`. Turning offline mode off
+//! emits `offline-mode-changed` with `replay_queue` set when the engine is
+//! reachable, and the queue is replayed in the background, each result
+//! emitted as `offline-mutation-replayed`.
+
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+use super::corpus::{self, CorpusIntegrityState};
+use super::engine::{check_engine_running, EngineError};
+use super::errors::AppError;
+use super::http::HttpError;
+use super::settings;
+use super::storage;
+
+/// Event emitted when offline mode is turned on or off
+const CHANGED_EVENT: &str = "offline-mode-changed";
+/// Event emitted as each queued mutation is replayed
+const REPLAYED_EVENT: &str = "offline-mutation-replayed";
+const QUEUE_FILE: &str = "offline_queue.json";
+
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+/// Serializes read-modify-write cycles on the queue file
+static QUEUE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OfflineModeChanged {
+    pub enabled: bool,
+    /// Offline mode was turned off and the engine answers, so queued
+    /// mutations are being replayed
+    pub replay_queue: bool,
+}
+
+/// Result of a command that needs the network: what it did, or
+/// `skipped: offline-mode` when offline mode kept it from running.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", content = "result", rename_all = "snake_case")]
+pub enum NetworkOutcome<T> {
+    Done(T),
+    Skipped(SkipReason),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SkipReason {
+    OfflineMode,
+}
+
+/// A change skipped in offline mode, kept to run once back online.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum QueuedMutation {
+    DownloadCorpus {
+        id: String,
+        #[serde(default)]
+        manifest_url: Option<String>,
+    },
+    RepairCorpus {
+        id: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplayedMutation {
+    pub mutation: QueuedMutation,
+    /// Why it failed; failed mutations are not queued again
+    pub error: Option<String>,
+}
+
+/// Whether offline mode is on.
+pub fn is_offline() -> bool {
+    OFFLINE.load(Ordering::SeqCst)
+}
+
+/// Whether `url` points at this machine. Unparsable URLs count as remote.
+fn is_local_url(url: &str) -> bool {
+    let Some(host) = Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(str::to_string))
+    else {
+        return false;
+    };
+    host.eq_ignore_ascii_case("localhost")
+        || host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse::<IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback())
+}
+
+/// The gate networked features consult before a request to `url`.
+pub fn check_url(url: &str) -> Result<(), HttpError> {
+    if is_offline() && !is_local_url(url) {
+        return Err(HttpError::Offline);
+    }
+    Ok(())
+}
+
+/// `result`, with a refusal by the offline gate reported as skipped.
+pub fn skip_when_offline<T>(
+    result: Result<T, EngineError>,
+) -> Result<NetworkOutcome<T>, EngineError> {
+    match result {
+        Ok(value) => Ok(NetworkOutcome::Done(value)),
+        Err(EngineError::Http(HttpError::Offline)) => {
+            Ok(NetworkOutcome::Skipped(SkipReason::OfflineMode))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+fn queue_path(app: &AppHandle) -> Result<PathBuf, String> {
+    storage::app_data_file(app, QUEUE_FILE)
+}
+
+fn read_queue(path: &Path) -> Result<Vec<QueuedMutation>, String> {
+    let _guard = QUEUE_LOCK.lock().unwrap();
+    storage::read_json_or_default(path)
+}
+
+fn update_queue(
+    path: &Path,
+    change: impl FnOnce(&mut Vec<QueuedMutation>),
+) -> Result<Vec<QueuedMutation>, String> {
+    let _guard = QUEUE_LOCK.lock().unwrap();
+    let mut queue: Vec<QueuedMutation> = storage::read_json_or_default(path)?;
+    change(&mut queue);
+    storage::write_json(path, &queue)?;
+    Ok(queue)
+}
+
+fn push_mutation(path: &Path, mutation: QueuedMutation) -> Result<(), String> {
+    update_queue(path, |queue| {
+        if !queue.contains(&mutation) {
+            queue.push(mutation);
+        }
+    })
+    .map(drop)
+}
+
+/// Queue `mutation` if `outcome` says it was skipped.
+pub fn queue_if_skipped<T>(app: &AppHandle, outcome: &NetworkOutcome<T>, mutation: QueuedMutation) {
+    if !matches!(outcome, NetworkOutcome::Skipped(_)) {
+        return;
+    }
+    if let Err(e) = queue_path(app).and_then(|path| push_mutation(&path, mutation)) {
+        tracing::warn!("Could not queue an offline mutation: {}", e);
+    }
+}
+
+/// Run each queued mutation with `run`, in order. Mutations that ran, or
+/// failed for another reason than offline mode, leave the queue.
+async fn replay<F>(
+    path: &Path,
+    run: impl Fn(QueuedMutation) -> F,
+    mut replayed: impl FnMut(ReplayedMutation),
+) -> Result<(), String>
+where
+    F: Future<Output = Result<(), EngineError>>,
+{
+    let queued = read_queue(path)?;
+    for mutation in queued {
+        let error = match run(mutation.clone()).await {
+            Ok(()) => None,
+            // Offline mode was turned back on; the rest waits for next time
+            Err(EngineError::Http(HttpError::Offline)) => break,
+            Err(e) => Some(e.to_string()),
+        };
+        update_queue(path, |queue| queue.retain(|m| *m != mutation))?;
+        replayed(ReplayedMutation { mutation, error });
+    }
+    Ok(())
+}
+
+async fn replay_queue(app: AppHandle, port: u16) {
+    let run = |mutation| {
+        let app = app.clone();
+        async move {
+            match mutation {
+                QueuedMutation::DownloadCorpus { id, manifest_url } => {
+                    corpus::install_corpus(&app, port, id, manifest_url)
+                        .await
+                        .map(drop)
+                }
+                QueuedMutation::RepairCorpus { id } => {
+                    let integrity = app.state::<CorpusIntegrityState>();
+                    corpus::repair(&app, &integrity, port, id).await.map(drop)
+                }
+            }
+        }
+    };
+    let emit = |replayed| {
+        let _ = app.emit(REPLAYED_EVENT, replayed);
+    };
+    let result = match queue_path(&app) {
+        Ok(path) => replay(&path, run, emit).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        tracing::warn!("Could not replay the offline queue: {}", e);
+    }
+}
+
+/// Restore offline mode at startup.
+pub fn init_offline_mode(app: &AppHandle) {
+    let enabled = settings::load_settings(app)
+        .map(|s| s.offline_mode)
+        .unwrap_or(false);
+    OFFLINE.store(enabled, Ordering::SeqCst);
+}
+
+/// Turn offline mode on or off (remembered across restarts). `port` is the
+/// local engine, checked before asking the frontend to replay its queue.
+#[tauri::command]
+pub fn set_offline_mode(app: AppHandle, enabled: bool, port: Option<u16>) -> Result<(), AppError> {
+    settings::update_settings(&app, |s| s.offline_mode = enabled)?;
+    let was_enabled = OFFLINE.swap(enabled, Ordering::SeqCst);
+    if was_enabled == enabled {
+        return Ok(());
+    }
+    let replay_port = port.filter(|&port| !enabled && check_engine_running(port).running);
+    let _ = app.emit(
+        CHANGED_EVENT,
+        OfflineModeChanged {
+            enabled,
+            replay_queue: replay_port.is_some(),
+        },
+    );
+    if let Some(port) = replay_port {
+        tauri::async_runtime::spawn(replay_queue(app, port));
+    }
+    Ok(())
+}
+
+/// Whether offline mode is on.
+#[tauri::command]
+pub fn get_offline_mode() -> bool {
+    is_offline()
+}
+
+/// Mutations waiting for offline mode to be turned off, oldest first.
+#[tauri::command]
+pub fn get_offline_queue(app: AppHandle) -> Result<Vec<QueuedMutation>, AppError> {
+    Ok(read_queue(&queue_path(&app)?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_is_local_url() {
+        assert!(is_local_url("http://127.0.0.1:8000/health"));
+        assert!(is_local_url("http://localhost:50051"));
+        assert!(is_local_url("http://[::1]:8000/"));
+        assert!(!is_local_url("https://example.org/corpora.json"));
+        assert!(!is_local_url("http://192.168.1.10:8000"));
+        assert!(!is_local_url("not a url"));
+    }
+
+    #[test]
+    fn test_offline_refusal_is_skipped() {
+        let skipped = skip_when_offline::<()>(Err(HttpError::Offline.into())).unwrap();
+        assert_eq!(
+            serde_json::to_value(&skipped).unwrap(),
+            json!({ "kind": "skipped", "result": "offline-mode" })
+        );
+        assert_eq!(skip_when_offline(Ok(3)).unwrap(), NetworkOutcome::Done(3));
+        assert!(skip_when_offline::<()>(Err(EngineError::NotRunning)).is_err());
+    }
+
+    #[test]
+    fn test_replay_queue() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(QUEUE_FILE);
+        let download = |id: &str| QueuedMutation::DownloadCorpus {
+            id: id.to_string(),
+            manifest_url: None,
+        };
+        let repair = |id: &str| QueuedMutation::RepairCorpus { id: id.to_string() };
+        for mutation in [
+            download("sblgnt"),
+            repair("lxx"),
+            download("sblgnt"),
+            repair("byz"),
+        ] {
+            push_mutation(&path, mutation).unwrap();
+        }
+
+        // Offline mode comes back on while "byz" is replayed
+        let mut replayed = Vec::new();
+        let run = |mutation: QueuedMutation| async move {
+            match mutation {
+                QueuedMutation::DownloadCorpus { .. } => Ok(()),
+                QueuedMutation::RepairCorpus { id } if id == "lxx" => {
+                    Err(EngineError::CorpusNotFound(id))
+                }
+                QueuedMutation::RepairCorpus { .. } => Err(HttpError::Offline.into()),
+            }
+        };
+        tauri::async_runtime::block_on(replay(&path, run, |r| replayed.push(r))).unwrap();
+
+        let results: Vec<_> = replayed
+            .into_iter()
+            .map(|r| (r.mutation, r.error.is_some()))
+            .collect();
+        assert_eq!(
+            results,
+            [(download("sblgnt"), false), (repair("lxx"), true)]
+        );
+        let left: Vec<QueuedMutation> = storage::read_json(&path).unwrap();
+        assert_eq!(left, [repair("byz")]);
+    }
+}
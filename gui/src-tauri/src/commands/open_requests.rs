@@ -0,0 +1,54 @@
+//! Queue of "open this" requests from outside the app.
+//!
+//! Command-line arguments (including those forwarded from a second
+//! instance), deep links and file associations all land here. Requests
+//! are queued because they can arrive before the frontend has loaded; the
+//! frontend drains the queue with `take_open_requests` once it is ready,
+//! after which new requests are only delivered as `open-request` events.
+
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// Event emitted when a request is queued
+const OPEN_REQUEST_EVENT: &str = "open-request";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum OpenRequest {
+    /// Navigate to a passage (normalized reference)
+    Passage { reference: String },
+    /// Open a project file
+    Project { path: PathBuf },
+}
+
+#[derive(Default)]
+struct QueueInner {
+    pending: Vec<OpenRequest>,
+    /// Set once the frontend has drained the queue
+    frontend_ready: bool,
+}
+
+/// Pending open requests, oldest first.
+#[derive(Default)]
+pub struct OpenRequestQueue(Mutex<QueueInner>);
+
+/// Deliver a request: queued until the frontend is ready, emitted after.
+pub fn queue_open_request(app: &AppHandle, request: OpenRequest) {
+    let state = app.state::<OpenRequestQueue>();
+    let mut queue = state.0.lock().unwrap();
+    if queue.frontend_ready {
+        let _ = app.emit(OPEN_REQUEST_EVENT, request);
+    } else {
+        queue.pending.push(request);
+    }
+}
+
+/// Take all pending open requests. Called by the frontend once it is ready.
+#[tauri::command]
+pub fn take_open_requests(queue: State<'_, OpenRequestQueue>) -> Vec<OpenRequest> {
+    let mut queue = queue.0.lock().unwrap();
+    queue.frontend_ready = true;
+    std::mem::take(&mut queue.pending)
+}
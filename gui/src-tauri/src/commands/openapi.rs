@@ -0,0 +1,90 @@
+//! Local copy of the engine's OpenAPI spec for the API docs view.
+//!
+//! The spec is cached in `openapi_spec.json` under the app data dir along
+//! with the engine version it came from. A cached spec from a different
+//! engine version is dropped as soon as the new version is seen.
+
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use tauri::AppHandle;
+
+use super::engine::EngineError;
+use super::http::{self, HttpError};
+use super::storage;
+
+const SPEC_FILE: &str = "openapi_spec.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedSpec {
+    /// Engine version the spec was fetched from, if known
+    engine_version: Option<String>,
+    fetched_at_ms: u64,
+    spec: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct EngineVersion {
+    version: String,
+}
+
+fn load_cached(app: &AppHandle) -> Option<CachedSpec> {
+    let path = storage::app_data_file(app, SPEC_FILE).ok()?;
+    storage::read_json(&path).ok()
+}
+
+/// Version reported by `/v1/engine/status`.
+pub async fn engine_version(port: u16) -> Option<String> {
+    let request = http::request(Method::GET, port, "/v1/engine/status").ok()?;
+    let status: EngineVersion = http::send_json(request).await.ok()?;
+    Some(status.version)
+}
+
+/// Fetch the engine's OpenAPI spec, cache it and return it as JSON text.
+#[tauri::command]
+pub async fn generate_openapi_spec(app: AppHandle, port: u16) -> Result<String, EngineError> {
+    let path = storage::app_data_file(&app, SPEC_FILE).map_err(EngineError::Storage)?;
+
+    // Drop a spec from another engine version even if the fetch below fails
+    let version = engine_version(port).await;
+    if let (Some(cached), Some(version)) = (load_cached(&app), version.as_ref()) {
+        if cached.engine_version.as_ref() != Some(version) {
+            let _ = fs::remove_file(&path);
+        }
+    }
+
+    let request = http::request(Method::GET, port, "/v1/openapi.json")?;
+    let body = http::send(request)
+        .await?
+        .text()
+        .await
+        .map_err(|e| HttpError::Decode(e.to_string()))?;
+    let spec: Value = serde_json::from_str(&body).map_err(|e| HttpError::Decode(e.to_string()))?;
+    if !spec.is_object() {
+        return Err(HttpError::Decode("OpenAPI spec is not a JSON object".to_string()).into());
+    }
+
+    let cached = CachedSpec {
+        // Engines without a status version still report one in the spec
+        engine_version: version.or_else(|| spec["info"]["version"].as_str().map(str::to_string)),
+        fetched_at_ms: storage::now_ms(),
+        spec,
+    };
+    storage::write_json(&path, &cached).map_err(EngineError::Storage)?;
+    Ok(body)
+}
+
+/// Last cached OpenAPI spec, without contacting the engine.
+#[tauri::command]
+pub fn get_cached_openapi_spec(app: AppHandle) -> Option<String> {
+    load_cached(&app).and_then(|cached| serde_json::to_string(&cached.spec).ok())
+}
+
+/// Seconds since the spec was cached; `u64::MAX` when nothing is cached.
+#[tauri::command]
+pub fn openapi_spec_age_seconds(app: AppHandle) -> u64 {
+    load_cached(&app).map_or(u64::MAX, |cached| {
+        storage::now_ms().saturating_sub(cached.fetched_at_ms) / 1000
+    })
+}
@@ -0,0 +1,417 @@
+//! OSIS 2.1.1 XML export of cached translations.
+//!
+//! Unlike USFM, one OSIS document can hold several books, so verses from
+//! all selected passages are merged in canonical order. Red-letter verses
+//! are wrapped in `<q who="Jesus">`. The document is re-parsed and checked
+//! structurally before anything is written. Source attribution goes in
+//! `<rights>` elements of the work header.
+
+use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::{Reader, Writer};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+use super::errors::AppError;
+use super::licensing;
+use super::passages::{load_passage, CachedPassage};
+use super::red_letters::red_letter_verses;
+use super::reference::{book_position, osis_book_id};
+use super::save_dialog::{validate_export_path, ExportKind};
+use super::storage;
+use super::tags;
+use super::usfm::split_verse_id;
+
+const OSIS_NAMESPACE: &str = "http://www.bibletechnologies.net/2003/OSIS/namespace";
+const XSI_NAMESPACE: &str = "http://www.w3.org/2001/XMLSchema-instance";
+const OSIS_SCHEMA: &str = "http://www.bibletechnologies.net/osisCore.2.1.1.xsd";
+/// osisWork name used for this app's translations
+const WORK: &str = "RedLetters";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OsisOptions {
+    /// Wrap red-letter verses in `<q who="Jesus">`
+    #[serde(default = "default_true", alias = "red_letter")]
+    pub highlight_red_letters: bool,
+    /// Include the private passage notes as `<note>` elements
+    #[serde(default)]
+    pub include_notes: bool,
+    /// Only passages with this tag; with no passage ids, every passage
+    /// carrying it
+    #[serde(default)]
+    pub tag: Option<String>,
+    /// Credit the source texts and engine in the header; most corpus
+    /// licenses require it when the file is shared
+    #[serde(default = "default_true")]
+    pub include_attribution: bool,
+}
+fn default_true() -> bool {
+    true
+}
+
+impl Default for OsisOptions {
+    fn default() -> Self {
+        Self {
+            highlight_red_letters: true,
+            include_notes: false,
+            tag: None,
+            include_attribution: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OsisExportReport {
+    pub path: String,
+    pub verses_written: usize,
+    /// Verse ids with no OSIS book id
+    pub unmapped: Vec<String>,
+    /// Passage ids without a cached translation, and verse ids with no text
+    pub skipped: Vec<String>,
+}
+
+/// One verse ready to render.
+#[derive(Debug, Clone)]
+struct OsisVerse {
+    book: &'static str,
+    chapter: u32,
+    verse: u32,
+    translation: String,
+    red_letter: bool,
+    notes: Vec<String>,
+}
+
+/// Collect verses from the given passages in canonical order.
+fn collect_verses(
+    passages: &[CachedPassage],
+    include_notes: bool,
+    unmapped: &mut Vec<String>,
+    skipped: &mut Vec<String>,
+) -> Vec<OsisVerse> {
+    let mut verses = BTreeMap::new();
+    for passage in passages {
+        let red = red_letter_verses(passage);
+        let mut note = passage
+            .note
+            .as_deref()
+            .map(str::trim)
+            .filter(|n| include_notes && !n.is_empty());
+        for v in &passage.verses {
+            let mapped = split_verse_id(&v.verse_id).and_then(|(book, chapter, verse)| {
+                Some((book_position(book)?, osis_book_id(book)?, chapter, verse))
+            });
+            let Some((position, book, chapter, verse)) = mapped else {
+                unmapped.push(v.verse_id.clone());
+                continue;
+            };
+            if v.translation.trim().is_empty() {
+                skipped.push(v.verse_id.clone());
+                continue;
+            }
+            // The first passage containing a verse wins
+            let entry = verses
+                .entry((position, chapter, verse))
+                .or_insert_with(|| OsisVerse {
+                    book,
+                    chapter,
+                    verse,
+                    translation: v
+                        .translation
+                        .split_whitespace()
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                    red_letter: red.contains(&v.verse_id),
+                    notes: Vec::new(),
+                });
+            // A passage note is attached to its first exported verse
+            if let Some(note) = note.take() {
+                entry.notes.push(note.to_string());
+            }
+        }
+    }
+    verses.into_values().collect()
+}
+
+fn render_osis(
+    verses: &[OsisVerse],
+    options: &OsisOptions,
+    attribution: &[String],
+) -> std::io::Result<String> {
+    let mut writer = Writer::new(Vec::new());
+    writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+    writer.write_event(Event::Text(BytesText::new("\n")))?;
+    writer.write_event(Event::Start(BytesStart::new("osis").with_attributes([
+        ("xmlns", OSIS_NAMESPACE),
+        ("xmlns:xsi", XSI_NAMESPACE),
+        (
+            "xsi:schemaLocation",
+            &*format!("{} {}", OSIS_NAMESPACE, OSIS_SCHEMA),
+        ),
+    ])))?;
+    writer.write_event(Event::Start(BytesStart::new("osisText").with_attributes([
+        ("osisIDWork", WORK),
+        ("osisRefWork", "Bible"),
+        ("xml:lang", "en"),
+    ])))?;
+    writer.create_element("header").write_inner_content(|w| {
+        w.create_element("work")
+            .with_attribute(("osisWork", WORK))
+            .write_inner_content(|w| {
+                w.create_element("title")
+                    .write_text_content(BytesText::new("Red Letters translation"))?;
+                for line in attribution {
+                    w.create_element("rights")
+                        .write_text_content(BytesText::new(line))?;
+                }
+                Ok(())
+            })?;
+        Ok(())
+    })?;
+
+    let mut open: Option<(&str, u32)> = None;
+    for v in verses {
+        if open.map(|(book, _)| book) != Some(v.book) {
+            if open.is_some() {
+                writer.write_event(Event::End(BytesEnd::new("chapter")))?;
+                writer.write_event(Event::End(BytesEnd::new("div")))?;
+            }
+            writer.write_event(Event::Start(
+                BytesStart::new("div").with_attributes([("type", "book"), ("osisID", v.book)]),
+            ))?;
+        } else if open.map(|(_, chapter)| chapter) != Some(v.chapter) {
+            writer.write_event(Event::End(BytesEnd::new("chapter")))?;
+        }
+        if open != Some((v.book, v.chapter)) {
+            let chapter_id = format!("{}.{}", v.book, v.chapter);
+            writer.write_event(Event::Start(
+                BytesStart::new("chapter").with_attributes([("osisID", chapter_id.as_str())]),
+            ))?;
+            open = Some((v.book, v.chapter));
+        }
+
+        let verse_id = format!("{}.{}.{}", v.book, v.chapter, v.verse);
+        writer
+            .create_element("verse")
+            .with_attribute(("osisID", verse_id.as_str()))
+            .write_inner_content(|w| {
+                if options.highlight_red_letters && v.red_letter {
+                    w.create_element("q")
+                        .with_attributes([("who", "Jesus"), ("marker", "")])
+                        .write_text_content(BytesText::new(&v.translation))?;
+                } else {
+                    w.write_event(Event::Text(BytesText::new(&v.translation)))?;
+                }
+                for note in &v.notes {
+                    w.create_element("note")
+                        .with_attribute(("type", "x-user"))
+                        .write_text_content(BytesText::new(note))?;
+                }
+                Ok(())
+            })?;
+    }
+    if open.is_some() {
+        writer.write_event(Event::End(BytesEnd::new("chapter")))?;
+        writer.write_event(Event::End(BytesEnd::new("div")))?;
+    }
+    writer.write_event(Event::End(BytesEnd::new("osisText")))?;
+    writer.write_event(Event::End(BytesEnd::new("osis")))?;
+
+    Ok(String::from_utf8_lossy(&writer.into_inner()).into_owned())
+}
+
+/// Re-parse a document and return the verse osisIDs found.
+///
+/// Requires a well-formed `<osis>` root in the OSIS namespace containing
+/// `<osisText>`, and every `<verse>` inside a `<chapter>` whose osisID
+/// prefixes the verse's.
+fn check_osis(xml: &str) -> Result<Vec<String>, String> {
+    let mut reader = Reader::from_str(xml);
+    let mut stack: Vec<(String, Option<String>)> = Vec::new();
+    let mut verses = Vec::new();
+    let mut has_text = false;
+
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| format!("at byte {}: {}", reader.error_position(), e))?;
+        let (start, empty) = match event {
+            Event::Start(start) => (start, false),
+            Event::Empty(start) => (start, true),
+            Event::End(_) => {
+                stack.pop();
+                continue;
+            }
+            Event::Eof => break,
+            _ => continue,
+        };
+
+        let name = String::from_utf8_lossy(start.name().as_ref()).into_owned();
+        let attr = |key: &str| -> Result<Option<String>, String> {
+            match start.try_get_attribute(key).map_err(|e| e.to_string())? {
+                Some(a) => Ok(Some(
+                    a.unescape_value().map_err(|e| e.to_string())?.into_owned(),
+                )),
+                None => Ok(None),
+            }
+        };
+        match (name.as_str(), stack.last()) {
+            ("osis", None) if attr("xmlns")?.as_deref() != Some(OSIS_NAMESPACE) => {
+                return Err("<osis> is not in the OSIS namespace".to_string())
+            }
+            ("osis", None) => {}
+            (_, None) => return Err(format!("Root element is <{}>, not <osis>", name)),
+            ("osisText", Some((parent, _))) if parent == "osis" => has_text = true,
+            ("chapter", Some((parent, _))) if parent != "div" => {
+                return Err("<chapter> outside a book <div>".to_string())
+            }
+            ("verse", parent) => {
+                let id = attr("osisID")?.ok_or("<verse> without an osisID")?;
+                let chapter = match parent {
+                    Some((parent, Some(chapter))) if parent == "chapter" => chapter,
+                    _ => return Err(format!("Verse {} outside a <chapter>", id)),
+                };
+                if id.rsplit_once('.').map(|(c, _)| c) != Some(chapter.as_str()) {
+                    return Err(format!("Verse {} inside chapter {}", id, chapter));
+                }
+                verses.push(id);
+            }
+            _ => {}
+        }
+        if !empty {
+            let id = attr("osisID")?;
+            stack.push((name, id));
+        }
+    }
+    if !has_text {
+        return Err("Missing <osisText>".to_string());
+    }
+    Ok(verses)
+}
+
+/// Export cached translations as an OSIS XML document.
+///
+/// `dest_path` should come from `choose_save_path` with kind `osis`.
+#[tauri::command]
+pub fn export_osis(
+    app: AppHandle,
+    passage_ids: Vec<String>,
+    dest_path: String,
+    options: Option<OsisOptions>,
+) -> Result<OsisExportReport, AppError> {
+    let options = options.unwrap_or_default();
+    let passage_ids = tags::passage_ids_for_export(&app, passage_ids, options.tag.as_deref())?;
+    let path = validate_export_path(&app, ExportKind::Osis, &PathBuf::from(dest_path))?;
+
+    let mut skipped = Vec::new();
+    let passages: Vec<CachedPassage> = passage_ids
+        .into_iter()
+        .filter_map(|id| match load_passage(&app, &id) {
+            Ok(passage) => Some(passage),
+            Err(_) => {
+                skipped.push(id);
+                None
+            }
+        })
+        .collect();
+
+    let mut unmapped = Vec::new();
+    let verses = collect_verses(
+        &passages,
+        options.include_notes,
+        &mut unmapped,
+        &mut skipped,
+    );
+    if verses.is_empty() {
+        return Err(AppError::InvalidInput(
+            "No cached verses to export".to_string(),
+        ));
+    }
+
+    let attribution = licensing::attribution_lines(options.include_attribution, &passages);
+    let osis = render_osis(&verses, &options, &attribution).map_err(|e| e.to_string())?;
+    let ids = check_osis(&osis).map_err(|e| format!("Generated OSIS is malformed: {}", e))?;
+    storage::write_atomic(&path, osis.as_bytes())?;
+
+    Ok(OsisExportReport {
+        path: path.to_string_lossy().into_owned(),
+        verses_written: ids.len(),
+        unmapped,
+        skipped,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testsupport::passage::PassageBuilder;
+
+    fn passage(verses: &[(&str, &str)], red: &[&str], note: Option<&str>) -> CachedPassage {
+        PassageBuilder::new()
+            .verses(verses, "")
+            .red_letters(red)
+            .note(note)
+            .build()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let passages = [
+            passage(
+                &[("John.3.16", "For God so loved"), ("John.3.17", "")],
+                &[],
+                Some("Compare <1 John 4:9> & 4:10"),
+            ),
+            passage(
+                &[("1Corinthians.13.4", "Love is patient"), ("Tobit.1.1", "x")],
+                &[],
+                None,
+            ),
+            passage(
+                &[
+                    ("Mark.1.15", "The time is fulfilled"),
+                    ("John.3.18", "He who"),
+                ],
+                &["Mark.1.15"],
+                None,
+            ),
+        ];
+        let (mut unmapped, mut skipped) = (Vec::new(), Vec::new());
+        let verses = collect_verses(&passages, true, &mut unmapped, &mut skipped);
+        assert_eq!(unmapped, ["Tobit.1.1"]);
+        assert_eq!(skipped, ["John.3.17"]);
+
+        let attribution = ["SBLGNT © SBL & Logos".to_string()];
+        let osis = render_osis(&verses, &OsisOptions::default(), &attribution).unwrap();
+        assert_eq!(
+            check_osis(&osis).unwrap(),
+            ["Mark.1.15", "John.3.16", "John.3.18", "1Cor.13.4"]
+        );
+        assert!(osis.contains(r#"<q who="Jesus" marker="">The time is fulfilled</q>"#));
+        assert!(osis.contains("Compare &lt;1 John 4:9&gt; &amp; 4:10"));
+        assert!(osis.contains("</title><rights>SBLGNT © SBL &amp; Logos</rights></work>"));
+
+        let verses = collect_verses(&passages, false, &mut Vec::new(), &mut Vec::new());
+        let osis = render_osis(&verses, &OsisOptions::default(), &[]).unwrap();
+        assert!(!osis.contains("<note") && !osis.contains("<rights"));
+    }
+
+    #[test]
+    fn test_check_osis_rejects_bad_structure() {
+        let wrap = |body: &str| {
+            format!(
+                r#"<osis xmlns="{}"><osisText>{}</osisText></osis>"#,
+                OSIS_NAMESPACE, body
+            )
+        };
+        assert!(check_osis(&wrap("")).unwrap().is_empty());
+        assert!(check_osis(&wrap(r#"<verse osisID="John.3.16">a</verse>"#)).is_err());
+        assert!(check_osis(&wrap(
+            r#"<div><chapter osisID="John.3"><verse osisID="John.4.1">a</verse></chapter></div>"#
+        ))
+        .is_err());
+        assert!(check_osis(&wrap("<div><chapter osisID=\"John.3\"></div>")).is_err());
+        assert!(check_osis("<osis><osisText/></osis>").is_err());
+        assert!(check_osis(&format!(r#"<osis xmlns="{}"/>"#, OSIS_NAMESPACE)).is_err());
+    }
+}
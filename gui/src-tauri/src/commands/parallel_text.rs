@@ -0,0 +1,285 @@
+//! Greek/English parallel text as TSV, for corpus work.
+//!
+//! Each line holds an aligned pair from a cached translation, its
+//! reference and a flag: `greek`, `translation`, `reference`, `flag`.
+//! Pairs are verses, or sentences inside a verse as split by
+//! `text_utils::split_sentences`. Where the two sides can't be paired (an
+//! empty side, or different sentence counts) the whole verse is written
+//! with a flag instead of being dropped. The report carries a SHA-256 of
+//! the file so exports can be compared. The source attribution goes in a
+//! text file next to it (see `licensing::write_sidecar`).
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+use super::document_export::{one_line, PassageExportFailure};
+use super::errors::AppError;
+use super::interlinear_table::{tsv_field, verse_reference};
+use super::licensing;
+use super::passages::{load_passage, CachedPassage};
+use super::save_dialog::{validate_export_path, ExportKind};
+use super::storage;
+use super::text_utils::split_sentences;
+
+const COLUMNS: [&str; 4] = ["greek", "translation", "reference", "flag"];
+/// Greek sentence ends: full stop, question mark (; and U+037E) and the
+/// ano teleia (U+0387, often typed as U+00B7)
+const GREEK_TERMINATORS: &[char] = &['.', ';', '\u{037E}', '\u{0387}', '\u{00B7}'];
+/// English counterparts; the ano teleia usually becomes a semicolon or
+/// colon in translation
+const ENGLISH_TERMINATORS: &[char] = &['.', '?', '!', ';', ':'];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlignmentGranularity {
+    Verse,
+    Sentence,
+}
+
+/// Why a pair couldn't be aligned; empty in the file when it was.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AlignmentFlag {
+    EmptyGreek,
+    EmptyTranslation,
+    SentenceCountMismatch,
+}
+
+impl AlignmentFlag {
+    fn as_str(self) -> &'static str {
+        match self {
+            AlignmentFlag::EmptyGreek => "empty_greek",
+            AlignmentFlag::EmptyTranslation => "empty_translation",
+            AlignmentFlag::SentenceCountMismatch => "sentence_count_mismatch",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Pair {
+    greek: String,
+    translation: String,
+    reference: String,
+    flag: Option<AlignmentFlag>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ParallelTextReport {
+    pub path: String,
+    pub passages_written: usize,
+    pub pairs: usize,
+    /// Pairs written with a flag
+    pub flagged: usize,
+    /// SHA-256 of the file, hex
+    pub sha256: String,
+    /// Source attribution written next to the file, if any
+    pub attribution_path: Option<String>,
+    pub failed: Vec<PassageExportFailure>,
+}
+
+fn sentences(text: &str, boundaries: &[char]) -> Vec<String> {
+    split_sentences(text, boundaries)
+        .into_iter()
+        .map(|s| s.text)
+        .collect()
+}
+
+/// Pairs for one verse.
+fn verse_pairs(
+    reference: &str,
+    greek: &str,
+    translation: &str,
+    granularity: AlignmentGranularity,
+) -> Vec<Pair> {
+    let (greek, translation) = (one_line(greek), one_line(translation));
+    let whole = |flag| Pair {
+        greek: greek.clone(),
+        translation: translation.clone(),
+        reference: reference.to_string(),
+        flag,
+    };
+    if greek.is_empty() {
+        return vec![whole(Some(AlignmentFlag::EmptyGreek))];
+    }
+    if translation.is_empty() {
+        return vec![whole(Some(AlignmentFlag::EmptyTranslation))];
+    }
+    if granularity == AlignmentGranularity::Verse {
+        return vec![whole(None)];
+    }
+
+    let greek_sentences = sentences(&greek, GREEK_TERMINATORS);
+    let english_sentences = sentences(&translation, ENGLISH_TERMINATORS);
+    if greek_sentences.len() != english_sentences.len() {
+        return vec![whole(Some(AlignmentFlag::SentenceCountMismatch))];
+    }
+    if greek_sentences.len() == 1 {
+        return vec![whole(None)];
+    }
+    greek_sentences
+        .into_iter()
+        .zip(english_sentences)
+        .enumerate()
+        .map(|(i, (greek, translation))| Pair {
+            greek,
+            translation,
+            reference: format!("{} #{}", reference, i + 1),
+            flag: None,
+        })
+        .collect()
+}
+
+/// Pairs for every verse of a passage. Untranslated verses are kept and
+/// flagged like any other unaligned pair.
+fn passage_pairs(passage: &CachedPassage, granularity: AlignmentGranularity) -> Vec<Pair> {
+    if passage.verses.is_empty() {
+        // Older cache entries only have the whole-passage text
+        return verse_pairs(
+            &passage.reference,
+            &passage.greek,
+            &passage.translation,
+            granularity,
+        );
+    }
+    passage
+        .verses
+        .iter()
+        .flat_map(|v| {
+            verse_pairs(
+                &verse_reference(&v.verse_id),
+                &v.greek,
+                &v.translation,
+                granularity,
+            )
+        })
+        .collect()
+}
+
+fn render(pairs: &[Pair]) -> Vec<u8> {
+    let mut out = String::new();
+    out.push_str(&COLUMNS.join("\t"));
+    out.push('\n');
+    for pair in pairs {
+        let fields = [
+            tsv_field(&pair.greek),
+            tsv_field(&pair.translation),
+            tsv_field(&pair.reference),
+            pair.flag.map_or("", AlignmentFlag::as_str).to_string(),
+        ];
+        out.push_str(&fields.join("\t"));
+        out.push('\n');
+    }
+    out.into_bytes()
+}
+
+/// Export aligned Greek/English pairs from cached passages as TSV.
+///
+/// `granularity` is `verse` or `sentence`. `dest_path` should come from
+/// `choose_save_path` with kind `csv`; a `.tsv` name is kept as is. The
+/// attribution file is written unless `include_attribution` is false.
+#[tauri::command]
+pub fn export_parallel_text(
+    app: AppHandle,
+    passage_ids: Vec<String>,
+    dest_path: String,
+    granularity: AlignmentGranularity,
+    include_attribution: Option<bool>,
+) -> Result<ParallelTextReport, AppError> {
+    let path = validate_export_path(&app, ExportKind::Csv, &PathBuf::from(dest_path))?;
+    if passage_ids.is_empty() {
+        return Err(AppError::InvalidInput("No passages selected".to_string()));
+    }
+
+    let mut attribution = licensing::export_attribution(include_attribution.unwrap_or(true));
+    let mut pairs = Vec::new();
+    let mut failed = Vec::new();
+    let mut passages_written = 0;
+    for id in &passage_ids {
+        match load_passage(&app, id) {
+            Ok(passage) => {
+                pairs.extend(passage_pairs(&passage, granularity));
+                passages_written += 1;
+                if let Some(attribution) = &mut attribution {
+                    attribution.add_passage(&passage);
+                }
+            }
+            Err(error) => failed.push(PassageExportFailure {
+                passage_id: id.clone(),
+                error,
+            }),
+        }
+    }
+
+    let content = render(&pairs);
+    storage::write_atomic(&path, &content)?;
+    let attribution_path = match &attribution {
+        Some(attribution) => Some(licensing::write_sidecar(&path, attribution)?),
+        None => None,
+    };
+    Ok(ParallelTextReport {
+        path: path.to_string_lossy().into_owned(),
+        passages_written,
+        pairs: pairs.len(),
+        flagged: pairs.iter().filter(|p| p.flag.is_some()).count(),
+        sha256: hex::encode(Sha256::digest(&content)),
+        attribution_path: attribution_path.map(|p| p.to_string_lossy().into_owned()),
+        failed,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_greek_sentence_splitting() {
+        let greek = "Ἐν ἀρχῇ ἦν ὁ λόγος· καὶ ὁ λόγος ἦν πρὸς τὸν θεόν. τίς ἐστιν;";
+        assert_eq!(
+            sentences(greek, GREEK_TERMINATORS),
+            [
+                "Ἐν ἀρχῇ ἦν ὁ λόγος·",
+                "καὶ ὁ λόγος ἦν πρὸς τὸν θεόν.",
+                "τίς ἐστιν;"
+            ]
+        );
+        assert_eq!(
+            sentences(
+                "He said, \"Go.\" See John 3.16... then",
+                ENGLISH_TERMINATORS
+            ),
+            ["He said, \"Go.\"", "See John 3.16... then"]
+        );
+    }
+
+    #[test]
+    fn test_verse_pairs() {
+        let pairs = verse_pairs(
+            "John 1:1",
+            "Ἐν ἀρχῇ ἦν ὁ λόγος· καὶ ὁ λόγος ἦν πρὸς τὸν θεόν.",
+            "In the beginning was the Word; and the Word was with God.",
+            AlignmentGranularity::Sentence,
+        );
+        assert_eq!(pairs.len(), 2);
+        assert_eq!(pairs[1].reference, "John 1:1 #2");
+        assert_eq!(pairs[1].translation, "and the Word was with God.");
+
+        let pairs = verse_pairs(
+            "John 1:1",
+            "Ἐν ἀρχῇ ἦν ὁ λόγος· καὶ ὁ λόγος ἦν πρὸς τὸν θεόν.",
+            "In the beginning was the Word, and the Word was with God.",
+            AlignmentGranularity::Sentence,
+        );
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].flag, Some(AlignmentFlag::SentenceCountMismatch));
+
+        let pairs = verse_pairs("John 1:2", "οὗτος ἦν", " ", AlignmentGranularity::Verse);
+        assert_eq!(pairs[0].flag, Some(AlignmentFlag::EmptyTranslation));
+
+        let tsv = String::from_utf8(render(&pairs)).unwrap();
+        assert_eq!(
+            tsv,
+            "greek\ttranslation\treference\tflag\nοὗτος ἦν\t\tJohn 1:2\tempty_translation\n"
+        );
+    }
+}
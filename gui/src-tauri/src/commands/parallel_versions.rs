@@ -0,0 +1,161 @@
+//! Several translations of one passage, side by side.
+//!
+//! The engine returns the passage in each requested version from
+//! `/v1/text/parallel/{reference}?versions=a,b`; `list_available_versions`
+//! lists what can be requested. Version ids are checked before any
+//! request so a typo doesn't turn into an engine error.
+
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use super::engine::EngineError;
+use super::http;
+use super::reference::validate_scripture_reference;
+use super::telemetry::{self, Feature};
+
+/// Event emitted once the versions of a passage have arrived
+const LOADED_EVENT: &str = "parallel-versions-loaded";
+const MIN_VERSION_ID_LEN: usize = 2;
+const MAX_VERSION_ID_LEN: usize = 20;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParallelVersion {
+    pub version_id: String,
+    pub version_name: String,
+    pub text: String,
+    /// BCP 47 tag, e.g. "grc" or "en"
+    #[serde(default)]
+    pub language: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VersionInfo {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub language: String,
+    #[serde(default)]
+    pub year: Option<i32>,
+    /// Greek or Hebrew text rather than a translation
+    #[serde(default)]
+    pub is_original_language: bool,
+    /// Under a license that restricts redistribution
+    #[serde(default)]
+    pub is_licensed: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ParallelVersionsLoaded {
+    pub reference: String,
+    /// Versions the engine returned, in the order asked for
+    pub version_ids: Vec<String>,
+}
+
+/// Version ids are 2 to 20 of `a-z`, `0-9`, `_` and `-`.
+fn is_valid_version_id(id: &str) -> bool {
+    (MIN_VERSION_ID_LEN..=MAX_VERSION_ID_LEN).contains(&id.len())
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_' || b == b'-')
+}
+
+fn check_version_ids(version_ids: &[String]) -> Result<(), EngineError> {
+    if version_ids.is_empty() {
+        return Err(EngineError::InvalidVersions(
+            "no versions selected".to_string(),
+        ));
+    }
+    let invalid: Vec<&str> = version_ids
+        .iter()
+        .map(String::as_str)
+        .filter(|id| !is_valid_version_id(id))
+        .collect();
+    if !invalid.is_empty() {
+        return Err(EngineError::InvalidVersions(invalid.join(", ")));
+    }
+    Ok(())
+}
+
+/// Put the versions in the order they were asked for.
+fn order_versions(versions: &mut [ParallelVersion], version_ids: &[String]) {
+    versions.sort_by_key(|v| {
+        version_ids
+            .iter()
+            .position(|id| *id == v.version_id)
+            .unwrap_or(version_ids.len())
+    });
+}
+
+/// A passage in several versions, e.g. "John 1:1" in "sblgnt" and "kjv".
+#[tauri::command]
+pub async fn get_parallel_versions(
+    app: AppHandle,
+    port: u16,
+    reference: String,
+    version_ids: Vec<String>,
+) -> Result<Vec<ParallelVersion>, EngineError> {
+    telemetry::record_feature(Feature::ParallelVersions);
+    check_version_ids(&version_ids)?;
+    let validation = validate_scripture_reference(reference);
+    let Some(normalized) = validation.normalized.filter(|_| validation.valid) else {
+        return Err(EngineError::InvalidReference(
+            validation.error.unwrap_or_default(),
+        ));
+    };
+
+    let path = format!("/v1/text/parallel/{}", normalized);
+    let request =
+        http::request(Method::GET, port, &path)?.query(&[("versions", version_ids.join(","))]);
+    let mut versions: Vec<ParallelVersion> = http::send_json(request).await?;
+    order_versions(&mut versions, &version_ids);
+
+    let _ = app.emit(
+        LOADED_EVENT,
+        ParallelVersionsLoaded {
+            reference: normalized,
+            version_ids: versions.iter().map(|v| v.version_id.clone()).collect(),
+        },
+    );
+    Ok(versions)
+}
+
+/// The versions the engine can return passages in.
+#[tauri::command]
+pub async fn list_available_versions(port: u16) -> Result<Vec<VersionInfo>, EngineError> {
+    Ok(http::send_json(http::request(Method::GET, port, "/v1/text/versions")?).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_version_ids() {
+        let ids = |ids: &[&str]| ids.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert!(check_version_ids(&ids(&["sblgnt", "kjv", "na28_apparatus"])).is_ok());
+        assert!(matches!(
+            check_version_ids(&[]),
+            Err(EngineError::InvalidVersions(_))
+        ));
+        assert!(matches!(
+            check_version_ids(&ids(&["kjv", "KJV", "x", "a-very-long-version-id"])),
+            Err(EngineError::InvalidVersions(detail))
+                if detail == "KJV, x, a-very-long-version-id"
+        ));
+    }
+
+    #[test]
+    fn test_order_versions() {
+        let version = |id: &str| ParallelVersion {
+            version_id: id.to_string(),
+            version_name: id.to_uppercase(),
+            text: String::new(),
+            language: "en".to_string(),
+        };
+        let mut versions = vec![version("web"), version("other"), version("kjv")];
+        order_versions(&mut versions, &["kjv".to_string(), "web".to_string()]);
+        let order: Vec<&str> = versions.iter().map(|v| v.version_id.as_str()).collect();
+        assert_eq!(order, ["kjv", "web", "other"]);
+    }
+}
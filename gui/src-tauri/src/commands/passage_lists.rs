@@ -0,0 +1,354 @@
+//! Named passage lists, seeded from CSV files.
+//!
+//! `import_passage_list` only reads and validates: each row's reference is
+//! normalized with `parse_reference_list`, and bad or repeated rows are reported
+//! with their line numbers. Nothing is stored until the caller passes the
+//! items to `save_passage_list` (or straight to `create_batch_job`).
+//!
+//! The parser accepts what spreadsheet apps write: a UTF-8 BOM, `,` `;` or
+//! tab delimiters, quoted fields with doubled quotes and embedded newlines.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use tauri::AppHandle;
+
+use super::errors::AppError;
+use super::reference::parse_reference_list;
+use super::storage;
+
+const PASSAGE_LISTS_FILE: &str = "passage_lists.json";
+/// Spreadsheets of references are small; anything bigger is the wrong file
+const MAX_IMPORT_BYTES: u64 = 5 * 1024 * 1024;
+/// Delimiters tried when none is given, in order of preference on ties
+const DELIMITERS: [char; 3] = [',', ';', '\t'];
+
+/// A column by 0-based index or by header name (case-insensitive).
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum ColumnRef {
+    Index(usize),
+    Name(String),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ColumnMapping {
+    pub reference: ColumnRef,
+    #[serde(default)]
+    pub label: Option<ColumnRef>,
+    /// Detected from the first line when unset
+    #[serde(default)]
+    pub delimiter: Option<char>,
+    /// Detected when unset: a first row whose reference doesn't parse is a header
+    #[serde(default)]
+    pub has_header: Option<bool>,
+}
+
+impl Default for ColumnMapping {
+    fn default() -> Self {
+        Self {
+            reference: ColumnRef::Index(0),
+            label: Some(ColumnRef::Index(1)),
+            delimiter: None,
+            has_header: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PassageListItem {
+    /// Normalized, e.g. "1 Corinthians 13:4-7"
+    pub reference: String,
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportedRow {
+    /// 1-based line the row starts on
+    pub line: usize,
+    pub item: PassageListItem,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportRowError {
+    pub line: usize,
+    pub input: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateRow {
+    pub line: usize,
+    pub reference: String,
+    /// Line of the row that was kept
+    pub first_line: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PassageListImport {
+    /// Valid rows in file order, without duplicates
+    pub rows: Vec<ImportedRow>,
+    pub errors: Vec<ImportRowError>,
+    pub duplicates: Vec<DuplicateRow>,
+    pub delimiter: char,
+    pub has_header: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PassageList {
+    pub name: String,
+    pub items: Vec<PassageListItem>,
+    pub saved_at_ms: u64,
+}
+
+/// Split CSV text into records of fields, each with its starting line.
+pub fn parse_csv(text: &str, delimiter: char) -> Vec<(usize, Vec<String>)> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let (mut line, mut start_line) = (1, 1);
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' if in_quotes => in_quotes = false,
+            '"' if field.is_empty() => in_quotes = true,
+            '\n' if in_quotes => {
+                field.push('\n');
+                line += 1;
+            }
+            '\r' if in_quotes => field.push('\r'),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' | '\r' => {
+                record.push(std::mem::take(&mut field));
+                records.push((start_line, std::mem::take(&mut record)));
+                line += 1;
+                start_line = line;
+            }
+            c if c == delimiter && !in_quotes => record.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push((start_line, record));
+    }
+    // Blank lines come through as a single empty field
+    records.retain(|(_, r)| r.iter().any(|f| !f.trim().is_empty()));
+    records
+}
+
+/// Most frequent delimiter outside quotes on the first line.
+fn detect_delimiter(text: &str) -> char {
+    let first_line = text.lines().next().unwrap_or_default();
+    let mut in_quotes = false;
+    let mut counts = [0usize; DELIMITERS.len()];
+    for c in first_line.chars() {
+        if c == '"' {
+            in_quotes = !in_quotes;
+        } else if let Some(i) = DELIMITERS.iter().position(|d| *d == c && !in_quotes) {
+            counts[i] += 1;
+        }
+    }
+    (0..DELIMITERS.len())
+        .rev()
+        .max_by_key(|i| counts[*i])
+        .filter(|i| counts[*i] > 0)
+        .map_or(',', |i| DELIMITERS[i])
+}
+
+fn column_index(column: &ColumnRef, header: Option<&[String]>) -> Result<usize, String> {
+    match column {
+        ColumnRef::Index(i) => Ok(*i),
+        ColumnRef::Name(name) => header
+            .ok_or_else(|| format!("Column \"{}\" needs a header row", name))?
+            .iter()
+            .position(|h| h.trim().eq_ignore_ascii_case(name.trim()))
+            .ok_or_else(|| format!("No column named \"{}\"", name)),
+    }
+}
+
+/// Validate the rows of a CSV passage list.
+fn import_csv(text: &str, mapping: &ColumnMapping) -> Result<PassageListImport, String> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let delimiter = mapping.delimiter.unwrap_or_else(|| detect_delimiter(text));
+    let mut records = parse_csv(text, delimiter);
+
+    let has_header = mapping.has_header.unwrap_or_else(|| {
+        matches!(mapping.reference, ColumnRef::Name(_))
+            || records.first().is_some_and(|(_, first)| {
+                let i = column_index(&mapping.reference, None).unwrap_or(0);
+                first
+                    .get(i)
+                    .is_none_or(|r| parse_reference_list(r).is_err())
+            })
+    });
+    let header = (has_header && !records.is_empty()).then(|| records.remove(0).1);
+    let reference_col = column_index(&mapping.reference, header.as_deref())?;
+    let label_col = mapping
+        .label
+        .as_ref()
+        .map(|c| column_index(c, header.as_deref()))
+        .transpose()?;
+
+    let mut import = PassageListImport {
+        rows: Vec::new(),
+        errors: Vec::new(),
+        duplicates: Vec::new(),
+        delimiter,
+        has_header,
+    };
+    let mut first_seen: HashMap<String, usize> = HashMap::new();
+    for (line, record) in records {
+        let input = record.get(reference_col).map_or("", |r| r.trim());
+        let reference = match parse_reference_list(input) {
+            Ok(parsed) => parsed.canonical,
+            Err(e) => {
+                import.errors.push(ImportRowError {
+                    line,
+                    input: input.to_string(),
+                    error: e.to_string(),
+                });
+                continue;
+            }
+        };
+        if let Some(first_line) = first_seen.get(&reference) {
+            import.duplicates.push(DuplicateRow {
+                line,
+                reference,
+                first_line: *first_line,
+            });
+            continue;
+        }
+        first_seen.insert(reference.clone(), line);
+        let label = label_col
+            .and_then(|i| record.get(i))
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty());
+        import.rows.push(ImportedRow {
+            line,
+            item: PassageListItem { reference, label },
+        });
+    }
+    Ok(import)
+}
+
+pub fn load_lists(app: &AppHandle) -> Result<Vec<PassageList>, String> {
+    storage::read_json_or_default(&storage::app_data_file(app, PASSAGE_LISTS_FILE)?)
+}
+
+/// Read and validate a CSV passage list without storing anything.
+///
+/// Defaults to references in the first column and labels in the second.
+#[tauri::command]
+pub fn import_passage_list(
+    path: String,
+    column_mapping: Option<ColumnMapping>,
+) -> Result<PassageListImport, AppError> {
+    let size = fs::metadata(&path)
+        .map_err(|e| format!("{}: {}", path, e))?
+        .len();
+    if size > MAX_IMPORT_BYTES {
+        return Err(AppError::InvalidInput(format!(
+            "{} is too large for a passage list",
+            path
+        )));
+    }
+    let bytes = fs::read(&path).map_err(|e| format!("{}: {}", path, e))?;
+    let text = String::from_utf8(bytes).map_err(|_| format!("{} is not UTF-8 text", path))?;
+    Ok(import_csv(&text, &column_mapping.unwrap_or_default())?)
+}
+
+/// Save a passage list under `name`, replacing a list with the same name.
+#[tauri::command]
+pub fn save_passage_list(
+    app: AppHandle,
+    name: String,
+    items: Vec<PassageListItem>,
+) -> Result<PassageList, AppError> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(AppError::InvalidInput(
+            "A passage list needs a name".to_string(),
+        ));
+    }
+    let list = PassageList {
+        name,
+        items,
+        saved_at_ms: storage::now_ms(),
+    };
+    let mut lists = load_lists(&app)?;
+    lists.retain(|l| l.name != list.name);
+    lists.push(list.clone());
+    lists.sort_by_key(|l| l.name.to_lowercase());
+    storage::write_json(&storage::app_data_file(&app, PASSAGE_LISTS_FILE)?, &lists)?;
+    Ok(list)
+}
+
+/// Saved passage lists, by name.
+#[tauri::command]
+pub fn list_passage_lists(app: AppHandle) -> Result<Vec<PassageList>, AppError> {
+    Ok(load_lists(&app)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_csv_quirks() {
+        let records = parse_csv("a;\"b;\"\"c\"\"\"\r\n\r\n\"multi\nline\";d\r\ne", ';');
+        assert_eq!(records.len(), 3);
+        assert_eq!(
+            records[0],
+            (1, vec!["a".to_string(), "b;\"c\"".to_string()])
+        );
+        assert_eq!(records[1].0, 3);
+        assert_eq!(records[1].1[0], "multi\nline");
+        assert_eq!(records[2], (5, vec!["e".to_string()]));
+
+        assert_eq!(detect_delimiter("Reference;Label\n"), ';');
+        assert_eq!(detect_delimiter("\"John 3:16, 17\"\tWeek 1"), '\t');
+        assert_eq!(detect_delimiter("John 3:16"), ',');
+    }
+
+    #[test]
+    fn test_import_csv() {
+        let text = "\u{feff}Reference;Label\n\
+                    John 3:16;Week 1\n\
+                    Hezekiah 1:1;Week 2\n\
+                    jn 3.16;Week 3\n\
+                    \"1 Cor 13:4-7\";\n";
+        let import = import_csv(text, &ColumnMapping::default()).unwrap();
+        assert!(import.has_header);
+        assert_eq!(import.delimiter, ';');
+        let items: Vec<_> = import.rows.iter().map(|r| &r.item).collect();
+        assert_eq!(
+            items,
+            [
+                &PassageListItem {
+                    reference: "John 3:16".to_string(),
+                    label: Some("Week 1".to_string()),
+                },
+                &PassageListItem {
+                    reference: "1 Corinthians 13:4-7".to_string(),
+                    label: None,
+                },
+            ]
+        );
+        assert_eq!(import.errors[0].line, 3);
+        assert_eq!(import.duplicates[0].line, 4);
+        assert_eq!(import.duplicates[0].first_line, 2);
+
+        let mapping = ColumnMapping {
+            reference: ColumnRef::Name("label".to_string()),
+            ..ColumnMapping::default()
+        };
+        assert!(import_csv("John 3:16,x\n", &mapping).is_err());
+    }
+}
@@ -0,0 +1,634 @@
+//! Typed translate command and the local cache of translated passages.
+//!
+//! Successful translations are stored as `passages/{id}.json` under the app
+//! data dir. The id is derived from the normalized reference, mode and
+//! translator, so re-translating a passage with the same settings replaces
+//! its entry. Sharing and export read from this cache rather than asking the
+//! frontend to hand the text back.
+//!
+//! When a re-translation changes the English output, the replaced entry is
+//! kept in `passage_history/{id}/{translated_at_ms}.json` (the last ten per
+//! passage) so runs can be compared.
+//!
+//! Passages longer than the configured verse limit are translated a chapter
+//! (or a run of verses) at a time and stitched back together; the chunks
+//! and the seams between them are recorded under `chunking` in the stored
+//! response.
+
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+use super::engine::EngineError;
+use super::errors::AppError;
+use super::greek_input::normalize_greek;
+use super::http::{self, HttpError};
+use super::local_search;
+use super::openapi::engine_version;
+use super::red_letters::{parse_speaker_spans, SpeakerSpan};
+use super::reference::{chapter_chunks, parse_reference_list};
+use super::settings;
+use super::storage::{self, now_ms};
+use super::telemetry::{self, Feature};
+use super::usfm::split_verse_id;
+
+/// Subdirectory of the app data dir holding cached passages
+const PASSAGES_DIR: &str = "passages";
+/// Subdirectory of the app data dir holding replaced translations
+const HISTORY_DIR: &str = "passage_history";
+/// Replaced translations kept per passage
+const MAX_HISTORY_RUNS: usize = 10;
+/// Engine translate endpoint (API routes are not /v1 prefixed)
+const TRANSLATE_PATH: &str = "/translate";
+/// Verses per translate request when the setting is unset
+const DEFAULT_MAX_TRANSLATE_VERSES: usize = 100;
+
+fn default_mode() -> String {
+    "readable".to_string()
+}
+
+fn default_translator() -> String {
+    "literal".to_string()
+}
+
+fn default_session_id() -> String {
+    "gui".to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranslateOptions {
+    #[serde(default = "default_mode")]
+    pub mode: String,
+    #[serde(default = "default_translator")]
+    pub translator: String,
+    #[serde(default = "default_session_id")]
+    pub session_id: String,
+    /// Passed through to the engine unchanged
+    #[serde(default)]
+    pub options: Map<String, Value>,
+}
+
+impl Default for TranslateOptions {
+    fn default() -> Self {
+        Self {
+            mode: default_mode(),
+            translator: default_translator(),
+            session_id: default_session_id(),
+            options: Map::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedVerse {
+    /// Canonical verse id, e.g. "John.3.16"
+    pub verse_id: String,
+    pub greek: String,
+    pub translation: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedPassage {
+    pub id: String,
+    pub reference: String,
+    pub normalized_ref: String,
+    pub mode: String,
+    pub translator: String,
+    pub greek: String,
+    pub translation: String,
+    pub verses: Vec<CachedVerse>,
+    /// Private user note; never shared or exported unless asked for
+    #[serde(default)]
+    pub note: Option<String>,
+    pub translated_at_ms: u64,
+    /// Who speaks where; absent when the engine gave no speaker data
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speakers: Option<Vec<SpeakerSpan>>,
+    /// Engine version that produced the translation, when it reported one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub engine_version: Option<String>,
+    /// Full engine response, for features that need tokens or the ledger
+    pub response: Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CachedPassageSummary {
+    pub id: String,
+    pub reference: String,
+    pub normalized_ref: String,
+    pub mode: String,
+    pub translator: String,
+    pub translated_at_ms: u64,
+    pub has_note: bool,
+}
+
+impl From<CachedPassage> for CachedPassageSummary {
+    fn from(p: CachedPassage) -> Self {
+        Self {
+            has_note: p.note.as_deref().is_some_and(|n| !n.trim().is_empty()),
+            id: p.id,
+            reference: p.reference,
+            normalized_ref: p.normalized_ref,
+            mode: p.mode,
+            translator: p.translator,
+            translated_at_ms: p.translated_at_ms,
+        }
+    }
+}
+
+/// Result of `translate_passage`: either a cached translation or a gate the
+/// user must acknowledge first (passed through as the engine sent it).
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", content = "result", rename_all = "snake_case")]
+pub enum TranslateOutcome {
+    Translation(Box<CachedPassage>),
+    Gate(Value),
+}
+
+#[derive(Debug, Deserialize)]
+struct EngineVerseBlock {
+    verse_id: String,
+    #[serde(default)]
+    sblgnt_text: String,
+    #[serde(default)]
+    translation_text: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct EngineTranslation {
+    reference: String,
+    #[serde(default)]
+    normalized_ref: String,
+    #[serde(default)]
+    sblgnt_text: String,
+    #[serde(default)]
+    translation_text: String,
+    #[serde(default)]
+    verse_blocks: Vec<EngineVerseBlock>,
+}
+
+/// Stable id for a passage translated with the given settings.
+pub fn passage_id(normalized_ref: &str, mode: &str, translator: &str) -> String {
+    let digest = Sha256::digest(format!("{}|{}|{}", normalized_ref, mode, translator));
+    hex::encode(&digest[..8])
+}
+
+fn passage_path(app: &AppHandle, id: &str) -> Result<PathBuf, String> {
+    // Ids are hex digests; anything else could escape the cache directory
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("Invalid passage id: {}", id));
+    }
+    Ok(storage::app_data_subdir(app, PASSAGES_DIR)?.join(format!("{}.json", id)))
+}
+
+/// Load a cached passage by id.
+pub fn load_passage(app: &AppHandle, id: &str) -> Result<CachedPassage, String> {
+    let path = passage_path(app, id)?;
+    if !path.exists() {
+        return Err(format!("No cached translation for passage {}", id));
+    }
+    storage::read_json(&path)
+}
+
+/// Store a passage in the cache.
+pub fn save_passage(app: &AppHandle, passage: &CachedPassage) -> Result<(), String> {
+    storage::write_json(&passage_path(app, &passage.id)?, passage)
+}
+
+fn history_dir(app: &AppHandle, id: &str) -> Result<PathBuf, String> {
+    // Validates the id the same way as the cache itself
+    passage_path(app, id)?;
+    storage::app_data_subdir(app, &format!("{}/{}", HISTORY_DIR, id))
+}
+
+/// Keep a replaced translation, dropping the oldest beyond the cap.
+pub fn archive_passage(app: &AppHandle, passage: &CachedPassage) -> Result<(), String> {
+    let dir = history_dir(app, &passage.id)?;
+    storage::write_json(
+        &dir.join(format!("{}.json", passage.translated_at_ms)),
+        passage,
+    )?;
+    for old in load_passage_history(app, &passage.id)?
+        .iter()
+        .skip(MAX_HISTORY_RUNS)
+    {
+        let _ = fs::remove_file(dir.join(format!("{}.json", old.translated_at_ms)));
+    }
+    Ok(())
+}
+
+/// Earlier translations of a cached passage, newest first.
+pub fn load_passage_history(app: &AppHandle, id: &str) -> Result<Vec<CachedPassage>, String> {
+    let dir = history_dir(app, id)?;
+    let entries = fs::read_dir(&dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    let mut runs: Vec<CachedPassage> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|entry| storage::read_json(&entry.path()).ok())
+        .collect();
+    runs.sort_by_key(|p| std::cmp::Reverse(p.translated_at_ms));
+    Ok(runs)
+}
+
+/// Ids of every cached passage, sorted, without reading the entries.
+pub fn cached_passage_ids(app: &AppHandle) -> Result<Vec<String>, String> {
+    let dir = storage::app_data_subdir(app, PASSAGES_DIR)?;
+    let entries = fs::read_dir(&dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    let mut ids: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension()? != "json" {
+                return None;
+            }
+            Some(path.file_stem()?.to_str()?.to_string())
+        })
+        .collect();
+    ids.sort();
+    Ok(ids)
+}
+
+/// Load every cached passage, skipping unreadable entries.
+pub fn load_all_passages(app: &AppHandle) -> Result<Vec<CachedPassage>, String> {
+    let dir = storage::app_data_subdir(app, PASSAGES_DIR)?;
+    let entries = fs::read_dir(&dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+
+    let mut passages: Vec<CachedPassage> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|entry| storage::read_json(&entry.path()).ok())
+        .collect();
+    passages.sort_by_key(|p| std::cmp::Reverse(p.translated_at_ms));
+    Ok(passages)
+}
+
+/// Translate a passage through the engine and cache the result.
+///
+/// The reference is typed or pasted, so it is cleaned up first.
+#[tauri::command]
+pub async fn translate_passage(
+    app: AppHandle,
+    port: u16,
+    reference: String,
+    options: Option<TranslateOptions>,
+) -> Result<TranslateOutcome, EngineError> {
+    telemetry::record_feature(Feature::Translation);
+    let reference = normalize_greek(&reference).text;
+    translate_and_cache(&app, port, &reference, options.unwrap_or_default()).await
+}
+
+/// Verses the engine is asked to translate at once.
+pub fn max_translate_verses(app: &AppHandle) -> usize {
+    settings::load_settings(app)
+        .ok()
+        .and_then(|s| s.max_translate_verses)
+        .unwrap_or(DEFAULT_MAX_TRANSLATE_VERSES)
+        .max(1)
+}
+
+fn is_gate(response: &Value) -> bool {
+    response.get("response_type").and_then(Value::as_str) == Some("gate")
+}
+
+async fn request_translation(
+    port: u16,
+    reference: &str,
+    options: &TranslateOptions,
+) -> Result<Value, EngineError> {
+    let body = serde_json::json!({
+        "reference": reference,
+        "mode": options.mode,
+        "session_id": options.session_id,
+        "translator": options.translator,
+        "options": options.options,
+    });
+    Ok(http::send_json(http::request(Method::POST, port, TRANSLATE_PATH)?.json(&body)).await?)
+}
+
+/// Where two chunks of a stitched translation meet.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct Seam {
+    /// Last verse of the earlier chunk
+    after_verse: String,
+    /// First verse of the later chunk
+    before_verse: String,
+    /// Speaker whose quotation ran across the seam and was joined up
+    joined_speaker: Option<String>,
+}
+
+fn first_and_last_verse(response: &Value) -> Option<(String, String)> {
+    let blocks = response.get("verse_blocks")?.as_array()?;
+    let id = |block: &Value| Some(block.get("verse_id")?.as_str()?.to_string());
+    Some((id(blocks.first()?)?, id(blocks.last()?)?))
+}
+
+/// Join speaker spans that a seam cut in two: the same speaker up to the
+/// last verse before the seam and from the first verse after it, in one
+/// chapter (spans never cross chapters).
+fn join_spans_at_seams(spans: &mut Vec<SpeakerSpan>, seams: &mut [Seam]) {
+    for seam in seams {
+        let same_chapter = match (
+            split_verse_id(&seam.after_verse),
+            split_verse_id(&seam.before_verse),
+        ) {
+            (Some(a), Some(b)) => (a.0, a.1) == (b.0, b.1),
+            _ => false,
+        };
+        if !same_chapter {
+            continue;
+        }
+        let Some(before) = spans.iter().position(|s| s.end_verse == seam.after_verse) else {
+            continue;
+        };
+        let Some(after) = spans
+            .iter()
+            .position(|s| s.start_verse == seam.before_verse && s.speaker == spans[before].speaker)
+        else {
+            continue;
+        };
+        let after_span = spans.remove(after);
+        let before = if after < before { before - 1 } else { before };
+        spans[before].end_verse = after_span.end_verse;
+        seam.joined_speaker = Some(after_span.speaker);
+    }
+}
+
+/// One translate response from the responses for each chunk, in order:
+/// texts joined, verse blocks and speaker spans concatenated (verses
+/// translated twice are kept once) and the chunks and seams recorded.
+fn stitch_responses(
+    reference: &str,
+    normalized_ref: &str,
+    chunks: &[String],
+    responses: Vec<Value>,
+) -> (Value, Option<Vec<SpeakerSpan>>) {
+    let mut seams = Vec::new();
+    let mut chunk_info = Vec::new();
+    let mut previous_last: Option<String> = None;
+    for (chunk, response) in chunks.iter().zip(&responses) {
+        let ends = first_and_last_verse(response);
+        if let (Some(after_verse), Some((first, _))) = (previous_last.take(), ends.as_ref()) {
+            seams.push(Seam {
+                after_verse,
+                before_verse: first.clone(),
+                joined_speaker: None,
+            });
+        }
+        previous_last = ends.as_ref().map(|(_, last)| last.clone());
+        chunk_info.push(serde_json::json!({
+            "reference": chunk,
+            "first_verse": ends.as_ref().map(|(first, _)| first),
+            "last_verse": ends.as_ref().map(|(_, last)| last),
+        }));
+    }
+
+    let text = |key: &str| {
+        responses
+            .iter()
+            .filter_map(|r| r.get(key).and_then(Value::as_str))
+            .filter(|t| !t.trim().is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let mut seen = HashSet::new();
+    let verse_blocks: Vec<Value> = responses
+        .iter()
+        .filter_map(|r| r.get("verse_blocks").and_then(Value::as_array))
+        .flatten()
+        .filter(|b| {
+            b.get("verse_id")
+                .and_then(Value::as_str)
+                .is_none_or(|id| seen.insert(id.to_string()))
+        })
+        .cloned()
+        .collect();
+    let speaker_spans: Vec<Value> = responses
+        .iter()
+        .filter_map(|r| r.get("speaker_spans").and_then(Value::as_array))
+        .flatten()
+        .cloned()
+        .collect();
+
+    // Everything else (ledger, session data) as the first chunk sent it
+    let mut stitched = responses.first().cloned().unwrap_or(Value::Null);
+    if let Value::Object(map) = &mut stitched {
+        map.insert("reference".into(), reference.into());
+        map.insert("normalized_ref".into(), normalized_ref.into());
+        map.insert("sblgnt_text".into(), text("sblgnt_text").into());
+        map.insert("translation_text".into(), text("translation_text").into());
+        map.insert("verse_blocks".into(), verse_blocks.into());
+        map.insert("speaker_spans".into(), speaker_spans.into());
+    }
+    let mut speakers = parse_speaker_spans(&stitched);
+    if let Some(spans) = speakers.as_mut() {
+        join_spans_at_seams(spans, &mut seams);
+    }
+    if let Value::Object(map) = &mut stitched {
+        map.insert(
+            "chunking".into(),
+            serde_json::json!({ "chunks": chunk_info, "seams": seams }),
+        );
+    }
+    (stitched, speakers)
+}
+
+/// Shared body of `translate_passage`, also used by exports that need data
+/// the cached translation lacks.
+///
+/// A passage of more than `max_translate_verses` verses is translated in
+/// chunks; a gate on any chunk is returned as is.
+pub async fn translate_and_cache(
+    app: &AppHandle,
+    port: u16,
+    reference: &str,
+    options: TranslateOptions,
+) -> Result<TranslateOutcome, EngineError> {
+    // References the parser rejects go to the engine as typed, which
+    // reports the error
+    let limit = max_translate_verses(app);
+    let parsed = parse_reference_list(reference)
+        .ok()
+        .filter(|p| p.verses.len() > limit);
+    let (response, speakers) = match parsed {
+        Some(parsed) => {
+            let chunks = chapter_chunks(&parsed, limit);
+            let mut responses = Vec::new();
+            for chunk in &chunks {
+                let response = request_translation(port, chunk, &options).await?;
+                if is_gate(&response) {
+                    return Ok(TranslateOutcome::Gate(response));
+                }
+                responses.push(response);
+            }
+            stitch_responses(reference, &parsed.canonical, &chunks, responses)
+        }
+        None => {
+            let response = request_translation(port, reference, &options).await?;
+            if is_gate(&response) {
+                return Ok(TranslateOutcome::Gate(response));
+            }
+            let speakers = parse_speaker_spans(&response);
+            (response, speakers)
+        }
+    };
+
+    let parsed: EngineTranslation =
+        serde_json::from_value(response.clone()).map_err(|e| HttpError::Decode(e.to_string()))?;
+    let normalized_ref = if parsed.normalized_ref.is_empty() {
+        parsed.reference.clone()
+    } else {
+        parsed.normalized_ref
+    };
+    let id = passage_id(&normalized_ref, &options.mode, &options.translator);
+    let options_hash = local_search::options_hash(&options);
+
+    // Keep the user's note when a passage is re-translated
+    let previous = load_passage(app, &id).ok();
+    let note = previous.as_ref().and_then(|p| p.note.clone());
+
+    let passage = CachedPassage {
+        id,
+        reference: parsed.reference,
+        normalized_ref,
+        mode: options.mode,
+        translator: options.translator,
+        greek: parsed.sblgnt_text,
+        translation: parsed.translation_text,
+        verses: parsed
+            .verse_blocks
+            .into_iter()
+            .map(|b| CachedVerse {
+                verse_id: b.verse_id,
+                greek: b.sblgnt_text,
+                translation: b.translation_text,
+            })
+            .collect(),
+        note,
+        translated_at_ms: now_ms(),
+        speakers,
+        engine_version: engine_version(port).await,
+        response,
+    };
+    if let Some(previous) = previous.filter(|p| p.translation != passage.translation) {
+        if let Err(e) = archive_passage(app, &previous) {
+            tracing::warn!(
+                "Could not keep the previous translation of {}: {}",
+                previous.id,
+                e
+            );
+        }
+    }
+    save_passage(app, &passage).map_err(EngineError::Storage)?;
+    if let Err(e) = local_search::index_passage(app, &passage, Some(&options_hash)) {
+        tracing::warn!("Could not index {} for search: {}", passage.id, e);
+    }
+
+    Ok(TranslateOutcome::Translation(Box::new(passage)))
+}
+
+/// Get a cached passage by id.
+#[tauri::command]
+pub fn get_cached_passage(app: AppHandle, passage_id: String) -> Result<CachedPassage, AppError> {
+    Ok(load_passage(&app, &passage_id)?)
+}
+
+/// List cached passages, most recently translated first.
+#[tauri::command]
+pub fn list_cached_passages(app: AppHandle) -> Result<Vec<CachedPassageSummary>, AppError> {
+    Ok(load_all_passages(&app)?
+        .into_iter()
+        .map(CachedPassageSummary::from)
+        .collect())
+}
+
+/// Set or clear the private note on a cached passage.
+#[tauri::command]
+pub fn set_passage_note(
+    app: AppHandle,
+    passage_id: String,
+    note: Option<String>,
+) -> Result<(), AppError> {
+    let mut passage = load_passage(&app, &passage_id)?;
+    passage.note = note.filter(|n| !n.trim().is_empty());
+    save_passage(&app, &passage)?;
+    if let Err(e) = local_search::index_passage_note(&app, &passage) {
+        tracing::warn!(
+            "Could not index the note on {} for search: {}",
+            passage.id,
+            e
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_passage_id_is_stable_and_setting_specific() {
+        let a = passage_id("John 3:16", "readable", "literal");
+        assert_eq!(a, passage_id("John 3:16", "readable", "literal"));
+        assert_eq!(a.len(), 16);
+        assert_ne!(a, passage_id("John 3:16", "traceable", "literal"));
+        assert_ne!(a, passage_id("John 3:17", "readable", "literal"));
+    }
+
+    #[test]
+    fn test_stitch_responses() {
+        let chunk = |first: u32, last: u32| {
+            let blocks: Vec<Value> = (first..=last)
+                .map(|v| {
+                    serde_json::json!({
+                        "verse_id": format!("John.4.{}", v),
+                        "sblgnt_text": format!("g{}", v),
+                        "translation_text": format!("e{}", v),
+                    })
+                })
+                .collect();
+            serde_json::json!({
+                "reference": format!("John 4:{}-{}", first, last),
+                "sblgnt_text": format!("g{}-{}", first, last),
+                "translation_text": format!("e{}-{}", first, last),
+                "verse_blocks": blocks,
+                "speaker_spans": [{
+                    "book": "John", "chapter": 4,
+                    "verse_start": first.max(2), "verse_end": last,
+                    "speaker": "Jesus",
+                }],
+            })
+        };
+        let chunks = ["John 4:1-3".to_string(), "John 4:4-5".to_string()];
+        let (response, speakers) = stitch_responses(
+            "John 4:1-5",
+            "John 4:1-5",
+            &chunks,
+            vec![chunk(1, 3), chunk(4, 5)],
+        );
+        assert_eq!(response["translation_text"], "e1-3 e4-5");
+        let ids: Vec<&str> = response["verse_blocks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|b| b["verse_id"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            ids,
+            ["John.4.1", "John.4.2", "John.4.3", "John.4.4", "John.4.5"]
+        );
+        // The quotation cut by the seam is one span again
+        let speakers = speakers.unwrap();
+        assert_eq!(speakers.len(), 1);
+        assert_eq!(speakers[0].start_verse, "John.4.2");
+        assert_eq!(speakers[0].end_verse, "John.4.5");
+        let seam = &response["chunking"]["seams"][0];
+        assert_eq!(seam["after_verse"], "John.4.3");
+        assert_eq!(seam["joined_speaker"], "Jesus");
+        assert_eq!(response["chunking"]["chunks"][1]["first_verse"], "John.4.4");
+    }
+}
@@ -0,0 +1,719 @@
+//! PDF output for exports that need a printable file.
+//!
+//! Text is laid out here rather than by a browser: words are measured
+//! with the glyph advances of the bundled DejaVu Serif and broken greedily
+//! into lines on A4 pages. Both faces are embedded whole as CID fonts with
+//! a ToUnicode map, so Greek renders (and copies out) on machines without
+//! a Greek font. Sections get a bookmark and can be linked from a contents
+//! list; pages are numbered at the foot.
+
+use miniz_oxide::deflate::compress_to_vec_zlib;
+use pdf_writer::types::{
+    ActionType, AnnotationType, CidFontType, FontFlags, PageMode, SystemInfo, UnicodeCmap,
+};
+use pdf_writer::{Content, Filter, Finish, Name, Pdf, Rect, Ref, Str, TextStr};
+use std::collections::BTreeMap;
+use tauri::path::BaseDirectory;
+use tauri::{AppHandle, Manager};
+use ttf_parser::{Face, GlyphId};
+use unicode_normalization::UnicodeNormalization;
+
+/// Regular and bold faces, as bundled resources
+pub const FONT_RESOURCES: [&str; 2] = ["fonts/DejaVuSerif.ttf", "fonts/DejaVuSerif-Bold.ttf"];
+const FONT_NAMES: [&[u8]; 2] = [b"DejaVuSerif", b"DejaVuSerif-Bold"];
+/// Names of the faces in each page's resources
+const FONT_KEYS: [&[u8]; 2] = [b"F1", b"F2"];
+
+/// A4, in points
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 56.0;
+const TEXT_WIDTH: f32 = PAGE_WIDTH - 2.0 * MARGIN;
+const BODY_SIZE: f32 = 11.0;
+const FOOTER_SIZE: f32 = 9.0;
+const LINE_SPACING: f32 = 1.4;
+/// Room kept for the page number of a contents entry
+const PAGE_NUMBER_WIDTH: f32 = 40.0;
+const CELL_PADDING: f32 = 4.0;
+/// The dark red of the other exports
+const RED: (f32, f32, f32) = (0.75, 0.0, 0.0);
+const RULE_GRAY: f32 = 0.8;
+
+/// How a run of text is set.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TextStyle {
+    pub bold: bool,
+    pub red: bool,
+    /// Smaller and raised, for verse numbers
+    pub superscript: bool,
+}
+
+/// Text in one style.
+#[derive(Debug, Clone, Copy)]
+pub struct Span<'s> {
+    pub text: &'s str,
+    pub style: TextStyle,
+}
+
+impl<'s> Span<'s> {
+    pub fn plain(text: &'s str) -> Self {
+        Self::styled(text, TextStyle::default())
+    }
+
+    pub fn styled(text: &'s str, style: TextStyle) -> Self {
+        Self { text, style }
+    }
+}
+
+/// The regular and bold font files a PDF is set in.
+pub struct PdfFonts {
+    regular: Vec<u8>,
+    bold: Vec<u8>,
+}
+
+impl PdfFonts {
+    pub fn new(regular: Vec<u8>, bold: Vec<u8>) -> Self {
+        Self { regular, bold }
+    }
+
+    /// The faces bundled with the app.
+    pub fn bundled(app: &AppHandle) -> Result<Self, String> {
+        let read = |resource: &str| {
+            let path = app
+                .path()
+                .resolve(resource, BaseDirectory::Resource)
+                .map_err(|e| e.to_string())?;
+            std::fs::read(&path).map_err(|e| format!("{}: {}", path.display(), e))
+        };
+        Ok(Self::new(
+            read(FONT_RESOURCES[0])?,
+            read(FONT_RESOURCES[1])?,
+        ))
+    }
+}
+
+struct Font<'f> {
+    face: Face<'f>,
+    data: &'f [u8],
+    /// Glyphs drawn so far, with the character each stands for
+    used: BTreeMap<u16, char>,
+}
+
+impl<'f> Font<'f> {
+    fn parse(data: &'f [u8]) -> Result<Self, String> {
+        let face = Face::parse(data, 0).map_err(|e| format!("Invalid PDF font: {}", e))?;
+        Ok(Self {
+            face,
+            data,
+            used: BTreeMap::new(),
+        })
+    }
+
+    /// `value` in font units as thousandths of an em.
+    fn scaled(&self, value: f32) -> f32 {
+        value * 1000.0 / f32::from(self.face.units_per_em())
+    }
+
+    fn glyph(&self, c: char) -> GlyphId {
+        self.face.glyph_index(c).unwrap_or(GlyphId(0))
+    }
+
+    fn advance(&self, glyph: GlyphId) -> f32 {
+        self.scaled(f32::from(self.face.glyph_hor_advance(glyph).unwrap_or(0)))
+    }
+
+    fn width(&self, text: &str, size: f32) -> f32 {
+        let em: f32 = text.chars().map(|c| self.advance(self.glyph(c))).sum();
+        em * size / 1000.0
+    }
+
+    /// Two-byte glyph ids, as Identity-H shows them.
+    fn encode(&mut self, text: &str) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(text.len() * 2);
+        for c in text.chars() {
+            let glyph = self.glyph(c);
+            self.used.entry(glyph.0).or_insert(c);
+            bytes.extend(glyph.0.to_be_bytes());
+        }
+        bytes
+    }
+}
+
+/// A word: its text in one or more styles, and its width.
+struct Word {
+    pieces: Vec<(String, TextStyle)>,
+    width: f32,
+}
+
+/// A bookmarked heading.
+struct Section {
+    title: String,
+    page: usize,
+    top: f32,
+}
+
+/// A PDF being laid out, top to bottom.
+pub struct PdfDocument<'f> {
+    fonts: [Font<'f>; 2],
+    pages: Vec<Content>,
+    /// Per page: link areas and the section each goes to
+    links: Vec<Vec<(Rect, usize)>>,
+    sections: Vec<Section>,
+    /// Top of the next line, up from the bottom of the page
+    y: f32,
+}
+
+fn line_height(size: f32) -> f32 {
+    size * LINE_SPACING
+}
+
+fn size_of(style: TextStyle, size: f32) -> f32 {
+    if style.superscript {
+        size * 0.7
+    } else {
+        size
+    }
+}
+
+impl<'f> PdfDocument<'f> {
+    pub fn new(fonts: &'f PdfFonts) -> Result<Self, String> {
+        let mut doc = Self {
+            fonts: [Font::parse(&fonts.regular)?, Font::parse(&fonts.bold)?],
+            pages: Vec::new(),
+            links: Vec::new(),
+            sections: Vec::new(),
+            y: 0.0,
+        };
+        doc.new_page();
+        Ok(doc)
+    }
+
+    fn new_page(&mut self) {
+        self.pages.push(Content::new());
+        self.links.push(Vec::new());
+        self.y = PAGE_HEIGHT - MARGIN;
+    }
+
+    fn at_page_top(&self) -> bool {
+        self.y >= PAGE_HEIGHT - MARGIN
+    }
+
+    /// Start a new page unless `height` still fits on this one.
+    fn ensure(&mut self, height: f32) {
+        if self.y - height < MARGIN && !self.at_page_top() {
+            self.new_page();
+        }
+    }
+
+    fn font(&self, style: TextStyle) -> &Font<'f> {
+        &self.fonts[usize::from(style.bold)]
+    }
+
+    /// Continue on a new page; nothing happens at the top of a page.
+    pub fn page_break(&mut self) {
+        if !self.at_page_top() {
+            self.new_page();
+        }
+    }
+
+    fn space(&mut self, points: f32) {
+        if !self.at_page_top() {
+            self.y -= points;
+        }
+    }
+
+    fn words(&self, spans: &[Span], size: f32) -> Vec<Word> {
+        let mut words = Vec::new();
+        let mut pieces: Vec<(String, TextStyle)> = Vec::new();
+        for span in spans {
+            for c in span.text.nfc() {
+                if c.is_whitespace() {
+                    if !pieces.is_empty() {
+                        words.push(self.word(std::mem::take(&mut pieces), size));
+                    }
+                    continue;
+                }
+                match pieces.last_mut() {
+                    Some((text, style)) if *style == span.style => text.push(c),
+                    _ => pieces.push((c.to_string(), span.style)),
+                }
+            }
+        }
+        if !pieces.is_empty() {
+            words.push(self.word(pieces, size));
+        }
+        words
+    }
+
+    fn word(&self, pieces: Vec<(String, TextStyle)>, size: f32) -> Word {
+        let width = pieces
+            .iter()
+            .map(|(text, style)| self.font(*style).width(text, size_of(*style, size)))
+            .sum();
+        Word { pieces, width }
+    }
+
+    /// Split a word wider than `width` into pieces that fit.
+    fn split_word(&self, word: Word, width: f32, size: f32) -> Vec<Word> {
+        let mut parts = Vec::new();
+        let mut current: Vec<(String, TextStyle)> = Vec::new();
+        let mut current_width = 0.0;
+        for (text, style) in word.pieces {
+            let font = self.font(style);
+            for c in text.chars() {
+                let advance = font.width(c.encode_utf8(&mut [0; 4]), size_of(style, size));
+                if current_width + advance > width && !current.is_empty() {
+                    parts.push(self.word(std::mem::take(&mut current), size));
+                    current_width = 0.0;
+                }
+                current_width += advance;
+                match current.last_mut() {
+                    Some((text, s)) if *s == style => text.push(c),
+                    _ => current.push((c.to_string(), style)),
+                }
+            }
+        }
+        if !current.is_empty() {
+            parts.push(self.word(current, size));
+        }
+        parts
+    }
+
+    /// Break `spans` into lines at most `width` wide.
+    fn lines(&self, spans: &[Span], size: f32, width: f32) -> Vec<Vec<Word>> {
+        let space = self.fonts[0].width(" ", size);
+        let mut lines = Vec::new();
+        let mut line: Vec<Word> = Vec::new();
+        let mut line_width = 0.0;
+        for word in self.words(spans, size) {
+            let words = if word.width > width {
+                self.split_word(word, width, size)
+            } else {
+                vec![word]
+            };
+            for word in words {
+                let needed = if line.is_empty() {
+                    word.width
+                } else {
+                    line_width + space + word.width
+                };
+                if needed > width && !line.is_empty() {
+                    lines.push(std::mem::take(&mut line));
+                    line_width = word.width;
+                } else {
+                    line_width = needed;
+                }
+                line.push(word);
+            }
+        }
+        if !line.is_empty() {
+            lines.push(line);
+        }
+        lines
+    }
+
+    fn draw_text(&mut self, text: &str, style: TextStyle, x: f32, baseline: f32, size: f32) {
+        let font = usize::from(style.bold);
+        let encoded = self.fonts[font].encode(text);
+        let content = self.pages.last_mut().unwrap();
+        if style.red {
+            content.set_fill_rgb(RED.0, RED.1, RED.2);
+        } else {
+            content.set_fill_gray(0.0);
+        }
+        content.begin_text();
+        content.set_font(Name(FONT_KEYS[font]), size_of(style, size));
+        if style.superscript {
+            content.set_rise(size * 0.35);
+        }
+        content.next_line(x, baseline);
+        content.show(Str(&encoded));
+        if style.superscript {
+            content.set_rise(0.0);
+        }
+        content.end_text();
+    }
+
+    fn draw_line(&mut self, line: &[Word], x: f32, baseline: f32, size: f32) {
+        let space = self.fonts[0].width(" ", size);
+        let mut x = x;
+        for word in line {
+            for (text, style) in &word.pieces {
+                self.draw_text(text, *style, x, baseline, size);
+                x += self.font(*style).width(text, size_of(*style, size));
+            }
+            x += space;
+        }
+    }
+
+    /// Set `spans` as lines of `size` points at `indent` from the margin.
+    fn text_block(&mut self, spans: &[Span], size: f32, indent: f32) {
+        for line in self.lines(spans, size, TEXT_WIDTH - indent) {
+            self.ensure(line_height(size));
+            let baseline = self.y - size;
+            self.draw_line(&line, MARGIN + indent, baseline, size);
+            self.y -= line_height(size);
+        }
+    }
+
+    /// A paragraph of body text, indented by `indent` points.
+    pub fn paragraph(&mut self, spans: &[Span], indent: f32) {
+        self.text_block(spans, BODY_SIZE, indent);
+        self.space(BODY_SIZE * 0.5);
+    }
+
+    /// Make room for a heading, keeping it on the page of the lines that
+    /// follow it; returns its size.
+    fn start_heading(&mut self, level: u8) -> f32 {
+        let size = match level {
+            1 => 20.0,
+            2 => 15.0,
+            _ => 12.5,
+        };
+        self.space(size * 0.8);
+        self.ensure(line_height(size) + 2.0 * line_height(BODY_SIZE));
+        size
+    }
+
+    fn heading_text(&mut self, text: &str, size: f32) {
+        let bold = TextStyle {
+            bold: true,
+            ..TextStyle::default()
+        };
+        self.text_block(&[Span::styled(text, bold)], size, 0.0);
+        self.space(size * 0.3);
+    }
+
+    /// A bold heading: 1 for the title, 2 for a section, 3 below that.
+    pub fn heading(&mut self, text: &str, level: u8) {
+        let size = self.start_heading(level);
+        self.heading_text(text, size);
+    }
+
+    /// A level 2 heading with a bookmark; returns its index for links.
+    pub fn section(&mut self, title: &str) -> usize {
+        let size = self.start_heading(2);
+        self.sections.push(Section {
+            title: title.to_string(),
+            page: self.pages.len() - 1,
+            top: self.y,
+        });
+        self.heading_text(title, size);
+        self.sections.len() - 1
+    }
+
+    /// A line of a contents list: `text` linking to section `target`, with
+    /// its page number when known.
+    pub fn contents_entry(&mut self, text: &str, page_number: Option<usize>, target: usize) {
+        let lines = self.lines(
+            &[Span::plain(text)],
+            BODY_SIZE,
+            TEXT_WIDTH - PAGE_NUMBER_WIDTH,
+        );
+        let height = line_height(BODY_SIZE) * lines.len() as f32;
+        self.ensure(height);
+        let top = self.y;
+        for line in &lines {
+            let baseline = self.y - BODY_SIZE;
+            self.draw_line(line, MARGIN, baseline, BODY_SIZE);
+            self.y -= line_height(BODY_SIZE);
+        }
+        if let Some(number) = page_number {
+            let number = number.to_string();
+            let width = self.fonts[0].width(&number, BODY_SIZE);
+            let x = PAGE_WIDTH - MARGIN - width;
+            self.draw_text(&number, TextStyle::default(), x, top - BODY_SIZE, BODY_SIZE);
+        }
+        let area = Rect::new(MARGIN, self.y, PAGE_WIDTH - MARGIN, top);
+        self.links.last_mut().unwrap().push((area, target));
+    }
+
+    fn rule(&mut self, y: f32) {
+        let content = self.pages.last_mut().unwrap();
+        content.set_stroke_gray(RULE_GRAY);
+        content.set_line_width(0.5);
+        content.move_to(MARGIN, y);
+        content.line_to(PAGE_WIDTH - MARGIN, y);
+        content.stroke();
+    }
+
+    fn table_row(&mut self, cells: &[Vec<Span>], widths: &[f32]) {
+        let cells: Vec<Vec<Vec<Word>>> = cells
+            .iter()
+            .zip(widths)
+            .map(|(cell, fraction)| {
+                self.lines(cell, BODY_SIZE, TEXT_WIDTH * fraction - 2.0 * CELL_PADDING)
+            })
+            .collect();
+        let rows = cells.iter().map(Vec::len).max().unwrap_or(0).max(1);
+        let height = line_height(BODY_SIZE) * rows as f32 + CELL_PADDING;
+        self.ensure(height);
+        let mut x = MARGIN;
+        for (lines, fraction) in cells.iter().zip(widths) {
+            for (i, line) in lines.iter().enumerate() {
+                let baseline = self.y - BODY_SIZE - line_height(BODY_SIZE) * i as f32;
+                self.draw_line(line, x + CELL_PADDING, baseline, BODY_SIZE);
+            }
+            x += TEXT_WIDTH * fraction;
+        }
+        self.y -= height;
+        self.rule(self.y + CELL_PADDING / 2.0);
+    }
+
+    /// A table across the text width; `widths` are the columns' shares of
+    /// it. The bold header row is repeated on every page the table spans.
+    pub fn table(&mut self, header: &[&str], rows: &[Vec<Vec<Span>>], widths: &[f32]) {
+        let bold = TextStyle {
+            bold: true,
+            ..TextStyle::default()
+        };
+        let header: Vec<Vec<Span>> = header
+            .iter()
+            .map(|title| vec![Span::styled(title, bold)])
+            .collect();
+        self.ensure(2.0 * (line_height(BODY_SIZE) + CELL_PADDING));
+        self.table_row(&header, widths);
+        for row in rows {
+            let page = self.pages.len();
+            let height = self.lines_height(row, widths);
+            self.ensure(height);
+            if self.pages.len() != page {
+                self.table_row(&header, widths);
+            }
+            self.table_row(row, widths);
+        }
+        self.space(BODY_SIZE * 0.5);
+    }
+
+    fn lines_height(&self, row: &[Vec<Span>], widths: &[f32]) -> f32 {
+        let rows = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, fraction)| {
+                self.lines(cell, BODY_SIZE, TEXT_WIDTH * fraction - 2.0 * CELL_PADDING)
+                    .len()
+            })
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        line_height(BODY_SIZE) * rows as f32 + CELL_PADDING
+    }
+
+    /// The page number (from 1) each section starts on.
+    pub fn section_pages(&self) -> Vec<usize> {
+        self.sections.iter().map(|s| s.page + 1).collect()
+    }
+
+    /// Number the pages and write the file.
+    pub fn finish(mut self, title: &str) -> Vec<u8> {
+        for (i, content) in self.pages.iter_mut().enumerate() {
+            let number = (i + 1).to_string();
+            let width = self.fonts[0].width(&number, FOOTER_SIZE);
+            let encoded = self.fonts[0].encode(&number);
+            content.set_fill_gray(0.3);
+            content.begin_text();
+            content.set_font(Name(FONT_KEYS[0]), FOOTER_SIZE);
+            content.next_line((PAGE_WIDTH - width) / 2.0, MARGIN / 2.0);
+            content.show(Str(&encoded));
+            content.end_text();
+        }
+
+        let mut alloc = Ref::new(1);
+        let catalog_id = alloc.bump();
+        let page_tree_id = alloc.bump();
+        let outline_id = alloc.bump();
+        let info_id = alloc.bump();
+        let page_ids: Vec<Ref> = self.pages.iter().map(|_| alloc.bump()).collect();
+        let content_ids: Vec<Ref> = self.pages.iter().map(|_| alloc.bump()).collect();
+        let font_ids: Vec<Ref> = self.fonts.iter().map(|_| alloc.bump()).collect();
+        let item_ids: Vec<Ref> = self.sections.iter().map(|_| alloc.bump()).collect();
+
+        let mut pdf = Pdf::new();
+        let mut catalog = pdf.catalog(catalog_id);
+        catalog.pages(page_tree_id);
+        if !self.sections.is_empty() {
+            catalog
+                .outlines(outline_id)
+                .page_mode(PageMode::UseOutlines);
+        }
+        catalog.finish();
+        pdf.document_info(info_id)
+            .title(TextStr(title))
+            .creator(TextStr("Red Letters"));
+        pdf.pages(page_tree_id)
+            .kids(page_ids.iter().copied())
+            .count(page_ids.len() as i32);
+
+        for (i, content) in self.pages.into_iter().enumerate() {
+            let mut page = pdf.page(page_ids[i]);
+            page.media_box(Rect::new(0.0, 0.0, PAGE_WIDTH, PAGE_HEIGHT))
+                .parent(page_tree_id)
+                .contents(content_ids[i]);
+            let mut resources = page.resources();
+            let mut fonts = resources.fonts();
+            for (key, id) in FONT_KEYS.iter().zip(&font_ids) {
+                fonts.pair(Name(key), *id);
+            }
+            fonts.finish();
+            resources.finish();
+            if !self.links[i].is_empty() {
+                let mut annotations = page.annotations();
+                for (area, target) in &self.links[i] {
+                    let section = &self.sections[*target];
+                    let mut link = annotations.push();
+                    link.subtype(AnnotationType::Link)
+                        .rect(*area)
+                        .border(0.0, 0.0, 0.0, None);
+                    link.action()
+                        .action_type(ActionType::GoTo)
+                        .destination()
+                        .page(page_ids[section.page])
+                        .xyz(0.0, section.top, None);
+                }
+            }
+            page.finish();
+            let data = compress_to_vec_zlib(&content.finish(), 6);
+            pdf.stream(content_ids[i], &data)
+                .filter(Filter::FlateDecode);
+        }
+
+        for (i, font) in self.fonts.iter().enumerate() {
+            write_font(&mut pdf, &mut alloc, font_ids[i], FONT_NAMES[i], font);
+        }
+
+        if let (Some(first), Some(last)) = (item_ids.first(), item_ids.last()) {
+            pdf.outline(outline_id)
+                .first(*first)
+                .last(*last)
+                .count(item_ids.len() as i32);
+            for (i, section) in self.sections.iter().enumerate() {
+                let mut item = pdf.outline_item(item_ids[i]);
+                item.title(TextStr(&section.title)).parent(outline_id);
+                if i > 0 {
+                    item.prev(item_ids[i - 1]);
+                }
+                if let Some(next) = item_ids.get(i + 1) {
+                    item.next(*next);
+                }
+                item.dest()
+                    .page(page_ids[section.page])
+                    .xyz(0.0, section.top, None);
+            }
+        }
+        pdf.finish()
+    }
+}
+
+/// Embed `font` whole as a Type 0 font over glyph ids.
+fn write_font(pdf: &mut Pdf, alloc: &mut Ref, id: Ref, name: &[u8], font: &Font) {
+    let cid_id = alloc.bump();
+    let descriptor_id = alloc.bump();
+    let cmap_id = alloc.bump();
+    let file_id = alloc.bump();
+    let system_info = SystemInfo {
+        registry: Str(b"Adobe"),
+        ordering: Str(b"Identity"),
+        supplement: 0,
+    };
+
+    pdf.type0_font(id)
+        .base_font(Name(name))
+        .encoding_predefined(Name(b"Identity-H"))
+        .descendant_font(cid_id)
+        .to_unicode(cmap_id);
+
+    let mut cid = pdf.cid_font(cid_id);
+    cid.subtype(CidFontType::Type2)
+        .base_font(Name(name))
+        .system_info(system_info)
+        .font_descriptor(descriptor_id)
+        .cid_to_gid_map_predefined(Name(b"Identity"));
+    let mut widths = cid.widths();
+    for glyph in font.used.keys() {
+        widths.consecutive(*glyph, [font.advance(GlyphId(*glyph))]);
+    }
+    widths.finish();
+    cid.finish();
+
+    let face = &font.face;
+    let bbox = face.global_bounding_box();
+    pdf.font_descriptor(descriptor_id)
+        .name(Name(name))
+        .flags(FontFlags::SERIF | FontFlags::SYMBOLIC)
+        .bbox(Rect::new(
+            font.scaled(f32::from(bbox.x_min)),
+            font.scaled(f32::from(bbox.y_min)),
+            font.scaled(f32::from(bbox.x_max)),
+            font.scaled(f32::from(bbox.y_max)),
+        ))
+        .italic_angle(0.0)
+        .ascent(font.scaled(f32::from(face.ascender())))
+        .descent(font.scaled(f32::from(face.descender())))
+        .cap_height(font.scaled(f32::from(face.capital_height().unwrap_or(face.ascender()))))
+        .stem_v(80.0)
+        .font_file2(file_id);
+
+    let mut cmap = UnicodeCmap::new(Name(b"Custom"), system_info);
+    for (glyph, c) in font.used.iter().filter(|(glyph, _)| **glyph != 0) {
+        cmap.pair(*glyph, *c);
+    }
+    pdf.cmap(cmap_id, &cmap.finish());
+
+    let data = compress_to_vec_zlib(font.data, 6);
+    pdf.stream(file_id, &data)
+        .filter(Filter::FlateDecode)
+        .pair(Name(b"Length1"), font.data.len() as i32);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testsupport::pdf_fonts as fonts;
+
+    #[test]
+    fn test_lines_wrap_within_width() {
+        let fonts = fonts();
+        let doc = PdfDocument::new(&fonts).unwrap();
+        let text = "Ἐν ἀρχῇ ἦν ὁ λόγος, καὶ ὁ λόγος ἦν πρὸς τὸν θεόν, καὶ θεὸς ἦν ὁ λόγος.";
+        let lines = doc.lines(&[Span::plain(text)], BODY_SIZE, 150.0);
+        assert!(lines.len() > 2);
+        let space = doc.fonts[0].width(" ", BODY_SIZE);
+        for line in &lines {
+            let width: f32 =
+                line.iter().map(|w| w.width).sum::<f32>() + space * (line.len() - 1) as f32;
+            assert!(width <= 150.0, "{}", width);
+        }
+        let words: usize = lines.iter().map(Vec::len).sum();
+        assert_eq!(words, text.split_whitespace().count());
+
+        let long = doc.lines(&[Span::plain(&"λ".repeat(100))], BODY_SIZE, 100.0);
+        assert!(long.len() > 1);
+        assert!(long.iter().all(|line| line[0].width <= 100.0));
+    }
+
+    #[test]
+    fn test_sections_and_pages() {
+        let fonts = fonts();
+        let mut doc = PdfDocument::new(&fonts).unwrap();
+        doc.heading("Study notes", 1);
+        doc.contents_entry("John 1:1-5", Some(2), 0);
+        doc.page_break();
+        doc.page_break();
+        assert_eq!(doc.section("John 1:1-5"), 0);
+        for _ in 0..80 {
+            doc.paragraph(&[Span::plain("In the beginning was the Word")], 0.0);
+        }
+        assert_eq!(doc.section("John 3:16"), 1);
+        assert_eq!(doc.section_pages()[0], 2);
+        assert!(doc.section_pages()[1] > 2);
+
+        let bytes = doc.finish("Study notes");
+        let text = String::from_utf8_lossy(&bytes);
+        assert!(bytes.starts_with(b"%PDF-"));
+        assert!(text.contains("/Identity-H"));
+        assert!(text.contains("/FontFile2"));
+        assert!(text.contains("/S /GoTo"));
+        assert!(text.contains("/Outlines"));
+        assert!(text.trim_end().ends_with("%%EOF"));
+    }
+}
@@ -0,0 +1,301 @@
+//! Presentation mode for projecting passages.
+//!
+//! A dedicated frameless, fullscreen window (label `presentation`) shows the
+//! current passage on a chosen monitor. The backend owns the session so that
+//! navigation in the main window and Escape in the presentation window
+//! (which calls `exit_presentation_mode`) go through the same state.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::{
+    AppHandle, Emitter, EventId, Listener, Manager, Monitor, PhysicalPosition, State, WebviewUrl,
+    WebviewWindow, WebviewWindowBuilder, WindowEvent,
+};
+
+use super::errors::AppError;
+
+/// Label of the presentation window
+const PRESENTATION_LABEL: &str = "presentation";
+/// Label of the main window (tauri.conf.json default)
+const MAIN_LABEL: &str = "main";
+/// Event sent to the presentation window with the passage to show
+const PRESENTATION_UPDATE_EVENT: &str = "presentation-update";
+/// Event the main window emits when the user navigates to another passage
+const PASSAGE_NAVIGATED_EVENT: &str = "passage-navigated";
+
+fn default_font_size() -> u32 {
+    48
+}
+
+#[derive(Debug, Serialize)]
+pub struct MonitorInfo {
+    pub name: Option<String>,
+    pub width: u32,
+    pub height: u32,
+    pub x: i32,
+    pub y: i32,
+    pub scale_factor: f64,
+    pub is_primary: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PresentationOptions {
+    /// Font size in CSS pixels
+    #[serde(default = "default_font_size")]
+    pub font_size: u32,
+    /// Monitor name from `list_monitors`; the primary display is used if absent
+    #[serde(default)]
+    pub monitor: Option<String>,
+    /// Show the Greek text alongside the translation
+    #[serde(default)]
+    pub show_greek: bool,
+}
+
+/// Payload of the `presentation-update` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct PresentationUpdate {
+    pub passage_ref: String,
+    pub options: PresentationOptions,
+}
+
+#[derive(Debug, Deserialize)]
+struct PassageNavigated {
+    passage_ref: String,
+}
+
+struct PresentationSession {
+    passage_ref: String,
+    options: PresentationOptions,
+    navigation_listener: EventId,
+    main_was_fullscreen: bool,
+    main_was_maximized: bool,
+}
+
+/// Active presentation session, if any.
+#[derive(Default)]
+pub struct PresentationState(Mutex<Option<PresentationSession>>);
+
+impl PresentationState {
+    fn current_update(&self) -> Option<PresentationUpdate> {
+        let session = self.0.lock().unwrap();
+        session.as_ref().map(|s| PresentationUpdate {
+            passage_ref: s.passage_ref.clone(),
+            options: s.options.clone(),
+        })
+    }
+}
+
+fn monitor_info(monitor: &Monitor, primary: Option<&Monitor>) -> MonitorInfo {
+    MonitorInfo {
+        name: monitor.name().cloned(),
+        width: monitor.size().width,
+        height: monitor.size().height,
+        x: monitor.position().x,
+        y: monitor.position().y,
+        scale_factor: monitor.scale_factor(),
+        is_primary: primary.is_some_and(|p| p.position() == monitor.position()),
+    }
+}
+
+/// Pick the requested monitor, falling back to the primary display.
+fn resolve_monitor(app: &AppHandle, name: Option<&str>) -> Result<Monitor, String> {
+    let monitors = app.available_monitors().map_err(|e| e.to_string())?;
+    if let Some(name) = name {
+        if let Some(m) = monitors
+            .iter()
+            .find(|m| m.name().map(String::as_str) == Some(name))
+        {
+            return Ok(m.clone());
+        }
+    }
+    app.primary_monitor()
+        .map_err(|e| e.to_string())?
+        .or_else(|| monitors.into_iter().next())
+        .ok_or_else(|| "No monitor available".to_string())
+}
+
+/// Move a window onto a monitor and make it fullscreen there.
+fn place_on_monitor(window: &WebviewWindow, monitor: &Monitor) -> Result<(), String> {
+    // Leave fullscreen first, otherwise some platforms ignore the move
+    window.set_fullscreen(false).map_err(|e| e.to_string())?;
+    window
+        .set_position(PhysicalPosition::new(
+            monitor.position().x,
+            monitor.position().y,
+        ))
+        .map_err(|e| e.to_string())?;
+    window.set_fullscreen(true).map_err(|e| e.to_string())
+}
+
+/// Re-home the presentation window if its monitor was disconnected.
+fn ensure_on_connected_monitor(window: &WebviewWindow) {
+    if let Ok(None) = window.current_monitor() {
+        if let Ok(Some(primary)) = window.primary_monitor() {
+            if let Err(e) = place_on_monitor(window, &primary) {
+                tracing::warn!("Could not move presentation window: {}", e);
+            }
+        }
+    }
+}
+
+fn create_presentation_window(app: &AppHandle) -> Result<WebviewWindow, String> {
+    let window = WebviewWindowBuilder::new(
+        app,
+        PRESENTATION_LABEL,
+        WebviewUrl::App("presentation".into()),
+    )
+    .title("Red Letters Presentation")
+    .decorations(false)
+    .skip_taskbar(true)
+    .build()
+    .map_err(|e| e.to_string())?;
+
+    let handle = window.clone();
+    window.on_window_event(move |event| match event {
+        WindowEvent::Moved(_) | WindowEvent::ScaleFactorChanged { .. } => {
+            ensure_on_connected_monitor(&handle);
+        }
+        WindowEvent::Destroyed => {
+            // Closed by the OS rather than exit_presentation_mode: drop the session
+            let app = handle.app_handle();
+            let session = app.state::<PresentationState>().0.lock().unwrap().take();
+            if let Some(session) = session {
+                app.unlisten(session.navigation_listener);
+            }
+        }
+        _ => {}
+    });
+
+    Ok(window)
+}
+
+/// List connected monitors for the presentation target picker.
+#[tauri::command]
+pub fn list_monitors(app: AppHandle) -> Result<Vec<MonitorInfo>, AppError> {
+    let primary = app.primary_monitor().map_err(|e| e.to_string())?;
+    let monitors = app.available_monitors().map_err(|e| e.to_string())?;
+    Ok(monitors
+        .iter()
+        .map(|m| monitor_info(m, primary.as_ref()))
+        .collect())
+}
+
+/// Show a passage fullscreen on the chosen monitor.
+///
+/// Calling this while a presentation is active repurposes the existing
+/// window (new passage, options, or monitor).
+#[tauri::command]
+pub fn enter_presentation_mode(
+    app: AppHandle,
+    state: State<'_, PresentationState>,
+    passage_ref: String,
+    display_options: PresentationOptions,
+) -> Result<(), AppError> {
+    let monitor = resolve_monitor(&app, display_options.monitor.as_deref())?;
+
+    let window = match app.get_webview_window(PRESENTATION_LABEL) {
+        Some(window) => window,
+        None => create_presentation_window(&app)?,
+    };
+    place_on_monitor(&window, &monitor)?;
+
+    {
+        let mut session = state.0.lock().unwrap();
+        match session.as_mut() {
+            Some(s) => {
+                s.passage_ref = passage_ref.clone();
+                s.options = display_options.clone();
+            }
+            None => {
+                let main = app.get_webview_window(MAIN_LABEL);
+                let listener = app.listen(PASSAGE_NAVIGATED_EVENT, {
+                    let app = app.clone();
+                    move |event| {
+                        if let Ok(nav) = serde_json::from_str::<PassageNavigated>(event.payload()) {
+                            sync_passage(&app, nav.passage_ref);
+                        }
+                    }
+                });
+                *session = Some(PresentationSession {
+                    passage_ref: passage_ref.clone(),
+                    options: display_options.clone(),
+                    navigation_listener: listener,
+                    main_was_fullscreen: main
+                        .as_ref()
+                        .and_then(|w| w.is_fullscreen().ok())
+                        .unwrap_or(false),
+                    main_was_maximized: main
+                        .as_ref()
+                        .and_then(|w| w.is_maximized().ok())
+                        .unwrap_or(false),
+                });
+            }
+        }
+    }
+
+    Ok(window
+        .emit(
+            PRESENTATION_UPDATE_EVENT,
+            PresentationUpdate {
+                passage_ref,
+                options: display_options,
+            },
+        )
+        .map_err(|e| e.to_string())?)
+}
+
+/// Forward a navigation in the main window to the presentation window.
+fn sync_passage(app: &AppHandle, passage_ref: String) {
+    let state = app.state::<PresentationState>();
+    let update = {
+        let mut session = state.0.lock().unwrap();
+        let Some(s) = session.as_mut() else {
+            return;
+        };
+        s.passage_ref = passage_ref;
+        PresentationUpdate {
+            passage_ref: s.passage_ref.clone(),
+            options: s.options.clone(),
+        }
+    };
+
+    if let Some(window) = app.get_webview_window(PRESENTATION_LABEL) {
+        ensure_on_connected_monitor(&window);
+        let _ = window.emit(PRESENTATION_UPDATE_EVENT, update);
+    }
+}
+
+/// Current passage and options, for the presentation window to render on load.
+#[tauri::command]
+pub fn get_presentation_state(state: State<'_, PresentationState>) -> Option<PresentationUpdate> {
+    state.current_update()
+}
+
+/// Close the presentation window and restore the main window.
+#[tauri::command]
+pub fn exit_presentation_mode(
+    app: AppHandle,
+    state: State<'_, PresentationState>,
+) -> Result<(), AppError> {
+    let session = state.0.lock().unwrap().take();
+
+    if let Some(window) = app.get_webview_window(PRESENTATION_LABEL) {
+        window.close().map_err(|e| e.to_string())?;
+    }
+
+    let Some(session) = session else {
+        return Ok(());
+    };
+    app.unlisten(session.navigation_listener);
+
+    if let Some(main) = app.get_webview_window(MAIN_LABEL) {
+        main.set_fullscreen(session.main_was_fullscreen)
+            .map_err(|e| e.to_string())?;
+        if session.main_was_maximized {
+            main.maximize().map_err(|e| e.to_string())?;
+        }
+        main.set_focus().map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
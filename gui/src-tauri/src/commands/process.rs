@@ -0,0 +1,29 @@
+//! Helper programs the app runs: `reg`, PowerShell, `say` and the like.
+//!
+//! Release builds on Windows have no console, so every console program
+//! they start gets a console window of its own unless it is created with
+//! `CREATE_NO_WINDOW`. Background polls would flash one up every few
+//! seconds.
+
+use std::ffi::OsStr;
+use std::process::Command;
+
+/// `CREATE_NO_WINDOW` from the process creation flags
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+/// A command for a helper program that never opens a console window.
+pub fn command(program: impl AsRef<OsStr>) -> Command {
+    let mut command = Command::new(program);
+    hide_console(&mut command);
+    command
+}
+
+#[cfg(target_os = "windows")]
+fn hide_console(command: &mut Command) {
+    use std::os::windows::process::CommandExt;
+    command.creation_flags(CREATE_NO_WINDOW);
+}
+
+#[cfg(not(target_os = "windows"))]
+fn hide_console(_command: &mut Command) {}
@@ -0,0 +1,366 @@
+//! Project files (`.rlproj`): a working set saved to reopen or share.
+//!
+//! A project file is a zip holding `manifest.json`:
+//!
+//! ```json
+//! {"format_version": 1, "app_version": "0.1.0", "saved_at_ms": 1714521600000,
+//!  "project": {"name": "Greek II", "passages": [{"reference": "John 1:1-18"}],
+//!  "passage_ids": ["…"], "translate": {"mode": "readable", …},
+//!  "tag_filter": ["week-3"], "window": {…}, "layout": {…}}}
+//! ```
+//!
+//! The format version is read before anything else, so a file written by
+//! a newer app is refused as such instead of being misread. Fields this
+//! version doesn't know are ignored. Opening resolves the project's
+//! passages and tags against the local cache and tag store, reports what
+//! is missing, and emits `project-opened` for the frontend to apply.
+//!
+//! Files reach `open_project` from the frontend, which gets them from the
+//! open-request queue: `--open`, a file association, or a file dropped on
+//! the main window (`register_project_drop`).
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fs;
+use std::io::{Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, DragDropEvent, Emitter, Manager, WindowEvent};
+use thiserror::Error;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use super::errors::{command_error, CommandError};
+use super::i18n::Localize;
+use super::open_requests::{queue_open_request, OpenRequest};
+use super::passage_lists::PassageListItem;
+use super::passages::{cached_passage_ids, TranslateOptions};
+use super::save_dialog::{validate_export_path, ExportKind};
+use super::shutdown::{main_window_state, WindowState};
+use super::storage;
+use super::tags::list_tags;
+
+/// Extension of project files
+pub const PROJECT_EXTENSION: &str = "rlproj";
+/// Manifest layout written by this version
+const PROJECT_FORMAT_VERSION: u32 = 1;
+const MANIFEST_NAME: &str = "manifest.json";
+const PROJECT_OPENED_EVENT: &str = "project-opened";
+/// Projects hold references and settings, not translations
+const MAX_PROJECT_BYTES: u64 = 10 * 1024 * 1024;
+const MAIN_LABEL: &str = "main";
+
+#[derive(Debug, Error)]
+pub enum ProjectError {
+    #[error(
+        "This project was created by a newer version of Red Letters ({app_version}); \
+         update the app to open it"
+    )]
+    NewerVersion {
+        format_version: u32,
+        app_version: String,
+    },
+    #[error("Not a Red Letters project: {0}")]
+    Invalid(String),
+    #[error("Project file error: {0}")]
+    File(String),
+}
+
+impl Localize for ProjectError {
+    fn code(&self) -> &'static str {
+        match self {
+            ProjectError::NewerVersion { .. } => "project-newer-version",
+            ProjectError::Invalid(_) => "project-invalid",
+            ProjectError::File(_) => "project-file-error",
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            ProjectError::NewerVersion {
+                format_version,
+                app_version,
+            } => vec![
+                ("format_version", format_version.to_string()),
+                ("app_version", app_version.clone()),
+            ],
+            ProjectError::Invalid(detail) | ProjectError::File(detail) => {
+                vec![("detail", detail.clone())]
+            }
+        }
+    }
+}
+
+impl CommandError for ProjectError {}
+
+command_error!(ProjectError);
+
+/// What a project restores. The frontend owns `layout`; the rest is
+/// checked against local data when the project is opened.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectState {
+    pub name: String,
+    /// References of the working set, as in a passage list
+    #[serde(default)]
+    pub passages: Vec<PassageListItem>,
+    /// Cached translations in the working set
+    #[serde(default)]
+    pub passage_ids: Vec<String>,
+    #[serde(default)]
+    pub translate: TranslateOptions,
+    /// Only show passages carrying these tags
+    #[serde(default)]
+    pub tag_filter: Vec<String>,
+    /// Main window geometry; taken from the window when saving without one
+    #[serde(default)]
+    pub window: Option<WindowState>,
+    /// Panels and view settings, as the frontend keeps them
+    #[serde(default)]
+    pub layout: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ProjectManifest {
+    format_version: u32,
+    /// App that wrote the file, for the newer-version error
+    app_version: String,
+    saved_at_ms: u64,
+    project: ProjectState,
+}
+
+/// Result of `open_project`, also the `project-opened` payload.
+#[derive(Debug, Clone, Serialize)]
+pub struct OpenedProject {
+    pub path: String,
+    pub project: ProjectState,
+    pub app_version: String,
+    pub saved_at_ms: u64,
+    /// Entries of `passage_ids` with no cached translation here
+    pub missing_passages: Vec<String>,
+    /// Entries of `tag_filter` no local item carries
+    pub missing_tags: Vec<String>,
+}
+
+/// Whether `path` names a project file.
+pub fn is_project_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(PROJECT_EXTENSION))
+}
+
+fn write_project(manifest: &ProjectManifest) -> Result<Vec<u8>, ProjectError> {
+    let file_error = |e: zip::result::ZipError| ProjectError::File(e.to_string());
+    let json =
+        serde_json::to_vec_pretty(manifest).map_err(|e| ProjectError::File(e.to_string()))?;
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    zip.start_file(MANIFEST_NAME, options).map_err(file_error)?;
+    zip.write_all(&json)
+        .map_err(|e| ProjectError::File(e.to_string()))?;
+    Ok(zip.finish().map_err(file_error)?.into_inner())
+}
+
+fn read_project(bytes: &[u8]) -> Result<ProjectManifest, ProjectError> {
+    let invalid = ProjectError::Invalid;
+    let mut archive = ZipArchive::new(Cursor::new(bytes)).map_err(|e| invalid(e.to_string()))?;
+    let entry = archive
+        .by_name(MANIFEST_NAME)
+        .map_err(|_| invalid(format!("no {}", MANIFEST_NAME)))?;
+    // The size in the header can lie; stop reading past the limit
+    let mut json = Vec::new();
+    entry
+        .take(MAX_PROJECT_BYTES + 1)
+        .read_to_end(&mut json)
+        .map_err(|e| invalid(e.to_string()))?;
+    if json.len() as u64 > MAX_PROJECT_BYTES {
+        return Err(invalid(format!("{} is too large", MANIFEST_NAME)));
+    }
+
+    let manifest: Value = serde_json::from_slice(&json).map_err(|e| invalid(e.to_string()))?;
+    let format_version = manifest
+        .get("format_version")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| invalid("no format version".to_string()))?;
+    if format_version > u64::from(PROJECT_FORMAT_VERSION) {
+        return Err(ProjectError::NewerVersion {
+            format_version: u32::try_from(format_version).unwrap_or(u32::MAX),
+            app_version: manifest
+                .get("app_version")
+                .and_then(Value::as_str)
+                .unwrap_or("unknown")
+                .to_string(),
+        });
+    }
+    serde_json::from_value(manifest).map_err(|e| invalid(e.to_string()))
+}
+
+/// Passage ids not in `cached`, and tags not in `tags` (ignoring case).
+fn missing_items(
+    project: &ProjectState,
+    cached: &HashSet<String>,
+    tags: &HashSet<String>,
+) -> (Vec<String>, Vec<String>) {
+    let passages = project
+        .passage_ids
+        .iter()
+        .filter(|id| !cached.contains(*id))
+        .cloned()
+        .collect();
+    let tags = project
+        .tag_filter
+        .iter()
+        .filter(|tag| !tags.contains(&tag.to_lowercase()))
+        .cloned()
+        .collect();
+    (passages, tags)
+}
+
+/// Queue project files dropped on the main window for the frontend to
+/// open, like ones passed on the command line.
+pub fn register_project_drop(app: &AppHandle) {
+    let Some(window) = app.get_webview_window(MAIN_LABEL) else {
+        return;
+    };
+    let handle = app.clone();
+    window.on_window_event(move |event| {
+        if let WindowEvent::DragDrop(DragDropEvent::Drop { paths, .. }) = event {
+            for path in paths.iter().filter(|p| is_project_file(p)) {
+                queue_open_request(&handle, OpenRequest::Project { path: path.clone() });
+            }
+        }
+    });
+}
+
+/// Save the working set as a project file and return its path.
+///
+/// `dest_path` should come from `choose_save_path` with kind `project`.
+#[tauri::command]
+pub fn save_project(
+    app: AppHandle,
+    dest_path: String,
+    project_state: ProjectState,
+) -> Result<String, ProjectError> {
+    let path = validate_export_path(&app, ExportKind::Project, &PathBuf::from(dest_path))
+        .map_err(ProjectError::File)?;
+    let mut project = project_state;
+    if project.name.trim().is_empty() {
+        return Err(ProjectError::Invalid("a project needs a name".to_string()));
+    }
+    if project.window.is_none() {
+        project.window = main_window_state(&app);
+    }
+    let manifest = ProjectManifest {
+        format_version: PROJECT_FORMAT_VERSION,
+        app_version: app.package_info().version.to_string(),
+        saved_at_ms: storage::now_ms(),
+        project,
+    };
+    storage::write_atomic(&path, &write_project(&manifest)?).map_err(ProjectError::File)?;
+    Ok(path.to_string_lossy().into_owned())
+}
+
+/// Open a project file, report which of its passages and tags are missing
+/// here, and emit `project-opened` with the result.
+#[tauri::command]
+pub fn open_project(app: AppHandle, path: String) -> Result<OpenedProject, ProjectError> {
+    let file_error = |e: std::io::Error| ProjectError::File(format!("{}: {}", path, e));
+    let size = fs::metadata(&path).map_err(file_error)?.len();
+    if size > MAX_PROJECT_BYTES {
+        return Err(ProjectError::Invalid(format!("{} is too large", path)));
+    }
+    let manifest = read_project(&fs::read(&path).map_err(file_error)?)?;
+
+    let cached: HashSet<String> = cached_passage_ids(&app)
+        .map_err(ProjectError::File)?
+        .into_iter()
+        .collect();
+    let tags: HashSet<String> = list_tags(app.clone())
+        .map_err(|e| ProjectError::File(e.to_string()))?
+        .into_iter()
+        .map(|usage| usage.tag.to_lowercase())
+        .collect();
+    let (missing_passages, missing_tags) = missing_items(&manifest.project, &cached, &tags);
+
+    let opened = OpenedProject {
+        path,
+        project: manifest.project,
+        app_version: manifest.app_version,
+        saved_at_ms: manifest.saved_at_ms,
+        missing_passages,
+        missing_tags,
+    };
+    let _ = app.emit(PROJECT_OPENED_EVENT, &opened);
+    Ok(opened)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(format_version: u32) -> ProjectManifest {
+        ProjectManifest {
+            format_version,
+            app_version: "0.1.0".to_string(),
+            saved_at_ms: 1_714_521_600_000,
+            project: ProjectState {
+                name: "Greek II".to_string(),
+                passages: vec![PassageListItem {
+                    reference: "John 1:1-18".to_string(),
+                    label: Some("Prologue".to_string()),
+                }],
+                passage_ids: vec!["aaaa".to_string(), "bbbb".to_string()],
+                tag_filter: vec!["Week-3".to_string(), "exam".to_string()],
+                layout: serde_json::json!({ "sidebar": "collapsed" }),
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_project_round_trip() {
+        let bytes = write_project(&manifest(PROJECT_FORMAT_VERSION)).unwrap();
+        let read = read_project(&bytes).unwrap();
+        assert_eq!(read.project.name, "Greek II");
+        assert_eq!(read.project.passages[0].label.as_deref(), Some("Prologue"));
+        assert_eq!(read.project.translate.translator, "literal");
+        assert_eq!(read.project.layout["sidebar"], "collapsed");
+        assert!(is_project_file(Path::new("/tmp/Course.RLPROJ")));
+        assert!(!is_project_file(Path::new("/tmp/course.zip")));
+    }
+
+    #[test]
+    fn test_newer_and_invalid_projects() {
+        let bytes = write_project(&manifest(PROJECT_FORMAT_VERSION + 1)).unwrap();
+        match read_project(&bytes) {
+            Err(ProjectError::NewerVersion {
+                format_version,
+                app_version,
+            }) => assert_eq!((format_version, app_version.as_str()), (2, "0.1.0")),
+            other => panic!("expected a newer-version error, got {:?}", other),
+        }
+
+        assert!(matches!(
+            read_project(b"not a zip"),
+            Err(ProjectError::Invalid(_))
+        ));
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        zip.start_file(MANIFEST_NAME, SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(br#"{"format_version": 1, "project": {}}"#)
+            .unwrap();
+        let bytes = zip.finish().unwrap().into_inner();
+        assert!(matches!(
+            read_project(&bytes),
+            Err(ProjectError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn test_missing_items() {
+        let project = manifest(PROJECT_FORMAT_VERSION).project;
+        let cached = HashSet::from(["aaaa".to_string()]);
+        let tags = HashSet::from(["week-3".to_string()]);
+        let (passages, tags) = missing_items(&project, &cached, &tags);
+        assert_eq!(passages, ["bbbb"]);
+        assert_eq!(tags, ["exam"]);
+    }
+}
@@ -0,0 +1,495 @@
+//! Pronunciation audio for lemmas.
+//!
+//! Audio comes from the engine's pronunciation endpoint and is kept in
+//! `pronunciations/` under the app data dir, with an index of what each
+//! file holds and when it was last played. The webview plays the files
+//! from `rl-asset://` URLs served by the protocol handler below, so audio
+//! never goes through `invoke` as base64. The directory is capped at
+//! `MAX_CACHE_BYTES`; the least recently used recordings go first.
+
+use reqwest::header::CONTENT_TYPE;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use tauri::http::{header, Request, Response, StatusCode};
+use tauri::{AppHandle, Emitter};
+use unicode_normalization::UnicodeNormalization;
+
+use super::engine::EngineError;
+use super::errors::AppError;
+use super::flashcards::passage_lemmas;
+use super::http::{self, HttpError};
+use super::passages::{
+    load_all_passages, translate_and_cache, CachedPassage, TranslateOptions, TranslateOutcome,
+};
+use super::reference::{canonical_reference, parse_reference_list};
+use super::storage;
+
+/// URI scheme the audio files are served on
+pub const ASSET_SCHEME: &str = "rl-asset";
+const AUDIO_DIR: &str = "pronunciations";
+const INDEX_FILE: &str = "index.json";
+const PRONUNCIATION_PATH: &str = "/v1/pronunciations";
+/// Optional response header with the recording's length
+const DURATION_HEADER: &str = "x-audio-duration-ms";
+const DEFAULT_VOICE: &str = "default";
+/// A few thousand short recordings
+const MAX_CACHE_BYTES: u64 = 64 * 1024 * 1024;
+const PREFETCH_COMPLETE_EVENT: &str = "pronunciation-prefetch-complete";
+
+/// Serializes changes to the index, which prefetching updates concurrently
+static INDEX_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioMetadata {
+    pub lemma: String,
+    pub voice: String,
+    /// File extension, e.g. "mp3"
+    pub format: String,
+    /// Absent when the engine doesn't report it
+    #[serde(default)]
+    pub duration_ms: Option<u64>,
+    pub size_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Pronunciation {
+    Audio {
+        /// `rl-asset` URL for an `<audio>` element
+        url: String,
+        metadata: AudioMetadata,
+    },
+    /// The engine has no recording of this lemma in this voice
+    NoAudio { lemma: String, voice: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    file_name: String,
+    metadata: AudioMetadata,
+    last_used_ms: u64,
+}
+
+/// Cached recordings by `cache_key`.
+type CacheIndex = HashMap<String, CacheEntry>;
+
+#[derive(Debug, Clone, Serialize)]
+struct PrefetchReport {
+    passage_ref: String,
+    voice: String,
+    fetched: usize,
+    already_cached: usize,
+    unavailable: usize,
+    failed: usize,
+}
+
+/// Key of a lemma's recording in a voice. Accents matter to pronunciation,
+/// so lemmas are only brought to NFC rather than normalized for lookup.
+fn cache_key(lemma: &str, voice: &str) -> String {
+    let lemma: String = lemma.trim().nfc().collect();
+    let digest = Sha256::digest(format!("{}\n{}", lemma, voice).as_bytes());
+    hex::encode(&digest[..16])
+}
+
+/// File extension for an audio content type.
+fn audio_extension(content_type: &str) -> Option<&'static str> {
+    let mime = content_type.split(';').next()?.trim().to_ascii_lowercase();
+    match mime.as_str() {
+        "audio/mpeg" | "audio/mp3" => Some("mp3"),
+        "audio/ogg" => Some("ogg"),
+        "audio/opus" => Some("opus"),
+        "audio/wav" | "audio/wave" | "audio/x-wav" => Some("wav"),
+        "audio/webm" => Some("webm"),
+        "audio/mp4" | "audio/aac" | "audio/x-m4a" => Some("m4a"),
+        _ => None,
+    }
+}
+
+/// Content type for a cached file's extension.
+fn audio_mime(extension: &str) -> Option<&'static str> {
+    match extension {
+        "mp3" => Some("audio/mpeg"),
+        "ogg" => Some("audio/ogg"),
+        "opus" => Some("audio/opus"),
+        "wav" => Some("audio/wav"),
+        "webm" => Some("audio/webm"),
+        "m4a" => Some("audio/mp4"),
+        _ => None,
+    }
+}
+
+/// URL the webview loads a cached file from. Windows and Android webviews
+/// only allow custom schemes through the `http://<scheme>.localhost` form.
+fn asset_url(file_name: &str) -> String {
+    if cfg!(any(windows, target_os = "android")) {
+        format!("http://{}.localhost/{}", ASSET_SCHEME, file_name)
+    } else {
+        format!("{}://localhost/{}", ASSET_SCHEME, file_name)
+    }
+}
+
+/// Drop least recently used entries until the rest fit in `max_bytes`,
+/// returning the dropped ones so their files can be deleted.
+fn evict(index: &mut CacheIndex, max_bytes: u64) -> Vec<CacheEntry> {
+    let mut total: u64 = index.values().map(|e| e.metadata.size_bytes).sum();
+    let mut by_age: Vec<(u64, String)> = index
+        .iter()
+        .map(|(key, entry)| (entry.last_used_ms, key.clone()))
+        .collect();
+    by_age.sort();
+    let mut evicted = Vec::new();
+    for (_, key) in by_age {
+        if total <= max_bytes {
+            break;
+        }
+        if let Some(entry) = index.remove(&key) {
+            total -= entry.metadata.size_bytes;
+            evicted.push(entry);
+        }
+    }
+    evicted
+}
+
+fn load_index(dir: &Path) -> Result<CacheIndex, String> {
+    storage::read_json_or_default(&dir.join(INDEX_FILE))
+}
+
+/// The index entry for `key` if its file is still there, marked as used.
+fn cached_entry(dir: &Path, key: &str) -> Result<Option<CacheEntry>, String> {
+    let _guard = INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut index = load_index(dir)?;
+    let Some(entry) = index.get_mut(key) else {
+        return Ok(None);
+    };
+    if !dir.join(&entry.file_name).is_file() {
+        index.remove(key);
+        storage::write_json(&dir.join(INDEX_FILE), &index)?;
+        return Ok(None);
+    }
+    entry.last_used_ms = storage::now_ms();
+    let entry = entry.clone();
+    storage::write_json(&dir.join(INDEX_FILE), &index)?;
+    Ok(Some(entry))
+}
+
+/// Store a downloaded recording and trim the cache back under its cap.
+fn store_audio(
+    dir: &Path,
+    key: String,
+    bytes: &[u8],
+    metadata: AudioMetadata,
+) -> Result<CacheEntry, String> {
+    let file_name = format!("{}.{}", key, metadata.format);
+    storage::write_atomic(&dir.join(&file_name), bytes)?;
+
+    let _guard = INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut index = load_index(dir)?;
+    let entry = CacheEntry {
+        file_name,
+        metadata,
+        last_used_ms: storage::now_ms(),
+    };
+    if let Some(previous) = index.insert(key, entry.clone()) {
+        if previous.file_name != entry.file_name {
+            let _ = fs::remove_file(dir.join(&previous.file_name));
+        }
+    }
+    for evicted in evict(&mut index, MAX_CACHE_BYTES) {
+        let _ = fs::remove_file(dir.join(&evicted.file_name));
+    }
+    storage::write_json(&dir.join(INDEX_FILE), &index)?;
+    Ok(entry)
+}
+
+fn audio_dir(app: &AppHandle) -> Result<std::path::PathBuf, EngineError> {
+    storage::app_data_subdir(app, AUDIO_DIR).map_err(EngineError::Storage)
+}
+
+/// A lemma's recording from the cache, downloading it on a miss. The
+/// second value says whether it had to be downloaded.
+async fn pronunciation(
+    app: &AppHandle,
+    port: u16,
+    lemma: &str,
+    voice: &str,
+) -> Result<(Pronunciation, bool), EngineError> {
+    let dir = audio_dir(app)?;
+    let key = cache_key(lemma, voice);
+    if let Some(entry) = cached_entry(&dir, &key).map_err(EngineError::Storage)? {
+        let url = asset_url(&entry.file_name);
+        return Ok((
+            Pronunciation::Audio {
+                url,
+                metadata: entry.metadata,
+            },
+            false,
+        ));
+    }
+
+    let request = http::request(Method::GET, port, PRONUNCIATION_PATH)?
+        .query(&[("lemma", lemma), ("voice", voice)]);
+    let response = match http::send(request).await {
+        Ok(response) => response,
+        Err(HttpError::Status { status: 404, .. }) => {
+            let no_audio = Pronunciation::NoAudio {
+                lemma: lemma.to_string(),
+                voice: voice.to_string(),
+            };
+            return Ok((no_audio, false));
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let content_type = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    let format = audio_extension(&content_type)
+        .ok_or_else(|| HttpError::Decode(format!("unsupported audio type {:?}", content_type)))?;
+    let duration_ms = response
+        .headers()
+        .get(DURATION_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse().ok());
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|e| HttpError::Transport(e.to_string()))?;
+
+    let metadata = AudioMetadata {
+        lemma: lemma.to_string(),
+        voice: voice.to_string(),
+        format: format.to_string(),
+        duration_ms,
+        size_bytes: bytes.len() as u64,
+    };
+    let entry = store_audio(&dir, key, &bytes, metadata).map_err(EngineError::Storage)?;
+    let audio = Pronunciation::Audio {
+        url: asset_url(&entry.file_name),
+        metadata: entry.metadata,
+    };
+    Ok((audio, true))
+}
+
+/// The cached translation of `reference`, translating it when none is.
+async fn passage_for(app: &AppHandle, port: u16, reference: &str) -> Result<CachedPassage, String> {
+    let canonical = canonical_reference(reference);
+    let cached = load_all_passages(app)?
+        .into_iter()
+        .find(|p| canonical_reference(&p.normalized_ref) == canonical);
+    if let Some(passage) = cached {
+        return Ok(passage);
+    }
+    match translate_and_cache(app, port, reference, TranslateOptions::default())
+        .await
+        .map_err(|e| e.to_string())?
+    {
+        TranslateOutcome::Translation(passage) => Ok(*passage),
+        TranslateOutcome::Gate(_) => {
+            Err("The engine needs an acknowledgement before translating".to_string())
+        }
+    }
+}
+
+/// Pronunciation of a lemma: an `rl-asset` URL with the recording's
+/// metadata, or `no_audio` when the engine has none for it.
+#[tauri::command]
+pub async fn get_pronunciation(
+    app: AppHandle,
+    port: u16,
+    lemma: String,
+    voice: Option<String>,
+) -> Result<Pronunciation, EngineError> {
+    let voice = voice.unwrap_or_else(|| DEFAULT_VOICE.to_string());
+    let (pronunciation, _) = pronunciation(&app, port, &lemma, &voice).await?;
+    Ok(pronunciation)
+}
+
+/// Download recordings of every lemma in a passage in the background.
+///
+/// Returns the number of lemmas queued; a `pronunciation-prefetch-complete`
+/// event reports what happened to them.
+#[tauri::command]
+pub async fn prefetch_pronunciations(
+    app: AppHandle,
+    port: u16,
+    passage_ref: String,
+    voice: Option<String>,
+) -> Result<usize, AppError> {
+    parse_reference_list(&passage_ref).map_err(|e| e.to_string())?;
+    let voice = voice.unwrap_or_else(|| DEFAULT_VOICE.to_string());
+    let passage = passage_for(&app, port, &passage_ref).await?;
+    let lemmas = passage_lemmas(&passage.response);
+    let queued = lemmas.len();
+
+    tauri::async_runtime::spawn(async move {
+        let mut report = PrefetchReport {
+            passage_ref,
+            voice,
+            fetched: 0,
+            already_cached: 0,
+            unavailable: 0,
+            failed: 0,
+        };
+        for lemma in lemmas {
+            match pronunciation(&app, port, &lemma, &report.voice).await {
+                Ok((Pronunciation::Audio { .. }, true)) => report.fetched += 1,
+                Ok((Pronunciation::Audio { .. }, false)) => report.already_cached += 1,
+                Ok((Pronunciation::NoAudio { .. }, _)) => report.unavailable += 1,
+                Err(e) => {
+                    tracing::warn!("Could not fetch the pronunciation of {}: {}", lemma, e);
+                    report.failed += 1;
+                }
+            }
+        }
+        let _ = app.emit(PREFETCH_COMPLETE_EVENT, &report);
+    });
+    Ok(queued)
+}
+
+/// Byte range asked for by a `Range` header, as inclusive offsets into a
+/// file of `len` bytes. Only single ranges are supported.
+fn byte_range(range: &str, len: u64) -> Option<(u64, u64)> {
+    let spec = range.trim().strip_prefix("bytes=")?;
+    if spec.contains(',') || len == 0 {
+        return None;
+    }
+    let (start, end) = spec.split_once('-')?;
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => {
+            let suffix: u64 = suffix.parse().ok()?;
+            (len.saturating_sub(suffix), len - 1)
+        }
+        (start, "") => (start.parse().ok()?, len - 1),
+        (start, end) => (start.parse().ok()?, end.parse::<u64>().ok()?.min(len - 1)),
+    };
+    (start <= end && start < len).then_some((start, end))
+}
+
+fn status_response(status: StatusCode) -> Response<Vec<u8>> {
+    Response::builder()
+        .status(status)
+        .body(Vec::new())
+        .unwrap_or_default()
+}
+
+/// Handler for the `rl-asset` scheme: serves cached recordings, and
+/// nothing else, with range support so the audio element can seek.
+pub fn serve_asset(app: &AppHandle, request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+    let file_name = request.uri().path().trim_start_matches('/');
+    let Some((stem, extension)) = file_name.split_once('.') else {
+        return status_response(StatusCode::NOT_FOUND);
+    };
+    let mime = audio_mime(extension);
+    let valid_stem = !stem.is_empty() && stem.chars().all(|c| c.is_ascii_hexdigit());
+    let (Some(mime), true) = (mime, valid_stem) else {
+        return status_response(StatusCode::NOT_FOUND);
+    };
+    let Ok(dir) = storage::app_data_subdir(app, AUDIO_DIR) else {
+        return status_response(StatusCode::INTERNAL_SERVER_ERROR);
+    };
+    let Ok(bytes) = fs::read(dir.join(file_name)) else {
+        return status_response(StatusCode::NOT_FOUND);
+    };
+
+    let len = bytes.len() as u64;
+    let builder = Response::builder()
+        .header(header::CONTENT_TYPE, mime)
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*");
+    let range = request
+        .headers()
+        .get(header::RANGE)
+        .and_then(|v| v.to_str().ok());
+    let response = match range {
+        None => builder.status(StatusCode::OK).body(bytes),
+        Some(range) => match byte_range(range, len) {
+            Some((start, end)) => builder
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(
+                    header::CONTENT_RANGE,
+                    format!("bytes {}-{}/{}", start, end, len),
+                )
+                .body(bytes[start as usize..=end as usize].to_vec()),
+            None => builder
+                .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(header::CONTENT_RANGE, format!("bytes */{}", len))
+                .body(Vec::new()),
+        },
+    };
+    response.unwrap_or_else(|_| status_response(StatusCode::INTERNAL_SERVER_ERROR))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, size_bytes: u64, last_used_ms: u64) -> CacheEntry {
+        CacheEntry {
+            file_name: format!("{}.mp3", name),
+            metadata: AudioMetadata {
+                lemma: name.to_string(),
+                voice: DEFAULT_VOICE.to_string(),
+                format: "mp3".to_string(),
+                duration_ms: None,
+                size_bytes,
+            },
+            last_used_ms,
+        }
+    }
+
+    #[test]
+    fn test_evict_least_recently_used() {
+        let mut index: CacheIndex = [
+            ("a", entry("a", 40, 300)),
+            ("b", entry("b", 40, 100)),
+            ("c", entry("c", 40, 200)),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v))
+        .collect();
+        assert!(evict(&mut index, 120).is_empty());
+
+        let evicted: Vec<String> = evict(&mut index, 50)
+            .into_iter()
+            .map(|e| e.file_name)
+            .collect();
+        assert_eq!(evicted, ["b.mp3", "c.mp3"]);
+        assert_eq!(index.keys().collect::<Vec<_>>(), ["a"]);
+    }
+
+    #[test]
+    fn test_cache_key_and_formats() {
+        // Precomposed and decomposed ά are the same lemma; voices differ
+        let key = cache_key("λόγος", DEFAULT_VOICE);
+        assert_eq!(key, cache_key(" λο\u{301}γος", DEFAULT_VOICE));
+        assert_ne!(key, cache_key("λογος", DEFAULT_VOICE));
+        assert_ne!(key, cache_key("λόγος", "erasmian"));
+        assert_eq!(key.len(), 32);
+
+        assert_eq!(audio_extension("audio/mpeg"), Some("mp3"));
+        assert_eq!(audio_extension("Audio/Ogg; codecs=opus"), Some("ogg"));
+        assert_eq!(audio_extension("application/json"), None);
+        for ext in ["mp3", "ogg", "opus", "wav", "webm", "m4a"] {
+            let mime = audio_mime(ext).unwrap();
+            assert_eq!(audio_extension(mime), Some(ext));
+        }
+    }
+
+    #[test]
+    fn test_byte_range() {
+        assert_eq!(byte_range("bytes=0-", 100), Some((0, 99)));
+        assert_eq!(byte_range("bytes=10-19", 100), Some((10, 19)));
+        assert_eq!(byte_range("bytes=90-200", 100), Some((90, 99)));
+        assert_eq!(byte_range("bytes=-10", 100), Some((90, 99)));
+        assert_eq!(byte_range("bytes=100-", 100), None);
+        assert_eq!(byte_range("bytes=0-1,5-6", 100), None);
+        assert_eq!(byte_range("items=0-1", 100), None);
+    }
+}
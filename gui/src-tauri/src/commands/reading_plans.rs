@@ -0,0 +1,689 @@
+//! Guided reading plans.
+//!
+//! A plan spreads a list of passages over days: books and chapter ranges
+//! are split into chapters, and `per_day` of them are assigned to each day
+//! from the start date. Each plan is stored as `reading_plans/{id}.json`
+//! under the app data dir. Dates are local calendar dates ("YYYY-MM-DD").
+//!
+//! Missed days are settled whenever a plan is read: a `shift` plan moves
+//! its unread days forward so the oldest falls on today, a `skip` plan
+//! marks them skipped.
+//!
+//! Plans saved before plans were scheduled by day hold a list of
+//! `entries`, each with its own due time. They are upgraded the first
+//! time they are read: every entry becomes a day of its own on the local
+//! date it was due, and the file is rewritten in the current format.
+
+use chrono::{DateTime, Duration as Days, Local, NaiveDate, NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::AppHandle;
+use tauri_plugin_notification::NotificationExt;
+
+use super::errors::AppError;
+use super::open_requests::{queue_open_request, OpenRequest};
+use super::reference::parse_reference_list;
+use super::save_dialog::{validate_export_path, ExportKind};
+use super::storage;
+
+const PLANS_DIR: &str = "reading_plans";
+const DATE_FORMAT: &str = "%Y-%m-%d";
+const TIME_FORMAT: &str = "%H:%M";
+/// How often the reminder thread checks the plans
+const REMINDER_POLL: Duration = Duration::from_secs(60);
+
+/// What happens to readings whose day passed unread.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MissedDays {
+    /// Move the rest of the plan forward a day per missed day
+    #[default]
+    Shift,
+    /// Mark the reading skipped and keep to the calendar
+    Skip,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReadingSchedule {
+    /// First day of the plan; today when unset
+    #[serde(default)]
+    pub start_date: Option<String>,
+    /// Passages (chapters, for whole books) assigned to each day
+    #[serde(default = "default_per_day")]
+    pub per_day: u32,
+    #[serde(default)]
+    pub missed_days: MissedDays,
+    /// Local "HH:MM" of the daily reminder; no reminder when unset
+    #[serde(default)]
+    pub reminder_time: Option<String>,
+}
+
+fn default_per_day() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadingStatus {
+    Pending,
+    Completed,
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadingDay {
+    pub date: String,
+    pub passages: Vec<String>,
+    pub status: ReadingStatus,
+    #[serde(default)]
+    pub completed_at_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadingPlan {
+    pub id: String,
+    pub name: String,
+    pub schedule: ReadingSchedule,
+    pub days: Vec<ReadingDay>,
+    /// Days unread readings have been moved forward in total
+    #[serde(default)]
+    pub days_shifted: u32,
+    /// Date of the last reminder, so each day reminds once
+    #[serde(default)]
+    pub last_reminder_date: Option<String>,
+    pub created_at_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TodayReading {
+    pub plan_id: String,
+    pub date: String,
+    /// Empty when nothing is assigned to today
+    pub passages: Vec<String>,
+    pub completed: bool,
+    /// 1-based day of the plan for today's reading
+    pub day: Option<u32>,
+    pub total_days: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReadingPlanProgress {
+    pub completed: u32,
+    pub skipped: u32,
+    pub remaining: u32,
+    /// Nothing has been moved forward or skipped
+    pub on_track: bool,
+    /// Days the plan has slipped: readings moved forward or skipped
+    pub days_behind: u32,
+}
+
+/// A reading in a plan saved in the old format.
+#[derive(Debug, Deserialize)]
+struct LegacyEntry {
+    reference: String,
+    due_date_ms: u64,
+    #[serde(default)]
+    completed: bool,
+    #[serde(default)]
+    completed_at_ms: Option<u64>,
+}
+
+/// A plan saved in the old format, before readings were grouped by day.
+#[derive(Debug, Deserialize)]
+struct LegacyPlan {
+    id: String,
+    name: String,
+    entries: Vec<LegacyEntry>,
+    created_at_ms: u64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum StoredPlan {
+    Current(ReadingPlan),
+    Legacy(LegacyPlan),
+}
+
+impl From<LegacyPlan> for ReadingPlan {
+    fn from(legacy: LegacyPlan) -> Self {
+        let mut entries = legacy.entries;
+        entries.sort_by_key(|e| e.due_date_ms);
+        let days: Vec<ReadingDay> = entries
+            .into_iter()
+            .map(|entry| ReadingDay {
+                date: format_date(local_date(entry.due_date_ms)),
+                passages: vec![entry.reference],
+                status: if entry.completed {
+                    ReadingStatus::Completed
+                } else {
+                    ReadingStatus::Pending
+                },
+                completed_at_ms: entry.completed_at_ms.filter(|_| entry.completed),
+            })
+            .collect();
+        let per_day = days
+            .chunk_by(|a, b| a.date == b.date)
+            .map(|same_date| same_date.len() as u32)
+            .max()
+            .unwrap_or(1);
+        ReadingPlan {
+            id: legacy.id,
+            name: legacy.name,
+            schedule: ReadingSchedule {
+                start_date: days.first().map(|d| d.date.clone()),
+                per_day,
+                missed_days: MissedDays::default(),
+                reminder_time: None,
+            },
+            days,
+            days_shifted: 0,
+            last_reminder_date: None,
+            created_at_ms: legacy.created_at_ms,
+        }
+    }
+}
+
+/// A plan with its progress, as exported.
+#[derive(Debug, Serialize)]
+struct ExportedPlan<'a> {
+    #[serde(flatten)]
+    plan: &'a ReadingPlan,
+    progress: ReadingPlanProgress,
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date.trim(), DATE_FORMAT)
+        .map_err(|_| format!("Invalid date (expected YYYY-MM-DD): {}", date))
+}
+
+fn format_date(date: NaiveDate) -> String {
+    date.format(DATE_FORMAT).to_string()
+}
+
+fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+/// Local calendar date of a Unix time in milliseconds.
+fn local_date(ms: u64) -> NaiveDate {
+    DateTime::from_timestamp_millis(ms as i64)
+        .unwrap_or_default()
+        .with_timezone(&Local)
+        .date_naive()
+}
+
+/// Plan file path; ids are UUIDs so a crafted id can't leave the directory.
+fn plan_path(app: &AppHandle, id: &str) -> Result<PathBuf, String> {
+    uuid::Uuid::parse_str(id).map_err(|_| format!("Invalid reading plan id: {}", id))?;
+    Ok(storage::app_data_subdir(app, PLANS_DIR)?.join(format!("{}.json", id)))
+}
+
+/// Read a plan file, upgrading and rewriting one in the old format.
+fn read_plan(path: &Path) -> Result<ReadingPlan, String> {
+    match storage::read_json(path)? {
+        StoredPlan::Current(plan) => Ok(plan),
+        StoredPlan::Legacy(legacy) => {
+            let plan = ReadingPlan::from(legacy);
+            storage::write_json(path, &plan)?;
+            tracing::info!("Upgraded reading plan {}", path.display());
+            Ok(plan)
+        }
+    }
+}
+
+/// Load a plan with its missed days settled as of `today`.
+fn load_plan(app: &AppHandle, id: &str, today: NaiveDate) -> Result<ReadingPlan, String> {
+    let path = plan_path(app, id)?;
+    if !path.exists() {
+        return Err(format!("No reading plan {}", id));
+    }
+    let mut plan = read_plan(&path)?;
+    if settle_missed_days(&mut plan, today) {
+        storage::write_json(&path, &plan)?;
+    }
+    Ok(plan)
+}
+
+/// Every stored plan with its path. Unreadable files are logged and
+/// skipped, so one damaged plan doesn't hide the others.
+fn load_all_plans(app: &AppHandle) -> Result<Vec<(PathBuf, ReadingPlan)>, String> {
+    let dir = storage::app_data_subdir(app, PLANS_DIR)?;
+    let entries = fs::read_dir(&dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    let mut plans: Vec<(PathBuf, ReadingPlan)> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|p| match read_plan(&p) {
+            Ok(plan) => Some((p, plan)),
+            Err(e) => {
+                tracing::warn!("Skipping unreadable reading plan: {}", e);
+                None
+            }
+        })
+        .collect();
+    plans.sort_by_key(|(_, plan)| plan.created_at_ms);
+    Ok(plans)
+}
+
+/// Assign `passages` to consecutive days from `start`, `per_day` at a time.
+fn schedule_days(passages: &[String], start: NaiveDate, per_day: u32) -> Vec<ReadingDay> {
+    passages
+        .chunks(per_day.max(1) as usize)
+        .enumerate()
+        .map(|(i, chunk)| ReadingDay {
+            date: format_date(start + Days::days(i as i64)),
+            passages: chunk.to_vec(),
+            status: ReadingStatus::Pending,
+            completed_at_ms: None,
+        })
+        .collect()
+}
+
+/// Apply the plan's missed-day policy to unread days before `today`.
+/// Returns whether anything changed.
+fn settle_missed_days(plan: &mut ReadingPlan, today: NaiveDate) -> bool {
+    let is_missed = |day: &ReadingDay| {
+        day.status == ReadingStatus::Pending && parse_date(&day.date).is_ok_and(|date| date < today)
+    };
+    let Some(oldest) = plan
+        .days
+        .iter()
+        .filter(|d| is_missed(d))
+        .filter_map(|d| parse_date(&d.date).ok())
+        .min()
+    else {
+        return false;
+    };
+    match plan.schedule.missed_days {
+        MissedDays::Skip => {
+            for day in plan.days.iter_mut().filter(|d| is_missed(d)) {
+                day.status = ReadingStatus::Skipped;
+            }
+        }
+        MissedDays::Shift => {
+            let shift = (today - oldest).num_days();
+            for day in plan
+                .days
+                .iter_mut()
+                .filter(|d| d.status == ReadingStatus::Pending)
+            {
+                if let Ok(date) = parse_date(&day.date) {
+                    day.date = format_date(date + Days::days(shift));
+                }
+            }
+            plan.days_shifted += shift as u32;
+        }
+    }
+    true
+}
+
+fn today_reading(plan: &ReadingPlan, today: NaiveDate) -> TodayReading {
+    let date = format_date(today);
+    let todays: Vec<(usize, &ReadingDay)> = plan
+        .days
+        .iter()
+        .enumerate()
+        .filter(|(_, d)| d.date == date)
+        .collect();
+    TodayReading {
+        plan_id: plan.id.clone(),
+        passages: todays
+            .iter()
+            .flat_map(|(_, d)| d.passages.iter().cloned())
+            .collect(),
+        completed: !todays.is_empty()
+            && todays
+                .iter()
+                .all(|(_, d)| d.status == ReadingStatus::Completed),
+        day: todays.first().map(|(i, _)| *i as u32 + 1),
+        total_days: plan.days.len() as u32,
+        date,
+    }
+}
+
+/// Mark the readings for `date` done, including skipped ones read late.
+fn complete_day(plan: &mut ReadingPlan, date: &str, now_ms: u64) -> Result<(), String> {
+    let date = format_date(parse_date(date)?);
+    let mut found = false;
+    for day in plan.days.iter_mut().filter(|d| d.date == date) {
+        found = true;
+        if day.status != ReadingStatus::Completed {
+            day.status = ReadingStatus::Completed;
+            day.completed_at_ms = Some(now_ms);
+        }
+    }
+    if !found {
+        return Err(format!("No reading scheduled for {}", date));
+    }
+    Ok(())
+}
+
+fn progress(plan: &ReadingPlan) -> ReadingPlanProgress {
+    let count = |status| plan.days.iter().filter(|d| d.status == status).count() as u32;
+    let skipped = count(ReadingStatus::Skipped);
+    let days_behind = plan.days_shifted + skipped;
+    ReadingPlanProgress {
+        completed: count(ReadingStatus::Completed),
+        skipped,
+        remaining: count(ReadingStatus::Pending),
+        on_track: days_behind == 0,
+        days_behind,
+    }
+}
+
+/// Whether the plan's reminder is due at `now`: past the reminder time,
+/// not yet shown today, and today's reading not done.
+fn reminder_due(plan: &ReadingPlan, now: NaiveDateTime) -> bool {
+    let Some(time) = plan
+        .schedule
+        .reminder_time
+        .as_deref()
+        .and_then(|t| NaiveTime::parse_from_str(t, TIME_FORMAT).ok())
+    else {
+        return false;
+    };
+    let reading = today_reading(plan, now.date());
+    now.time() >= time
+        && plan.last_reminder_date.as_deref() != Some(reading.date.as_str())
+        && !reading.passages.is_empty()
+        && !reading.completed
+}
+
+/// Show today's reading as a notification. Desktop notifications don't
+/// report clicks, so the passage is also queued as an open request; the
+/// frontend takes it to today's reading.
+fn remind(app: &AppHandle, plan: &ReadingPlan, reading: &TodayReading) {
+    let reference = reading.passages.join("; ");
+    let shown = app
+        .notification()
+        .builder()
+        .title(&plan.name)
+        .body(format!("Today's reading: {}", reference))
+        .show();
+    if let Err(e) = shown {
+        tracing::warn!("Could not show reading reminder: {}", e);
+    }
+    let reference = parse_reference_list(&reference).map_or(reference, |r| r.canonical);
+    queue_open_request(app, OpenRequest::Passage { reference });
+}
+
+fn send_due_reminders(app: &AppHandle, now: NaiveDateTime) -> Result<(), String> {
+    for (path, mut plan) in load_all_plans(app)? {
+        let settled = settle_missed_days(&mut plan, now.date());
+        let due = reminder_due(&plan, now);
+        if due {
+            let reading = today_reading(&plan, now.date());
+            remind(app, &plan, &reading);
+            plan.last_reminder_date = Some(reading.date);
+        }
+        if settled || due {
+            storage::write_json(&path, &plan)?;
+        }
+    }
+    Ok(())
+}
+
+/// Check the plans in the background and remind about today's reading.
+/// A reminder whose time passed while the app was closed is shown once
+/// the app starts.
+pub fn start_reading_reminders(app: &AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        if let Err(e) = send_due_reminders(&app, Local::now().naive_local()) {
+            tracing::warn!("Reading reminders: {}", e);
+        }
+        std::thread::sleep(REMINDER_POLL);
+    });
+}
+
+/// Save a new reading plan, assigning the passages to days.
+#[tauri::command]
+pub fn create_reading_plan(
+    app: AppHandle,
+    name: String,
+    passage_list: Vec<String>,
+    schedule: ReadingSchedule,
+) -> Result<ReadingPlan, AppError> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(AppError::InvalidInput(
+            "A reading plan needs a name".to_string(),
+        ));
+    }
+    let mut passages = Vec::new();
+    for passage in passage_list.iter().filter(|p| !p.trim().is_empty()) {
+        let parsed = parse_reference_list(passage).map_err(|e| format!("{}: {}", passage, e))?;
+        passages.extend(parsed.chapter_passages());
+    }
+    if passages.is_empty() {
+        return Err(AppError::InvalidInput(
+            "A reading plan needs at least one reading".to_string(),
+        ));
+    }
+    if schedule.per_day == 0 {
+        return Err(AppError::InvalidInput(
+            "A reading plan needs at least one passage a day".to_string(),
+        ));
+    }
+    if let Some(time) = &schedule.reminder_time {
+        NaiveTime::parse_from_str(time, TIME_FORMAT)
+            .map_err(|_| format!("Invalid reminder time (expected HH:MM): {}", time))?;
+    }
+    let start = match &schedule.start_date {
+        Some(date) => parse_date(date)?,
+        None => today(),
+    };
+
+    let plan = ReadingPlan {
+        id: uuid::Uuid::new_v4().to_string(),
+        name,
+        days: schedule_days(&passages, start, schedule.per_day),
+        schedule: ReadingSchedule {
+            start_date: Some(format_date(start)),
+            ..schedule
+        },
+        days_shifted: 0,
+        last_reminder_date: None,
+        created_at_ms: storage::now_ms(),
+    };
+    storage::write_json(&plan_path(&app, &plan.id)?, &plan)?;
+    Ok(plan)
+}
+
+/// Today's passages in a plan and whether they've been read.
+#[tauri::command]
+pub fn get_today_reading(app: AppHandle, plan_id: String) -> Result<TodayReading, AppError> {
+    let today = today();
+    Ok(today_reading(&load_plan(&app, &plan_id, today)?, today))
+}
+
+/// How far along a plan is, and how far it has slipped.
+#[tauri::command]
+pub fn get_reading_plan_progress(
+    app: AppHandle,
+    id: String,
+) -> Result<ReadingPlanProgress, AppError> {
+    Ok(progress(&load_plan(&app, &id, today())?))
+}
+
+/// Mark the reading for a day of a plan as done.
+///
+/// `date` is the day's "YYYY-MM-DD" date. This replaced the old
+/// `reference` argument when plans moved to daily readings; to mark a
+/// passage, pass the date of the day it is assigned to.
+#[tauri::command]
+pub fn mark_reading_complete(
+    app: AppHandle,
+    plan_id: String,
+    date: String,
+) -> Result<(), AppError> {
+    let mut plan = load_plan(&app, &plan_id, today())?;
+    complete_day(&mut plan, &date, storage::now_ms())?;
+    Ok(storage::write_json(&plan_path(&app, &plan_id)?, &plan)?)
+}
+
+/// Export every reading plan with its progress as JSON.
+///
+/// `output_path` should come from `choose_save_path` with the `json` kind.
+#[tauri::command]
+pub fn export_reading_plans(app: AppHandle, output_path: String) -> Result<u32, AppError> {
+    let path = validate_export_path(&app, ExportKind::Json, &PathBuf::from(output_path))?;
+    let today = today();
+    let mut plans: Vec<ReadingPlan> = load_all_plans(&app)?
+        .into_iter()
+        .map(|(_, plan)| plan)
+        .collect();
+    for plan in &mut plans {
+        settle_missed_days(plan, today);
+    }
+    let exported: Vec<ExportedPlan> = plans
+        .iter()
+        .map(|plan| ExportedPlan {
+            plan,
+            progress: progress(plan),
+        })
+        .collect();
+    storage::write_json(&path, &exported)?;
+    Ok(exported.len() as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        parse_date(s).unwrap()
+    }
+
+    fn plan(missed_days: MissedDays) -> ReadingPlan {
+        let passages = parse_reference_list("Mark 1-3").unwrap().chapter_passages();
+        ReadingPlan {
+            id: String::new(),
+            name: "Mark in a month".to_string(),
+            schedule: ReadingSchedule {
+                start_date: Some("2024-02-28".to_string()),
+                per_day: 1,
+                missed_days,
+                reminder_time: Some("07:30".to_string()),
+            },
+            days: schedule_days(&passages, date("2024-02-28"), 1),
+            days_shifted: 0,
+            last_reminder_date: None,
+            created_at_ms: 0,
+        }
+    }
+
+    fn dates(plan: &ReadingPlan) -> Vec<&str> {
+        plan.days.iter().map(|d| d.date.as_str()).collect()
+    }
+
+    #[test]
+    fn test_schedule_days() {
+        let plan = plan(MissedDays::Shift);
+        assert_eq!(dates(&plan), ["2024-02-28", "2024-02-29", "2024-03-01"]);
+        assert_eq!(plan.days[0].passages, ["Mark 1:1-45"]);
+
+        let passages: Vec<String> = ["a", "b", "c"].map(String::from).to_vec();
+        let days = schedule_days(&passages, date("2024-01-01"), 2);
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[1].passages, ["c"]);
+    }
+
+    #[test]
+    fn test_shift_missed_days() {
+        let mut plan = plan(MissedDays::Shift);
+        complete_day(&mut plan, "2024-02-28", 10).unwrap();
+        assert!(!settle_missed_days(&mut plan, date("2024-02-29")));
+
+        // Two days missed: the unread days move forward two days
+        assert!(settle_missed_days(&mut plan, date("2024-03-02")));
+        assert_eq!(dates(&plan), ["2024-02-28", "2024-03-02", "2024-03-03"]);
+        let today = today_reading(&plan, date("2024-03-02"));
+        assert_eq!(today.passages, ["Mark 2:1-28"]);
+        assert_eq!(
+            (today.day, today.total_days, today.completed),
+            (Some(2), 3, false)
+        );
+
+        let progress = progress(&plan);
+        assert_eq!((progress.completed, progress.remaining), (1, 2));
+        assert_eq!((progress.on_track, progress.days_behind), (false, 2));
+    }
+
+    #[test]
+    fn test_skip_missed_days() {
+        let mut plan = plan(MissedDays::Skip);
+        assert!(settle_missed_days(&mut plan, date("2024-03-01")));
+        assert_eq!(dates(&plan), ["2024-02-28", "2024-02-29", "2024-03-01"]);
+        assert_eq!(plan.days[1].status, ReadingStatus::Skipped);
+
+        // A skipped reading can still be read late
+        complete_day(&mut plan, "2024-02-29", 10).unwrap();
+        complete_day(&mut plan, "2024-03-01", 10).unwrap();
+        assert!(today_reading(&plan, date("2024-03-01")).completed);
+        let progress = progress(&plan);
+        assert_eq!((progress.completed, progress.skipped), (2, 1));
+        assert!(complete_day(&mut plan, "2024-03-05", 10).is_err());
+        assert!(complete_day(&mut plan, "March 1", 10).is_err());
+    }
+
+    #[test]
+    fn test_legacy_plan_upgrade() {
+        let due = |date: &str| {
+            let midday = date
+                .parse::<NaiveDate>()
+                .unwrap()
+                .and_hms_opt(12, 0, 0)
+                .unwrap();
+            midday.and_local_timezone(Local).unwrap().timestamp_millis()
+        };
+        let stored = serde_json::json!({
+            "id": "3f2b8c1e-0000-4000-8000-000000000000",
+            "name": "Mark in a month",
+            "entries": [
+                {"reference": "Mark 2", "due_date_ms": due("2024-02-29"), "completed": false},
+                {"reference": "Mark 1", "due_date_ms": due("2024-02-28"),
+                 "completed": true, "completed_at_ms": 10},
+                {"reference": "Mark 3", "due_date_ms": due("2024-02-29")},
+            ],
+            "created_at_ms": 5,
+        });
+        let StoredPlan::Legacy(legacy) = serde_json::from_value(stored).unwrap() else {
+            panic!("old plan read as current");
+        };
+        let mut plan = ReadingPlan::from(legacy);
+        assert_eq!(dates(&plan), ["2024-02-28", "2024-02-29", "2024-02-29"]);
+        assert_eq!(plan.days[0].status, ReadingStatus::Completed);
+        assert_eq!(plan.days[0].completed_at_ms, Some(10));
+        assert_eq!(plan.schedule.start_date.as_deref(), Some("2024-02-28"));
+        assert_eq!(plan.schedule.per_day, 2);
+
+        let today = today_reading(&plan, date("2024-02-29"));
+        assert_eq!(today.passages, ["Mark 2", "Mark 3"]);
+        complete_day(&mut plan, "2024-02-29", 20).unwrap();
+        assert_eq!(progress(&plan).remaining, 0);
+
+        // The upgraded plan reads back as current
+        let json = serde_json::to_value(&plan).unwrap();
+        assert!(matches!(
+            serde_json::from_value(json).unwrap(),
+            StoredPlan::Current(_)
+        ));
+    }
+
+    #[test]
+    fn test_reminder_due() {
+        let mut plan = plan(MissedDays::Shift);
+        let at = |s: &str| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap();
+        assert!(!reminder_due(&plan, at("2024-02-28 07:00")));
+        assert!(reminder_due(&plan, at("2024-02-28 09:15")));
+        plan.last_reminder_date = Some("2024-02-28".to_string());
+        assert!(!reminder_due(&plan, at("2024-02-28 09:15")));
+        complete_day(&mut plan, "2024-02-29", 10).unwrap();
+        assert!(!reminder_due(&plan, at("2024-02-29 09:15")));
+        // Nothing assigned after the plan ends
+        assert!(!reminder_due(&plan, at("2024-03-09 09:15")));
+    }
+}
@@ -0,0 +1,309 @@
+//! Red-letter (words of Jesus) data.
+//!
+//! The engine reports who is speaking in three ways, depending on its
+//! version: top-level `speaker_spans` (the shape of its `/spans` endpoint),
+//! a `speaker` on each verse block, or `is_red_letter` on tokens. The
+//! translate response is parsed into `SpeakerSpan`s once and cached with
+//! the passage; passages cached before that are parsed from the stored
+//! response on demand. When the engine says nothing about speakers the
+//! field is absent rather than an empty list, and nothing is highlighted.
+
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashSet};
+use tauri::AppHandle;
+
+use super::errors::AppError;
+use super::http;
+use super::passages::{load_all_passages, CachedPassage};
+use super::reference::resolve_book;
+use super::usfm::split_verse_id;
+
+/// Speaker whose words are printed in red
+pub const RED_LETTER_SPEAKER: &str = "Jesus";
+/// Legend closing Markdown documents that emphasize red-letter verses
+pub const MARKDOWN_LEGEND: &str = "*Words of Jesus are shown in **bold**.*";
+/// Engine endpoint listing every speech span (API routes are not /v1 prefixed)
+const SPANS_PATH: &str = "/spans";
+
+/// A run of verses spoken by one speaker.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpeakerSpan {
+    /// First verse, e.g. "John.3.16"
+    pub start_verse: String,
+    /// Last verse, inclusive
+    pub end_verse: String,
+    pub speaker: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f64>,
+    /// Where the attribution comes from, as the engine reports it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+impl SpeakerSpan {
+    fn verse(verse_id: &str, speaker: &str, confidence: Option<f64>, source: Option<&str>) -> Self {
+        Self {
+            start_verse: verse_id.to_string(),
+            end_verse: verse_id.to_string(),
+            speaker: speaker.to_string(),
+            confidence,
+            source: source.map(str::to_string),
+        }
+    }
+
+    pub fn is_red_letter(&self) -> bool {
+        self.speaker.eq_ignore_ascii_case(RED_LETTER_SPEAKER)
+    }
+
+    /// Whether the span includes a verse.
+    pub fn covers(&self, verse_id: &str) -> bool {
+        let (Some(start), Some(end), Some(verse)) = (
+            split_verse_id(&self.start_verse),
+            split_verse_id(&self.end_verse),
+            split_verse_id(verse_id),
+        ) else {
+            return self.start_verse == verse_id;
+        };
+        verse.0 == start.0
+            && (start.1, start.2) <= (verse.1, verse.2)
+            && (verse.1, verse.2) <= (end.1, end.2)
+    }
+}
+
+/// A span in the engine's `/spans` shape.
+#[derive(Debug, Deserialize)]
+struct EngineSpan {
+    book: String,
+    chapter: u32,
+    verse_start: u32,
+    verse_end: u32,
+    speaker: String,
+    #[serde(default)]
+    confidence: Option<f64>,
+    #[serde(default)]
+    source: Option<String>,
+}
+
+impl EngineSpan {
+    fn into_span(self) -> SpeakerSpan {
+        let book = resolve_book(&self.book).unwrap_or(&self.book).to_string();
+        let verse_id = |verse| format!("{}.{}.{}", book, self.chapter, verse);
+        SpeakerSpan {
+            start_verse: verse_id(self.verse_start),
+            end_verse: verse_id(self.verse_end.max(self.verse_start)),
+            speaker: self.speaker,
+            confidence: self.confidence,
+            source: self.source,
+        }
+    }
+}
+
+/// A verse block's `speaker`: a name, or an object with `speaker` (or
+/// `name`), `confidence` and `source`.
+fn block_speaker(verse_id: &str, speaker: &Value) -> Option<SpeakerSpan> {
+    match speaker {
+        Value::String(name) if !name.trim().is_empty() => {
+            Some(SpeakerSpan::verse(verse_id, name.trim(), None, None))
+        }
+        Value::Object(map) => {
+            let name = map
+                .get("speaker")
+                .or_else(|| map.get("name"))
+                .and_then(Value::as_str)?;
+            Some(SpeakerSpan::verse(
+                verse_id,
+                name,
+                map.get("confidence").and_then(Value::as_f64),
+                map.get("source").and_then(Value::as_str),
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Speaker spans in a translate response; `None` when it has none.
+pub fn parse_speaker_spans(response: &Value) -> Option<Vec<SpeakerSpan>> {
+    let mut spans: Vec<SpeakerSpan> = response
+        .get("speaker_spans")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|span| serde_json::from_value::<EngineSpan>(span.clone()).ok())
+        .map(EngineSpan::into_span)
+        .collect();
+
+    let blocks = response.get("verse_blocks").and_then(Value::as_array);
+    for block in blocks.into_iter().flatten() {
+        let Some(verse_id) = block.get("verse_id").and_then(Value::as_str) else {
+            continue;
+        };
+        if let Some(span) = block
+            .get("speaker")
+            .and_then(|s| block_speaker(verse_id, s))
+        {
+            spans.push(span);
+        } else if block
+            .get("tokens")
+            .and_then(Value::as_array)
+            .is_some_and(|tokens| {
+                tokens
+                    .iter()
+                    .any(|t| t.get("is_red_letter").and_then(Value::as_bool) == Some(true))
+            })
+        {
+            spans.push(SpeakerSpan::verse(
+                verse_id,
+                RED_LETTER_SPEAKER,
+                None,
+                Some("tokens"),
+            ));
+        }
+    }
+    (!spans.is_empty()).then_some(spans)
+}
+
+/// Speaker spans of a cached passage, parsing older cache entries.
+pub fn passage_speakers(passage: &CachedPassage) -> Option<Vec<SpeakerSpan>> {
+    passage
+        .speakers
+        .clone()
+        .or_else(|| parse_speaker_spans(&passage.response))
+}
+
+/// Ids of the passage's verses that contain words of Jesus.
+pub fn red_letter_verses(passage: &CachedPassage) -> HashSet<String> {
+    let Some(spans) = passage_speakers(passage) else {
+        return HashSet::new();
+    };
+    let red: Vec<&SpeakerSpan> = spans.iter().filter(|s| s.is_red_letter()).collect();
+    passage
+        .verses
+        .iter()
+        .filter(|v| red.iter().any(|s| s.covers(&v.verse_id)))
+        .map(|v| v.verse_id.clone())
+        .collect()
+}
+
+/// Verse ids in `book` that red-letter spans cover.
+fn spans_in_book(spans: &[SpeakerSpan], book: &str) -> Vec<String> {
+    let mut verses = Vec::new();
+    for span in spans.iter().filter(|s| s.is_red_letter()) {
+        let (Some(start), Some(end)) = (
+            split_verse_id(&span.start_verse),
+            split_verse_id(&span.end_verse),
+        ) else {
+            continue;
+        };
+        // Spans never cross chapters in the engine's data
+        if start.0 == book && end.0 == book && start.1 == end.1 {
+            verses.extend((start.2..=end.2).map(|v| format!("{}.{}.{}", book, start.1, v)));
+        }
+    }
+    verses
+}
+
+/// Verses in a book that contain words of Jesus, in canonical order.
+///
+/// Uses the cached translations, plus the engine's span table when `port`
+/// is given; if the engine can't be reached the cached data is returned.
+#[tauri::command]
+pub async fn get_red_letter_verses(
+    app: AppHandle,
+    port: Option<u16>,
+    book: String,
+) -> Result<Vec<String>, AppError> {
+    let book = resolve_book(&book).ok_or_else(|| format!("Unknown book: {}", book))?;
+
+    let mut verses: BTreeSet<(u32, u32)> = BTreeSet::new();
+    let mut add = |ids: Vec<String>| {
+        verses.extend(ids.iter().filter_map(|id| {
+            let (_, chapter, verse) = split_verse_id(id)?;
+            Some((chapter, verse))
+        }))
+    };
+    for passage in load_all_passages(&app)? {
+        let red = red_letter_verses(&passage);
+        add(red
+            .into_iter()
+            .filter(|id| split_verse_id(id).is_some_and(|(b, ..)| b == book))
+            .collect());
+    }
+
+    if let Some(port) = port {
+        let spans = async {
+            let request =
+                http::request(Method::GET, port, SPANS_PATH).map_err(|e| e.to_string())?;
+            http::send_json::<Vec<EngineSpan>>(request)
+                .await
+                .map_err(|e| e.to_string())
+        }
+        .await;
+        match spans {
+            Ok(spans) => {
+                let spans: Vec<SpeakerSpan> =
+                    spans.into_iter().map(EngineSpan::into_span).collect();
+                add(spans_in_book(&spans, book));
+            }
+            Err(e) => tracing::warn!("Using cached red-letter data only: {}", e),
+        }
+    }
+
+    Ok(verses
+        .into_iter()
+        .map(|(chapter, verse)| format!("{}.{}.{}", book, chapter, verse))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_speaker_spans() {
+        let response = serde_json::json!({
+            "speaker_spans": [{
+                "book": "Mark", "chapter": 1, "verse_start": 15, "verse_end": 17,
+                "speaker": "Jesus", "confidence": 0.9, "source": "curated"
+            }],
+            "verse_blocks": [
+                { "verse_id": "Mark.1.14", "speaker": "narrator" },
+                { "verse_id": "Mark.1.15", "tokens": [{ "is_red_letter": true }] },
+                { "verse_id": "Mark.1.18", "speaker": { "name": "Jesus", "confidence": 0.5 } },
+            ]
+        });
+        let spans = parse_speaker_spans(&response).unwrap();
+        assert_eq!(spans.len(), 4);
+        assert_eq!(spans[0].start_verse, "Mark.1.15");
+        assert_eq!(spans[0].end_verse, "Mark.1.17");
+        assert!(spans[0].covers("Mark.1.16"));
+        assert!(!spans[0].covers("Mark.1.18") && !spans[0].covers("Mark.2.16"));
+        assert!(!spans[1].is_red_letter());
+        assert_eq!(spans[2].source.as_deref(), Some("tokens"));
+        assert_eq!(spans[3].confidence, Some(0.5));
+
+        let silent = serde_json::json!({
+            "verse_blocks": [{ "verse_id": "Mark.1.1", "tokens": [{ "is_red_letter": false }] }]
+        });
+        assert_eq!(parse_speaker_spans(&silent), None);
+        assert_eq!(parse_speaker_spans(&Value::Null), None);
+    }
+
+    #[test]
+    fn test_spans_in_book() {
+        let spans = vec![
+            SpeakerSpan::verse("John.3.16", "Jesus", None, None),
+            SpeakerSpan {
+                end_verse: "John.4.3".to_string(),
+                ..SpeakerSpan::verse("John.4.1", "Jesus", None, None)
+            },
+            SpeakerSpan::verse("John.3.17", "narrator", None, None),
+            SpeakerSpan::verse("Mark.1.15", "Jesus", None, None),
+        ];
+        assert_eq!(
+            spans_in_book(&spans, "John"),
+            ["John.3.16", "John.4.1", "John.4.2", "John.4.3"]
+        );
+    }
+}
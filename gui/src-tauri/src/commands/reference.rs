@@ -0,0 +1,1707 @@
+//! Verse reference parsing.
+//!
+//! Book names follow the engine's canonical NT book ids (see
+//! `redletters.pipeline.passage_ref`), with the common abbreviations and
+//! the German, Spanish, French and Greek names. Accepts verse and chapter
+//! ranges ("Jn 3.16-18", "John 3:16-4:2", "1 Cor 13-14"), en-dash ranges,
+//! whole books ("Jude") and lists ("John 3:16, 18; 4:1; Rom 5:8").
+//!
+//! In a list, a bare number after a comma continues the verses of the
+//! previous item and after a semicolon names a chapter. In one-chapter
+//! books a bare number is always a verse, so "Jude 3" is Jude 1:3.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::ops::Range;
+use thiserror::Error;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+use super::errors::{command_error, AppError, CommandError};
+use super::i18n::{self, Localize};
+
+/// Canonical id, display name, USFM code, OSIS id, aliases and verse
+/// counts of a book.
+struct Book {
+    id: &'static str,
+    display: &'static str,
+    usfm: &'static str,
+    osis: &'static str,
+    /// As `book_key` produces them: lowercase, no spaces or accents
+    aliases: &'static [&'static str],
+    /// Highest verse number of each chapter. Where editions number a
+    /// chapter differently the larger count is used, so no edition's
+    /// references are rejected.
+    verses: &'static [u32],
+}
+
+const fn book(
+    id: &'static str,
+    display: &'static str,
+    usfm: &'static str,
+    osis: &'static str,
+    aliases: &'static [&'static str],
+    verses: &'static [u32],
+) -> Book {
+    Book {
+        id,
+        display,
+        usfm,
+        osis,
+        aliases,
+        verses,
+    }
+}
+
+/// NT books in canonical order
+const BOOKS: &[Book] = &[
+    book(
+        "Matthew",
+        "Matthew",
+        "MAT",
+        "Matt",
+        &[
+            "matt",
+            "mat",
+            "mt",
+            "matthaus",
+            "mateo",
+            "matthieu",
+            "ματθαιον",
+            "ματθαιοσ",
+        ],
+        &[
+            25, 23, 17, 25, 48, 34, 29, 34, 38, 42, 30, 50, 58, 36, 39, 28, 27, 35, 30, 34, 46, 46,
+            39, 51, 46, 75, 66, 20,
+        ],
+    ),
+    book(
+        "Mark",
+        "Mark",
+        "MRK",
+        "Mark",
+        &["mk", "mr", "markus", "marcos", "marc", "μαρκον", "μαρκοσ"],
+        &[
+            45, 28, 35, 41, 43, 56, 37, 38, 50, 52, 33, 44, 37, 72, 47, 20,
+        ],
+    ),
+    book(
+        "Luke",
+        "Luke",
+        "LUK",
+        "Luke",
+        &["lk", "luk", "lukas", "lucas", "luc", "λουκαν", "λουκασ"],
+        &[
+            80, 52, 38, 44, 39, 49, 50, 56, 62, 42, 54, 59, 35, 35, 32, 31, 37, 43, 48, 47, 38, 71,
+            56, 53,
+        ],
+    ),
+    book(
+        "John",
+        "John",
+        "JHN",
+        "John",
+        &[
+            "jn",
+            "jhn",
+            "joh",
+            "johannes",
+            "juan",
+            "jean",
+            "ιωαννην",
+            "ιωαννησ",
+        ],
+        &[
+            51, 25, 36, 54, 47, 71, 53, 59, 41, 42, 57, 50, 38, 31, 27, 33, 26, 40, 42, 31, 25,
+        ],
+    ),
+    book(
+        "Acts",
+        "Acts",
+        "ACT",
+        "Acts",
+        &[
+            "ac",
+            "act",
+            "apostelgeschichte",
+            "apg",
+            "hechos",
+            "hch",
+            "actes",
+            "πραξεισ",
+        ],
+        &[
+            26, 47, 26, 37, 42, 15, 60, 40, 43, 48, 30, 25, 52, 28, 41, 40, 34, 28, 41, 38, 40, 30,
+            35, 27, 27, 32, 44, 31,
+        ],
+    ),
+    book(
+        "Romans",
+        "Romans",
+        "ROM",
+        "Rom",
+        &["rom", "rm", "ro", "romer", "romanos", "romains", "ρωμαιουσ"],
+        &[
+            32, 29, 31, 25, 21, 23, 25, 39, 33, 21, 36, 21, 14, 23, 33, 27,
+        ],
+    ),
+    book(
+        "1Corinthians",
+        "1 Corinthians",
+        "1CO",
+        "1Cor",
+        &[
+            "1cor",
+            "1co",
+            "1korinther",
+            "1kor",
+            "1corintios",
+            "1corinthiens",
+            "1κορινθιουσ",
+        ],
+        &[
+            31, 16, 23, 21, 13, 20, 40, 13, 27, 33, 34, 31, 13, 40, 58, 24,
+        ],
+    ),
+    book(
+        "2Corinthians",
+        "2 Corinthians",
+        "2CO",
+        "2Cor",
+        &[
+            "2cor",
+            "2co",
+            "2korinther",
+            "2kor",
+            "2corintios",
+            "2corinthiens",
+            "2κορινθιουσ",
+        ],
+        &[24, 17, 18, 18, 21, 18, 16, 24, 15, 18, 33, 21, 14],
+    ),
+    book(
+        "Galatians",
+        "Galatians",
+        "GAL",
+        "Gal",
+        &["gal", "ga", "galater", "galatas", "galates", "γαλατασ"],
+        &[24, 21, 29, 31, 26, 18],
+    ),
+    book(
+        "Ephesians",
+        "Ephesians",
+        "EPH",
+        "Eph",
+        &[
+            "eph",
+            "ep",
+            "epheser",
+            "efesios",
+            "ef",
+            "ephesiens",
+            "εφεσιουσ",
+        ],
+        &[23, 22, 21, 32, 33, 24],
+    ),
+    book(
+        "Philippians",
+        "Philippians",
+        "PHP",
+        "Phil",
+        &[
+            "phil",
+            "php",
+            "pp",
+            "philipper",
+            "filipenses",
+            "flp",
+            "philippiens",
+            "φιλιππησιουσ",
+        ],
+        &[30, 30, 21, 23],
+    ),
+    book(
+        "Colossians",
+        "Colossians",
+        "COL",
+        "Col",
+        &[
+            "col",
+            "kolosser",
+            "kol",
+            "colosenses",
+            "colossiens",
+            "κολοσσαεισ",
+        ],
+        &[29, 23, 25, 18],
+    ),
+    book(
+        "1Thessalonians",
+        "1 Thessalonians",
+        "1TH",
+        "1Thess",
+        &[
+            "1thess",
+            "1th",
+            "1thessalonicher",
+            "1tesalonicenses",
+            "1thessaloniciens",
+            "1θεσσαλονικεισ",
+        ],
+        &[10, 20, 13, 18, 28],
+    ),
+    book(
+        "2Thessalonians",
+        "2 Thessalonians",
+        "2TH",
+        "2Thess",
+        &[
+            "2thess",
+            "2th",
+            "2thessalonicher",
+            "2tesalonicenses",
+            "2thessaloniciens",
+            "2θεσσαλονικεισ",
+        ],
+        &[12, 17, 18],
+    ),
+    book(
+        "1Timothy",
+        "1 Timothy",
+        "1TI",
+        "1Tim",
+        &[
+            "1tim",
+            "1ti",
+            "1timotheus",
+            "1timoteo",
+            "1timothee",
+            "1τιμοθεον",
+        ],
+        &[20, 15, 16, 16, 25, 21],
+    ),
+    book(
+        "2Timothy",
+        "2 Timothy",
+        "2TI",
+        "2Tim",
+        &[
+            "2tim",
+            "2ti",
+            "2timotheus",
+            "2timoteo",
+            "2timothee",
+            "2τιμοθεον",
+        ],
+        &[18, 26, 17, 22],
+    ),
+    book(
+        "Titus",
+        "Titus",
+        "TIT",
+        "Titus",
+        &["tit", "ti", "tito", "tite", "τιτον"],
+        &[16, 15, 15],
+    ),
+    book(
+        "Philemon",
+        "Philemon",
+        "PHM",
+        "Phlm",
+        &["phlm", "phm", "philem", "filemon", "φιλημονα"],
+        &[25],
+    ),
+    book(
+        "Hebrews",
+        "Hebrews",
+        "HEB",
+        "Heb",
+        &["heb", "hebraer", "hebr", "hebreos", "hebreux", "εβραιουσ"],
+        &[14, 18, 19, 16, 14, 20, 28, 13, 28, 39, 40, 29, 25],
+    ),
+    book(
+        "James",
+        "James",
+        "JAS",
+        "Jas",
+        &[
+            "jas",
+            "jm",
+            "jakobus",
+            "jak",
+            "santiago",
+            "stg",
+            "jacques",
+            "ιακωβου",
+        ],
+        &[27, 26, 18, 17, 20],
+    ),
+    book(
+        "1Peter",
+        "1 Peter",
+        "1PE",
+        "1Pet",
+        &[
+            "1pet",
+            "1pe",
+            "1pt",
+            "1petrus",
+            "1pedro",
+            "1pierre",
+            "1πετρου",
+        ],
+        &[25, 25, 22, 19, 14],
+    ),
+    book(
+        "2Peter",
+        "2 Peter",
+        "2PE",
+        "2Pet",
+        &[
+            "2pet",
+            "2pe",
+            "2pt",
+            "2petrus",
+            "2pedro",
+            "2pierre",
+            "2πετρου",
+        ],
+        &[21, 22, 18],
+    ),
+    book(
+        "1John",
+        "1 John",
+        "1JN",
+        "1John",
+        &[
+            "1jn",
+            "1jo",
+            "1jhn",
+            "1johannes",
+            "1juan",
+            "1jean",
+            "1ιωαννου",
+        ],
+        &[10, 29, 24, 21, 21],
+    ),
+    book(
+        "2John",
+        "2 John",
+        "2JN",
+        "2John",
+        &[
+            "2jn",
+            "2jo",
+            "2jhn",
+            "2johannes",
+            "2juan",
+            "2jean",
+            "2ιωαννου",
+        ],
+        &[13],
+    ),
+    book(
+        "3John",
+        "3 John",
+        "3JN",
+        "3John",
+        &[
+            "3jn",
+            "3jo",
+            "3jhn",
+            "3johannes",
+            "3juan",
+            "3jean",
+            "3ιωαννου",
+        ],
+        &[15],
+    ),
+    book(
+        "Jude",
+        "Jude",
+        "JUD",
+        "Jude",
+        &["jud", "jd", "judas", "ιουδα"],
+        &[25],
+    ),
+    book(
+        "Revelation",
+        "Revelation",
+        "REV",
+        "Rev",
+        &[
+            "rev",
+            "re",
+            "rv",
+            "offenbarung",
+            "offb",
+            "apocalipsis",
+            "apocalypse",
+            "apoc",
+            "αποκαλυψισ",
+        ],
+        &[
+            20, 29, 22, 11, 14, 17, 17, 13, 21, 11, 19, 18, 18, 20, 8, 21, 18, 24, 21, 15, 27, 21,
+        ],
+    ),
+];
+
+/// Where in the input an error is, as UTF-16 offsets so they index a
+/// JavaScript string directly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    fn new(input: &str, range: Range<usize>) -> Self {
+        let utf16 = |i: usize| input[..i].encode_utf16().count();
+        Span {
+            start: utf16(range.start),
+            end: utf16(range.end),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ReferenceError {
+    #[error("Empty reference")]
+    Empty,
+    #[error("Unknown book: {text}")]
+    UnknownBook { text: String, span: Span },
+    #[error("No book given for \"{text}\"")]
+    MissingBook { text: String, span: Span },
+    #[error("Invalid chapter or verse: \"{text}\"")]
+    InvalidNumber { text: String, span: Span },
+    #[error("Invalid range: \"{text}\"")]
+    InvalidRange { text: String, span: Span },
+    #[error("{book} has no chapter {chapter}")]
+    ChapterOutOfRange {
+        book: String,
+        chapter: u32,
+        span: Span,
+    },
+    #[error("{book} {chapter} has no verse {verse}")]
+    VerseOutOfRange {
+        book: String,
+        chapter: u32,
+        verse: u32,
+        span: Span,
+    },
+}
+
+impl ReferenceError {
+    /// Offending part of the input; `None` for an empty reference.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            ReferenceError::Empty => None,
+            ReferenceError::UnknownBook { span, .. }
+            | ReferenceError::MissingBook { span, .. }
+            | ReferenceError::InvalidNumber { span, .. }
+            | ReferenceError::InvalidRange { span, .. }
+            | ReferenceError::ChapterOutOfRange { span, .. }
+            | ReferenceError::VerseOutOfRange { span, .. } => Some(*span),
+        }
+    }
+}
+
+impl Localize for ReferenceError {
+    fn code(&self) -> &'static str {
+        match self {
+            ReferenceError::Empty => "reference-empty",
+            ReferenceError::UnknownBook { .. } => "reference-unknown-book",
+            ReferenceError::MissingBook { .. } => "reference-missing-book",
+            ReferenceError::InvalidNumber { .. } => "reference-invalid-number",
+            ReferenceError::InvalidRange { .. } => "reference-invalid-range",
+            ReferenceError::ChapterOutOfRange { .. } => "reference-chapter-out-of-range",
+            ReferenceError::VerseOutOfRange { .. } => "reference-verse-out-of-range",
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        let mut params = match self {
+            ReferenceError::Empty => Vec::new(),
+            ReferenceError::UnknownBook { text, .. }
+            | ReferenceError::MissingBook { text, .. }
+            | ReferenceError::InvalidNumber { text, .. }
+            | ReferenceError::InvalidRange { text, .. } => vec![("text", text.clone())],
+            ReferenceError::ChapterOutOfRange { book, chapter, .. } => {
+                vec![("book", book.clone()), ("chapter", chapter.to_string())]
+            }
+            ReferenceError::VerseOutOfRange {
+                book,
+                chapter,
+                verse,
+                ..
+            } => vec![
+                ("book", book.clone()),
+                ("chapter", chapter.to_string()),
+                ("verse", verse.to_string()),
+            ],
+        };
+        if let Some(span) = self.span() {
+            params.push(("start", span.start.to_string()));
+            params.push(("end", span.end.to_string()));
+        }
+        params
+    }
+}
+
+impl CommandError for ReferenceError {}
+
+command_error!(ReferenceError);
+
+/// A run of verses in one book. Both verses are `None` for whole chapters.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReferenceRange {
+    /// Canonical book id, e.g. "1Corinthians"
+    pub book: String,
+    pub start_chapter: u32,
+    pub start_verse: Option<u32>,
+    pub end_chapter: u32,
+    pub end_verse: Option<u32>,
+}
+
+impl ReferenceRange {
+    /// (chapter, first verse, last verse) for each chapter covered.
+    fn chapters(&self) -> Vec<(u32, u32, u32)> {
+        let counts = book_by_id(&self.book).map_or(&[][..], |b| b.verses);
+        (self.start_chapter..=self.end_chapter)
+            .map(|chapter| {
+                let last = counts.get(chapter as usize - 1).copied().unwrap_or(0);
+                let first = match self.start_verse {
+                    Some(verse) if chapter == self.start_chapter => verse,
+                    _ => 1,
+                };
+                let last = match self.end_verse {
+                    Some(verse) if chapter == self.end_chapter => verse,
+                    _ => last,
+                };
+                (chapter, first, last)
+            })
+            .collect()
+    }
+
+    /// "3:16-18", "3:16-4:2" or "3-4" with `dash` between the ends;
+    /// without the leading chapter when continuing verses of the same
+    /// chapter ("18-20").
+    fn location(&self, with_chapter: bool, dash: &str) -> String {
+        let (Some(start), Some(end)) = (self.start_verse, self.end_verse) else {
+            return if self.end_chapter == self.start_chapter {
+                self.start_chapter.to_string()
+            } else {
+                format!("{}{}{}", self.start_chapter, dash, self.end_chapter)
+            };
+        };
+        let mut location = if with_chapter {
+            format!("{}:{}", self.start_chapter, start)
+        } else {
+            start.to_string()
+        };
+        if self.end_chapter != self.start_chapter {
+            location.push_str(&format!("{}{}:{}", dash, self.end_chapter, end));
+        } else if end != start {
+            location.push_str(&format!("{}{}", dash, end));
+        }
+        location
+    }
+}
+
+/// How `format_reference` writes book names, ranges and lists.
+#[derive(Debug, Clone, Copy)]
+pub struct ReferenceFormat<'a> {
+    /// SBL abbreviations ("1 Cor") instead of full names
+    pub abbreviate_books: bool,
+    pub range_dash: &'a str,
+    /// Before more verses of the same chapter
+    pub verse_separator: &'a str,
+    /// Before another chapter or book
+    pub chapter_separator: &'a str,
+}
+
+/// The parser's own output format
+const CANONICAL: ReferenceFormat<'static> = ReferenceFormat {
+    abbreviate_books: false,
+    range_dash: "-",
+    verse_separator: ", ",
+    chapter_separator: "; ",
+};
+
+/// A parsed and normalized reference or list of references.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ParsedReference {
+    /// e.g. "John 3:16-18, 20; 4:1"
+    pub canonical: String,
+    pub ranges: Vec<ReferenceRange>,
+    /// Engine verse ids in input order without repeats, e.g. "John.3.16"
+    pub verses: Vec<String>,
+}
+
+impl ParsedReference {
+    /// One reference per chapter with explicit verses, the form the
+    /// engine translates: "John 3; Jude 3" gives "John 3:1-36", "Jude 1:3".
+    pub fn chapter_passages(&self) -> Vec<String> {
+        let mut passages = Vec::new();
+        let mut pieces: Vec<ReferenceRange> = Vec::new();
+        for range in &self.ranges {
+            for (chapter, first, last) in range.chapters() {
+                let same_chapter = pieces
+                    .last()
+                    .is_some_and(|p| p.book == range.book && p.start_chapter == chapter);
+                if !same_chapter && !pieces.is_empty() {
+                    passages.push(format_reference(&pieces, &CANONICAL));
+                    pieces.clear();
+                }
+                pieces.push(ReferenceRange {
+                    book: range.book.clone(),
+                    start_chapter: chapter,
+                    start_verse: Some(first),
+                    end_chapter: chapter,
+                    end_verse: Some(last),
+                });
+            }
+        }
+        if !pieces.is_empty() {
+            passages.push(format_reference(&pieces, &CANONICAL));
+        }
+        passages
+    }
+}
+
+fn book_by_id(id: &str) -> Option<&'static Book> {
+    BOOKS.iter().find(|b| b.id == id)
+}
+
+/// Lowercase without accents, spaces or dots, with final sigma as σ; a
+/// leading roman numeral becomes a digit.
+fn book_key(name: &str) -> String {
+    let lower = name
+        .trim()
+        .nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .collect::<String>()
+        .to_lowercase();
+    let lower = ["iii ", "ii ", "i "]
+        .iter()
+        .zip(["3", "2", "1"])
+        .find_map(|(roman, digit)| {
+            lower
+                .strip_prefix(roman)
+                .map(|rest| format!("{}{}", digit, rest))
+        })
+        .unwrap_or(lower);
+    lower
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '.')
+        .map(|c| if c == 'ς' { 'σ' } else { c })
+        .collect()
+}
+
+fn lookup_book(name: &str) -> Option<&'static Book> {
+    let key = book_key(name);
+    BOOKS.iter().find(|b| {
+        book_key(b.id) == key
+            || book_key(b.display) == key
+            || b.aliases.iter().any(|alias| *alias == key)
+    })
+}
+
+/// Resolve a book name or abbreviation to its canonical id.
+pub fn resolve_book(name: &str) -> Option<&'static str> {
+    lookup_book(name).map(|b| b.id)
+}
+
+/// USFM book code for a canonical book id, e.g. "John" -> "JHN".
+pub fn usfm_book_code(book: &str) -> Option<&'static str> {
+    book_by_id(book).map(|b| b.usfm)
+}
+
+/// Canonical book id for a USFM book code, e.g. "JHN" -> "John".
+pub fn book_for_usfm_code(code: &str) -> Option<&'static str> {
+    BOOKS
+        .iter()
+        .find(|b| b.usfm.eq_ignore_ascii_case(code))
+        .map(|b| b.id)
+}
+
+/// Display name for a canonical book id, e.g. "1Corinthians" -> "1 Corinthians".
+pub fn book_display_name(book: &str) -> Option<&'static str> {
+    book_by_id(book).map(|b| b.display)
+}
+
+/// Every book id with the verse count of each of its chapters, in
+/// canonical order.
+pub fn book_verse_counts() -> impl Iterator<Item = (&'static str, &'static [u32])> {
+    BOOKS.iter().map(|b| (b.id, b.verses))
+}
+
+/// Canonical position of a book (Matthew = 0), for sorting.
+pub fn book_position(book: &str) -> Option<usize> {
+    BOOKS.iter().position(|b| b.id == book)
+}
+
+/// OSIS book id for a canonical book id, e.g. "1Corinthians" -> "1Cor".
+pub fn osis_book_id(book: &str) -> Option<&'static str> {
+    book_by_id(book).map(|b| b.osis)
+}
+
+/// SBL abbreviation for a canonical book id, e.g. "1Corinthians" -> "1 Cor".
+/// These are the OSIS ids with a space after the book number.
+pub fn book_abbreviation(book: &str) -> Option<String> {
+    let osis = book_by_id(book)?.osis;
+    Some(match osis.strip_prefix(|c: char| c.is_ascii_digit()) {
+        Some(rest) => format!("{} {}", &osis[..1], rest),
+        None => osis.to_string(),
+    })
+}
+
+/// Text of ranges. Verses continuing the previous range's chapter follow
+/// the verse separator; other ranges of the same book the chapter
+/// separator without the book name. A whole book is always named, since
+/// "Jude 3; 1" would read back as Jude 1:1.
+pub fn format_reference(ranges: &[ReferenceRange], format: &ReferenceFormat) -> String {
+    let mut out = String::new();
+    let mut previous: Option<&ReferenceRange> = None;
+    for range in ranges {
+        let book = book_by_id(&range.book);
+        let whole_book = book.is_some_and(|b| {
+            range.start_verse.is_none()
+                && range.start_chapter == 1
+                && range.end_chapter as usize == b.verses.len()
+        });
+        match previous {
+            Some(p)
+                if p.book == range.book
+                    && p.end_verse.is_some()
+                    && range.start_verse.is_some()
+                    && range.start_chapter == p.end_chapter =>
+            {
+                out.push_str(format.verse_separator);
+                out.push_str(&range.location(false, format.range_dash));
+            }
+            Some(p) if p.book == range.book && !whole_book => {
+                out.push_str(format.chapter_separator);
+                out.push_str(&range.location(true, format.range_dash));
+            }
+            _ => {
+                if previous.is_some() {
+                    out.push_str(format.chapter_separator);
+                }
+                let name = match book {
+                    Some(_) if format.abbreviate_books => book_abbreviation(&range.book),
+                    Some(b) => Some(b.display.to_string()),
+                    None => None,
+                };
+                out.push_str(name.as_deref().unwrap_or(&range.book));
+                if !whole_book {
+                    out.push(' ');
+                    out.push_str(&range.location(true, format.range_dash));
+                }
+            }
+        }
+        previous = Some(range);
+    }
+    out
+}
+
+/// (book, chapter, verse) of each verse in input order, without repeats.
+fn verse_points(ranges: &[ReferenceRange]) -> Vec<(&str, u32, u32)> {
+    let mut seen = HashSet::new();
+    let mut points = Vec::new();
+    for range in ranges {
+        for (chapter, first, last) in range.chapters() {
+            for verse in first..=last {
+                if seen.insert((range.book.as_str(), chapter, verse)) {
+                    points.push((range.book.as_str(), chapter, verse));
+                }
+            }
+        }
+    }
+    points
+}
+
+fn verse_ids(ranges: &[ReferenceRange]) -> Vec<String> {
+    verse_points(ranges)
+        .into_iter()
+        .map(|(book, chapter, verse)| format!("{}.{}.{}", book, chapter, verse))
+        .collect()
+}
+
+/// Ranges covering exactly `points`, joining runs of consecutive verses,
+/// also across the end of a chapter.
+fn ranges_of(points: &[(&str, u32, u32)]) -> Vec<ReferenceRange> {
+    let mut ranges: Vec<ReferenceRange> = Vec::new();
+    for &(book, chapter, verse) in points {
+        if let Some(last) = ranges.last_mut().filter(|r| r.book == book) {
+            let end_verse = last.end_verse.unwrap_or(0);
+            let chapter_length = book_by_id(book)
+                .and_then(|b| b.verses.get(last.end_chapter as usize - 1))
+                .copied();
+            let follows = (chapter == last.end_chapter && verse == end_verse + 1)
+                || (chapter == last.end_chapter + 1
+                    && verse == 1
+                    && chapter_length == Some(end_verse));
+            if follows {
+                last.end_chapter = chapter;
+                last.end_verse = Some(verse);
+                continue;
+            }
+        }
+        ranges.push(ReferenceRange {
+            book: book.to_string(),
+            start_chapter: chapter,
+            start_verse: Some(verse),
+            end_chapter: chapter,
+            end_verse: Some(verse),
+        });
+    }
+    ranges
+}
+
+/// `range` without surrounding whitespace.
+fn trimmed(input: &str, range: Range<usize>) -> Range<usize> {
+    let text = &input[range.clone()];
+    let start = range.start + (text.len() - text.trim_start().len());
+    let end = range.end - (text.len() - text.trim_end().len());
+    start..end.max(start)
+}
+
+fn parse_number(input: &str, range: Range<usize>) -> Result<u32, ReferenceError> {
+    let range = trimmed(input, range);
+    let text = &input[range.clone()];
+    text.parse::<u32>()
+        .ok()
+        .filter(|n| *n > 0)
+        .ok_or_else(|| ReferenceError::InvalidNumber {
+            text: text.to_string(),
+            span: Span::new(input, range),
+        })
+}
+
+/// "chapter:verse" (or "chapter.verse") or a bare number.
+struct Point {
+    chapter: Option<u32>,
+    number: u32,
+    range: Range<usize>,
+}
+
+fn parse_point(input: &str, range: Range<usize>) -> Result<Point, ReferenceError> {
+    let range = trimmed(input, range);
+    let point = match input[range.clone()].find([':', '.']) {
+        Some(i) => Point {
+            chapter: Some(parse_number(input, range.start..range.start + i)?),
+            number: parse_number(input, range.start + i + 1..range.end)?,
+            range,
+        },
+        None => Point {
+            chapter: None,
+            number: parse_number(input, range.clone())?,
+            range,
+        },
+    };
+    Ok(point)
+}
+
+fn check_point(
+    input: &str,
+    book: &Book,
+    chapter: u32,
+    verse: Option<u32>,
+    range: &Range<usize>,
+) -> Result<(), ReferenceError> {
+    let span = Span::new(input, range.clone());
+    let Some(last) = book.verses.get(chapter as usize - 1) else {
+        return Err(ReferenceError::ChapterOutOfRange {
+            book: book.display.to_string(),
+            chapter,
+            span,
+        });
+    };
+    match verse {
+        Some(verse) if verse > *last => Err(ReferenceError::VerseOutOfRange {
+            book: book.display.to_string(),
+            chapter,
+            verse,
+            span,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Book and chapter the previous list item ended in.
+struct Context {
+    book: &'static Book,
+    chapter: u32,
+    /// The item named verses, so a bare number after a comma is a verse
+    verses: bool,
+}
+
+fn parse_item(
+    input: &str,
+    item: Range<usize>,
+    context: Option<&Context>,
+    after_semicolon: bool,
+) -> Result<(ReferenceRange, Context), ReferenceError> {
+    let text = &input[item.clone()];
+    // The book runs to the first digit after its first letter, so the
+    // number of "1 Cor" stays part of it
+    let book_end = text
+        .char_indices()
+        .find(|(_, c)| c.is_alphabetic())
+        .map(|(alpha, _)| {
+            text[alpha..]
+                .find(|c: char| c.is_ascii_digit())
+                .map_or(text.len(), |i| alpha + i)
+        });
+    let (book, context, location) = match (book_end, context) {
+        (Some(end), _) => {
+            let name = trimmed(input, item.start..item.start + end);
+            let book =
+                lookup_book(&input[name.clone()]).ok_or_else(|| ReferenceError::UnknownBook {
+                    text: input[name.clone()].to_string(),
+                    span: Span::new(input, name),
+                })?;
+            (book, None, trimmed(input, item.start + end..item.end))
+        }
+        (None, Some(context)) => (context.book, Some(context), item),
+        (None, None) => {
+            return Err(ReferenceError::MissingBook {
+                text: text.to_string(),
+                span: Span::new(input, item),
+            })
+        }
+    };
+
+    let chapters = book.verses.len() as u32;
+    if location.is_empty() {
+        let range = ReferenceRange {
+            book: book.id.to_string(),
+            start_chapter: 1,
+            start_verse: None,
+            end_chapter: chapters,
+            end_verse: None,
+        };
+        let context = Context {
+            book,
+            chapter: chapters,
+            verses: false,
+        };
+        return Ok((range, context));
+    }
+
+    let location_text = &input[location.clone()];
+    let mut dashes = location_text.match_indices(['-', '\u{2013}', '\u{2014}']);
+    let (start_range, end_range) = match dashes.next() {
+        None => (location.clone(), None),
+        Some(_) if dashes.next().is_some() => {
+            return Err(ReferenceError::InvalidRange {
+                text: location_text.to_string(),
+                span: Span::new(input, location),
+            })
+        }
+        Some((i, dash)) => {
+            let i = location.start + i;
+            (location.start..i, Some(i + dash.len()..location.end))
+        }
+    };
+
+    // Chapter a bare number is a verse of, if any
+    let verse_chapter = if chapters == 1 {
+        Some(1)
+    } else {
+        context
+            .filter(|c| c.verses && !after_semicolon)
+            .map(|c| c.chapter)
+    };
+    let start = parse_point(input, start_range)?;
+    let (start_chapter, start_verse) = match (start.chapter, verse_chapter) {
+        (Some(chapter), _) | (None, Some(chapter)) => (chapter, Some(start.number)),
+        (None, None) => (start.number, None),
+    };
+    check_point(input, book, start_chapter, start_verse, &start.range)?;
+
+    let (end_chapter, end_verse) = match end_range {
+        None => (start_chapter, start_verse),
+        Some(end_range) => {
+            let end = parse_point(input, end_range)?;
+            let (chapter, verse) = match end.chapter {
+                Some(chapter) => (chapter, Some(end.number)),
+                None if start_verse.is_some() => (start_chapter, Some(end.number)),
+                None => (end.number, None),
+            };
+            check_point(input, book, chapter, verse, &end.range)?;
+            (chapter, verse)
+        }
+    };
+    // "3-4:2" runs from the start of chapter 3
+    let start_verse = start_verse.or(end_verse.map(|_| 1));
+    if (end_chapter, end_verse.unwrap_or(u32::MAX)) < (start_chapter, start_verse.unwrap_or(0)) {
+        return Err(ReferenceError::InvalidRange {
+            text: location_text.to_string(),
+            span: Span::new(input, location),
+        });
+    }
+
+    let range = ReferenceRange {
+        book: book.id.to_string(),
+        start_chapter,
+        start_verse,
+        end_chapter,
+        end_verse,
+    };
+    let context = Context {
+        book,
+        chapter: end_chapter,
+        verses: end_verse.is_some(),
+    };
+    Ok((range, context))
+}
+
+/// Parse a reference or a comma/semicolon separated list of them.
+pub fn parse_reference_list(input: &str) -> Result<ParsedReference, ReferenceError> {
+    let mut ranges = Vec::new();
+    let mut context: Option<Context> = None;
+    let mut after_semicolon = false;
+    let mut start = 0;
+    let separators = input
+        .match_indices([',', ';'])
+        .chain(std::iter::once((input.len(), "")));
+    for (i, separator) in separators {
+        let item = trimmed(input, start..i);
+        if !item.is_empty() {
+            let (range, next) = parse_item(input, item, context.as_ref(), after_semicolon)?;
+            ranges.push(range);
+            context = Some(next);
+        }
+        after_semicolon = separator == ";";
+        start = i + separator.len();
+    }
+    if ranges.is_empty() {
+        return Err(ReferenceError::Empty);
+    }
+
+    Ok(ParsedReference {
+        canonical: format_reference(&ranges, &CANONICAL),
+        verses: verse_ids(&ranges),
+        ranges,
+    })
+}
+
+/// Reference in the parser's canonical form, so "Jn 3.16" matches
+/// "John 3:16". Input the parser rejects is only trimmed.
+pub fn canonical_reference(reference: &str) -> String {
+    parse_reference_list(reference)
+        .map_or_else(|_| reference.trim().to_string(), |parsed| parsed.canonical)
+}
+
+/// Parse a reference or list of references into its canonical form and
+/// the verses it covers. Errors carry the offending span of the input.
+#[tauri::command]
+pub fn parse_reference(input: String) -> Result<ParsedReference, ReferenceError> {
+    parse_reference_list(&input)
+}
+
+/// Whether a single reference names an existing place, for checking input
+/// as it is typed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReferenceValidation {
+    pub valid: bool,
+    /// Canonical form, e.g. "John 3:16" for "Ἰωάννης 3.16"
+    pub normalized: Option<String>,
+    /// Canonical book id, e.g. "1Corinthians"
+    pub book_id: Option<String>,
+    /// Absent for a whole book
+    pub chapter: Option<u32>,
+    /// Absent for a whole chapter
+    pub verse: Option<u32>,
+    /// Localized message when not valid
+    pub error: Option<String>,
+}
+
+fn validate_reference(reference: &str) -> ReferenceValidation {
+    let invalid = |error: String| ReferenceValidation {
+        valid: false,
+        normalized: None,
+        book_id: None,
+        chapter: None,
+        verse: None,
+        error: Some(error),
+    };
+    let parsed = match parse_reference_list(reference) {
+        Ok(parsed) => parsed,
+        Err(e) => return invalid(i18n::localized_message(&e)),
+    };
+    let [range] = parsed.ranges.as_slice() else {
+        return invalid("Expected a single reference, not a list".to_string());
+    };
+    let whole_book = range.start_verse.is_none()
+        && range.start_chapter == 1
+        && book_by_id(&range.book).is_some_and(|b| range.end_chapter as usize == b.verses.len());
+    ReferenceValidation {
+        valid: true,
+        normalized: Some(parsed.canonical.clone()),
+        book_id: Some(range.book.clone()),
+        chapter: (!whole_book).then_some(range.start_chapter),
+        verse: range.start_verse,
+        error: None,
+    }
+}
+
+/// Check a reference such as "John 3:16" or "Ἰωάννης 3:16" against the
+/// book, chapter and verse counts. Ranges are valid and report where they
+/// start.
+#[tauri::command]
+pub fn validate_scripture_reference(reference: String) -> ReferenceValidation {
+    validate_reference(&reference)
+}
+
+/// Each verse of a reference or range, e.g. "John 3:16-18" gives
+/// "John 3:16", "John 3:17" and "John 3:18".
+#[tauri::command]
+pub fn parse_reference_range(range: String) -> Result<Vec<String>, AppError> {
+    let parsed = parse_reference_list(&range)
+        .map_err(|e| AppError::InvalidInput(i18n::localized_message(&e)))?;
+    Ok(parsed
+        .verses
+        .iter()
+        .filter_map(|id| {
+            let mut parts = id.splitn(3, '.');
+            let (book, chapter, verse) = (parts.next()?, parts.next()?, parts.next()?);
+            Some(format!(
+                "{} {}:{}",
+                book_display_name(book)?,
+                chapter,
+                verse
+            ))
+        })
+        .collect())
+}
+
+/// How `chunk_passage` splits a long passage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChunkStrategy {
+    /// `max_units` chapters per chunk
+    Chapter,
+    /// `max_units` paragraphs per chunk; by chapter without paragraph data
+    Paragraph,
+    /// `max_units` verses per chunk
+    VerseCount,
+}
+
+/// Split a parsed reference into ordered chunks of at most `max_units`
+/// chapters, paragraphs or verses. `paragraph_starts` holds the verse ids
+/// that open a paragraph in the corpus. Verses are never split, so a chunk
+/// always has at least one.
+pub fn chunk_reference(
+    parsed: &ParsedReference,
+    strategy: ChunkStrategy,
+    max_units: usize,
+    paragraph_starts: &[String],
+) -> Vec<String> {
+    let points = verse_points(&parsed.ranges);
+    let starts: HashSet<&str> = paragraph_starts.iter().map(String::as_str).collect();
+    let strategy = match strategy {
+        ChunkStrategy::Paragraph if starts.is_empty() => ChunkStrategy::Chapter,
+        strategy => strategy,
+    };
+    let mut units: Vec<Vec<(&str, u32, u32)>> = Vec::new();
+    for (i, &point) in points.iter().enumerate() {
+        let (book, chapter, verse) = point;
+        let new_unit = match (strategy, i.checked_sub(1).map(|p| points[p])) {
+            (_, None) | (ChunkStrategy::VerseCount, _) => true,
+            (ChunkStrategy::Chapter, Some(previous)) => (previous.0, previous.1) != (book, chapter),
+            (ChunkStrategy::Paragraph, Some(previous)) => {
+                previous.0 != book
+                    || starts.contains(format!("{}.{}.{}", book, chapter, verse).as_str())
+            }
+        };
+        match units.last_mut() {
+            Some(unit) if !new_unit => unit.push(point),
+            _ => units.push(vec![point]),
+        }
+    }
+    units
+        .chunks(max_units.max(1))
+        .map(|group| format_reference(&ranges_of(&group.concat()), &CANONICAL))
+        .collect()
+}
+
+/// One chunk per chapter, as `ParsedReference::chapter_passages`, with
+/// chapters of more than `max_verses` verses split into runs of that many.
+pub fn chapter_chunks(parsed: &ParsedReference, max_verses: usize) -> Vec<String> {
+    let points = verse_points(&parsed.ranges);
+    let mut chunks = Vec::new();
+    for chapter in points.chunk_by(|a, b| (a.0, a.1) == (b.0, b.1)) {
+        for run in chapter.chunks(max_verses.max(1)) {
+            chunks.push(format_reference(&ranges_of(run), &CANONICAL));
+        }
+    }
+    chunks
+}
+
+/// Split a long passage into engine-sized chunks, in order: e.g. "Mark"
+/// by chapter gives "Mark 1:1-45", "Mark 2:1-28", and so on.
+#[tauri::command]
+pub fn chunk_passage(
+    passage_ref: String,
+    strategy: ChunkStrategy,
+    max_units: usize,
+    paragraph_starts: Option<Vec<String>>,
+) -> Result<Vec<String>, AppError> {
+    let parsed = parse_reference_list(&passage_ref)
+        .map_err(|e| AppError::InvalidInput(i18n::localized_message(&e)))?;
+    Ok(chunk_reference(
+        &parsed,
+        strategy,
+        max_units,
+        &paragraph_starts.unwrap_or_default(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testsupport::proptest_config;
+    use proptest::prelude::*;
+
+    fn canonical(input: &str) -> String {
+        parse_reference_list(input)
+            .unwrap_or_else(|e| panic!("{}: {}", input, e))
+            .canonical
+    }
+
+    #[test]
+    fn test_parse_and_format() {
+        let cases = [
+            ("John 3:16", "John 3:16"),
+            ("jn 3.16-18", "John 3:16-18"),
+            ("John 3:16\u{2013}18", "John 3:16-18"),
+            ("John 3:16 \u{2014} 18", "John 3:16-18"),
+            ("1 Cor 13", "1 Corinthians 13"),
+            ("1Co 13:4-7", "1 Corinthians 13:4-7"),
+            ("I Thess 4:13", "1 Thessalonians 4:13"),
+            ("Rev. 22:21", "Revelation 22:21"),
+            ("John 3:16-4:2", "John 3:16-4:2"),
+            ("John 3 - 4", "John 3-4"),
+            ("Jn 3-4:2", "John 3:1-4:2"),
+            ("Romans", "Romans"),
+            ("Rom 1-16", "Romans"),
+            ("Jude 3", "Jude 1:3"),
+            ("Jude 1:3-5", "Jude 1:3-5"),
+            ("Jude", "Jude"),
+            ("3 John 15", "3 John 1:15"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(canonical(input), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_lists() {
+        let cases = [
+            ("John 3:16, 18-20; 4:1", "John 3:16, 18-20; 4:1"),
+            ("John 3:16,18;4", "John 3:16, 18; 4"),
+            ("John 3:16-4:2, 5", "John 3:16-4:2, 5"),
+            ("John 3, 5", "John 3; 5"),
+            ("Mt 5; 6; Lk 11:2-4", "Matthew 5; 6; Luke 11:2-4"),
+            ("John 3:16; John 3:17", "John 3:16, 17"),
+            ("Jude 3; 5", "Jude 1:3, 5"),
+            ("Rom 5:8; Jude", "Romans 5:8; Jude"),
+            ("John 3:16;", "John 3:16"),
+            // Found by prop_canonical_round_trip: a repeated one-chapter
+            // book came out as "2 John; 1"
+            ("2 John, 2 John", "2 John; 2 John"),
+            ("Jude 3; Jude", "Jude 1:3; Jude"),
+            ("John 3:16; John", "John 3:16; John"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(canonical(input), expected, "{}", input);
+            // The canonical form reads back as itself
+            assert_eq!(canonical(expected), expected, "{}", expected);
+        }
+    }
+
+    #[test]
+    fn test_non_english_book_names() {
+        let cases = [
+            ("Ἰωάννην 3:16", "John 3:16"),
+            ("ΙΩΑΝΝΗΣ 1:1", "John 1:1"),
+            ("Ἀποκάλυψις 22:21", "Revelation 22:21"),
+            ("Römer 8:28", "Romans 8:28"),
+            ("Offb 1:8", "Revelation 1:8"),
+            ("1 Korinther 13", "1 Corinthians 13"),
+            ("Mateo 5:3", "Matthew 5:3"),
+            ("Hechos 2:1", "Acts 2:1"),
+            ("1 Jean 4:8", "1 John 4:8"),
+            ("Hébreux 11:1", "Hebrews 11:1"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(canonical(input), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_expanded_verses() {
+        let verses = |input: &str| parse_reference_list(input).unwrap().verses;
+        assert_eq!(
+            verses("John 3:16-18"),
+            ["John.3.16", "John.3.17", "John.3.18"]
+        );
+        assert_eq!(
+            verses("Jn 3:35-4:2"),
+            ["John.3.35", "John.3.36", "John.4.1", "John.4.2"]
+        );
+        assert_eq!(
+            verses("John 3:17; 3:16-17, 1"),
+            ["John.3.17", "John.3.16", "John.3.1"]
+        );
+        let jude = verses("Jude");
+        assert_eq!(jude.len(), 25);
+        assert_eq!(jude.last().unwrap(), "Jude.1.25");
+        assert_eq!(verses("1 Cor 13").len(), 13);
+        assert_eq!(verses("Mark").len(), 678);
+    }
+
+    #[test]
+    fn test_chapter_passages() {
+        let parsed = parse_reference_list("John 3:16, 18; 4; Jude 3; 2 John").unwrap();
+        assert_eq!(
+            parsed.chapter_passages(),
+            ["John 3:16, 18", "John 4:1-54", "Jude 1:3", "2 John 1:1-13"]
+        );
+        let parsed = parse_reference_list("Jn 3:35-4:2").unwrap();
+        assert_eq!(parsed.chapter_passages(), ["John 3:35-36", "John 4:1-2"]);
+    }
+
+    #[test]
+    fn test_chunk_reference() {
+        let parsed = parse_reference_list("John 3-5").unwrap();
+        assert_eq!(
+            chunk_reference(&parsed, ChunkStrategy::Chapter, 2, &[]),
+            ["John 3:1-4:54", "John 5:1-47"]
+        );
+        assert_eq!(
+            chunk_reference(&parsed, ChunkStrategy::VerseCount, 40, &[]),
+            [
+                "John 3:1-4:4",
+                "John 4:5-44",
+                "John 4:45-5:30",
+                "John 5:31-47"
+            ]
+        );
+        let starts = [
+            "John.3.1".to_string(),
+            "John.3.22".to_string(),
+            "John.4.1".to_string(),
+        ];
+        assert_eq!(
+            chunk_reference(&parsed, ChunkStrategy::Paragraph, 2, &starts),
+            ["John 3:1-36", "John 4:1-5:47"]
+        );
+        // Without paragraph data, by chapter
+        assert_eq!(
+            chunk_reference(&parsed, ChunkStrategy::Paragraph, 1, &[]).len(),
+            3
+        );
+        // A single verse is never split
+        let verse = parse_reference_list("John 3:16").unwrap();
+        assert_eq!(
+            chunk_reference(&verse, ChunkStrategy::VerseCount, 0, &[]),
+            ["John 3:16"]
+        );
+    }
+
+    #[test]
+    fn test_chapter_chunks() {
+        let parsed = parse_reference_list("John 3:16, 18; 4; Jude 3; 2 John").unwrap();
+        assert_eq!(
+            chapter_chunks(&parsed, usize::MAX),
+            parsed.chapter_passages()
+        );
+        let parsed = parse_reference_list("John 4").unwrap();
+        assert_eq!(
+            chapter_chunks(&parsed, 20),
+            ["John 4:1-20", "John 4:21-40", "John 4:41-54"]
+        );
+    }
+
+    #[test]
+    fn test_validate_reference() {
+        let validation = validate_reference("Ἰωάννης 3:16");
+        assert!(validation.valid);
+        assert_eq!(validation.normalized.as_deref(), Some("John 3:16"));
+        assert_eq!(validation.book_id.as_deref(), Some("John"));
+        assert_eq!((validation.chapter, validation.verse), (Some(3), Some(16)));
+
+        let chapter = validate_reference("1 Cor 13");
+        assert_eq!(chapter.book_id.as_deref(), Some("1Corinthians"));
+        assert_eq!((chapter.chapter, chapter.verse), (Some(13), None));
+        let book = validate_reference("Mark");
+        assert_eq!((book.chapter, book.verse), (None, None));
+
+        for input in ["John 3:37", "John 22", "Hezekiah 1:1", "", "John 3:16; 4:1"] {
+            let validation = validate_reference(input);
+            assert!(!validation.valid, "{}", input);
+            assert!(validation.error.is_some(), "{}", input);
+            assert_eq!(validation.normalized, None, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_reference_range() {
+        assert_eq!(
+            parse_reference_range("John 3:16-18".to_string()).unwrap(),
+            ["John 3:16", "John 3:17", "John 3:18"]
+        );
+        assert_eq!(
+            parse_reference_range("1 Cor 13:12-13".to_string()).unwrap(),
+            ["1 Corinthians 13:12", "1 Corinthians 13:13"]
+        );
+        assert!(parse_reference_range("John 3:16-40".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_canonical_reference() {
+        assert_eq!(canonical_reference("jn 3.16"), "John 3:16");
+        assert_eq!(canonical_reference(" John 3:16-4:2 "), "John 3:16-4:2");
+        assert_eq!(canonical_reference(" Hezekiah 1:1 "), "Hezekiah 1:1");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let span = |start, end| Span { start, end };
+        let cases = [
+            ("  ", ReferenceError::Empty),
+            (" ; ", ReferenceError::Empty),
+            (
+                "Hezekiah 1:1",
+                ReferenceError::UnknownBook {
+                    text: "Hezekiah".to_string(),
+                    span: span(0, 8),
+                },
+            ),
+            (
+                "16; John 3",
+                ReferenceError::MissingBook {
+                    text: "16".to_string(),
+                    span: span(0, 2),
+                },
+            ),
+            (
+                "John 3:16, x",
+                ReferenceError::UnknownBook {
+                    text: "x".to_string(),
+                    span: span(11, 12),
+                },
+            ),
+            (
+                "Ἰωάννην 3:x6",
+                ReferenceError::InvalidNumber {
+                    text: "x6".to_string(),
+                    span: span(10, 12),
+                },
+            ),
+            (
+                "John 3:0",
+                ReferenceError::InvalidNumber {
+                    text: "0".to_string(),
+                    span: span(7, 8),
+                },
+            ),
+            (
+                "John 3:",
+                ReferenceError::InvalidNumber {
+                    text: String::new(),
+                    span: span(7, 7),
+                },
+            ),
+            (
+                "John 3:18-16",
+                ReferenceError::InvalidRange {
+                    text: "3:18-16".to_string(),
+                    span: span(5, 12),
+                },
+            ),
+            (
+                "John 1-2-3",
+                ReferenceError::InvalidRange {
+                    text: "1-2-3".to_string(),
+                    span: span(5, 10),
+                },
+            ),
+            (
+                "Mark 17:1",
+                ReferenceError::ChapterOutOfRange {
+                    book: "Mark".to_string(),
+                    chapter: 17,
+                    span: span(5, 9),
+                },
+            ),
+            (
+                "John 3:16-37",
+                ReferenceError::VerseOutOfRange {
+                    book: "John".to_string(),
+                    chapter: 3,
+                    verse: 37,
+                    span: span(10, 12),
+                },
+            ),
+            (
+                "Jude 26",
+                ReferenceError::VerseOutOfRange {
+                    book: "Jude".to_string(),
+                    chapter: 1,
+                    verse: 26,
+                    span: span(5, 7),
+                },
+            ),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(parse_reference_list(input), Err(expected), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_error_params_carry_span() {
+        let error = parse_reference_list("John 99").unwrap_err();
+        assert_eq!(error.code(), "reference-chapter-out-of-range");
+        assert_eq!(
+            error.params(),
+            [
+                ("book", "John".to_string()),
+                ("chapter", "99".to_string()),
+                ("start", "5".to_string()),
+                ("end", "7".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_verse_counts() {
+        assert_eq!(BOOKS.len(), 27);
+        let chapters: usize = BOOKS.iter().map(|b| b.verses.len()).sum();
+        assert_eq!(chapters, 260);
+        for book in BOOKS {
+            for alias in book.aliases {
+                assert_eq!(book_key(alias), *alias, "{}", alias);
+                assert_eq!(resolve_book(alias), Some(book.id), "{}", alias);
+            }
+        }
+    }
+
+    /// Every name `lookup_book` knows `book` by
+    fn book_names(book: &'static Book) -> Vec<String> {
+        let mut names = vec![book.id.to_string(), book.display.to_string()];
+        names.extend(book_abbreviation(book.id));
+        names.extend(book.aliases.iter().map(|alias| alias.to_string()));
+        names
+    }
+
+    /// A place in `book` as typed: "", "3", "3:16", "3.16-4:2" or "3-4"
+    fn location(book: &'static Book) -> BoxedStrategy<String> {
+        let chapters = book.verses.len() as u32;
+        let point = move || {
+            (1..=chapters).prop_flat_map(move |c| (Just(c), 1..=book.verses[c as usize - 1]))
+        };
+        let separator = || prop::sample::select(vec![":", "."]);
+        let dash = || prop::sample::select(vec!["-", "\u{2013}", "\u{2014}", " - "]);
+        prop_oneof![
+            Just(String::new()),
+            (1..=chapters).prop_map(|c| c.to_string()),
+            (point(), separator()).prop_map(|((c, v), s)| format!("{}{}{}", c, s, v)),
+            (point(), point(), separator(), dash()).prop_map(|(a, b, s, d)| {
+                let (a, b) = (a.min(b), a.max(b));
+                format!("{}{}{}{}{}{}{}", a.0, s, a.1, d, b.0, s, b.1)
+            }),
+            (1..=chapters, 1..=chapters, dash()).prop_map(|(a, b, d)| {
+                let (a, b) = (a.min(b), a.max(b));
+                format!("{}{}{}", a, d, b)
+            }),
+        ]
+        .boxed()
+    }
+
+    /// A valid reference to a book, under any of its names
+    fn reference_item() -> impl Strategy<Value = String> {
+        (0..BOOKS.len()).prop_flat_map(|i| {
+            let book = &BOOKS[i];
+            (prop::sample::select(book_names(book)), location(book))
+                .prop_map(|(name, location)| format!("{} {}", name, location))
+        })
+    }
+
+    /// A book's display name with one of its letters dropped, replaced or
+    /// swapped with the next, or a letter inserted after one; digits stay
+    /// where they are, so the whole name is still taken as the book
+    fn misspelled_book() -> impl Strategy<Value = String> {
+        let letters = vec!['x', 'q', 'e', 'h', 'α', 'ω', 'é', 'о', 'ı', 'İ'];
+        (
+            prop::sample::select(BOOKS.iter().map(|b| b.display).collect::<Vec<_>>()),
+            0..4usize,
+            any::<prop::sample::Index>(),
+            prop::sample::select(letters),
+        )
+            .prop_map(|(name, edit, at, letter)| {
+                let mut chars: Vec<char> = name.chars().collect();
+                let positions: Vec<usize> = (0..chars.len())
+                    .filter(|&i| chars[i].is_alphabetic())
+                    .collect();
+                let i = positions[at.index(positions.len())];
+                match edit {
+                    0 => {
+                        chars.remove(i);
+                    }
+                    1 => chars.insert(i + 1, letter),
+                    2 if chars.get(i + 1).is_some_and(|c| c.is_alphabetic()) => {
+                        chars.swap(i, i + 1)
+                    }
+                    _ => chars[i] = letter,
+                }
+                chars.into_iter().collect()
+            })
+    }
+
+    proptest! {
+        #![proptest_config(proptest_config())]
+
+        #[test]
+        fn prop_parse_never_panics(input in any::<String>()) {
+            let _ = parse_reference_list(&input);
+        }
+
+        #[test]
+        fn prop_parse_reference_shaped_input(input in "[ 0-9:.,;\u{2013}IJRohnmudeαΙω-]{0,24}") {
+            if let Err(error) = parse_reference_list(&input) {
+                let utf16_len = input.encode_utf16().count();
+                prop_assert!(error.span().is_none_or(|s| s.start <= s.end && s.end <= utf16_len));
+            }
+        }
+
+        #[test]
+        fn prop_canonical_round_trip(
+            items in prop::collection::vec(reference_item(), 1..4),
+            separator in prop::sample::select(vec!["; ", ", ", ";"]),
+        ) {
+            let input = items.join(separator);
+            let parsed = parse_reference_list(&input).unwrap();
+            let reparsed = parse_reference_list(&parsed.canonical).unwrap();
+            prop_assert_eq!(reparsed, parsed, "{}", input);
+        }
+
+        #[test]
+        fn prop_misspelled_book(name in misspelled_book()) {
+            let input = format!("{} 1:1", name);
+            match parse_reference_list(&input) {
+                // Some misspellings are still a name of a book
+                Ok(_) => prop_assert!(resolve_book(&name).is_some(), "{}", input),
+                Err(ReferenceError::UnknownBook { text, span }) => {
+                    prop_assert_eq!(&text, &name);
+                    prop_assert_eq!((span.start, span.end), (0, name.encode_utf16().count()));
+                }
+                Err(error) => prop_assert!(false, "{}: {:?}", input, error),
+            }
+        }
+    }
+}
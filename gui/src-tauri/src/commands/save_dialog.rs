@@ -0,0 +1,312 @@
+//! Native save dialog preconfigured per export type.
+//!
+//! `choose_save_path` picks the starting directory (the last one used for
+//! that kind), extension filter and a generated file name, then validates
+//! the user's choice. Export commands take the returned path and run it
+//! through `validate_export_path` again, since the frontend could pass
+//! anything.
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::DialogExt;
+
+use super::errors::AppError;
+use super::projects::PROJECT_EXTENSION;
+use super::settings;
+use super::telemetry::{self, TelemetryEvent};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportKind {
+    Pdf,
+    Usfm,
+    Osis,
+    Markdown,
+    Html,
+    Docx,
+    Csv,
+    Json,
+    Flashcards,
+    AnkiPackage,
+    Diagnostics,
+    Backup,
+    AuditLog,
+    History,
+    Project,
+}
+
+struct ExportSpec {
+    filter_name: &'static str,
+    /// Accepted extensions; the first is appended when missing
+    extensions: &'static [&'static str],
+    /// File name stem used when no name is suggested
+    default_stem: &'static str,
+}
+
+impl ExportKind {
+    fn key(self) -> &'static str {
+        match self {
+            ExportKind::Pdf => "pdf",
+            ExportKind::Usfm => "usfm",
+            ExportKind::Osis => "osis",
+            ExportKind::Markdown => "markdown",
+            ExportKind::Html => "html",
+            ExportKind::Docx => "docx",
+            ExportKind::Csv => "csv",
+            ExportKind::Json => "json",
+            ExportKind::Flashcards => "flashcards",
+            ExportKind::AnkiPackage => "anki_package",
+            ExportKind::Diagnostics => "diagnostics",
+            ExportKind::Backup => "backup",
+            ExportKind::AuditLog => "audit_log",
+            ExportKind::History => "history",
+            ExportKind::Project => "project",
+        }
+    }
+
+    fn spec(self) -> ExportSpec {
+        match self {
+            ExportKind::Pdf => ExportSpec {
+                filter_name: "PDF document",
+                extensions: &["pdf"],
+                default_stem: "passage",
+            },
+            ExportKind::Usfm => ExportSpec {
+                filter_name: "USFM",
+                extensions: &["usfm", "sfm"],
+                default_stem: "passage",
+            },
+            ExportKind::Osis => ExportSpec {
+                filter_name: "OSIS XML",
+                extensions: &["xml", "osis"],
+                default_stem: "passage",
+            },
+            ExportKind::Markdown => ExportSpec {
+                filter_name: "Markdown",
+                extensions: &["md", "markdown"],
+                default_stem: "passage",
+            },
+            ExportKind::Html => ExportSpec {
+                filter_name: "HTML document",
+                extensions: &["html", "htm"],
+                default_stem: "passage",
+            },
+            ExportKind::Docx => ExportSpec {
+                filter_name: "Word document",
+                extensions: &["docx"],
+                default_stem: "passage",
+            },
+            ExportKind::Csv => ExportSpec {
+                filter_name: "CSV",
+                extensions: &["csv", "tsv"],
+                default_stem: "redletters-export",
+            },
+            ExportKind::Json => ExportSpec {
+                filter_name: "JSON",
+                extensions: &["json"],
+                default_stem: "redletters-export",
+            },
+            ExportKind::Flashcards => ExportSpec {
+                filter_name: "Anki notes",
+                extensions: &["txt", "tsv"],
+                default_stem: "vocabulary",
+            },
+            ExportKind::AnkiPackage => ExportSpec {
+                filter_name: "Anki deck package",
+                extensions: &["apkg"],
+                default_stem: "vocabulary",
+            },
+            ExportKind::Diagnostics => ExportSpec {
+                filter_name: "Zip archive",
+                extensions: &["zip"],
+                default_stem: "redletters-diagnostics",
+            },
+            ExportKind::Backup => ExportSpec {
+                filter_name: "Red Letters backup",
+                extensions: &["zip"],
+                default_stem: "redletters-backup",
+            },
+            ExportKind::AuditLog => ExportSpec {
+                filter_name: "JSON Lines",
+                extensions: &["jsonl"],
+                default_stem: "redletters-audit-log",
+            },
+            ExportKind::History => ExportSpec {
+                filter_name: "JSON Lines",
+                extensions: &["jsonl"],
+                default_stem: "redletters-history",
+            },
+            ExportKind::Project => ExportSpec {
+                filter_name: "Red Letters project",
+                extensions: &[PROJECT_EXTENSION],
+                default_stem: "project",
+            },
+        }
+    }
+}
+
+/// Turn a suggested name (often a passage reference like "John 3:16-18")
+/// into a file-name-safe stem.
+fn sanitize_stem(name: &str) -> String {
+    let mut stem = String::new();
+    for c in name.trim().chars() {
+        if c.is_alphanumeric() || c == '_' || c == '.' {
+            stem.push(c);
+        } else if !stem.ends_with('-') {
+            stem.push('-');
+        }
+    }
+    stem.trim_matches(|c| c == '-' || c == '.').to_string()
+}
+
+/// Generated file name: "{reference}-{date}.{ext}".
+fn default_file_name(kind: ExportKind, suggested_name: Option<&str>, date: &str) -> String {
+    let spec = kind.spec();
+    let stem = suggested_name
+        .map(sanitize_stem)
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| spec.default_stem.to_string());
+    format!("{}-{}.{}", stem, date, spec.extensions[0])
+}
+
+/// Append the kind's extension unless the path already has an accepted one.
+fn with_extension(path: &Path, kind: ExportKind) -> PathBuf {
+    let spec = kind.spec();
+    let has_extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| spec.extensions.iter().any(|x| x.eq_ignore_ascii_case(e)));
+    if has_extension {
+        return path.to_path_buf();
+    }
+    let mut name = path.as_os_str().to_os_string();
+    name.push(".");
+    name.push(spec.extensions[0]);
+    PathBuf::from(name)
+}
+
+/// Refuse paths inside the app's own data, config, cache and log dirs.
+fn ensure_outside_app_dirs(app: &AppHandle, path: &Path) -> Result<(), String> {
+    let resolver = app.path();
+    let app_dirs = [
+        resolver.app_data_dir(),
+        resolver.app_local_data_dir(),
+        resolver.app_config_dir(),
+        resolver.app_cache_dir(),
+        resolver.app_log_dir(),
+    ];
+
+    // The file may not exist yet, so resolve symlinks on its directory
+    let parent = path.parent().unwrap_or(path);
+    let parent = parent
+        .canonicalize()
+        .unwrap_or_else(|_| parent.to_path_buf());
+
+    for dir in app_dirs.into_iter().flatten() {
+        let dir = dir.canonicalize().unwrap_or(dir);
+        if parent.starts_with(&dir) {
+            return Err(format!(
+                "Refusing to save inside the app's data directory ({})",
+                dir.display()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Validate a destination for an export: correct extension, outside app dirs.
+pub fn validate_export_path(
+    app: &AppHandle,
+    kind: ExportKind,
+    path: &Path,
+) -> Result<PathBuf, String> {
+    if !path.is_absolute() {
+        return Err(format!("Export path must be absolute: {}", path.display()));
+    }
+    let path = with_extension(path, kind);
+    ensure_outside_app_dirs(app, &path)?;
+    Ok(path)
+}
+
+/// Open the native save dialog for an export.
+///
+/// Returns `None` if the user cancelled.
+#[tauri::command]
+pub async fn choose_save_path(
+    app: AppHandle,
+    kind: ExportKind,
+    suggested_name: Option<String>,
+) -> Result<Option<String>, AppError> {
+    let spec = kind.spec();
+    let date = Local::now().format("%Y-%m-%d").to_string();
+    let file_name = default_file_name(kind, suggested_name.as_deref(), &date);
+
+    let directory = settings::load_settings(&app)?
+        .last_save_dirs
+        .get(kind.key())
+        .filter(|dir| dir.is_dir())
+        .cloned()
+        .or_else(|| app.path().document_dir().ok());
+
+    let mut dialog = app
+        .dialog()
+        .file()
+        .set_file_name(file_name)
+        .add_filter(spec.filter_name, spec.extensions);
+    if let Some(directory) = directory {
+        dialog = dialog.set_directory(directory);
+    }
+
+    let Some(chosen) = dialog.blocking_save_file() else {
+        return Ok(None);
+    };
+    let chosen = chosen.into_path().map_err(|e| e.to_string())?;
+    let path = validate_export_path(&app, kind, &chosen)?;
+    telemetry::record(TelemetryEvent::ExportFormat { format: kind });
+
+    if let Some(parent) = path.parent() {
+        let parent = parent.to_path_buf();
+        if let Err(e) = settings::update_settings(&app, |s| {
+            s.last_save_dirs.insert(kind.key().to_string(), parent);
+        }) {
+            tracing::warn!("Could not remember save directory: {}", e);
+        }
+    }
+
+    Ok(Some(path.to_string_lossy().into_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_file_name() {
+        assert_eq!(
+            default_file_name(ExportKind::Pdf, Some("John 3:16-18"), "2024-05-01"),
+            "John-3-16-18-2024-05-01.pdf"
+        );
+        assert_eq!(
+            default_file_name(ExportKind::Backup, Some("  /  "), "2024-05-01"),
+            "redletters-backup-2024-05-01.zip"
+        );
+    }
+
+    #[test]
+    fn test_with_extension() {
+        assert_eq!(
+            with_extension(Path::new("/tmp/Romans 8"), ExportKind::Usfm),
+            PathBuf::from("/tmp/Romans 8.usfm")
+        );
+        assert_eq!(
+            with_extension(Path::new("/tmp/mark.SFM"), ExportKind::Usfm),
+            PathBuf::from("/tmp/mark.SFM")
+        );
+        assert_eq!(
+            with_extension(Path::new("/tmp/report.final"), ExportKind::Pdf),
+            PathBuf::from("/tmp/report.final.pdf")
+        );
+    }
+}
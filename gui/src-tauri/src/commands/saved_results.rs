@@ -0,0 +1,247 @@
+//! Bookmarked search hits.
+//!
+//! Saved hits live in `saved_results.json` under the app data dir, newest
+//! first. Saving the same hit again refreshes it instead of adding a copy,
+//! and the least recently saved entries are dropped beyond 5,000.
+
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+use super::document_export::escape_markdown;
+use super::errors::AppError;
+use super::interlinear_table::csv_field;
+use super::reference::canonical_reference;
+use super::save_dialog::{validate_export_path, ExportKind};
+use super::search::SearchHit;
+use super::storage;
+use super::tags::{self, TaggedItemKind};
+
+const SAVED_RESULTS_FILE: &str = "saved_results.json";
+/// Least recently saved entries are evicted beyond this many
+const MAX_SAVED_RESULTS: usize = 5000;
+/// Entries per page of `list_saved_search_results`
+const SAVED_RESULTS_PAGE_SIZE: usize = 50;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedSearchResult {
+    pub id: String,
+    pub hit: SearchHit,
+    #[serde(default)]
+    pub note: Option<String>,
+    pub saved_at_ms: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Json,
+    Csv,
+    Markdown,
+}
+
+impl ExportFormat {
+    pub fn kind(self) -> ExportKind {
+        match self {
+            ExportFormat::Json => ExportKind::Json,
+            ExportFormat::Csv => ExportKind::Csv,
+            ExportFormat::Markdown => ExportKind::Markdown,
+        }
+    }
+}
+
+pub fn load_saved(app: &AppHandle) -> Result<Vec<SavedSearchResult>, String> {
+    storage::read_json_or_default(&storage::app_data_file(app, SAVED_RESULTS_FILE)?)
+}
+
+fn store_saved(app: &AppHandle, saved: &[SavedSearchResult]) -> Result<(), String> {
+    storage::write_json(&storage::app_data_file(app, SAVED_RESULTS_FILE)?, saved)
+}
+
+/// Add or refresh `entry`, keep the list newest first and evict the oldest
+/// beyond the cap. A hit is the same when reference and snippet match.
+fn insert_saved(saved: &mut Vec<SavedSearchResult>, mut entry: SavedSearchResult) {
+    if let Some(i) = saved
+        .iter()
+        .position(|s| s.hit.reference == entry.hit.reference && s.hit.snippet == entry.hit.snippet)
+    {
+        entry.id = saved.remove(i).id;
+    }
+    saved.push(entry);
+    saved.sort_by_key(|s| std::cmp::Reverse(s.saved_at_ms));
+    saved.truncate(MAX_SAVED_RESULTS);
+}
+
+/// Snippet without the `<mark>` tags added by highlighted searches.
+fn plain_snippet(snippet: &str) -> String {
+    snippet.replace("<mark>", "").replace("</mark>", "")
+}
+
+fn write_saved(
+    out: &mut dyn Write,
+    format: ExportFormat,
+    saved: &[SavedSearchResult],
+) -> std::io::Result<()> {
+    match format {
+        ExportFormat::Json => {
+            serde_json::to_writer_pretty(&mut *out, saved)?;
+            writeln!(out)
+        }
+        ExportFormat::Csv => {
+            write!(out, "reference,snippet,score,note,saved_at_ms\r\n")?;
+            for s in saved {
+                write!(
+                    out,
+                    "{},{},{},{},{}\r\n",
+                    csv_field(&s.hit.reference),
+                    csv_field(&plain_snippet(&s.hit.snippet)),
+                    s.hit.score,
+                    csv_field(s.note.as_deref().unwrap_or_default()),
+                    s.saved_at_ms
+                )?;
+            }
+            Ok(())
+        }
+        ExportFormat::Markdown => {
+            writeln!(out, "# Saved search results\n")?;
+            for s in saved {
+                write!(
+                    out,
+                    "- **{}** — {}",
+                    escape_markdown(&s.hit.reference),
+                    escape_markdown(&plain_snippet(&s.hit.snippet))
+                )?;
+                match s.note.as_deref().filter(|n| !n.trim().is_empty()) {
+                    Some(note) => writeln!(out, "\n  *{}*", escape_markdown(note.trim()))?,
+                    None => writeln!(out)?,
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Bookmark a search hit with an optional note.
+#[tauri::command]
+pub fn save_search_result(
+    app: AppHandle,
+    mut hit: SearchHit,
+    note: Option<String>,
+) -> Result<SavedSearchResult, AppError> {
+    hit.reference = canonical_reference(&hit.reference);
+    let entry = SavedSearchResult {
+        id: uuid::Uuid::new_v4().to_string(),
+        hit,
+        note: note.filter(|n| !n.trim().is_empty()),
+        saved_at_ms: storage::now_ms(),
+    };
+    let mut saved = load_saved(&app)?;
+    insert_saved(&mut saved, entry);
+    store_saved(&app, &saved)?;
+    // Newest first, so the entry just saved is at the front
+    Ok(saved.remove(0))
+}
+
+/// One page (1-based) of saved hits, newest first.
+#[tauri::command]
+pub fn list_saved_search_results(
+    app: AppHandle,
+    page: u32,
+) -> Result<Vec<SavedSearchResult>, AppError> {
+    let start = (page.max(1) as usize - 1) * SAVED_RESULTS_PAGE_SIZE;
+    Ok(load_saved(&app)?
+        .into_iter()
+        .skip(start)
+        .take(SAVED_RESULTS_PAGE_SIZE)
+        .collect())
+}
+
+/// Remove a saved hit; `false` when there was none with that id.
+#[tauri::command]
+pub fn delete_saved_search_result(app: AppHandle, id: String) -> Result<bool, AppError> {
+    let mut saved = load_saved(&app)?;
+    let before = saved.len();
+    saved.retain(|s| s.id != id);
+    if saved.len() == before {
+        return Ok(false);
+    }
+    store_saved(&app, &saved)?;
+    tags::forget_item(&app, TaggedItemKind::SavedResult, &id)?;
+    Ok(true)
+}
+
+/// Write all saved hits, or those with `tag`, to a file and return how
+/// many were written.
+///
+/// `output_path` should come from `choose_save_path` with the kind that
+/// matches `format` (`json`, `csv` or `markdown`).
+#[tauri::command]
+pub fn export_saved_results(
+    app: AppHandle,
+    output_path: String,
+    format: ExportFormat,
+    tag: Option<String>,
+) -> Result<u32, AppError> {
+    let path = validate_export_path(&app, format.kind(), &PathBuf::from(output_path))?;
+    let mut saved = load_saved(&app)?;
+    if let Some(tag) = tag {
+        let tagged = tags::saved_result_ids_with_tag(&app, &tag)?;
+        saved.retain(|s| tagged.contains(&s.id));
+    }
+    storage::write_atomic_with(&path, |out| write_saved(out, format, &saved))?;
+    Ok(saved.len() as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, reference: &str, saved_at_ms: u64) -> SavedSearchResult {
+        SavedSearchResult {
+            id: id.to_string(),
+            hit: SearchHit {
+                reference: reference.to_string(),
+                snippet: "ἐν <mark>ἀρχῇ</mark>".to_string(),
+                score: 1.5,
+            },
+            note: None,
+            saved_at_ms,
+        }
+    }
+
+    #[test]
+    fn test_insert_saved_refreshes_and_evicts() {
+        let mut saved: Vec<_> = (0..MAX_SAVED_RESULTS as u64)
+            .map(|i| entry(&i.to_string(), &format!("John 1:{}", i), i))
+            .collect();
+        saved.reverse();
+
+        // Saving a known hit again keeps its id and moves it to the front
+        insert_saved(&mut saved, entry("new", "John 1:3", 10_000));
+        assert_eq!(saved.len(), MAX_SAVED_RESULTS);
+        assert_eq!(saved[0].id, "3");
+
+        insert_saved(&mut saved, entry("new", "Mark 1:1", 10_001));
+        assert_eq!(saved.len(), MAX_SAVED_RESULTS);
+        assert_eq!(saved[0].id, "new");
+        assert!(saved.iter().all(|s| s.id != "0"));
+    }
+
+    #[test]
+    fn test_write_saved_formats() {
+        let mut note = entry("a", "John 1:1", 1);
+        note.note = Some("beginning, \"arche\"".to_string());
+        let saved = [note];
+
+        let mut csv = Vec::new();
+        write_saved(&mut csv, ExportFormat::Csv, &saved).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert!(csv.contains("John 1:1,ἐν ἀρχῇ,1.5,\"beginning, \"\"arche\"\"\",1\r\n"));
+
+        let mut md = Vec::new();
+        write_saved(&mut md, ExportFormat::Markdown, &saved).unwrap();
+        let md = String::from_utf8(md).unwrap();
+        assert!(md.contains("- **John 1:1** — ἐν ἀρχῇ\n  *beginning, \"arche\"*\n"));
+    }
+}
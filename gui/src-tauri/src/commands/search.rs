@@ -0,0 +1,358 @@
+//! Full-text search through the engine.
+
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, State};
+
+use super::cache::TtlCache;
+use super::engine::EngineError;
+use super::errors::AppError;
+use super::http::{self, HttpError};
+use super::storage;
+
+/// Largest page the engine will return
+const MAX_PAGE_SIZE: u32 = 100;
+/// Shorter prefixes match too much to be worth a request
+const MIN_PREFIX_LEN: usize = 2;
+/// How long suggestions for a prefix are reused while the user types
+const SUGGESTION_TTL: Duration = Duration::from_secs(30);
+/// Prefixes kept in the suggestion cache
+const SUGGESTION_CACHE_SIZE: usize = 256;
+/// How long the engine's filter facets are reused
+const FILTERS_TTL: Duration = Duration::from_secs(10 * 60);
+const SEARCH_HISTORY_FILE: &str = "search_history.json";
+/// Unique queries kept in the search history
+const MAX_SEARCH_HISTORY: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Testament {
+    OT,
+    NT,
+    Apocrypha,
+    All,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchQuery {
+    pub text: String,
+    /// e.g. "grc" or "en"; the engine searches all languages when unset
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Restrict to one lexicon's glosses
+    #[serde(default)]
+    pub lexicon: Option<String>,
+    /// Book ids; empty means all books
+    #[serde(default)]
+    pub books: Vec<String>,
+    #[serde(default)]
+    pub testament: Option<Testament>,
+    /// 1-based
+    pub page: u32,
+    pub page_size: u32,
+    /// Wrap matches in the snippet in `<mark>`
+    #[serde(default)]
+    pub highlight: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub reference: String,
+    pub snippet: String,
+    pub score: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResults {
+    pub hits: Vec<SearchHit>,
+    pub total: u64,
+    pub took_ms: u64,
+    pub page: u32,
+    /// Spelling suggestions when there are few or no hits
+    #[serde(default)]
+    pub suggestions: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SuggestionKind {
+    Word,
+    Phrase,
+    Reference,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchSuggestion {
+    pub text: String,
+    pub kind: SuggestionKind,
+    pub score: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHistoryEntry {
+    pub query: String,
+    pub executed_at_ms: u64,
+    #[serde(default)]
+    pub result_count: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookInfo {
+    pub id: String,
+    pub name: String,
+    pub testament: String,
+    pub chapter_count: u32,
+}
+
+/// Facets for the advanced search form.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchFilterConfig {
+    pub available_books: Vec<BookInfo>,
+    pub available_lexicons: Vec<String>,
+    pub supported_languages: Vec<String>,
+    pub max_results_per_page: u32,
+}
+
+/// Filter facets, keyed by engine port.
+pub struct SearchFilterCache(Mutex<TtlCache<u16, SearchFilterConfig>>);
+
+impl Default for SearchFilterCache {
+    fn default() -> Self {
+        Self(Mutex::new(TtlCache::new(FILTERS_TTL)))
+    }
+}
+
+impl SearchFilterCache {
+    /// Drop cached facets, e.g. after the active lexicon changes.
+    pub fn invalidate(&self) {
+        self.0.lock().unwrap().clear();
+    }
+}
+
+/// Search bar completions, keyed by engine port, prefix and limit.
+pub struct SuggestionCache(Mutex<TtlCache<(u16, String, u32), Vec<SearchSuggestion>>>);
+
+impl Default for SuggestionCache {
+    fn default() -> Self {
+        Self(Mutex::new(TtlCache::with_capacity(
+            SUGGESTION_TTL,
+            SUGGESTION_CACHE_SIZE,
+        )))
+    }
+}
+
+/// Trimmed prefix, or `None` when it is too short to suggest for.
+fn suggestion_prefix(prefix: &str) -> Option<&str> {
+    let prefix = prefix.trim();
+    (prefix.chars().count() >= MIN_PREFIX_LEN).then_some(prefix)
+}
+
+/// Move `entry` to the front, replacing an earlier run of the same query,
+/// and drop the oldest entries beyond the cap.
+fn push_history(history: &mut Vec<SearchHistoryEntry>, entry: SearchHistoryEntry) {
+    history.retain(|e| e.query != entry.query);
+    history.push(entry);
+    history.sort_by_key(|e| std::cmp::Reverse(e.executed_at_ms));
+    history.truncate(MAX_SEARCH_HISTORY);
+}
+
+fn load_history(app: &AppHandle) -> Result<Vec<SearchHistoryEntry>, String> {
+    storage::read_json_or_default(&storage::app_data_file(app, SEARCH_HISTORY_FILE)?)
+}
+
+fn validate_query(query: &SearchQuery) -> Result<(), EngineError> {
+    let invalid = |detail: &str| Err(EngineError::InvalidSearchQuery(detail.to_string()));
+    if query.text.trim().is_empty() {
+        return invalid("search text is empty");
+    }
+    if query.page == 0 {
+        return invalid("page starts at 1");
+    }
+    if query.page_size == 0 || query.page_size > MAX_PAGE_SIZE {
+        return Err(EngineError::InvalidSearchQuery(format!(
+            "page_size must be between 1 and {}",
+            MAX_PAGE_SIZE
+        )));
+    }
+    Ok(())
+}
+
+/// Run a full-text search on the engine and add it to the search history.
+#[tauri::command]
+pub async fn submit_search_query(
+    app: AppHandle,
+    port: u16,
+    query: SearchQuery,
+) -> Result<SearchResults, EngineError> {
+    validate_query(&query)?;
+
+    let request = http::request(Method::POST, port, "/v1/search")?.json(&query);
+    match http::send_json::<SearchResults>(request).await {
+        Ok(results) => {
+            // History is a convenience; a failed write doesn't fail the search
+            let _ = record_search_execution(app, query.text.clone(), results.total);
+            Ok(results)
+        }
+        // The engine rejects query syntax it can't parse
+        Err(HttpError::Status {
+            status: 400 | 422,
+            body,
+        }) => Err(EngineError::InvalidSearchQuery(body)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Completions for the search bar; empty for prefixes under two characters.
+#[tauri::command]
+pub async fn get_search_suggestions(
+    cache: State<'_, SuggestionCache>,
+    port: u16,
+    prefix: String,
+    limit: u32,
+) -> Result<Vec<SearchSuggestion>, EngineError> {
+    let Some(prefix) = suggestion_prefix(&prefix) else {
+        return Ok(Vec::new());
+    };
+    let key = (port, prefix.to_string(), limit);
+    if let Some(suggestions) = cache.0.lock().unwrap().get(&key) {
+        return Ok(suggestions);
+    }
+
+    let request = http::request(Method::GET, port, "/v1/search/suggest")?
+        .query(&[("q", prefix.to_string()), ("limit", limit.to_string())]);
+    let suggestions: Vec<SearchSuggestion> = http::send_json(request).await?;
+    cache.0.lock().unwrap().insert(key, suggestions.clone());
+    Ok(suggestions)
+}
+
+/// Books, lexicons and languages the engine can filter searches by.
+#[tauri::command]
+pub async fn get_advanced_search_filters(
+    cache: State<'_, SearchFilterCache>,
+    port: u16,
+) -> Result<SearchFilterConfig, EngineError> {
+    if let Some(filters) = cache.0.lock().unwrap().get(&port) {
+        return Ok(filters);
+    }
+
+    let request = http::request(Method::GET, port, "/v1/search/filters")?;
+    let filters: SearchFilterConfig = http::send_json(request).await?;
+    cache.0.lock().unwrap().insert(port, filters.clone());
+    Ok(filters)
+}
+
+/// Add a query to the search history, or refresh it if already there.
+#[tauri::command]
+pub fn record_search_execution(
+    app: AppHandle,
+    query: String,
+    result_count: u64,
+) -> Result<(), AppError> {
+    let query = query.trim().to_string();
+    if query.is_empty() {
+        return Ok(());
+    }
+    let mut history = load_history(&app)?;
+    push_history(
+        &mut history,
+        SearchHistoryEntry {
+            query,
+            executed_at_ms: storage::now_ms(),
+            result_count: Some(result_count),
+        },
+    );
+    Ok(storage::write_json(
+        &storage::app_data_file(&app, SEARCH_HISTORY_FILE)?,
+        &history,
+    )?)
+}
+
+/// Most recent searches first, at most `limit`.
+#[tauri::command]
+pub fn get_search_history(app: AppHandle, limit: usize) -> Vec<SearchHistoryEntry> {
+    let mut history = load_history(&app).unwrap_or_default();
+    history.truncate(limit);
+    history
+}
+
+/// Forget all recorded searches; returns how many there were.
+#[tauri::command]
+pub fn clear_search_history(app: AppHandle) -> u32 {
+    let count = load_history(&app).map_or(0, |h| h.len() as u32);
+    if let Ok(path) = storage::app_data_file(&app, SEARCH_HISTORY_FILE) {
+        let _ = std::fs::remove_file(path);
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_query() {
+        let query = SearchQuery {
+            text: "λόγος".to_string(),
+            language: None,
+            lexicon: None,
+            books: Vec::new(),
+            testament: Some(Testament::NT),
+            page: 1,
+            page_size: 100,
+            highlight: true,
+        };
+        assert!(validate_query(&query).is_ok());
+        for bad in [
+            SearchQuery {
+                page_size: 101,
+                ..query.clone()
+            },
+            SearchQuery {
+                page_size: 0,
+                ..query.clone()
+            },
+            SearchQuery {
+                page: 0,
+                ..query.clone()
+            },
+            SearchQuery {
+                text: "  ".to_string(),
+                ..query.clone()
+            },
+        ] {
+            assert!(validate_query(&bad).is_err());
+        }
+        assert_eq!(serde_json::to_value(query).unwrap()["testament"], "NT");
+    }
+
+    #[test]
+    fn test_push_history_dedupes() {
+        let entry = |query: &str, at: u64| SearchHistoryEntry {
+            query: query.to_string(),
+            executed_at_ms: at,
+            result_count: None,
+        };
+        let mut history: Vec<_> = (0..MAX_SEARCH_HISTORY as u64)
+            .rev()
+            .map(|i| entry(&format!("q{}", i), i))
+            .collect();
+
+        push_history(&mut history, entry("q5", 1000));
+        assert_eq!(history.len(), MAX_SEARCH_HISTORY);
+        assert_eq!(history[0].query, "q5");
+        assert_eq!(history.iter().filter(|e| e.query == "q5").count(), 1);
+
+        push_history(&mut history, entry("new", 1001));
+        assert_eq!(history.len(), MAX_SEARCH_HISTORY);
+        assert_eq!(history[0].query, "new");
+        assert!(history.iter().all(|e| e.query != "q0"));
+    }
+
+    #[test]
+    fn test_suggestion_prefix() {
+        assert_eq!(suggestion_prefix(" λό "), Some("λό"));
+        assert_eq!(suggestion_prefix("λ "), None);
+        assert_eq!(suggestion_prefix("   "), None);
+    }
+}
@@ -0,0 +1,92 @@
+//! Persistent app settings, stored as `settings.json` in the app data dir.
+//!
+//! Fields are added as features need them; `#[serde(default)]` keeps older
+//! settings files loading after an upgrade.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::AppHandle;
+
+use super::accessibility::AccessibilitySettings;
+use super::crash_reports::CrashReportingSettings;
+use super::logging::{self, BackendLogLevel};
+use super::storage;
+use super::telemetry::TelemetrySettings;
+use super::verse_of_the_day::VerseOfTheDaySettings;
+
+/// Settings file name under the app data dir
+const SETTINGS_FILE: &str = "settings.json";
+
+/// Serializes read-modify-write cycles on the settings file
+static SETTINGS_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    /// Last directory used in the save dialog, keyed by export kind
+    pub last_save_dirs: BTreeMap<String, PathBuf>,
+    /// Locale for backend messages; the OS locale is used when unset
+    pub locale: Option<String>,
+    /// Watch the clipboard for copied Greek text (opt-in)
+    pub clipboard_watch: bool,
+    /// Custom theme id applied on top of the built-in styles
+    pub custom_theme: Option<String>,
+    /// Where the start screen's verse of the day comes from
+    pub verse_of_the_day: VerseOfTheDaySettings,
+    /// Start the webview without GPU acceleration
+    pub hardware_acceleration_disabled: bool,
+    /// Unset until the user changes them; the OS preferences apply until then
+    pub accessibility: Option<AccessibilitySettings>,
+    /// Opt-in and endpoint for sending crash reports
+    pub crash_reporting: CrashReportingSettings,
+    /// Time allowed for saving state on close; two seconds when unset
+    pub pre_shutdown_delay_ms: Option<u64>,
+    /// Verses sent to the engine in one translate request; longer passages
+    /// are translated in chunks. 100 when unset
+    pub max_translate_verses: Option<usize>,
+    /// Refuse requests that would leave this machine
+    pub offline_mode: bool,
+    /// Backend log level and module overrides; `info` when unset
+    pub backend_log: Option<BackendLogLevel>,
+    /// Opt-in and endpoint for anonymous usage counts
+    pub telemetry: TelemetrySettings,
+}
+
+/// Load settings, falling back to defaults if the file doesn't exist yet.
+pub fn load_settings(app: &AppHandle) -> Result<AppSettings, String> {
+    storage::read_json_or_default(&storage::app_data_file(app, SETTINGS_FILE)?)
+}
+
+/// Load settings before the app is built, when there is no `AppHandle` to
+/// resolve paths with. Uses the same app data dir as Tauri; any error
+/// gives the defaults.
+pub fn load_settings_before_start(identifier: &str) -> AppSettings {
+    let Some(path) = dirs::data_dir().map(|dir| dir.join(identifier).join(SETTINGS_FILE)) else {
+        return AppSettings::default();
+    };
+    storage::read_json_or_default(&path).unwrap_or_else(|e| {
+        tracing::warn!("Could not read settings: {}", e);
+        AppSettings::default()
+    })
+}
+
+/// Apply a change to the stored settings and return the result.
+///
+/// Values aren't logged: settings hold endpoints and clipboard options.
+#[tracing::instrument(skip_all, fields(request_id = %logging::request_id()))]
+pub fn update_settings(
+    app: &AppHandle,
+    change: impl FnOnce(&mut AppSettings),
+) -> Result<AppSettings, String> {
+    let _guard = SETTINGS_LOCK.lock().unwrap();
+    let path = storage::app_data_file(app, SETTINGS_FILE)?;
+    let mut settings: AppSettings = storage::read_json_or_default(&path)?;
+    change(&mut settings);
+    storage::write_json(&path, &settings).inspect_err(|e| {
+        tracing::warn!("Could not save settings: {}", e);
+    })?;
+    tracing::debug!("Saved settings");
+    Ok(settings)
+}
@@ -0,0 +1,395 @@
+//! Share a cached passage.
+//!
+//! The passage is rendered as plain text or Markdown, capped in size, and
+//! handed to the system share sheet: NSSharingServicePicker on macOS,
+//! DataTransferManager on Windows. The sheet has to be opened on the main
+//! thread and reports back through a channel once the user picks a target
+//! or dismisses it. Linux has no share sheet, and a sheet that can't be
+//! opened falls back the same way: the text goes to the clipboard and the
+//! result says `fallback: "clipboard"`.
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+use super::citation::{self, passage_citation, CitationStyle};
+use super::clipboard_watch::note_app_clipboard_write;
+use super::errors::AppError;
+use super::passages::{load_passage, CachedPassage};
+
+/// Maximum characters handed to the share target
+const MAX_SHARE_CHARS: usize = 8_000;
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+const MAIN_LABEL: &str = "main";
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShareFormat {
+    Plain,
+    Markdown,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ShareResult {
+    /// False when the user dismissed the share sheet
+    pub shared: bool,
+    /// Set when the native share sheet wasn't used, e.g. "clipboard"
+    pub fallback: Option<String>,
+    pub truncated: bool,
+    pub char_count: usize,
+}
+
+/// Verse number from a canonical verse id ("John.3.16" -> "16").
+fn verse_number(verse_id: &str) -> &str {
+    verse_id.rsplit('.').next().unwrap_or(verse_id)
+}
+
+/// Render a passage for sharing. Private notes are only included on request;
+/// a citation in `citation` style closes the text when given.
+fn render_passage(
+    passage: &CachedPassage,
+    format: ShareFormat,
+    include_notes: bool,
+    citation: Option<&CitationStyle>,
+) -> String {
+    let mut out = match format {
+        ShareFormat::Plain => format!("{}\n\n", passage.reference),
+        ShareFormat::Markdown => format!("**{}**\n\n", passage.reference),
+    };
+
+    if passage.verses.is_empty() {
+        out.push_str(passage.translation.trim());
+    } else {
+        let verses: Vec<String> = passage
+            .verses
+            .iter()
+            .map(|v| match format {
+                ShareFormat::Plain => {
+                    format!("{} {}", verse_number(&v.verse_id), v.translation.trim())
+                }
+                ShareFormat::Markdown => {
+                    format!(
+                        "<sup>{}</sup> {}",
+                        verse_number(&v.verse_id),
+                        v.translation.trim()
+                    )
+                }
+            })
+            .collect();
+        out.push_str(&verses.join(" "));
+    }
+
+    if include_notes {
+        if let Some(note) = passage.note.as_deref().filter(|n| !n.trim().is_empty()) {
+            match format {
+                ShareFormat::Plain => out.push_str(&format!("\n\nNote: {}", note.trim())),
+                ShareFormat::Markdown => out.push_str(&format!("\n\n> {}", note.trim())),
+            }
+        }
+    }
+
+    if let Some(style) = citation {
+        out.push_str("\n\n");
+        out.push_str(&passage_citation(style, passage));
+    }
+
+    out
+}
+
+/// Cap text at `max` characters (not bytes), marking the cut with an ellipsis.
+fn truncate_chars(text: &str, max: usize) -> (String, bool) {
+    if text.chars().count() <= max {
+        return (text.to_string(), false);
+    }
+    let mut cut: String = text.chars().take(max.saturating_sub(1)).collect();
+    cut.push('…');
+    (cut, true)
+}
+
+/// How a share sheet closed.
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+enum SheetOutcome {
+    Shared,
+    Cancelled,
+    Failed(String),
+}
+
+#[cfg(target_os = "macos")]
+mod picker {
+    use super::SheetOutcome;
+    use objc2::rc::Retained;
+    use objc2::runtime::{AnyObject, NSObject, NSObjectProtocol, ProtocolObject};
+    use objc2::{
+        define_class, msg_send, AnyThread, DefinedClass, MainThreadMarker, MainThreadOnly,
+    };
+    use objc2_app_kit::{
+        NSSharingService, NSSharingServicePicker, NSSharingServicePickerDelegate, NSView,
+    };
+    use objc2_foundation::{NSArray, NSPoint, NSRect, NSRectEdge, NSSize, NSString};
+    use std::cell::RefCell;
+    use std::sync::mpsc::Sender;
+
+    define_class!(
+        /// Reports the service the user picked, or none when the picker
+        /// was dismissed
+        #[unsafe(super(NSObject))]
+        #[thread_kind = MainThreadOnly]
+        #[name = "RLShareSheetDelegate"]
+        #[ivars = Sender<SheetOutcome>]
+        struct ShareDelegate;
+
+        unsafe impl NSObjectProtocol for ShareDelegate {}
+
+        unsafe impl NSSharingServicePickerDelegate for ShareDelegate {
+            #[unsafe(method(sharingServicePicker:didChooseSharingService:))]
+            fn did_choose(
+                &self,
+                _picker: &NSSharingServicePicker,
+                service: Option<&NSSharingService>,
+            ) {
+                let outcome = match service {
+                    Some(_) => SheetOutcome::Shared,
+                    None => SheetOutcome::Cancelled,
+                };
+                let _ = self.ivars().send(outcome);
+            }
+        }
+    );
+
+    thread_local! {
+        /// The last picker and its delegate. The picker only holds its
+        /// delegate weakly, so both are kept until the next share.
+        static OPEN_PICKER: RefCell<Option<(Retained<NSSharingServicePicker>, Retained<ShareDelegate>)>> =
+            const { RefCell::new(None) };
+    }
+
+    /// Open the picker under the top edge of the window's content view.
+    pub fn show(
+        window: &tauri::WebviewWindow,
+        text: &str,
+        done: Sender<SheetOutcome>,
+    ) -> Result<(), String> {
+        let mtm = MainThreadMarker::new().ok_or("The share sheet needs the main thread")?;
+        let view = window.ns_view().map_err(|e| e.to_string())?;
+        // Safety: tauri hands out the window's live NSView
+        let view = unsafe { (view as *const NSView).as_ref() }
+            .ok_or("The main window has no content view")?;
+
+        let item = NSString::from_str(text);
+        let items = NSArray::<AnyObject>::from_slice(&[&item]);
+        // Safety: NSString conforms to NSPasteboardWriting
+        let picker = unsafe {
+            NSSharingServicePicker::initWithItems(NSSharingServicePicker::alloc(), &items)
+        };
+        let delegate: Retained<ShareDelegate> = {
+            let this = ShareDelegate::alloc(mtm).set_ivars(done);
+            unsafe { msg_send![super(this), init] }
+        };
+        picker.setDelegate(Some(ProtocolObject::from_ref(&*delegate)));
+
+        let bounds = view.bounds();
+        let anchor = NSRect::new(
+            NSPoint::new(bounds.size.width / 2.0, 0.0),
+            NSSize::new(1.0, 1.0),
+        );
+        picker.showRelativeToRect_ofView_preferredEdge(anchor, view, NSRectEdge::MinY);
+        OPEN_PICKER.with(|open| *open.borrow_mut() = Some((picker, delegate)));
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod picker {
+    use super::SheetOutcome;
+    use std::sync::mpsc::Sender;
+    use std::sync::{Arc, Mutex};
+    use windows::core::{factory, IInspectable, Ref, HSTRING};
+    use windows::ApplicationModel::DataTransfer::{
+        DataPackage, DataRequestedEventArgs, DataTransferManager, ShareCompletedEventArgs,
+    };
+    use windows::Foundation::TypedEventHandler;
+    use windows::Win32::UI::Shell::IDataTransferManagerInterop;
+
+    /// Open the share UI for the window. The manager asks for the data
+    /// once the UI is up; the package then reports whether the user
+    /// picked a target or dismissed the UI.
+    pub fn show(
+        window: &tauri::WebviewWindow,
+        text: &str,
+        done: Sender<SheetOutcome>,
+    ) -> Result<(), String> {
+        let hwnd = window.hwnd().map_err(|e| e.to_string())?;
+        let interop = factory::<DataTransferManager, IDataTransferManagerInterop>()
+            .map_err(|e| e.to_string())?;
+        let manager: DataTransferManager =
+            unsafe { interop.GetForWindow(hwnd) }.map_err(|e| e.to_string())?;
+
+        let title = HSTRING::from(window.title().unwrap_or_default());
+        let text = HSTRING::from(text);
+        let registration = Arc::new(Mutex::new(None));
+        let unregister = registration.clone();
+        let on_request = TypedEventHandler::new(
+            move |manager: Ref<DataTransferManager>, args: Ref<DataRequestedEventArgs>| {
+                // Answer this share only; the next one registers again
+                if let Some(token) = unregister.lock().unwrap().take() {
+                    manager.ok()?.RemoveDataRequested(token)?;
+                }
+                let data = args.ok()?.Request()?.Data()?;
+                data.Properties()?.SetTitle(&title)?;
+                data.SetText(&text)?;
+                let shared = done.clone();
+                data.ShareCompleted(&TypedEventHandler::new(
+                    move |_: Ref<DataPackage>, _: Ref<ShareCompletedEventArgs>| {
+                        let _ = shared.send(SheetOutcome::Shared);
+                        Ok(())
+                    },
+                ))?;
+                let cancelled = done.clone();
+                let on_cancel =
+                    TypedEventHandler::new(move |_: Ref<DataPackage>, _: Ref<IInspectable>| {
+                        let _ = cancelled.send(SheetOutcome::Cancelled);
+                        Ok(())
+                    });
+                // ShareCanceled needs Windows 10 2004. Before that a
+                // dismissed sheet can't be told apart, so handing the
+                // text over counts as shared
+                if data.ShareCanceled(&on_cancel).is_err() {
+                    let _ = done.send(SheetOutcome::Shared);
+                }
+                Ok(())
+            },
+        );
+        let token = manager
+            .DataRequested(&on_request)
+            .map_err(|e| e.to_string())?;
+        *registration.lock().unwrap() = Some(token);
+        unsafe { interop.ShowShareUIForWindow(hwnd) }.map_err(|e| e.to_string())
+    }
+}
+
+/// Open the platform share sheet on the main thread and wait for it to
+/// close. `None` when the platform has no share sheet.
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+async fn native_share(app: &AppHandle, text: String) -> Result<Option<bool>, String> {
+    use tauri::Manager;
+
+    let window = app
+        .get_webview_window(MAIN_LABEL)
+        .ok_or_else(|| "The main window is not open".to_string())?;
+    let (tx, rx) = std::sync::mpsc::channel();
+    let failed = tx.clone();
+    app.run_on_main_thread(move || {
+        if let Err(e) = picker::show(&window, &text, tx) {
+            let _ = failed.send(SheetOutcome::Failed(e));
+        }
+    })
+    .map_err(|e| e.to_string())?;
+    let outcome = tauri::async_runtime::spawn_blocking(move || rx.recv())
+        .await
+        .map_err(|e| e.to_string())?;
+    match outcome {
+        Ok(SheetOutcome::Shared) => Ok(Some(true)),
+        // The sheet went away without saying how
+        Ok(SheetOutcome::Cancelled) | Err(_) => Ok(Some(false)),
+        Ok(SheetOutcome::Failed(e)) => Err(e),
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+async fn native_share(_app: &AppHandle, _text: String) -> Result<Option<bool>, String> {
+    Ok(None)
+}
+
+/// Share a cached passage through the system share sheet, or the
+/// clipboard where there is none.
+///
+/// Cancelling the share sheet is reported as `shared: false`, not an error.
+#[tauri::command]
+pub async fn share_passage(
+    app: AppHandle,
+    passage_id: String,
+    format: ShareFormat,
+    include_notes: Option<bool>,
+    citation_style: Option<String>,
+) -> Result<ShareResult, AppError> {
+    let style = citation_style
+        .as_deref()
+        .map(citation::citation_style)
+        .transpose()?;
+    let passage = load_passage(&app, &passage_id)?;
+    let rendered = render_passage(&passage, format, include_notes.unwrap_or(false), style);
+    let (text, truncated) = truncate_chars(&rendered, MAX_SHARE_CHARS);
+    let char_count = text.chars().count();
+
+    match native_share(&app, text.clone()).await {
+        Ok(Some(shared)) => {
+            return Ok(ShareResult {
+                shared,
+                fallback: None,
+                truncated,
+                char_count,
+            })
+        }
+        Ok(None) => {}
+        Err(e) => tracing::warn!("Share sheet unavailable, copying instead: {}", e),
+    }
+
+    note_app_clipboard_write(&app, &text);
+    app.clipboard()
+        .write_text(text.clone())
+        .map_err(|e| e.to_string())?;
+
+    Ok(ShareResult {
+        shared: true,
+        fallback: Some("clipboard".to_string()),
+        truncated,
+        char_count,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testsupport::passage::PassageBuilder;
+
+    fn passage(note: Option<&str>) -> CachedPassage {
+        PassageBuilder::new()
+            .id("abc")
+            .reference("John 3:16-17")
+            .verse("John.3.16", "", "For God so loved the world")
+            .verse("John.3.17", "", "For God did not send the Son")
+            .note(note)
+            .build()
+    }
+
+    #[test]
+    fn test_render_strips_notes_unless_requested() {
+        let p = passage(Some("private thought"));
+        let plain = render_passage(&p, ShareFormat::Plain, false, None);
+        assert_eq!(
+            plain,
+            "John 3:16-17\n\n16 For God so loved the world 17 For God did not send the Son"
+        );
+        assert!(render_passage(&p, ShareFormat::Plain, true, None)
+            .ends_with("\n\nNote: private thought"));
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let md = render_passage(&passage(None), ShareFormat::Markdown, true, None);
+        assert!(md.starts_with("**John 3:16-17**\n\n<sup>16</sup> For God"));
+    }
+
+    #[test]
+    fn test_render_with_citation() {
+        let sbl = citation::citation_style("sbl").unwrap();
+        let plain = render_passage(&passage(None), ShareFormat::Plain, false, Some(sbl));
+        assert!(plain.ends_with("the Son\n\nJohn 3:16–17 Red Letters"));
+    }
+
+    #[test]
+    fn test_truncate_chars_respects_char_boundaries() {
+        assert_eq!(truncate_chars("λόγος", 10), ("λόγος".to_string(), false));
+        assert_eq!(truncate_chars("λόγος", 3), ("λό…".to_string(), true));
+    }
+}
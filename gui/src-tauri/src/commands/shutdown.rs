@@ -0,0 +1,222 @@
+//! Orderly shutdown when the main window is closed.
+//!
+//! Closing the main window is held back while pending state is saved:
+//! the window's geometry goes to `window_state.json`, running batch jobs
+//! are paused so their last items are checkpointed, relayed WebSocket
+//! connections are closed, and the clean-shutdown flag is set for crash
+//! detection. If that takes longer than the pre-shutdown delay (two
+//! seconds unless the user raised it), the process exits anyway.
+//! `relaunch` goes through the same steps before starting a new instance.
+
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, PhysicalPosition, PhysicalSize, WebviewWindow, WindowEvent};
+
+use super::batch_jobs::BatchJobState;
+use super::crash_reports;
+use super::errors::AppError;
+use super::settings;
+use super::storage;
+use super::websocket::WebSocketRelays;
+
+const MAIN_LABEL: &str = "main";
+const WINDOW_STATE_FILE: &str = "window_state.json";
+/// Time allowed for the shutdown steps unless the user set another
+const DEFAULT_PRE_SHUTDOWN_DELAY_MS: u64 = 2_000;
+/// Bounds for `set_pre_shutdown_delay_ms`
+const MIN_PRE_SHUTDOWN_DELAY_MS: u64 = 500;
+const MAX_PRE_SHUTDOWN_DELAY_MS: u64 = 60_000;
+/// How often to check whether paused batch jobs have stopped
+const JOB_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Set once the first close request starts the shutdown
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WindowState {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub maximized: bool,
+}
+
+fn window_state(window: &WebviewWindow) -> Result<WindowState, String> {
+    let position = window.outer_position().map_err(|e| e.to_string())?;
+    let size = window.inner_size().map_err(|e| e.to_string())?;
+    Ok(WindowState {
+        x: position.x,
+        y: position.y,
+        width: size.width,
+        height: size.height,
+        maximized: window.is_maximized().map_err(|e| e.to_string())?,
+    })
+}
+
+/// Geometry of the main window, if it is open.
+pub fn main_window_state(app: &AppHandle) -> Option<WindowState> {
+    let window = app.get_webview_window(MAIN_LABEL)?;
+    window_state(&window).ok()
+}
+
+fn save_window_state(app: &AppHandle) -> Result<(), String> {
+    let Some(window) = app.get_webview_window(MAIN_LABEL) else {
+        return Ok(());
+    };
+    let state = window_state(&window)?;
+    storage::write_json(&storage::app_data_file(app, WINDOW_STATE_FILE)?, &state)
+}
+
+/// Put the main window back where it was last closed, as long as that
+/// spot is still on a connected monitor.
+pub fn restore_window_state(app: &AppHandle) {
+    let Some(window) = app.get_webview_window(MAIN_LABEL) else {
+        return;
+    };
+    let Ok(path) = storage::app_data_file(app, WINDOW_STATE_FILE) else {
+        return;
+    };
+    let Ok(state) = storage::read_json::<WindowState>(&path) else {
+        return;
+    };
+    let on_screen = app.available_monitors().is_ok_and(|monitors| {
+        monitors.iter().any(|m| {
+            let (origin, size) = (m.position(), m.size());
+            (origin.x..origin.x + size.width as i32).contains(&state.x)
+                && (origin.y..origin.y + size.height as i32).contains(&state.y)
+        })
+    });
+    if on_screen {
+        let _ = window.set_size(PhysicalSize::new(state.width, state.height));
+        let _ = window.set_position(PhysicalPosition::new(state.x, state.y));
+    }
+    if state.maximized {
+        let _ = window.maximize();
+    }
+}
+
+/// Pause running batch jobs and wait until their workers have stopped,
+/// which is when the last finished item has been checkpointed.
+fn pause_batch_jobs(app: &AppHandle, deadline: Instant) {
+    let jobs = app.state::<BatchJobState>();
+    if jobs.pause_all() == 0 {
+        return;
+    }
+    while jobs.active_count() > 0 && Instant::now() < deadline {
+        std::thread::sleep(JOB_POLL_INTERVAL);
+    }
+}
+
+fn run_shutdown_steps(app: &AppHandle, deadline: Instant) {
+    if let Err(e) = save_window_state(app) {
+        tracing::warn!("Could not save the window state: {}", e);
+    }
+    pause_batch_jobs(app, deadline);
+    app.state::<WebSocketRelays>().close_all();
+    crash_reports::mark_clean_shutdown(app);
+}
+
+fn shut_down(app: AppHandle, relaunch: Option<Command>) {
+    let delay_ms = settings::load_settings(&app)
+        .ok()
+        .and_then(|s| s.pre_shutdown_delay_ms)
+        .unwrap_or(DEFAULT_PRE_SHUTDOWN_DELAY_MS);
+    let budget = Duration::from_millis(delay_ms);
+    std::thread::spawn(move || {
+        let (tx, rx) = mpsc::channel();
+        let steps_app = app.clone();
+        std::thread::spawn(move || {
+            run_shutdown_steps(&steps_app, Instant::now() + budget);
+            let _ = tx.send(());
+        });
+        let finished = rx.recv_timeout(budget).is_ok();
+        if !finished {
+            tracing::warn!("Shutdown took longer than {} ms; exiting anyway", delay_ms);
+        }
+        if let Some(command) = relaunch {
+            start_replacement(&app, command);
+            return;
+        }
+        if !finished {
+            std::process::exit(0);
+        }
+        app.exit(0);
+    });
+}
+
+/// Start `command` and exit. The single-instance lock is released first,
+/// or the new process would hand its arguments to this one and quit.
+fn start_replacement(app: &AppHandle, mut command: Command) {
+    let handle = app.clone();
+    let queued = app.run_on_main_thread(move || {
+        handle.cleanup_before_exit();
+        if let Err(e) = command.spawn() {
+            tracing::error!("Could not relaunch: {}", e);
+        }
+        std::process::exit(0);
+    });
+    if let Err(e) = queued {
+        tracing::error!("Could not relaunch: {}", e);
+        std::process::exit(0);
+    }
+}
+
+/// Save pending state like closing the main window does, then replace this
+/// process with `command`. Does nothing if the app is already shutting down.
+pub fn relaunch(app: &AppHandle, command: Command) {
+    if !SHUTTING_DOWN.swap(true, Ordering::SeqCst) {
+        shut_down(app.clone(), Some(command));
+    }
+}
+
+/// Save pending state before the app exits when the main window is closed.
+pub fn register_shutdown_handler(app: &AppHandle) {
+    let Some(window) = app.get_webview_window(MAIN_LABEL) else {
+        return;
+    };
+    let handle = app.clone();
+    window.on_window_event(move |event| {
+        if let WindowEvent::CloseRequested { api, .. } = event {
+            api.prevent_close();
+            // Further close requests wait for the first to finish
+            if !SHUTTING_DOWN.swap(true, Ordering::SeqCst) {
+                shut_down(handle.clone(), None);
+            }
+        }
+    });
+}
+
+fn validate_delay(ms: u64) -> Result<u64, String> {
+    if (MIN_PRE_SHUTDOWN_DELAY_MS..=MAX_PRE_SHUTDOWN_DELAY_MS).contains(&ms) {
+        Ok(ms)
+    } else {
+        Err(format!(
+            "The pre-shutdown delay must be between {} and {} ms",
+            MIN_PRE_SHUTDOWN_DELAY_MS, MAX_PRE_SHUTDOWN_DELAY_MS
+        ))
+    }
+}
+
+/// Allow more (or less) time for saving state when the app is closed.
+#[tauri::command]
+pub fn set_pre_shutdown_delay_ms(app: AppHandle, ms: u64) -> Result<(), AppError> {
+    let ms = validate_delay(ms)?;
+    settings::update_settings(&app, |s| s.pre_shutdown_delay_ms = Some(ms))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_delay() {
+        assert_eq!(validate_delay(DEFAULT_PRE_SHUTDOWN_DELAY_MS), Ok(2_000));
+        assert_eq!(validate_delay(60_000), Ok(60_000));
+        assert!(validate_delay(0).is_err());
+        assert!(validate_delay(60_001).is_err());
+    }
+}
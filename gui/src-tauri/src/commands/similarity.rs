@@ -0,0 +1,147 @@
+//! Passages related to a given one by shared vocabulary.
+//!
+//! The engine ranks passages with `/v1/text/similarity`. Results are cached
+//! for ten minutes per reference and count; `min_score` is applied to the
+//! cached list, so changing it doesn't ask the engine again.
+
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, State};
+
+use super::cache::TtlCache;
+use super::engine::EngineError;
+use super::http::{self, HttpError};
+use super::reference::validate_scripture_reference;
+
+/// Event emitted when the related passages of a reference are ready
+const READY_EVENT: &str = "similarity-results-ready";
+const SIMILARITY_TTL: Duration = Duration::from_secs(10 * 60);
+const SIMILARITY_CACHE_SIZE: usize = 200;
+const MAX_TOP_K: u32 = 50;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SimilarPassage {
+    pub reference: String,
+    /// 0–1, higher is more similar
+    pub score: f32,
+    /// Lemmas the two passages have in common
+    #[serde(default)]
+    pub shared_vocabulary_count: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SimilarityResultsReady {
+    pub reference: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct SimilarityRequest<'a> {
+    reference: &'a str,
+    top_k: u32,
+}
+
+/// Unfiltered results by canonical reference and `top_k`.
+pub struct SimilarityCache(Mutex<TtlCache<(String, u32), Vec<SimilarPassage>>>);
+
+impl Default for SimilarityCache {
+    fn default() -> Self {
+        Self(Mutex::new(TtlCache::with_capacity(
+            SIMILARITY_TTL,
+            SIMILARITY_CACHE_SIZE,
+        )))
+    }
+}
+
+/// Most similar first, without those under `min_score`.
+fn rank(mut passages: Vec<SimilarPassage>, min_score: Option<f32>) -> Vec<SimilarPassage> {
+    if let Some(min_score) = min_score {
+        passages.retain(|p| p.score >= min_score);
+    }
+    passages.sort_by(|a, b| b.score.total_cmp(&a.score));
+    passages
+}
+
+async fn fetch_similar(
+    port: u16,
+    reference: &str,
+    top_k: u32,
+) -> Result<Vec<SimilarPassage>, EngineError> {
+    let body = SimilarityRequest { reference, top_k };
+    let request = http::request(Method::POST, port, "/v1/text/similarity")?.json(&body);
+    match http::send_json(request).await {
+        Ok(passages) => Ok(passages),
+        Err(HttpError::Status { status: 404, .. }) => {
+            Err(EngineError::PassageNotFound(reference.to_string()))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// The `top_k` passages (at most 50) most like `reference`, e.g. the
+/// other tellings of a parable.
+#[tauri::command]
+pub async fn compute_textual_similarity(
+    app: AppHandle,
+    cache: State<'_, SimilarityCache>,
+    port: u16,
+    reference: String,
+    top_k: u32,
+    min_score: Option<f32>,
+) -> Result<Vec<SimilarPassage>, EngineError> {
+    let validation = validate_scripture_reference(reference);
+    let Some(normalized) = validation.normalized.filter(|_| validation.valid) else {
+        return Err(EngineError::InvalidReference(
+            validation.error.unwrap_or_default(),
+        ));
+    };
+    let top_k = top_k.clamp(1, MAX_TOP_K);
+    let key = (normalized, top_k);
+
+    let cached = cache.0.lock().unwrap().get(&key);
+    let passages = match cached {
+        Some(passages) => passages,
+        None => {
+            let passages = fetch_similar(port, &key.0, top_k).await?;
+            let mut entries = cache.0.lock().unwrap();
+            entries.insert(key.clone(), passages.clone());
+            passages
+        }
+    };
+
+    let passages = rank(passages, min_score);
+    let _ = app.emit(
+        READY_EVENT,
+        SimilarityResultsReady {
+            reference: key.0,
+            count: passages.len(),
+        },
+    );
+    Ok(passages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rank() {
+        let passages: Vec<SimilarPassage> = serde_json::from_str(
+            r#"[{"reference": "Luke 15:4", "score": 0.42, "shared_vocabulary_count": 9},
+                {"reference": "Matthew 18:12", "score": 0.81, "shared_vocabulary_count": 14},
+                {"reference": "John 10:11", "score": 0.12}]"#,
+        )
+        .unwrap();
+        assert_eq!(passages[2].shared_vocabulary_count, 0);
+
+        let ranked = rank(passages.clone(), None);
+        let order: Vec<&str> = ranked.iter().map(|p| p.reference.as_str()).collect();
+        assert_eq!(order, ["Matthew 18:12", "Luke 15:4", "John 10:11"]);
+
+        let ranked = rank(passages, Some(0.4));
+        assert_eq!(ranked.len(), 2);
+        assert!(ranked.iter().all(|p| p.score >= 0.4));
+    }
+}
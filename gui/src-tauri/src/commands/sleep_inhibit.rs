@@ -0,0 +1,148 @@
+//! Keeping the system awake while long jobs run.
+//!
+//! - Windows: `SetThreadExecutionState(ES_CONTINUOUS | ES_SYSTEM_REQUIRED)`.
+//!   The state belongs to the thread that set it, so a thread of its own
+//!   holds it until the inhibitor is dropped.
+//! - macOS: `caffeinate -i -w <pid>`, which also lets go if the app dies.
+//! - Linux: `systemd-inhibit` running `cat` on a pipe the inhibitor holds.
+//!   Closing the pipe, or the app exiting, ends it and releases the lock.
+//!
+//! Inhibiting is best effort: when it fails the job runs anyway and a
+//! warning is logged.
+
+/// Holds off idle system sleep until dropped.
+pub struct SleepInhibitor {
+    _held: Option<Inhibit>,
+}
+
+impl SleepInhibitor {
+    /// Start inhibiting sleep; `why` is shown where the platform lists
+    /// inhibitors (`systemd-inhibit --list`).
+    pub fn acquire(why: &str) -> Self {
+        match Inhibit::acquire(why) {
+            Ok(inhibit) => Self {
+                _held: Some(inhibit),
+            },
+            Err(e) => {
+                tracing::warn!("Could not keep the system awake: {}", e);
+                Self { _held: None }
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+struct Inhibit {
+    /// Dropping it wakes the holding thread to clear the state
+    release: Option<std::sync::mpsc::Sender<()>>,
+    thread: Option<std::thread::JoinHandle<()>>,
+}
+
+#[cfg(target_os = "windows")]
+impl Inhibit {
+    fn acquire(_why: &str) -> Result<Self, String> {
+        use std::sync::mpsc;
+        use windows_sys::Win32::System::Power::{
+            SetThreadExecutionState, ES_CONTINUOUS, ES_SYSTEM_REQUIRED,
+        };
+
+        let (release, released) = mpsc::channel::<()>();
+        let (set, was_set) = mpsc::channel();
+        let thread = std::thread::Builder::new()
+            .name("sleep-inhibitor".to_string())
+            .spawn(move || {
+                // SAFETY: takes and returns plain flags
+                let previous =
+                    unsafe { SetThreadExecutionState(ES_CONTINUOUS | ES_SYSTEM_REQUIRED) };
+                let _ = set.send(previous != 0);
+                // Returns once the sender is dropped
+                let _ = released.recv();
+                // SAFETY: as above
+                unsafe { SetThreadExecutionState(ES_CONTINUOUS) };
+            })
+            .map_err(|e| e.to_string())?;
+        let inhibit = Self {
+            release: Some(release),
+            thread: Some(thread),
+        };
+        match was_set.recv() {
+            Ok(true) => Ok(inhibit),
+            _ => Err("SetThreadExecutionState failed".to_string()),
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl Drop for Inhibit {
+    fn drop(&mut self) {
+        self.release.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+struct Inhibit(std::process::Child);
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+impl Inhibit {
+    fn acquire(why: &str) -> Result<Self, String> {
+        use std::process::Stdio;
+
+        let mut command = inhibit_command(why);
+        command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        // An inhibitor that can't take the lock just exits
+        let child = command
+            .spawn()
+            .map_err(|e| format!("{:?}: {}", command.get_program(), e))?;
+        Ok(Self(child))
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn inhibit_command(_why: &str) -> std::process::Command {
+    let mut command = super::process::command("caffeinate");
+    command
+        .arg("-i")
+        .arg("-w")
+        .arg(std::process::id().to_string());
+    command
+}
+
+#[cfg(target_os = "linux")]
+fn inhibit_command(why: &str) -> std::process::Command {
+    let mut command = super::process::command("systemd-inhibit");
+    command
+        .arg("--what=idle:sleep")
+        .arg("--who=Red Letters")
+        .arg(format!("--why={}", why))
+        .arg("--mode=block")
+        .arg("cat");
+    command
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+impl Drop for Inhibit {
+    fn drop(&mut self) {
+        // `cat` ends on EOF and `systemd-inhibit` with it; `caffeinate`
+        // only waits for the app and has to be stopped
+        self.0.stdin.take();
+        #[cfg(target_os = "macos")]
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+struct Inhibit;
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+impl Inhibit {
+    fn acquire(_why: &str) -> Result<Self, String> {
+        Err("not supported on this platform".to_string())
+    }
+}
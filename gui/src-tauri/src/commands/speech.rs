@@ -0,0 +1,432 @@
+//! Text to speech through the OS speech engine.
+//!
+//! Each platform's engine is driven through its command-line front end:
+//! - macOS: `say` (the same synthesizer as AVSpeechSynthesizer)
+//! - Windows: SAPI through `System.Speech`, scripted with PowerShell
+//! - Linux: speech-dispatcher's `spd-say`
+//!
+//! One utterance plays at a time; starting another stops the one before.
+//! Only SAPI reports where it is in the text, so `tts-progress` events
+//! (character offsets for highlighting along) only come on Windows;
+//! `tts-finished` comes everywhere. A machine without the front end
+//! (minimal Linux installs) gets `TtsError::Unavailable`.
+
+use serde::Serialize;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, State};
+use thiserror::Error;
+
+use super::errors::{command_error, CommandError};
+use super::i18n::Localize;
+use super::process;
+
+const PROGRESS_EVENT: &str = "tts-progress";
+const FINISHED_EVENT: &str = "tts-finished";
+/// Allowed range of `rate`, 1.0 being the voice's normal speed
+const RATE_RANGE: std::ops::RangeInclusive<f32> = 0.25..=2.0;
+/// Languages of the voices offered: English translations, Greek readback
+const VOICE_LANGUAGES: &[&str] = &["en", "el"];
+/// `say`'s default speed, in words per minute
+#[cfg(target_os = "macos")]
+const SAY_DEFAULT_WPM: f32 = 175.0;
+
+#[derive(Debug, Error)]
+pub enum TtsError {
+    #[error("No speech engine is available on this system ({0})")]
+    Unavailable(String),
+    #[error("Speech rate must be between 0.25 and 2.0, got {0}")]
+    InvalidRate(f32),
+    #[error("Speech failed: {0}")]
+    Failed(String),
+}
+
+impl Localize for TtsError {
+    fn code(&self) -> &'static str {
+        match self {
+            TtsError::Unavailable(_) => "tts-unavailable",
+            TtsError::InvalidRate(_) => "tts-invalid-rate",
+            TtsError::Failed(_) => "tts-failed",
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            TtsError::Unavailable(detail) | TtsError::Failed(detail) => {
+                vec![("detail", detail.clone())]
+            }
+            TtsError::InvalidRate(rate) => vec![("rate", rate.to_string())],
+        }
+    }
+}
+
+impl CommandError for TtsError {
+    fn retryable(&self) -> bool {
+        matches!(self, TtsError::Failed(_))
+    }
+}
+
+command_error!(TtsError);
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TtsVoice {
+    /// Pass as `voice` to `speak_text`
+    pub id: String,
+    pub name: String,
+    /// BCP 47, e.g. "en-GB"
+    pub language: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TtsProgress {
+    pub utterance_id: u64,
+    /// Character offset of the word being spoken
+    pub offset: usize,
+    /// Characters in the word
+    pub length: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TtsFinished {
+    pub utterance_id: u64,
+    /// Stopped by `stop_speaking` or a newer utterance
+    pub interrupted: bool,
+}
+
+struct Utterance {
+    id: u64,
+    child: Child,
+}
+
+/// The utterance playing, if any.
+#[derive(Default)]
+pub struct SpeechState {
+    current: Mutex<Option<Utterance>>,
+    next_id: Mutex<u64>,
+}
+
+/// Turn `say -v '?'` or `spd-say -L` locale tags into BCP 47.
+#[cfg(any(not(target_os = "windows"), test))]
+fn normalize_language(tag: &str) -> String {
+    tag.replace('_', "-")
+}
+
+fn is_wanted_language(language: &str) -> bool {
+    let primary = language
+        .split('-')
+        .next()
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    VOICE_LANGUAGES.contains(&primary.as_str())
+}
+
+/// Parse `say -v '?'`: `Melina              el_GR    # Γεια σου! …`.
+/// Names can contain spaces, so the locale is the last field before `#`.
+#[cfg(any(target_os = "macos", test))]
+fn parse_say_voices(output: &str) -> Vec<TtsVoice> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields = line.split('#').next()?.trim_end();
+            let (name, locale) = fields.rsplit_once(char::is_whitespace)?;
+            let name = name.trim();
+            (!name.is_empty()).then(|| TtsVoice {
+                id: name.to_string(),
+                name: name.to_string(),
+                language: normalize_language(locale),
+            })
+        })
+        .collect()
+}
+
+/// Parse `spd-say -L`: a `NAME LANGUAGE VARIANT` header, then one voice
+/// per line. Names can contain spaces, so the last two fields are the
+/// language and variant.
+#[cfg(any(not(any(target_os = "macos", target_os = "windows")), test))]
+fn parse_spd_voices(output: &str) -> Vec<TtsVoice> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 3 || fields[0] == "NAME" {
+                return None;
+            }
+            let name = fields[..fields.len() - 2].join(" ");
+            Some(TtsVoice {
+                id: name.clone(),
+                name,
+                language: normalize_language(fields[fields.len() - 2]),
+            })
+        })
+        .collect()
+}
+
+/// Parse the voice listing script's `name<TAB>culture` lines.
+#[cfg(any(target_os = "windows", test))]
+fn parse_sapi_voices(output: &str) -> Vec<TtsVoice> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (name, culture) = line.trim_end().split_once('\t')?;
+            Some(TtsVoice {
+                id: name.to_string(),
+                name: name.to_string(),
+                language: culture.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Parse a `progress <offset> <length>` line from the SAPI script.
+fn parse_progress(line: &str) -> Option<(usize, usize)> {
+    let mut fields = line.strip_prefix("progress ")?.split_whitespace();
+    let offset = fields.next()?.parse().ok()?;
+    let length = fields.next()?.parse().ok()?;
+    Some((offset, length))
+}
+
+/// Escape text for a single-quoted PowerShell string.
+#[cfg(target_os = "windows")]
+fn ps_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+/// Run a listing command and return its stdout.
+fn listing_output(command: &mut Command) -> Result<String, TtsError> {
+    let output = command
+        .output()
+        .map_err(|e| TtsError::Unavailable(e.to_string()))?;
+    if !output.status.success() {
+        return Err(TtsError::Unavailable(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(target_os = "macos")]
+fn installed_voices() -> Result<Vec<TtsVoice>, TtsError> {
+    listing_output(process::command("say").args(["-v", "?"])).map(|out| parse_say_voices(&out))
+}
+
+#[cfg(target_os = "windows")]
+fn installed_voices() -> Result<Vec<TtsVoice>, TtsError> {
+    const SCRIPT: &str = "[Console]::OutputEncoding = [Text.Encoding]::UTF8; \
+        Add-Type -AssemblyName System.Speech; \
+        (New-Object System.Speech.Synthesis.SpeechSynthesizer).GetInstalledVoices() | \
+        Where-Object Enabled | \
+        ForEach-Object { $_.VoiceInfo.Name + \"`t\" + $_.VoiceInfo.Culture.Name }";
+    listing_output(process::command("powershell").args(["-NoProfile", "-Command", SCRIPT]))
+        .map(|out| parse_sapi_voices(&out))
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn installed_voices() -> Result<Vec<TtsVoice>, TtsError> {
+    listing_output(process::command("spd-say").arg("-L")).map(|out| parse_spd_voices(&out))
+}
+
+/// The command speaking `text`, which it reads from stdin unless the
+/// front end only takes it as an argument.
+#[cfg(target_os = "macos")]
+fn speak_command(text: &str, voice: Option<&str>, rate: f32) -> (Command, Option<String>) {
+    let mut command = process::command("say");
+    command.args(["-r", &(SAY_DEFAULT_WPM * rate).round().to_string()]);
+    if let Some(voice) = voice {
+        command.args(["-v", voice]);
+    }
+    // With no message argument, `say` reads stdin
+    (command, Some(text.to_string()))
+}
+
+#[cfg(target_os = "windows")]
+fn speak_command(text: &str, voice: Option<&str>, rate: f32) -> (Command, Option<String>) {
+    // SAPI rates run from -10 to 10
+    let sapi_rate = ((rate - 1.0) * 10.0).round().clamp(-10.0, 10.0);
+    let select_voice = voice
+        .map(|v| format!("$s.SelectVoice({}); ", ps_quote(v)))
+        .unwrap_or_default();
+    // stdin is decoded with the OEM code page unless told otherwise, which
+    // would turn Greek into question marks
+    let script = format!(
+        "[Console]::InputEncoding = [Text.Encoding]::UTF8; \
+         Add-Type -AssemblyName System.Speech; \
+         $s = New-Object System.Speech.Synthesis.SpeechSynthesizer; \
+         {select_voice}$s.Rate = {sapi_rate}; \
+         $s.add_SpeakProgress({{ param($o, $e) \
+           [Console]::Out.WriteLine(\"progress $($e.CharacterPosition) $($e.CharacterCount)\"); \
+           [Console]::Out.Flush() }}); \
+         $s.Speak([Console]::In.ReadToEnd())"
+    );
+    let mut command = process::command("powershell");
+    command.args(["-NoProfile", "-Command", &script]);
+    (command, Some(text.to_string()))
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn speak_command(text: &str, voice: Option<&str>, rate: f32) -> (Command, Option<String>) {
+    // spd-say rates run from -100 to 100; -w keeps it running until done
+    let spd_rate = ((rate - 1.0) * 100.0).round().clamp(-100.0, 100.0);
+    let mut command = process::command("spd-say");
+    command.args(["-w", "-r", &spd_rate.to_string()]);
+    if let Some(voice) = voice {
+        command.args(["-y", voice]);
+    }
+    command.args(["--", text]);
+    (command, None)
+}
+
+/// Stop the utterance playing, if any. Returns whether one was.
+fn stop_current(state: &SpeechState) -> bool {
+    let Some(mut utterance) = state.current.lock().unwrap().take() else {
+        return false;
+    };
+    let _ = utterance.child.kill();
+    let _ = utterance.child.wait();
+    true
+}
+
+/// Follow an utterance's output until it ends, then clear it from the
+/// state unless a newer one has replaced it.
+fn watch_utterance(app: AppHandle, id: u64, stdout: std::process::ChildStdout) {
+    for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+        if let Some((offset, length)) = parse_progress(&line) {
+            let _ = app.emit(
+                PROGRESS_EVENT,
+                TtsProgress {
+                    utterance_id: id,
+                    offset,
+                    length,
+                },
+            );
+        }
+    }
+    // Output ends when the process exits or is killed
+    let state = app.state::<SpeechState>();
+    let mut current = state.current.lock().unwrap();
+    let finished_here = current.as_ref().is_some_and(|u| u.id == id);
+    if finished_here {
+        if let Some(mut utterance) = current.take() {
+            let _ = utterance.child.wait();
+        }
+    }
+    drop(current);
+    let _ = app.emit(
+        FINISHED_EVENT,
+        TtsFinished {
+            utterance_id: id,
+            interrupted: !finished_here,
+        },
+    );
+}
+
+/// Installed voices for English and Greek.
+#[tauri::command]
+pub fn list_tts_voices() -> Result<Vec<TtsVoice>, TtsError> {
+    let mut voices: Vec<TtsVoice> = installed_voices()?
+        .into_iter()
+        .filter(|v| is_wanted_language(&v.language))
+        .collect();
+    voices.sort_by(|a, b| a.language.cmp(&b.language).then(a.name.cmp(&b.name)));
+    Ok(voices)
+}
+
+/// Speak `text`, stopping whatever was being spoken. `voice` is an id from
+/// `list_tts_voices` (the system default when absent) and `rate` a
+/// multiplier on the voice's normal speed. Returns the utterance id the
+/// `tts-progress` and `tts-finished` events carry.
+#[tauri::command]
+pub fn speak_text(
+    app: AppHandle,
+    state: State<'_, SpeechState>,
+    text: String,
+    voice: Option<String>,
+    rate: Option<f32>,
+) -> Result<u64, TtsError> {
+    let rate = rate.unwrap_or(1.0);
+    if !RATE_RANGE.contains(&rate) {
+        return Err(TtsError::InvalidRate(rate));
+    }
+    stop_current(&state);
+
+    let (mut command, input) = speak_command(&text, voice.as_deref(), rate);
+    let mut child = command
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => TtsError::Unavailable(e.to_string()),
+            _ => TtsError::Failed(e.to_string()),
+        })?;
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        // Dropping stdin closes it, which ends the text
+        stdin
+            .write_all(input.as_bytes())
+            .map_err(|e| TtsError::Failed(e.to_string()))?;
+    }
+    let stdout = child.stdout.take();
+
+    let id = {
+        let mut next_id = state.next_id.lock().unwrap();
+        *next_id += 1;
+        *next_id
+    };
+    *state.current.lock().unwrap() = Some(Utterance { id, child });
+    if let Some(stdout) = stdout {
+        std::thread::spawn(move || watch_utterance(app, id, stdout));
+    }
+    Ok(id)
+}
+
+/// Stop speaking. Returns whether anything was being spoken.
+#[tauri::command]
+pub fn stop_speaking(state: State<'_, SpeechState>) -> bool {
+    stop_current(&state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_voice_listings() {
+        let say = "Daniel              en_GB    # Hello! My name is Daniel.\n\
+                   Bad News            en_US    # The light you see at the end of the tunnel.\n\
+                   Melina              el_GR    # Γεια σου! Με λένε Melina.\n";
+        let voices = parse_say_voices(say);
+        assert_eq!(voices.len(), 3);
+        assert_eq!(voices[1].name, "Bad News");
+        assert_eq!(voices[2].language, "el-GR");
+
+        let spd = "NAME                  LANGUAGE  VARIANT\n\
+                   English (Great Britain) en      none\n\
+                   Greek                   el      none\n";
+        let voices = parse_spd_voices(spd);
+        assert_eq!(voices.len(), 2);
+        assert_eq!(voices[0].name, "English (Great Britain)");
+        assert_eq!(voices[1].language, "el");
+
+        let sapi = "Microsoft Zira Desktop\ten-US\r\nMicrosoft Stefanos\tel-GR\r\n";
+        let voices = parse_sapi_voices(sapi);
+        assert_eq!(voices[1].name, "Microsoft Stefanos");
+        assert_eq!(voices[1].language, "el-GR");
+    }
+
+    #[test]
+    fn test_voice_language_filter_and_progress() {
+        assert!(is_wanted_language("en-US"));
+        assert!(is_wanted_language("el"));
+        assert!(is_wanted_language("EN-GB"));
+        assert!(!is_wanted_language("de-DE"));
+        assert!(!is_wanted_language(""));
+
+        assert_eq!(parse_progress("progress 12 5"), Some((12, 5)));
+        assert_eq!(parse_progress("progress 12"), None);
+        assert_eq!(parse_progress("12 5"), None);
+    }
+}
@@ -0,0 +1,27 @@
+//! State the frontend reads once when it starts.
+//!
+//! Bundling it in one call keeps the start screen from waiting on a
+//! round trip per feature. Nothing here needs the engine, so it is
+//! available before the engine is up.
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+use super::i18n::current_locale;
+use super::verse_of_the_day::{start_verse, VerseOfTheDay};
+
+#[derive(Debug, Serialize)]
+pub struct AppStartState {
+    pub locale: String,
+    /// Today's verse; its text is missing until it has been translated once
+    pub verse_of_the_day: Option<VerseOfTheDay>,
+}
+
+/// Everything the start screen shows.
+#[tauri::command]
+pub async fn get_app_start_state(app: AppHandle) -> AppStartState {
+    AppStartState {
+        locale: current_locale(),
+        verse_of_the_day: start_verse(&app).await,
+    }
+}
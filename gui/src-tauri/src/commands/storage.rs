@@ -0,0 +1,148 @@
+//! Small helpers for JSON files under the app data directory.
+//!
+//! Writes go through a temp file and rename so a crash mid-write never
+//! leaves a truncated file behind. Each write gets its own temp file, so
+//! two writers of the same path can't interleave.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+use tempfile::NamedTempFile;
+
+/// Milliseconds since the Unix epoch.
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Resolve (and create) a directory under the app data dir.
+pub fn app_data_subdir(app: &AppHandle, name: &str) -> Result<PathBuf, String> {
+    let base = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let dir = if name.is_empty() {
+        base
+    } else {
+        base.join(name)
+    };
+    fs::create_dir_all(&dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    Ok(dir)
+}
+
+/// Path of a file directly under the app data dir.
+pub fn app_data_file(app: &AppHandle, file_name: &str) -> Result<PathBuf, String> {
+    Ok(app_data_subdir(app, "")?.join(file_name))
+}
+
+/// Read a JSON file, returning `T::default()` if it doesn't exist yet.
+pub fn read_json_or_default<T: DeserializeOwned + Default>(path: &Path) -> Result<T, String> {
+    match fs::read_to_string(path) {
+        Ok(contents) => {
+            serde_json::from_str(&contents).map_err(|e| format!("{}: {}", path.display(), e))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => Err(format!("{}: {}", path.display(), e)),
+    }
+}
+
+/// Read a JSON file that must exist.
+pub fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, String> {
+    let contents = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    serde_json::from_str(&contents).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Write a JSON file atomically (temp file + rename).
+pub fn write_json<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<(), String> {
+    let contents = serde_json::to_vec_pretty(value).map_err(|e| e.to_string())?;
+    write_atomic(path, &contents)
+}
+
+/// Write bytes atomically (temp file + rename).
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), String> {
+    write_atomic_with(path, |out| out.write_all(contents))
+}
+
+/// A new, uniquely named temp file in `path`'s directory, so the rename
+/// stays on one filesystem. It's deleted when dropped unless persisted.
+fn temp_file_for(path: &Path) -> Result<NamedTempFile, String> {
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    let mut builder = tempfile::Builder::new();
+    // Same permissions as a plain create, rather than tempfile's 0600
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        builder.permissions(fs::Permissions::from_mode(0o666));
+    }
+    builder
+        .suffix(".tmp")
+        .tempfile_in(dir)
+        .map_err(|e| format!("{}: {}", dir.display(), e))
+}
+
+/// Stream a file atomically (temp file + rename). On error the temp file
+/// is removed and any existing file at `path` is left untouched.
+pub fn write_atomic_with<F>(path: &Path, write: F) -> Result<(), String>
+where
+    F: FnOnce(&mut dyn Write) -> io::Result<()>,
+{
+    let mut tmp = temp_file_for(path)?;
+    let result = (|| {
+        let mut out = BufWriter::new(tmp.as_file_mut());
+        write(&mut out)?;
+        out.into_inner().map_err(|e| e.into_error())?.sync_all()
+    })();
+    // Dropping `tmp` removes the temp file
+    if let Err(e) = result {
+        return Err(format!("{}: {}", tmp.path().display(), e));
+    }
+    tmp.persist(path)
+        .map(drop)
+        .map_err(|e| format!("{}: {}", path.display(), e.error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_atomic() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("data.json");
+
+        // Writers of the same path don't share a temp file
+        let writers: Vec<_> = (0..8u8)
+            .map(|i| {
+                let path = path.clone();
+                std::thread::spawn(move || write_atomic(&path, &[b'a' + i; 64 * 1024]))
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap().unwrap();
+        }
+        let contents = fs::read(&path).unwrap();
+        assert_eq!(contents.len(), 64 * 1024);
+        assert!(contents.iter().all(|&b| b == contents[0]));
+
+        // A failed write keeps the old file and cleans up after itself
+        let err = write_atomic_with(&path, |out| {
+            out.write_all(b"partial")?;
+            Err(io::Error::other("disk full"))
+        })
+        .unwrap_err();
+        assert!(err.contains("disk full"), "{}", err);
+        assert_eq!(fs::read(&path).unwrap(), contents);
+        let entries: Vec<_> = fs::read_dir(path.parent().unwrap())
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(entries, ["data.json"]);
+    }
+}
@@ -0,0 +1,184 @@
+//! Strong's Concordance entries from the engine.
+//!
+//! Full dictionary entries (definition, KJV usage, occurrence count) for
+//! both testaments come from `/v1/strongs/{testament}/{number}`; the
+//! offline lexicon pack in `lexicon` only maps Greek numbers to lemmas.
+//! Entries don't change, so they are cached for a day, up to
+//! `STRONGS_CACHE_SIZE` of them.
+
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::State;
+
+use super::cache::TtlCache;
+use super::engine::EngineError;
+use super::http;
+use super::telemetry::{self, Feature};
+
+const STRONGS_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+const STRONGS_CACHE_SIZE: usize = 2000;
+/// Highest number in Strong's Hebrew dictionary
+const MAX_STRONGS_HEBREW: u32 = 8674;
+/// Highest number in Strong's Greek dictionary
+const MAX_STRONGS_GREEK: u32 = 5624;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Testament {
+    /// Hebrew and Aramaic numbers (H1–H8674)
+    Ot,
+    /// Greek numbers (G1–G5624)
+    Nt,
+}
+
+impl Testament {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Testament::Ot => "ot",
+            Testament::Nt => "nt",
+        }
+    }
+
+    /// "H" or "G", as written before the number.
+    fn prefix(self) -> char {
+        match self {
+            Testament::Ot => 'H',
+            Testament::Nt => 'G',
+        }
+    }
+
+    fn max_number(self) -> u32 {
+        match self {
+            Testament::Ot => MAX_STRONGS_HEBREW,
+            Testament::Nt => MAX_STRONGS_GREEK,
+        }
+    }
+}
+
+/// A Strong's dictionary entry. Named apart from `lexicon::StrongsEntry`,
+/// the offline pack's number-to-lemma mapping.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StrongsDictionaryEntry {
+    pub number: u32,
+    /// In the original script, e.g. "λόγος"
+    pub word: String,
+    #[serde(default)]
+    pub transliteration: String,
+    #[serde(default)]
+    pub pronunciation: String,
+    #[serde(default)]
+    pub definition: String,
+    /// How the KJV renders the word, e.g. "word, saying, account"
+    #[serde(default)]
+    pub kjv_usage: String,
+    /// Occurrences in the testament
+    #[serde(default)]
+    pub occurrences: u32,
+}
+
+/// Entries by number and testament.
+pub struct StrongsCache(Mutex<TtlCache<(u32, Testament), StrongsDictionaryEntry>>);
+
+impl Default for StrongsCache {
+    fn default() -> Self {
+        Self(Mutex::new(TtlCache::with_capacity(
+            STRONGS_TTL,
+            STRONGS_CACHE_SIZE,
+        )))
+    }
+}
+
+fn check_number(number: u32, testament: Testament) -> Result<(), EngineError> {
+    if (1..=testament.max_number()).contains(&number) {
+        return Ok(());
+    }
+    let label = format!("{}{}", testament.prefix(), number);
+    Err(match testament {
+        Testament::Nt => EngineError::InvalidStrongsNumber(label),
+        Testament::Ot => EngineError::InvalidHebrewStrongsNumber(label),
+    })
+}
+
+/// The Strong's entry for a number, e.g. 3056 in the NT for λόγος.
+#[tauri::command]
+pub async fn get_strongs_entry(
+    cache: State<'_, StrongsCache>,
+    port: u16,
+    number: u32,
+    testament: Testament,
+) -> Result<StrongsDictionaryEntry, EngineError> {
+    check_number(number, testament)?;
+    let key = (number, testament);
+    if let Some(entry) = cache.0.lock().unwrap().get(&key) {
+        return Ok(entry);
+    }
+    let path = format!("/v1/strongs/{}/{}", testament.as_str(), number);
+    let entry: StrongsDictionaryEntry =
+        http::send_json(http::request(Method::GET, port, &path)?).await?;
+    cache.0.lock().unwrap().insert(key, entry.clone());
+    Ok(entry)
+}
+
+/// Entries whose definition or KJV usage contains an English word, in
+/// one testament or both.
+#[tauri::command]
+pub async fn search_strongs_by_english(
+    cache: State<'_, StrongsCache>,
+    port: u16,
+    keyword: String,
+    testament: Option<Testament>,
+) -> Result<Vec<StrongsDictionaryEntry>, EngineError> {
+    telemetry::record_feature(Feature::StrongsSearch);
+    let keyword = keyword.trim();
+    if keyword.is_empty() {
+        return Ok(Vec::new());
+    }
+    let testaments = match testament {
+        Some(testament) => vec![testament],
+        None => vec![Testament::Ot, Testament::Nt],
+    };
+    let mut entries = Vec::new();
+    for testament in testaments {
+        let path = format!("/v1/strongs/{}/search", testament.as_str());
+        let request = http::request(Method::GET, port, &path)?.query(&[("q", keyword)]);
+        let found: Vec<StrongsDictionaryEntry> = http::send_json(request).await?;
+        {
+            let mut cache = cache.0.lock().unwrap();
+            for entry in &found {
+                cache.insert((entry.number, testament), entry.clone());
+            }
+        }
+        entries.extend(found);
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_number() {
+        assert!(check_number(3056, Testament::Nt).is_ok());
+        assert!(check_number(8674, Testament::Ot).is_ok());
+        assert!(matches!(
+            check_number(5625, Testament::Nt),
+            Err(EngineError::InvalidStrongsNumber(n)) if n == "G5625"
+        ));
+        assert!(matches!(
+            check_number(0, Testament::Ot),
+            Err(EngineError::InvalidHebrewStrongsNumber(n)) if n == "H0"
+        ));
+    }
+
+    #[test]
+    fn test_entry_defaults() {
+        let entry: StrongsDictionaryEntry =
+            serde_json::from_str(r#"{"number": 3056, "word": "λόγος"}"#).unwrap();
+        assert_eq!(entry.word, "λόγος");
+        assert_eq!(entry.occurrences, 0);
+        assert_eq!(serde_json::to_string(&Testament::Ot).unwrap(), r#""ot""#);
+    }
+}
@@ -0,0 +1,876 @@
+//! Study documents: one Markdown, HTML or PDF file with the Greek,
+//! translation, notes and vocabulary of each passage, behind a table of
+//! contents.
+//!
+//! The parts come from the other exports: verses are laid out as in
+//! `document_export`, notes are the passage's private note plus the
+//! annotations on its verses, and the vocabulary table is the flashcard
+//! vocabulary with lexicon glosses and frequencies. Passages that aren't
+//! cached yet are translated first (with progress events) unless the
+//! options say to skip them. The document closes with the source
+//! attribution, as the other exports do. In a PDF each passage starts on
+//! a new page, and the contents give its page number.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use tauri::{AppHandle, Emitter};
+
+use super::annotations::load_annotations;
+use super::document_export::{
+    escape_html, escape_markdown, greek_font_face, one_line, passage_verses,
+    write_html_attribution, write_markdown_attribution, ATTRIBUTION_HEADING, GREEK_FONT_STACK,
+    HTML_STYLE,
+};
+use super::errors::AppError;
+use super::flashcards::{passage_vocabulary, VocabularyEntry};
+use super::glossary::personal_glossary;
+use super::interlinear_table::verse_reference;
+use super::lexicon::LemmaSource;
+use super::licensing;
+use super::passages::{
+    load_passage, translate_and_cache, CachedPassage, TranslateOptions, TranslateOutcome,
+};
+use super::pdf::{PdfDocument, PdfFonts, Span, TextStyle};
+use super::red_letters::MARKDOWN_LEGEND;
+use super::reference::{canonical_reference, parse_reference_list};
+use super::save_dialog::{validate_export_path, ExportKind};
+use super::storage;
+
+/// Event emitted after each passage is prepared
+const PROGRESS_EVENT: &str = "study-document-progress";
+const DEFAULT_TITLE: &str = "Study notes";
+const PDF_LEGEND: &str = "Words of Jesus are shown in red.";
+/// Shares of the text width of the vocabulary table's columns in a PDF
+const PDF_VOCABULARY_COLUMNS: [f32; 3] = [0.3, 0.55, 0.15];
+const STUDY_STYLE: &str = "
+nav ol { padding-left: 1.5rem; }
+h3 { font-size: 1rem; margin-bottom: 0.25rem; }
+table.vocabulary { border-collapse: collapse; font-size: 0.9rem; }
+table.vocabulary th, table.vocabulary td { border-bottom: 1px solid #ddd; padding: 0.2rem 0.6rem; text-align: left; }
+td.personal-gloss::after { content: ' (personal)'; font-size: 0.8em; color: #666; }
+";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StudyFormat {
+    Markdown,
+    Html,
+    Pdf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StudySection {
+    Greek,
+    Translation,
+    Notes,
+    Vocabulary,
+}
+
+/// Every section, in document order
+const ALL_SECTIONS: [StudySection; 4] = [
+    StudySection::Greek,
+    StudySection::Translation,
+    StudySection::Notes,
+    StudySection::Vocabulary,
+];
+
+/// What to do with a passage that has no cached translation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MissingTranslation {
+    /// Translate it through the engine and cache it
+    #[default]
+    Translate,
+    Skip,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct StudyDocumentOptions {
+    pub missing: MissingTranslation,
+    /// Used for passages translated on demand
+    pub translate: TranslateOptions,
+    /// Document title; "Study notes" when unset
+    pub title: Option<String>,
+    /// Emphasize red-letter verses (bold with a legend in Markdown, red in
+    /// HTML and PDF)
+    pub highlight_red_letters: bool,
+    /// Closing list of source texts, their licenses and the engine
+    pub include_attribution: bool,
+}
+
+impl Default for StudyDocumentOptions {
+    fn default() -> Self {
+        Self {
+            missing: MissingTranslation::default(),
+            translate: TranslateOptions::default(),
+            title: None,
+            highlight_red_letters: true,
+            include_attribution: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StudyPassageState {
+    Cached,
+    /// Translated for this document
+    Translated,
+    Skipped,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StudyPassageStatus {
+    /// As given: a cached passage id or a reference
+    pub passage_id: String,
+    pub reference: Option<String>,
+    pub status: StudyPassageState,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StudyDocumentReport {
+    pub path: String,
+    pub passages: Vec<StudyPassageStatus>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StudyDocumentProgress {
+    pub passages_done: usize,
+    pub passages_total: usize,
+}
+
+/// A passage with everything its sections need.
+struct StudyPassage {
+    passage: CachedPassage,
+    /// Annotations on its verses, as (verse, text)
+    annotations: Vec<(String, String)>,
+    vocabulary: Vec<VocabularyEntry>,
+}
+
+/// The private note, if it has any text.
+fn passage_note(passage: &CachedPassage) -> Option<&str> {
+    passage.note.as_deref().filter(|n| !n.trim().is_empty())
+}
+
+fn write_markdown(
+    out: &mut dyn Write,
+    title: &str,
+    passages: &[StudyPassage],
+    sections: &[StudySection],
+    highlight_red_letters: bool,
+    attribution: &[String],
+) -> io::Result<()> {
+    let mut red_letters_shown = false;
+    write!(out, "# {}\n\n## Contents\n\n", escape_markdown(title))?;
+    for (i, p) in passages.iter().enumerate() {
+        writeln!(
+            out,
+            "{}. [{}](#passage-{})",
+            i + 1,
+            escape_markdown(&p.passage.reference),
+            i + 1
+        )?;
+    }
+    for (i, p) in passages.iter().enumerate() {
+        let verses = passage_verses(&p.passage).map_err(io::Error::other)?;
+        write!(
+            out,
+            "\n<a id=\"passage-{}\"></a>\n\n## {}\n",
+            i + 1,
+            escape_markdown(&p.passage.reference)
+        )?;
+        for section in sections {
+            match section {
+                StudySection::Greek => {
+                    write!(out, "\n### Greek\n\n")?;
+                    for v in &verses {
+                        write!(out, "<sup>{}</sup> {} ", v.number, one_line(v.greek))?;
+                    }
+                    writeln!(out)?;
+                }
+                StudySection::Translation => {
+                    write!(out, "\n### Translation\n\n")?;
+                    for v in &verses {
+                        let text = escape_markdown(&one_line(v.translation));
+                        if highlight_red_letters && v.red_letter {
+                            red_letters_shown = true;
+                            write!(out, "<sup>{}</sup> **{}** ", v.number, text)?;
+                        } else {
+                            write!(out, "<sup>{}</sup> {} ", v.number, text)?;
+                        }
+                    }
+                    writeln!(out)?;
+                }
+                StudySection::Notes => {
+                    let note = passage_note(&p.passage);
+                    if note.is_none() && p.annotations.is_empty() {
+                        continue;
+                    }
+                    write!(out, "\n### Notes\n\n")?;
+                    if let Some(note) = note {
+                        write!(out, "{}\n\n", escape_markdown(note.trim()))?;
+                    }
+                    for (verse, text) in &p.annotations {
+                        writeln!(
+                            out,
+                            "- **{}** — {}",
+                            escape_markdown(verse),
+                            escape_markdown(&one_line(text))
+                        )?;
+                    }
+                }
+                StudySection::Vocabulary => {
+                    if p.vocabulary.is_empty() {
+                        continue;
+                    }
+                    write!(
+                        out,
+                        "\n### Vocabulary\n\n| Lemma | Gloss | Frequency |\n|---|---|---|\n"
+                    )?;
+                    for word in &p.vocabulary {
+                        let mut gloss =
+                            escape_markdown(&one_line(word.gloss.as_deref().unwrap_or("")));
+                        if word.source == LemmaSource::Personal {
+                            gloss.push_str(" *(personal)*");
+                        }
+                        writeln!(
+                            out,
+                            "| {} | {} | {} |",
+                            escape_markdown(&word.lemma),
+                            gloss,
+                            word.frequency
+                        )?;
+                    }
+                }
+            }
+        }
+    }
+    if red_letters_shown {
+        write!(out, "\n{}\n", MARKDOWN_LEGEND)?;
+    }
+    write_markdown_attribution(out, attribution)
+}
+
+fn write_html(
+    out: &mut dyn Write,
+    title: &str,
+    font_face: Option<&str>,
+    passages: &[StudyPassage],
+    sections: &[StudySection],
+    highlight_red_letters: bool,
+    attribution: &[String],
+) -> io::Result<()> {
+    let greek_font = match font_face {
+        Some(_) => format!("'RedLettersGreek', {}", GREEK_FONT_STACK),
+        None => GREEK_FONT_STACK.to_string(),
+    };
+    write!(
+        out,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{}</title>\n<style>{}\n:root {{ --greek-font: {}; }}{}{}</style>\n</head>\n<body>\n",
+        escape_html(title),
+        font_face.unwrap_or(""),
+        greek_font,
+        HTML_STYLE,
+        STUDY_STYLE
+    )?;
+    writeln!(out, "<h1>{}</h1>\n<nav><ol>", escape_html(title))?;
+    for (i, p) in passages.iter().enumerate() {
+        writeln!(
+            out,
+            "<li><a href=\"#passage-{}\">{}</a></li>",
+            i + 1,
+            escape_html(&p.passage.reference)
+        )?;
+    }
+    writeln!(out, "</ol></nav>")?;
+
+    for (i, p) in passages.iter().enumerate() {
+        let verses = passage_verses(&p.passage).map_err(io::Error::other)?;
+        writeln!(
+            out,
+            "<section id=\"passage-{}\">\n<h2>{}</h2>",
+            i + 1,
+            escape_html(&p.passage.reference)
+        )?;
+        for section in sections {
+            match section {
+                StudySection::Greek => {
+                    write!(out, "<h3>Greek</h3>\n<p class=\"greek\" lang=\"grc\">")?;
+                    for v in &verses {
+                        write!(
+                            out,
+                            "<span class=\"verse-num\">{}</span>{} ",
+                            v.number,
+                            escape_html(&one_line(v.greek))
+                        )?;
+                    }
+                    writeln!(out, "</p>")?;
+                }
+                StudySection::Translation => {
+                    write!(out, "<h3>Translation</h3>\n<p>")?;
+                    for v in &verses {
+                        let class = if highlight_red_letters && v.red_letter {
+                            "gloss red-letter"
+                        } else {
+                            "gloss"
+                        };
+                        write!(
+                            out,
+                            "<span class=\"verse-num\">{}</span><span class=\"{}\">{}</span> ",
+                            v.number,
+                            class,
+                            escape_html(&one_line(v.translation))
+                        )?;
+                    }
+                    writeln!(out, "</p>")?;
+                }
+                StudySection::Notes => {
+                    let note = passage_note(&p.passage);
+                    if note.is_none() && p.annotations.is_empty() {
+                        continue;
+                    }
+                    writeln!(out, "<h3>Notes</h3>")?;
+                    if let Some(note) = note {
+                        writeln!(
+                            out,
+                            "<p>{}</p>",
+                            escape_html(note.trim()).replace('\n', "<br>")
+                        )?;
+                    }
+                    if !p.annotations.is_empty() {
+                        writeln!(out, "<ul>")?;
+                        for (verse, text) in &p.annotations {
+                            writeln!(
+                                out,
+                                "<li><b>{}</b> — {}</li>",
+                                escape_html(verse),
+                                escape_html(text)
+                            )?;
+                        }
+                        writeln!(out, "</ul>")?;
+                    }
+                }
+                StudySection::Vocabulary => {
+                    if p.vocabulary.is_empty() {
+                        continue;
+                    }
+                    writeln!(
+                        out,
+                        "<h3>Vocabulary</h3>\n<table class=\"vocabulary\">\n\
+                         <tr><th>Lemma</th><th>Gloss</th><th>Frequency</th></tr>"
+                    )?;
+                    for word in &p.vocabulary {
+                        let class = if word.source == LemmaSource::Personal {
+                            " class=\"personal-gloss\""
+                        } else {
+                            ""
+                        };
+                        writeln!(
+                            out,
+                            "<tr><td class=\"greek\" lang=\"grc\">{}</td><td{}>{}</td><td>{}</td></tr>",
+                            escape_html(&word.lemma),
+                            class,
+                            escape_html(word.gloss.as_deref().unwrap_or("")),
+                            word.frequency
+                        )?;
+                    }
+                    writeln!(out, "</table>")?;
+                }
+            }
+        }
+        writeln!(out, "</section>")?;
+    }
+    write_html_attribution(out, attribution)?;
+    writeln!(out, "</body>\n</html>")
+}
+
+fn pdf_layout<'f>(
+    fonts: &'f PdfFonts,
+    title: &str,
+    page_numbers: &[Option<usize>],
+    passages: &[StudyPassage],
+    sections: &[StudySection],
+    highlight_red_letters: bool,
+    attribution: &[String],
+) -> Result<PdfDocument<'f>, String> {
+    let mut doc = PdfDocument::new(fonts)?;
+    let mut red_letters_shown = false;
+    doc.heading(title, 1);
+    doc.heading("Contents", 2);
+    for (i, p) in passages.iter().enumerate() {
+        doc.contents_entry(&p.passage.reference, page_numbers[i], i);
+    }
+    let number = TextStyle {
+        superscript: true,
+        ..TextStyle::default()
+    };
+    for p in passages {
+        let verses = passage_verses(&p.passage)?;
+        doc.page_break();
+        doc.section(&p.passage.reference);
+        for section in sections {
+            match section {
+                StudySection::Greek => {
+                    doc.heading("Greek", 3);
+                    let spans: Vec<Span> = verses
+                        .iter()
+                        .flat_map(|v| [Span::styled(v.number, number), Span::plain(v.greek)])
+                        .collect();
+                    doc.paragraph(&spans, 0.0);
+                }
+                StudySection::Translation => {
+                    doc.heading("Translation", 3);
+                    let mut spans = Vec::new();
+                    for v in &verses {
+                        let red = highlight_red_letters && v.red_letter;
+                        red_letters_shown |= red;
+                        let style = TextStyle {
+                            red,
+                            ..TextStyle::default()
+                        };
+                        spans.push(Span::styled(v.number, number));
+                        spans.push(Span::styled(v.translation, style));
+                    }
+                    doc.paragraph(&spans, 0.0);
+                }
+                StudySection::Notes => {
+                    let note = passage_note(&p.passage);
+                    if note.is_none() && p.annotations.is_empty() {
+                        continue;
+                    }
+                    doc.heading("Notes", 3);
+                    for line in note.into_iter().flat_map(|n| n.trim().lines()) {
+                        doc.paragraph(&[Span::plain(line)], 0.0);
+                    }
+                    let bold = TextStyle {
+                        bold: true,
+                        ..TextStyle::default()
+                    };
+                    for (verse, text) in &p.annotations {
+                        doc.paragraph(
+                            &[
+                                Span::styled(verse, bold),
+                                Span::plain(" — "),
+                                Span::plain(text),
+                            ],
+                            12.0,
+                        );
+                    }
+                }
+                StudySection::Vocabulary => {
+                    if p.vocabulary.is_empty() {
+                        continue;
+                    }
+                    doc.heading("Vocabulary", 3);
+                    let rows: Vec<(String, String, String)> = p
+                        .vocabulary
+                        .iter()
+                        .map(|word| {
+                            let mut gloss = word.gloss.clone().unwrap_or_default();
+                            if word.source == LemmaSource::Personal {
+                                gloss.push_str(" (personal)");
+                            }
+                            (word.lemma.clone(), gloss, word.frequency.to_string())
+                        })
+                        .collect();
+                    let rows: Vec<Vec<Vec<Span>>> = rows
+                        .iter()
+                        .map(|(lemma, gloss, frequency)| {
+                            vec![
+                                vec![Span::plain(lemma)],
+                                vec![Span::plain(gloss)],
+                                vec![Span::plain(frequency)],
+                            ]
+                        })
+                        .collect();
+                    doc.table(
+                        &["Lemma", "Gloss", "Frequency"],
+                        &rows,
+                        &PDF_VOCABULARY_COLUMNS,
+                    );
+                }
+            }
+        }
+    }
+    if red_letters_shown {
+        doc.paragraph(&[Span::plain(PDF_LEGEND)], 0.0);
+    }
+    if !attribution.is_empty() {
+        doc.heading(ATTRIBUTION_HEADING, 3);
+        for line in attribution {
+            doc.paragraph(&[Span::plain(line)], 0.0);
+        }
+    }
+    Ok(doc)
+}
+
+fn write_pdf(
+    out: &mut dyn Write,
+    title: &str,
+    fonts: &PdfFonts,
+    passages: &[StudyPassage],
+    sections: &[StudySection],
+    highlight_red_letters: bool,
+    attribution: &[String],
+) -> io::Result<()> {
+    let layout = |page_numbers: &[Option<usize>]| {
+        pdf_layout(
+            fonts,
+            title,
+            page_numbers,
+            passages,
+            sections,
+            highlight_red_letters,
+            attribution,
+        )
+        .map_err(io::Error::other)
+    };
+    // Where the passages start is only known once they are laid out; the
+    // numbers don't change the layout, so a second pass can print them.
+    let first = layout(&vec![None; passages.len()])?;
+    let page_numbers: Vec<Option<usize>> = first.section_pages().into_iter().map(Some).collect();
+    out.write_all(&layout(&page_numbers)?.finish(title))
+}
+
+/// The cached passage `item` names, or its translation when `item` is a
+/// reference that isn't cached yet.
+async fn resolve_passage(
+    app: &AppHandle,
+    port: Option<u16>,
+    item: &str,
+    options: &StudyDocumentOptions,
+) -> Result<(CachedPassage, StudyPassageState), (StudyPassageState, String)> {
+    if let Ok(passage) = load_passage(app, item) {
+        return Ok((passage, StudyPassageState::Cached));
+    }
+    if options.missing == MissingTranslation::Skip {
+        return Err((
+            StudyPassageState::Skipped,
+            "No cached translation".to_string(),
+        ));
+    }
+    if parse_reference_list(item).is_err() {
+        return Err((
+            StudyPassageState::Failed,
+            format!("{} is neither a cached passage nor a reference", item),
+        ));
+    }
+    let Some(port) = port else {
+        return Err((
+            StudyPassageState::Skipped,
+            "Not cached, and the engine isn't running".to_string(),
+        ));
+    };
+    match translate_and_cache(app, port, item, options.translate.clone()).await {
+        Ok(TranslateOutcome::Translation(passage)) => Ok((*passage, StudyPassageState::Translated)),
+        Ok(TranslateOutcome::Gate(_)) => Err((
+            StudyPassageState::Failed,
+            "The engine needs an acknowledgement before translating".to_string(),
+        )),
+        Err(e) => Err((StudyPassageState::Failed, e.to_string())),
+    }
+}
+
+/// Annotations on the verses of `passage`, in verse order.
+fn passage_annotations(
+    passage: &CachedPassage,
+    by_verse: &HashMap<String, Vec<String>>,
+) -> Vec<(String, String)> {
+    passage
+        .verses
+        .iter()
+        .map(|v| verse_reference(&v.verse_id))
+        .flat_map(|verse| {
+            let texts = by_verse
+                .get(&canonical_reference(&verse))
+                .cloned()
+                .unwrap_or_default();
+            texts.into_iter().map(move |text| (verse.clone(), text))
+        })
+        .collect()
+}
+
+/// Export one study document covering several passages.
+///
+/// `passage_ids` are cached passage ids, or references for passages to
+/// translate first (see `StudyDocumentOptions::missing`). `sections`
+/// chooses and orders the parts of each passage; all four when unset.
+/// `dest_path` should come from `choose_save_path` with the kind matching
+/// `format`. The report gives each passage's status in input order.
+#[tauri::command]
+pub async fn export_study_document(
+    app: AppHandle,
+    port: Option<u16>,
+    passage_ids: Vec<String>,
+    dest_path: String,
+    format: StudyFormat,
+    sections: Option<Vec<StudySection>>,
+    options: Option<StudyDocumentOptions>,
+) -> Result<StudyDocumentReport, AppError> {
+    let kind = match format {
+        StudyFormat::Markdown => ExportKind::Markdown,
+        StudyFormat::Html => ExportKind::Html,
+        StudyFormat::Pdf => ExportKind::Pdf,
+    };
+    let path = validate_export_path(&app, kind, &PathBuf::from(dest_path))?;
+    if passage_ids.is_empty() {
+        return Err(AppError::InvalidInput("No passages selected".to_string()));
+    }
+    let options = options.unwrap_or_default();
+    let mut sections_in_order = Vec::new();
+    for section in sections.unwrap_or_else(|| ALL_SECTIONS.to_vec()) {
+        if !sections_in_order.contains(&section) {
+            sections_in_order.push(section);
+        }
+    }
+    let sections = sections_in_order;
+    if sections.is_empty() {
+        return Err(AppError::InvalidInput("No sections selected".to_string()));
+    }
+
+    let mut by_verse: HashMap<String, Vec<String>> = HashMap::new();
+    if sections.contains(&StudySection::Notes) {
+        for annotation in load_annotations(&app)? {
+            by_verse
+                .entry(annotation.reference)
+                .or_default()
+                .push(annotation.text);
+        }
+    }
+
+    let glossary = personal_glossary(&app);
+    let mut passages = Vec::new();
+    let mut statuses = Vec::new();
+    for (done, item) in passage_ids.iter().enumerate() {
+        let resolved =
+            resolve_passage(&app, port, item, &options)
+                .await
+                .and_then(|(passage, state)| match passage_verses(&passage) {
+                    Ok(_) => Ok((passage, state)),
+                    Err(e) => Err((StudyPassageState::Failed, e)),
+                });
+        statuses.push(match resolved {
+            Ok((passage, state)) => {
+                let status = StudyPassageStatus {
+                    passage_id: item.clone(),
+                    reference: Some(passage.reference.clone()),
+                    status: state,
+                    error: None,
+                };
+                let vocabulary = if sections.contains(&StudySection::Vocabulary) {
+                    passage_vocabulary(&passage, &glossary)
+                } else {
+                    Vec::new()
+                };
+                passages.push(StudyPassage {
+                    annotations: passage_annotations(&passage, &by_verse),
+                    vocabulary,
+                    passage,
+                });
+                status
+            }
+            Err((state, error)) => StudyPassageStatus {
+                passage_id: item.clone(),
+                reference: None,
+                status: state,
+                error: Some(error),
+            },
+        });
+        let _ = app.emit(
+            PROGRESS_EVENT,
+            StudyDocumentProgress {
+                passages_done: done + 1,
+                passages_total: passage_ids.len(),
+            },
+        );
+    }
+    if passages.is_empty() {
+        return Err(AppError::Failed(
+            "None of the selected passages could be included".to_string(),
+        ));
+    }
+
+    let title = options.title.as_deref().unwrap_or(DEFAULT_TITLE);
+    let attribution = licensing::attribution_lines(
+        options.include_attribution,
+        passages.iter().map(|p| &p.passage),
+    );
+    match format {
+        StudyFormat::Pdf => {
+            let fonts = PdfFonts::bundled(&app)?;
+            storage::write_atomic_with(&path, |out| {
+                write_pdf(
+                    out,
+                    title,
+                    &fonts,
+                    &passages,
+                    &sections,
+                    options.highlight_red_letters,
+                    &attribution,
+                )
+            })?
+        }
+        StudyFormat::Html => {
+            let font_face = greek_font_face(&app);
+            storage::write_atomic_with(&path, |out| {
+                write_html(
+                    out,
+                    title,
+                    font_face.as_deref(),
+                    &passages,
+                    &sections,
+                    options.highlight_red_letters,
+                    &attribution,
+                )
+            })?
+        }
+        _ => storage::write_atomic_with(&path, |out| {
+            write_markdown(
+                out,
+                title,
+                &passages,
+                &sections,
+                options.highlight_red_letters,
+                &attribution,
+            )
+        })?,
+    }
+
+    Ok(StudyDocumentReport {
+        path: path.to_string_lossy().into_owned(),
+        passages: statuses,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::red_letters::SpeakerSpan;
+    use crate::testsupport::passage::PassageBuilder;
+    use crate::testsupport::pdf_fonts;
+
+    fn study_passage() -> StudyPassage {
+        let passage = PassageBuilder::new()
+            .id("p")
+            .reference("John 3:16-17")
+            .verse("John.3.16", "Οὕτως γὰρ ἠγάπησεν ὁ θεὸς", "For God so loved")
+            .verse("John.3.17", "οὐ γὰρ ἀπέστειλεν", "For God did not send")
+            .note(Some("Compare 1 John 4:9"))
+            .speakers(vec![SpeakerSpan {
+                start_verse: "John.3.16".to_string(),
+                end_verse: "John.3.17".to_string(),
+                speaker: "Jesus".to_string(),
+                confidence: None,
+                source: None,
+            }])
+            .response(serde_json::json!({}))
+            .build();
+        let mut by_verse = HashMap::new();
+        by_verse.insert(
+            canonical_reference("John 3:17"),
+            vec!["Purpose clause".to_string()],
+        );
+        StudyPassage {
+            annotations: passage_annotations(&passage, &by_verse),
+            vocabulary: vec![VocabularyEntry {
+                lemma: "ἀγαπάω".to_string(),
+                gloss: Some("to love | cherish".to_string()),
+                frequency: 143,
+                source: LemmaSource::Personal,
+            }],
+            passage,
+        }
+    }
+
+    #[test]
+    fn test_markdown_study_document() {
+        let passages = [study_passage()];
+        let mut out = Vec::new();
+        write_markdown(&mut out, "Exam", &passages, &ALL_SECTIONS, true, &[]).unwrap();
+        let md = String::from_utf8(out).unwrap();
+        assert!(md.starts_with("# Exam\n\n## Contents\n\n1. [John 3:16-17](#passage-1)\n"));
+        let order: Vec<usize> = [
+            "### Greek",
+            "### Translation",
+            "### Notes",
+            "### Vocabulary",
+        ]
+        .iter()
+        .map(|h| md.find(h).unwrap())
+        .collect();
+        assert!(order.windows(2).all(|w| w[0] < w[1]));
+        assert!(md.contains("<sup>16</sup> Οὕτως γὰρ ἠγάπησεν ὁ θεὸς "));
+        assert!(md.contains("<sup>17</sup> **For God did not send**"));
+        assert!(md.ends_with(&format!("\n{}\n", MARKDOWN_LEGEND)));
+        assert!(md.contains("- **John 3:17** — Purpose clause\n"));
+        assert!(md.contains("| ἀγαπάω | to love \\| cherish *(personal)* | 143 |\n"));
+    }
+
+    #[test]
+    fn test_sections_can_be_omitted() {
+        let passages = [study_passage()];
+        let mut out = Vec::new();
+        let sections = [StudySection::Vocabulary, StudySection::Translation];
+        let attribution = ["SBLGNT & MorphGNT".to_string()];
+        write_html(
+            &mut out,
+            "Exam",
+            None,
+            &passages,
+            &sections,
+            true,
+            &attribution,
+        )
+        .unwrap();
+        let html = String::from_utf8(out).unwrap();
+        assert!(html.contains("<li><a href=\"#passage-1\">John 3:16-17</a></li>"));
+        assert!(
+            html.find("<h3>Vocabulary</h3>").unwrap() < html.find("<h3>Translation</h3>").unwrap()
+        );
+        assert!(!html.contains("<h3>Greek</h3>"));
+        assert!(!html.contains("Purpose clause"));
+        assert!(html.contains("<li>SBLGNT &amp; MorphGNT</li>\n</ul></footer>\n</body>"));
+        assert!(html.trim_end().ends_with("</html>"));
+    }
+
+    #[test]
+    fn test_pdf_study_document() {
+        let fonts = pdf_fonts();
+        let passages = [study_passage(), study_passage()];
+        let doc = pdf_layout(
+            &fonts,
+            "Exam",
+            &[None, None],
+            &passages,
+            &ALL_SECTIONS,
+            true,
+            &[],
+        )
+        .unwrap();
+        assert_eq!(doc.section_pages(), [2, 3]);
+
+        let mut out = Vec::new();
+        write_pdf(
+            &mut out,
+            "Exam",
+            &fonts,
+            &passages,
+            &ALL_SECTIONS,
+            true,
+            &[],
+        )
+        .unwrap();
+        let pdf = String::from_utf8_lossy(&out);
+        assert!(pdf.starts_with("%PDF-"));
+        assert!(pdf.contains("/Title (Exam)"));
+        assert_eq!(pdf.matches("/Title (John 3:16-17)").count(), 2);
+        assert_eq!(pdf.matches("/Subtype /Link").count(), 2);
+    }
+}
@@ -0,0 +1,416 @@
+//! User tags on saved search results and cached translations.
+//!
+//! Tags live in one file, `tags.json` under the app data dir, mapping
+//! items of either store to their tags, so renaming a tag everywhere is a
+//! single atomic write. Tags match case-insensitively but keep the case
+//! they were first written in: tagging with "Exam-2" after "exam-2" exists
+//! adds "exam-2".
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use tauri::AppHandle;
+
+use super::errors::AppError;
+use super::passages::{load_all_passages, load_passage, CachedPassageSummary};
+use super::saved_results::{load_saved, SavedSearchResult};
+use super::storage;
+
+const TAGS_FILE: &str = "tags.json";
+/// Longest tag, in characters
+const MAX_TAG_LEN: usize = 64;
+
+/// Serializes read-modify-write cycles on the tags file
+static TAGS_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaggedItemKind {
+    /// A bookmarked search hit
+    SavedResult,
+    /// A cached translation
+    Passage,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct TaggedRef {
+    kind: TaggedItemKind,
+    id: String,
+    tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TagUsage {
+    pub tag: String,
+    /// Items carrying the tag
+    pub count: u32,
+}
+
+/// An item found by `find_by_tag`, with all its tags.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", content = "item", rename_all = "snake_case")]
+pub enum TaggedEntry {
+    SavedResult(SavedSearchResult),
+    Passage(CachedPassageSummary),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TaggedItem {
+    #[serde(flatten)]
+    pub entry: TaggedEntry,
+    pub tags: Vec<String>,
+}
+
+/// Key tags are matched by.
+fn tag_key(tag: &str) -> String {
+    tag.to_lowercase()
+}
+
+/// Trim a tag and collapse inner whitespace, rejecting empty or long ones.
+fn normalize_tag(tag: &str) -> Result<String, String> {
+    let tag = tag.split_whitespace().collect::<Vec<_>>().join(" ");
+    if tag.is_empty() {
+        return Err("A tag can't be empty".to_string());
+    }
+    if tag.chars().count() > MAX_TAG_LEN {
+        return Err(format!("Tags are limited to {} characters", MAX_TAG_LEN));
+    }
+    Ok(tag)
+}
+
+fn load_refs(app: &AppHandle) -> Result<Vec<TaggedRef>, String> {
+    storage::read_json_or_default(&storage::app_data_file(app, TAGS_FILE)?)
+}
+
+fn store_refs(app: &AppHandle, refs: &[TaggedRef]) -> Result<(), String> {
+    storage::write_json(&storage::app_data_file(app, TAGS_FILE)?, refs)
+}
+
+/// Display form to use for `tag`: the existing one if the tag is in use.
+fn display_form(refs: &[TaggedRef], tag: &str) -> String {
+    let key = tag_key(tag);
+    refs.iter()
+        .flat_map(|r| &r.tags)
+        .find(|t| tag_key(t) == key)
+        .cloned()
+        .unwrap_or_else(|| tag.to_string())
+}
+
+/// Add `tag` to an item; returns whether it was new to the item.
+fn tag_item(refs: &mut Vec<TaggedRef>, kind: TaggedItemKind, id: &str, tag: &str) -> bool {
+    let tag = display_form(refs, tag);
+    let i = match refs.iter().position(|r| r.kind == kind && r.id == id) {
+        Some(i) => i,
+        None => {
+            refs.push(TaggedRef {
+                kind,
+                id: id.to_string(),
+                tags: Vec::new(),
+            });
+            refs.len() - 1
+        }
+    };
+    let key = tag_key(&tag);
+    if refs[i].tags.iter().any(|t| tag_key(t) == key) {
+        return false;
+    }
+    refs[i].tags.push(tag);
+    true
+}
+
+/// Remove `tag` from an item, dropping items left without tags.
+fn untag_item(refs: &mut Vec<TaggedRef>, kind: TaggedItemKind, id: &str, tag: &str) -> bool {
+    let key = tag_key(tag);
+    let mut removed = false;
+    for r in refs.iter_mut().filter(|r| r.kind == kind && r.id == id) {
+        let before = r.tags.len();
+        r.tags.retain(|t| tag_key(t) != key);
+        removed |= r.tags.len() != before;
+    }
+    refs.retain(|r| !r.tags.is_empty());
+    removed
+}
+
+/// Rename `old` to `new` on every item; returns the number of items
+/// changed. Items that already have `new` just lose `old`.
+fn rename_in(refs: &mut [TaggedRef], old: &str, new: &str) -> u32 {
+    let (old_key, new_key) = (tag_key(old), tag_key(new));
+    // Merging into a tag in use keeps its spelling
+    let new = if new_key == old_key {
+        new.to_string()
+    } else {
+        display_form(refs, new)
+    };
+    let mut changed = 0;
+    for r in refs.iter_mut() {
+        let Some(i) = r.tags.iter().position(|t| tag_key(t) == old_key) else {
+            continue;
+        };
+        changed += 1;
+        let has_new = r
+            .tags
+            .iter()
+            .enumerate()
+            .any(|(j, t)| j != i && tag_key(t) == new_key);
+        if has_new {
+            r.tags.remove(i);
+        } else {
+            r.tags[i] = new.clone();
+        }
+    }
+    changed
+}
+
+fn usage(refs: &[TaggedRef]) -> Vec<TagUsage> {
+    let mut counts: BTreeMap<String, TagUsage> = BTreeMap::new();
+    for tag in refs.iter().flat_map(|r| &r.tags) {
+        counts
+            .entry(tag_key(tag))
+            .or_insert_with(|| TagUsage {
+                tag: tag.clone(),
+                count: 0,
+            })
+            .count += 1;
+    }
+    counts.into_values().collect()
+}
+
+/// Ids of items of `kind` carrying `tag`.
+fn ids_with_tag(refs: &[TaggedRef], kind: TaggedItemKind, tag: &str) -> Vec<String> {
+    let key = tag_key(tag);
+    refs.iter()
+        .filter(|r| r.kind == kind && r.tags.iter().any(|t| tag_key(t) == key))
+        .map(|r| r.id.clone())
+        .collect()
+}
+
+fn item_exists(app: &AppHandle, kind: TaggedItemKind, id: &str) -> Result<bool, String> {
+    Ok(match kind {
+        TaggedItemKind::SavedResult => load_saved(app)?.iter().any(|s| s.id == id),
+        TaggedItemKind::Passage => load_passage(app, id).is_ok(),
+    })
+}
+
+/// Passage ids for an export: `passage_ids` as given, or, with a tag,
+/// only those carrying it. No ids and a tag means every tagged passage.
+pub fn passage_ids_for_export(
+    app: &AppHandle,
+    passage_ids: Vec<String>,
+    tag: Option<&str>,
+) -> Result<Vec<String>, String> {
+    let Some(tag) = tag else {
+        return Ok(passage_ids);
+    };
+    let tagged = ids_with_tag(&load_refs(app)?, TaggedItemKind::Passage, tag);
+    let ids: Vec<String> = if passage_ids.is_empty() {
+        tagged
+    } else {
+        passage_ids
+            .into_iter()
+            .filter(|id| tagged.contains(id))
+            .collect()
+    };
+    if ids.is_empty() {
+        return Err(format!("No passages are tagged {}", tag));
+    }
+    Ok(ids)
+}
+
+/// Ids of saved search results carrying `tag`.
+pub fn saved_result_ids_with_tag(app: &AppHandle, tag: &str) -> Result<Vec<String>, String> {
+    Ok(ids_with_tag(
+        &load_refs(app)?,
+        TaggedItemKind::SavedResult,
+        tag,
+    ))
+}
+
+/// Drop the tags of an item that was deleted.
+pub fn forget_item(app: &AppHandle, kind: TaggedItemKind, id: &str) -> Result<(), String> {
+    let _guard = TAGS_LOCK.lock().unwrap();
+    let mut refs = load_refs(app)?;
+    let before = refs.len();
+    refs.retain(|r| !(r.kind == kind && r.id == id));
+    if refs.len() != before {
+        store_refs(app, &refs)?;
+    }
+    Ok(())
+}
+
+/// Tag a saved search result or cached translation. Returns the item's
+/// tags.
+#[tauri::command]
+pub fn add_tag(
+    app: AppHandle,
+    item_kind: TaggedItemKind,
+    item_id: String,
+    tag: String,
+) -> Result<Vec<String>, AppError> {
+    let tag = normalize_tag(&tag)?;
+    if !item_exists(&app, item_kind, &item_id)? {
+        return Err(AppError::NotFound(format!("No item {}", item_id)));
+    }
+    let _guard = TAGS_LOCK.lock().unwrap();
+    let mut refs = load_refs(&app)?;
+    if tag_item(&mut refs, item_kind, &item_id, &tag) {
+        store_refs(&app, &refs)?;
+    }
+    Ok(refs
+        .into_iter()
+        .find(|r| r.kind == item_kind && r.id == item_id)
+        .map(|r| r.tags)
+        .unwrap_or_default())
+}
+
+/// Remove a tag from an item; `false` when the item didn't have it.
+#[tauri::command]
+pub fn remove_tag(
+    app: AppHandle,
+    item_kind: TaggedItemKind,
+    item_id: String,
+    tag: String,
+) -> Result<bool, AppError> {
+    let _guard = TAGS_LOCK.lock().unwrap();
+    let mut refs = load_refs(&app)?;
+    let removed = untag_item(&mut refs, item_kind, &item_id, &tag);
+    if removed {
+        store_refs(&app, &refs)?;
+    }
+    Ok(removed)
+}
+
+/// Every tag in use and how many items carry it, sorted by name.
+#[tauri::command]
+pub fn list_tags(app: AppHandle) -> Result<Vec<TagUsage>, AppError> {
+    Ok(usage(&load_refs(&app)?))
+}
+
+/// Rename a tag on every item at once; returns the number of items
+/// changed. Renaming onto an existing tag merges the two.
+#[tauri::command]
+pub fn rename_tag(app: AppHandle, old: String, new: String) -> Result<u32, AppError> {
+    let new = normalize_tag(&new)?;
+    let _guard = TAGS_LOCK.lock().unwrap();
+    let mut refs = load_refs(&app)?;
+    let changed = rename_in(&mut refs, &old, &new);
+    if changed > 0 {
+        store_refs(&app, &refs)?;
+    }
+    Ok(changed)
+}
+
+/// Items carrying `tag`, from the given stores (all when unset). Saved
+/// results come newest first, then passages most recently translated
+/// first. Items deleted since they were tagged are left out.
+#[tauri::command]
+pub fn find_by_tag(
+    app: AppHandle,
+    tag: String,
+    kinds: Option<Vec<TaggedItemKind>>,
+) -> Result<Vec<TaggedItem>, AppError> {
+    let refs = load_refs(&app)?;
+    let key = tag_key(&tag);
+    let tagged: HashMap<(TaggedItemKind, &str), &Vec<String>> = refs
+        .iter()
+        .filter(|r| r.tags.iter().any(|t| tag_key(t) == key))
+        .map(|r| ((r.kind, r.id.as_str()), &r.tags))
+        .collect();
+    let wanted = |kind| {
+        kinds.as_ref().is_none_or(|k| k.contains(&kind)) && tagged.keys().any(|(k, _)| *k == kind)
+    };
+    let tags_of = |kind, id: &str| tagged.get(&(kind, id)).map(|t| t.to_vec());
+
+    let mut items = Vec::new();
+    if wanted(TaggedItemKind::SavedResult) {
+        for saved in load_saved(&app)? {
+            if let Some(tags) = tags_of(TaggedItemKind::SavedResult, &saved.id) {
+                items.push(TaggedItem {
+                    entry: TaggedEntry::SavedResult(saved),
+                    tags,
+                });
+            }
+        }
+    }
+    if wanted(TaggedItemKind::Passage) {
+        for passage in load_all_passages(&app)? {
+            if let Some(tags) = tags_of(TaggedItemKind::Passage, &passage.id) {
+                items.push(TaggedItem {
+                    entry: TaggedEntry::Passage(CachedPassageSummary::from(passage)),
+                    tags,
+                });
+            }
+        }
+    }
+    Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use TaggedItemKind::{Passage, SavedResult};
+
+    fn tags_of(refs: &[TaggedRef], kind: TaggedItemKind, id: &str) -> Vec<String> {
+        refs.iter()
+            .find(|r| r.kind == kind && r.id == id)
+            .map(|r| r.tags.clone())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_normalize_tag() {
+        assert_eq!(normalize_tag("  exam   2 ").unwrap(), "exam 2");
+        assert!(normalize_tag(" \t").is_err());
+        assert!(normalize_tag(&"λ".repeat(MAX_TAG_LEN)).is_ok());
+        assert!(normalize_tag(&"λ".repeat(MAX_TAG_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn test_tags_match_case_insensitively() {
+        let mut refs = Vec::new();
+        assert!(tag_item(&mut refs, Passage, "aa", "Prayer"));
+        assert!(tag_item(&mut refs, SavedResult, "s1", "prayer"));
+        assert!(!tag_item(&mut refs, Passage, "aa", "PRAYER"));
+        assert!(tag_item(&mut refs, Passage, "aa", "parables"));
+        // The first spelling is kept
+        assert_eq!(tags_of(&refs, SavedResult, "s1"), ["Prayer"]);
+        assert_eq!(
+            usage(&refs),
+            [
+                TagUsage {
+                    tag: "parables".to_string(),
+                    count: 1
+                },
+                TagUsage {
+                    tag: "Prayer".to_string(),
+                    count: 2
+                },
+            ]
+        );
+        assert_eq!(ids_with_tag(&refs, Passage, "prayer"), ["aa"]);
+
+        assert!(untag_item(&mut refs, SavedResult, "s1", "PRAYER"));
+        assert!(!untag_item(&mut refs, SavedResult, "s1", "prayer"));
+        // Items without tags are dropped
+        assert_eq!(refs.len(), 1);
+    }
+
+    #[test]
+    fn test_rename_tag() {
+        let mut refs = Vec::new();
+        tag_item(&mut refs, Passage, "aa", "exam-2");
+        tag_item(&mut refs, Passage, "bb", "exam-2");
+        tag_item(&mut refs, Passage, "bb", "Finals");
+        tag_item(&mut refs, SavedResult, "s1", "prayer");
+
+        assert_eq!(rename_in(&mut refs, "EXAM-2", "Midterm"), 2);
+        assert_eq!(tags_of(&refs, Passage, "aa"), ["Midterm"]);
+        // Renaming onto a tag the item has merges them
+        assert_eq!(rename_in(&mut refs, "midterm", "finals"), 2);
+        assert_eq!(tags_of(&refs, Passage, "aa"), ["Finals"]);
+        assert_eq!(tags_of(&refs, Passage, "bb"), ["Finals"]);
+        // Changing only the case
+        assert_eq!(rename_in(&mut refs, "finals", "FINALS"), 2);
+        assert_eq!(tags_of(&refs, Passage, "bb"), ["FINALS"]);
+        assert_eq!(rename_in(&mut refs, "missing", "x"), 0);
+    }
+}
@@ -0,0 +1,395 @@
+//! Opt-in, anonymous usage counts.
+//!
+//! Only the events in `TelemetryEvent` are counted: a feature was used, an
+//! export format was chosen, the engine started or failed to. Counts are
+//! kept per UTC day in `telemetry/queue.json` with a random install id and
+//! nothing else: no text, references or paths. Every hour the queue is
+//! posted to the configured endpoint, unless offline; counts that can't be
+//! sent within `RETENTION_DAYS` are dropped. `get_telemetry_preview`
+//! returns exactly the body that would be posted.
+//!
+//! Nothing is recorded until the user opts in, and opting out deletes the
+//! queue. Builds without the `telemetry` feature leave out the queue and
+//! the upload entirely; the commands stay so the frontend can tell.
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use super::errors::AppError;
+use super::save_dialog::ExportKind;
+use super::settings;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TelemetrySettings {
+    /// Opt-in; nothing is recorded while unset
+    pub enabled: bool,
+    /// Where queued counts are posted; they stay queued while unset
+    pub endpoint: Option<String>,
+}
+
+/// Features whose use is counted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Feature {
+    Translation,
+    Interlinear,
+    Alignment,
+    Commentary,
+    ParallelVersions,
+    StrongsSearch,
+}
+
+/// Everything telemetry can record. Adding a variant changes what users
+/// agreed to share; keep it to counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum TelemetryEvent {
+    FeatureUsed { feature: Feature },
+    ExportFormat { format: ExportKind },
+    EngineStart { success: bool },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventCount {
+    #[serde(flatten)]
+    pub event: TelemetryEvent,
+    /// UTC date, e.g. "2026-10-16"
+    pub day: String,
+    pub count: u32,
+}
+
+/// The queued counts; also the body posted to the endpoint.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TelemetryQueue {
+    /// Random id made when the first event is queued; a purge starts a new one
+    pub install_id: String,
+    pub events: Vec<EventCount>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TelemetryPreview {
+    /// False in builds without the `telemetry` feature
+    pub available: bool,
+    pub enabled: bool,
+    pub endpoint: Option<String>,
+    /// What the next upload would send
+    pub queue: TelemetryQueue,
+}
+
+#[cfg(feature = "telemetry")]
+mod queue {
+    use chrono::{NaiveDate, Utc};
+    use reqwest::Method;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Mutex, OnceLock};
+    use std::time::Duration;
+    use tauri::AppHandle;
+
+    use super::{EventCount, TelemetryEvent, TelemetryQueue};
+    use crate::commands::http::{self, HttpError};
+    use crate::commands::settings;
+    use crate::commands::storage;
+
+    const TELEMETRY_DIR: &str = "telemetry";
+    const QUEUE_FILE: &str = "queue.json";
+    /// Days unsent counts are kept
+    const RETENTION_DAYS: i64 = 30;
+    const UPLOAD_INTERVAL: Duration = Duration::from_secs(60 * 60);
+    const DAY_FORMAT: &str = "%Y-%m-%d";
+
+    /// Mirrors the setting so `record` needs no `AppHandle`
+    static ENABLED: AtomicBool = AtomicBool::new(false);
+    /// Set once the app data dir is known
+    static QUEUE_PATH: OnceLock<PathBuf> = OnceLock::new();
+    /// Serializes read-modify-write cycles on the queue file
+    static QUEUE_LOCK: Mutex<()> = Mutex::new(());
+
+    impl TelemetryQueue {
+        pub(super) fn add(&mut self, event: TelemetryEvent, day: &str) {
+            if self.install_id.is_empty() {
+                self.install_id = uuid::Uuid::new_v4().to_string();
+            }
+            match self
+                .events
+                .iter_mut()
+                .find(|e| e.event == event && e.day == day)
+            {
+                Some(entry) => entry.count += 1,
+                None => self.events.push(EventCount {
+                    event,
+                    day: day.to_string(),
+                    count: 1,
+                }),
+            }
+        }
+
+        /// Drop counts older than the retention period, and any with an
+        /// unreadable day.
+        pub(super) fn prune(&mut self, today: NaiveDate) {
+            self.events.retain(|e| {
+                NaiveDate::parse_from_str(&e.day, DAY_FORMAT)
+                    .is_ok_and(|day| (today - day).num_days() < RETENTION_DAYS)
+            });
+        }
+
+        /// Subtract counts that were uploaded; events recorded during the
+        /// upload stay queued.
+        pub(super) fn remove_sent(&mut self, sent: &[EventCount]) {
+            for sent in sent {
+                if let Some(entry) = self
+                    .events
+                    .iter_mut()
+                    .find(|e| e.event == sent.event && e.day == sent.day)
+                {
+                    entry.count = entry.count.saturating_sub(sent.count);
+                }
+            }
+            self.events.retain(|e| e.count > 0);
+        }
+    }
+
+    fn today() -> NaiveDate {
+        Utc::now().date_naive()
+    }
+
+    fn queue_path() -> Result<&'static PathBuf, String> {
+        QUEUE_PATH
+            .get()
+            .ok_or_else(|| "Telemetry is not initialized".to_string())
+    }
+
+    fn update_queue(change: impl FnOnce(&mut TelemetryQueue)) -> Result<TelemetryQueue, String> {
+        let path = queue_path()?;
+        let _guard = QUEUE_LOCK.lock().unwrap();
+        let mut queue: TelemetryQueue = storage::read_json_or_default(path)?;
+        change(&mut queue);
+        storage::write_json(path, &queue)?;
+        Ok(queue)
+    }
+
+    pub fn record(event: TelemetryEvent) {
+        if !ENABLED.load(Ordering::SeqCst) || QUEUE_PATH.get().is_none() {
+            return;
+        }
+        let day = today().format(DAY_FORMAT).to_string();
+        if let Err(e) = update_queue(|queue| queue.add(event, &day)) {
+            tracing::warn!("Could not queue a telemetry event: {}", e);
+        }
+    }
+
+    pub fn queued() -> Result<TelemetryQueue, String> {
+        let _guard = QUEUE_LOCK.lock().unwrap();
+        storage::read_json_or_default(queue_path()?)
+    }
+
+    pub fn purge() -> Result<(), String> {
+        let path = queue_path()?;
+        let _guard = QUEUE_LOCK.lock().unwrap();
+        match fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(format!("{}: {}", path.display(), e))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn set_enabled(enabled: bool) {
+        ENABLED.store(enabled, Ordering::SeqCst);
+    }
+
+    /// Post the queue and remove what was sent. Skipped while opted out,
+    /// without an endpoint or offline.
+    fn upload(app: &AppHandle) -> Result<(), String> {
+        let telemetry = settings::load_settings(app)?.telemetry;
+        if !telemetry.enabled {
+            return Ok(());
+        }
+        let queue = update_queue(|queue| queue.prune(today()))?;
+        let Some(endpoint) = telemetry.endpoint.filter(|_| !queue.events.is_empty()) else {
+            return Ok(());
+        };
+        let request = match http::remote(Method::POST, &endpoint) {
+            Ok(request) => request,
+            Err(HttpError::Offline) => return Ok(()),
+            Err(e) => return Err(e.to_string()),
+        };
+        let response = tauri::async_runtime::block_on(request.json(&queue).send())
+            .map_err(|e| format!("Could not send telemetry: {}", e.without_url()))?;
+        if !response.status().is_success() {
+            return Err(format!(
+                "The telemetry endpoint returned HTTP {}",
+                response.status().as_u16()
+            ));
+        }
+        update_queue(|current| current.remove_sent(&queue.events))?;
+        Ok(())
+    }
+
+    pub fn init(app: &AppHandle) {
+        let path = match storage::app_data_subdir(app, TELEMETRY_DIR) {
+            Ok(dir) => dir.join(QUEUE_FILE),
+            Err(e) => {
+                tracing::warn!("Telemetry unavailable: {}", e);
+                return;
+            }
+        };
+        let _ = QUEUE_PATH.set(path);
+        let enabled = settings::load_settings(app)
+            .map(|s| s.telemetry.enabled)
+            .unwrap_or(false);
+        ENABLED.store(enabled, Ordering::SeqCst);
+
+        let app = app.clone();
+        std::thread::spawn(move || loop {
+            if let Err(e) = upload(&app) {
+                tracing::warn!("Telemetry upload: {}", e);
+            }
+            std::thread::sleep(UPLOAD_INTERVAL);
+        });
+    }
+}
+
+#[cfg(not(feature = "telemetry"))]
+mod queue {
+    use tauri::AppHandle;
+
+    use super::{TelemetryEvent, TelemetryQueue};
+
+    pub fn record(_event: TelemetryEvent) {}
+
+    pub fn queued() -> Result<TelemetryQueue, String> {
+        Ok(TelemetryQueue::default())
+    }
+
+    pub fn purge() -> Result<(), String> {
+        Ok(())
+    }
+
+    pub fn set_enabled(_enabled: bool) {}
+
+    pub fn init(_app: &AppHandle) {}
+}
+
+/// Count an event, if the user opted in.
+pub fn record(event: TelemetryEvent) {
+    queue::record(event);
+}
+
+/// Count a use of `feature`, if the user opted in.
+pub fn record_feature(feature: Feature) {
+    record(TelemetryEvent::FeatureUsed { feature });
+}
+
+/// Restore the opt-in and start the hourly upload. Called from setup.
+pub fn init_telemetry(app: &AppHandle) {
+    queue::init(app);
+}
+
+/// What is queued, exactly as it would be sent, and where to.
+#[tauri::command]
+pub fn get_telemetry_preview(app: AppHandle) -> Result<TelemetryPreview, AppError> {
+    let telemetry = settings::load_settings(&app)?.telemetry;
+    Ok(TelemetryPreview {
+        available: cfg!(feature = "telemetry"),
+        enabled: telemetry.enabled,
+        endpoint: telemetry.endpoint,
+        queue: queue::queued()?,
+    })
+}
+
+/// Opt in to or out of telemetry. Opting out deletes the queue.
+#[tauri::command]
+pub fn set_telemetry_enabled(app: AppHandle, enabled: bool) -> Result<(), AppError> {
+    if enabled && !cfg!(feature = "telemetry") {
+        return Err(AppError::InvalidInput(
+            "Telemetry is not included in this build".to_string(),
+        ));
+    }
+    settings::update_settings(&app, |s| s.telemetry.enabled = enabled)?;
+    queue::set_enabled(enabled);
+    if !enabled {
+        queue::purge()?;
+    }
+    Ok(())
+}
+
+/// Set where queued counts are posted; `None` keeps them on this machine.
+#[tauri::command]
+pub fn set_telemetry_endpoint(app: AppHandle, endpoint: Option<String>) -> Result<(), AppError> {
+    if let Some(url) = &endpoint {
+        if !url.starts_with("https://") && !url.starts_with("http://") {
+            return Err(AppError::InvalidInput(format!(
+                "Invalid telemetry endpoint: {}",
+                url
+            )));
+        }
+    }
+    settings::update_settings(&app, |s| s.telemetry.endpoint = endpoint)?;
+    Ok(())
+}
+
+/// Delete everything queued. The next event starts a new install id.
+#[tauri::command]
+pub fn purge_telemetry() -> Result<(), AppError> {
+    Ok(queue::purge()?)
+}
+
+#[cfg(all(test, feature = "telemetry"))]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_queue_counts() {
+        let translation = TelemetryEvent::FeatureUsed {
+            feature: Feature::Translation,
+        };
+        let mut queue = TelemetryQueue::default();
+        queue.add(translation, "2026-10-15");
+        queue.add(translation, "2026-10-15");
+        queue.add(translation, "2026-10-16");
+        queue.add(
+            TelemetryEvent::ExportFormat {
+                format: ExportKind::Pdf,
+            },
+            "2026-10-16",
+        );
+        assert_eq!(queue.install_id.len(), 36);
+        assert_eq!(queue.events.len(), 3);
+        assert_eq!(queue.events[0].count, 2);
+
+        // Sent counts are subtracted; one more arrived during the upload
+        let sent = queue.events.clone();
+        queue.add(translation, "2026-10-15");
+        queue.remove_sent(&sent);
+        assert_eq!(queue.events.len(), 1);
+        assert_eq!(queue.events[0].day, "2026-10-15");
+        assert_eq!(queue.events[0].count, 1);
+
+        assert_eq!(
+            serde_json::to_value(&queue.events[0]).unwrap(),
+            serde_json::json!({
+                "event": "feature_used",
+                "feature": "translation",
+                "day": "2026-10-15",
+                "count": 1,
+            })
+        );
+    }
+
+    #[test]
+    fn test_prune() {
+        let start = TelemetryEvent::EngineStart { success: false };
+        let mut queue = TelemetryQueue::default();
+        for day in ["2026-09-16", "2026-09-17", "2026-10-16", "not a day"] {
+            queue.add(start, day);
+        }
+        queue.prune(NaiveDate::from_ymd_opt(2026, 10, 16).unwrap());
+        let days: Vec<&str> = queue.events.iter().map(|e| e.day.as_str()).collect();
+        assert_eq!(days, ["2026-09-17", "2026-10-16"]);
+    }
+}
@@ -0,0 +1,890 @@
+//! Text helpers that need no engine: telling Greek text from other text,
+//! how it is encoded, where its sentences end, word statistics, and
+//! diffs between readings of a text.
+//!
+//! Characters are classified by Unicode block: Greek and Coptic
+//! (U+0370–U+03FF), Greek Extended (U+1F00–U+1FFF, precomposed polytonic
+//! letters) and Combining Diacritical Marks (U+0300–U+036F), which count
+//! as Greek when they follow a Greek letter, as in NFD text.
+//!
+//! Sentences end at `.`, `!` and `?`, and in Greek also at the question
+//! mark (`;`, U+037E) and the ano teleia (`·`, U+0387 or U+00B7). A
+//! boundary inside quotation marks doesn't end the sentence, so a quoted
+//! speech stays with the sentence that introduces it.
+//!
+//! Diffs use Myers' shortest edit script over characters (a letter with
+//! its combining marks), words or sentences. Tokens keep everything
+//! between them (spaces, punctuation), so joining the `equal` and
+//! `delete` operations gives back the base text and joining the `equal`
+//! and `insert` ones the variant. Matching is exact: a changed accent or
+//! breathing is a difference.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::errors::AppError;
+
+/// Combining marks used only by polytonic orthography: grave, psili,
+/// dasia, perispomeni and ypogegrammeni
+const POLYTONIC_MARKS: &[char] = &['\u{0300}', '\u{0313}', '\u{0314}', '\u{0342}', '\u{0345}'];
+/// Beta Code diacritics, written after the letter (or after `*` for capitals)
+const BETA_CODE_MARKS: &[char] = &[')', '(', '/', '\\', '=', '|', '+'];
+
+/// Quotation marks and the mark that closes each
+const QUOTES: &[(char, char)] = &[('“', '”'), ('‘', '’'), ('«', '»'), ('‹', '›'), ('"', '"')];
+/// Brackets that stay with the sentence ending just before them
+const CLOSING_BRACKETS: &[char] = &[')', ']'];
+const GREEK_BOUNDARIES: &[char] = &['.', '!', '?', ';', '\u{037E}', '\u{0387}', '\u{00B7}'];
+const ENGLISH_BOUNDARIES: &[char] = &['.', '!', '?'];
+/// Most passages `get_text_statistics` accepts in one call
+const MAX_STATS_PASSAGES: usize = 10_000;
+/// Words listed at each end of the frequency table
+const STATS_WORD_LIST_LEN: usize = 20;
+/// Most tokens inserted plus deleted a diff may find; the search keeps
+/// state for every edit, so texts that differ more are refused
+const MAX_DIFF_EDITS: usize = 2_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SplitLanguage {
+    Greek,
+    English,
+    /// Greek boundaries after Greek words, English ones elsewhere, so an
+    /// English semicolon doesn't end a sentence
+    Mixed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SentenceSpan {
+    pub text: String,
+    /// Character (not byte) offsets in the input, end exclusive
+    pub start_char: usize,
+    pub end_char: usize,
+    pub contains_greek: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GreekTextAnalysis {
+    pub has_greek: bool,
+    pub greek_char_count: u32,
+    /// Characters other than whitespace
+    pub total_char_count: u32,
+    /// Share of `total_char_count` that is Greek, 0–100
+    pub greek_percentage: f32,
+    /// `none`, `monotonic`, `polytonic_precomposed`,
+    /// `polytonic_decomposed` or `beta_code`
+    pub likely_encoding: String,
+    pub has_polytonic_diacritics: bool,
+    pub has_extended_greek: bool,
+}
+
+fn is_basic_greek(c: char) -> bool {
+    matches!(c, '\u{0370}'..='\u{03FF}')
+}
+
+fn is_extended_greek(c: char) -> bool {
+    matches!(c, '\u{1F00}'..='\u{1FFF}')
+}
+
+fn is_combining_mark(c: char) -> bool {
+    matches!(c, '\u{0300}'..='\u{036F}')
+}
+
+/// A letter from the Greek and Coptic or Greek Extended blocks.
+pub fn is_greek_letter(c: char) -> bool {
+    (is_basic_greek(c) || is_extended_greek(c)) && c.is_alphabetic()
+}
+
+/// Whether ASCII text reads as Beta Code: Latin letters carrying the Beta
+/// Code diacritics, e.g. `lo/gos` or `*)ihsou=s`.
+fn looks_like_beta_code(text: &str) -> bool {
+    if !text.is_ascii() {
+        return false;
+    }
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let marked = words
+        .iter()
+        .filter(|word| {
+            let chars: Vec<char> = word.chars().collect();
+            chars.windows(2).any(|pair| {
+                (pair[0].is_ascii_alphabetic() || pair[0] == '*')
+                    && BETA_CODE_MARKS.contains(&pair[1])
+            })
+        })
+        .count();
+    // Most Greek words carry an accent; stray slashes in English don't
+    marked * 2 > words.len()
+}
+
+/// Classify the characters of `text`.
+pub fn analyze_greek(text: &str) -> GreekTextAnalysis {
+    let (mut greek, mut total) = (0u32, 0u32);
+    let (mut polytonic, mut extended, mut decomposed) = (false, false, false);
+    let mut after_greek = false;
+    for c in text.chars().filter(|c| !c.is_whitespace()) {
+        total += 1;
+        if is_combining_mark(c) {
+            if after_greek {
+                greek += 1;
+                decomposed = true;
+                polytonic |= POLYTONIC_MARKS.contains(&c);
+            }
+            continue;
+        }
+        after_greek = is_greek_letter(c);
+        if is_basic_greek(c) || is_extended_greek(c) {
+            greek += 1;
+        }
+        if is_extended_greek(c) {
+            extended = true;
+            polytonic = true;
+        }
+    }
+
+    let has_greek = text.chars().any(is_greek_letter);
+    let likely_encoding = if has_greek && extended {
+        "polytonic_precomposed"
+    } else if has_greek && decomposed {
+        if polytonic {
+            "polytonic_decomposed"
+        } else {
+            "monotonic"
+        }
+    } else if has_greek {
+        "monotonic"
+    } else if looks_like_beta_code(text) {
+        "beta_code"
+    } else {
+        "none"
+    };
+    GreekTextAnalysis {
+        has_greek,
+        greek_char_count: greek,
+        total_char_count: total,
+        greek_percentage: if total == 0 {
+            0.0
+        } else {
+            greek as f32 * 100.0 / total as f32
+        },
+        likely_encoding: likely_encoding.to_string(),
+        has_polytonic_diacritics: polytonic,
+        has_extended_greek: extended,
+    }
+}
+
+/// Whether the `.` at `i` is part of a number ("3.16") or an ellipsis
+/// rather than a full stop.
+fn is_inner_dot(chars: &[char], i: usize) -> bool {
+    let prev = i.checked_sub(1).map(|p| chars[p]);
+    let next = chars.get(i + 1).copied();
+    next == Some('.')
+        || prev == Some('.')
+        || (prev.is_some_and(|p| p.is_ascii_digit()) && next.is_some_and(|n| n.is_ascii_digit()))
+}
+
+/// The span of `chars[start..end]` without surrounding whitespace.
+fn span(chars: &[char], start: usize, end: usize) -> Option<SentenceSpan> {
+    let start = start
+        + chars[start..end]
+            .iter()
+            .take_while(|c| c.is_whitespace())
+            .count();
+    let end = end
+        - chars[start..end]
+            .iter()
+            .rev()
+            .take_while(|c| c.is_whitespace())
+            .count();
+    if start == end {
+        return None;
+    }
+    let text: String = chars[start..end].iter().collect();
+    Some(SentenceSpan {
+        contains_greek: text.chars().any(is_greek_letter),
+        text,
+        start_char: start,
+        end_char: end,
+    })
+}
+
+/// Split text into sentences. `is_boundary` gets each punctuation
+/// candidate and the last letter before it.
+fn split_by(text: &str, is_boundary: impl Fn(char, Option<char>) -> bool) -> Vec<SentenceSpan> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut spans = Vec::new();
+    // Closing marks of the quotations we are inside, innermost last
+    let mut open_quotes: Vec<char> = Vec::new();
+    let mut last_letter = None;
+    let mut start = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        i += 1;
+        let ends_sentence = if open_quotes.last() == Some(&c) {
+            open_quotes.pop();
+            // A quotation closing right after a boundary ends the sentence
+            open_quotes.is_empty()
+                && i >= 2
+                && is_boundary(chars[i - 2], last_letter)
+                && !(chars[i - 2] == '.' && is_inner_dot(&chars, i - 2))
+        } else if let Some(&(_, close)) = QUOTES.iter().find(|(open, _)| *open == c) {
+            open_quotes.push(close);
+            false
+        } else if c.is_alphabetic() {
+            last_letter = Some(c);
+            false
+        } else {
+            open_quotes.is_empty()
+                && is_boundary(c, last_letter)
+                && !(c == '.' && is_inner_dot(&chars, i - 1))
+        };
+        if !ends_sentence {
+            continue;
+        }
+        while chars.get(i).is_some_and(|c| CLOSING_BRACKETS.contains(c)) {
+            i += 1;
+        }
+        spans.extend(span(&chars, start, i));
+        start = i;
+    }
+    spans.extend(span(&chars, start, chars.len()));
+    spans
+}
+
+/// Split text into sentences ending at any of `boundaries`.
+pub fn split_sentences(text: &str, boundaries: &[char]) -> Vec<SentenceSpan> {
+    split_by(text, |c, _| boundaries.contains(&c))
+}
+
+/// How much of `text` is Greek and how it is likely encoded.
+#[tauri::command]
+pub fn detect_greek_in_text(text: String) -> GreekTextAnalysis {
+    analyze_greek(&text)
+}
+
+/// Sentences of `text` as `language` punctuates them.
+pub fn sentences_in(text: &str, language: SplitLanguage) -> Vec<SentenceSpan> {
+    match language {
+        SplitLanguage::Greek => split_sentences(text, GREEK_BOUNDARIES),
+        SplitLanguage::English => split_sentences(text, ENGLISH_BOUNDARIES),
+        SplitLanguage::Mixed => split_by(text, |c, last_letter| {
+            ENGLISH_BOUNDARIES.contains(&c)
+                || (GREEK_BOUNDARIES.contains(&c) && last_letter.is_some_and(is_greek_letter))
+        }),
+    }
+}
+
+/// Split text into sentences, with Greek punctuation understood.
+#[tauri::command]
+pub fn split_text_into_sentences(text: String, language: SplitLanguage) -> Vec<SentenceSpan> {
+    sentences_in(&text, language)
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TextPassage {
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CorpusStats {
+    pub total_words: u64,
+    pub unique_words: u64,
+    /// Words per sentence
+    pub avg_sentence_length: f32,
+    /// Words that occur exactly once
+    pub hapax_legomena_count: u32,
+    /// Type-token ratio: unique words over total words
+    pub vocabulary_richness: f32,
+    /// Top 20, most frequent first; ties in alphabetical order
+    pub most_frequent_words: Vec<(String, u32)>,
+    /// Bottom 20, least frequent first; ties in alphabetical order
+    pub least_frequent_words: Vec<(String, u32)>,
+}
+
+/// Lowercased words: runs of letters and digits, with combining marks
+/// kept so decomposed Greek stays whole.
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !(c.is_alphanumeric() || is_combining_mark(c)))
+        .filter(|w| w.chars().any(char::is_alphanumeric))
+        .map(str::to_lowercase)
+}
+
+fn corpus_stats(passages: &[TextPassage]) -> CorpusStats {
+    let mut counts: HashMap<String, u32> = HashMap::new();
+    let mut total_words = 0u64;
+    let mut sentences = 0u64;
+    for passage in passages {
+        for sentence in sentences_in(&passage.text, SplitLanguage::Mixed) {
+            let before = total_words;
+            for word in words(&sentence.text) {
+                *counts.entry(word).or_default() += 1;
+                total_words += 1;
+            }
+            sentences += u64::from(total_words > before);
+        }
+    }
+
+    let mut ranked: Vec<(String, u32)> = counts.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    let most_frequent_words = ranked.iter().take(STATS_WORD_LIST_LEN).cloned().collect();
+    ranked.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+    let least_frequent_words = ranked.iter().take(STATS_WORD_LIST_LEN).cloned().collect();
+
+    let unique_words = ranked.len() as u64;
+    let ratio = |n: u64, d: u64| if d == 0 { 0.0 } else { n as f32 / d as f32 };
+    CorpusStats {
+        total_words,
+        unique_words,
+        avg_sentence_length: ratio(total_words, sentences),
+        hapax_legomena_count: ranked.iter().filter(|(_, n)| *n == 1).count() as u32,
+        vocabulary_richness: ratio(unique_words, total_words),
+        most_frequent_words,
+        least_frequent_words,
+    }
+}
+
+/// Word and sentence statistics over a collection of passages (at most
+/// 10,000).
+#[tauri::command]
+pub fn get_text_statistics(passages: Vec<TextPassage>) -> Result<CorpusStats, AppError> {
+    if passages.len() > MAX_STATS_PASSAGES {
+        return Err(AppError::InvalidInput(format!(
+            "Statistics are limited to {} passages; {} were given",
+            MAX_STATS_PASSAGES,
+            passages.len()
+        )));
+    }
+    Ok(corpus_stats(&passages))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffGranularity {
+    /// Letters with their combining marks, and every other character
+    Character,
+    /// Words, runs of whitespace and single punctuation marks
+    Word,
+    /// Sentences as `SplitLanguage::Mixed` ends them, each with the
+    /// whitespace before it
+    Sentence,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "op", content = "text", rename_all = "snake_case")]
+pub enum DiffOp {
+    Equal(String),
+    /// Only in the variant
+    Insert(String),
+    /// Only in the base
+    Delete(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TextDiff {
+    /// Runs of the same operation are merged; a change is its deletion
+    /// followed by its insertion
+    pub operations: Vec<DiffOp>,
+    /// `diff_similarity_score` of the operations
+    pub similarity: f32,
+}
+
+/// Byte offset of each character of `text`, then of its end.
+fn byte_offsets(text: &str) -> Vec<usize> {
+    text.char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(text.len()))
+        .collect()
+}
+
+/// Split `text` into diff tokens that join back into it.
+fn diff_tokens(text: &str, granularity: DiffGranularity) -> Vec<&str> {
+    let is_word = |c: char| c.is_alphanumeric() || is_combining_mark(c);
+    let mut tokens = Vec::new();
+    match granularity {
+        DiffGranularity::Character | DiffGranularity::Word => {
+            let mut chars = text.char_indices().peekable();
+            while let Some((start, c)) = chars.next() {
+                let continues = |next: char| match granularity {
+                    DiffGranularity::Word if is_word(c) => is_word(next),
+                    DiffGranularity::Word if c.is_whitespace() => next.is_whitespace(),
+                    _ => is_combining_mark(next),
+                };
+                while chars.peek().is_some_and(|&(_, next)| continues(next)) {
+                    chars.next();
+                }
+                let end = chars.peek().map_or(text.len(), |&(i, _)| i);
+                tokens.push(&text[start..end]);
+            }
+        }
+        DiffGranularity::Sentence => {
+            let bytes = byte_offsets(text);
+            let mut start = 0;
+            for span in sentences_in(text, SplitLanguage::Mixed) {
+                tokens.push(&text[start..bytes[span.end_char]]);
+                start = bytes[span.end_char];
+            }
+            if start < text.len() {
+                tokens.push(&text[start..]);
+            }
+        }
+    }
+    tokens
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    Equal,
+    Insert,
+    Delete,
+}
+
+/// Myers' shortest edit script turning `a` into `b`, one edit per token,
+/// or `None` if it takes more than `max_edits` insertions and deletions.
+///
+/// `trace[d]` holds, for each diagonal `k` in `-d..=d`, the furthest `x`
+/// reached with `d` edits; the script is read back from it.
+fn myers<T: PartialEq>(a: &[T], b: &[T], max_edits: usize) -> Option<Vec<Edit>> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = n + m;
+    let index = |k: isize| (k + max + 1) as usize;
+    let mut v = vec![0isize; 2 * max as usize + 3];
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+    'search: for d in 0..=max {
+        if d as usize > max_edits {
+            return None;
+        }
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && v[index(k - 1)] < v[index(k + 1)]) {
+                v[index(k + 1)]
+            } else {
+                v[index(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[index(k)] = x;
+            if x >= n && y >= m {
+                trace.push(v[index(-d)..=index(d)].to_vec());
+                break 'search;
+            }
+        }
+        trace.push(v[index(-d)..=index(d)].to_vec());
+    }
+
+    let mut edits = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for d in (1..trace.len() as isize).rev() {
+        let previous = &trace[d as usize - 1];
+        let furthest = |k: isize| previous[(k + d - 1) as usize];
+        let k = x - y;
+        let previous_k = if k == -d || (k != d && furthest(k - 1) < furthest(k + 1)) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let previous_x = furthest(previous_k);
+        let previous_y = previous_x - previous_k;
+        while x > previous_x && y > previous_y {
+            edits.push(Edit::Equal);
+            x -= 1;
+            y -= 1;
+        }
+        edits.push(if x == previous_x {
+            Edit::Insert
+        } else {
+            Edit::Delete
+        });
+        (x, y) = (previous_x, previous_y);
+    }
+    edits.extend((0..x).map(|_| Edit::Equal));
+    edits.reverse();
+    Some(edits)
+}
+
+/// Append `text` to the last operation if it is the same kind.
+fn push_op(operations: &mut Vec<DiffOp>, edit: Edit, text: &str) {
+    match (operations.last_mut(), edit) {
+        (Some(DiffOp::Equal(last)), Edit::Equal)
+        | (Some(DiffOp::Insert(last)), Edit::Insert)
+        | (Some(DiffOp::Delete(last)), Edit::Delete) => last.push_str(text),
+        _ => operations.push(match edit {
+            Edit::Equal => DiffOp::Equal(text.to_string()),
+            Edit::Insert => DiffOp::Insert(text.to_string()),
+            Edit::Delete => DiffOp::Delete(text.to_string()),
+        }),
+    }
+}
+
+/// Diff two texts; `Err` when they differ in more than `MAX_DIFF_EDITS`
+/// tokens.
+pub fn diff_texts(
+    base: &str,
+    variant: &str,
+    granularity: DiffGranularity,
+) -> Result<TextDiff, String> {
+    let (a, b) = (
+        diff_tokens(base, granularity),
+        diff_tokens(variant, granularity),
+    );
+    // The search only needs the tokens between a shared start and end
+    let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (middle_a, middle_b) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+    let edits = myers(middle_a, middle_b, MAX_DIFF_EDITS).ok_or_else(|| {
+        format!(
+            "The texts are too different to compare (more than {} changes)",
+            MAX_DIFF_EDITS
+        )
+    })?;
+
+    let mut operations = Vec::new();
+    for token in &a[..prefix] {
+        push_op(&mut operations, Edit::Equal, token);
+    }
+    let (mut i, mut j) = (0, 0);
+    for edit in edits {
+        let token = match edit {
+            Edit::Equal | Edit::Delete => middle_a[i],
+            Edit::Insert => middle_b[j],
+        };
+        push_op(&mut operations, edit, token);
+        i += usize::from(edit != Edit::Insert);
+        j += usize::from(edit != Edit::Delete);
+    }
+    for token in &a[a.len() - suffix..] {
+        push_op(&mut operations, Edit::Equal, token);
+    }
+
+    let mut diff = TextDiff {
+        operations,
+        similarity: 0.0,
+    };
+    diff.similarity = diff_similarity_score(&diff);
+    Ok(diff)
+}
+
+/// Levenshtein similarity of the two texts, from 0 (nothing shared) to 1
+/// (identical): one minus the edit distance over the longer text's length,
+/// in characters. Within each run of changes, deleted and inserted
+/// characters pair up as substitutions, so the distance is the one along
+/// the diff's alignment.
+pub fn diff_similarity_score(diff: &TextDiff) -> f32 {
+    let (mut base_len, mut variant_len, mut distance) = (0, 0, 0);
+    let (mut deleted, mut inserted) = (0, 0);
+    for op in &diff.operations {
+        match op {
+            DiffOp::Equal(text) => {
+                distance += deleted.max(inserted);
+                (deleted, inserted) = (0, 0);
+                let len = text.chars().count();
+                base_len += len;
+                variant_len += len;
+            }
+            DiffOp::Delete(text) => {
+                deleted += text.chars().count();
+                base_len += text.chars().count();
+            }
+            DiffOp::Insert(text) => {
+                inserted += text.chars().count();
+                variant_len += text.chars().count();
+            }
+        }
+    }
+    distance += deleted.max(inserted);
+    let longest = base_len.max(variant_len);
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - distance as f32 / longest as f32
+}
+
+/// Compare two readings of a text, such as manuscript variants.
+#[tauri::command]
+pub fn diff_text_passages(
+    base: String,
+    variant: String,
+    granularity: DiffGranularity,
+) -> Result<TextDiff, AppError> {
+    Ok(diff_texts(&base, &variant, granularity)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_modern_greek() {
+        let analysis = analyze_greek("Καλημέρα κόσμε");
+        assert!(analysis.has_greek);
+        assert_eq!(
+            (analysis.greek_char_count, analysis.total_char_count),
+            (13, 13)
+        );
+        assert_eq!(analysis.greek_percentage, 100.0);
+        assert_eq!(analysis.likely_encoding, "monotonic");
+        assert!(!analysis.has_polytonic_diacritics);
+        assert!(!analysis.has_extended_greek);
+    }
+
+    #[test]
+    fn test_classical_polytonic_greek() {
+        let analysis = analyze_greek("Ἐν ἀρχῇ ἦν ὁ λόγος");
+        assert!(analysis.has_greek && analysis.has_extended_greek);
+        assert!(analysis.has_polytonic_diacritics);
+        assert_eq!(analysis.likely_encoding, "polytonic_precomposed");
+        assert_eq!(analysis.greek_char_count, analysis.total_char_count);
+
+        // The same words decomposed (NFD): base letters plus combining marks
+        let analysis = analyze_greek("Ε\u{0313}ν α\u{0313}ρχη\u{0342}\u{0345}");
+        assert!(analysis.has_polytonic_diacritics && !analysis.has_extended_greek);
+        assert_eq!(analysis.likely_encoding, "polytonic_decomposed");
+        assert_eq!(analysis.greek_char_count, 10);
+    }
+
+    #[test]
+    fn test_mixed_greek_and_latin() {
+        let analysis = analyze_greek("logos (λόγος) \u{0301}");
+        assert!(analysis.has_greek);
+        // A combining mark after anything but a Greek letter is not Greek
+        assert_eq!(
+            (analysis.greek_char_count, analysis.total_char_count),
+            (5, 13)
+        );
+        assert!((analysis.greek_percentage - 38.46).abs() < 0.01);
+
+        let analysis = analyze_greek("e)n a)rxh=| h)=n o( lo/gos");
+        assert!(!analysis.has_greek);
+        assert_eq!(analysis.likely_encoding, "beta_code");
+        assert_eq!(analyze_greek("and/or 3/4").likely_encoding, "none");
+    }
+
+    #[test]
+    fn test_empty_string() {
+        let analysis = analyze_greek("");
+        assert!(!analysis.has_greek);
+        assert_eq!(
+            (analysis.greek_char_count, analysis.total_char_count),
+            (0, 0)
+        );
+        assert_eq!(analysis.greek_percentage, 0.0);
+        assert_eq!(analysis.likely_encoding, "none");
+    }
+
+    fn texts(spans: &[SentenceSpan]) -> Vec<&str> {
+        spans.iter().map(|s| s.text.as_str()).collect()
+    }
+
+    #[test]
+    fn test_split_greek_sentences() {
+        // John 1:1-2, with the ano teleia in both forms
+        let text = "Ἐν ἀρχῇ ἦν ὁ λόγος, καὶ ὁ λόγος ἦν πρὸς τὸν θεόν, καὶ θεὸς ἦν ὁ λόγος\u{0387} \
+                    οὗτος ἦν ἐν ἀρχῇ πρὸς τὸν θεόν·";
+        let spans = split_text_into_sentences(text.to_string(), SplitLanguage::Greek);
+        assert_eq!(spans.len(), 2);
+        assert!(spans[1].text.starts_with("οὗτος"));
+        assert!(spans.iter().all(|s| s.contains_greek));
+
+        // John 18:38, with both forms of the question mark
+        let spans = split_text_into_sentences(
+            "λέγει αὐτῷ ὁ Πιλᾶτος· τί ἐστιν ἀλήθεια; καὶ τοῦτο εἰπὼν πάλιν ἐξῆλθεν\u{037E} ναί."
+                .to_string(),
+            SplitLanguage::Greek,
+        );
+        assert_eq!(
+            texts(&spans),
+            [
+                "λέγει αὐτῷ ὁ Πιλᾶτος·",
+                "τί ἐστιν ἀλήθεια;",
+                "καὶ τοῦτο εἰπὼν πάλιν ἐξῆλθεν\u{037E}",
+                "ναί."
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_keeps_quotations_together() {
+        let spans = split_sentences("ἔλεγεν· \"Λόγος. ὃς ἦν\" καὶ ἀπῆλθεν.", GREEK_BOUNDARIES);
+        assert_eq!(texts(&spans), ["ἔλεγεν·", "\"Λόγος. ὃς ἦν\" καὶ ἀπῆλθεν."]);
+
+        // Mark 1:3, quotation nested in narrative; the boundary just before
+        // the closing mark ends the sentence after it
+        let text = "φωνὴ βοῶντος· «ἑτοιμάσατε τὴν ὁδὸν κυρίου. εὐθείας ποιεῖτε.» ἐγένετο Ἰωάννης.";
+        assert_eq!(
+            texts(&split_sentences(text, GREEK_BOUNDARIES)),
+            [
+                "φωνὴ βοῶντος·",
+                "«ἑτοιμάσατε τὴν ὁδὸν κυρίου. εὐθείας ποιεῖτε.»",
+                "ἐγένετο Ἰωάννης."
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_offsets_and_mixed_text() {
+        let text = "  In John 1:1 (3.16...) we read; ἐν ἀρχῇ ἦν ὁ λόγος· then a note.";
+        let spans = split_text_into_sentences(text.to_string(), SplitLanguage::Mixed);
+        assert_eq!(
+            texts(&spans),
+            [
+                "In John 1:1 (3.16...) we read; ἐν ἀρχῇ ἦν ὁ λόγος·",
+                "then a note."
+            ]
+        );
+        let chars: Vec<char> = text.chars().collect();
+        for span in &spans {
+            let slice: String = chars[span.start_char..span.end_char].iter().collect();
+            assert_eq!(slice, span.text);
+        }
+        assert_eq!(spans[0].start_char, 2);
+        assert!(spans[0].contains_greek && !spans[1].contains_greek);
+        assert!(split_text_into_sentences(" ".to_string(), SplitLanguage::English).is_empty());
+    }
+
+    fn text_passage(text: &str) -> TextPassage {
+        TextPassage {
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_corpus_stats() {
+        // John 1:1 and 1:2
+        let passages = [
+            text_passage("Ἐν ἀρχῇ ἦν ὁ λόγος, καὶ ὁ λόγος ἦν πρὸς τὸν θεόν, καὶ θεὸς ἦν ὁ λόγος."),
+            text_passage("Οὗτος ἦν ἐν ἀρχῇ πρὸς τὸν θεόν."),
+        ];
+        let stats = corpus_stats(&passages);
+        assert_eq!((stats.total_words, stats.unique_words), (24, 11));
+        assert_eq!(stats.avg_sentence_length, 12.0);
+        assert_eq!(stats.most_frequent_words[0], ("ἦν".to_string(), 4));
+        assert_eq!(stats.most_frequent_words[1], ("λόγος".to_string(), 3));
+        // "Ἐν" and "ἐν" are the same word once lowercased
+        assert!(stats.most_frequent_words.contains(&("ἐν".to_string(), 2)));
+        assert_eq!(stats.hapax_legomena_count, 2);
+        assert_eq!(stats.least_frequent_words[0].1, 1);
+        assert!((stats.vocabulary_richness - 11.0 / 24.0).abs() < 1e-6);
+
+        let empty = corpus_stats(&[text_passage(" — ")]);
+        assert_eq!((empty.total_words, empty.avg_sentence_length), (0, 0.0));
+        assert_eq!(empty.vocabulary_richness, 0.0);
+        assert!(empty.most_frequent_words.is_empty());
+    }
+
+    fn diff(base: &str, variant: &str, granularity: DiffGranularity) -> TextDiff {
+        let diff = diff_texts(base, variant, granularity).unwrap();
+        // Each side can be rebuilt from the operations
+        let (mut a, mut b) = (String::new(), String::new());
+        for op in &diff.operations {
+            match op {
+                DiffOp::Equal(t) => {
+                    a.push_str(t);
+                    b.push_str(t);
+                }
+                DiffOp::Delete(t) => a.push_str(t),
+                DiffOp::Insert(t) => b.push_str(t),
+            }
+        }
+        assert_eq!((a.as_str(), b.as_str()), (base, variant));
+        diff
+    }
+
+    fn equal(text: &str) -> DiffOp {
+        DiffOp::Equal(text.to_string())
+    }
+    fn insert(text: &str) -> DiffOp {
+        DiffOp::Insert(text.to_string())
+    }
+    fn delete(text: &str) -> DiffOp {
+        DiffOp::Delete(text.to_string())
+    }
+
+    #[test]
+    fn test_character_diff() {
+        // Romans 5:1, indicative or subjunctive
+        let d = diff("ἔχομεν", "ἔχωμεν", DiffGranularity::Character);
+        assert_eq!(
+            d.operations,
+            [equal("ἔχ"), delete("ο"), insert("ω"), equal("μεν")]
+        );
+        assert!((d.similarity - 5.0 / 6.0).abs() < 1e-6);
+
+        // A decomposed letter and its accent are one character
+        let d = diff("λο\u{0301}γος", "λο\u{0300}γος", DiffGranularity::Character);
+        assert_eq!(d.operations[1], delete("ο\u{0301}"));
+        assert_eq!(d.operations[2], insert("ο\u{0300}"));
+    }
+
+    #[test]
+    fn test_word_diff() {
+        // John 1:18, SBLGNT against the Byzantine text
+        let d = diff(
+            "μονογενὴς θεὸς ὁ ὢν εἰς τὸν κόλπον",
+            "ὁ μονογενὴς υἱός, ὁ ὢν εἰς τὸν κόλπον",
+            DiffGranularity::Word,
+        );
+        assert_eq!(
+            d.operations,
+            [
+                insert("ὁ "),
+                equal("μονογενὴς "),
+                delete("θεὸς"),
+                insert("υἱός,"),
+                equal(" ὁ ὢν εἰς τὸν κόλπον")
+            ]
+        );
+
+        // Ephesians 1:1, "in Ephesus" missing from P46, Sinaiticus and
+        // Vaticanus
+        let d = diff(
+            "τοῖς ἁγίοις τοῖς οὖσιν ἐν Ἐφέσῳ καὶ πιστοῖς",
+            "τοῖς ἁγίοις τοῖς οὖσιν καὶ πιστοῖς",
+            DiffGranularity::Word,
+        );
+        assert_eq!(
+            d.operations,
+            [
+                equal("τοῖς ἁγίοις τοῖς οὖσιν "),
+                delete("ἐν Ἐφέσῳ "),
+                equal("καὶ πιστοῖς")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sentence_diff() {
+        // Matthew 6:13 with and without the doxology
+        let base = "καὶ μὴ εἰσενέγκῃς ἡμᾶς εἰς πειρασμόν, ἀλλὰ ῥῦσαι ἡμᾶς ἀπὸ τοῦ πονηροῦ.";
+        let doxology = " ὅτι σοῦ ἐστιν ἡ βασιλεία καὶ ἡ δύναμις καὶ ἡ δόξα εἰς τοὺς αἰῶνας· ἀμήν.";
+        let variant = format!("{}{}", base, doxology);
+        let d = diff(base, &variant, DiffGranularity::Sentence);
+        assert_eq!(d.operations, [equal(base), insert(doxology)]);
+        let base_len = base.chars().count() as f32;
+        assert!((d.similarity - base_len / variant.chars().count() as f32).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_diff_edge_cases() {
+        let d = diff("", "", DiffGranularity::Word);
+        assert!(d.operations.is_empty());
+        assert_eq!(d.similarity, 1.0);
+        let d = diff("", "ἀμήν", DiffGranularity::Word);
+        assert_eq!((d.operations, d.similarity), (vec![insert("ἀμήν")], 0.0));
+        let same = diff("ἐν ἀρχῇ", "ἐν ἀρχῇ", DiffGranularity::Character);
+        assert_eq!(
+            (same.operations, same.similarity),
+            (vec![equal("ἐν ἀρχῇ")], 1.0)
+        );
+
+        assert_eq!(myers(&[1, 2, 3], &[4, 5, 6], 5), None);
+        let long_a = "α".repeat(MAX_DIFF_EDITS);
+        let long_b = "β".repeat(MAX_DIFF_EDITS);
+        assert!(diff_texts(&long_a, &long_b, DiffGranularity::Character).is_err());
+    }
+}
@@ -0,0 +1,250 @@
+//! Custom CSS themes.
+//!
+//! A theme is a JSON file in `themes/` under the app data dir that sets
+//! CSS custom properties. Applying one injects a `:root { ... }` block as a
+//! `<style>` element into every window; the applied theme is remembered in
+//! the settings and injected again whenever a page finishes loading.
+//!
+//! Only custom properties (`--name`) are accepted, and values can't close
+//! the block or the style element, so a theme can recolor the app but not
+//! restyle or script it.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, Webview};
+
+use super::errors::AppError;
+use super::settings;
+use super::storage;
+
+const THEMES_DIR: &str = "themes";
+/// Id of the injected `<style>` element
+const STYLE_ELEMENT_ID: &str = "redletters-custom-theme";
+/// Theme files are a few kilobytes of variables
+const MAX_THEME_BYTES: u64 = 256 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThemeDefinition {
+    /// Letters, digits, `-` and `_`; also the file name
+    pub id: String,
+    pub name: String,
+    /// Custom property names (with the leading `--`) to values
+    pub css_variables: HashMap<String, String>,
+    #[serde(default)]
+    pub author: Option<String>,
+}
+
+fn is_identifier(s: &str) -> bool {
+    !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Check a theme before it is stored or injected.
+fn validate_theme(theme: &ThemeDefinition) -> Result<(), String> {
+    if !is_identifier(&theme.id) {
+        return Err(format!("Invalid theme id: {:?}", theme.id));
+    }
+    if theme.name.trim().is_empty() {
+        return Err("A theme needs a name".to_string());
+    }
+    if theme.css_variables.is_empty() {
+        return Err("A theme needs at least one CSS variable".to_string());
+    }
+    for (name, value) in &theme.css_variables {
+        let valid_name = name.strip_prefix("--").is_some_and(is_identifier);
+        if !valid_name {
+            return Err(format!("Invalid CSS variable name: {:?}", name));
+        }
+        let valid_value = !value.trim().is_empty()
+            && !value.contains(['{', '}', ';', '<', '>', '\\', '\n', '\r'])
+            && !value.contains("/*");
+        if !valid_value {
+            return Err(format!("Invalid value for {}: {:?}", name, value));
+        }
+    }
+    Ok(())
+}
+
+/// The theme's variables as a `:root` block, sorted by name.
+fn css_block(theme: &ThemeDefinition) -> String {
+    let mut variables: Vec<_> = theme.css_variables.iter().collect();
+    variables.sort();
+    let mut css = String::from(":root {\n");
+    for (name, value) in variables {
+        css.push_str(&format!("  {}: {};\n", name, value.trim()));
+    }
+    css.push('}');
+    css
+}
+
+/// Script that puts `css` in the theme's style element, or removes the
+/// element when `css` is `None`.
+fn injection_script(css: Option<&str>) -> String {
+    let id = serde_json::to_string(STYLE_ELEMENT_ID).unwrap();
+    match css {
+        Some(css) => format!(
+            "(() => {{ let s = document.getElementById({id}); \
+             if (!s) {{ s = document.createElement('style'); s.id = {id}; document.head.appendChild(s); }} \
+             s.textContent = {css}; }})();",
+            id = id,
+            css = serde_json::to_string(css).unwrap()
+        ),
+        None => format!("document.getElementById({})?.remove();", id),
+    }
+}
+
+fn theme_path(app: &AppHandle, id: &str) -> Result<PathBuf, String> {
+    if !is_identifier(id) {
+        return Err(format!("Invalid theme id: {:?}", id));
+    }
+    Ok(storage::app_data_subdir(app, THEMES_DIR)?.join(format!("{}.json", id)))
+}
+
+fn load_theme(app: &AppHandle, id: &str) -> Result<ThemeDefinition, String> {
+    let path = theme_path(app, id)?;
+    if !path.exists() {
+        return Err(format!("No theme {}", id));
+    }
+    let theme: ThemeDefinition = storage::read_json(&path)?;
+    validate_theme(&theme)?;
+    Ok(theme)
+}
+
+/// Run `script` in every open window.
+fn eval_everywhere(app: &AppHandle, script: &str) -> Result<(), String> {
+    for window in app.webview_windows().values() {
+        window.eval(script).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Inject the remembered theme into a page that just loaded.
+pub fn reapply_theme(webview: &Webview) {
+    let app = webview.app_handle();
+    let Some(id) = settings::load_settings(app)
+        .ok()
+        .and_then(|s| s.custom_theme)
+    else {
+        return;
+    };
+    match load_theme(app, &id) {
+        Ok(theme) => {
+            let _ = webview.eval(injection_script(Some(&css_block(&theme))));
+        }
+        Err(e) => tracing::warn!("Could not apply theme {}: {}", id, e),
+    }
+}
+
+/// Installed custom themes, sorted by name. Invalid files are skipped.
+#[tauri::command]
+pub fn get_available_themes(app: AppHandle) -> Result<Vec<ThemeDefinition>, AppError> {
+    let dir = storage::app_data_subdir(&app, THEMES_DIR)?;
+    let entries = fs::read_dir(&dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    let mut themes: Vec<ThemeDefinition> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|p| {
+            let theme = storage::read_json::<ThemeDefinition>(&p)
+                .and_then(|t| validate_theme(&t).map(|_| t));
+            if let Err(e) = &theme {
+                tracing::warn!("Skipping theme {}: {}", p.display(), e);
+            }
+            theme.ok()
+        })
+        .collect();
+    themes.sort_by_cached_key(|t| t.name.to_lowercase());
+    Ok(themes)
+}
+
+/// Apply an installed theme to every window and remember it.
+#[tauri::command]
+pub fn apply_custom_theme(app: AppHandle, theme_id: String) -> Result<(), AppError> {
+    let theme = load_theme(&app, &theme_id)?;
+    eval_everywhere(&app, &injection_script(Some(&css_block(&theme))))?;
+    settings::update_settings(&app, |s| s.custom_theme = Some(theme_id))?;
+    Ok(())
+}
+
+/// Install a theme from a JSON file, replacing one with the same id.
+#[tauri::command]
+pub fn import_theme(app: AppHandle, file_path: String) -> Result<ThemeDefinition, AppError> {
+    let path = Path::new(&file_path);
+    let size = fs::metadata(path)
+        .map_err(|e| format!("{}: {}", file_path, e))?
+        .len();
+    if size > MAX_THEME_BYTES {
+        return Err(AppError::InvalidInput(format!(
+            "{} is too large for a theme",
+            file_path
+        )));
+    }
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", file_path, e))?;
+    let theme: ThemeDefinition =
+        serde_json::from_str(&text).map_err(|e| format!("{}: {}", file_path, e))?;
+    validate_theme(&theme)?;
+    storage::write_json(&theme_path(&app, &theme.id)?, &theme)?;
+    Ok(theme)
+}
+
+/// Remove the custom theme from every window and forget it.
+#[tauri::command]
+pub fn reset_to_default_theme(app: AppHandle) -> Result<(), AppError> {
+    eval_everywhere(&app, &injection_script(None))?;
+    settings::update_settings(&app, |s| s.custom_theme = None)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn theme(variables: &[(&str, &str)]) -> ThemeDefinition {
+        ThemeDefinition {
+            id: "sepia".to_string(),
+            name: "Sepia".to_string(),
+            css_variables: variables
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            author: None,
+        }
+    }
+
+    #[test]
+    fn test_css_block() {
+        let sepia = theme(&[("--bg", " #f4ecd8 "), ("--accent", "rgb(176, 0, 32)")]);
+        assert!(validate_theme(&sepia).is_ok());
+        assert_eq!(
+            css_block(&sepia),
+            ":root {\n  --accent: rgb(176, 0, 32);\n  --bg: #f4ecd8;\n}"
+        );
+        let script = injection_script(Some(&css_block(&sepia)));
+        assert!(script.contains(r#"s.textContent = ":root {\n  --accent"#));
+    }
+
+    #[test]
+    fn test_validate_theme() {
+        for (name, value) in [
+            ("background", "red"),
+            ("--bg color", "red"),
+            ("--bg", "red; } body { display: none"),
+            ("--bg", "red</style><script>"),
+            ("--bg", "url(x) /* */"),
+            ("--bg", " "),
+        ] {
+            assert!(
+                validate_theme(&theme(&[(name, value)])).is_err(),
+                "{}",
+                value
+            );
+        }
+        let mut bad_id = theme(&[("--bg", "red")]);
+        bad_id.id = "../settings".to_string();
+        assert!(validate_theme(&bad_id).is_err());
+        assert!(validate_theme(&theme(&[])).is_err());
+    }
+}
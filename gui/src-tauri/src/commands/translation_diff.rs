@@ -0,0 +1,344 @@
+//! Word-level comparison of two translations of the same passage.
+//!
+//! A run is a cached passage (its id) or an earlier translation kept in the
+//! passage history (`{id}@{translated_at_ms}`). The English text is split
+//! into words and punctuation, each carrying the whitespace after it, so
+//! joining a side's spans gives back its text exactly. Matching ignores
+//! that whitespace.
+
+use serde::Serialize;
+use serde_json::Value;
+use similar::{capture_diff_slices, Algorithm, DiffTag};
+use std::collections::BTreeMap;
+use tauri::AppHandle;
+
+use super::errors::AppError;
+use super::interlinear_table::ledger;
+use super::passages::{load_all_passages, load_passage, load_passage_history, CachedPassage};
+use super::reference::canonical_reference;
+
+/// Longest text, in words and punctuation marks, either side may have
+const MAX_DIFF_TOKENS: usize = 20_000;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TranslationRun {
+    /// Pass to `compare_translations`
+    pub run_id: String,
+    pub passage_id: String,
+    pub reference: String,
+    pub mode: String,
+    pub translator: String,
+    pub translated_at_ms: u64,
+    /// The entry currently in the cache, rather than a replaced one
+    pub current: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffKind {
+    Equal,
+    Inserted,
+    Deleted,
+    Replaced,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DiffSpan {
+    pub kind: DiffKind,
+    /// Text from run A; empty for insertions
+    pub a: String,
+    /// Text from run B; empty for deletions
+    pub b: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GlossChange {
+    pub verse_id: String,
+    pub position: u64,
+    pub surface: String,
+    pub gloss_a: Option<String>,
+    pub gloss_b: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TranslationComparison {
+    pub run_a: TranslationRun,
+    pub run_b: TranslationRun,
+    pub spans: Vec<DiffSpan>,
+    /// Words and punctuation marks in spans other than `equal`
+    pub changed_a: usize,
+    pub changed_b: usize,
+    /// Token gloss differences; `None` unless both runs have a token ledger
+    pub gloss_changes: Option<Vec<GlossChange>>,
+}
+
+/// Split text into words and punctuation marks with their trailing
+/// whitespace. Letters, digits, combining marks and apostrophes inside a
+/// word stay together, so "don't" and accented Greek are one token.
+fn tokenize(text: &str) -> Vec<&str> {
+    let is_word =
+        |c: char| c.is_alphanumeric() || unicode_normalization::char::is_combining_mark(c);
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    let mut start = 0;
+    while let Some((i, c)) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+        // Leading whitespace of the text stays with the first token
+        start = start.min(i);
+        if is_word(c) {
+            while let Some(&(j, next)) = chars.peek() {
+                let apostrophe = matches!(next, '\'' | '\u{2019}')
+                    && text[j + next.len_utf8()..]
+                        .chars()
+                        .next()
+                        .is_some_and(is_word);
+                if !is_word(next) && !apostrophe {
+                    break;
+                }
+                chars.next();
+            }
+        }
+        while chars.peek().is_some_and(|(_, c)| c.is_whitespace()) {
+            chars.next();
+        }
+        let end = chars.peek().map_or(text.len(), |(i, _)| *i);
+        tokens.push(&text[start..end]);
+        start = end;
+    }
+    tokens
+}
+
+/// Word diff of two texts as spans covering both texts in order.
+fn diff_words(a: &str, b: &str) -> Result<(Vec<DiffSpan>, usize, usize), String> {
+    let (tokens_a, tokens_b) = (tokenize(a), tokenize(b));
+    let longest = tokens_a.len().max(tokens_b.len());
+    if longest > MAX_DIFF_TOKENS {
+        return Err(format!(
+            "Passage is too long to compare ({} words, the limit is {})",
+            longest, MAX_DIFF_TOKENS
+        ));
+    }
+    let keys_a: Vec<&str> = tokens_a.iter().map(|t| t.trim_end()).collect();
+    let keys_b: Vec<&str> = tokens_b.iter().map(|t| t.trim_end()).collect();
+
+    let (mut changed_a, mut changed_b) = (0, 0);
+    let spans = capture_diff_slices(Algorithm::Myers, &keys_a, &keys_b)
+        .iter()
+        .map(|op| {
+            let (tag, range_a, range_b) = op.as_tag_tuple();
+            let kind = match tag {
+                DiffTag::Equal => DiffKind::Equal,
+                DiffTag::Insert => DiffKind::Inserted,
+                DiffTag::Delete => DiffKind::Deleted,
+                DiffTag::Replace => DiffKind::Replaced,
+            };
+            if kind != DiffKind::Equal {
+                changed_a += range_a.len();
+                changed_b += range_b.len();
+            }
+            DiffSpan {
+                kind,
+                a: tokens_a[range_a].concat(),
+                b: tokens_b[range_b].concat(),
+            }
+        })
+        .collect();
+    Ok((spans, changed_a, changed_b))
+}
+
+/// Surface form and gloss of ledger tokens, keyed by (verse id, position).
+type LedgerGlosses = BTreeMap<(String, u64), (String, Option<String>)>;
+
+fn ledger_glosses(response: &Value) -> Option<LedgerGlosses> {
+    let mut glosses = BTreeMap::new();
+    for verse in ledger(response)? {
+        let verse_id = verse["verse_id"].as_str().unwrap_or_default();
+        for token in verse["tokens"].as_array().into_iter().flatten() {
+            let Some(position) = token["position"].as_u64() else {
+                continue;
+            };
+            let gloss = token["gloss"]
+                .as_str()
+                .filter(|g| !g.is_empty())
+                .map(str::to_string);
+            let surface = token["surface"].as_str().unwrap_or_default().to_string();
+            glosses.insert((verse_id.to_string(), position), (surface, gloss));
+        }
+    }
+    Some(glosses)
+}
+
+fn gloss_changes(a: &Value, b: &Value) -> Option<Vec<GlossChange>> {
+    let (a, mut b) = (ledger_glosses(a)?, ledger_glosses(b)?);
+    let mut changes = Vec::new();
+    for ((verse_id, position), (surface, gloss_a)) in a {
+        let gloss_b = b.remove(&(verse_id.clone(), position)).and_then(|(_, g)| g);
+        if gloss_a != gloss_b {
+            changes.push(GlossChange {
+                verse_id,
+                position,
+                surface,
+                gloss_a,
+                gloss_b,
+            });
+        }
+    }
+    // Tokens only run B has
+    for ((verse_id, position), (surface, gloss_b)) in b {
+        if gloss_b.is_some() {
+            changes.push(GlossChange {
+                verse_id,
+                position,
+                surface,
+                gloss_a: None,
+                gloss_b,
+            });
+        }
+    }
+    Some(changes)
+}
+
+fn run_summary(passage: &CachedPassage, current: bool) -> TranslationRun {
+    TranslationRun {
+        run_id: if current {
+            passage.id.clone()
+        } else {
+            format!("{}@{}", passage.id, passage.translated_at_ms)
+        },
+        passage_id: passage.id.clone(),
+        reference: passage.reference.clone(),
+        mode: passage.mode.clone(),
+        translator: passage.translator.clone(),
+        translated_at_ms: passage.translated_at_ms,
+        current,
+    }
+}
+
+fn load_run(app: &AppHandle, run_id: &str) -> Result<(CachedPassage, bool), String> {
+    let Some((id, at_ms)) = run_id.split_once('@') else {
+        return Ok((load_passage(app, run_id)?, true));
+    };
+    let at_ms: u64 = at_ms
+        .parse()
+        .map_err(|_| format!("Invalid translation run: {}", run_id))?;
+    load_passage_history(app, id)?
+        .into_iter()
+        .find(|p| p.translated_at_ms == at_ms)
+        .map(|p| (p, false))
+        .ok_or_else(|| format!("No translation run {}", run_id))
+}
+
+/// Cached and earlier translations of a passage, newest first.
+#[tauri::command]
+pub fn list_translation_runs(
+    app: AppHandle,
+    passage_ref: String,
+) -> Result<Vec<TranslationRun>, AppError> {
+    let wanted = canonical_reference(&passage_ref);
+    let mut runs = Vec::new();
+    for passage in load_all_passages(&app)? {
+        if canonical_reference(&passage.normalized_ref) != wanted {
+            continue;
+        }
+        for earlier in load_passage_history(&app, &passage.id).unwrap_or_default() {
+            runs.push(run_summary(&earlier, false));
+        }
+        runs.push(run_summary(&passage, true));
+    }
+    runs.sort_by_key(|r| std::cmp::Reverse(r.translated_at_ms));
+    Ok(runs)
+}
+
+/// Word-level diff of the English text of two runs of `passage_ref`.
+///
+/// With `include_glosses`, token glosses are compared as well when both
+/// runs were translated in traceable mode.
+#[tauri::command]
+pub fn compare_translations(
+    app: AppHandle,
+    passage_ref: String,
+    run_a: String,
+    run_b: String,
+    include_glosses: Option<bool>,
+) -> Result<TranslationComparison, AppError> {
+    let wanted = canonical_reference(&passage_ref);
+    let (a, a_current) = load_run(&app, &run_a)?;
+    let (b, b_current) = load_run(&app, &run_b)?;
+    for run in [&a, &b] {
+        if canonical_reference(&run.normalized_ref) != wanted {
+            return Err(AppError::InvalidInput(format!(
+                "Run {} is a translation of {}, not {}",
+                run.id, run.reference, passage_ref
+            )));
+        }
+    }
+
+    let (spans, changed_a, changed_b) = diff_words(&a.translation, &b.translation)?;
+    let gloss_changes = include_glosses
+        .unwrap_or(false)
+        .then(|| gloss_changes(&a.response, &b.response))
+        .flatten();
+    Ok(TranslationComparison {
+        run_a: run_summary(&a, a_current),
+        run_b: run_summary(&b, b_current),
+        spans,
+        changed_a,
+        changed_b,
+        gloss_changes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize("  Don't fear, little flock!"),
+            ["  Don't ", "fear", ", ", "little ", "flock", "!"]
+        );
+        assert_eq!(tokenize("ἐν ἀρχῇ ἦν"), ["ἐν ", "ἀρχῇ ", "ἦν"]);
+        assert!(tokenize(" \n").is_empty());
+    }
+
+    #[test]
+    fn test_diff_words() {
+        let a = "In the beginning was the Word, and the Word was with God.";
+        let b = "In the beginning was the Message, and the Message was with God.";
+        let (spans, changed_a, changed_b) = diff_words(a, b).unwrap();
+        assert_eq!((changed_a, changed_b), (2, 2));
+        assert_eq!(spans.iter().map(|s| s.a.as_str()).collect::<String>(), a);
+        assert_eq!(spans.iter().map(|s| s.b.as_str()).collect::<String>(), b);
+        let replaced: Vec<_> = spans
+            .iter()
+            .filter(|s| s.kind == DiffKind::Replaced)
+            .map(|s| (s.a.trim(), s.b.trim()))
+            .collect();
+        assert_eq!(replaced, [("Word", "Message"), ("Word", "Message")]);
+
+        let (spans, _, _) = diff_words("Jesus wept.", "Jesus wept bitterly.").unwrap();
+        assert_eq!(spans[1].kind, DiffKind::Inserted);
+        assert_eq!(spans[1].b, "bitterly");
+
+        let long = "word ".repeat(MAX_DIFF_TOKENS + 1);
+        assert!(diff_words(&long, "word").is_err());
+    }
+
+    #[test]
+    fn test_gloss_changes() {
+        let run = |gloss: &str| {
+            serde_json::json!({"ledger": [{"verse_id": "John.1.1", "tokens": [
+                {"position": 0, "surface": "Ἐν", "gloss": "in"},
+                {"position": 4, "surface": "λόγος", "gloss": gloss},
+            ]}]})
+        };
+        let changes = gloss_changes(&run("word"), &run("message")).unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].position, 4);
+        assert_eq!(changes[0].gloss_b.as_deref(), Some("message"));
+        assert!(gloss_changes(&run("word"), &serde_json::json!({})).is_none());
+    }
+}
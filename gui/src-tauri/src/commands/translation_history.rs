@@ -0,0 +1,502 @@
+//! Translation history as JSON Lines, for scripts and for moving it to
+//! another machine.
+//!
+//! The history is every cached passage plus the earlier runs kept in
+//! `passage_history`. Each line of an export is one run:
+//!
+//! ```json
+//! {"schema_version": 1, "passage_id": "3f1c…", "reference": "John 3:16",
+//!  "normalized_ref": "John 3:16", "books": ["John"], "mode": "readable",
+//!  "translator": "literal", "translated_at_ms": 1718000000000,
+//!  "greek": "…", "translation": "…",
+//!  "verses": [{"verse_id": "John.3.16", "greek": "…", "translation": "…"}],
+//!  "note": "…", "speakers": [{"start_verse": "John.3.16",
+//!  "end_verse": "John.3.16", "speaker": "Jesus"}], "engine_version": "0.9.0",
+//!  "response": {…}}
+//! ```
+//!
+//! `passage_id` is the cache key (normalized reference, mode and
+//! translator). `note` is only present when the export asked for notes,
+//! `speakers` only when the engine reported who speaks and `engine_version`
+//! only when it was known at translation time.
+//! `response` is the engine's response with session, credential and
+//! machine fields (see `PRIVATE_KEYS`) removed; nothing else identifying
+//! the user or the machine is written. Readers should ignore unknown
+//! fields; a change that breaks old readers bumps `schema_version`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::PathBuf;
+use tauri::{AppHandle, Emitter};
+
+use super::errors::AppError;
+use super::local_search::{self, reference_books};
+use super::passages::{
+    archive_passage, cached_passage_ids, load_passage, load_passage_history, passage_id,
+    save_passage, CachedPassage, CachedVerse,
+};
+use super::red_letters::SpeakerSpan;
+use super::reference::resolve_book;
+use super::save_dialog::{validate_export_path, ExportKind};
+use super::storage;
+
+pub const HISTORY_SCHEMA_VERSION: u32 = 1;
+const EXPORT_PROGRESS_EVENT: &str = "history-export-progress";
+const IMPORT_PROGRESS_EVENT: &str = "history-import-progress";
+/// Import progress is reported every this many lines
+const IMPORT_PROGRESS_EVERY: usize = 100;
+/// Response fields dropped on export, at any depth (case-insensitive)
+const PRIVATE_KEYS: &[&str] = &[
+    "session_id",
+    "token",
+    "access_token",
+    "api_key",
+    "authorization",
+    "user_id",
+    "username",
+    "hostname",
+    "machine_id",
+    "install_id",
+    "ip_address",
+    "data_dir",
+    "cache_dir",
+];
+
+/// One translation run, as written to the export.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryRecord {
+    pub schema_version: u32,
+    pub passage_id: String,
+    pub reference: String,
+    pub normalized_ref: String,
+    /// Canonical books the reference covers; informational, not imported
+    #[serde(default)]
+    pub books: Vec<String>,
+    pub mode: String,
+    pub translator: String,
+    pub translated_at_ms: u64,
+    pub greek: String,
+    pub translation: String,
+    #[serde(default)]
+    pub verses: Vec<CachedVerse>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// Who speaks where, when the engine said
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speakers: Option<Vec<SpeakerSpan>>,
+    /// Engine version that produced the translation, when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub engine_version: Option<String>,
+    #[serde(default)]
+    pub response: Value,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct HistoryFilters {
+    /// Runs translated at or after this time
+    #[serde(default)]
+    pub from_ms: Option<u64>,
+    /// Runs translated before this time
+    #[serde(default)]
+    pub to_ms: Option<u64>,
+    /// Passages in this book, by any name `resolve_book` accepts
+    #[serde(default)]
+    pub book: Option<String>,
+    /// Include private passage notes
+    #[serde(default)]
+    pub include_notes: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryExportReport {
+    pub path: String,
+    pub records: usize,
+    pub passages: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryExportProgress {
+    pub passages_done: usize,
+    pub passages_total: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryImportProgress {
+    pub lines_done: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryLineError {
+    /// 1-based line number in the file
+    pub line: usize,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HistoryImportResult {
+    /// Runs of passages that weren't cached here before
+    pub imported: u32,
+    /// Runs added to, or replacing runs of, passages already cached
+    pub merged: u32,
+    /// Runs already present, skipped because of `dedupe`
+    pub skipped: u32,
+    pub errors: Vec<HistoryLineError>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ImportAction {
+    Imported,
+    Merged,
+    Skipped,
+}
+
+/// Remove `PRIVATE_KEYS` from a JSON value, recursively.
+fn strip_private(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.retain(|key, _| !PRIVATE_KEYS.iter().any(|k| k.eq_ignore_ascii_case(key)));
+            map.values_mut().for_each(strip_private);
+        }
+        Value::Array(items) => items.iter_mut().for_each(strip_private),
+        _ => {}
+    }
+}
+
+impl HistoryRecord {
+    fn from_passage(mut passage: CachedPassage, include_notes: bool) -> Self {
+        strip_private(&mut passage.response);
+        Self {
+            schema_version: HISTORY_SCHEMA_VERSION,
+            books: reference_books(&passage.normalized_ref),
+            passage_id: passage.id,
+            reference: passage.reference,
+            normalized_ref: passage.normalized_ref,
+            mode: passage.mode,
+            translator: passage.translator,
+            translated_at_ms: passage.translated_at_ms,
+            greek: passage.greek,
+            translation: passage.translation,
+            verses: passage.verses,
+            note: passage.note.filter(|_| include_notes),
+            speakers: passage.speakers,
+            engine_version: passage.engine_version,
+            response: passage.response,
+        }
+    }
+
+    /// Check the record and turn it into a cache entry.
+    fn into_passage(self) -> Result<CachedPassage, String> {
+        if self.schema_version == 0 || self.schema_version > HISTORY_SCHEMA_VERSION {
+            return Err(format!(
+                "Unsupported schema_version {} (expected {})",
+                self.schema_version, HISTORY_SCHEMA_VERSION
+            ));
+        }
+        if self.normalized_ref.trim().is_empty() {
+            return Err("normalized_ref is empty".to_string());
+        }
+        // The id names the cache file, so it must be the one we'd derive
+        if self.passage_id != passage_id(&self.normalized_ref, &self.mode, &self.translator) {
+            return Err("passage_id does not match the reference, mode and translator".to_string());
+        }
+        if self.translated_at_ms == 0 {
+            return Err("translated_at_ms is missing".to_string());
+        }
+        Ok(CachedPassage {
+            id: self.passage_id,
+            reference: self.reference,
+            normalized_ref: self.normalized_ref,
+            mode: self.mode,
+            translator: self.translator,
+            greek: self.greek,
+            translation: self.translation,
+            verses: self.verses,
+            note: self.note.filter(|n| !n.trim().is_empty()),
+            translated_at_ms: self.translated_at_ms,
+            speakers: self.speakers,
+            engine_version: self.engine_version,
+            response: self.response,
+        })
+    }
+}
+
+/// Whether a run passes the date and book filters; `book` is resolved.
+fn matches(passage: &CachedPassage, filters: &HistoryFilters, book: Option<&str>) -> bool {
+    filters
+        .from_ms
+        .is_none_or(|from| passage.translated_at_ms >= from)
+        && filters.to_ms.is_none_or(|to| passage.translated_at_ms < to)
+        && book.is_none_or(|book| {
+            reference_books(&passage.normalized_ref)
+                .iter()
+                .any(|b| b == book)
+        })
+}
+
+fn write_record(out: &mut dyn Write, record: &HistoryRecord) -> io::Result<()> {
+    serde_json::to_writer(&mut *out, record)?;
+    out.write_all(b"\n")
+}
+
+/// Write every matching run, one passage at a time. Returns the number of
+/// records and of passages they came from.
+fn write_history(
+    app: &AppHandle,
+    out: &mut dyn Write,
+    filters: &HistoryFilters,
+    book: Option<&str>,
+) -> io::Result<(usize, usize)> {
+    let ids = cached_passage_ids(app).map_err(io::Error::other)?;
+    let (mut records, mut passages) = (0, 0);
+    for (done, id) in ids.iter().enumerate() {
+        let current = match load_passage(app, id) {
+            Ok(passage) => passage,
+            Err(e) => {
+                tracing::warn!("Skipping unreadable cached passage {}: {}", id, e);
+                continue;
+            }
+        };
+        let earlier = load_passage_history(app, id).unwrap_or_default();
+        let mut written = 0;
+        for run in std::iter::once(current).chain(earlier) {
+            if matches(&run, filters, book) {
+                write_record(
+                    out,
+                    &HistoryRecord::from_passage(run, filters.include_notes),
+                )?;
+                written += 1;
+            }
+        }
+        records += written;
+        passages += usize::from(written > 0);
+        let _ = app.emit(
+            EXPORT_PROGRESS_EVENT,
+            HistoryExportProgress {
+                passages_done: done + 1,
+                passages_total: ids.len(),
+            },
+        );
+    }
+    Ok((records, passages))
+}
+
+/// Add one run to the cache. A run of a cached passage becomes the
+/// current entry if it is newer and joins the kept history otherwise.
+fn import_passage(
+    app: &AppHandle,
+    passage: CachedPassage,
+    dedupe: bool,
+) -> Result<ImportAction, String> {
+    let Ok(current) = load_passage(app, &passage.id) else {
+        save_passage(app, &passage)?;
+        index(app, &passage);
+        return Ok(ImportAction::Imported);
+    };
+
+    let earlier = load_passage_history(app, &passage.id).unwrap_or_default();
+    let duplicate = std::iter::once(&current).chain(&earlier).any(|run| {
+        run.translated_at_ms == passage.translated_at_ms || run.translation == passage.translation
+    });
+    if dedupe && duplicate {
+        return Ok(ImportAction::Skipped);
+    }
+
+    if passage.translated_at_ms == current.translated_at_ms {
+        let note = passage.note.clone().or(current.note);
+        let passage = CachedPassage { note, ..passage };
+        save_passage(app, &passage)?;
+        index(app, &passage);
+    } else if passage.translated_at_ms > current.translated_at_ms {
+        archive_passage(app, &current)?;
+        let note = passage.note.clone().or(current.note.clone());
+        let passage = CachedPassage { note, ..passage };
+        save_passage(app, &passage)?;
+        index(app, &passage);
+    } else {
+        archive_passage(app, &passage)?;
+    }
+    Ok(ImportAction::Merged)
+}
+
+fn index(app: &AppHandle, passage: &CachedPassage) {
+    if let Err(e) = local_search::index_passage(app, passage, None) {
+        tracing::warn!("Could not index {} for search: {}", passage.id, e);
+    }
+}
+
+fn import_lines(
+    app: &AppHandle,
+    reader: impl BufRead,
+    dedupe: bool,
+) -> Result<HistoryImportResult, String> {
+    let mut result = HistoryImportResult::default();
+    for (i, line) in reader.lines().enumerate() {
+        let line_number = i + 1;
+        let line = line.map_err(|e| format!("line {}: {}", line_number, e))?;
+        if !line.trim().is_empty() {
+            let outcome = serde_json::from_str::<HistoryRecord>(&line)
+                .map_err(|e| e.to_string())
+                .and_then(HistoryRecord::into_passage)
+                .and_then(|passage| import_passage(app, passage, dedupe));
+            match outcome {
+                Ok(ImportAction::Imported) => result.imported += 1,
+                Ok(ImportAction::Merged) => result.merged += 1,
+                Ok(ImportAction::Skipped) => result.skipped += 1,
+                Err(error) => result.errors.push(HistoryLineError {
+                    line: line_number,
+                    error,
+                }),
+            }
+        }
+        if line_number % IMPORT_PROGRESS_EVERY == 0 {
+            let _ = app.emit(
+                IMPORT_PROGRESS_EVENT,
+                HistoryImportProgress {
+                    lines_done: line_number,
+                },
+            );
+        }
+    }
+    Ok(result)
+}
+
+/// Stream the translation history to a JSON Lines file.
+///
+/// `dest_path` should come from `choose_save_path` with kind `history`.
+/// Emits `history-export-progress` after each passage.
+#[tauri::command]
+pub async fn export_history(
+    app: AppHandle,
+    dest_path: String,
+    filters: Option<HistoryFilters>,
+) -> Result<HistoryExportReport, AppError> {
+    let path = validate_export_path(&app, ExportKind::History, &PathBuf::from(dest_path))?;
+    let filters = filters.unwrap_or_default();
+    let book = match &filters.book {
+        Some(book) => Some(resolve_book(book).ok_or_else(|| format!("Unknown book: {}", book))?),
+        None => None,
+    };
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut counts = (0, 0);
+        storage::write_atomic_with(&path, |out| {
+            counts = write_history(&app, out, &filters, book)?;
+            Ok(())
+        })?;
+        Ok(HistoryExportReport {
+            path: path.to_string_lossy().into_owned(),
+            records: counts.0,
+            passages: counts.1,
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Import a history export line by line.
+///
+/// With `dedupe`, runs already in the cache (same cache key and either the
+/// same timestamp or the same translation) are skipped; otherwise they are
+/// imported again, replacing a run with the same timestamp. Lines that
+/// can't be read are reported and the rest of the file still imported.
+/// Emits `history-import-progress` every 100 lines.
+#[tauri::command]
+pub async fn import_history(
+    app: AppHandle,
+    path: String,
+    dedupe: bool,
+) -> Result<HistoryImportResult, AppError> {
+    let file = File::open(&path).map_err(|e| format!("{}: {}", path, e))?;
+    Ok(tauri::async_runtime::spawn_blocking(move || {
+        import_lines(&app, BufReader::new(file), dedupe)
+    })
+    .await
+    .map_err(|e| e.to_string())??)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testsupport::passage::PassageBuilder;
+
+    fn passage(normalized_ref: &str, translated_at_ms: u64) -> CachedPassage {
+        PassageBuilder::new()
+            .id(&passage_id(normalized_ref, "readable", "literal"))
+            .reference(normalized_ref)
+            .greek("Ἐν ἀρχῇ ἦν ὁ λόγος")
+            .translation("In the beginning was the Word")
+            .note(Some("check ἀρχή"))
+            .translated_at_ms(translated_at_ms)
+            .response(serde_json::json!({
+                "session_id": "gui",
+                "verse_blocks": [{ "verse_id": "John.1.1", "Token": "x" }],
+                "ledger": { "hostname": "study-pc", "steps": 3 },
+            }))
+            .build()
+    }
+
+    #[test]
+    fn test_record_excludes_private_data() {
+        let record = HistoryRecord::from_passage(passage("John 1:1", 5), false);
+        assert_eq!(record.books, ["John"]);
+        assert_eq!(record.note, None);
+        assert_eq!(
+            record.response,
+            serde_json::json!({
+                "verse_blocks": [{ "verse_id": "John.1.1" }],
+                "ledger": { "steps": 3 },
+            })
+        );
+        let line = serde_json::to_string(&record).unwrap();
+        assert!(!line.contains("\"note\""));
+
+        let record = HistoryRecord::from_passage(passage("John 1:1", 5), true);
+        assert_eq!(record.note.as_deref(), Some("check ἀρχή"));
+    }
+
+    #[test]
+    fn test_record_round_trip_and_validation() {
+        let record = HistoryRecord::from_passage(passage("John 1:1", 5), true);
+        let line = serde_json::to_string(&record).unwrap();
+        let read: HistoryRecord = serde_json::from_str(&line).unwrap();
+        let restored = read.clone().into_passage().unwrap();
+        assert_eq!(restored.id, passage_id("John 1:1", "readable", "literal"));
+        assert_eq!(restored.translated_at_ms, 5);
+
+        let tampered = HistoryRecord {
+            passage_id: "../../etc".to_string(),
+            ..read.clone()
+        };
+        assert!(tampered.into_passage().is_err());
+        let future = HistoryRecord {
+            schema_version: HISTORY_SCHEMA_VERSION + 1,
+            ..read
+        };
+        assert!(future.into_passage().is_err());
+    }
+
+    #[test]
+    fn test_filters() {
+        let run = passage("Mark 1:1", 1_000);
+        let in_range = HistoryFilters {
+            from_ms: Some(1_000),
+            to_ms: Some(2_000),
+            ..Default::default()
+        };
+        assert!(matches(&run, &in_range, None));
+        assert!(matches(&run, &in_range, Some("Mark")));
+        assert!(!matches(&run, &in_range, Some("John")));
+        let later = HistoryFilters {
+            from_ms: Some(1_001),
+            ..Default::default()
+        };
+        assert!(!matches(&run, &later, None));
+        let before = HistoryFilters {
+            to_ms: Some(1_000),
+            ..Default::default()
+        };
+        assert!(!matches(&run, &before, None));
+    }
+}
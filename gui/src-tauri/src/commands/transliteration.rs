@@ -0,0 +1,314 @@
+//! Romanization of polytonic Greek.
+//!
+//! Text is decomposed (NFD) so every letter arrives with its marks as
+//! separate combining characters, then transliterated a word at a time:
+//! rough breathing becomes a leading h, γ before γ/κ/ξ/χ is nasal, and υ
+//! is y outside diphthongs in the SBL scheme. Anything that isn't Greek is
+//! passed through unchanged.
+
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+
+const SMOOTH: char = '\u{313}';
+const ROUGH: char = '\u{314}';
+const ACUTE: char = '\u{301}';
+const GRAVE: char = '\u{300}';
+const PERISPOMENI: char = '\u{342}';
+const DIAERESIS: char = '\u{308}';
+const YPOGEGRAMMENI: char = '\u{345}';
+/// Latin circumflex, for a preserved perispomeni
+const CIRCUMFLEX: char = '\u{302}';
+/// SBL marks an iota subscript with a hook below the vowel
+const OGONEK: char = '\u{328}';
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransliterationScheme {
+    /// SBL Handbook academic style: ē, ō, y, ą for ᾳ, initial ρ as rh
+    #[default]
+    Sbl,
+    /// No macrons or hooks: e, o, u, ai for ᾳ
+    Simple,
+}
+
+/// A Greek letter and the marks decomposed from it.
+#[derive(Debug, Clone, Default)]
+struct Letter {
+    /// Lowercase base letter, final sigma folded into σ
+    base: char,
+    upper: bool,
+    rough: bool,
+    iota_subscript: bool,
+    diaeresis: bool,
+    /// Acute, grave or perispomeni
+    accent: Option<char>,
+}
+
+enum Token {
+    Letter(Letter),
+    Other(char),
+}
+
+fn is_greek_letter(c: char) -> bool {
+    matches!(c, 'Α'..='Ω' | 'α'..='ω' | 'ϲ' | 'Ϲ')
+}
+
+fn is_vowel(c: char) -> bool {
+    matches!(c, 'α' | 'ε' | 'η' | 'ι' | 'ο' | 'υ' | 'ω')
+}
+
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens: Vec<Token> = Vec::new();
+    for c in text.chars() {
+        // Checked before decomposing: the Greek question mark and ano
+        // teleia decompose to ';' and '·'
+        let decomposed: Vec<char> = match c {
+            '\u{37E}' => vec!['?'],
+            '\u{387}' | '·' => vec![';'],
+            _ => std::iter::once(c).nfd().collect(),
+        };
+        for d in decomposed {
+            if is_greek_letter(d) {
+                let lower = d.to_lowercase().next().unwrap_or(d);
+                tokens.push(Token::Letter(Letter {
+                    base: match lower {
+                        'ς' | 'ϲ' => 'σ',
+                        other => other,
+                    },
+                    upper: d.is_uppercase(),
+                    ..Letter::default()
+                }));
+                continue;
+            }
+            let Some(Token::Letter(letter)) = tokens.last_mut() else {
+                tokens.push(Token::Other(d));
+                continue;
+            };
+            match d {
+                ROUGH => letter.rough = true,
+                YPOGEGRAMMENI => letter.iota_subscript = true,
+                DIAERESIS => letter.diaeresis = true,
+                ACUTE | GRAVE | PERISPOMENI => letter.accent = Some(d),
+                // Smooth breathing (and koronis), macrons and breves
+                SMOOTH | '\u{304}' | '\u{306}' => {}
+                _ => tokens.push(Token::Other(d)),
+            }
+        }
+    }
+    tokens
+}
+
+/// Latin for one letter of `word`, before case is applied.
+fn letter_text(
+    word: &[Letter],
+    i: usize,
+    scheme: TransliterationScheme,
+    keep_accents: bool,
+) -> String {
+    let sbl = scheme == TransliterationScheme::Sbl;
+    let letter = &word[i];
+    let prev = i.checked_sub(1).map(|p| word[p].base);
+    let next = word.get(i + 1);
+    let text = match letter.base {
+        'α' => "a",
+        'β' => "b",
+        'γ' if next.is_some_and(|n| matches!(n.base, 'γ' | 'κ' | 'ξ' | 'χ')) => "n",
+        'γ' => "g",
+        'δ' => "d",
+        'ε' => "e",
+        'ζ' => "z",
+        'η' if sbl => "ē",
+        'η' => "e",
+        'θ' => "th",
+        'ι' => "i",
+        'κ' => "k",
+        'λ' => "l",
+        'μ' => "m",
+        'ν' => "n",
+        'ξ' => "x",
+        'ο' => "o",
+        'π' => "p",
+        // SBL writes initial and doubled ρ as rh whether or not the
+        // breathing is printed; the simple scheme only when it is
+        'ρ' if letter.rough || (sbl && (i == 0 || prev == Some('ρ'))) => "rh",
+        'ρ' => "r",
+        'σ' => "s",
+        'τ' => "t",
+        'υ' if !sbl => "u",
+        'υ' => {
+            let after_vowel = !letter.diaeresis && matches!(prev, Some('α' | 'ε' | 'η' | 'ο'));
+            let before_iota = next.is_some_and(|n| n.base == 'ι' && !n.diaeresis);
+            if after_vowel || before_iota {
+                "u"
+            } else {
+                "y"
+            }
+        }
+        'φ' => "ph",
+        'χ' => "ch",
+        'ψ' => "ps",
+        'ω' if sbl => "ō",
+        'ω' => "o",
+        other => return other.to_string(),
+    };
+    // Marks go straight after the vowel, which is always one character;
+    // NFC puts them in canonical order and composes what it can
+    let mut marks = String::new();
+    if let Some(accent) = letter.accent.filter(|_| keep_accents) {
+        marks.push(if accent == PERISPOMENI {
+            CIRCUMFLEX
+        } else {
+            accent
+        });
+    }
+    if letter.diaeresis {
+        marks.push(DIAERESIS);
+    }
+    let mut trailing = "";
+    if letter.iota_subscript {
+        if sbl {
+            marks.push(OGONEK);
+        } else {
+            trailing = "i";
+        }
+    }
+    format!("{}{}{}", text, marks, trailing)
+}
+
+fn transliterate_word(
+    word: &[Letter],
+    scheme: TransliterationScheme,
+    keep_accents: bool,
+    out: &mut String,
+) {
+    let all_caps = word.len() > 1 && word.iter().all(|l| l.upper);
+    // The breathing of an initial diphthong is printed on its second vowel
+    let aspirated = word.iter().take(2).any(|l| l.rough && is_vowel(l.base));
+    let mut text = String::new();
+    if aspirated {
+        text.push('h');
+    }
+    for i in 0..word.len() {
+        text.push_str(&letter_text(word, i, scheme, keep_accents));
+    }
+
+    let upper = word.first().is_some_and(|l| l.upper);
+    if all_caps {
+        out.push_str(&text.to_uppercase());
+    } else if upper {
+        let mut chars = text.chars();
+        if let Some(first) = chars.next() {
+            out.extend(first.to_uppercase());
+            out.push_str(chars.as_str());
+        }
+    } else {
+        out.push_str(&text);
+    }
+}
+
+/// Transliterate Greek in `text`, leaving everything else as it is.
+/// Accents are dropped unless `keep_accents` is set.
+pub fn transliterate_text(text: &str, scheme: TransliterationScheme, keep_accents: bool) -> String {
+    let mut out = String::new();
+    let mut word: Vec<Letter> = Vec::new();
+    for token in tokenize(text) {
+        match token {
+            Token::Letter(letter) => word.push(letter),
+            Token::Other(c) => {
+                transliterate_word(&word, scheme, keep_accents, &mut out);
+                word.clear();
+                out.push(c);
+            }
+        }
+    }
+    transliterate_word(&word, scheme, keep_accents, &mut out);
+    out.nfc().collect()
+}
+
+/// Romanize Greek text in the given scheme (SBL by default).
+#[tauri::command]
+pub fn transliterate(
+    text: String,
+    scheme: Option<TransliterationScheme>,
+    keep_accents: Option<bool>,
+) -> String {
+    transliterate_text(
+        &text,
+        scheme.unwrap_or_default(),
+        keep_accents.unwrap_or(false),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use TransliterationScheme::{Sbl, Simple};
+
+    #[test]
+    fn test_transliteration_table() {
+        // (Greek, SBL, simple), accents stripped
+        let cases = [
+            ("ᾅ", "h\u{105}", "hai"),
+            ("ᾅδης", "h\u{105}dēs", "haides"),
+            ("ῥῆμα", "rhēma", "rhema"),
+            ("Ῥώμη", "Rhōmē", "Rhome"),
+            ("Πύρρος", "Pyrrhos", "Purros"),
+            ("ἄγγελος", "angelos", "angelos"),
+            ("ἀνάγκη", "anankē", "ananke"),
+            ("ἐλέγχω", "elenchō", "elencho"),
+            ("σφίγξ", "sphinx", "sphinx"),
+            ("οὐρανοῦ", "ouranou", "ouranou"),
+            ("αὐτοῦ", "autou", "autou"),
+            ("υἱός", "huios", "huios"),
+            ("Οὗτος", "Houtos", "Houtos"),
+            ("εὑρίσκω", "heuriskō", "heurisko"),
+            ("ψυχή", "psychē", "psuche"),
+            ("Μωϋσῆς", "Mōÿsēs", "Moüses"),
+            ("ἀΐδιος", "aïdios", "aïdios"),
+            ("ΧΡΙΣΤΟΣ", "CHRISTOS", "CHRISTOS"),
+            ("Ἰησοῦς Χριστός", "Iēsous Christos", "Iesous Christos"),
+            (
+                "ἐν ἀρχῇ ἦν ὁ λόγος·",
+                "en arch\u{119}\u{304} ēn ho logos;",
+                "en archei en ho logos;",
+            ),
+            ("τί\u{37E}", "ti?", "ti?"),
+            ("λο\u{301}γοϲ", "logos", "logos"),
+            (
+                "John 1:1 — ἐν ἀρχῇ",
+                "John 1:1 — en arch\u{119}\u{304}",
+                "John 1:1 — en archei",
+            ),
+        ];
+        for (greek, sbl, simple) in cases {
+            assert_eq!(transliterate_text(greek, Sbl, false), sbl, "{}", greek);
+            assert_eq!(
+                transliterate_text(greek, Simple, false),
+                simple,
+                "{}",
+                greek
+            );
+        }
+    }
+
+    #[test]
+    fn test_preserved_accents() {
+        let cases = [
+            ("λόγος", Sbl, "lógos"),
+            ("ὁ", Sbl, "ho"),
+            ("οὐρανοῦ", Simple, "ouranoû"),
+            ("ᾅδης", Sbl, "h\u{105}\u{301}dēs"),
+            ("ᾅδης", Simple, "háides"),
+            ("Ἄβελ", Sbl, "Ábel"),
+            ("Μωϋσῆς", Sbl, "Mōÿsē\u{302}s"),
+        ];
+        for (greek, scheme, expected) in cases {
+            assert_eq!(
+                transliterate_text(greek, scheme, true),
+                expected,
+                "{}",
+                greek
+            );
+        }
+    }
+}
@@ -0,0 +1,350 @@
+//! USFM 3 export of cached translations.
+//!
+//! Verses from the selected passages are merged in canonical order into one
+//! book (USFM files hold a single book). Verses the engine marked as spoken
+//! by Jesus are wrapped in `\wj ... \wj*`. The rendered text is checked for
+//! balanced markers before anything is written. Source attribution goes
+//! in `\rem` lines after the header.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+use super::errors::AppError;
+use super::licensing;
+use super::passages::{load_passage, CachedPassage};
+use super::red_letters::red_letter_verses;
+use super::reference::usfm_book_code;
+use super::save_dialog::{validate_export_path, ExportKind};
+use super::storage;
+use super::tags;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UsfmGreek {
+    #[default]
+    Omit,
+    /// `\rem` line before each verse
+    Remark,
+    /// Footnote after each verse
+    Footnote,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UsfmOptions {
+    #[serde(default)]
+    pub greek: UsfmGreek,
+    /// Wrap red-letter verses in `\wj`
+    #[serde(default = "default_true", alias = "red_letter")]
+    pub highlight_red_letters: bool,
+    /// Only passages with this tag; with no passage ids, every passage
+    /// carrying it
+    #[serde(default)]
+    pub tag: Option<String>,
+    /// Credit the source texts and engine in `\rem` lines; most corpus
+    /// licenses require it when the file is shared
+    #[serde(default = "default_true")]
+    pub include_attribution: bool,
+}
+fn default_true() -> bool {
+    true
+}
+
+impl Default for UsfmOptions {
+    fn default() -> Self {
+        Self {
+            greek: UsfmGreek::Omit,
+            highlight_red_letters: true,
+            tag: None,
+            include_attribution: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UsfmExportReport {
+    pub path: String,
+    pub verses_written: usize,
+    /// Passage ids without a cached translation, and verse ids with no text
+    pub skipped: Vec<String>,
+}
+
+/// One verse ready to render.
+#[derive(Debug, Clone)]
+struct UsfmVerse {
+    chapter: u32,
+    verse: u32,
+    greek: String,
+    translation: String,
+    red_letter: bool,
+}
+
+/// Split "John.3.16" into (book, chapter, verse).
+pub fn split_verse_id(verse_id: &str) -> Option<(&str, u32, u32)> {
+    let mut parts = verse_id.rsplitn(3, '.');
+    let verse = parts.next()?.parse().ok()?;
+    let chapter = parts.next()?.parse().ok()?;
+    Some((parts.next()?, chapter, verse))
+}
+
+/// USFM has no escaping: drop marker characters and flatten line breaks.
+fn clean_text(text: &str) -> String {
+    text.replace(['\\', '|'], "/")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Collect verses for one book, in order, from the given passages.
+fn collect_verses(
+    passages: &[CachedPassage],
+    skipped: &mut Vec<String>,
+) -> Result<Option<(String, Vec<UsfmVerse>)>, String> {
+    let mut book: Option<String> = None;
+    let mut verses = BTreeMap::new();
+    for passage in passages {
+        let red = red_letter_verses(passage);
+        for v in &passage.verses {
+            let Some((verse_book, chapter, verse)) = split_verse_id(&v.verse_id) else {
+                skipped.push(v.verse_id.clone());
+                continue;
+            };
+            match &book {
+                Some(b) if b != verse_book => {
+                    return Err(format!(
+                        "USFM files hold a single book; export {} and {} separately",
+                        b, verse_book
+                    ))
+                }
+                Some(_) => {}
+                None => book = Some(verse_book.to_string()),
+            }
+            if v.translation.trim().is_empty() {
+                skipped.push(v.verse_id.clone());
+                continue;
+            }
+            // The first passage containing a verse wins
+            verses.entry((chapter, verse)).or_insert_with(|| UsfmVerse {
+                chapter,
+                verse,
+                greek: clean_text(&v.greek),
+                translation: clean_text(&v.translation),
+                red_letter: red.contains(&v.verse_id),
+            });
+        }
+    }
+    Ok(book.map(|b| (b, verses.into_values().collect())))
+}
+
+fn render_usfm(
+    code: &str,
+    verses: &[UsfmVerse],
+    options: &UsfmOptions,
+    attribution: &[String],
+) -> String {
+    let mut out = format!(
+        "\\id {} Red Letters translation\n\\usfm 3.0\n\\h {}\n",
+        code, code
+    );
+    for line in attribution {
+        out.push_str(&format!("\\rem {}\n", clean_text(line)));
+    }
+    let mut chapter = 0;
+    for v in verses {
+        if v.chapter != chapter {
+            chapter = v.chapter;
+            out.push_str(&format!("\\c {}\n\\p\n", chapter));
+        }
+        if options.greek == UsfmGreek::Remark && !v.greek.is_empty() {
+            out.push_str(&format!("\\rem {}\n", v.greek));
+        }
+        out.push_str(&format!("\\v {} ", v.verse));
+        if options.highlight_red_letters && v.red_letter {
+            out.push_str(&format!("\\wj {}\\wj*", v.translation));
+        } else {
+            out.push_str(&v.translation);
+        }
+        if options.greek == UsfmGreek::Footnote && !v.greek.is_empty() {
+            out.push_str(&format!(
+                "\\f + \\fr {}:{} \\ft {}\\f*",
+                v.chapter, v.verse, v.greek
+            ));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Check marker structure and return the verse references ("JHN 3:16") found.
+///
+/// Requires `\id` first, `\v` only after `\c`, balanced `\wj`/`\f` pairs,
+/// and `\fr`/`\ft` only inside a footnote.
+fn check_usfm(text: &str) -> Result<Vec<String>, String> {
+    let mut refs = Vec::new();
+    let (mut book, mut chapter) = (None::<String>, None::<String>);
+    let mut open: Vec<&str> = Vec::new();
+
+    let mut rest = text;
+    while let Some(pos) = rest.find('\\') {
+        rest = &rest[pos + 1..];
+        let end = rest
+            .find(|c: char| c.is_whitespace() || c == '\\')
+            .unwrap_or(rest.len());
+        let marker = &rest[..end];
+        let arg = rest[end..].split_whitespace().next().unwrap_or("");
+
+        if book.is_none() && marker != "id" {
+            return Err(format!("\\{} before \\id", marker));
+        }
+        match marker {
+            "id" => book = Some(arg.to_string()),
+            "c" => chapter = Some(arg.to_string()),
+            "v" => {
+                let chapter = chapter
+                    .as_deref()
+                    .ok_or_else(|| format!("\\v {} before any \\c", arg))?;
+                refs.push(format!(
+                    "{} {}:{}",
+                    book.as_deref().unwrap_or(""),
+                    chapter,
+                    arg
+                ));
+            }
+            "wj" | "f" => open.push(marker),
+            "wj*" | "f*" => {
+                let name = &marker[..marker.len() - 1];
+                if open.pop() != Some(name) {
+                    return Err(format!("Unbalanced \\{}", marker));
+                }
+            }
+            "fr" | "ft" if open.last() != Some(&"f") => {
+                return Err(format!("\\{} outside a footnote", marker));
+            }
+            _ => {}
+        }
+        // Character markers may not span verses or paragraphs
+        if matches!(marker, "c" | "v" | "p" | "rem") && !open.is_empty() {
+            return Err(format!("\\{} not closed before \\{}", open[0], marker));
+        }
+    }
+    if let Some(marker) = open.first() {
+        return Err(format!("\\{} is never closed", marker));
+    }
+    Ok(refs)
+}
+
+/// Export cached translations as a USFM 3 file.
+///
+/// `dest_path` should come from `choose_save_path` with kind `usfm`.
+#[tauri::command]
+pub fn export_usfm(
+    app: AppHandle,
+    passage_ids: Vec<String>,
+    dest_path: String,
+    options: Option<UsfmOptions>,
+) -> Result<UsfmExportReport, AppError> {
+    let options = options.unwrap_or_default();
+    let passage_ids = tags::passage_ids_for_export(&app, passage_ids, options.tag.as_deref())?;
+    let path = validate_export_path(&app, ExportKind::Usfm, &PathBuf::from(dest_path))?;
+
+    let mut skipped = Vec::new();
+    let passages: Vec<CachedPassage> = passage_ids
+        .into_iter()
+        .filter_map(|id| match load_passage(&app, &id) {
+            Ok(passage) => Some(passage),
+            Err(_) => {
+                skipped.push(id);
+                None
+            }
+        })
+        .collect();
+
+    let (book, verses) = collect_verses(&passages, &mut skipped)?
+        .filter(|(_, verses)| !verses.is_empty())
+        .ok_or_else(|| "No cached verses to export".to_string())?;
+    let code = usfm_book_code(&book).ok_or_else(|| format!("No USFM code for {}", book))?;
+
+    let attribution = licensing::attribution_lines(options.include_attribution, &passages);
+    let usfm = render_usfm(code, &verses, &options, &attribution);
+    let refs = check_usfm(&usfm).map_err(|e| format!("Generated USFM is malformed: {}", e))?;
+    storage::write_atomic(&path, usfm.as_bytes())?;
+
+    Ok(UsfmExportReport {
+        path: path.to_string_lossy().into_owned(),
+        verses_written: refs.len(),
+        skipped,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testsupport::passage::PassageBuilder;
+
+    fn passage(verses: &[(&str, &str)], red: &[&str]) -> CachedPassage {
+        PassageBuilder::new()
+            .verses(verses, "ἀμὴν ἀμὴν")
+            .red_letters(red)
+            .build()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let passages = [
+            passage(&[("John.3.16", "For God so loved"), ("John.3.17", "")], &[]),
+            passage(
+                &[
+                    ("John.4.1", "When Jesus knew"),
+                    ("John.3.18", "He who believes"),
+                ],
+                &["John.3.18"],
+            ),
+        ];
+        let mut skipped = Vec::new();
+        let (book, verses) = collect_verses(&passages, &mut skipped).unwrap().unwrap();
+        assert_eq!(book, "John");
+        assert_eq!(skipped, ["John.3.17"]);
+
+        for greek in [UsfmGreek::Omit, UsfmGreek::Remark, UsfmGreek::Footnote] {
+            let options = UsfmOptions {
+                greek,
+                highlight_red_letters: true,
+                tag: None,
+                include_attribution: true,
+            };
+            let usfm = render_usfm("JHN", &verses, &options, &[]);
+            assert_eq!(
+                check_usfm(&usfm).unwrap(),
+                ["JHN 3:16", "JHN 3:18", "JHN 4:1"]
+            );
+            assert!(usfm.contains("\\v 18 \\wj He who believes\\wj*"));
+            assert_eq!(usfm.contains("\\rem"), greek == UsfmGreek::Remark);
+            assert_eq!(usfm.contains("\\f +"), greek == UsfmGreek::Footnote);
+        }
+
+        let attribution = ["SBLGNT \\ MorphGNT".to_string()];
+        let usfm = render_usfm("JHN", &verses, &UsfmOptions::default(), &attribution);
+        assert!(usfm.contains("\\h JHN\n\\rem SBLGNT / MorphGNT\n\\c 3"));
+        assert!(check_usfm(&usfm).is_ok());
+    }
+
+    #[test]
+    fn test_multiple_books_rejected() {
+        let passages = [passage(&[("John.3.16", "a"), ("Mark.1.1", "b")], &[])];
+        assert!(collect_verses(&passages, &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_check_usfm_rejects_unbalanced_markers() {
+        assert!(check_usfm("\\id JHN\n\\c 1\n\\v 1 \\wj text\n\\v 2 more").is_err());
+        assert!(check_usfm("\\id JHN\n\\c 1\n\\v 1 text\\wj*").is_err());
+        assert!(check_usfm("\\id JHN\n\\v 1 text").is_err());
+        assert!(check_usfm("\\c 1\n\\v 1 text").is_err());
+        assert!(check_usfm("\\id JHN\n\\c 1\n\\v 1 \\ft note").is_err());
+        assert_eq!(
+            check_usfm("\\id JHN\n\\c 1\n\\v 1 text").unwrap(),
+            ["JHN 1:1"]
+        );
+    }
+}
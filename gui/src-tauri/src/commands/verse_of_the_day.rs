@@ -0,0 +1,394 @@
+//! Verse of the day for the start screen.
+//!
+//! The verse is picked from the date, so it is the same all day: either
+//! from a curated list (bundled, or a manifest fetched from the configured
+//! URL at most once a day) or from every verse of a chosen book. The day's
+//! pick is kept in `verse_of_the_day.json` under the app data dir, so
+//! repeated calls and offline launches show the same verse, and
+//! `refresh_verse_of_the_day` moves on to another one.
+//!
+//! The text comes from the passage cache, or from the engine when a port
+//! is given and the verse hasn't been translated yet. Offline, the verse
+//! is returned without text and filled in on a later call.
+
+use chrono::Local;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::OnceLock;
+use tauri::AppHandle;
+
+use super::errors::AppError;
+use super::http::{self, HttpError};
+use super::passages::{
+    load_all_passages, load_passage, passage_id, translate_and_cache, CachedPassage,
+    TranslateOptions, TranslateOutcome,
+};
+use super::reference::{
+    book_display_name, canonical_reference, parse_reference_list, resolve_book,
+};
+use super::settings;
+use super::storage;
+
+const BUNDLED_VERSES: &str = include_str!("../../assets/verses_of_the_day.json");
+const DAY_FILE: &str = "verse_of_the_day.json";
+const MANIFEST_FILE: &str = "verse_of_the_day_manifest.json";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VerseSource {
+    #[default]
+    Curated,
+    /// Any verse of `VerseOfTheDaySettings::book`
+    Book,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VerseOfTheDaySettings {
+    pub source: VerseSource,
+    /// Canonical book id, e.g. "John"
+    pub book: Option<String>,
+    /// Manifest replacing the bundled curated list when it can be fetched
+    pub manifest_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct VerseManifest {
+    version: u32,
+    verses: Vec<String>,
+}
+
+/// Last manifest fetched from `url`.
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedManifest {
+    url: String,
+    fetched_date: String,
+    manifest: VerseManifest,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerseOfTheDay {
+    /// Local date, "YYYY-MM-DD"
+    pub date: String,
+    pub reference: String,
+    pub greek: Option<String>,
+    pub translation: Option<String>,
+    /// Cached passage the text came from
+    pub passage_id: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct DayState {
+    date: String,
+    /// Verses passed over today with `refresh_verse_of_the_day`
+    skips: u32,
+    verse: Option<VerseOfTheDay>,
+}
+
+fn bundled_verses() -> &'static [String] {
+    static VERSES: OnceLock<Vec<String>> = OnceLock::new();
+    VERSES.get_or_init(|| {
+        serde_json::from_str::<VerseManifest>(BUNDLED_VERSES)
+            .map(|m| m.verses)
+            .unwrap_or_else(|e| {
+                tracing::warn!("Invalid bundled verse list: {}", e);
+                Vec::new()
+            })
+    })
+}
+
+/// Reference for a verse id: "1Corinthians.13.4" -> "1 Corinthians 13:4".
+fn verse_reference(verse_id: &str) -> Option<String> {
+    let mut parts = verse_id.split('.');
+    let book = book_display_name(parts.next()?)?;
+    Some(format!("{} {}:{}", book, parts.next()?, parts.next()?))
+}
+
+/// Position in a list of `len` for `date`, moved on by `skips`.
+fn pick_index(date: &str, skips: u32, len: usize) -> usize {
+    let digest = Sha256::digest(date.as_bytes());
+    let seed = u64::from_be_bytes(digest[..8].try_into().unwrap());
+    ((seed % len as u64) as usize + skips as usize) % len
+}
+
+/// The verses to choose from under `settings`.
+fn candidates(settings: &VerseOfTheDaySettings, curated: &[String]) -> Result<Vec<String>, String> {
+    match settings.source {
+        VerseSource::Curated => Ok(curated
+            .iter()
+            .filter_map(|r| parse_reference_list(r).ok())
+            .map(|r| r.canonical)
+            .collect()),
+        VerseSource::Book => {
+            let book = settings
+                .book
+                .as_deref()
+                .ok_or("No book chosen for the verse of the day")?;
+            let parsed = parse_reference_list(book).map_err(|e| format!("{}: {}", book, e))?;
+            Ok(parsed
+                .verses
+                .iter()
+                .filter_map(|id| verse_reference(id))
+                .collect())
+        }
+    }
+}
+
+fn pick_verse(
+    settings: &VerseOfTheDaySettings,
+    curated: &[String],
+    date: &str,
+    skips: u32,
+) -> Result<String, String> {
+    let verses = candidates(settings, curated)?;
+    if verses.is_empty() {
+        return Err("No verses to choose the verse of the day from".to_string());
+    }
+    Ok(verses[pick_index(date, skips, verses.len())].clone())
+}
+
+/// Curated verses: the configured manifest, fetched at most once a day
+/// and kept for offline use, else the bundled list.
+async fn curated_verses(
+    app: &AppHandle,
+    settings: &VerseOfTheDaySettings,
+    today: &str,
+) -> Vec<String> {
+    let Some(url) = &settings.manifest_url else {
+        return bundled_verses().to_vec();
+    };
+    let Ok(path) = storage::app_data_file(app, MANIFEST_FILE) else {
+        return bundled_verses().to_vec();
+    };
+    let cached = storage::read_json::<CachedManifest>(&path)
+        .ok()
+        .filter(|c| &c.url == url);
+    if cached.as_ref().is_some_and(|c| c.fetched_date == today) {
+        return cached.map(|c| c.manifest.verses).unwrap_or_default();
+    }
+    let fetched = match http::remote(Method::GET, url) {
+        Ok(request) => http::send_json::<VerseManifest>(request).await,
+        Err(e) => Err(e),
+    };
+    match fetched {
+        Ok(manifest) => {
+            let cached = CachedManifest {
+                url: url.clone(),
+                fetched_date: today.to_string(),
+                manifest,
+            };
+            if let Err(e) = storage::write_json(&path, &cached) {
+                tracing::warn!("Could not keep the verse manifest: {}", e);
+            }
+            cached.manifest.verses
+        }
+        Err(e) => {
+            if matches!(e, HttpError::Offline) {
+                tracing::debug!("Verse manifest {}: skipped: offline-mode", url);
+            } else {
+                tracing::warn!("Could not fetch the verse manifest from {}: {}", url, e);
+            }
+            match cached {
+                Some(cached) => cached.manifest.verses,
+                None => bundled_verses().to_vec(),
+            }
+        }
+    }
+}
+
+/// A cached translation of `reference` with the default settings.
+fn cached_passage(app: &AppHandle, reference: &str) -> Option<CachedPassage> {
+    let options = TranslateOptions::default();
+    load_passage(
+        app,
+        &passage_id(reference, &options.mode, &options.translator),
+    )
+    .ok()
+    .or_else(|| {
+        load_all_passages(app).ok()?.into_iter().find(|p| {
+            p.mode == options.mode
+                && p.translator == options.translator
+                && canonical_reference(&p.normalized_ref) == reference
+        })
+    })
+}
+
+/// Fill in the verse's text from the cache, or the engine on `port`.
+async fn fill_text(app: &AppHandle, port: Option<u16>, verse: &mut VerseOfTheDay) {
+    let mut passage = cached_passage(app, &verse.reference);
+    if let (None, Some(port)) = (&passage, port) {
+        match translate_and_cache(app, port, &verse.reference, TranslateOptions::default()).await {
+            Ok(TranslateOutcome::Translation(translated)) => passage = Some(*translated),
+            Ok(TranslateOutcome::Gate(_)) => {}
+            Err(e) => tracing::warn!("Could not translate {}: {}", verse.reference, e),
+        }
+    }
+    if let Some(passage) = passage {
+        verse.greek = Some(passage.greek);
+        verse.translation = Some(passage.translation);
+        verse.passage_id = Some(passage.id);
+    }
+}
+
+/// Today's verse, choosing a new one when `skip` is set.
+async fn verse_of_the_day(
+    app: &AppHandle,
+    port: Option<u16>,
+    skip: bool,
+) -> Result<VerseOfTheDay, String> {
+    let today = Local::now().date_naive().format("%Y-%m-%d").to_string();
+    let path = storage::app_data_file(app, DAY_FILE)?;
+    let mut state: DayState = storage::read_json_or_default(&path)?;
+    if state.date != today {
+        state = DayState {
+            date: today.clone(),
+            ..Default::default()
+        };
+    }
+    if skip {
+        state.skips += 1;
+        state.verse = None;
+    }
+
+    let mut verse = match state.verse.take() {
+        Some(verse) => verse,
+        None => {
+            let settings = settings::load_settings(app)?.verse_of_the_day;
+            let curated = curated_verses(app, &settings, &today).await;
+            VerseOfTheDay {
+                reference: pick_verse(&settings, &curated, &today, state.skips)?,
+                date: today,
+                greek: None,
+                translation: None,
+                passage_id: None,
+            }
+        }
+    };
+    if verse.translation.is_none() {
+        fill_text(app, port, &mut verse).await;
+    }
+    state.verse = Some(verse.clone());
+    storage::write_json(&path, &state)?;
+    Ok(verse)
+}
+
+/// Today's verse with its text when available. Pass the engine port to
+/// translate a verse that isn't cached yet.
+#[tauri::command]
+pub async fn get_verse_of_the_day(
+    app: AppHandle,
+    port: Option<u16>,
+) -> Result<VerseOfTheDay, AppError> {
+    Ok(verse_of_the_day(&app, port, false).await?)
+}
+
+/// Replace today's verse with another one.
+#[tauri::command]
+pub async fn refresh_verse_of_the_day(
+    app: AppHandle,
+    port: Option<u16>,
+) -> Result<VerseOfTheDay, AppError> {
+    Ok(verse_of_the_day(&app, port, true).await?)
+}
+
+/// Choose where the verse of the day comes from. Today's verse is
+/// picked again under the new settings.
+#[tauri::command]
+pub fn set_verse_of_the_day_settings(
+    app: AppHandle,
+    mut settings: VerseOfTheDaySettings,
+) -> Result<VerseOfTheDaySettings, AppError> {
+    if let Some(book) = &settings.book {
+        settings.book = Some(
+            resolve_book(book)
+                .ok_or_else(|| AppError::InvalidInput(format!("Unknown book: {}", book)))?
+                .to_string(),
+        );
+    }
+    if settings.source == VerseSource::Book && settings.book.is_none() {
+        return Err(AppError::InvalidInput(
+            "Choose a book for the verse of the day".to_string(),
+        ));
+    }
+    if let Some(url) = &settings.manifest_url {
+        if !url.starts_with("https://") && !url.starts_with("http://") {
+            return Err(AppError::InvalidInput(format!(
+                "Invalid manifest URL: {}",
+                url
+            )));
+        }
+    }
+    settings::update_settings(&app, |s| s.verse_of_the_day = settings.clone())?;
+    let day = storage::app_data_file(&app, DAY_FILE)?;
+    if day.exists() {
+        std::fs::remove_file(&day).map_err(|e| format!("{}: {}", day.display(), e))?;
+    }
+    Ok(settings)
+}
+
+/// Today's verse as already cached or picked offline, for the start state.
+pub async fn start_verse(app: &AppHandle) -> Option<VerseOfTheDay> {
+    verse_of_the_day(app, None, false)
+        .await
+        .map_err(|e| tracing::warn!("Verse of the day unavailable: {}", e))
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_verses_are_canonical() {
+        let verses = bundled_verses();
+        assert!(verses.len() > 50);
+        for verse in verses {
+            let parsed = parse_reference_list(verse).unwrap();
+            assert_eq!(&parsed.canonical, verse);
+            assert_eq!(parsed.verses.len(), 1, "{}", verse);
+        }
+    }
+
+    #[test]
+    fn test_pick_is_stable_per_day() {
+        let settings = VerseOfTheDaySettings::default();
+        let curated = bundled_verses();
+        let first = pick_verse(&settings, curated, "2024-12-25", 0).unwrap();
+        assert_eq!(
+            pick_verse(&settings, curated, "2024-12-25", 0).unwrap(),
+            first
+        );
+        assert_ne!(
+            pick_verse(&settings, curated, "2024-12-25", 1).unwrap(),
+            first
+        );
+        assert_eq!(
+            pick_index("2024-12-25", 3, 3),
+            pick_index("2024-12-25", 0, 3)
+        );
+    }
+
+    #[test]
+    fn test_pick_from_book() {
+        let settings = VerseOfTheDaySettings {
+            source: VerseSource::Book,
+            book: Some("1Corinthians".to_string()),
+            manifest_url: None,
+        };
+        let verse = pick_verse(&settings, &[], "2024-12-25", 0).unwrap();
+        assert!(verse.starts_with("1 Corinthians "), "{}", verse);
+        assert_eq!(candidates(&settings, &[]).unwrap().len(), 437);
+        assert_eq!(verse_reference("Jude.1.3").as_deref(), Some("Jude 1:3"));
+        assert!(pick_verse(
+            &VerseOfTheDaySettings {
+                book: None,
+                ..settings
+            },
+            &[],
+            "2024-12-25",
+            0
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,271 @@
+//! Local webhook listener for engine integrations that push events.
+//!
+//! A deliberately minimal HTTP/1.1 server on 127.0.0.1: one request per
+//! connection, POST to the configured path only, JSON bodies up to 1 MB.
+//! With a secret, requests must carry `X-Webhook-Signature` (hex
+//! HMAC-SHA256 of the body, optionally prefixed `sha256=`). Only one
+//! listener runs at a time.
+
+use hmac::{Hmac, Mac};
+use serde_json::Value;
+use sha2::Sha256;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{Emitter, State, Window};
+
+use super::errors::AppError;
+
+/// Event emitted with each accepted webhook body
+const WEBHOOK_EVENT: &str = "webhook-received";
+/// Header carrying the body signature
+const SIGNATURE_HEADER: &str = "x-webhook-signature";
+/// Largest accepted request body
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+/// Largest accepted request line plus headers
+const MAX_HEAD_BYTES: usize = 16 * 1024;
+/// Per-connection read timeout, so a stalled client can't block the listener
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+struct ActiveListener {
+    port: u16,
+    stop: Arc<AtomicBool>,
+}
+
+#[derive(Default)]
+pub struct WebhookListenerState(Mutex<Option<ActiveListener>>);
+
+/// Check a hex HMAC-SHA256 signature of `body` in constant time.
+fn signature_valid(secret: &str, body: &[u8], signature: &str) -> bool {
+    let signature = signature.trim();
+    let signature = signature.strip_prefix("sha256=").unwrap_or(signature);
+    let Ok(expected) = hex::decode(signature) else {
+        return false;
+    };
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    mac.verify_slice(&expected).is_ok()
+}
+
+struct WebhookRequest {
+    method: String,
+    path: String,
+    signature: Option<String>,
+    body: Vec<u8>,
+}
+
+/// Read one request; `Err` carries the status to answer with.
+fn read_request(stream: &TcpStream) -> Result<WebhookRequest, u16> {
+    let mut reader = BufReader::new(stream);
+    let mut head_len = 0;
+    let mut line = String::new();
+    let mut read_line = |line: &mut String| -> Result<(), u16> {
+        line.clear();
+        let n = reader.read_line(line).map_err(|_| 400u16)?;
+        head_len += n;
+        if n == 0 || head_len > MAX_HEAD_BYTES {
+            return Err(if n == 0 { 400 } else { 431 });
+        }
+        Ok(())
+    };
+
+    read_line(&mut line)?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next().ok_or(400u16)?.to_string();
+    let target = parts.next().ok_or(400u16)?;
+    let path = target.split('?').next().unwrap_or(target).to_string();
+
+    let (mut content_length, mut signature) = (0usize, None);
+    loop {
+        read_line(&mut line)?;
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        let Some((name, value)) = header.split_once(':') else {
+            return Err(400);
+        };
+        let (name, value) = (name.trim().to_ascii_lowercase(), value.trim());
+        if name == "content-length" {
+            content_length = value.parse().map_err(|_| 400u16)?;
+        } else if name == SIGNATURE_HEADER {
+            signature = Some(value.to_string());
+        }
+    }
+    if content_length > MAX_BODY_BYTES {
+        return Err(413);
+    }
+
+    let mut body = vec![0u8; content_length];
+    reader.read_exact(&mut body).map_err(|_| 400u16)?;
+    Ok(WebhookRequest {
+        method,
+        path,
+        signature,
+        body,
+    })
+}
+
+fn respond(mut stream: &TcpStream, status: u16) {
+    let reason = match status {
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        _ => "Request Header Fields Too Large",
+    };
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        status, reason
+    );
+}
+
+/// Handle one connection, returning the JSON body to emit if it was accepted.
+fn handle_connection(stream: &TcpStream, path: &str, secret: Option<&str>) -> Option<Value> {
+    let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+    let result = read_request(stream).and_then(|request| {
+        if request.path != path {
+            return Err(404);
+        }
+        if request.method != "POST" {
+            return Err(405);
+        }
+        if let Some(secret) = secret {
+            let signed = request
+                .signature
+                .as_deref()
+                .is_some_and(|sig| signature_valid(secret, &request.body, sig));
+            if !signed {
+                return Err(401);
+            }
+        }
+        serde_json::from_slice::<Value>(&request.body).map_err(|_| 400)
+    });
+    match result {
+        Ok(body) => {
+            respond(stream, 204);
+            Some(body)
+        }
+        Err(status) => {
+            respond(stream, status);
+            None
+        }
+    }
+}
+
+/// Start the webhook listener on `port` (0 picks a free port) and return
+/// the bound port. Fails if a listener is already running.
+#[tauri::command]
+pub fn start_webhook_listener(
+    state: State<'_, WebhookListenerState>,
+    window: Window,
+    port: u16,
+    path: String,
+    secret: Option<String>,
+) -> Result<u16, AppError> {
+    let mut active = state.0.lock().unwrap();
+    if let Some(listener) = active.as_ref() {
+        return Err(AppError::InvalidInput(format!(
+            "A webhook listener is already running on port {}",
+            listener.port
+        )));
+    }
+    let path = format!("/{}", path.trim_start_matches('/'));
+    let secret = secret.filter(|s| !s.is_empty());
+
+    let listener = TcpListener::bind(("127.0.0.1", port)).map_err(|e| e.to_string())?;
+    let bound = listener.local_addr().map_err(|e| e.to_string())?.port();
+    let stop = Arc::new(AtomicBool::new(false));
+
+    let thread_stop = stop.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            if thread_stop.load(Ordering::SeqCst) {
+                break;
+            }
+            let Ok(stream) = stream else {
+                continue;
+            };
+            if let Some(body) = handle_connection(&stream, &path, secret.as_deref()) {
+                let _ = window.emit(WEBHOOK_EVENT, body);
+            }
+        }
+    });
+
+    *active = Some(ActiveListener { port: bound, stop });
+    Ok(bound)
+}
+
+/// Stop the webhook listener. A no-op if none is running.
+#[tauri::command]
+pub fn stop_webhook_listener(state: State<'_, WebhookListenerState>) -> Result<(), AppError> {
+    if let Some(listener) = state.0.lock().unwrap().take() {
+        listener.stop.store(true, Ordering::SeqCst);
+        // Wake the accept loop so it sees the stop flag and drops the socket
+        let _ = TcpStream::connect(("127.0.0.1", listener.port));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signature_valid() {
+        let body = br#"{"event":"job.completed"}"#;
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"s3cret").unwrap();
+        mac.update(body);
+        let signature = hex::encode(mac.finalize().into_bytes());
+
+        assert!(signature_valid("s3cret", body, &signature));
+        assert!(signature_valid(
+            "s3cret",
+            body,
+            &format!("sha256={}", signature)
+        ));
+        assert!(!signature_valid("other", body, &signature));
+        assert!(!signature_valid("s3cret", b"{}", &signature));
+        assert!(!signature_valid("s3cret", body, "not hex"));
+    }
+
+    #[test]
+    fn test_handle_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let send = |request: &'static str| {
+            std::thread::spawn(move || {
+                let mut client = TcpStream::connect(addr).unwrap();
+                client.write_all(request.as_bytes()).unwrap();
+                let mut response = String::new();
+                client.read_to_string(&mut response).unwrap();
+                response
+            })
+        };
+
+        let client = send("POST /hook HTTP/1.1\r\nContent-Length: 11\r\n\r\n{\"ok\":true}");
+        let (stream, _) = listener.accept().unwrap();
+        let body = handle_connection(&stream, "/hook", None);
+        drop(stream);
+        assert_eq!(body, Some(serde_json::json!({"ok": true})));
+        assert!(client.join().unwrap().starts_with("HTTP/1.1 204"));
+
+        let client = send("POST /hook HTTP/1.1\r\nContent-Length: 2\r\n\r\n{}");
+        let (stream, _) = listener.accept().unwrap();
+        assert_eq!(handle_connection(&stream, "/hook", Some("s3cret")), None);
+        drop(stream);
+        assert!(client.join().unwrap().starts_with("HTTP/1.1 401"));
+
+        let client = send("GET /hook HTTP/1.1\r\n\r\n");
+        let (stream, _) = listener.accept().unwrap();
+        assert_eq!(handle_connection(&stream, "/hook", None), None);
+        drop(stream);
+        assert!(client.join().unwrap().starts_with("HTTP/1.1 405"));
+    }
+}
@@ -0,0 +1,228 @@
+//! WebSocket relay for when the webview's CSP blocks direct connections.
+//!
+//! Each connection runs on its own thread. The socket is read with a short
+//! timeout so the same thread can also drain outgoing messages queued by
+//! `send_websocket_message`; tungstenite sockets aren't shared between
+//! threads. Each connection is logged in a span under its connection id;
+//! messages themselves are not logged.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::net::TcpStream;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State, Window};
+use tungstenite::client::IntoClientRequest;
+use tungstenite::protocol::frame::coding::CloseCode;
+use tungstenite::protocol::CloseFrame;
+use tungstenite::{Message, WebSocket};
+
+use super::auth::get_auth_token;
+use super::errors::AppError;
+use super::logging;
+
+/// Event emitted for each incoming text frame
+const WS_MESSAGE_EVENT: &str = "ws-message-received";
+/// Event emitted once a connection has closed
+const WS_CLOSED_EVENT: &str = "ws-closed";
+/// How long a read waits before checking for outgoing messages
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// Timeout for the TCP connect and handshake
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WsMessageReceived {
+    pub connection_id: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WsClosed {
+    pub connection_id: String,
+    /// Close code from the engine; `None` if the connection dropped
+    pub code: Option<u16>,
+}
+
+enum Outgoing {
+    Text(String),
+    Close,
+}
+
+/// Outgoing message queues of open connections, by connection id.
+#[derive(Default)]
+pub struct WebSocketRelays(Mutex<HashMap<String, Sender<Outgoing>>>);
+
+impl WebSocketRelays {
+    /// Ask every open connection to close.
+    pub fn close_all(&self) {
+        for sender in self.0.lock().unwrap().values() {
+            let _ = sender.send(Outgoing::Close);
+        }
+    }
+}
+
+fn connect(port: u16, path: &str) -> Result<WebSocket<TcpStream>, String> {
+    let url = format!("ws://127.0.0.1:{}/{}", port, path.trim_start_matches('/'));
+    let mut request = url.into_client_request().map_err(|e| e.to_string())?;
+    if let Ok(token) = get_auth_token() {
+        let value = format!("Bearer {}", token.token)
+            .parse()
+            .map_err(|_| "Invalid auth token".to_string())?;
+        request.headers_mut().insert("Authorization", value);
+    }
+
+    let addr = ("127.0.0.1", port);
+    let stream = std::net::ToSocketAddrs::to_socket_addrs(&addr)
+        .map_err(|e| e.to_string())?
+        .next()
+        .ok_or_else(|| "Could not resolve engine address".to_string())
+        .and_then(|addr| {
+            TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT).map_err(|e| e.to_string())
+        })?;
+    stream
+        .set_read_timeout(Some(CONNECT_TIMEOUT))
+        .map_err(|e| e.to_string())?;
+    let (socket, _) = tungstenite::client(request, stream).map_err(|e| e.to_string())?;
+    socket
+        .get_ref()
+        .set_read_timeout(Some(POLL_INTERVAL))
+        .map_err(|e| e.to_string())?;
+    Ok(socket)
+}
+
+/// Pump one connection until it closes; returns the close code, if any.
+fn relay(
+    mut socket: WebSocket<TcpStream>,
+    outgoing: Receiver<Outgoing>,
+    window: &Window,
+    connection_id: &str,
+) -> Option<u16> {
+    let mut closing = false;
+    loop {
+        while !closing {
+            match outgoing.try_recv() {
+                Ok(Outgoing::Text(text)) => {
+                    if socket.send(Message::Text(text)).is_err() {
+                        return None;
+                    }
+                }
+                Ok(Outgoing::Close) | Err(TryRecvError::Disconnected) => {
+                    let _ = socket.close(Some(CloseFrame {
+                        code: CloseCode::Normal,
+                        reason: "".into(),
+                    }));
+                    // Keep reading until the engine acknowledges the close
+                    closing = true;
+                }
+                Err(TryRecvError::Empty) => break,
+            }
+        }
+
+        match socket.read() {
+            Ok(Message::Text(message)) => {
+                let _ = window.emit(
+                    WS_MESSAGE_EVENT,
+                    WsMessageReceived {
+                        connection_id: connection_id.to_string(),
+                        message,
+                    },
+                );
+            }
+            Ok(Message::Close(frame)) => {
+                // Answer the close handshake before reporting it
+                let _ = socket.flush();
+                return frame.map(|f| u16::from(f.code));
+            }
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(tungstenite::Error::ConnectionClosed) => return Some(1000),
+            Err(_) => return None,
+        }
+    }
+}
+
+/// Open a relayed WebSocket to the engine and return its connection id.
+///
+/// Incoming text frames arrive as `ws-message-received` events and the end
+/// of the connection as `ws-closed`.
+#[tauri::command]
+pub async fn proxy_engine_websocket(
+    app: AppHandle,
+    port: u16,
+    path: String,
+    window: Window,
+) -> Result<String, AppError> {
+    let connection_id = uuid::Uuid::new_v4().to_string();
+    let url = logging::url_for_log(&format!(
+        "ws://127.0.0.1:{}/{}",
+        port,
+        path.trim_start_matches('/')
+    ));
+    let span = tracing::info_span!("websocket", connection_id = %connection_id, url = %url);
+
+    let connect_span = span.clone();
+    let socket = tauri::async_runtime::spawn_blocking(move || {
+        let _entered = connect_span.entered();
+        connect(port, &path).inspect_err(|e| tracing::warn!("Could not connect: {}", e))
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    let (sender, receiver) = mpsc::channel();
+    app.state::<WebSocketRelays>()
+        .0
+        .lock()
+        .unwrap()
+        .insert(connection_id.clone(), sender);
+
+    let id = connection_id.clone();
+    std::thread::spawn(move || {
+        let _entered = span.entered();
+        tracing::info!("Opened");
+        let code = relay(socket, receiver, &window, &id);
+        tracing::info!(code, "Closed");
+        app.state::<WebSocketRelays>().0.lock().unwrap().remove(&id);
+        let _ = window.emit(
+            WS_CLOSED_EVENT,
+            WsClosed {
+                connection_id: id,
+                code,
+            },
+        );
+    });
+
+    Ok(connection_id)
+}
+
+fn queue(relays: &WebSocketRelays, connection_id: &str, message: Outgoing) -> Result<(), String> {
+    relays
+        .0
+        .lock()
+        .unwrap()
+        .get(connection_id)
+        .ok_or_else(|| format!("No open WebSocket connection {}", connection_id))?
+        .send(message)
+        .map_err(|_| format!("WebSocket connection {} is closing", connection_id))
+}
+
+/// Send a text frame on a relayed connection.
+#[tauri::command]
+pub fn send_websocket_message(
+    relays: State<'_, WebSocketRelays>,
+    connection_id: String,
+    message: String,
+) -> Result<(), AppError> {
+    Ok(queue(&relays, &connection_id, Outgoing::Text(message))?)
+}
+
+/// Close a relayed connection; `ws-closed` follows once the engine confirms.
+#[tauri::command]
+pub fn close_websocket(
+    relays: State<'_, WebSocketRelays>,
+    connection_id: String,
+) -> Result<(), AppError> {
+    Ok(queue(&relays, &connection_id, Outgoing::Close)?)
+}
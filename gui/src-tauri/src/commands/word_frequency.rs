@@ -0,0 +1,582 @@
+//! Lemma frequency tables for vocabulary prioritization.
+//!
+//! An installed corpus that ships MorphGNT token files (`*morphgnt.txt`,
+//! one token per line with the lemma in the last column) is counted here
+//! on a worker thread, with progress events per file. Passage lists,
+//! books and corpora without token files are counted by the engine's
+//! stats endpoint instead.
+//!
+//! Tables are cached in `word_frequencies/` under the app data dir, keyed
+//! by the scope and the corpus version they were computed from, so asking
+//! again is instant until the corpus changes.
+//!
+//! The rank of a single lemma, and lemmas by rank for graded reading lists,
+//! come from the engine's `/v1/frequency` endpoints for the NT, the LXX,
+//! classical Greek or all of them, and are cached in memory for a day.
+
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, State};
+use unicode_normalization::UnicodeNormalization;
+
+use super::cache::TtlCache;
+use super::corpus::{data_root, installed_from_manifest, list_installed_corpora};
+use super::engine::EngineError;
+use super::errors::AppError;
+use super::http;
+use super::interlinear_table::csv_field;
+use super::passage_lists::load_lists;
+use super::reference::resolve_book;
+use super::save_dialog::{validate_export_path, ExportKind};
+use super::storage;
+
+/// Event emitted while a local corpus is counted
+const PROGRESS_EVENT: &str = "word-frequency-progress";
+const CACHE_DIR: &str = "word_frequencies";
+/// Engine endpoint counting lemmas in a book, references or corpus
+const STATS_PATH: &str = "/v1/stats/lemma-frequencies";
+/// MorphGNT token files end with this
+const TOKEN_FILE_SUFFIX: &str = "morphgnt.txt";
+/// Columns of a MorphGNT line; the lemma is the last
+const MORPHGNT_COLUMNS: usize = 7;
+/// Coverage levels reported in `WordFrequencyTable::percentiles`
+const COVERAGE_LEVELS: [u8; 4] = [50, 80, 90, 95];
+/// Engine endpoint for lemma ranks
+const FREQUENCY_PATH: &str = "/v1/frequency";
+const RANK_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+const RANK_CACHE_SIZE: usize = 5000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FrequencyScope {
+    /// A saved passage list, by name
+    PassageList { name: String },
+    /// A whole book, by any name `resolve_book` accepts
+    Book { book: String },
+    /// An installed corpus, by id
+    Corpus { id: String },
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct WordFrequencyOptions {
+    /// Leave out the most frequent lemmas (articles, conjunctions, ...);
+    /// ranks and coverage still count them
+    pub exclude_top_n: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LemmaFrequency {
+    pub lemma: String,
+    pub count: u64,
+    /// 1 for the most frequent lemma; ties share a rank
+    pub rank: u32,
+    /// Percentage of all tokens covered by this lemma and every lemma
+    /// ranked above it
+    pub coverage: f64,
+}
+
+/// How many of the most frequent lemmas cover `percent` of the tokens.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoveragePercentile {
+    pub percent: u8,
+    pub lemmas: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WordFrequencyTable {
+    pub scope: FrequencyScope,
+    pub total_tokens: u64,
+    pub distinct_lemmas: usize,
+    /// Most frequent first, after `exclude_top_n`
+    pub lemmas: Vec<LemmaFrequency>,
+    pub percentiles: Vec<CoveragePercentile>,
+    /// Corpus version the counts come from, when known
+    pub corpus_version: Option<String>,
+}
+
+/// Corpus a lemma's rank is counted in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FrequencyCorpus {
+    NewTestament,
+    /// The Septuagint
+    Lxx,
+    ClassicalGreek,
+    All,
+}
+
+impl FrequencyCorpus {
+    fn as_str(self) -> &'static str {
+        match self {
+            FrequencyCorpus::NewTestament => "new_testament",
+            FrequencyCorpus::Lxx => "lxx",
+            FrequencyCorpus::ClassicalGreek => "classical_greek",
+            FrequencyCorpus::All => "all",
+        }
+    }
+}
+
+/// Where a lemma ranks in a corpus.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FrequencyInfo {
+    pub lemma: String,
+    /// 1 for the most frequent lemma
+    pub rank: u32,
+    pub occurrences: u32,
+    /// Share of all tokens, 0–100
+    #[serde(default)]
+    pub percentage_of_corpus: f32,
+    /// Canonical ids of the books the lemma occurs in
+    #[serde(default)]
+    pub found_in_books: Vec<String>,
+}
+
+/// Ranks by lemma (NFC) and corpus.
+pub struct FrequencyRankCache(Mutex<TtlCache<(String, FrequencyCorpus), FrequencyInfo>>);
+
+impl Default for FrequencyRankCache {
+    fn default() -> Self {
+        Self(Mutex::new(TtlCache::with_capacity(
+            RANK_TTL,
+            RANK_CACHE_SIZE,
+        )))
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WordFrequencyProgress {
+    pub corpus_id: String,
+    pub files_done: usize,
+    pub files_total: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct EngineLemmaCount {
+    lemma: String,
+    count: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct EngineFrequencies {
+    lemmas: Vec<EngineLemmaCount>,
+    #[serde(default)]
+    corpus_version: Option<String>,
+}
+
+/// Rank counts, most frequent first (ties alphabetically), and work out
+/// cumulative coverage and the coverage percentiles.
+fn build_table(
+    scope: FrequencyScope,
+    counts: HashMap<String, u64>,
+    corpus_version: Option<String>,
+) -> WordFrequencyTable {
+    let mut counts: Vec<(String, u64)> = counts.into_iter().filter(|(_, n)| *n > 0).collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    let total: u64 = counts.iter().map(|(_, n)| n).sum();
+
+    let mut lemmas = Vec::with_capacity(counts.len());
+    let mut covered = 0;
+    let mut rank = 0;
+    let mut previous = None;
+    for (i, (lemma, count)) in counts.into_iter().enumerate() {
+        if previous != Some(count) {
+            rank = i as u32 + 1;
+            previous = Some(count);
+        }
+        covered += count;
+        lemmas.push(LemmaFrequency {
+            lemma,
+            count,
+            rank,
+            coverage: covered as f64 * 100.0 / total as f64,
+        });
+    }
+    let percentiles = COVERAGE_LEVELS
+        .iter()
+        .map(|&percent| CoveragePercentile {
+            percent,
+            lemmas: lemmas
+                .iter()
+                .position(|l| l.coverage >= f64::from(percent))
+                .map_or(lemmas.len(), |i| i + 1),
+        })
+        .collect();
+    WordFrequencyTable {
+        scope,
+        total_tokens: total,
+        distinct_lemmas: lemmas.len(),
+        lemmas,
+        percentiles,
+        corpus_version,
+    }
+}
+
+/// Add the lemmas of a MorphGNT token file to `counts`. Lines without
+/// all seven columns are skipped.
+fn count_morphgnt(text: &str, counts: &mut HashMap<String, u64>) {
+    for line in text.lines() {
+        let columns: Vec<&str> = line.split_whitespace().collect();
+        if columns.len() < MORPHGNT_COLUMNS {
+            continue;
+        }
+        let lemma: String = columns[columns.len() - 1].nfc().collect();
+        *counts.entry(lemma).or_default() += 1;
+    }
+}
+
+/// MorphGNT token files under `dir`, sorted by path.
+fn token_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for path in entries.flatten().map(|e| e.path()) {
+            if path.is_dir() {
+                pending.push(path);
+            } else if path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.ends_with(TOKEN_FILE_SUFFIX))
+            {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+fn count_local(
+    app: &AppHandle,
+    corpus_id: &str,
+    files: &[PathBuf],
+) -> Result<HashMap<String, u64>, String> {
+    let mut counts = HashMap::new();
+    for (i, path) in files.iter().enumerate() {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        count_morphgnt(&text, &mut counts);
+        let _ = app.emit(
+            PROGRESS_EVENT,
+            WordFrequencyProgress {
+                corpus_id: corpus_id.to_string(),
+                files_done: i + 1,
+                files_total: files.len(),
+            },
+        );
+    }
+    Ok(counts)
+}
+
+/// Request body for the engine's stats endpoint.
+fn engine_query(app: &AppHandle, scope: &FrequencyScope) -> Result<serde_json::Value, String> {
+    Ok(match scope {
+        FrequencyScope::PassageList { name } => {
+            let list = load_lists(app)?
+                .into_iter()
+                .find(|l| &l.name == name)
+                .ok_or_else(|| format!("No passage list named {}", name))?;
+            let references: Vec<String> = list.items.into_iter().map(|i| i.reference).collect();
+            serde_json::json!({ "references": references })
+        }
+        FrequencyScope::Book { book } => {
+            let book = resolve_book(book).ok_or_else(|| format!("Unknown book: {}", book))?;
+            serde_json::json!({ "book": book })
+        }
+        FrequencyScope::Corpus { id } => serde_json::json!({ "corpus": id }),
+    })
+}
+
+/// Versions of the engine's active corpora, e.g. "sblgnt@2.1", for
+/// caching tables the engine computed.
+async fn active_corpus_version(port: u16) -> Option<String> {
+    let corpora = list_installed_corpora(port).await.ok()?;
+    let mut active: Vec<String> = corpora
+        .into_iter()
+        .filter(|c| c.active)
+        .map(|c| format!("{}@{}", c.id, c.version))
+        .collect();
+    active.sort();
+    (!active.is_empty()).then(|| active.join(","))
+}
+
+fn cache_path(app: &AppHandle, scope: &FrequencyScope, version: &str) -> Result<PathBuf, String> {
+    let scope = serde_json::to_string(scope).map_err(|e| e.to_string())?;
+    let digest = Sha256::digest(format!("{}|{}", scope, version));
+    Ok(storage::app_data_subdir(app, CACHE_DIR)?
+        .join(format!("{}.json", hex::encode(&digest[..8]))))
+}
+
+/// The full table for `scope`, from the cache when it is current.
+async fn frequency_table(
+    app: &AppHandle,
+    port: Option<u16>,
+    scope: FrequencyScope,
+) -> Result<WordFrequencyTable, String> {
+    // An installed corpus with token files is counted here
+    if let FrequencyScope::Corpus { id } = &scope {
+        let corpus = installed_from_manifest(&data_root().map_err(|e| e.to_string())?)
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .find(|c| &c.id == id);
+        if let Some(corpus) = corpus {
+            let files = token_files(Path::new(&corpus.install_path));
+            if !files.is_empty() {
+                let cache = cache_path(app, &scope, &corpus.version)?;
+                if let Ok(table) = storage::read_json(&cache) {
+                    return Ok(table);
+                }
+                let app = app.clone();
+                let table = tauri::async_runtime::spawn_blocking(move || {
+                    let counts = count_local(&app, &corpus.id, &files)?;
+                    Ok::<_, String>(build_table(scope, counts, Some(corpus.version)))
+                })
+                .await
+                .map_err(|e| e.to_string())??;
+                storage::write_json(&cache, &table)?;
+                return Ok(table);
+            }
+        }
+    }
+
+    let port = port.ok_or_else(|| "The engine is needed to count this selection".to_string())?;
+    let body = engine_query(app, &scope)?;
+    let version = active_corpus_version(port).await;
+    let cache = match &version {
+        Some(version) => Some(cache_path(app, &scope, version)?),
+        None => None,
+    };
+    if let Some(table) = cache.as_ref().and_then(|c| storage::read_json(c).ok()) {
+        return Ok(table);
+    }
+    let request = http::request(Method::POST, port, STATS_PATH)
+        .map_err(|e| e.to_string())?
+        .json(&body);
+    let response: EngineFrequencies = http::send_json(request).await.map_err(|e| e.to_string())?;
+    let mut counts = HashMap::new();
+    for entry in response.lemmas {
+        *counts.entry(entry.lemma.nfc().collect()).or_default() += entry.count;
+    }
+    let table = build_table(scope, counts, response.corpus_version.or(version));
+    if let Some(cache) = cache {
+        storage::write_json(&cache, &table)?;
+    }
+    Ok(table)
+}
+
+fn apply_options(
+    mut table: WordFrequencyTable,
+    options: &WordFrequencyOptions,
+) -> WordFrequencyTable {
+    let skip = options.exclude_top_n.min(table.lemmas.len());
+    table.lemmas.drain(..skip);
+    table
+}
+
+fn write_csv(out: &mut dyn Write, table: &WordFrequencyTable) -> std::io::Result<()> {
+    write!(out, "rank,lemma,count,coverage\r\n")?;
+    for l in &table.lemmas {
+        write!(
+            out,
+            "{},{},{},{:.2}\r\n",
+            l.rank,
+            csv_field(&l.lemma),
+            l.count,
+            l.coverage
+        )?;
+    }
+    Ok(())
+}
+
+/// Count lemmas in a passage list, a book or an installed corpus, most
+/// frequent first.
+///
+/// Installed corpora with token files are counted locally (emitting
+/// `word-frequency-progress`); everything else needs the engine on `port`.
+#[tauri::command]
+pub async fn compute_word_frequencies(
+    app: AppHandle,
+    port: Option<u16>,
+    scope: FrequencyScope,
+    options: Option<WordFrequencyOptions>,
+) -> Result<WordFrequencyTable, AppError> {
+    let table = frequency_table(&app, port, scope).await?;
+    Ok(apply_options(table, &options.unwrap_or_default()))
+}
+
+/// Write a frequency table as CSV and return the number of rows written.
+///
+/// `dest_path` should come from `choose_save_path` with kind `csv`.
+#[tauri::command]
+pub async fn export_word_frequencies(
+    app: AppHandle,
+    port: Option<u16>,
+    scope: FrequencyScope,
+    options: Option<WordFrequencyOptions>,
+    dest_path: String,
+) -> Result<u32, AppError> {
+    let path = validate_export_path(&app, ExportKind::Csv, &PathBuf::from(dest_path))?;
+    let table = frequency_table(&app, port, scope).await?;
+    let table = apply_options(table, &options.unwrap_or_default());
+    storage::write_atomic_with(&path, |out| write_csv(out, &table))?;
+    Ok(table.lemmas.len() as u32)
+}
+
+/// Frequency rank of a lemma, e.g. λόγος in the NT.
+#[tauri::command]
+pub async fn get_word_frequency_rank(
+    cache: State<'_, FrequencyRankCache>,
+    port: u16,
+    lemma: String,
+    corpus: FrequencyCorpus,
+) -> Result<FrequencyInfo, EngineError> {
+    let lemma: String = lemma.trim().nfc().collect();
+    let key = (lemma, corpus);
+    if let Some(info) = cache.0.lock().unwrap().get(&key) {
+        return Ok(info);
+    }
+    // The URL parser percent-encodes the Greek
+    let path = format!("{}/{}", FREQUENCY_PATH, key.0);
+    let request = http::request(Method::GET, port, &path)?.query(&[("corpus", corpus.as_str())]);
+    let info: FrequencyInfo = http::send_json(request).await?;
+    cache.0.lock().unwrap().insert(key, info.clone());
+    Ok(info)
+}
+
+/// Shared body of `get_words_by_frequency_range`, also used to build
+/// flashcard decks; not cached.
+pub async fn words_by_rank(
+    port: u16,
+    rank_min: u32,
+    rank_max: u32,
+    corpus: FrequencyCorpus,
+) -> Result<Vec<FrequencyInfo>, EngineError> {
+    let rank_min = rank_min.max(1);
+    if rank_min > rank_max {
+        return Ok(Vec::new());
+    }
+    let request = http::request(Method::GET, port, FREQUENCY_PATH)?.query(&[
+        ("corpus", corpus.as_str().to_string()),
+        ("rank_min", rank_min.to_string()),
+        ("rank_max", rank_max.to_string()),
+    ]);
+    let mut words: Vec<FrequencyInfo> = http::send_json(request).await?;
+    words.sort_by_key(|w| w.rank);
+    Ok(words)
+}
+
+/// Lemmas ranked `rank_min` to `rank_max` (inclusive) in a corpus, most
+/// frequent first, for graded reading lists.
+#[tauri::command]
+pub async fn get_words_by_frequency_range(
+    cache: State<'_, FrequencyRankCache>,
+    port: u16,
+    rank_min: u32,
+    rank_max: u32,
+    corpus: FrequencyCorpus,
+) -> Result<Vec<FrequencyInfo>, EngineError> {
+    let words = words_by_rank(port, rank_min, rank_max, corpus).await?;
+    let mut cache = cache.0.lock().unwrap();
+    for word in &words {
+        cache.insert((word.lemma.nfc().collect(), corpus), word.clone());
+    }
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MORPHGNT: &str = "\
+040101 P- -------- Ἐν Ἐν ἐν ἐν
+040101 N- ----DSF- ἀρχῇ ἀρχῇ ἀρχῇ ἀρχή
+040101 V- 3IAI-S-- ἦν ἦν ἦν εἰμί
+040101 RA ----NSM- ὁ ὁ ὁ ὁ
+040101 N- ----NSM- λόγος, λόγος λόγος λόγος
+040101 C- -------- καὶ καὶ καί καί
+040101 RA ----NSM- ὁ ὁ ὁ ὁ
+040101 N- ----NSM- λόγος λόγος λόγος λόγος
+040101 V- 3IAI-S-- ἦν ἦν ἦν εἰμί
+040101 P- -------- πρὸς πρὸς πρός πρός
+040101 RA ----ASM- τὸν τὸν τόν ὁ
+040101 N- ----ASM- θεόν, θεόν θεόν θεός
+
+truncated line
+";
+
+    fn table() -> WordFrequencyTable {
+        let mut counts = HashMap::new();
+        count_morphgnt(MORPHGNT, &mut counts);
+        build_table(
+            FrequencyScope::Book {
+                book: "John".to_string(),
+            },
+            counts,
+            None,
+        )
+    }
+
+    #[test]
+    fn test_frequency_info_defaults() {
+        let info: FrequencyInfo =
+            serde_json::from_str(r#"{"lemma": "λόγος", "rank": 67, "occurrences": 330}"#).unwrap();
+        assert_eq!(info.rank, 67);
+        assert!(info.found_in_books.is_empty());
+        let corpus: FrequencyCorpus = serde_json::from_str(r#""classical_greek""#).unwrap();
+        assert_eq!(corpus.as_str(), "classical_greek");
+        assert_eq!(
+            serde_json::to_string(&FrequencyCorpus::Lxx).unwrap(),
+            r#""lxx""#
+        );
+    }
+
+    #[test]
+    fn test_ranks_and_coverage() {
+        let table = table();
+        assert_eq!((table.total_tokens, table.distinct_lemmas), (12, 8));
+        let top: Vec<_> = table
+            .lemmas
+            .iter()
+            .map(|l| (l.lemma.as_str(), l.count, l.rank))
+            .collect();
+        assert_eq!(
+            top[..4],
+            [("ὁ", 3, 1), ("εἰμί", 2, 2), ("λόγος", 2, 2), ("θεός", 1, 4)]
+        );
+        assert_eq!(table.lemmas[0].coverage, 25.0);
+        assert_eq!(table.lemmas.last().unwrap().coverage, 100.0);
+        // ὁ, εἰμί, λόγος cover 7 of 12 tokens
+        assert_eq!(
+            table.percentiles[0],
+            CoveragePercentile {
+                percent: 50,
+                lemmas: 3
+            }
+        );
+    }
+
+    #[test]
+    fn test_exclude_top_n_and_csv() {
+        let options = WordFrequencyOptions { exclude_top_n: 1 };
+        let table = apply_options(table(), &options);
+        assert_eq!(table.lemmas.len(), 7);
+        assert_eq!((table.lemmas[0].rank, table.total_tokens), (2, 12));
+
+        let mut csv = Vec::new();
+        write_csv(&mut csv, &table).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert!(csv.starts_with("rank,lemma,count,coverage\r\n2,εἰμί,2,41.67\r\n"));
+
+        let all = WordFrequencyOptions { exclude_top_n: 100 };
+        assert!(apply_options(table, &all).lemmas.is_empty());
+    }
+}
@@ -1,5 +1,407 @@
 //! Red Letters GUI library crate.
 //!
-//! This exposes the commands module for the Tauri app.
+//! This exposes the commands module and the Tauri app builder.
+//!
+//! The app provides:
+//! - Keychain access for auth tokens (ADR-005)
+//! - Engine process management helpers
+//! - Command-line handling (`--open`), including arguments forwarded from a
+//!   second instance
+//!
+//! The actual API communication happens in the React frontend.
 
+pub mod cli;
 pub mod commands;
+#[cfg(test)]
+mod testsupport;
+
+use cli::CliArgs;
+use commands::{
+    add_tag, align_parallel_texts, apply_custom_theme, cancel_batch_job, check_engine_running,
+    check_token_permissions, choose_save_path, chunk_passage, clear_morphology_cache,
+    clear_search_history, close_websocket, compare_translations, compute_textual_similarity,
+    compute_word_frequencies, create_annotation, create_batch_job, create_reading_plan,
+    create_user, delete_annotation, delete_auth_token, delete_personal_gloss,
+    delete_saved_search_result, delete_user, detect_greek_in_text, diff_text_passages,
+    disable_hardware_acceleration, discard_crash_report, download_corpus, emit_custom_event,
+    enable_hardware_acceleration, end_impersonation, enter_presentation_mode,
+    exit_presentation_mode, export_annotations, export_docx, export_engine_audit_log,
+    export_flashcard_deck_to_anki, export_flashcards, export_history, export_html,
+    export_interlinear_table, export_interlinear_to_html, export_markdown, export_osis,
+    export_parallel_text, export_personal_glossary, export_reading_plans, export_saved_results,
+    export_study_document, export_usfm, export_word_frequencies, find_by_tag, format_citation,
+    generate_flashcard_deck, generate_interlinear_view, generate_openapi_spec,
+    get_accessibility_settings, get_advanced_search_filters, get_app_start_state, get_auth_token,
+    get_available_locales, get_available_themes, get_backend_log_level, get_backend_log_path,
+    get_backend_log_tail, get_badge_breakdown, get_batch_job, get_bible_book_tree,
+    get_cached_openapi_spec, get_cached_passage, get_clipboard_watch, get_command_history,
+    get_corpus_integrity_summary, get_cors_config, get_crash_reports, get_effective_theme,
+    get_engine_audit_log, get_engine_command_hint, get_engine_config, get_engine_config_schema,
+    get_engine_installed_extensions, get_engine_rate_limits, get_engine_startup_options,
+    get_engine_thread_count, get_font_list, get_hardware_acceleration_status, get_license_info,
+    get_locale, get_morphology, get_morphology_cache_stats, get_offline_mode, get_offline_queue,
+    get_parallel_versions, get_pending_crash_reports, get_personal_gloss, get_presentation_state,
+    get_process_count, get_pronunciation, get_rate_limit_status, get_reading_plan_progress,
+    get_red_letter_verses, get_search_history, get_search_suggestions, get_strongs_entry,
+    get_system_accessibility_prefs, get_telemetry_preview, get_text_commentary,
+    get_text_statistics, get_today_reading, get_token_description, get_verse_of_the_day,
+    get_word_frequency_rank, get_words_by_frequency_range, grpc_health_check, highlight_text_spans,
+    impersonate_user, import_annotations, import_history, import_local_corpus, import_passage_list,
+    import_personal_glossary, import_theme, install_bundled_font, kill_all_engine_processes,
+    list_annotations, list_auth_tokens_from_engine, list_available_commentaries,
+    list_available_corpora, list_available_versions, list_batch_jobs, list_cached_passages,
+    list_citation_styles, list_installed_corpora, list_monitors, list_passage_lists,
+    list_personal_glossary, list_saved_search_results, list_tags, list_translation_runs,
+    list_tts_voices, list_users, load_translation_strings, lookup_lemma, lookup_lemma_offline,
+    lookup_strongs, mark_reading_complete, normalize_greek_input, open_project,
+    openapi_spec_age_seconds, parse_reference, parse_reference_range, pause_batch_job,
+    prefetch_morphology, prefetch_pronunciations, proxy_engine_websocket, purge_telemetry,
+    queue_open_request, rebuild_search_index, record_search_execution, refresh_verse_of_the_day,
+    reload_extension, remove_corpus, remove_tag, rename_tag, repair_corpus, reset_to_default_theme,
+    resume_batch_job, retry_failed_items, revoke_auth_token_by_id, save_passage_list, save_project,
+    save_search_result, search_lemmas_offline, search_local, search_strongs_by_english,
+    send_crash_report, send_websocket_message, set_accessibility_settings, set_auth_token,
+    set_backend_log_level, set_clipboard_watch, set_cors_config, set_crash_reporting,
+    set_engine_config, set_engine_rate_limit, set_engine_thread_limit, set_locale,
+    set_offline_mode, set_passage_note, set_personal_gloss, set_pre_shutdown_delay_ms,
+    set_telemetry_enabled, set_telemetry_endpoint, set_token_description,
+    set_verse_of_the_day_settings, share_passage, speak_text, split_text_into_sentences,
+    start_batch_job, start_clipboard_monitor, start_engine_safe_mode, start_webhook_listener,
+    stop_clipboard_monitor, stop_speaking, stop_webhook_listener, strongs_for_lemma,
+    submit_search_query, take_open_requests, test_cors_preflight, translate_passage, transliterate,
+    unload_extension, update_annotation, update_user_role, validate_scripture_reference,
+    verify_corpus, AlignmentCache, BadgeState, BatchJobState, BookTreeCache, ClipboardWatchState,
+    CommandHistory, CommentaryCache, ConfigSchemaCache, CorpusIntegrityState, FrequencyRankCache,
+    HighlightCache, ImpersonationState, InterlinearCache, MorphologyCache, OpenRequestQueue,
+    PermissionsCache, PresentationState, SearchFilterCache, SimilarityCache, SpeechState,
+    StrongsCache, SuggestionCache, WebSocketRelays, WebhookListenerState,
+};
+use std::panic::AssertUnwindSafe;
+use std::path::Path;
+use tauri::webview::PageLoadEvent;
+use tauri::{Manager, RunEvent};
+
+/// Build and run the Tauri application.
+pub fn run(args: CliArgs) {
+    let context = tauri::generate_context!();
+    commands::logging::init_logging(&context.config().identifier);
+    commands::hardware_acceleration::apply_gpu_preference(
+        args.disable_gpu,
+        &context.config().identifier,
+    );
+
+    let app = tauri::Builder::default()
+        // Must be registered first: a second launch forwards its arguments here
+        .plugin(tauri_plugin_single_instance::init(|app, argv, cwd| {
+            let forwarded = cli::parse_args(argv.into_iter().skip(1), Path::new(&cwd));
+            if let Some(request) = forwarded.open {
+                queue_open_request(app, request);
+            }
+            if let Some(w) = app.get_webview_window("main") {
+                let _ = w.unminimize();
+                let _ = w.set_focus();
+            }
+        }))
+        .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
+        .manage(AlignmentCache::default())
+        .manage(BadgeState::default())
+        .manage(BatchJobState::default())
+        .manage(BookTreeCache::default())
+        .manage(ClipboardWatchState::default())
+        .manage(CommandHistory::default())
+        .manage(CommentaryCache::default())
+        .manage(ConfigSchemaCache::default())
+        .manage(CorpusIntegrityState::default())
+        .manage(FrequencyRankCache::default())
+        .manage(HighlightCache::default())
+        .manage(ImpersonationState::default())
+        .manage(InterlinearCache::default())
+        .manage(MorphologyCache::default())
+        .manage(OpenRequestQueue::default())
+        .manage(PermissionsCache::default())
+        .manage(PresentationState::default())
+        .manage(SearchFilterCache::default())
+        .manage(SimilarityCache::default())
+        .manage(SpeechState::default())
+        .manage(StrongsCache::default())
+        .manage(SuggestionCache::default())
+        .manage(WebSocketRelays::default())
+        .manage(WebhookListenerState::default())
+        .invoke_handler(commands::crash_reports::track_commands(
+            tauri::generate_handler![
+                get_auth_token,
+                set_auth_token,
+                delete_auth_token,
+                list_auth_tokens_from_engine,
+                revoke_auth_token_by_id,
+                set_token_description,
+                get_token_description,
+                check_token_permissions,
+                impersonate_user,
+                end_impersonation,
+                check_engine_running,
+                start_engine_safe_mode,
+                get_engine_command_hint,
+                get_engine_config_schema,
+                get_engine_config,
+                set_engine_config,
+                get_cors_config,
+                set_cors_config,
+                test_cors_preflight,
+                generate_openapi_spec,
+                get_cached_openapi_spec,
+                openapi_spec_age_seconds,
+                submit_search_query,
+                get_search_suggestions,
+                get_advanced_search_filters,
+                get_search_history,
+                clear_search_history,
+                record_search_execution,
+                save_search_result,
+                list_saved_search_results,
+                delete_saved_search_result,
+                export_saved_results,
+                create_batch_job,
+                start_batch_job,
+                resume_batch_job,
+                pause_batch_job,
+                cancel_batch_job,
+                get_batch_job,
+                list_batch_jobs,
+                retry_failed_items,
+                import_passage_list,
+                save_passage_list,
+                list_passage_lists,
+                create_reading_plan,
+                get_reading_plan_progress,
+                mark_reading_complete,
+                get_today_reading,
+                export_reading_plans,
+                get_verse_of_the_day,
+                refresh_verse_of_the_day,
+                set_verse_of_the_day_settings,
+                get_app_start_state,
+                get_pronunciation,
+                prefetch_pronunciations,
+                load_translation_strings,
+                transliterate,
+                get_hardware_acceleration_status,
+                disable_hardware_acceleration,
+                enable_hardware_acceleration,
+                normalize_greek_input,
+                get_accessibility_settings,
+                set_accessibility_settings,
+                search_local,
+                rebuild_search_index,
+                get_crash_reports,
+                send_crash_report,
+                set_crash_reporting,
+                add_tag,
+                remove_tag,
+                list_tags,
+                rename_tag,
+                find_by_tag,
+                set_pre_shutdown_delay_ms,
+                compute_word_frequencies,
+                export_word_frequencies,
+                get_process_count,
+                kill_all_engine_processes,
+                export_study_document,
+                get_engine_thread_count,
+                set_engine_thread_limit,
+                set_personal_gloss,
+                get_personal_gloss,
+                list_personal_glossary,
+                delete_personal_gloss,
+                export_personal_glossary,
+                import_personal_glossary,
+                get_available_themes,
+                apply_custom_theme,
+                import_theme,
+                reset_to_default_theme,
+                parse_reference,
+                format_citation,
+                get_font_list,
+                install_bundled_font,
+                list_citation_styles,
+                list_annotations,
+                create_annotation,
+                update_annotation,
+                delete_annotation,
+                export_annotations,
+                import_annotations,
+                get_engine_startup_options,
+                grpc_health_check,
+                list_available_corpora,
+                list_installed_corpora,
+                download_corpus,
+                remove_corpus,
+                verify_corpus,
+                repair_corpus,
+                get_corpus_integrity_summary,
+                emit_custom_event,
+                get_command_history,
+                lookup_lemma,
+                lookup_lemma_offline,
+                search_lemmas_offline,
+                lookup_strongs,
+                strongs_for_lemma,
+                start_webhook_listener,
+                stop_webhook_listener,
+                proxy_engine_websocket,
+                send_websocket_message,
+                close_websocket,
+                export_osis,
+                export_markdown,
+                export_html,
+                export_docx,
+                export_interlinear_table,
+                export_flashcards,
+                export_usfm,
+                get_engine_installed_extensions,
+                reload_extension,
+                unload_extension,
+                list_monitors,
+                enter_presentation_mode,
+                exit_presentation_mode,
+                get_presentation_state,
+                translate_passage,
+                get_cached_passage,
+                list_cached_passages,
+                list_translation_runs,
+                compare_translations,
+                get_morphology,
+                prefetch_morphology,
+                get_morphology_cache_stats,
+                clear_morphology_cache,
+                set_passage_note,
+                share_passage,
+                set_clipboard_watch,
+                get_clipboard_watch,
+                start_clipboard_monitor,
+                stop_clipboard_monitor,
+                export_parallel_text,
+                detect_greek_in_text,
+                export_history,
+                import_history,
+                split_text_into_sentences,
+                get_red_letter_verses,
+                get_text_statistics,
+                diff_text_passages,
+                get_license_info,
+                save_project,
+                open_project,
+                align_parallel_texts,
+                speak_text,
+                stop_speaking,
+                list_tts_voices,
+                validate_scripture_reference,
+                parse_reference_range,
+                import_local_corpus,
+                get_strongs_entry,
+                search_strongs_by_english,
+                chunk_passage,
+                get_word_frequency_rank,
+                get_words_by_frequency_range,
+                set_offline_mode,
+                get_offline_mode,
+                get_offline_queue,
+                generate_interlinear_view,
+                export_interlinear_to_html,
+                get_text_commentary,
+                list_available_commentaries,
+                get_backend_log_path,
+                get_backend_log_tail,
+                get_bible_book_tree,
+                set_backend_log_level,
+                get_backend_log_level,
+                get_parallel_versions,
+                list_available_versions,
+                get_telemetry_preview,
+                set_telemetry_enabled,
+                set_telemetry_endpoint,
+                purge_telemetry,
+                highlight_text_spans,
+                get_pending_crash_reports,
+                discard_crash_report,
+                compute_textual_similarity,
+                generate_flashcard_deck,
+                export_flashcard_deck_to_anki,
+                choose_save_path,
+                get_engine_audit_log,
+                export_engine_audit_log,
+                create_user,
+                delete_user,
+                list_users,
+                update_user_role,
+                set_engine_rate_limit,
+                get_engine_rate_limits,
+                get_rate_limit_status,
+                get_system_accessibility_prefs,
+                get_effective_theme,
+                take_open_requests,
+                get_available_locales,
+                get_locale,
+                set_locale,
+                get_badge_breakdown,
+            ],
+        ))
+        .register_uri_scheme_protocol(commands::pronunciation::ASSET_SCHEME, |ctx, request| {
+            commands::pronunciation::serve_asset(ctx.app_handle(), &request)
+        })
+        .on_page_load(|webview, payload| {
+            if payload.event() == PageLoadEvent::Finished {
+                commands::themes::reapply_theme(webview);
+            }
+        })
+        .setup(move |app| {
+            commands::crash_reports::init_crash_reporting(app.handle());
+            commands::offline::init_offline_mode(app.handle());
+            commands::telemetry::init_telemetry(app.handle());
+            commands::shutdown::restore_window_state(app.handle());
+            commands::shutdown::register_shutdown_handler(app.handle());
+            commands::projects::register_project_drop(app.handle());
+            commands::i18n::init_locale(app.handle());
+            if let Some(request) = args.open {
+                queue_open_request(app.handle(), request);
+            }
+            commands::accessibility::start_accessibility_watcher(app.handle());
+            commands::clipboard_watch::init_clipboard_watch(app.handle());
+            commands::reading_plans::start_reading_reminders(app.handle());
+
+            #[cfg(debug_assertions)]
+            {
+                // Open devtools in debug builds
+                if let Some(w) = app.get_webview_window("main") {
+                    w.open_devtools();
+                }
+            }
+            Ok(())
+        })
+        .build(context)
+        .expect("error while building tauri application");
+
+    // The panic hook has written the record by the time this catches the
+    // unwind. Panics inside platform callbacks abort before reaching here.
+    let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+        app.run(|app, event| match event {
+            RunEvent::Exit => commands::crash_reports::mark_clean_shutdown(app),
+            // macOS delivers opened files (file associations) as events
+            #[cfg(any(target_os = "macos", target_os = "ios"))]
+            RunEvent::Opened { urls } => {
+                let paths = urls.iter().filter_map(|url| url.to_file_path().ok());
+                for path in paths.filter(|p| commands::projects::is_project_file(p)) {
+                    queue_open_request(app, commands::OpenRequest::Project { path });
+                }
+            }
+            _ => {}
+        });
+    }));
+    if result.is_err() {
+        tracing::error!("The app stopped after a panic");
+        std::process::exit(101);
+    }
+}
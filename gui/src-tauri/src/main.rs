@@ -1,45 +1,22 @@
 //! Red Letters Desktop GUI - Tauri backend entry point.
 //!
-//! This is a minimal Tauri app that provides:
-//! - Keychain access for auth tokens (ADR-005)
-//! - Engine process management helpers
-//!
-//! The actual API communication happens in the React frontend.
+//! The app itself is built in the library crate (`redletters_gui_lib::run`)
+//! so commands are compiled once and can be exercised from tests.
 
 #![cfg_attr(
     all(not(debug_assertions), target_os = "windows"),
     windows_subsystem = "windows"
 )]
 
-mod commands;
-
-use commands::{
-    check_engine_running, delete_auth_token, get_auth_token, get_engine_command_hint,
-    set_auth_token, start_engine_safe_mode,
-};
-use tauri::Manager;
+use redletters_gui_lib::cli;
 
 fn main() {
-    tauri::Builder::default()
-        .plugin(tauri_plugin_shell::init())
-        .invoke_handler(tauri::generate_handler![
-            get_auth_token,
-            set_auth_token,
-            delete_auth_token,
-            check_engine_running,
-            start_engine_safe_mode,
-            get_engine_command_hint,
-        ])
-        .setup(|app| {
-            #[cfg(debug_assertions)]
-            {
-                // Open devtools in debug builds
-                if let Some(w) = app.get_webview_window("main") {
-                    w.open_devtools();
-                }
-            }
-            Ok(())
-        })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let args = cli::parse_args(std::env::args().skip(1), &cwd);
+    if args.help {
+        println!("{}", cli::USAGE);
+        return;
+    }
+
+    redletters_gui_lib::run(args);
 }
@@ -0,0 +1,104 @@
+//! In-memory keychain for tests.
+//!
+//! `keyring`'s own mock keeps each entry's password to itself, so a token
+//! set through one `Entry` can't be read through another. This backend
+//! keeps every password in one map keyed by service and account, like the
+//! OS keychain does.
+
+use keyring::credential::{Credential, CredentialApi, CredentialBuilderApi};
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::sync::{Mutex, Once};
+
+static STORE: Mutex<BTreeMap<(String, String), String>> = Mutex::new(BTreeMap::new());
+
+#[derive(Debug)]
+struct MockCredential {
+    service: String,
+    account: String,
+}
+
+impl MockCredential {
+    fn key(&self) -> (String, String) {
+        (self.service.clone(), self.account.clone())
+    }
+}
+
+impl CredentialApi for MockCredential {
+    fn set_password(&self, password: &str) -> keyring::Result<()> {
+        STORE
+            .lock()
+            .unwrap()
+            .insert(self.key(), password.to_string());
+        Ok(())
+    }
+
+    fn get_password(&self) -> keyring::Result<String> {
+        STORE
+            .lock()
+            .unwrap()
+            .get(&self.key())
+            .cloned()
+            .ok_or(keyring::Error::NoEntry)
+    }
+
+    fn delete_password(&self) -> keyring::Result<()> {
+        STORE
+            .lock()
+            .unwrap()
+            .remove(&self.key())
+            .map(|_| ())
+            .ok_or(keyring::Error::NoEntry)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+struct MockCredentialBuilder;
+
+impl CredentialBuilderApi for MockCredentialBuilder {
+    fn build(
+        &self,
+        _target: Option<&str>,
+        service: &str,
+        account: &str,
+    ) -> keyring::Result<Box<Credential>> {
+        Ok(Box::new(MockCredential {
+            service: service.to_string(),
+            account: account.to_string(),
+        }))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Make this the `keyring` backend for the rest of the test process.
+pub fn install() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| keyring::set_default_credential_builder(Box::new(MockCredentialBuilder)));
+}
+
+pub fn clear() {
+    STORE.lock().unwrap().clear();
+}
+
+/// Store a password directly, e.g. a malformed token `set_auth_token`
+/// would refuse.
+pub fn set(service: &str, account: &str, password: &str) {
+    STORE.lock().unwrap().insert(
+        (service.to_string(), account.to_string()),
+        password.to_string(),
+    );
+}
+
+pub fn get(service: &str, account: &str) -> Option<String> {
+    STORE
+        .lock()
+        .unwrap()
+        .get(&(service.to_string(), account.to_string()))
+        .cloned()
+}
@@ -0,0 +1,277 @@
+//! A stand-in engine for tests.
+//!
+//! Serves canned responses for `/health`, `/v1/engine/status` (version),
+//! `/translate`, `/v1/lexicon/lookup` and `/v1/engine/shutdown` on a free
+//! local port. It runs on its own thread and runtime, so it outlives the
+//! runtime a test happens to use. Any route can be given another response
+//! with `respond`, and `fail_next` makes the next requests to a path fail
+//! with an error status. Every request is recorded.
+
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{HeaderMap, Method, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use axum::{Json, Router};
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use tokio::sync::Notify;
+
+/// Version reported by `/v1/engine/status`
+pub const MOCK_VERSION: &str = "0.0.0-mock";
+/// Answered, then the engine stops listening
+const SHUTDOWN_PATH: &str = "/v1/engine/shutdown";
+
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    pub authorization: Option<String>,
+    pub request_id: Option<String>,
+    /// `Null` when the body is empty or not JSON
+    pub body: Value,
+}
+
+#[derive(Default)]
+struct MockState {
+    /// (status, JSON body) by (method, path)
+    responses: HashMap<(String, String), (u16, Value)>,
+    /// Error statuses still to be injected, by path
+    failures: HashMap<String, VecDeque<u16>>,
+    requests: Vec<RecordedRequest>,
+}
+
+#[derive(Clone)]
+struct Shared {
+    state: Arc<Mutex<MockState>>,
+    stop: Arc<Notify>,
+}
+
+pub struct MockEngine {
+    port: u16,
+    shared: Shared,
+    thread: Option<JoinHandle<()>>,
+}
+
+fn default_responses() -> HashMap<(String, String), (u16, Value)> {
+    let route = |method: &str, path: &str| (method.to_string(), path.to_string());
+    HashMap::from([
+        (route("GET", "/health"), (200, json!({"status": "ok"}))),
+        (
+            route("GET", "/v1/engine/status"),
+            (200, json!({"status": "running", "version": MOCK_VERSION})),
+        ),
+        (
+            route("POST", "/translate"),
+            (
+                200,
+                json!({
+                    "response_type": "translation",
+                    "reference": "John 1:1",
+                    "normalized_ref": "John 1:1",
+                    "sblgnt_text": "Ἐν ἀρχῇ ἦν ὁ λόγος",
+                    "translation_text": "In the beginning was the Word",
+                    "verse_blocks": [{
+                        "verse_id": "John.1.1",
+                        "sblgnt_text": "Ἐν ἀρχῇ ἦν ὁ λόγος",
+                        "translation_text": "In the beginning was the Word"
+                    }]
+                }),
+            ),
+        ),
+        (
+            route("GET", "/v1/lexicon/lookup"),
+            (
+                200,
+                json!({
+                    "lemma": "λόγος",
+                    "glosses": ["word", "message"],
+                    "morphology": "noun, masc., 2nd decl.",
+                    "strongs": "G3056"
+                }),
+            ),
+        ),
+        (
+            route("POST", SHUTDOWN_PATH),
+            (202, json!({"status": "shutting_down"})),
+        ),
+    ])
+}
+
+fn status_code(status: u16) -> StatusCode {
+    StatusCode::from_u16(status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+async fn handle(
+    State(shared): State<Shared>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let path = uri.path().to_string();
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+    };
+    let (failure, response) = {
+        let mut state = shared.state.lock().unwrap();
+        state.requests.push(RecordedRequest {
+            method: method.to_string(),
+            path: path.clone(),
+            authorization: header("authorization"),
+            request_id: header("x-request-id"),
+            body: serde_json::from_slice(&body).unwrap_or(Value::Null),
+        });
+        let failure = state.failures.get_mut(&path).and_then(VecDeque::pop_front);
+        let response = state
+            .responses
+            .get(&(method.to_string(), path.clone()))
+            .cloned();
+        (failure, response)
+    };
+
+    if let Some(status) = failure {
+        return (status_code(status), "injected failure").into_response();
+    }
+    if method == Method::POST && path == SHUTDOWN_PATH {
+        shared.stop.notify_one();
+    }
+    match response {
+        Some((status, body)) => (status_code(status), Json(body)).into_response(),
+        None => (StatusCode::NOT_FOUND, "no such route").into_response(),
+    }
+}
+
+impl MockEngine {
+    /// Start serving on a free port; the port accepts connections as soon
+    /// as this returns.
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind mock engine");
+        listener.set_nonblocking(true).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let shared = Shared {
+            state: Arc::new(Mutex::new(MockState {
+                responses: default_responses(),
+                ..MockState::default()
+            })),
+            stop: Arc::new(Notify::new()),
+        };
+
+        let server = shared.clone();
+        let thread = thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .expect("failed to build mock engine runtime");
+            runtime.block_on(async move {
+                let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+                let stop = server.stop.clone();
+                let app = Router::new().fallback(handle).with_state(server);
+                let _ = axum::serve(listener, app)
+                    .with_graceful_shutdown(async move { stop.notified().await })
+                    .await;
+            });
+        });
+
+        Self {
+            port,
+            shared,
+            thread: Some(thread),
+        }
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Answer `method path` with `status` and a JSON body from now on.
+    pub fn respond(&self, method: &str, path: &str, status: u16, body: Value) {
+        self.shared
+            .state
+            .lock()
+            .unwrap()
+            .responses
+            .insert((method.to_string(), path.to_string()), (status, body));
+    }
+
+    /// Answer the next requests to `path` with these statuses, one per
+    /// request, before going back to the normal response.
+    pub fn fail_next(&self, path: &str, statuses: impl IntoIterator<Item = u16>) {
+        self.shared
+            .state
+            .lock()
+            .unwrap()
+            .failures
+            .entry(path.to_string())
+            .or_default()
+            .extend(statuses);
+    }
+
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.shared.state.lock().unwrap().requests.clone()
+    }
+
+    /// Requests made to `path` so far, whatever their method.
+    pub fn request_count(&self, path: &str) -> usize {
+        self.shared
+            .state
+            .lock()
+            .unwrap()
+            .requests
+            .iter()
+            .filter(|r| r.path == path)
+            .count()
+    }
+
+    /// Stop listening, as a crashed or shut down engine would. Requests
+    /// made afterwards fail to connect.
+    pub fn stop(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            self.shared.stop.notify_one();
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for MockEngine {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Something other than the engine listening on a port: a web server that
+/// answers every request with 404, like a dev server left running. The
+/// listener lives until the test process exits.
+pub struct ForeignListener {
+    port: u16,
+}
+
+impl ForeignListener {
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind listener");
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else {
+                    continue;
+                };
+                let mut request = [0u8; 4096];
+                let _ = stream.read(&mut request);
+                let _ = stream.write_all(
+                    b"HTTP/1.1 404 Not Found\r\nContent-Length: 9\r\nConnection: close\r\n\r\nNot Found",
+                );
+            }
+        });
+        Self { port }
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+}
@@ -0,0 +1,125 @@
+//! Shared fixtures for tests that go through the engine, the keychain or
+//! the filesystem.
+//!
+//! - [`mock_engine`]: an HTTP engine on a free local port with
+//!   programmable responses and failure injection
+//! - [`keychain`]: an in-memory keychain installed as the `keyring` backend
+//! - [`passage`]: a builder for cached passages
+//! - [`TestEnv`]: a temp home directory for the fallback token, settings,
+//!   engine data and caches
+//! - [`proptest_config`]: the case budget of property tests
+//! - [`pdf_fonts`]: the bundled fonts PDF exports are set in
+//!
+//! Environment variables and the keychain are process-wide, so a `TestEnv`
+//! holds a lock for as long as it lives: tests using one run one at a time.
+
+pub mod keychain;
+pub mod mock_engine;
+pub mod passage;
+
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use tempfile::TempDir;
+
+use crate::commands::pdf::{PdfFonts, FONT_RESOURCES};
+
+/// Variables pointed into the temp dir: `dirs` and the engine data root
+/// resolve through them on Linux and macOS
+const ENV_VARS: &[(&str, &str)] = &[
+    ("HOME", ""),
+    ("XDG_CONFIG_HOME", ".config"),
+    ("XDG_DATA_HOME", ".local/share"),
+    ("XDG_CACHE_HOME", ".cache"),
+    ("REDLETTERS_DATA_ROOT", ".redletters/data"),
+];
+
+/// Property test cases per run when `PROPTEST_CASES` isn't set
+const DEFAULT_PROPTEST_CASES: u32 = 64;
+
+static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+/// Few enough cases to keep CI fast; set `PROPTEST_CASES` to go deeper
+/// locally, e.g. `PROPTEST_CASES=20000 cargo test prop_`. Failures aren't
+/// persisted: a counterexample becomes an explicit case in the module's
+/// tests.
+pub fn proptest_config() -> proptest::test_runner::Config {
+    let cases = std::env::var("PROPTEST_CASES")
+        .ok()
+        .and_then(|cases| cases.parse().ok())
+        .unwrap_or(DEFAULT_PROPTEST_CASES);
+    proptest::test_runner::Config {
+        cases,
+        failure_persistence: None,
+        ..proptest::test_runner::Config::default()
+    }
+}
+
+/// The fonts `pdf` would find among the app's resources.
+pub fn pdf_fonts() -> PdfFonts {
+    let read = |resource| {
+        fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join(resource)).expect("bundled font")
+    };
+    PdfFonts::new(read(FONT_RESOURCES[0]), read(FONT_RESOURCES[1]))
+}
+
+/// A temp home directory and an empty mock keychain, restored on drop.
+pub struct TestEnv {
+    dir: TempDir,
+    saved: Vec<(&'static str, Option<OsString>)>,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl TestEnv {
+    pub fn new() -> Self {
+        // A failed test mustn't fail every test after it
+        let lock = ENV_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let saved = ENV_VARS
+            .iter()
+            .map(|(name, relative)| {
+                let previous = std::env::var_os(name);
+                std::env::set_var(name, dir.path().join(relative));
+                (*name, previous)
+            })
+            .collect();
+        keychain::install();
+        keychain::clear();
+        Self {
+            dir,
+            saved,
+            _lock: lock,
+        }
+    }
+
+    pub fn home(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// Write `token` to `~/.greek2english/.auth_token` with 0600 perms.
+    pub fn write_fallback_token(&self, token: &str) -> PathBuf {
+        let dir = self.home().join(".greek2english");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(".auth_token");
+        fs::write(&path, format!("{}\n", token)).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+        }
+        path
+    }
+}
+
+impl Drop for TestEnv {
+    fn drop(&mut self) {
+        for (name, previous) in self.saved.drain(..) {
+            match previous {
+                Some(value) => std::env::set_var(name, value),
+                None => std::env::remove_var(name),
+            }
+        }
+        keychain::clear();
+    }
+}
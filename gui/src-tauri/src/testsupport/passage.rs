@@ -0,0 +1,134 @@
+//! Cached passages for export, search and history tests.
+
+use serde_json::Value;
+
+use crate::commands::passages::{CachedPassage, CachedVerse};
+use crate::commands::red_letters::SpeakerSpan;
+
+/// Builds a `CachedPassage`; unset fields are empty, a "readable"
+/// "literal" translation with id "0011223344556677" made at time 0.
+pub struct PassageBuilder {
+    passage: CachedPassage,
+    red_letters: Option<Vec<String>>,
+}
+
+impl PassageBuilder {
+    pub fn new() -> Self {
+        Self {
+            passage: CachedPassage {
+                id: "0011223344556677".to_string(),
+                reference: String::new(),
+                normalized_ref: String::new(),
+                mode: "readable".to_string(),
+                translator: "literal".to_string(),
+                greek: String::new(),
+                translation: String::new(),
+                verses: Vec::new(),
+                note: None,
+                translated_at_ms: 0,
+                speakers: None,
+                engine_version: None,
+                response: Value::Null,
+            },
+            red_letters: None,
+        }
+    }
+
+    pub fn id(mut self, id: &str) -> Self {
+        self.passage.id = id.to_string();
+        self
+    }
+
+    /// Sets both the display and the normalized reference.
+    pub fn reference(mut self, reference: &str) -> Self {
+        self.passage.reference = reference.to_string();
+        self.passage.normalized_ref = reference.to_string();
+        self
+    }
+
+    pub fn mode(mut self, mode: &str) -> Self {
+        self.passage.mode = mode.to_string();
+        self
+    }
+
+    pub fn translator(mut self, translator: &str) -> Self {
+        self.passage.translator = translator.to_string();
+        self
+    }
+
+    pub fn greek(mut self, greek: &str) -> Self {
+        self.passage.greek = greek.to_string();
+        self
+    }
+
+    pub fn translation(mut self, translation: &str) -> Self {
+        self.passage.translation = translation.to_string();
+        self
+    }
+
+    pub fn verse(mut self, verse_id: &str, greek: &str, translation: &str) -> Self {
+        self.passage.verses.push(CachedVerse {
+            verse_id: verse_id.to_string(),
+            greek: greek.to_string(),
+            translation: translation.to_string(),
+        });
+        self
+    }
+
+    /// Adds `(verse_id, translation)` verses that all have the same Greek.
+    pub fn verses(self, verses: &[(&str, &str)], greek: &str) -> Self {
+        verses
+            .iter()
+            .fold(self, |builder, (id, text)| builder.verse(id, greek, text))
+    }
+
+    pub fn note(mut self, note: Option<&str>) -> Self {
+        self.passage.note = note.map(str::to_string);
+        self
+    }
+
+    pub fn translated_at_ms(mut self, translated_at_ms: u64) -> Self {
+        self.passage.translated_at_ms = translated_at_ms;
+        self
+    }
+
+    pub fn speakers(mut self, speakers: Vec<SpeakerSpan>) -> Self {
+        self.passage.speakers = Some(speakers);
+        self
+    }
+
+    pub fn engine_version(mut self, engine_version: Option<&str>) -> Self {
+        self.passage.engine_version = engine_version.map(str::to_string);
+        self
+    }
+
+    pub fn response(mut self, response: Value) -> Self {
+        self.passage.response = response;
+        self
+    }
+
+    /// Replaces the response with one verse block per verse, of which
+    /// those in `red` have a red-letter token.
+    pub fn red_letters(mut self, red: &[&str]) -> Self {
+        self.red_letters = Some(red.iter().map(|id| id.to_string()).collect());
+        self
+    }
+
+    pub fn build(mut self) -> CachedPassage {
+        if let Some(red) = self.red_letters {
+            let blocks: Vec<Value> = self
+                .passage
+                .verses
+                .iter()
+                .map(|verse| {
+                    serde_json::json!({
+                        "verse_id": verse.verse_id,
+                        "tokens": [{"is_red_letter": red.contains(&verse.verse_id)}],
+                    })
+                })
+                .collect();
+            self.passage.response = serde_json::json!({ "verse_blocks": blocks });
+        }
+        self.passage
+    }
+}
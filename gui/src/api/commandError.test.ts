@@ -0,0 +1,25 @@
+/**
+ * Tests for command error helpers.
+ */
+
+import { describe, it, expect } from "vitest";
+import { commandErrorMessage, isCommandError } from "./commandError";
+
+describe("commandErrorMessage", () => {
+  it("shows the envelope message", () => {
+    const err = {
+      code: "app/not_found",
+      message: "No batch job 7",
+      details: { detail: "No batch job 7" },
+      retryable: false,
+    };
+    expect(isCommandError(err)).toBe(true);
+    expect(commandErrorMessage(err)).toBe("No batch job 7");
+  });
+
+  it("falls back for other rejections", () => {
+    expect(isCommandError("boom")).toBe(false);
+    expect(commandErrorMessage("boom")).toBe("boom");
+    expect(commandErrorMessage(new Error("failed"))).toBe("failed");
+  });
+});
@@ -0,0 +1,32 @@
+/**
+ * Errors rejected by Tauri commands.
+ *
+ * Every command rejects with the envelope described in
+ * docs/error-codes.md; branch on `code`, display `message`.
+ */
+
+/** Error envelope sent by a failing Tauri command */
+export interface CommandError {
+  code: string;
+  message: string;
+  details: Record<string, string>;
+  retryable: boolean;
+}
+
+/** Whether a rejection value is a command error envelope */
+export function isCommandError(err: unknown): err is CommandError {
+  return (
+    typeof err === "object" &&
+    err !== null &&
+    typeof (err as CommandError).code === "string" &&
+    typeof (err as CommandError).message === "string"
+  );
+}
+
+/** Text to show for a rejected `invoke` */
+export function commandErrorMessage(err: unknown): string {
+  if (isCommandError(err) || err instanceof Error) {
+    return err.message;
+  }
+  return String(err);
+}
@@ -0,0 +1,349 @@
+/**
+ * EngineConfigForm - Editor for the engine's config.toml.
+ *
+ * The fields, their input types, limits and defaults all come from the
+ * engine's config schema, so new engine settings show up without a GUI
+ * change. Only edited fields are sent back.
+ */
+
+import { useEffect, useMemo, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { theme, commonStyles } from "../theme";
+import { commandErrorMessage } from "../api/commandError";
+import {
+  changedValues,
+  configFields,
+  fieldKey,
+  getValue,
+  setValue,
+  validateConfig,
+  withDefaults,
+  type ConfigField,
+  type ConfigValues,
+  type JsonSchema,
+} from "../utils/configSchema";
+
+interface EngineConfigFormProps {
+  /** Port of the engine being configured */
+  port: number;
+}
+
+const fieldStyle: React.CSSProperties = {
+  marginBottom: theme.spacing.lg,
+};
+
+const labelStyle: React.CSSProperties = {
+  display: "block",
+  marginBottom: theme.spacing.xs,
+  fontSize: theme.fontSize.base,
+  color: theme.colors.textSecondary,
+};
+
+const groupStyle: React.CSSProperties = {
+  ...commonStyles.label,
+  marginTop: theme.spacing.lg,
+};
+
+const hintStyle: React.CSSProperties = {
+  fontSize: theme.fontSize.sm,
+  color: theme.colors.textTertiary,
+  marginTop: theme.spacing.xs,
+};
+
+const errorStyle: React.CSSProperties = {
+  fontSize: theme.fontSize.sm,
+  color: theme.colors.error,
+  marginTop: theme.spacing.xs,
+};
+
+const inputStyle: React.CSSProperties = {
+  ...commonStyles.input,
+  padding: `${theme.spacing.sm} ${theme.spacing.md}`,
+  minWidth: "240px",
+};
+
+const buttonRowStyle: React.CSSProperties = {
+  display: "flex",
+  alignItems: "center",
+  gap: theme.spacing.sm,
+};
+
+/** Number input text as a config value; bad text is kept for validation */
+function parseNumber(text: string): unknown {
+  if (text.trim() === "") return undefined;
+  const num = Number(text);
+  return Number.isNaN(num) ? text : num;
+}
+
+function FieldInput({
+  field,
+  value,
+  invalid,
+  onChange,
+}: {
+  field: ConfigField;
+  value: unknown;
+  invalid: boolean;
+  onChange: (value: unknown) => void;
+}) {
+  const id = `engine-config-${fieldKey(field)}`;
+  const style = invalid
+    ? { ...inputStyle, borderColor: theme.colors.error }
+    : inputStyle;
+
+  switch (field.kind) {
+    case "boolean":
+      return (
+        <input
+          id={id}
+          type="checkbox"
+          checked={value === true}
+          onChange={(e) => onChange(e.target.checked)}
+        />
+      );
+    case "select":
+      return (
+        <select
+          id={id}
+          value={value === undefined ? "" : String(value)}
+          onChange={(e) => onChange(e.target.value || undefined)}
+          style={style}
+        >
+          {!field.required && <option value="">(engine default)</option>}
+          {field.options.map((option) => (
+            <option key={option} value={option}>
+              {option}
+            </option>
+          ))}
+        </select>
+      );
+    case "multiselect": {
+      const selected = Array.isArray(value) ? value.map(String) : [];
+      return (
+        <div
+          id={id}
+          style={{ display: "flex", flexWrap: "wrap", gap: theme.spacing.md }}
+        >
+          {field.options.map((option) => (
+            <label
+              key={option}
+              style={{ fontSize: theme.fontSize.base, cursor: "pointer" }}
+            >
+              <input
+                type="checkbox"
+                checked={selected.includes(option)}
+                onChange={(e) =>
+                  onChange(
+                    e.target.checked
+                      ? [...selected, option]
+                      : selected.filter((s) => s !== option),
+                  )
+                }
+                style={{ marginRight: theme.spacing.xs }}
+              />
+              {option}
+            </label>
+          ))}
+        </div>
+      );
+    }
+    case "integer":
+    case "number":
+      return (
+        <input
+          id={id}
+          type="number"
+          value={value === undefined ? "" : String(value)}
+          min={field.schema.minimum}
+          max={field.schema.maximum}
+          step={field.kind === "integer" ? 1 : "any"}
+          onChange={(e) => onChange(parseNumber(e.target.value))}
+          style={style}
+        />
+      );
+    case "text":
+      return (
+        <input
+          id={id}
+          type="text"
+          value={typeof value === "string" ? value : ""}
+          onChange={(e) => onChange(e.target.value)}
+          style={style}
+        />
+      );
+  }
+}
+
+export function EngineConfigForm({ port }: EngineConfigFormProps) {
+  const [schema, setSchema] = useState<JsonSchema | null>(null);
+  const [original, setOriginal] = useState<ConfigValues>({});
+  const [values, setValues] = useState<ConfigValues>({});
+  const [errors, setErrors] = useState<Record<string, string>>({});
+  const [loadError, setLoadError] = useState<string | null>(null);
+  const [usingDefaults, setUsingDefaults] = useState(false);
+  const [status, setStatus] = useState<string | null>(null);
+  const [saving, setSaving] = useState(false);
+
+  useEffect(() => {
+    let cancelled = false;
+    setLoadError(null);
+    (async () => {
+      try {
+        const loaded = await invoke<JsonSchema>("get_engine_config_schema", {
+          port,
+        });
+        // Engines without a config endpoint still get the form, at defaults
+        const current = await invoke<ConfigValues>("get_engine_config", {
+          port,
+        }).catch(() => null);
+        if (cancelled) return;
+        const filled = withDefaults(loaded, current ?? {});
+        setSchema(loaded);
+        setOriginal(filled);
+        setValues(filled);
+        setUsingDefaults(current === null);
+        setErrors({});
+      } catch (err) {
+        if (!cancelled) setLoadError(commandErrorMessage(err));
+      }
+    })();
+    return () => {
+      cancelled = true;
+    };
+  }, [port]);
+
+  const fields = useMemo(() => (schema ? configFields(schema) : []), [schema]);
+
+  if (loadError) {
+    return <div style={errorStyle}>{loadError}</div>;
+  }
+  if (!schema) {
+    return <div style={hintStyle}>Loading engine configuration...</div>;
+  }
+
+  const handleChange = (field: ConfigField, value: unknown) => {
+    setValues((current) => setValue(current, field.path, value));
+    setStatus(null);
+    // A flagged field loses its error once it is edited
+    const key = fieldKey(field);
+    if (errors[key]) {
+      setErrors((current) => {
+        const rest = { ...current };
+        delete rest[key];
+        return rest;
+      });
+    }
+  };
+
+  const handleSave = async () => {
+    const invalid = validateConfig(fields, values);
+    setErrors(invalid);
+    if (Object.keys(invalid).length > 0) {
+      setStatus("Fix the highlighted fields first");
+      return;
+    }
+    const changes = changedValues(fields, original, values);
+    if (Object.keys(changes).length === 0) {
+      setStatus("No changes");
+      return;
+    }
+
+    setSaving(true);
+    try {
+      await invoke("set_engine_config", { port, changes });
+      setOriginal(values);
+      setUsingDefaults(false);
+      setStatus("Saved");
+    } catch (err) {
+      setStatus(commandErrorMessage(err));
+    } finally {
+      setSaving(false);
+    }
+  };
+
+  let lastGroup: string | undefined;
+
+  return (
+    <div>
+      {usingDefaults && (
+        <p style={{ ...hintStyle, marginBottom: theme.spacing.lg }}>
+          The engine did not report its current settings; the form shows the
+          defaults.
+        </p>
+      )}
+
+      {fields.map((field) => {
+        const key = fieldKey(field);
+        const heading = field.group !== lastGroup ? field.group : undefined;
+        lastGroup = field.group;
+        const defaultValue = field.schema.default;
+        return (
+          <div key={key}>
+            {heading && <div style={groupStyle}>{heading}</div>}
+            <div style={fieldStyle}>
+              <label htmlFor={`engine-config-${key}`} style={labelStyle}>
+                {field.label}
+                {field.required && " *"}
+              </label>
+              <FieldInput
+                field={field}
+                value={getValue(values, field.path)}
+                invalid={key in errors}
+                onChange={(value) => handleChange(field, value)}
+              />
+              {errors[key] && <div style={errorStyle}>{errors[key]}</div>}
+              {(field.description || defaultValue !== undefined) && (
+                <div style={hintStyle}>
+                  {field.description}
+                  {field.description && defaultValue !== undefined && " · "}
+                  {defaultValue !== undefined &&
+                    `Default: ${
+                      Array.isArray(defaultValue)
+                        ? defaultValue.join(", ")
+                        : String(defaultValue)
+                    }`}
+                </div>
+              )}
+            </div>
+          </div>
+        );
+      })}
+
+      <div style={buttonRowStyle}>
+        <button
+          onClick={handleSave}
+          disabled={saving}
+          style={{
+            ...commonStyles.buttonPrimary,
+            padding: "8px 16px",
+            cursor: saving ? "wait" : "pointer",
+            opacity: saving ? 0.6 : 1,
+          }}
+        >
+          {saving ? "Saving..." : "Save Configuration"}
+        </button>
+        <button
+          onClick={() => {
+            setValues(withDefaults(schema, {}));
+            setErrors({});
+            setStatus(null);
+          }}
+          style={{
+            padding: "8px 16px",
+            borderRadius: "4px",
+            border: "1px solid var(--rl-border-strong)",
+            backgroundColor: "transparent",
+            color: "var(--rl-text-muted)",
+            cursor: "pointer",
+            fontSize: "var(--rl-fs-base)",
+          }}
+        >
+          Reset to Defaults
+        </button>
+        {status && (
+          <span style={{ ...hintStyle, marginTop: 0 }}>{status}</span>
+        )}
+      </div>
+    </div>
+  );
+}
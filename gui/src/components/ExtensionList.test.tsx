@@ -0,0 +1,54 @@
+/**
+ * Tests for the extension list rows.
+ */
+
+import { describe, it, expect, vi } from "vitest";
+import { render, screen, fireEvent } from "@testing-library/react";
+import { ExtensionRow, type EngineExtension } from "./ExtensionList";
+
+describe("ExtensionRow", () => {
+  const extension: EngineExtension = {
+    id: "lxx-morph",
+    name: "LXX morphology",
+    version: "1.2.0",
+    enabled: true,
+    loaded_at_ms: 1_700_000_000_000,
+    error: null,
+  };
+
+  const renderRow = (overrides: Partial<EngineExtension> = {}) => {
+    const onReload = vi.fn();
+    const onUnload = vi.fn();
+    render(
+      <ExtensionRow
+        extension={{ ...extension, ...overrides }}
+        busy={false}
+        onReload={onReload}
+        onUnload={onUnload}
+      />,
+    );
+    return { onReload, onUnload };
+  };
+
+  it("shows no badge for a loaded extension", () => {
+    renderRow();
+    expect(screen.getByText("LXX morphology")).toBeInTheDocument();
+    expect(screen.queryByText(/Load error/)).not.toBeInTheDocument();
+  });
+
+  it("shows a warning badge with the load error", () => {
+    renderRow({ loaded_at_ms: null, error: "ImportError: no module lxx" });
+    const badge = screen.getByText(/Load error/);
+    expect(badge).toHaveAttribute("title", "ImportError: no module lxx");
+    expect(screen.getByText("Not loaded")).toBeInTheDocument();
+    expect(screen.getByText("Unload")).toBeDisabled();
+  });
+
+  it("calls the row actions", () => {
+    const { onReload, onUnload } = renderRow();
+    fireEvent.click(screen.getByText("Reload"));
+    fireEvent.click(screen.getByText("Unload"));
+    expect(onReload).toHaveBeenCalledTimes(1);
+    expect(onUnload).toHaveBeenCalledTimes(1);
+  });
+});
@@ -0,0 +1,197 @@
+/**
+ * ExtensionList - Engine extensions with reload/unload controls.
+ *
+ * Extensions that failed to load are still listed by the engine, with
+ * their load error; they get a warning badge carrying the error text.
+ * The list refreshes on `extension-state-changed` events.
+ */
+
+import { useCallback, useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { theme } from "../theme";
+import { commandErrorMessage } from "../api/commandError";
+
+/** An extension as returned by `get_engine_installed_extensions` */
+export interface EngineExtension {
+  id: string;
+  name: string;
+  version: string;
+  enabled: boolean;
+  loaded_at_ms: number | null;
+  error: string | null;
+}
+
+interface ExtensionListProps {
+  /** Port of the engine the extensions are loaded in */
+  port: number;
+}
+
+interface ExtensionRowProps {
+  extension: EngineExtension;
+  /** An action on this extension is in flight */
+  busy: boolean;
+  onReload: () => void;
+  onUnload: () => void;
+}
+
+/** Event emitted by the backend after a reload or unload */
+const EXTENSION_STATE_EVENT = "extension-state-changed";
+
+const rowStyle: React.CSSProperties = {
+  display: "flex",
+  alignItems: "center",
+  gap: theme.spacing.sm,
+  padding: `${theme.spacing.sm} 0`,
+  borderBottom: "1px solid var(--rl-border-subtle)",
+};
+
+const badgeStyle: React.CSSProperties = {
+  display: "inline-flex",
+  alignItems: "center",
+  padding: "2px 8px",
+  borderRadius: theme.borderRadius.full,
+  backgroundColor: theme.colors.warning,
+  color: "#1f2937",
+  fontSize: theme.fontSize.xs,
+  fontWeight: theme.fontWeight.semibold,
+  cursor: "help",
+};
+
+const metaStyle: React.CSSProperties = {
+  fontSize: theme.fontSize.sm,
+  color: theme.colors.textTertiary,
+};
+
+const actionButtonStyle: React.CSSProperties = {
+  padding: "4px 10px",
+  borderRadius: "4px",
+  border: "1px solid var(--rl-border-strong)",
+  backgroundColor: "transparent",
+  color: "var(--rl-text-muted)",
+  fontSize: "var(--rl-fs-sm)",
+};
+
+export function ExtensionRow({
+  extension,
+  busy,
+  onReload,
+  onUnload,
+}: ExtensionRowProps) {
+  const loadedAt = extension.loaded_at_ms;
+  const loaded = loadedAt !== null && !extension.error;
+  const buttonStyle = {
+    ...actionButtonStyle,
+    cursor: busy ? "wait" : "pointer",
+    opacity: busy ? 0.6 : 1,
+  };
+
+  return (
+    <div style={rowStyle}>
+      <div style={{ flex: 1 }}>
+        <div style={{ fontSize: theme.fontSize.base }}>
+          {extension.name}{" "}
+          <span style={metaStyle}>
+            {extension.id} {extension.version}
+          </span>
+          {extension.error && (
+            <span
+              title={extension.error}
+              style={{ ...badgeStyle, marginLeft: theme.spacing.sm }}
+            >
+              ⚠ Load error
+            </span>
+          )}
+        </div>
+        <div style={metaStyle}>
+          {!extension.enabled
+            ? "Disabled"
+            : loaded
+              ? `Loaded ${new Date(loadedAt).toLocaleString()}`
+              : "Not loaded"}
+        </div>
+      </div>
+      <button onClick={onReload} disabled={busy} style={buttonStyle}>
+        Reload
+      </button>
+      <button onClick={onUnload} disabled={busy || !loaded} style={buttonStyle}>
+        Unload
+      </button>
+    </div>
+  );
+}
+
+export function ExtensionList({ port }: ExtensionListProps) {
+  const [extensions, setExtensions] = useState<EngineExtension[] | null>(null);
+  const [error, setError] = useState<string | null>(null);
+  const [busyId, setBusyId] = useState<string | null>(null);
+
+  const refresh = useCallback(async () => {
+    try {
+      setExtensions(
+        await invoke<EngineExtension[]>("get_engine_installed_extensions", {
+          port,
+        }),
+      );
+      setError(null);
+    } catch (err) {
+      setError(commandErrorMessage(err));
+    }
+  }, [port]);
+
+  useEffect(() => {
+    refresh();
+    const unlisten = listen(EXTENSION_STATE_EVENT, () => {
+      refresh();
+    });
+    return () => {
+      unlisten.then((stop) => stop());
+    };
+  }, [refresh]);
+
+  const runAction = async (command: string, id: string) => {
+    setBusyId(id);
+    setError(null);
+    try {
+      // The list refreshes from the event the command emits
+      await invoke(command, { port, id });
+    } catch (err) {
+      setError(commandErrorMessage(err));
+      // A failed reload leaves the extension's load error to show
+      refresh();
+    } finally {
+      setBusyId(null);
+    }
+  };
+
+  return (
+    <div>
+      {error && (
+        <div
+          style={{
+            fontSize: theme.fontSize.sm,
+            color: theme.colors.error,
+            marginBottom: theme.spacing.sm,
+          }}
+        >
+          {error}
+        </div>
+      )}
+      {extensions === null ? (
+        !error && <div style={metaStyle}>Loading extensions...</div>
+      ) : extensions.length === 0 ? (
+        <div style={metaStyle}>No extensions installed</div>
+      ) : (
+        extensions.map((extension) => (
+          <ExtensionRow
+            key={extension.id}
+            extension={extension}
+            busy={busyId === extension.id}
+            onReload={() => runAction("reload_extension", extension.id)}
+            onUnload={() => runAction("unload_extension", extension.id)}
+          />
+        ))
+      )}
+    </div>
+  );
+}
@@ -0,0 +1,21 @@
+/**
+ * Tests for the offline queue labels.
+ */
+
+import { describe, it, expect } from "vitest";
+import { describeMutation } from "./OfflineModeToggle";
+
+describe("describeMutation", () => {
+  it("names the command and the corpus", () => {
+    expect(
+      describeMutation({
+        command: "download_corpus",
+        id: "sblgnt",
+        manifest_url: null,
+      }),
+    ).toBe("Download corpus sblgnt");
+    expect(describeMutation({ command: "repair_corpus", id: "lxx" })).toBe(
+      "Repair corpus lxx",
+    );
+  });
+});
@@ -0,0 +1,149 @@
+/**
+ * OfflineModeToggle - Switch for offline mode and its queued changes.
+ *
+ * While offline mode is on, remote manifests and downloads are skipped
+ * and corpus downloads and repairs are queued. Turning it off with the
+ * engine running makes the backend replay the queue; each replayed change
+ * arrives as an `offline-mutation-replayed` event.
+ */
+
+import { useCallback, useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { theme } from "../theme";
+import { commandErrorMessage } from "../api/commandError";
+
+/** A change queued by the backend while offline, from `get_offline_queue` */
+export type QueuedMutation =
+  | { command: "download_corpus"; id: string; manifest_url?: string | null }
+  | { command: "repair_corpus"; id: string };
+
+interface OfflineModeChanged {
+  enabled: boolean;
+  replay_queue: boolean;
+}
+
+interface ReplayedMutation {
+  mutation: QueuedMutation;
+  error: string | null;
+}
+
+interface OfflineModeToggleProps {
+  /** Port of the local engine, checked before the queue is replayed */
+  port: number;
+}
+
+const CHANGED_EVENT = "offline-mode-changed";
+const REPLAYED_EVENT = "offline-mutation-replayed";
+
+const hintStyle: React.CSSProperties = {
+  fontSize: theme.fontSize.sm,
+  color: theme.colors.textTertiary,
+  marginTop: theme.spacing.xs,
+};
+
+export function describeMutation(mutation: QueuedMutation): string {
+  switch (mutation.command) {
+    case "download_corpus":
+      return `Download corpus ${mutation.id}`;
+    case "repair_corpus":
+      return `Repair corpus ${mutation.id}`;
+  }
+}
+
+export function OfflineModeToggle({ port }: OfflineModeToggleProps) {
+  const [enabled, setEnabled] = useState(false);
+  const [queue, setQueue] = useState<QueuedMutation[]>([]);
+  const [busy, setBusy] = useState(false);
+  const [status, setStatus] = useState<string | null>(null);
+
+  const refreshQueue = useCallback(async () => {
+    try {
+      setQueue(await invoke<QueuedMutation[]>("get_offline_queue"));
+    } catch (err) {
+      setStatus(commandErrorMessage(err));
+    }
+  }, []);
+
+  useEffect(() => {
+    invoke<boolean>("get_offline_mode")
+      .then(setEnabled)
+      .catch((err) => setStatus(commandErrorMessage(err)));
+    refreshQueue();
+
+    const unlistenChanged = listen<OfflineModeChanged>(CHANGED_EVENT, (e) => {
+      setEnabled(e.payload.enabled);
+      if (e.payload.replay_queue) {
+        setStatus("Sending queued changes...");
+      }
+    });
+    const unlistenReplayed = listen<ReplayedMutation>(REPLAYED_EVENT, (e) => {
+      const { mutation, error } = e.payload;
+      setStatus(
+        error
+          ? `${describeMutation(mutation)} failed: ${error}`
+          : `${describeMutation(mutation)} done`,
+      );
+      refreshQueue();
+    });
+    return () => {
+      unlistenChanged.then((stop) => stop());
+      unlistenReplayed.then((stop) => stop());
+    };
+  }, [refreshQueue]);
+
+  const handleToggle = async (next: boolean) => {
+    setBusy(true);
+    setStatus(null);
+    try {
+      await invoke("set_offline_mode", { enabled: next, port });
+      setEnabled(next);
+    } catch (err) {
+      setStatus(commandErrorMessage(err));
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  return (
+    <div>
+      <label
+        style={{
+          display: "flex",
+          alignItems: "center",
+          gap: theme.spacing.sm,
+          fontSize: theme.fontSize.base,
+          cursor: busy ? "wait" : "pointer",
+        }}
+      >
+        <input
+          type="checkbox"
+          checked={enabled}
+          disabled={busy}
+          onChange={(e) => handleToggle(e.target.checked)}
+        />
+        Offline mode
+      </label>
+      <p style={hintStyle}>
+        Only the local engine, the offline lexicon and the caches are used.
+        Corpus downloads and repairs are queued and sent when offline mode is
+        turned off.
+      </p>
+      {queue.length > 0 && (
+        <div style={{ marginTop: theme.spacing.sm }}>
+          <div style={{ fontSize: theme.fontSize.sm }}>
+            Waiting to be sent:
+          </div>
+          <ul style={{ ...hintStyle, paddingLeft: theme.spacing.lg }}>
+            {queue.map((mutation) => (
+              <li key={`${mutation.command}:${mutation.id}`}>
+                {describeMutation(mutation)}
+              </li>
+            ))}
+          </ul>
+        </div>
+      )}
+      {status && <div style={hintStyle}>{status}</div>}
+    </div>
+  );
+}
@@ -2,11 +2,23 @@
  * Settings screen - Engine configuration.
  */
 
-import { useState } from "react";
+import { useEffect, useState } from "react";
 import { useAppStore, selectSettings } from "../store";
 import type { EngineMode } from "../api/types";
 import { invoke } from "@tauri-apps/api/core";
+import { commandErrorMessage } from "../api/commandError";
 import { AUTH_TOKEN_KEY } from "../constants/storageKeys";
+import { EngineConfigForm } from "../components/EngineConfigForm";
+import { ExtensionList } from "../components/ExtensionList";
+import { OfflineModeToggle } from "../components/OfflineModeToggle";
+
+interface HardwareAccelStatus {
+  gpu_enabled: boolean;
+  renderer: string | null;
+  vendor: string | null;
+  webgl_version: string | null;
+  acceleration_disabled: boolean;
+}
 
 interface SettingsProps {
   engineMode?: EngineMode;
@@ -35,6 +47,21 @@ export function Settings({
   const [testing, setTesting] = useState(false);
   const [resetConfirm, setResetConfirm] = useState(false);
   const [safeStarting, setSafeStarting] = useState(false);
+  const [gpuStatus, setGpuStatus] = useState<HardwareAccelStatus | null>(null);
+  const [gpuError, setGpuError] = useState<string | null>(null);
+  const [restartingGpu, setRestartingGpu] = useState(false);
+  const [tokenDescription, setTokenDescription] = useState("");
+  const [descriptionStatus, setDescriptionStatus] = useState<string | null>(
+    null,
+  );
+
+  // The label lives next to the token in the keychain (desktop app only)
+  useEffect(() => {
+    if (!("__TAURI__" in window)) return;
+    invoke<string | null>("get_token_description")
+      .then((description) => setTokenDescription(description ?? ""))
+      .catch((err) => console.error("Failed to load token description:", err));
+  }, []);
 
   const handlePortChange = (value: string) => {
     setPort(value);
@@ -81,6 +108,43 @@ export function Settings({
     }
   };
 
+  const handleSaveDescription = async () => {
+    try {
+      await invoke("set_token_description", { description: tokenDescription });
+      setDescriptionStatus("Saved");
+    } catch (err) {
+      setDescriptionStatus(commandErrorMessage(err));
+    }
+  };
+
+  const handleCheckGpu = async () => {
+    setGpuError(null);
+    try {
+      setGpuStatus(
+        await invoke<HardwareAccelStatus>("get_hardware_acceleration_status"),
+      );
+    } catch (err) {
+      setGpuError(commandErrorMessage(err));
+    }
+  };
+
+  const gpuTurnedOff = gpuStatus?.acceleration_disabled ?? false;
+
+  const handleToggleGpu = async () => {
+    setRestartingGpu(true);
+    try {
+      // Relaunches the app on success
+      await invoke(
+        gpuTurnedOff
+          ? "enable_hardware_acceleration"
+          : "disable_hardware_acceleration",
+      );
+    } catch (err) {
+      setGpuError(commandErrorMessage(err));
+      setRestartingGpu(false);
+    }
+  };
+
   return (
     <div style={{ padding: "24px" }}>
       <h1 style={{ marginBottom: "24px" }}>Settings</h1>
@@ -208,6 +272,65 @@ export function Settings({
                 : "Not configured"}
             </span>
           </div>
+          {"__TAURI__" in window && (
+            <div
+              style={{
+                display: "flex",
+                alignItems: "center",
+                gap: "8px",
+                marginTop: "8px",
+              }}
+            >
+              <label
+                htmlFor="token-description"
+                style={{ fontSize: "var(--rl-fs-base)" }}
+              >
+                Description:
+              </label>
+              <input
+                id="token-description"
+                type="text"
+                value={tokenDescription}
+                placeholder="e.g. Laptop"
+                onChange={(e) => {
+                  setTokenDescription(e.target.value);
+                  setDescriptionStatus(null);
+                }}
+                style={{
+                  padding: "8px 12px",
+                  borderRadius: "4px",
+                  border: "1px solid var(--rl-border-strong)",
+                  backgroundColor: "var(--rl-bg-app)",
+                  color: "var(--rl-text)",
+                  fontSize: "var(--rl-fs-base)",
+                }}
+              />
+              <button
+                onClick={handleSaveDescription}
+                style={{
+                  padding: "6px 12px",
+                  borderRadius: "4px",
+                  border: "1px solid var(--rl-border-strong)",
+                  backgroundColor: "transparent",
+                  color: "var(--rl-text-muted)",
+                  cursor: "pointer",
+                  fontSize: "var(--rl-fs-base)",
+                }}
+              >
+                Save
+              </button>
+              {descriptionStatus && (
+                <span
+                  style={{
+                    fontSize: "var(--rl-fs-sm)",
+                    color: "var(--rl-text-dim)",
+                  }}
+                >
+                  {descriptionStatus}
+                </span>
+              )}
+            </div>
+          )}
           <p
             style={{
               fontSize: "var(--rl-fs-sm)",
@@ -313,6 +436,200 @@ export function Settings({
         </p>
       </section>
 
+      {/* Engine Configuration (desktop app only) */}
+      {"__TAURI__" in window && (
+        <section
+          style={{
+            padding: "16px",
+            backgroundColor: "var(--rl-bg-card)",
+            borderRadius: "8px",
+            marginBottom: "16px",
+            border: "1px solid var(--rl-border)",
+            borderTop: "1px solid var(--rl-border-subtle)",
+            boxShadow: "var(--rl-shadow-md)",
+          }}
+        >
+          <h2
+            style={{
+              fontSize: "var(--rl-fs-md)",
+              fontWeight: 600,
+              marginBottom: "16px",
+            }}
+          >
+            Engine Configuration
+          </h2>
+          <EngineConfigForm port={settings.enginePort} />
+        </section>
+      )}
+
+      {/* Extensions (desktop app only) */}
+      {"__TAURI__" in window && (
+        <section
+          style={{
+            padding: "16px",
+            backgroundColor: "var(--rl-bg-card)",
+            borderRadius: "8px",
+            marginBottom: "16px",
+            border: "1px solid var(--rl-border)",
+            borderTop: "1px solid var(--rl-border-subtle)",
+            boxShadow: "var(--rl-shadow-md)",
+          }}
+        >
+          <h2
+            style={{
+              fontSize: "var(--rl-fs-md)",
+              fontWeight: 600,
+              marginBottom: "16px",
+            }}
+          >
+            Extensions
+          </h2>
+          <ExtensionList port={settings.enginePort} />
+        </section>
+      )}
+
+      {/* Offline Mode (desktop app only) */}
+      {"__TAURI__" in window && (
+        <section
+          style={{
+            padding: "16px",
+            backgroundColor: "var(--rl-bg-card)",
+            borderRadius: "8px",
+            marginBottom: "16px",
+            border: "1px solid var(--rl-border)",
+            borderTop: "1px solid var(--rl-border-subtle)",
+            boxShadow: "var(--rl-shadow-md)",
+          }}
+        >
+          <h2
+            style={{
+              fontSize: "var(--rl-fs-md)",
+              fontWeight: 600,
+              marginBottom: "16px",
+            }}
+          >
+            Offline Mode
+          </h2>
+          <OfflineModeToggle port={settings.enginePort} />
+        </section>
+      )}
+
+      {/* Graphics */}
+      <section
+        style={{
+          padding: "16px",
+          backgroundColor: "var(--rl-bg-card)",
+          borderRadius: "8px",
+          marginBottom: "16px",
+          border: "1px solid var(--rl-border)",
+          borderTop: "1px solid var(--rl-border-subtle)",
+          boxShadow: "var(--rl-shadow-md)",
+        }}
+      >
+        <h2
+          style={{
+            fontSize: "var(--rl-fs-md)",
+            fontWeight: 600,
+            marginBottom: "16px",
+          }}
+        >
+          Graphics
+        </h2>
+
+        <div style={{ marginBottom: "16px" }}>
+          <div style={{ fontSize: "var(--rl-fs-base)", marginBottom: "4px" }}>
+            GPU Acceleration:{" "}
+            <span
+              style={{
+                fontWeight: 600,
+                color:
+                  gpuStatus === null
+                    ? "var(--rl-text-dim)"
+                    : gpuStatus.gpu_enabled
+                      ? "var(--rl-success)"
+                      : "var(--rl-warning)",
+              }}
+            >
+              {gpuStatus === null
+                ? "Not checked"
+                : gpuStatus.gpu_enabled
+                  ? "On"
+                  : "Off"}
+            </span>
+          </div>
+          {gpuStatus?.renderer && (
+            <div
+              style={{
+                fontSize: "var(--rl-fs-sm)",
+                color: "var(--rl-text-dim)",
+              }}
+            >
+              {gpuStatus.renderer}
+              {gpuStatus.webgl_version && ` (${gpuStatus.webgl_version})`}
+            </div>
+          )}
+          {gpuError && (
+            <div
+              style={{
+                fontSize: "var(--rl-fs-sm)",
+                color: "var(--rl-error)",
+                marginTop: "4px",
+              }}
+            >
+              {gpuError}
+            </div>
+          )}
+          <p
+            style={{
+              fontSize: "var(--rl-fs-sm)",
+              color: "var(--rl-text-dim)",
+              marginTop: "4px",
+            }}
+          >
+            If text flickers or windows draw incorrectly (common in virtual
+            machines), disabling the GPU usually fixes it. The app restarts.
+            To turn it back on, check the GPU and choose Enable GPU.
+          </p>
+        </div>
+
+        <div style={{ display: "flex", gap: "8px" }}>
+          <button
+            onClick={handleCheckGpu}
+            style={{
+              padding: "8px 16px",
+              borderRadius: "4px",
+              border: "1px solid var(--rl-border)",
+              backgroundColor: "transparent",
+              color: "var(--rl-text)",
+              cursor: "pointer",
+              fontSize: "var(--rl-fs-base)",
+            }}
+          >
+            Check GPU
+          </button>
+          <button
+            onClick={handleToggleGpu}
+            disabled={restartingGpu}
+            style={{
+              padding: "8px 16px",
+              borderRadius: "4px",
+              border: "1px solid var(--rl-warning)",
+              backgroundColor: "transparent",
+              color: "var(--rl-warning)",
+              cursor: restartingGpu ? "wait" : "pointer",
+              fontSize: "var(--rl-fs-base)",
+              opacity: restartingGpu ? 0.6 : 1,
+            }}
+          >
+            {restartingGpu
+              ? "Restarting..."
+              : gpuTurnedOff
+                ? "Enable GPU"
+                : "Disable GPU"}
+          </button>
+        </div>
+      </section>
+
       {/* Integrity Settings */}
       <section
         style={{
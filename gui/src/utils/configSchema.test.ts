@@ -0,0 +1,131 @@
+/**
+ * Unit tests for engine config schema utilities.
+ */
+
+import { describe, it, expect } from "vitest";
+import {
+  changedValues,
+  configFields,
+  fieldKey,
+  validateConfig,
+  withDefaults,
+  type JsonSchema,
+} from "./configSchema";
+
+const schema: JsonSchema = {
+  type: "object",
+  required: ["port"],
+  properties: {
+    port: {
+      type: "integer",
+      title: "Port",
+      minimum: 1024,
+      maximum: 65535,
+      default: 47200,
+    },
+    log_level: {
+      type: "string",
+      enum: ["debug", "info"],
+      default: "info",
+    },
+    safe_mode: { type: "boolean", default: false },
+    default_styles: {
+      type: "array",
+      items: { type: "string", enum: ["natural", "ultra-literal"] },
+      uniqueItems: true,
+    },
+    extra_paths: { type: "array", items: { type: "string" } },
+    ranking: {
+      type: "object",
+      title: "Ranking weights",
+      properties: {
+        sense_weight: {
+          type: "number",
+          minimum: 0,
+          maximum: 1,
+          default: 0.35,
+        },
+      },
+    },
+  },
+};
+
+describe("configFields", () => {
+  it("maps schema types to form fields", () => {
+    const fields = configFields(schema);
+    expect(fields.map(fieldKey)).toEqual([
+      "port",
+      "log_level",
+      "safe_mode",
+      "default_styles",
+      "ranking.sense_weight",
+    ]);
+    expect(fields.map((f) => f.kind)).toEqual([
+      "integer",
+      "select",
+      "boolean",
+      "multiselect",
+      "number",
+    ]);
+    expect(fields[0].required).toBe(true);
+    expect(fields[1].label).toBe("Log level");
+    expect(fields[1].options).toEqual(["debug", "info"]);
+    expect(fields[4].group).toBe("Ranking weights");
+  });
+});
+
+describe("withDefaults", () => {
+  it("fills only missing values", () => {
+    const config = withDefaults(schema, { port: 5000, ranking: {} });
+    expect(config).toEqual({
+      port: 5000,
+      log_level: "info",
+      safe_mode: false,
+      ranking: { sense_weight: 0.35 },
+    });
+  });
+});
+
+describe("validateConfig", () => {
+  const fields = configFields(schema);
+
+  it("accepts the defaults", () => {
+    expect(validateConfig(fields, withDefaults(schema, {}))).toEqual({});
+  });
+
+  it("reports each invalid field", () => {
+    const errors = validateConfig(fields, {
+      port: 80.5,
+      log_level: "verbose",
+      default_styles: ["natural", "natural"],
+      ranking: { sense_weight: 2 },
+    });
+    expect(errors).toEqual({
+      port: "Must be a whole number",
+      log_level: "Must be one of debug, info",
+      default_styles: "Choices must not repeat",
+      "ranking.sense_weight": "Must be at most 1",
+    });
+    expect(validateConfig(fields, {})).toEqual({ port: "Required" });
+    expect(validateConfig(fields, { port: "80" }).port).toBe(
+      "Must be a number",
+    );
+  });
+});
+
+describe("changedValues", () => {
+  it("keeps only edited fields, nested like the config", () => {
+    const fields = configFields(schema);
+    const original = withDefaults(schema, {});
+    const edited = {
+      ...original,
+      safe_mode: true,
+      ranking: { sense_weight: 0.5 },
+    };
+    expect(changedValues(fields, original, edited)).toEqual({
+      safe_mode: true,
+      ranking: { sense_weight: 0.5 },
+    });
+    expect(changedValues(fields, original, original)).toEqual({});
+  });
+});
@@ -0,0 +1,241 @@
+/**
+ * Engine config schema utilities for the settings form.
+ *
+ * Turns the JSON Schema returned by `get_engine_config_schema` into form
+ * fields, fills in default values and validates edits before they are
+ * sent to `set_engine_config`.
+ */
+
+/** The subset of JSON Schema the engine config schema uses */
+export interface JsonSchema {
+  type?: string;
+  title?: string;
+  description?: string;
+  enum?: unknown[];
+  default?: unknown;
+  minimum?: number;
+  maximum?: number;
+  minLength?: number;
+  maxLength?: number;
+  pattern?: string;
+  items?: JsonSchema;
+  uniqueItems?: boolean;
+  properties?: Record<string, JsonSchema>;
+  required?: string[];
+}
+
+export type ConfigValues = Record<string, unknown>;
+
+export type FieldKind =
+  | "text"
+  | "integer"
+  | "number"
+  | "boolean"
+  | "select"
+  | "multiselect";
+
+export interface ConfigField {
+  /** Keys from the top of the config down to this field */
+  path: string[];
+  label: string;
+  description?: string;
+  /** Title of the object the field is nested in */
+  group?: string;
+  kind: FieldKind;
+  /** Choices for select and multiselect fields */
+  options: string[];
+  required: boolean;
+  schema: JsonSchema;
+}
+
+/** "morph_fit_weight" -> "Morph fit weight" */
+function humanize(key: string): string {
+  const words = key.replace(/[_-]+/g, " ").trim();
+  return words.charAt(0).toUpperCase() + words.slice(1);
+}
+
+function fieldKind(schema: JsonSchema): FieldKind | null {
+  if (schema.enum) return "select";
+  switch (schema.type) {
+    case "string":
+      return "text";
+    case "integer":
+    case "number":
+    case "boolean":
+      return schema.type;
+    case "array":
+      return schema.items?.enum ? "multiselect" : null;
+    default:
+      return null;
+  }
+}
+
+/**
+ * Form fields for a config schema, in schema order. Nested objects are
+ * flattened into their fields; properties of other types are left out
+ * of the form and keep their current value.
+ */
+export function configFields(
+  schema: JsonSchema,
+  parent: string[] = [],
+  group?: string,
+): ConfigField[] {
+  const fields: ConfigField[] = [];
+  for (const [key, property] of Object.entries(schema.properties ?? {})) {
+    const path = [...parent, key];
+    if (property.type === "object") {
+      fields.push(
+        ...configFields(property, path, property.title ?? humanize(key)),
+      );
+      continue;
+    }
+    const kind = fieldKind(property);
+    if (!kind) continue;
+    fields.push({
+      path,
+      label: property.title ?? humanize(key),
+      description: property.description,
+      group,
+      kind,
+      options: (property.enum ?? property.items?.enum ?? []).map(String),
+      required: schema.required?.includes(key) ?? false,
+      schema: property,
+    });
+  }
+  return fields;
+}
+
+/** Key identifying a field, e.g. "ranking.sense_weight_weight" */
+export function fieldKey(field: ConfigField): string {
+  return field.path.join(".");
+}
+
+export function getValue(config: ConfigValues, path: string[]): unknown {
+  let value: unknown = config;
+  for (const key of path) {
+    if (typeof value !== "object" || value === null) return undefined;
+    value = (value as ConfigValues)[key];
+  }
+  return value;
+}
+
+/** Copy of `config` with the value at `path` replaced */
+export function setValue(
+  config: ConfigValues,
+  path: string[],
+  value: unknown,
+): ConfigValues {
+  const [key, ...rest] = path;
+  if (rest.length === 0) {
+    return { ...config, [key]: value };
+  }
+  const child = config[key];
+  const nested =
+    typeof child === "object" && child !== null ? (child as ConfigValues) : {};
+  return { ...config, [key]: setValue(nested, rest, value) };
+}
+
+/** `config` with the schema default filled in for every missing field */
+export function withDefaults(
+  schema: JsonSchema,
+  config: ConfigValues,
+): ConfigValues {
+  let filled = config;
+  for (const field of configFields(schema)) {
+    if (
+      getValue(filled, field.path) === undefined &&
+      field.schema.default !== undefined
+    ) {
+      filled = setValue(filled, field.path, field.schema.default);
+    }
+  }
+  return filled;
+}
+
+/** Why a field value is invalid, or null if it is valid */
+export function validateField(
+  field: ConfigField,
+  value: unknown,
+): string | null {
+  const { schema } = field;
+  if (value === undefined || value === "") {
+    return field.required ? "Required" : null;
+  }
+
+  switch (field.kind) {
+    case "integer":
+    case "number": {
+      if (typeof value !== "number" || !Number.isFinite(value)) {
+        return "Must be a number";
+      }
+      if (field.kind === "integer" && !Number.isInteger(value)) {
+        return "Must be a whole number";
+      }
+      if (schema.minimum !== undefined && value < schema.minimum) {
+        return `Must be at least ${schema.minimum}`;
+      }
+      if (schema.maximum !== undefined && value > schema.maximum) {
+        return `Must be at most ${schema.maximum}`;
+      }
+      return null;
+    }
+    case "boolean":
+      return typeof value === "boolean" ? null : "Must be on or off";
+    case "select":
+      return field.options.includes(String(value))
+        ? null
+        : `Must be one of ${field.options.join(", ")}`;
+    case "multiselect": {
+      if (!Array.isArray(value)) return "Must be a list";
+      const unknown = value.find((v) => !field.options.includes(String(v)));
+      if (unknown !== undefined) return `Unknown choice ${String(unknown)}`;
+      if (schema.uniqueItems && new Set(value).size !== value.length) {
+        return "Choices must not repeat";
+      }
+      return null;
+    }
+    case "text": {
+      if (typeof value !== "string") return "Must be text";
+      if (schema.minLength !== undefined && value.length < schema.minLength) {
+        return `Must be at least ${schema.minLength} characters`;
+      }
+      if (schema.maxLength !== undefined && value.length > schema.maxLength) {
+        return `Must be at most ${schema.maxLength} characters`;
+      }
+      if (schema.pattern && !new RegExp(schema.pattern, "u").test(value)) {
+        return "Has the wrong format";
+      }
+      return null;
+    }
+  }
+}
+
+/** Errors of the invalid fields, keyed by `fieldKey` */
+export function validateConfig(
+  fields: ConfigField[],
+  config: ConfigValues,
+): Record<string, string> {
+  const errors: Record<string, string> = {};
+  for (const field of fields) {
+    const error = validateField(field, getValue(config, field.path));
+    if (error) errors[fieldKey(field)] = error;
+  }
+  return errors;
+}
+
+/** Fields of `edited` that differ from `original`, nested like the config */
+export function changedValues(
+  fields: ConfigField[],
+  original: ConfigValues,
+  edited: ConfigValues,
+): ConfigValues {
+  let changes: ConfigValues = {};
+  for (const field of fields) {
+    const value = getValue(edited, field.path);
+    const before = getValue(original, field.path);
+    if (JSON.stringify(value) !== JSON.stringify(before)) {
+      changes = setValue(changes, field.path, value);
+    }
+  }
+  return changes;
+}